@@ -12,6 +12,8 @@ pub struct Model {
     pub title: String,
     pub oao: bool,
     pub locked: bool,
+    #[sea_orm(column_type = "JsonBinary")]
+    pub config: Json,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
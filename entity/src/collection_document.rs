@@ -11,6 +11,8 @@ pub struct Model {
     pub owner: Uuid,
     #[sea_orm(column_type = "JsonBinary")]
     pub f: Json,
+    pub seq: i64,
+    pub locked: bool,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
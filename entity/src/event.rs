@@ -13,6 +13,7 @@ pub struct Model {
     pub category_id: i32,
     #[sea_orm(column_type = "JsonBinary")]
     pub payload: Json,
+    pub published: bool,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -2,6 +2,7 @@ pub mod collection;
 pub mod collection_document;
 pub mod event;
 pub mod grant;
+pub mod idempotency_key;
 
 use collection_document::Model as Documents;
 
@@ -6,3 +6,4 @@ pub mod collection;
 pub mod collection_document;
 pub mod event;
 pub mod grant;
+pub mod idempotency_key;
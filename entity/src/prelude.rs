@@ -4,3 +4,4 @@ pub use super::collection::Entity as Collection;
 pub use super::collection_document::Entity as CollectionDocument;
 pub use super::event::Entity as Event;
 pub use super::grant::Entity as Grant;
+pub use super::idempotency_key::Entity as IdempotencyKey;
@@ -12,6 +12,7 @@ use openapi::{Api, ApiNoContext, Client, ContextWrapperExt, models,
                       ListRecoverablesInCollectionResponse,
                       SearchCollectionResponse,
                       StoreIntoCollectionResponse,
+                      StoreManyIntoCollectionResponse,
                       UpdateItemByIdResponse,
                       CreateEventResponse,
                       RebuildGrantsResponse,
@@ -151,6 +152,15 @@ fn main() {
         },
         */
         /* Disabled because there's no example.
+        Some("StoreManyIntoCollection") => {
+            let result = rt.block_on(client.store_many_into_collection(
+                  "collection_example".to_string(),
+                  &Vec::new()
+            ));
+            info!("{:?} (X-Span-ID: {:?})", result, (client.context() as &dyn Has<XSpanIdString>).get().clone());
+        },
+        */
+        /* Disabled because there's no example.
         Some("UpdateItemById") => {
             let result = rt.block_on(client.update_item_by_id(
                   "collection_example".to_string(),
@@ -101,6 +101,7 @@ use openapi::{
     ListRecoverablesInCollectionResponse,
     SearchCollectionResponse,
     StoreIntoCollectionResponse,
+    StoreManyIntoCollectionResponse,
     UpdateItemByIdResponse,
     CreateEventResponse,
     RebuildGrantsResponse,
@@ -200,6 +201,17 @@ impl<C> Api<C> for Server<C> where C: Has<XSpanIdString> + Send + Sync
         Err(ApiError("Generic failure".into()))
     }
 
+    /// Create many items
+    async fn store_many_into_collection(
+        &self,
+        collection: String,
+        collection_item: &Vec<models::CollectionItem>,
+        context: &C) -> Result<StoreManyIntoCollectionResponse, ApiError>
+    {
+        info!("store_many_into_collection(\"{}\", {:?}) - X-Span-ID: {:?}", collection, collection_item, context.get().0.clone());
+        Err(ApiError("Generic failure".into()))
+    }
+
     /// Replace item
     async fn update_item_by_id(
         &self,
@@ -87,6 +87,17 @@ pub enum StoreIntoCollectionResponse {
     CreatingTheCollectionFailed
 }
 
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[must_use]
+pub enum StoreManyIntoCollectionResponse {
+    /// successful operation
+    SuccessfulOperation
+    (models::BatchStoreResult)
+    ,
+    /// Invalid request
+    InvalidRequest
+}
+
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 #[must_use]
 pub enum UpdateItemByIdResponse {
@@ -184,6 +195,13 @@ pub trait Api<C: Send + Sync> {
         collection_item: models::CollectionItem,
         context: &C) -> Result<StoreIntoCollectionResponse, ApiError>;
 
+    /// Create many items
+    async fn store_many_into_collection(
+        &self,
+        collection: String,
+        collection_item: &Vec<models::CollectionItem>,
+        context: &C) -> Result<StoreManyIntoCollectionResponse, ApiError>;
+
     /// Replace item
     async fn update_item_by_id(
         &self,
@@ -274,6 +292,13 @@ pub trait ApiNoContext<C: Send + Sync> {
         collection_item: models::CollectionItem,
         ) -> Result<StoreIntoCollectionResponse, ApiError>;
 
+    /// Create many items
+    async fn store_many_into_collection(
+        &self,
+        collection: String,
+        collection_item: &Vec<models::CollectionItem>,
+        ) -> Result<StoreManyIntoCollectionResponse, ApiError>;
+
     /// Replace item
     async fn update_item_by_id(
         &self,
@@ -406,6 +431,17 @@ impl<T: Api<C> + Send + Sync, C: Clone + Send + Sync> ApiNoContext<C> for Contex
         self.api().store_into_collection(collection, collection_item, &context).await
     }
 
+    /// Create many items
+    async fn store_many_into_collection(
+        &self,
+        collection: String,
+        collection_item: &Vec<models::CollectionItem>,
+        ) -> Result<StoreManyIntoCollectionResponse, ApiError>
+    {
+        let context = self.context().clone();
+        self.api().store_many_into_collection(collection, collection_item, &context).await
+    }
+
     /// Replace item
     async fn update_item_by_id(
         &self,
@@ -29,6 +29,7 @@ use crate::{Api,
      ListRecoverablesInCollectionResponse,
      SearchCollectionResponse,
      StoreIntoCollectionResponse,
+     StoreManyIntoCollectionResponse,
      UpdateItemByIdResponse,
      CreateEventResponse,
      RebuildGrantsResponse
@@ -41,6 +42,7 @@ mod paths {
         pub static ref GLOBAL_REGEX_SET: regex::RegexSet = regex::RegexSet::new(vec![
             r"^/api/collections$",
             r"^/api/collections/(?P<collection>[^/?#]*)$",
+            r"^/api/collections/(?P<collection>[^/?#]*)/batch$",
             r"^/api/collections/(?P<collection>[^/?#]*)/searches$",
             r"^/api/collections/(?P<collection>[^/?#]*)/(?P<documentId>[^/?#]*)$",
             r"^/api/events$",
@@ -57,29 +59,36 @@ mod paths {
             regex::Regex::new(r"^/api/collections/(?P<collection>[^/?#]*)$")
                 .expect("Unable to create regex for COLLECTIONS_COLLECTION");
     }
-    pub(crate) static ID_COLLECTIONS_COLLECTION_SEARCHES: usize = 2;
+    pub(crate) static ID_COLLECTIONS_COLLECTION_BATCH: usize = 2;
+    lazy_static! {
+        pub static ref REGEX_COLLECTIONS_COLLECTION_BATCH: regex::Regex =
+            #[allow(clippy::invalid_regex)]
+            regex::Regex::new(r"^/api/collections/(?P<collection>[^/?#]*)/batch$")
+                .expect("Unable to create regex for COLLECTIONS_COLLECTION_BATCH");
+    }
+    pub(crate) static ID_COLLECTIONS_COLLECTION_SEARCHES: usize = 3;
     lazy_static! {
         pub static ref REGEX_COLLECTIONS_COLLECTION_SEARCHES: regex::Regex =
             #[allow(clippy::invalid_regex)]
             regex::Regex::new(r"^/api/collections/(?P<collection>[^/?#]*)/searches$")
                 .expect("Unable to create regex for COLLECTIONS_COLLECTION_SEARCHES");
     }
-    pub(crate) static ID_COLLECTIONS_COLLECTION_DOCUMENTID: usize = 3;
+    pub(crate) static ID_COLLECTIONS_COLLECTION_DOCUMENTID: usize = 4;
     lazy_static! {
         pub static ref REGEX_COLLECTIONS_COLLECTION_DOCUMENTID: regex::Regex =
             #[allow(clippy::invalid_regex)]
             regex::Regex::new(r"^/api/collections/(?P<collection>[^/?#]*)/(?P<documentId>[^/?#]*)$")
                 .expect("Unable to create regex for COLLECTIONS_COLLECTION_DOCUMENTID");
     }
-    pub(crate) static ID_EVENTS: usize = 4;
-    pub(crate) static ID_MAINTENANCE_COLLECTION_REBUILD_GRANTS: usize = 5;
+    pub(crate) static ID_EVENTS: usize = 5;
+    pub(crate) static ID_MAINTENANCE_COLLECTION_REBUILD_GRANTS: usize = 6;
     lazy_static! {
         pub static ref REGEX_MAINTENANCE_COLLECTION_REBUILD_GRANTS: regex::Regex =
             #[allow(clippy::invalid_regex)]
             regex::Regex::new(r"^/api/maintenance/(?P<collection>[^/?#]*)/rebuild-grants$")
                 .expect("Unable to create regex for MAINTENANCE_COLLECTION_REBUILD_GRANTS");
     }
-    pub(crate) static ID_RECOVERABLES_COLLECTION: usize = 6;
+    pub(crate) static ID_RECOVERABLES_COLLECTION: usize = 7;
     lazy_static! {
         pub static ref REGEX_RECOVERABLES_COLLECTION: regex::Regex =
             #[allow(clippy::invalid_regex)]
@@ -1030,6 +1039,114 @@ impl<T, C> hyper::service::Service<(Request<Body>, C)> for Service<T, C> where
                         }
             },
 
+            // StoreManyIntoCollection - POST /collections/{collection}/batch
+            hyper::Method::POST if path.matched(paths::ID_COLLECTIONS_COLLECTION_BATCH) => {
+                // Path parameters
+                let path: &str = uri.path();
+                let path_params =
+                    paths::REGEX_COLLECTIONS_COLLECTION_BATCH
+                    .captures(path)
+                    .unwrap_or_else(||
+                        panic!("Path {} matched RE COLLECTIONS_COLLECTION_BATCH in set but failed match against \"{}\"", path, paths::REGEX_COLLECTIONS_COLLECTION_BATCH.as_str())
+                    );
+
+                let param_collection = match percent_encoding::percent_decode(path_params["collection"].as_bytes()).decode_utf8() {
+                    Ok(param_collection) => match param_collection.parse::<String>() {
+                        Ok(param_collection) => param_collection,
+                        Err(e) => return Ok(Response::builder()
+                                        .status(StatusCode::BAD_REQUEST)
+                                        .body(Body::from(format!("Couldn't parse path parameter collection: {}", e)))
+                                        .expect("Unable to create Bad Request response for invalid path parameter")),
+                    },
+                    Err(_) => return Ok(Response::builder()
+                                        .status(StatusCode::BAD_REQUEST)
+                                        .body(Body::from(format!("Couldn't percent-decode path parameter as UTF-8: {}", &path_params["collection"])))
+                                        .expect("Unable to create Bad Request response for invalid percent decode"))
+                };
+
+                // Body parameters (note that non-required body parameters will ignore garbage
+                // values, rather than causing a 400 response). Produce warning header and logs for
+                // any unused fields.
+                let result = body.into_raw().await;
+                match result {
+                            Ok(body) => {
+                                let mut unused_elements = Vec::new();
+                                let param_collection_item: Option<Vec<models::CollectionItem>> = if !body.is_empty() {
+                                    let deserializer = &mut serde_json::Deserializer::from_slice(&body);
+                                    match serde_ignored::deserialize(deserializer, |path| {
+                                            warn!("Ignoring unknown field in body: {}", path);
+                                            unused_elements.push(path.to_string());
+                                    }) {
+                                        Ok(param_collection_item) => param_collection_item,
+                                        Err(e) => return Ok(Response::builder()
+                                                        .status(StatusCode::BAD_REQUEST)
+                                                        .body(Body::from(format!("Couldn't parse body parameter CollectionItem - doesn't match schema: {}", e)))
+                                                        .expect("Unable to create Bad Request response for invalid body parameter CollectionItem due to schema")),
+                                    }
+                                } else {
+                                    None
+                                };
+                                let param_collection_item = match param_collection_item {
+                                    Some(param_collection_item) => param_collection_item,
+                                    None => return Ok(Response::builder()
+                                                        .status(StatusCode::BAD_REQUEST)
+                                                        .body(Body::from("Missing required body parameter CollectionItem"))
+                                                        .expect("Unable to create Bad Request response for missing body parameter CollectionItem")),
+                                };
+
+                                let result = api_impl.store_many_into_collection(
+                                            param_collection,
+                                            param_collection_item.as_ref(),
+                                        &context
+                                    ).await;
+                                let mut response = Response::new(Body::empty());
+                                response.headers_mut().insert(
+                                            HeaderName::from_static("x-span-id"),
+                                            HeaderValue::from_str((&context as &dyn Has<XSpanIdString>).get().0.clone().as_str())
+                                                .expect("Unable to create X-Span-ID header value"));
+
+                                        if !unused_elements.is_empty() {
+                                            response.headers_mut().insert(
+                                                HeaderName::from_static("warning"),
+                                                HeaderValue::from_str(format!("Ignoring unknown fields in body: {:?}", unused_elements).as_str())
+                                                    .expect("Unable to create Warning header value"));
+                                        }
+
+                                        match result {
+                                            Ok(rsp) => match rsp {
+                                                StoreManyIntoCollectionResponse::SuccessfulOperation
+                                                    (body)
+                                                => {
+                                                    *response.status_mut() = StatusCode::from_u16(201).expect("Unable to turn 201 into a StatusCode");
+                                                    response.headers_mut().insert(
+                                                        CONTENT_TYPE,
+                                                        HeaderValue::from_str("application/json")
+                                                            .expect("Unable to create Content-Type header for STORE_MANY_INTO_COLLECTION_SUCCESSFUL_OPERATION"));
+                                                    let body_content = serde_json::to_string(&body).expect("impossible to fail to serialize");
+                                                    *response.body_mut() = Body::from(body_content);
+                                                },
+                                                StoreManyIntoCollectionResponse::InvalidRequest
+                                                => {
+                                                    *response.status_mut() = StatusCode::from_u16(400).expect("Unable to turn 400 into a StatusCode");
+                                                },
+                                            },
+                                            Err(_) => {
+                                                // Application code returned an error. This should not happen, as the implementation should
+                                                // return a valid response.
+                                                *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+                                                *response.body_mut() = Body::from("An internal error occurred");
+                                            },
+                                        }
+
+                                        Ok(response)
+                            },
+                            Err(e) => Ok(Response::builder()
+                                                .status(StatusCode::BAD_REQUEST)
+                                                .body(Body::from(format!("Couldn't read body parameter CollectionItem: {}", e)))
+                                                .expect("Unable to create Bad Request response due to unable to read body parameter CollectionItem")),
+                        }
+            },
+
             // UpdateItemById - PUT /collections/{collection}
             hyper::Method::PUT if path.matched(paths::ID_COLLECTIONS_COLLECTION) => {
                 // Path parameters
@@ -1284,6 +1401,7 @@ impl<T, C> hyper::service::Service<(Request<Body>, C)> for Service<T, C> where
 
             _ if path.matched(paths::ID_COLLECTIONS) => method_not_allowed(),
             _ if path.matched(paths::ID_COLLECTIONS_COLLECTION) => method_not_allowed(),
+            _ if path.matched(paths::ID_COLLECTIONS_COLLECTION_BATCH) => method_not_allowed(),
             _ if path.matched(paths::ID_COLLECTIONS_COLLECTION_SEARCHES) => method_not_allowed(),
             _ if path.matched(paths::ID_COLLECTIONS_COLLECTION_DOCUMENTID) => method_not_allowed(),
             _ if path.matched(paths::ID_EVENTS) => method_not_allowed(),
@@ -1316,6 +1434,8 @@ impl<T> RequestParser<T> for ApiRequestParser {
             hyper::Method::POST if path.matched(paths::ID_COLLECTIONS_COLLECTION_SEARCHES) => Some("SearchCollection"),
             // StoreIntoCollection - POST /collections/{collection}
             hyper::Method::POST if path.matched(paths::ID_COLLECTIONS_COLLECTION) => Some("StoreIntoCollection"),
+            // StoreManyIntoCollection - POST /collections/{collection}/batch
+            hyper::Method::POST if path.matched(paths::ID_COLLECTIONS_COLLECTION_BATCH) => Some("StoreManyIntoCollection"),
             // UpdateItemById - PUT /collections/{collection}
             hyper::Method::PUT if path.matched(paths::ID_COLLECTIONS_COLLECTION) => Some("UpdateItemById"),
             // CreateEvent - POST /events
@@ -48,6 +48,8 @@ LETTERS_BEAR_USER_SECRET=pwRV5k0IzscIJ34WKMndamsyefWBV0pD
 
 OIDCTOKEN=""
 API=http://localhost:3002/api
+# Body of responses to requests without permission
+UNAUTHORIZED='{"error":{"code":"unauthorized","message":"Unauthorized"}}'
 
 function authorize_client {
   OIDCTOKEN=$(curl --silent --location --request POST 'http://localhost:8101/realms/folivafy/protocol/openid-connect/token' \
@@ -91,7 +93,7 @@ sleep 0.5
 echo "- Access denied for user without coladmin role"
 authorize_client $NO_ROLE_CLIENT $NO_ROLE_SECRET
 RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections)
-if [ "$RESP" != "Unauthorized" ]
+if [ "$RESP" != "$UNAUTHORIZED" ]
 then
       echo -e "${RED}Failure:${NC} user is allowed to list collections!\n${RESP}\n"
 fi
@@ -126,7 +128,7 @@ RESP=$(curl --silent \
   --request POST \
   --header "Authorization: Bearer $OIDCTOKEN" \
   --header "Content-Type: application/json" \
-  --data '{"name": "shapes","title": "Shapes","oao": false}' \
+  --data '{"name": "shapes","title": "Shapes","oao": false,"config": {"deletedDocumentAccess": "owner"}}' \
   $API/collections)
 if [ "$RESP" != "Collection shapes created" ]
 then
@@ -142,19 +144,33 @@ RESP=$(curl --silent \
   --header "Content-Type: application/json" \
   --data '{"name": "shapes","title": "Shapes","oao": false}' \
   $API/collections)
-if [ "$RESP" != "{\"message\":\"Duplicate collection name\"}" ]
+if [ "$RESP" != "{\"error\":{\"code\":\"bad_request\",\"message\":\"Duplicate collection name\"}}" ]
 then
       echo -e "${RED}Failure:${NC} user is not allowed to create a collection!\n$RESP"
 fi
 
 
+echo "- Cannot create a collection with a reserved name"
+authorize_client $COLADMIN_CLIENT $COLADMIN_SECRET
+RESP=$(curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"name": "maintenance","title": "Maintenance","oao": false}' \
+  $API/collections)
+if [ "$RESP" != "{\"error\":{\"code\":\"bad_request\",\"message\":\"Collection name maintenance is reserved\"}}" ]
+then
+      echo -e "${RED}Failure:${NC} collection with a reserved name was created!\n$RESP"
+fi
+
+
 echo "- Can create letters collection"
 authorize_client $COLADMIN_CLIENT $COLADMIN_SECRET
 RESP=$(curl --silent \
   --request POST \
   --header "Authorization: Bearer $OIDCTOKEN" \
   --header "Content-Type: application/json" \
-  --data '{"name": "letters","title": "Letters","oao": true}' \
+  --data '{"name": "letters","title": "Letters","oao": true,"config": {"parent": {"collection": "letters", "field": "reply_to"}, "parentFields": {"reply_title": "title"}},"indexedFields": ["reply_to", "reply_to"]}' \
   $API/collections)
 if [ "$RESP" != "Collection letters created" ]
 then
@@ -178,7 +194,7 @@ RESP=$(curl --silent \
   --request POST \
   --header "Authorization: Bearer $OIDCTOKEN" \
   --header "Content-Type: application/json" \
-  --data '{"name": "fluids","title": "Fluids","oao": false}' \
+  --data '{"name": "fluids","title": "Fluids","oao": false,"config": {"eventCategories": {"1": "Document updated"}, "fieldFormats": {"price": {"type": "currency", "currency": "EUR"}}, "immutableFields": ["sku"], "requiredWhen": [{"field": "supplier", "when": "kind", "equals": "import"}]}}' \
   $API/collections)
 if [ "$RESP" != "Collection fluids created" ]
 then
@@ -186,6 +202,36 @@ then
 fi
 
 
+echo "- Can page through collections"
+authorize_client $COLADMIN_CLIENT $COLADMIN_SECRET
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections?limit=1&offset=2")
+NAMES=$(echo $RESP | jq -r '[.limit, .offset, .total, .items[].name] | join(" ")')
+if [ "$NAMES" != "1 2 4 letters" ]
+then
+      echo -e "${RED}Failure:${NC} paged list of collections does not match!\n$RESP"
+fi
+
+
+echo "- Can filter collections by flags"
+authorize_client $COLADMIN_CLIENT $COLADMIN_SECRET
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections?oao=true&locked=false")
+NAMES=$(echo $RESP | jq -r '[.total, .items[].name] | join(" ")')
+if [ "$NAMES" != "1 letters" ]
+then
+      echo -e "${RED}Failure:${NC} filtered list of collections does not match!\n$RESP"
+fi
+
+
+echo "- Collections admin can request large pages"
+authorize_client $COLADMIN_CLIENT $COLADMIN_SECRET
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections?limit=1000")
+LIMIT=$(echo $RESP | jq -r '.limit')
+if [ "$LIMIT" != "1000" ]
+then
+      echo -e "${RED}Failure:${NC} collections admin cannot request 1000 items!\n$RESP"
+fi
+
+
 #####################################################
 ##
 ##  Public shapes collection
@@ -214,7 +260,7 @@ RESP=$(curl --silent \
   --header "Content-Type: application/json" \
   --data '{"id": "ea25fa9d-4650-41ae-a1fa-00bd226b648f","f": {"title": "Rectangle", "price": 14}}' \
   $API/collections/shapes)
-if [ "$RESP" != "{\"message\":\"Duplicate document\"}" ]
+if [ "$RESP" != "{\"error\":{\"code\":\"bad_request\",\"message\":\"Duplicate document\"}}" ]
 then
       echo -e "${RED}Failure:${NC} duplicate rectangle document!\n$RESP"
 fi
@@ -237,7 +283,7 @@ fi
 echo "- Access denied for user $NO_ROLE without shapes reader role"
 authorize_client $NO_ROLE_CLIENT $NO_ROLE_SECRET
 RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/shapes)
-if [ "$RESP" != "Unauthorized" ]
+if [ "$RESP" != "$UNAUTHORIZED" ]
 then
       echo -e "${RED}Failure:${NC} user is allowed to list documents!\n$RESP"
 fi
@@ -246,7 +292,7 @@ fi
 echo "- Access denied for user $SHAPES_EDITOR_CLIENT without shapes reader role"
 authorize_client $SHAPES_EDITOR_CLIENT $SHAPES_EDITOR_SECRET
 RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/shapes)
-if [ "$RESP" != "Unauthorized" ]
+if [ "$RESP" != "$UNAUTHORIZED" ]
 then
       echo -e "${RED}Failure:${NC} user is allowed to list documents!\n$RESP"
 fi
@@ -255,7 +301,7 @@ fi
 echo "- Can list shapes"
 authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
 RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/shapes)
-if [ "$RESP" == "Unauthorized" ]
+if [ "$RESP" == "$UNAUTHORIZED" ]
 then
       echo -e "${RED}Failure:${NC} user is not allowed to list documents!\n$RESP"
 fi
@@ -269,7 +315,7 @@ fi
 echo "- Can list shapes with additional fields"
 authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
 RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/shapes?sort=title-\&extraFields=price)
-if [ "$RESP" == "Unauthorized" ]
+if [ "$RESP" == "$UNAUTHORIZED" ]
 then
       echo -e "${RED}Failure:${NC} user is not allowed to list documents!\n$RESP"
 fi
@@ -283,7 +329,7 @@ fi
 echo "- Can list shapes with additional fields and author_id"
 authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
 RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/shapes?sort=title-\&extraFields=price,author_id)
-if [ "$RESP" == "Unauthorized" ]
+if [ "$RESP" == "$UNAUTHORIZED" ]
 then
       echo -e "${RED}Failure:${NC} user is not allowed to list documents!\n$RESP"
 fi
@@ -297,7 +343,7 @@ fi
 echo "- Can list shapes with exact title match"
 authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
 RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/shapes?exactTitle=Rectangle)
-if [ "$RESP" == "Unauthorized" ]
+if [ "$RESP" == "$UNAUTHORIZED" ]
 then
       echo -e "${RED}Failure:${NC} user is not allowed to list documents!\n$RESP"
 fi
@@ -360,7 +406,7 @@ fi
 echo "- Can list shapes sorted asc by fields"
 authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
 RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/shapes?sort=geo.edges\%2B\&extraFields=geo)
-if [ "$RESP" == "Unauthorized" ]
+if [ "$RESP" == "$UNAUTHORIZED" ]
 then
       echo -e "${RED}Failure:${NC} user is not allowed to list documents!\n$RESP"
 fi
@@ -374,7 +420,7 @@ fi
 echo "- Can list shapes sorted desc by fields"
 authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
 RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/shapes?sort=geo.edges-\&extraFields=geo)
-if [ "$RESP" == "Unauthorized" ]
+if [ "$RESP" == "$UNAUTHORIZED" ]
 then
       echo -e "${RED}Failure:${NC} user is not allowed to list documents!\n$RESP"
 fi
@@ -388,7 +434,7 @@ fi
 echo "- Can list shapes containing a filter value"
 authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
 RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/shapes?pfilter=title\%3D\~"c")
-if [ "$RESP" == "Unauthorized" ]
+if [ "$RESP" == "$UNAUTHORIZED" ]
 then
       echo -e "${RED}Failure:${NC} user is not allowed to list documents!\n$RESP"
 fi
@@ -402,7 +448,7 @@ fi
 echo "- Can list shapes starting with a filter value"
 authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
 RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/shapes?pfilter=title\%3D\@"H")
-if [ "$RESP" == "Unauthorized" ]
+if [ "$RESP" == "$UNAUTHORIZED" ]
 then
       echo -e "${RED}Failure:${NC} user is not allowed to list documents!\n$RESP"
 fi
@@ -416,7 +462,7 @@ fi
 echo "- Can list shapes with a \`contains\` filter value on geo.edges"
 authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
 RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/shapes?sort=geo.edges-\&extraFields=geo\&pfilter=geo.edges\%3D\%5B2,3,4\%5D)
-if [ "$RESP" == "Unauthorized" ]
+if [ "$RESP" == "$UNAUTHORIZED" ]
 then
       echo -e "${RED}Failure:${NC} user is not allowed to list documents!\n$RESP"
 fi
@@ -430,7 +476,7 @@ fi
 echo "- Can list shapes with a \`contains\` filter value"
 authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
 RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/shapes?sort=geo.edges-\&extraFields=geo\&pfilter=title\%3D\%5BCircle,Triangle,Rectangle\%5D)
-if [ "$RESP" == "Unauthorized" ]
+if [ "$RESP" == "$UNAUTHORIZED" ]
 then
       echo -e "${RED}Failure:${NC} user is not allowed to list documents!\n$RESP"
 fi
@@ -449,7 +495,7 @@ RESP=$(curl --silent \
   --header "Content-Type: application/json" \
   --data '{"filter": {"or": [{"f":"title","o":"startswith","v":"Ci"},{"f":"title","o":"containstext","v":"ctang"}]}}' \
   $API/collections/shapes/search?sort=title-\&extraFields=price,author_id)
-if [ "$RESP" == "Unauthorized" ]
+if [ "$RESP" == "$UNAUTHORIZED" ]
 then
       echo -e "${RED}Failure:${NC} user is not allowed to list documents!\n$RESP"
 fi
@@ -460,6 +506,21 @@ then
 fi
 
 
+echo "- Can search shapes with title only"
+authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
+RESP=$(curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"filter": {"f":"title","o":"startswith","v":"Ci"}}' \
+  $API/collections/shapes/search?titleOnly=true\&extraFields=price,author_id)
+FIELDS=$(echo $RESP | jq -r '[.items[].f | keys[]] | join(" ")')
+if [ "$FIELDS" != "title" ]
+then
+      echo -e "${RED}Failure:${NC} search result contains more than the title!\n$FIELDS\n$RESP"
+fi
+
+
 echo "- User can create d12 shape document"
 authorize_client $SHAPES_EDITOR_CLIENT $SHAPES_EDITOR_SECRET
 RESP=$(curl --silent \
@@ -476,7 +537,7 @@ fi
 echo "- Check that list of shapes contains d12"
 authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
 RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/shapes)
-if [ "$RESP" == "Unauthorized" ]
+if [ "$RESP" == "$UNAUTHORIZED" ]
 then
       echo -e "${RED}Failure:${NC} user is not allowed to list documents!\n$RESP"
 fi
@@ -490,7 +551,7 @@ fi
 echo "- Check reader can access document d12"
 authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
 RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/shapes/dd326434-c1f4-4b07-a933-298bd3eb45dd)
-if [ "$RESP" == "Unauthorized" ]
+if [ "$RESP" == "$UNAUTHORIZED" ]
 then
       echo -e "${RED}Failure:${NC} user is not allowed to read d12!\n$RESP"
 fi
@@ -509,7 +570,7 @@ RESP=$(curl --silent \
   --header "Content-Type: application/json" \
   --data '{"category": 2,"collection": "shapes", "document": "dd326434-c1f4-4b07-a933-298bd3eb45dd","e": {}}' \
   $API/events)
-if [ "$RESP" != "Unauthorized" ]
+if [ "$RESP" != "$UNAUTHORIZED" ]
 then
       echo -e "${RED}Failure:${NC} reader is allowed to delete d12!\n$RESP"
 fi
@@ -523,7 +584,7 @@ RESP=$(curl --silent \
   --header "Content-Type: application/json" \
   --data '{"category": 2,"collection": "shapes", "document": "dd326434-c1f4-4b07-a933-298bd3eb45dd","e": {}}' \
   $API/events)
-if [ "$RESP" != "Unauthorized" ]
+if [ "$RESP" != "$UNAUTHORIZED" ]
 then
       echo -e "${RED}Failure:${NC} editor is allowed to delete d12!\n$RESP"
 fi
@@ -536,7 +597,7 @@ RESP=$(curl --silent \
   --header "Content-Type: application/json" \
   $API/recoverables/shapes
 )
-if [ "$RESP" == "Unauthorized" ]
+if [ "$RESP" == "$UNAUTHORIZED" ]
 then
       echo -e "${RED}Failure:${NC} user is not allowed to list recoverables!\n$RESP"
 fi
@@ -560,6 +621,34 @@ then
 fi
 
 
+echo "- Reader gets large page requests reduced to the maximum page size"
+authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes?limit=1000")
+LIMIT=$(echo $RESP | jq -r '.limit')
+if [ "$LIMIT" != "250" ]
+then
+      echo -e "${RED}Failure:${NC} reader page size is not reduced to 250 items!\n$RESP"
+fi
+
+
+echo "- Reader gets the default page size without a limit"
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes")
+LIMIT=$(echo $RESP | jq -r '.limit')
+if [ "$LIMIT" != "50" ]
+then
+      echo -e "${RED}Failure:${NC} reader does not get the default page size!\n$RESP"
+fi
+
+
+echo "- Reader cannot list deleted shapes"
+authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes?includeDeleted=true")
+if [ "$RESP" != '{"error":{"code":"forbidden","message":"Only administrators can list deleted documents"}}' ]
+then
+      echo -e "${RED}Failure:${NC} reader is allowed to list deleted documents!\n$RESP"
+fi
+
+
 echo "- Remover cannot delete shape twice"
 authorize_client $SHAPES_REMOVER_CLIENT $SHAPES_REMOVER_SECRET
 RESP=$(curl --silent \
@@ -568,15 +657,43 @@ RESP=$(curl --silent \
   --header "Content-Type: application/json" \
   --data '{"category": 2,"collection": "shapes", "document": "dd326434-c1f4-4b07-a933-298bd3eb45dd","e": {}}' \
   $API/events)
-if [ "$RESP" != "{\"message\":\"Document already deleted\"}" ]
+if [ "$RESP" != "{\"error\":{\"code\":\"bad_request\",\"message\":\"Document already deleted\"}}" ]
 then
       echo -e "${RED}Failure:${NC} Remover is allowed to delete d12 twice!\n$RESP"
 fi
 
+
+echo "- Owner can read deleted d12 with includeDeleted"
+authorize_client $SHAPES_EDITOR_CLIENT $SHAPES_EDITOR_SECRET
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes/dd326434-c1f4-4b07-a933-298bd3eb45dd?includeDeleted=true")
+FIELDS=$(echo $RESP | jq -r '[.f.title, .deleted, (.deletedAt != null)] | join(" ")')
+if [ "$FIELDS" != "d12 true true" ]
+then
+      echo -e "${RED}Failure:${NC} owner cannot read deleted d12!\n$FIELDS\n$RESP"
+fi
+
+
+echo "- Owner cannot read deleted d12 without includeDeleted"
+authorize_client $SHAPES_EDITOR_CLIENT $SHAPES_EDITOR_SECRET
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes/dd326434-c1f4-4b07-a933-298bd3eb45dd")
+if [ "$RESP" != '{"error":{"code":"not_found","message":"Document dd326434-c1f4-4b07-a933-298bd3eb45dd not found"}}' ]
+then
+      echo -e "${RED}Failure:${NC} deleted d12 is readable without includeDeleted!\n$RESP"
+fi
+
+
+echo "- Reader cannot read deleted d12 with includeDeleted"
+authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes/dd326434-c1f4-4b07-a933-298bd3eb45dd?includeDeleted=true")
+if [ "$RESP" != '{"error":{"code":"not_found","message":"Document dd326434-c1f4-4b07-a933-298bd3eb45dd not found"}}' ]
+then
+      echo -e "${RED}Failure:${NC} reader can read deleted d12!\n$RESP"
+fi
+
 echo "- Check that list of shapes no longer contains d12"
 authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
 RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/shapes)
-if [ "$RESP" == "Unauthorized" ]
+if [ "$RESP" == "$UNAUTHORIZED" ]
 then
       echo -e "${RED}Failure:${NC} user is not allowed to list documents!\n$RESP"
 fi
@@ -590,7 +707,7 @@ fi
 echo "- Check reader can no longer access document d12"
 authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
 RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/shapes/dd326434-c1f4-4b07-a933-298bd3eb45dd)
-if [ "$RESP" != "Document dd326434-c1f4-4b07-a933-298bd3eb45dd not found" ]
+if [ "$RESP" != '{"error":{"code":"not_found","message":"Document dd326434-c1f4-4b07-a933-298bd3eb45dd not found"}}' ]
 then
       echo -e "${RED}Failure:${NC} user found d12!\n$RESP"
 fi
@@ -599,7 +716,7 @@ fi
 echo "- Check editor can no longer access document d12"
 authorize_client $SHAPES_EDITOR_CLIENT $SHAPES_EDITOR_SECRET
 RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/shapes/dd326434-c1f4-4b07-a933-298bd3eb45dd)
-if [ "$RESP" != "Unauthorized" ] # Editor has no read permission
+if [ "$RESP" != "$UNAUTHORIZED" ] # Editor has no read permission
 then
       echo -e "${RED}Failure:${NC} editor found d12!\n$RESP"
 fi
@@ -608,7 +725,7 @@ fi
 echo "- Check remover can no longer access document d12"
 authorize_client $SHAPES_REMOVER_CLIENT $SHAPES_REMOVER_SECRET
 RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/shapes/dd326434-c1f4-4b07-a933-298bd3eb45dd)
-if [ "$RESP" != "Document dd326434-c1f4-4b07-a933-298bd3eb45dd not found" ]
+if [ "$RESP" != '{"error":{"code":"not_found","message":"Document dd326434-c1f4-4b07-a933-298bd3eb45dd not found"}}' ]
 then
       echo -e "${RED}Failure:${NC} remover found d12!\n$RESP"
 fi
@@ -622,7 +739,7 @@ RESP=$(curl --silent \
   --header "Content-Type: application/json" \
   --data '{"category": 3,"collection": "shapes", "document": "dd326434-c1f4-4b07-a933-298bd3eb45dd","e": {}}' \
   $API/events)
-if [ "$RESP" != "Unauthorized" ]
+if [ "$RESP" != "$UNAUTHORIZED" ]
 then
       echo -e "${RED}Failure:${NC} reader is allowed to recover d12!\n$RESP"
 fi
@@ -635,7 +752,7 @@ RESP=$(curl --silent \
   --header "Content-Type: application/json" \
   --data '{"category": 3,"collection": "shapes", "document": "be7c1d84-e27d-42a0-8abd-54a1b2c17e36","e": {}}' \
   $API/events)
-if [ "$RESP" != "{\"message\":\"Document is not in deleted stage\"}" ]
+if [ "$RESP" != "{\"error\":{\"code\":\"bad_request\",\"message\":\"Document is not in deleted stage\"}}" ]
 then
       echo -e "${RED}Failure:${NC} Remover is allowed to recover hexagon!\n$RESP"
 fi
@@ -649,7 +766,7 @@ RESP=$(curl --silent \
   --header "Content-Type: application/json" \
   --data '{"category": 3,"collection": "shapes", "document": "dd326434-c1f4-4b07-a933-298bd3eb45dd","e": {}}' \
   $API/events)
-if [ "$RESP" == "Unauthorized" ]
+if [ "$RESP" == "$UNAUTHORIZED" ]
 then
       echo -e "${RED}Failure:${NC} remover is not allowed to recover d12!\n$RESP"
 fi
@@ -722,7 +839,7 @@ fi
 echo "- Access denied for user $NO_ROLE_CLIENT without letters reader role"
 authorize_client $NO_ROLE_CLIENT $NO_ROLE_SECRET
 RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/letters)
-if [ "$RESP" != "Unauthorized" ]
+if [ "$RESP" != "$UNAUTHORIZED" ]
 then
       echo -e "${RED}Failure:${NC} user is allowed to list letters!\n$RESP"
 fi
@@ -731,7 +848,7 @@ fi
 echo "- User 1 can list its letters"
 authorize_client $LETTERS_ALPACA_USER_CLIENT $LETTERS_ALPACA_USER_SECRET
 RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/letters)
-if [ "$RESP" == "Unauthorized" ]
+if [ "$RESP" == "$UNAUTHORIZED" ]
 then
       echo -e "${RED}Failure:${NC} user is not allowed to list letters!\n$RESP"
 fi
@@ -746,7 +863,7 @@ fi
 echo "- User 2 can list its letters"
 authorize_client $LETTERS_BEAR_USER_CLIENT $LETTERS_BEAR_USER_SECRET
 RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/letters)
-if [ "$RESP" == "Unauthorized" ]
+if [ "$RESP" == "$UNAUTHORIZED" ]
 then
       echo -e "${RED}Failure:${NC} user is not allowed to list letters!\n$RESP"
 fi
@@ -761,7 +878,7 @@ fi
 echo "- User 1 can list its letters with sorting"
 authorize_client $LETTERS_ALPACA_USER_CLIENT $LETTERS_ALPACA_USER_SECRET
 RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API'/collections/letters?sort=content-,title-&extraFields=content')
-if [ "$RESP" == "Unauthorized" ]
+if [ "$RESP" == "$UNAUTHORIZED" ]
 then
       echo -e "${RED}Failure:${NC} user is not allowed to list letters!\n$RESP"
 fi
@@ -782,7 +899,7 @@ fi
 echo "- User can read rectangle"
 authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
 RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/shapes/ea25fa9d-4650-41ae-a1fa-00bd226b648f)
-if [ "$RESP" == "Unauthorized" ]
+if [ "$RESP" == "$UNAUTHORIZED" ]
 then
       echo -e "${RED}Failure:${NC} user is not allowed to read rectangle!\n$RESP"
 fi
@@ -796,7 +913,7 @@ fi
 echo "- Other user can read rectangle"
 authorize_client $SHAPES_READER_OTHER_CLIENT $SHAPES_READER_OTHER_SECRET
 RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/shapes/ea25fa9d-4650-41ae-a1fa-00bd226b648f)
-if [ "$RESP" == "Unauthorized" ]
+if [ "$RESP" == "$UNAUTHORIZED" ]
 then
       echo -e "${RED}Failure:${NC} other user is not allowed to read rectangle!\n$RESP"
 fi
@@ -810,7 +927,7 @@ fi
 echo "- User 1 can retrieve its letter"
 authorize_client $LETTERS_ALPACA_USER_CLIENT $LETTERS_ALPACA_USER_SECRET
 RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/letters/ff901d16-a533-4ad7-9e75-d69407440804)
-if [ "$RESP" == "Unauthorized" ]
+if [ "$RESP" == "$UNAUTHORIZED" ]
 then
       echo -e "${RED}Failure:${NC} user is not allowed to read alpaca letter 1!\n$RESP"
 fi
@@ -824,7 +941,7 @@ fi
 echo "- User 2 cannot retrieve other users letter"
 authorize_client $LETTERS_BEAR_USER_CLIENT $LETTERS_BEAR_USER_SECRET
 RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/letters/ff901d16-a533-4ad7-9e75-d69407440804)
-if [ "$RESP" != "Document ff901d16-a533-4ad7-9e75-d69407440804 not found" ]
+if [ "$RESP" != '{"error":{"code":"not_found","message":"Document ff901d16-a533-4ad7-9e75-d69407440804 not found"}}' ]
 then
       echo -e "${RED}Failure:${NC} user is allowed to read alpaca letter 1!\n$RESP"
 fi
@@ -833,7 +950,7 @@ fi
 echo "- Fluid user can read water"
 authorize_client $FLUIDS_EDITOR_CLIENT $FLUIDS_EDITOR_SECRET
 RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/fluids/702562c8-8017-4b95-9c07-dfaceb5496ed)
-if [ "$RESP" == "Unauthorized" ]
+if [ "$RESP" == "$UNAUTHORIZED" ]
 then
       echo -e "${RED}Failure:${NC} other user is not allowed to read water!\n$RESP"
 fi
@@ -847,7 +964,7 @@ fi
 echo "- Fluid user can read rectangle"
 authorize_client $FLUIDS_EDITOR_CLIENT $FLUIDS_EDITOR_SECRET
 RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/shapes/ea25fa9d-4650-41ae-a1fa-00bd226b648f)
-if [ "$RESP" == "Unauthorized" ]
+if [ "$RESP" == "$UNAUTHORIZED" ]
 then
       echo -e "${RED}Failure:${NC} user is not allowed to read rectangle!\n$RESP"
 fi
@@ -861,16 +978,45 @@ fi
 echo "- No user can read rectangle accessed through wrong collection"
 authorize_client $FLUIDS_EDITOR_CLIENT $FLUIDS_EDITOR_SECRET
 RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/fluids/ea25fa9d-4650-41ae-a1fa-00bd226b648f)
-if [ "$RESP" == "Unauthorized" ]
+if [ "$RESP" == "$UNAUTHORIZED" ]
 then
       echo -e "${RED}Failure:${NC} user is not allowed to read rectangle!\n$RESP"
 fi
-if [ "$RESP" != "Document ea25fa9d-4650-41ae-a1fa-00bd226b648f not found" ]
+if [ "$RESP" != '{"error":{"code":"not_found","message":"Document ea25fa9d-4650-41ae-a1fa-00bd226b648f not found"}}' ]
 then
       echo -e "${RED}Failure:${NC} document was available!\n$RESP"
 fi
 
 
+echo "- Fluid user can search multiple collections"
+authorize_client $FLUIDS_EDITOR_CLIENT $FLUIDS_EDITOR_SECRET
+RESP=$(curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"collections": ["shapes", "fluids"], "filter": {"f":"title","o":"in","v":["Circle","Rectangle","Water"]}}' \
+  $API/search?sort=title\%2B)
+CONTENT=$(echo $RESP | jq -r '[.total, (.items[] | .collection, .f.title)] | join(" ")')
+if [ "$CONTENT" != "3 shapes Circle shapes Rectangle fluids Water" ]
+then
+      echo -e "${RED}Failure:${NC} search result does not contain documents from both collections!\n$CONTENT\n$RESP"
+fi
+
+
+echo "- Fluid user cannot search collections without reader role"
+authorize_client $FLUIDS_EDITOR_CLIENT $FLUIDS_EDITOR_SECRET
+RESP=$(curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"collections": ["fluids", "letters"]}' \
+  $API/search)
+if [ "$RESP" != "$UNAUTHORIZED" ]
+then
+      echo -e "${RED}Failure:${NC} user can search letters!\n$RESP"
+fi
+
+
 #####################################################
 ##
 ##  Document update access
@@ -892,7 +1038,7 @@ then
 fi
 authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
 RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/shapes/ea25fa9d-4650-41ae-a1fa-00bd226b648f)
-if [ "$RESP" == "Unauthorized" ]
+if [ "$RESP" == "$UNAUTHORIZED" ]
 then
       echo -e "${RED}Failure:${NC} user is not allowed to read square!\n$RESP"
 fi
@@ -916,7 +1062,7 @@ then
       echo -e "${RED}Failure:${NC} user is not allowed to update Alpaca letter 1!\n$RESP"
 fi
 RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/letters/ff901d16-a533-4ad7-9e75-d69407440804)
-if [ "$RESP" == "Unauthorized" ]
+if [ "$RESP" == "$UNAUTHORIZED" ]
 then
       echo -e "${RED}Failure:${NC} user is not allowed to read Alpaca letter 1!\n$RESP"
 fi
@@ -969,7 +1115,7 @@ RESP=$(curl --silent \
   --header "Content-Type: application/json" \
   --data "{\"filter\": {\"f\":\"author_id\",\"o\":\"eq\",\"v\": \"${SHAPES_EDITOR_UID}\"}}" \
   $API/collections/shapes/search?extraFields=price)
-if [ "$RESP" == "Unauthorized" ]
+if [ "$RESP" == "$UNAUTHORIZED" ]
 then
       echo -e "${RED}Failure:${NC} user is not allowed to list documents!\n$RESP"
 fi
@@ -991,7 +1137,7 @@ RESP=$(curl --silent \
   --header "Content-Type: application/json" \
   --data "{\"filter\": {\"f\":\"author_id\",\"o\":\"eq\",\"v\": \"${SHAPES_EDITOR2_UID}\"}}" \
   $API/collections/shapes/search)
-if [ "$RESP" == "Unauthorized" ]
+if [ "$RESP" == "$UNAUTHORIZED" ]
 then
       echo -e "${RED}Failure:${NC} user is not allowed to list documents!\n$RESP"
 fi
@@ -1007,10 +1153,20 @@ then
 fi
 
 
+echo "- Can list shapes sorted by author_id"
+authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes?sort=author_id-!,title+&extraFields=author_id&limit=100")
+SORTED=$(echo $RESP | jq -r '[.items[].f.author_id] as $a | ($a == ($a | sort | reverse)) and ($a | index("'"${SHAPES_EDITOR2_UID}"'") != null)')
+if [ "$SORTED" != "true" ]
+then
+      echo -e "${RED}Failure:${NC} list of shapes is not sorted by author_id!\n$RESP"
+fi
+
+
 echo "- Can list shapes with author_id filter"
 authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
 RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/shapes?pfilter=author_id\%3D${SHAPES_EDITOR_UID}\&extraFields=price)
-if [ "$RESP" == "Unauthorized" ]
+if [ "$RESP" == "$UNAUTHORIZED" ]
 then
       echo -e "${RED}Failure:${NC} user is not allowed to list documents!\n$RESP"
 fi
@@ -1027,5 +1183,1762 @@ fi
 
 
 
+echo "- Concurrent claims return distinct documents"
+authorize_client $SHAPES_EDITOR_CLIENT $SHAPES_EDITOR_SECRET
+for TITLE in Job1 Job2 Job3
+do
+  curl --silent \
+    --request POST \
+    --header "Authorization: Bearer $OIDCTOKEN" \
+    --header "Content-Type: application/json" \
+    --data "{\"f\": {\"title\": \"$TITLE\", \"status\": \"open\"}}" \
+    $API/collections/shapes > /dev/null
+done
+CLAIM1=$(mktemp)
+CLAIM2=$(mktemp)
+for CLAIMFILE in $CLAIM1 $CLAIM2
+do
+  curl --silent \
+    --request POST \
+    --header "Authorization: Bearer $OIDCTOKEN" \
+    --header "Content-Type: application/json" \
+    --data '{"filter": {"f":"status","o":"eq","v":"open"}, "field": "claim", "ttl": 300}' \
+    $API/collections/shapes/claim > $CLAIMFILE &
+done
+wait
+ID1=$(jq -r '.id' $CLAIM1)
+ID2=$(jq -r '.id' $CLAIM2)
+if [ "$ID1" == "null" ] || [ "$ID2" == "null" ] || [ "$ID1" == "$ID2" ]
+then
+      echo -e "${RED}Failure:${NC} claims did not return distinct documents!\n$(cat $CLAIM1)\n$(cat $CLAIM2)"
+fi
+BY=$(jq -r '.f.claim.by' $CLAIM1)
+if [ "$BY" != "$SHAPES_EDITOR_UID" ]
+then
+      echo -e "${RED}Failure:${NC} claim does not contain claimant!\n$(cat $CLAIM1)"
+fi
+VERSION=$(jq -r '.f.version' $CLAIM1)
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/shapes/$ID1)
+CONTENT=$(echo $RESP | jq -r '.f.version')
+if [ "$VERSION" == "null" ] || [ "$CONTENT" != "$VERSION" ]
+then
+      echo -e "${RED}Failure:${NC} claim did not increment the version!\n$(cat $CLAIM1)\n$RESP"
+fi
+rm -f $CLAIM1 $CLAIM2
+
+
+echo "- Fluid user reads formatted price"
+authorize_client $FLUIDS_EDITOR_CLIENT $FLUIDS_EDITOR_SECRET
+RESP=$(curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"id": "5b0e2b4c-8a0d-4f65-9a6e-2e7d2f0e8c11","f": {"title": "Oil", "price": 1234}}' \
+  $API/collections/fluids)
+if [ "$RESP" != "Document saved" ]
+then
+      echo -e "${RED}Failure:${NC} user is not allowed to save oil document!\n$RESP"
+fi
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/fluids/5b0e2b4c-8a0d-4f65-9a6e-2e7d2f0e8c11)
+CONTENT=$(echo $RESP | jq -r '[.f.price, .f.price_formatted] | join(" ")')
+if [ "$CONTENT" != "1234 12.34 EUR" ]
+then
+      echo -e "${RED}Failure:${NC} price is not formatted!\n$RESP"
+fi
+
+
+echo "- Changes feed returns changes after a sequence number"
+authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/shapes/changes?since=0\&limit=250)
+SORTED=$(echo $RESP | jq -r '[.items[].seq] == ([.items[].seq] | sort)')
+if [ "$SORTED" != "true" ]
+then
+      echo -e "${RED}Failure:${NC} changes are not ordered by sequence number!\n$RESP"
+fi
+LASTSEQ=$(echo $RESP | jq -r '.lastSeq')
+authorize_client $SHAPES_EDITOR_CLIENT $SHAPES_EDITOR_SECRET
+curl --silent \
+  --request PUT \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"id": "ea25fa9d-4650-41ae-a1fa-00bd226b648f","f": {"title": "Square", "area": 4}}' \
+  $API/collections/shapes > /dev/null
+authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/shapes/changes?since=$LASTSEQ)
+CONTENT=$(echo $RESP | jq -r --argjson last "$LASTSEQ" '[.items[] | select(.seq > $last) | .id + " " + (.f.area | tostring)] | join(",")')
+if [ "$CONTENT" != "ea25fa9d-4650-41ae-a1fa-00bd226b648f 4" ]
+then
+      echo -e "${RED}Failure:${NC} changes feed does not contain the update!\n$CONTENT\n$RESP"
+fi
+
+
+echo "- Changes feed does not skip a change of a slower transaction"
+LASTSEQ=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/shapes/changes?since=0\&limit=250 | jq -r '.lastSeq')
+psql --quiet "$DATABASE_URL" \
+  -c "BEGIN" \
+  -c "UPDATE collection_document SET f = f || '{\"area\": 5}' WHERE id = 'ea25fa9d-4650-41ae-a1fa-00bd226b648f'" \
+  -c "SELECT pg_sleep(3)" \
+  -c "COMMIT" > /dev/null &
+SLOWTX=$!
+sleep 1
+psql --quiet "$DATABASE_URL" \
+  -c "UPDATE collection_document SET f = f || '{\"price\": 10}' WHERE id = '1dec98bb-564e-4e40-81b9-e9aa5ab098f6'" > /dev/null &
+FASTTX=$!
+sleep 1
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/shapes/changes?since=$LASTSEQ)
+LASTSEQ=$(echo $RESP | jq -r '.lastSeq')
+wait $SLOWTX $FASTTX
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/shapes/changes?since=$LASTSEQ)
+CONTENT=$(echo $RESP | jq -r '[.items[].id] | join(",")')
+if [ "$CONTENT" != "ea25fa9d-4650-41ae-a1fa-00bd226b648f,1dec98bb-564e-4e40-81b9-e9aa5ab098f6" ]
+then
+      echo -e "${RED}Failure:${NC} changes feed skipped the change of the slower transaction!\n$CONTENT\n$RESP"
+fi
+
+
+echo "- Administrator can export all collections"
+authorize_client $COLADMIN_CLIENT $COLADMIN_SECRET
+BACKUP=$(mktemp)
+curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/backup?includeEvents=true\&includeGrants=true > $BACKUP
+CONTENT=$(head -1 $BACKUP | jq -r '.type + " " + (.version | tostring)')
+if [ "$CONTENT" != "manifest 1" ]
+then
+      echo -e "${RED}Failure:${NC} backup does not start with manifest!\n$(head -1 $BACKUP)"
+fi
+CONTENT=$(jq -r 'select(.type == "collection") | .name' $BACKUP | sort | jq -R -s -r 'split("\n") | map(select(. != "")) | join(" ")')
+if [ "$CONTENT" != "fluids folivafy-mail letters shapes" ]
+then
+      echo -e "${RED}Failure:${NC} backup does not contain all collections!\n$CONTENT"
+fi
+DOCUMENTS=$(jq -r 'select(.type == "document") | .id' $BACKUP | wc -l)
+CONTENT=$(tail -1 $BACKUP | jq -r '.type + " " + (.documents | tostring)')
+if [ "$CONTENT" != "end $DOCUMENTS" ]
+then
+      echo -e "${RED}Failure:${NC} backup does not end with the document count!\n$CONTENT"
+fi
+EVENTS=$(jq -r 'select(.type == "event") | .documentId' $BACKUP | wc -l)
+if [ "$EVENTS" == "0" ]
+then
+      echo -e "${RED}Failure:${NC} backup does not contain events!"
+fi
+
+echo "- Restore rejects incomplete backup"
+RESP=$(head -1 $BACKUP | curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/x-ndjson" \
+  --data-binary @- \
+  $API/restore)
+if [ "$RESP" != '{"error":{"code":"bad_request","message":"Backup is incomplete"}}' ]
+then
+      echo -e "${RED}Failure:${NC} incomplete backup was restored!\n$RESP"
+fi
+
+echo "- Reader cannot export collections"
+authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/backup)
+if [ "$RESP" != "$UNAUTHORIZED" ]
+then
+      echo -e "${RED}Failure:${NC} reader can export collections!\n$RESP"
+fi
+rm -f $BACKUP
+
+
+echo "- Immutable fields cannot be changed"
+authorize_client $FLUIDS_EDITOR_CLIENT $FLUIDS_EDITOR_SECRET
+RESP=$(curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"id": "0f5c7a3e-2b1d-4c8e-9f6a-7d3b2e1c0a94","f": {"title": "Milk", "sku": "M-100"}}' \
+  $API/collections/fluids)
+if [ "$RESP" != "Document saved" ]
+then
+      echo -e "${RED}Failure:${NC} user is not allowed to save milk document!\n$RESP"
+fi
+RESP=$(curl --silent \
+  --request PUT \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"id": "0f5c7a3e-2b1d-4c8e-9f6a-7d3b2e1c0a94","f": {"title": "Whole milk", "sku": "M-100"}}' \
+  $API/collections/fluids)
+if [ "$RESP" != "Document updated" ]
+then
+      echo -e "${RED}Failure:${NC} update with unchanged immutable field was rejected!\n$RESP"
+fi
+RESP=$(curl --silent \
+  --request PUT \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"id": "0f5c7a3e-2b1d-4c8e-9f6a-7d3b2e1c0a94","f": {"title": "Whole milk", "sku": "M-200"}}' \
+  $API/collections/fluids)
+CONTENT=$(echo $RESP | jq -r '.fields | join(",")')
+if [ "$CONTENT" != "sku" ]
+then
+      echo -e "${RED}Failure:${NC} update of immutable field was accepted!\n$RESP"
+fi
+
+
+echo "- Editor can list shapes shared with them"
+authorize_client $SHAPES_EDITOR_CLIENT $SHAPES_EDITOR_SECRET
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/shapes?sharedWithMe=true\&limit=250)
+CONTENT=$(echo $RESP | jq -r '[.items[].f.title] | (index("editor2_shape_1") != null) and (index("Circle") == null)')
+if [ "$CONTENT" != "true" ]
+then
+      echo -e "${RED}Failure:${NC} shared documents list contains owned documents or misses shared ones!\n$RESP"
+fi
+
+
+echo "- Filter preserves large integers"
+authorize_client $FLUIDS_EDITOR_CLIENT $FLUIDS_EDITOR_SECRET
+for DOC in 6b1e4c2a-8d3f-4a7b-9c5e-2f0a1d3b4c50:9007199254740992 6b1e4c2a-8d3f-4a7b-9c5e-2f0a1d3b4c51:9007199254740993
+do
+  VOLUME=${DOC#*:}
+  RESP=$(curl --silent \
+    --request POST \
+    --header "Authorization: Bearer $OIDCTOKEN" \
+    --header "Content-Type: application/json" \
+    --data '{"id": "'${DOC%%:*}'","f": {"title": "Ocean '$VOLUME'", "volume": '$VOLUME'}}' \
+    $API/collections/fluids)
+  if [ "$RESP" != "Document saved" ]
+  then
+        echo -e "${RED}Failure:${NC} user is not allowed to save ocean document!\n$RESP"
+  fi
+done
+RESP=$(curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"filter": {"f": "volume", "o": "eq", "v": 9007199254740993}}' \
+  $API/collections/fluids/search)
+CONTENT=$(echo $RESP | jq -r '[.items[].f.title] | join(",")')
+if [ "$CONTENT" != "Ocean 9007199254740993" ]
+then
+      echo -e "${RED}Failure:${NC} large integer filter is not exact!\n$RESP"
+fi
+RESP=$(curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"filter": {"f": "volume", "o": "gt", "v": 1e15}}' \
+  $API/collections/fluids/search)
+CONTENT=$(echo $RESP | jq -r '.total')
+if [ "$CONTENT" != "2" ]
+then
+      echo -e "${RED}Failure:${NC} exponent filter does not match!\n$RESP"
+fi
+
+
+echo "- Reply inherits grants of the parent letter"
+authorize_client $LETTERS_BEAR_USER_CLIENT $LETTERS_BEAR_USER_SECRET
+RESP=$(curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"id": "5d0c3b7e-1f4a-4e2b-8c6d-9a7f0e3b2c11","f": {"title": "Bear reply", "reply_to": "44c64580-a795-4d56-a69d-140f726153f8"}}' \
+  $API/collections/letters)
+if [ "$RESP" != "Document saved" ]
+then
+      echo -e "${RED}Failure:${NC} user is not allowed to save reply document!\n$RESP"
+fi
+RESP=$(curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"id": "5d0c3b7e-1f4a-4e2b-8c6d-9a7f0e3b2c12","f": {"title": "Bear orphan reply", "reply_to": "00000000-0000-4000-8000-000000000000"}}' \
+  $API/collections/letters)
+if [ "$RESP" != "Document saved" ]
+then
+      echo -e "${RED}Failure:${NC} reply with missing parent was rejected!\n$RESP"
+fi
+authorize_client $LETTERS_ALPACA_USER_CLIENT $LETTERS_ALPACA_USER_SECRET
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/letters/5d0c3b7e-1f4a-4e2b-8c6d-9a7f0e3b2c11)
+CONTENT=$(echo $RESP | jq -r '.f.title')
+if [ "$CONTENT" != "Bear reply" ]
+then
+      echo -e "${RED}Failure:${NC} author of the parent letter cannot read the reply!\n$RESP"
+fi
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/letters/5d0c3b7e-1f4a-4e2b-8c6d-9a7f0e3b2c12)
+CONTENT=$(echo $RESP | jq -r '.f.title')
+if [ "$CONTENT" == "Bear orphan reply" ]
+then
+      echo -e "${RED}Failure:${NC} user can read a reply without access to its parent!\n$RESP"
+fi
+
+
+echo "- Ancestors of a reply list the parent chain"
+authorize_client $LETTERS_ALPACA_USER_CLIENT $LETTERS_ALPACA_USER_SECRET
+RESP=$(curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"id": "5d0c3b7e-1f4a-4e2b-8c6d-9a7f0e3b2c13","f": {"title": "Alpaca reply", "reply_to": "5d0c3b7e-1f4a-4e2b-8c6d-9a7f0e3b2c11"}}' \
+  $API/collections/letters)
+if [ "$RESP" != "Document saved" ]
+then
+      echo -e "${RED}Failure:${NC} user is not allowed to save reply to reply!\n$RESP"
+fi
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/letters/5d0c3b7e-1f4a-4e2b-8c6d-9a7f0e3b2c13/ancestors)
+CONTENT=$(echo $RESP | jq -r '[.items[] | .f.title] | join(",")')
+if [ "$CONTENT" != "Bear reply,Alpaca letter 2" ]
+then
+      echo -e "${RED}Failure:${NC} ancestors of the reply are wrong!\n$CONTENT\n$RESP"
+fi
+
+
+echo "- Ancestors end before an inaccessible parent"
+authorize_client $LETTERS_BEAR_USER_CLIENT $LETTERS_BEAR_USER_SECRET
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/letters/5d0c3b7e-1f4a-4e2b-8c6d-9a7f0e3b2c11/ancestors)
+CONTENT=$(echo $RESP | jq -r '.items | length')
+if [ "$CONTENT" != "0" ]
+then
+      echo -e "${RED}Failure:${NC} user can list an inaccessible ancestor!\n$RESP"
+fi
+
+
+echo "- Reply loses the grants of its previous parent letter"
+authorize_client $LETTERS_BEAR_USER_CLIENT $LETTERS_BEAR_USER_SECRET
+curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"id": "5d0c3b7e-1f4a-4e2b-8c6d-9a7f0e3b2c14","f": {"title": "Bear moving reply", "reply_to": "44c64580-a795-4d56-a69d-140f726153f8"}}' \
+  $API/collections/letters > /dev/null
+curl --silent \
+  --request PUT \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"id": "5d0c3b7e-1f4a-4e2b-8c6d-9a7f0e3b2c14","f": {"title": "Bear moving reply", "reply_to": "fc3b8fce-cac3-4dd5-92b9-11d5963b9a89"}}' \
+  $API/collections/letters > /dev/null
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/letters/5d0c3b7e-1f4a-4e2b-8c6d-9a7f0e3b2c14)
+CONTENT=$(echo $RESP | jq -r '.f.reply_to')
+if [ "$CONTENT" != "fc3b8fce-cac3-4dd5-92b9-11d5963b9a89" ]
+then
+      echo -e "${RED}Failure:${NC} user cannot move the reply to another letter!\n$RESP"
+fi
+authorize_client $LETTERS_ALPACA_USER_CLIENT $LETTERS_ALPACA_USER_SECRET
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/letters/5d0c3b7e-1f4a-4e2b-8c6d-9a7f0e3b2c14)
+CONTENT=$(echo $RESP | jq -r '.f.title')
+if [ "$CONTENT" == "Bear moving reply" ]
+then
+      echo -e "${RED}Failure:${NC} author of the previous parent letter can still read the reply!\n$RESP"
+fi
+
+
+echo "- Search rejects unknown filter operators"
+authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
+RESP=$(curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"filter": {"and": [{"f": "title", "o": "equals", "v": "Circle"}]}}' \
+  $API/collections/shapes/search)
+CONTENT=$(echo $RESP | jq -r '.error.message | contains("Unknown filter operator \"equals\", valid operators are: eq, ne")')
+if [ "$CONTENT" != "true" ]
+then
+      echo -e "${RED}Failure:${NC} unknown filter operator was not rejected!\n$RESP"
+fi
+
+
+echo "- Created events are counted per collection and category"
+metric_shapes_events() {
+  curl --silent http://localhost:3002/app/metrics \
+    | grep '^folivafy_events_created_total{collection="shapes",category="1"}' \
+    | cut -d ' ' -f 2
+}
+BEFORE=$(metric_shapes_events)
+authorize_client $SHAPES_EDITOR_CLIENT $SHAPES_EDITOR_SECRET
+RESP=$(curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"id": "3c8e2a71-5b4d-4f0e-9a6c-1d2e3f4a5b60","f": {"title": "Counted shape"}}' \
+  $API/collections/shapes)
+if [ "$RESP" != "Document saved" ]
+then
+      echo -e "${RED}Failure:${NC} user is not allowed to save counted shape!\n$RESP"
+fi
+AFTER=$(metric_shapes_events)
+if [ "$AFTER" != "$((${BEFORE:-0} + 1))" ]
+then
+      echo -e "${RED}Failure:${NC} event counter did not increase from $BEFORE to $AFTER!"
+fi
+
+
+echo "- Search returns only the first document in sort order"
+authorize_client $FLUIDS_EDITOR_CLIENT $FLUIDS_EDITOR_SECRET
+for DOC in 8a4f2c1e-7b3d-4e5a-9f0c-6d1e2a3b4c70:2 8a4f2c1e-7b3d-4e5a-9f0c-6d1e2a3b4c71:1 8a4f2c1e-7b3d-4e5a-9f0c-6d1e2a3b4c72:3
+do
+  RANK=${DOC#*:}
+  RESP=$(curl --silent \
+    --request POST \
+    --header "Authorization: Bearer $OIDCTOKEN" \
+    --header "Content-Type: application/json" \
+    --data '{"id": "'${DOC%%:*}'","f": {"title": "Ranked '$RANK'", "kind": "ranked", "rank": "'$RANK'"}}' \
+    $API/collections/fluids)
+  if [ "$RESP" != "Document saved" ]
+  then
+        echo -e "${RED}Failure:${NC} user is not allowed to save ranked document!\n$RESP"
+  fi
+done
+RESP=$(curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"filter": {"f": "kind", "o": "eq", "v": "ranked"}}' \
+  $API/collections/fluids/search?first=true\&sort=rank-)
+CONTENT=$(echo $RESP | jq -r '.id + " " + .f.title')
+if [ "$CONTENT" != "8a4f2c1e-7b3d-4e5a-9f0c-6d1e2a3b4c72 Ranked 3" ]
+then
+      echo -e "${RED}Failure:${NC} search did not return the first document!\n$RESP"
+fi
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/fluids?first=true&sort=rank%2B&pfilter=kind='ranked'")
+CONTENT=$(echo $RESP | jq -r '.f.title')
+if [ "$CONTENT" != "Ranked 1" ]
+then
+      echo -e "${RED}Failure:${NC} list did not return the first document!\n$RESP"
+fi
+RESP=$(curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"filter": {"f": "kind", "o": "eq", "v": "unranked"}}' \
+  $API/collections/fluids/search?first=true)
+if [ "$RESP" != '{"error":{"code":"not_found","message":"No matching document"}}' ]
+then
+      echo -e "${RED}Failure:${NC} search without match did not return not found!\n$RESP"
+fi
+
+
+echo "- Conditionally required fields are enforced"
+authorize_client $FLUIDS_EDITOR_CLIENT $FLUIDS_EDITOR_SECRET
+RESP=$(curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"id": "8c1f4e2a-6b3d-4a7e-9f0c-2d5b8e1a4c71","f": {"title": "Imported oil", "kind": "import"}}' \
+  $API/collections/fluids)
+CODE=$(echo $RESP | jq -r '[.error.field, .error.code] | join(" ")')
+if [ "$CODE" != "supplier required" ]
+then
+      echo -e "${RED}Failure:${NC} missing conditionally required field was accepted!\n$RESP"
+fi
+RESP=$(curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"id": "8c1f4e2a-6b3d-4a7e-9f0c-2d5b8e1a4c71","f": {"title": "Local oil", "kind": "local"}}' \
+  $API/collections/fluids)
+if [ "$RESP" != "Document saved" ]
+then
+      echo -e "${RED}Failure:${NC} field is required although the condition is not met!\n$RESP"
+fi
+
+echo "- Server time is available without authentication"
+RESP=$(curl --silent $API/time)
+NOW=$(echo $RESP | jq -r '.now')
+if ! date --date "$NOW" > /dev/null 2>&1 || [[ ! "$NOW" =~ ^[0-9]{4}-[0-9]{2}-[0-9]{2}T[0-9:.]+Z$ ]]
+then
+      echo -e "${RED}Failure:${NC} server time is not a RFC 3339 timestamp!\n$RESP"
+fi
+
+echo "- Documents with the nil id are rejected"
+authorize_client $SHAPES_EDITOR_CLIENT $SHAPES_EDITOR_SECRET
+RESP=$(curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"id": "00000000-0000-0000-0000-000000000000","f": {"title": "Nothing"}}' \
+  $API/collections/shapes)
+CODE=$(echo $RESP | jq -r '[.error.field, .error.code] | join(" ")')
+if [ "$CODE" != "id nil" ]
+then
+      echo -e "${RED}Failure:${NC} document with nil id was accepted!\n$RESP"
+fi
+
+echo "- Recompute corrects stale parent fields"
+authorize_client $LETTERS_ALPACA_USER_CLIENT $LETTERS_ALPACA_USER_SECRET
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/letters/5d0c3b7e-1f4a-4e2b-8c6d-9a7f0e3b2c11)
+VERSION=$(echo $RESP | jq -r '.f.version // 0')
+CONTENT=$(echo $RESP | jq -r '.f.reply_title')
+if [ "$CONTENT" != "Alpaca letter 2" ]
+then
+      echo -e "${RED}Failure:${NC} reply does not contain the parent title!\n$RESP"
+fi
+RESP=$(curl --silent \
+  --request PUT \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"id": "44c64580-a795-4d56-a69d-140f726153f8","f": {"title": "Alpaca letter 2 (renamed)", "content": "bar"}}' \
+  $API/collections/letters)
+if [ "$RESP" != "Document updated" ]
+then
+      echo -e "${RED}Failure:${NC} user cannot rename the parent letter!\n$RESP"
+fi
+authorize_client $COLADMIN_CLIENT $COLADMIN_SECRET
+RESP=$(curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  "$API/maintenance/letters/recompute?pfilter=reply_to%3D%2744c64580-a795-4d56-a69d-140f726153f8%27")
+CONTENT=$(echo $RESP | jq -r '[.documents, .updated] | join(" ")')
+if [ "$CONTENT" != "1 1" ]
+then
+      echo -e "${RED}Failure:${NC} recompute did not update the reply!\n$RESP"
+fi
+authorize_client $LETTERS_ALPACA_USER_CLIENT $LETTERS_ALPACA_USER_SECRET
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/letters/5d0c3b7e-1f4a-4e2b-8c6d-9a7f0e3b2c11)
+CONTENT=$(echo $RESP | jq -r '.f.reply_title')
+if [ "$CONTENT" != "Alpaca letter 2 (renamed)" ]
+then
+      echo -e "${RED}Failure:${NC} stale parent title was not recomputed!\n$RESP"
+fi
+CONTENT=$(echo $RESP | jq -r --argjson before "$VERSION" '.f.version == $before + 1')
+if [ "$CONTENT" != "true" ]
+then
+      echo -e "${RED}Failure:${NC} recompute did not increment the version!\n$RESP"
+fi
+
+echo "- Reader cannot explain a filter"
+authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes?pfilter=title%3D%27Circle%27&explainFilter=true")
+if [ "$RESP" != "$UNAUTHORIZED" ]
+then
+      echo -e "${RED}Failure:${NC} reader is allowed to explain filters!\n$RESP"
+fi
+
+
+echo "- Reader receives the enveloped list with Accept-Version v2"
+authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" --header "Accept-Version: v2" "$API/collections/shapes?limit=2")
+SHAPE=$(echo $RESP | jq -r '[(.data | type), (.meta.limit | tostring), (has("items") | tostring)] | join(" ")')
+if [ "$SHAPE" != "array 2 false" ]
+then
+      echo -e "${RED}Failure:${NC} list is not enveloped for v2!\n$SHAPE\n$RESP"
+fi
+
+
+echo "- Admin finds no documents without grants"
+authorize_client $COLADMIN_CLIENT $COLADMIN_SECRET
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/maintenance/shapes/orphans")
+CONTENT=$(echo $RESP | jq -r '.total')
+if [ "$CONTENT" != "0" ]
+then
+      echo -e "${RED}Failure:${NC} documents with grants are listed as orphans!\n$RESP"
+fi
+authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/maintenance/shapes/orphans")
+if [ "$RESP" != "$UNAUTHORIZED" ]
+then
+      echo -e "${RED}Failure:${NC} reader is allowed to list orphans!\n$RESP"
+fi
+
+
+echo "- Reader receives an exact total with approximateCount"
+authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
+EXACT=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes" | jq -r '.total')
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes?approximateCount=true")
+CONTENT=$(echo $RESP | jq -r '[(.total | tostring), (.totalApproximate | tostring)] | join(" ")')
+if [ "$CONTENT" != "$EXACT false" ]
+then
+      echo -e "${RED}Failure:${NC} small collection is not counted exactly!\n$CONTENT\n$RESP"
+fi
+
+
+echo "- Editor receives the hook summary with Prefer: hook-summary"
+authorize_client $FLUIDS_EDITOR_CLIENT $FLUIDS_EDITOR_SECRET
+RESP=$(curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --header "Prefer: hook-summary" \
+  --data '{"id": "8c1f4e2a-6b3d-4a7e-9f0c-2d5b8e1a4c72","f": {"title": "Summarized oil", "kind": "local"}}' \
+  $API/collections/fluids)
+CONTENT=$(echo $RESP | jq -r '[.message, (.events | length | tostring), .documents[0]] | join(" ")')
+if [ "$CONTENT" != "Document saved 0 8c1f4e2a-6b3d-4a7e-9f0c-2d5b8e1a4c72" ]
+then
+      echo -e "${RED}Failure:${NC} hook summary not returned!\n$CONTENT\n$RESP"
+fi
+
+
+echo "- Editor stores 100 shapes in one batch"
+authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
+BEFORE_TOTAL=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes" | jq -r '.total')
+BEFORE_EVENTS=$(metric_shapes_events)
+BATCH=$(jq -nc '[range(100) | {id: ("5d2c9e4b-1a7f-4c3e-8b6d-" + ("000000000000" + tostring)[-12:]), f: {title: "Batch shape \(.)"}}]')
+authorize_client $SHAPES_EDITOR_CLIENT $SHAPES_EDITOR_SECRET
+RESP=$(curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data "$BATCH" \
+  $API/collections/shapes/batch)
+if [ "$(echo $RESP | jq -r '.documents')" != "100" ]
+then
+      echo -e "${RED}Failure:${NC} batch was not stored!\n$RESP"
+fi
+authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
+AFTER_TOTAL=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes" | jq -r '.total')
+if [ "$AFTER_TOTAL" != "$((BEFORE_TOTAL + 100))" ]
+then
+      echo -e "${RED}Failure:${NC} document count did not increase from $BEFORE_TOTAL to $((BEFORE_TOTAL + 100)), got $AFTER_TOTAL!"
+fi
+AFTER_EVENTS=$(metric_shapes_events)
+if [ "$AFTER_EVENTS" != "$((${BEFORE_EVENTS:-0} + 100))" ]
+then
+      echo -e "${RED}Failure:${NC} event count did not increase from $BEFORE_EVENTS by 100, got $AFTER_EVENTS!"
+fi
+
+
+echo "- Batch with an invalid item is rolled back"
+authorize_client $SHAPES_EDITOR_CLIENT $SHAPES_EDITOR_SECRET
+RESP=$(curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '[{"id": "6e3d0f5c-2b8a-4d4f-9c7e-000000000001","f": {"title": "Valid batch shape"}}, {"id": "00000000-0000-0000-0000-000000000000","f": {"title": "Invalid batch shape"}}]' \
+  $API/collections/shapes/batch)
+if [ "$(echo $RESP | jq -r '.error.details.index')" != "1" ]
+then
+      echo -e "${RED}Failure:${NC} index of the invalid item not reported!\n$RESP"
+fi
+authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes/6e3d0f5c-2b8a-4d4f-9c7e-000000000001")
+if [ "$RESP" != '{"error":{"code":"not_found","message":"Document 6e3d0f5c-2b8a-4d4f-9c7e-000000000001 not found"}}' ]
+then
+      echo -e "${RED}Failure:${NC} valid item of the rejected batch was stored!\n$RESP"
+fi
+
+echo "- Batch with the id of a stored document reports the index"
+authorize_client $SHAPES_EDITOR_CLIENT $SHAPES_EDITOR_SECRET
+RESP=$(curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '[{"id": "6e3d0f5c-2b8a-4d4f-9c7e-000000000002","f": {"title": "New batch shape"}}, {"id": "5d2c9e4b-1a7f-4c3e-8b6d-000000000007","f": {"title": "Stored batch shape"}}]' \
+  $API/collections/shapes/batch)
+if [ "$(echo $RESP | jq -c '.error.details')" != '{"index":1,"message":"Duplicate document"}' ]
+then
+      echo -e "${RED}Failure:${NC} index of the duplicate item not reported!\n$RESP"
+fi
+
+echo "- References list only the accessible referencing documents"
+authorize_client $LETTERS_ALPACA_USER_CLIENT $LETTERS_ALPACA_USER_SECRET
+RESP=$(curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"id": "7a1e4c2d-3b5f-4d6a-8e9c-0f1a2b3c4d51","f": {"title": "Alpaca note", "about": "5d0c3b7e-1f4a-4e2b-8c6d-9a7f0e3b2c11"}}' \
+  $API/collections/letters)
+if [ "$RESP" != "Document saved" ]
+then
+      echo -e "${RED}Failure:${NC} user is not allowed to save alpaca note!\n$RESP"
+fi
+authorize_client $LETTERS_BEAR_USER_CLIENT $LETTERS_BEAR_USER_SECRET
+RESP=$(curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"id": "7a1e4c2d-3b5f-4d6a-8e9c-0f1a2b3c4d52","f": {"title": "Bear note", "about": "5d0c3b7e-1f4a-4e2b-8c6d-9a7f0e3b2c11"}}' \
+  $API/collections/letters)
+if [ "$RESP" != "Document saved" ]
+then
+      echo -e "${RED}Failure:${NC} user is not allowed to save bear note!\n$RESP"
+fi
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/letters/5d0c3b7e-1f4a-4e2b-8c6d-9a7f0e3b2c11/references?from=letters&field=about")
+CONTENT=$(echo $RESP | jq -r '[(.total | tostring), (.items[] | .id)] | join(" ")')
+if [ "$CONTENT" != "1 7a1e4c2d-3b5f-4d6a-8e9c-0f1a2b3c4d52" ]
+then
+      echo -e "${RED}Failure:${NC} references are wrong!\n$CONTENT\n$RESP"
+fi
+RESP=$(curl --silent --output /dev/null --write-out "%{http_code}" --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/letters/5d0c3b7e-1f4a-4e2b-8c6d-9a7f0e3b2c11/references?from=letters&field=about'")
+if [ "$RESP" != "400" ]
+then
+      echo -e "${RED}Failure:${NC} invalid referencing field was not rejected!\n$RESP"
+fi
+
+echo "- Count equals the total of the list for the same filter"
+authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
+for PFILTER in "" "title%3D%40%22Batch%22" "title%3D%22Circle%22"
+do
+  LIST_TOTAL=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes?pfilter=$PFILTER" | jq -r '.total')
+  RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes/count?pfilter=$PFILTER")
+  if [ "$(echo $RESP | jq -r '.total')" != "$LIST_TOTAL" ]
+  then
+        echo -e "${RED}Failure:${NC} count for filter '$PFILTER' differs from list total $LIST_TOTAL!\n$RESP"
+  fi
+done
+authorize_client $NO_ROLE_CLIENT $NO_ROLE_SECRET
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes/count")
+if [ "$RESP" != "$UNAUTHORIZED" ]
+then
+      echo -e "${RED}Failure:${NC} user without reader role can count shapes!\n$RESP"
+fi
+
+echo "- Import with invalid lines stores all valid lines"
+authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
+BEFORE_TOTAL=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes/count" | jq -r '.total')
+IMPORT=$(jq -nc 'range(250) | {id: (if . % 10 == 9 then "00000000-0000-0000-0000-000000000000" else "4c8b1e6d-2f3a-4b5c-9d7e-" + ("000000000000" + tostring)[-12:] end), f: {title: "Imported shape \(.)"}}'; echo '{"id": "no json')
+authorize_client $SHAPES_EDITOR_CLIENT $SHAPES_EDITOR_SECRET
+RESP=$(echo "$IMPORT" | curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/x-ndjson" \
+  --data-binary @- \
+  $API/collections/shapes/import)
+CONTENT=$(echo "$RESP" | jq -sr '[(map(select(.type == "error")) | length | tostring), (map(select(.type == "progress")) | length | tostring), (last | [.type, .processed, .imported, .failed, .aborted] | map(tostring) | join(" "))] | join(" ")')
+if [ "$CONTENT" != "26 2 end 251 225 26 false" ]
+then
+      echo -e "${RED}Failure:${NC} import result is wrong!\n$CONTENT\n$RESP"
+fi
+authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
+AFTER_TOTAL=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes/count" | jq -r '.total')
+if [ "$AFTER_TOTAL" != "$((BEFORE_TOTAL + 225))" ]
+then
+      echo -e "${RED}Failure:${NC} document count did not increase from $BEFORE_TOTAL to $((BEFORE_TOTAL + 225)), got $AFTER_TOTAL!"
+fi
+
+
+echo "- Atomic import with an invalid line stores nothing"
+IMPORT=$(jq -nc 'range(20) | {id: (if . == 15 then "00000000-0000-0000-0000-000000000000" else "4c8b1e6d-2f3a-4b5c-9d7f-" + ("000000000000" + tostring)[-12:] end), f: {title: "Atomic shape \(.)"}}')
+authorize_client $SHAPES_EDITOR_CLIENT $SHAPES_EDITOR_SECRET
+RESP=$(echo "$IMPORT" | curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/x-ndjson" \
+  --data-binary @- \
+  "$API/collections/shapes/import?atomic=true")
+CONTENT=$(echo "$RESP" | jq -sr 'map([.type, .line, .processed, .imported, .aborted] | map(select(. != null) | tostring) | join(" ")) | join(", ")')
+if [ "$CONTENT" != "error 16, end 16 0 true" ]
+then
+      echo -e "${RED}Failure:${NC} atomic import was not aborted!\n$CONTENT\n$RESP"
+fi
+authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes/count")
+if [ "$(echo $RESP | jq -r '.total')" != "$AFTER_TOTAL" ]
+then
+      echo -e "${RED}Failure:${NC} aborted atomic import stored documents!\n$RESP"
+fi
+
+echo "- Merge patch adds, overwrites and removes fields"
+authorize_client $SHAPES_EDITOR_CLIENT $SHAPES_EDITOR_SECRET
+RESP=$(curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"id": "2b7e9d1c-4a3f-4e8b-9c6d-5f1a0e2d3c41","f": {"title": "Patched shape", "price": 14, "size": {"width": 2, "height": 3}}}' \
+  $API/collections/shapes)
+if [ "$RESP" != "Document saved" ]
+then
+      echo -e "${RED}Failure:${NC} shape for patch was not saved!\n$RESP"
+fi
+RESP=$(curl --silent \
+  --request PATCH \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/merge-patch+json" \
+  --data '{"title": "Square", "color": "red", "price": null, "size": {"width": null}}' \
+  $API/collections/shapes/2b7e9d1c-4a3f-4e8b-9c6d-5f1a0e2d3c41)
+if [ "$RESP" != "Document updated" ]
+then
+      echo -e "${RED}Failure:${NC} shape was not patched!\n$RESP"
+fi
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes/2b7e9d1c-4a3f-4e8b-9c6d-5f1a0e2d3c41")
+CONTENT=$(echo $RESP | jq -c '.f | {title, color, price, size}')
+if [ "$CONTENT" != '{"title":"Square","color":"red","price":null,"size":{"height":3}}' ] || [ "$(echo $RESP | jq -r '.f | has("price")')" != "false" ]
+then
+      echo -e "${RED}Failure:${NC} patched shape is wrong!\n$CONTENT\n$RESP"
+fi
+RESP=$(curl --silent --output /dev/null --write-out "%{http_code}" \
+  --request PATCH \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/merge-patch+json" \
+  --data '{"title": "Missing"}' \
+  $API/collections/shapes/2b7e9d1c-4a3f-4e8b-9c6d-5f1a0e2d3c49)
+if [ "$RESP" != "404" ]
+then
+      echo -e "${RED}Failure:${NC} patch of a missing shape did not return 404!\n$RESP"
+fi
+
+echo "- Cursor pagination returns every shape exactly once"
+authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
+TOTAL=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes?limit=1" | jq -r '.total')
+CURSOR=""
+IDS=""
+PAGES=0
+while [ "$CURSOR" != "null" ] && [ $PAGES -lt 1000 ]
+do
+  RESP=$(curl --silent --get --header "Authorization: Bearer $OIDCTOKEN" --data-urlencode "cursor=$CURSOR" --data "limit=7&sort=title%2B" "$API/collections/shapes")
+  IDS="$IDS $(echo $RESP | jq -r '.items[].id')"
+  CURSOR=$(echo $RESP | jq -r '.nextCursor')
+  PAGES=$((PAGES + 1))
+done
+COUNT=$(echo $IDS | tr ' ' '\n' | grep -c .)
+UNIQUE=$(echo $IDS | tr ' ' '\n' | grep . | sort -u | wc -l)
+if [ "$COUNT" != "$TOTAL" ] || [ "$UNIQUE" != "$TOTAL" ]
+then
+      echo -e "${RED}Failure:${NC} cursor pagination returned $COUNT ids ($UNIQUE unique) of $TOTAL shapes!"
+fi
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes?cursor=&sort=title%2B,price-")
+if [ "$(echo $RESP | jq -r '.error.message')" != "Cursor pagination requires a single sort field" ]
+then
+      echo -e "${RED}Failure:${NC} cursor with several sort fields was not rejected!\n$RESP"
+fi
+
+echo "- List without total tells whether more shapes follow"
+authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
+TOTAL=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes?limit=1" | jq -r '.total')
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes?withTotal=false&limit=$((TOTAL - 1))")
+if [ "$(echo $RESP | jq -r '.hasMore')" != "true" ] || [ "$(echo $RESP | jq -r '.items | length')" != "$((TOTAL - 1))" ]
+then
+      echo -e "${RED}Failure:${NC} list without total did not report more shapes!\n$RESP"
+fi
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes?withTotal=false&limit=$TOTAL")
+if [ "$(echo $RESP | jq -r '.hasMore')" != "false" ] || [ "$(echo $RESP | jq -r '.items | length')" != "$TOTAL" ]
+then
+      echo -e "${RED}Failure:${NC} list without total reported more shapes after the last one!\n$RESP"
+fi
+
+echo "- Locked shape rejects writes while its sibling accepts them"
+authorize_client $SHAPES_EDITOR_CLIENT $SHAPES_EDITOR_SECRET
+for SHAPE_ID in 6d0c3f4e-8a1b-4c2d-9e7f-0a1b2c3d4e51 6d0c3f4e-8a1b-4c2d-9e7f-0a1b2c3d4e52
+do
+  RESP=$(curl --silent \
+    --request POST \
+    --header "Authorization: Bearer $OIDCTOKEN" \
+    --header "Content-Type: application/json" \
+    --data "{\"id\": \"$SHAPE_ID\",\"f\": {\"title\": \"Invoice shape\", \"price\": 20}}" \
+    $API/collections/shapes)
+  if [ "$RESP" != "Document saved" ]
+  then
+        echo -e "${RED}Failure:${NC} shape $SHAPE_ID for lock was not saved!\n$RESP"
+  fi
+done
+RESP=$(curl --silent --output /dev/null --write-out "%{http_code}" \
+  --request PUT \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  $API/collections/shapes/6d0c3f4e-8a1b-4c2d-9e7f-0a1b2c3d4e51/lock)
+if [ "$RESP" != "401" ]
+then
+      echo -e "${RED}Failure:${NC} editor is allowed to lock a shape!\n$RESP"
+fi
+authorize_client $COLADMIN_CLIENT $COLADMIN_SECRET
+RESP=$(curl --silent --output /dev/null --write-out "%{http_code}" \
+  --request PUT \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  $API/collections/shapes/6d0c3f4e-8a1b-4c2d-9e7f-0a1b2c3d4e51/lock)
+if [ "$RESP" != "204" ]
+then
+      echo -e "${RED}Failure:${NC} shape was not locked!\n$RESP"
+fi
+authorize_client $SHAPES_EDITOR_CLIENT $SHAPES_EDITOR_SECRET
+RESP=$(curl --silent --output /dev/null --write-out "%{http_code}" \
+  --request PATCH \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/merge-patch+json" \
+  --data '{"price": 21}' \
+  $API/collections/shapes/6d0c3f4e-8a1b-4c2d-9e7f-0a1b2c3d4e51)
+if [ "$RESP" != "423" ]
+then
+      echo -e "${RED}Failure:${NC} locked shape was updated!\n$RESP"
+fi
+RESP=$(curl --silent \
+  --request PATCH \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/merge-patch+json" \
+  --data '{"price": 21}' \
+  $API/collections/shapes/6d0c3f4e-8a1b-4c2d-9e7f-0a1b2c3d4e52)
+if [ "$RESP" != "Document updated" ]
+then
+      echo -e "${RED}Failure:${NC} sibling of the locked shape was not updated!\n$RESP"
+fi
+authorize_client $COLADMIN_CLIENT $COLADMIN_SECRET
+RESP=$(curl --silent --output /dev/null --write-out "%{http_code}" \
+  --request DELETE \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  $API/collections/shapes/6d0c3f4e-8a1b-4c2d-9e7f-0a1b2c3d4e51/lock)
+if [ "$RESP" != "204" ]
+then
+      echo -e "${RED}Failure:${NC} shape was not unlocked!\n$RESP"
+fi
+authorize_client $SHAPES_EDITOR_CLIENT $SHAPES_EDITOR_SECRET
+RESP=$(curl --silent \
+  --request PATCH \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/merge-patch+json" \
+  --data '{"price": 21}' \
+  $API/collections/shapes/6d0c3f4e-8a1b-4c2d-9e7f-0a1b2c3d4e51)
+if [ "$RESP" != "Document updated" ]
+then
+      echo -e "${RED}Failure:${NC} unlocked shape was not updated!\n$RESP"
+fi
+
+echo "- Remover restores a deleted shape"
+authorize_client $SHAPES_EDITOR_CLIENT $SHAPES_EDITOR_SECRET
+RESP=$(curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"id": "0f6a2b9c-3d4e-4f50-8a61-7b8c9d0e1f23","f": {"title": "Restorable shape", "price": 30}}' \
+  $API/collections/shapes)
+if [ "$RESP" != "Document saved" ]
+then
+      echo -e "${RED}Failure:${NC} restorable shape was not saved!\n$RESP"
+fi
+authorize_client $SHAPES_REMOVER_CLIENT $SHAPES_REMOVER_SECRET
+RESP=$(curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"category": 2,"collection": "shapes", "document": "0f6a2b9c-3d4e-4f50-8a61-7b8c9d0e1f23","e": {}}' \
+  $API/events)
+if [ "$RESP" != "Done" ]
+then
+      echo -e "${RED}Failure:${NC} restorable shape was not deleted!\n$RESP"
+fi
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/recoverables/shapes?exactTitle=Restorable%20shape")
+IDS=$(echo $RESP | jq -r '[.items[].id] | join(" ")')
+if [ "$IDS" != "0f6a2b9c-3d4e-4f50-8a61-7b8c9d0e1f23" ]
+then
+      echo -e "${RED}Failure:${NC} deleted shape is not recoverable!\n$RESP"
+fi
+RESP=$(curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  $API/recoverables/shapes/0f6a2b9c-3d4e-4f50-8a61-7b8c9d0e1f23/restore)
+if [ "$RESP" != "Done" ]
+then
+      echo -e "${RED}Failure:${NC} deleted shape was not restored!\n$RESP"
+fi
+authorize_client $SHAPES_EDITOR_CLIENT $SHAPES_EDITOR_SECRET
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes?exactTitle=Restorable%20shape")
+IDS=$(echo $RESP | jq -r '[.items[].id] | join(" ")')
+if [ "$IDS" != "0f6a2b9c-3d4e-4f50-8a61-7b8c9d0e1f23" ]
+then
+      echo -e "${RED}Failure:${NC} restored shape is not listed!\n$RESP"
+fi
+authorize_client $SHAPES_REMOVER_CLIENT $SHAPES_REMOVER_SECRET
+RESP=$(curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  $API/recoverables/shapes/0f6a2b9c-3d4e-4f50-8a61-7b8c9d0e1f23/restore)
+if [ "$RESP" != "{\"error\":{\"code\":\"bad_request\",\"message\":\"Document is not in deleted stage\"}}" ]
+then
+      echo -e "${RED}Failure:${NC} shape that is not deleted was restored!\n$RESP"
+fi
+
+echo "- Collections admin replaces and reads the settings of a collection"
+authorize_client $COLADMIN_CLIENT $COLADMIN_SECRET
+RESP=$(curl --silent --output /dev/null --write-out "%{http_code}" \
+  --request PUT \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"deletedDocumentAccess": "owner", "eventCategories": {"1020": "Approved"}}' \
+  $API/collections/shapes/config)
+if [ "$RESP" != "204" ]
+then
+      echo -e "${RED}Failure:${NC} settings of shapes were not replaced!\n$RESP"
+fi
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/shapes/config)
+FIELDS=$(echo $RESP | jq -r '[.deletedDocumentAccess, .eventCategories["1020"]] | join(" ")')
+if [ "$FIELDS" != "owner Approved" ]
+then
+      echo -e "${RED}Failure:${NC} settings of shapes were not read back!\n$FIELDS\n$RESP"
+fi
+RESP=$(curl --silent \
+  --request PUT \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"deletedDocumentAccess": "owner", "idVersions": [9]}' \
+  $API/collections/shapes/config)
+FIELDS=$(echo $RESP | jq -r 'keys | join(" ")')
+if [ "$FIELDS" != "id_versions" ]
+then
+      echo -e "${RED}Failure:${NC} invalid settings of shapes were accepted!\n$RESP"
+fi
+authorize_client $NO_ROLE_CLIENT $NO_ROLE_SECRET
+RESP=$(curl --silent --output /dev/null --write-out "%{http_code}" \
+  --request PUT \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{}' \
+  $API/collections/shapes/config)
+if [ "$RESP" != "401" ]
+then
+      echo -e "${RED}Failure:${NC} user without role replaced the settings of shapes!\n$RESP"
+fi
+
+echo "- Collections admin reads the effective settings of a collection"
+authorize_client $COLADMIN_CLIENT $COLADMIN_SECRET
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/shapes/effective-config)
+CONTENT=$(echo $RESP | jq -c '{name, access: .config.deletedDocumentAccess, category: .config.eventCategories["1020"], textSearchFields: .config.textSearchFields, maxLimit: .pageLimits.maxLimit, eventHooks: .hooks.eventCategories}')
+if [ "$CONTENT" != '{"name":"shapes","access":"owner","category":"Approved","textSearchFields":[],"maxLimit":250,"eventHooks":[2,3]}' ]
+then
+      echo -e "${RED}Failure:${NC} effective settings of shapes!\n$CONTENT\n$RESP"
+fi
+authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
+RESP=$(curl --silent --output /dev/null --write-out "%{http_code}" --header "Authorization: Bearer $OIDCTOKEN" $API/collections/shapes/effective-config)
+if [ "$RESP" != "401" ]
+then
+      echo -e "${RED}Failure:${NC} reader read the effective settings of shapes!\n$RESP"
+fi
+
+echo "- Numeric sort on a declared text field is rejected"
+authorize_client $COLADMIN_CLIENT $COLADMIN_SECRET
+RESP=$(curl --silent --output /dev/null --write-out "%{http_code}" \
+  --request PUT \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"deletedDocumentAccess": "owner", "fieldTypes": {"title": "text", "price": "number"}}' \
+  $API/collections/shapes/config)
+if [ "$RESP" != "204" ]
+then
+      echo -e "${RED}Failure:${NC} field types of shapes were not stored!\n$RESP"
+fi
+authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes?sort=titlef")
+if [ "$RESP" != "{\"error\":{\"code\":\"bad_request\",\"message\":\"Field title is text, sort it with + or -\"}}" ]
+then
+      echo -e "${RED}Failure:${NC} numeric sort on text field was accepted!\n$RESP"
+fi
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes?sort=title%2B,priceb")
+TOTAL=$(echo $RESP | jq -r '.total')
+if [ "$TOTAL" == "null" ]
+then
+      echo -e "${RED}Failure:${NC} sort matching the field types was rejected!\n$RESP"
+fi
+
+echo "- Distinct values are counted per value"
+authorize_client $SHAPES_EDITOR_CLIENT $SHAPES_EDITOR_SECRET
+FACET_NUMBER=0
+for FACET_STATUS in open open open done done closed
+do
+  FACET_NUMBER=$((FACET_NUMBER + 1))
+  RESP=$(curl --silent \
+    --request POST \
+    --header "Authorization: Bearer $OIDCTOKEN" \
+    --header "Content-Type: application/json" \
+    --data "{\"id\": \"7a9e0c52-1b3d-4e5f-8a6b-0c1d2e3f4a5$FACET_NUMBER\",\"f\": {\"title\": \"Facet shape\", \"status\": \"$FACET_STATUS\"}}" \
+    $API/collections/shapes)
+  if [ "$RESP" != "Document saved" ]
+  then
+        echo -e "${RED}Failure:${NC} facet shape was not saved!\n$RESP"
+  fi
+done
+authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes/distinct?field=status&exactTitle=Facet%20shape")
+FIELDS=$(echo $RESP | jq -r '[(.items[] | "\(.value)=\(.count)"), .hasMore] | join(" ")')
+if [ "$FIELDS" != "open=3 done=2 closed=1 false" ]
+then
+      echo -e "${RED}Failure:${NC} distinct values are not counted!\n$FIELDS\n$RESP"
+fi
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes/distinct?field=status&exactTitle=Facet%20shape&limit=2")
+FIELDS=$(echo $RESP | jq -r '[(.items[] | "\(.value)=\(.count)"), .hasMore] | join(" ")')
+if [ "$FIELDS" != "open=3 done=2 true" ]
+then
+      echo -e "${RED}Failure:${NC} distinct values are not limited!\n$FIELDS\n$RESP"
+fi
+
+echo "- Collections admin lists readable grants of a shape"
+authorize_client $COLADMIN_CLIENT $COLADMIN_SECRET
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes/6d0c3f4e-8a1b-4c2d-9e7f-0a1b2c3d4e51/grants?readable=true")
+FIELDS=$(echo $RESP | jq -r '[.[] | select(.realm == "read-collection") | .description, .name] | join(" ")')
+if [ "$FIELDS" != "Readers of the collection C_SHAPES_READER" ]
+then
+      echo -e "${RED}Failure:${NC} grants of the shape are not readable!\n$FIELDS\n$RESP"
+fi
+authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
+RESP=$(curl --silent --output /dev/null --write-out "%{http_code}" --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes/6d0c3f4e-8a1b-4c2d-9e7f-0a1b2c3d4e51/grants")
+if [ "$RESP" != "401" ]
+then
+      echo -e "${RED}Failure:${NC} reader can list the grants of a shape!\n$RESP"
+fi
+
+echo "- Shape nested deeper than the maximum field depth is rejected"
+authorize_client $COLADMIN_CLIENT $COLADMIN_SECRET
+RESP=$(curl --silent --output /dev/null --write-out "%{http_code}" \
+  --request PUT \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"deletedDocumentAccess": "owner", "fieldTypes": {"title": "text", "price": "number"}, "maxFieldDepth": 2}' \
+  $API/collections/shapes/config)
+if [ "$RESP" != "204" ]
+then
+      echo -e "${RED}Failure:${NC} maximum field depth of shapes was not stored!\n$RESP"
+fi
+authorize_client $SHAPES_EDITOR_CLIENT $SHAPES_EDITOR_SECRET
+RESP=$(curl --silent \
+    --request POST \
+    --header "Authorization: Bearer $OIDCTOKEN" \
+    --header "Content-Type: application/json" \
+    --data '{"id": "3e5b7d91-2c4a-4f6e-8b0d-1a2b3c4d5e61","f": {"title": "Nested shape", "corners": {"top": {"left": 1}}}}' \
+    $API/collections/shapes)
+if [ "$RESP" != "{\"error\":{\"code\":\"bad_request\",\"message\":\"Document fields are nested deeper than 2 levels\"}}" ]
+then
+      echo -e "${RED}Failure:${NC} deeply nested shape was accepted!\n$RESP"
+fi
+RESP=$(curl --silent \
+    --request POST \
+    --header "Authorization: Bearer $OIDCTOKEN" \
+    --header "Content-Type: application/json" \
+    --data '{"id": "3e5b7d91-2c4a-4f6e-8b0d-1a2b3c4d5e62","f": {"title": "Nested shape", "corners": {"top": 1}}}' \
+    $API/collections/shapes)
+if [ "$RESP" != "Document saved" ]
+then
+      echo -e "${RED}Failure:${NC} nested shape within the depth was not saved!\n$RESP"
+fi
+
+echo "- Can list shapes that are not owned by a user"
+authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes?exactTitle=Facet%20shape&pfilter=author_id!%3D${SHAPES_EDITOR_UID}")
+TOTAL=$(echo $RESP | jq -r '.total')
+if [ "$TOTAL" != "0" ]
+then
+      echo -e "${RED}Failure:${NC} list of documents contains shapes of author_id=${SHAPES_EDITOR_UID}!\n$RESP"
+fi
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes?exactTitle=Facet%20shape&pfilter=author_id%3D!%5B00000000-0000-0000-0000-000000000000%5D")
+TOTAL=$(echo $RESP | jq -r '.total')
+if [ "$TOTAL" != "6" ]
+then
+      echo -e "${RED}Failure:${NC} list of documents with excluded authors does not match!\n$RESP"
+fi
+
+echo "- Editor validates shapes without storing them"
+authorize_client $SHAPES_EDITOR_CLIENT $SHAPES_EDITOR_SECRET
+RESP=$(curl --silent \
+    --request POST \
+    --header "Authorization: Bearer $OIDCTOKEN" \
+    --header "Content-Type: application/json" \
+    --data '{"id": "6d0c3f4e-8a1b-4c2d-9e7f-0a1b2c3d4e51","f": {"title": "Nested shape", "corners": {"top": {"left": 1}}}}' \
+    $API/collections/shapes/validate)
+FIELDS=$(echo $RESP | jq -r '[.valid, .errors.id[0].code, .errors.f[0].code] | join(" ")')
+if [ "$FIELDS" != "false unique max_depth" ]
+then
+      echo -e "${RED}Failure:${NC} invalid shape was not reported!\n$FIELDS\n$RESP"
+fi
+RESP=$(curl --silent \
+    --request POST \
+    --header "Authorization: Bearer $OIDCTOKEN" \
+    --header "Content-Type: application/json" \
+    --data '{"id": "3e5b7d91-2c4a-4f6e-8b0d-1a2b3c4d5e63","f": {"title": "Valid shape"}}' \
+    $API/collections/shapes/validate)
+if [ "$RESP" != "{\"valid\":true}" ]
+then
+      echo -e "${RED}Failure:${NC} valid shape was not accepted!\n$RESP"
+fi
+authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
+RESP=$(curl --silent --output /dev/null --write-out "%{http_code}" --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes/3e5b7d91-2c4a-4f6e-8b0d-1a2b3c4d5e63")
+if [ "$RESP" != "404" ]
+then
+      echo -e "${RED}Failure:${NC} validated shape was stored!\n$RESP"
+fi
+
+echo "- Reader lists the events of a shape page by page"
+authorize_client $SHAPES_EDITOR_CLIENT $SHAPES_EDITOR_SECRET
+RESP=$(curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"id": "4b2d6f80-1a3c-4e5d-9f70-8a9b0c1d2e31","f": {"title": "Event shape", "price": 10}}' \
+  $API/collections/shapes)
+if [ "$RESP" != "Document saved" ]
+then
+      echo -e "${RED}Failure:${NC} event shape was not saved!\n$RESP"
+fi
+for PRICE in 11 12
+do
+  RESP=$(curl --silent \
+    --request PUT \
+    --header "Authorization: Bearer $OIDCTOKEN" \
+    --header "Content-Type: application/json" \
+    --data '{"id": "4b2d6f80-1a3c-4e5d-9f70-8a9b0c1d2e31","f": {"title": "Event shape", "price": '$PRICE'}}' \
+    $API/collections/shapes)
+  if [ "$RESP" != "Document updated" ]
+  then
+        echo -e "${RED}Failure:${NC} event shape was not updated!\n$RESP"
+  fi
+done
+authorize_client $SHAPES_REMOVER_CLIENT $SHAPES_REMOVER_SECRET
+RESP=$(curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"category": 2,"collection": "shapes", "document": "4b2d6f80-1a3c-4e5d-9f70-8a9b0c1d2e31","e": {}}' \
+  $API/events)
+if [ "$RESP" != "Done" ]
+then
+      echo -e "${RED}Failure:${NC} event shape was not deleted!\n$RESP"
+fi
+RESP=$(curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  $API/recoverables/shapes/4b2d6f80-1a3c-4e5d-9f70-8a9b0c1d2e31/restore)
+if [ "$RESP" != "Done" ]
+then
+      echo -e "${RED}Failure:${NC} event shape was not restored!\n$RESP"
+fi
+authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes/4b2d6f80-1a3c-4e5d-9f70-8a9b0c1d2e31/events?category=1")
+CONTENT=$(echo $RESP | jq -c '{total, categories: [.items[].category]}')
+if [ "$CONTENT" != '{"total":3,"categories":[1,1,1]}' ]
+then
+      echo -e "${RED}Failure:${NC} update events of event shape!\n$RESP"
+fi
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes/4b2d6f80-1a3c-4e5d-9f70-8a9b0c1d2e31/events?category=2")
+CONTENT=$(echo $RESP | jq -c '{total, categories: [.items[].category]}')
+if [ "$CONTENT" != '{"total":1,"categories":[2]}' ]
+then
+      echo -e "${RED}Failure:${NC} delete events of event shape!\n$RESP"
+fi
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes/4b2d6f80-1a3c-4e5d-9f70-8a9b0c1d2e31/events?limit=2&offset=1")
+CONTENT=$(echo $RESP | jq -c '{limit, offset, total, categories: [.items[].category]}')
+if [ "$CONTENT" != '{"limit":2,"offset":1,"total":5,"categories":[2,1]}' ]
+then
+      echo -e "${RED}Failure:${NC} page of events of event shape!\n$RESP"
+fi
+
+echo "- Reader lists the events of the shapes collection within a time window"
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes/4b2d6f80-1a3c-4e5d-9f70-8a9b0c1d2e31/events?category=2")
+DELETED_TS=$(echo $RESP | jq -r '.items[0].ts')
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes/events?category=2&from=$DELETED_TS")
+CONTENT=$(echo $RESP | jq -c '[.items[] | select(.documentId == "4b2d6f80-1a3c-4e5d-9f70-8a9b0c1d2e31") | .category]')
+if [ "$CONTENT" != '[2]' ]
+then
+      echo -e "${RED}Failure:${NC} events from the start of the window!\n$RESP"
+fi
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes/events?category=2&to=$DELETED_TS")
+CONTENT=$(echo $RESP | jq -c '[.items[] | select(.documentId == "4b2d6f80-1a3c-4e5d-9f70-8a9b0c1d2e31") | .category]')
+if [ "$CONTENT" != '[]' ]
+then
+      echo -e "${RED}Failure:${NC} events before the end of the window!\n$RESP"
+fi
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes/events?from=2000-01-01T00:00:00Z&to=2000-01-02T00:00:00Z")
+CONTENT=$(echo $RESP | jq -c '{total, items}')
+if [ "$CONTENT" != '{"total":0,"items":[]}' ]
+then
+      echo -e "${RED}Failure:${NC} events outside of the window!\n$RESP"
+fi
+
+echo "- Editor uploads a shape in chunks"
+authorize_client $SHAPES_EDITOR_CLIENT $SHAPES_EDITOR_SECRET
+DOC='{"id": "5c3e7a91-2b4d-4f6e-8a10-9b0c1d2e3f42","f": {"title": "Uploaded shape", "price": 7}}'
+RESP=$(curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"size": '${#DOC}'}' \
+  $API/collections/shapes/uploads)
+UPLOAD_ID=$(echo $RESP | jq -r '.id')
+OFFSET=$(echo $RESP | jq -r '.offset')
+if [ "$OFFSET" != "0" ]
+then
+      echo -e "${RED}Failure:${NC} upload of shape was not started!\n$RESP"
+fi
+for START in 0 30 60 90
+do
+  CHUNK="${DOC:$START:30}"
+  RESP=$(curl --silent \
+    --request PATCH \
+    --header "Authorization: Bearer $OIDCTOKEN" \
+    --header "Content-Type: application/octet-stream" \
+    --data-binary "$CHUNK" \
+    "$API/collections/shapes/uploads/$UPLOAD_ID?offset=$START")
+  OFFSET=$(echo $RESP | jq -r '.offset')
+  if [ "$OFFSET" != "$(( START + ${#CHUNK} ))" ]
+  then
+        echo -e "${RED}Failure:${NC} chunk at $START was not uploaded!\n$RESP"
+  fi
+done
+RESP=$(curl --silent --output /dev/null --write-out "%{http_code}" \
+  --request PATCH \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/octet-stream" \
+  --data-binary "${DOC:0:30}" \
+  "$API/collections/shapes/uploads/$UPLOAD_ID?offset=0")
+if [ "$RESP" != "409" ]
+then
+      echo -e "${RED}Failure:${NC} chunk at wrong offset was accepted!\n$RESP"
+fi
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/shapes/uploads/$UPLOAD_ID)
+CONTENT=$(echo $RESP | jq -c '[.size, .offset]')
+if [ "$CONTENT" != "[${#DOC},${#DOC}]" ]
+then
+      echo -e "${RED}Failure:${NC} progress of upload!\n$RESP"
+fi
+RESP=$(curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  $API/collections/shapes/uploads/$UPLOAD_ID/finalize)
+if [ "$RESP" != "Document saved" ]
+then
+      echo -e "${RED}Failure:${NC} uploaded shape was not saved!\n$RESP"
+fi
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/shapes/5c3e7a91-2b4d-4f6e-8a10-9b0c1d2e3f42)
+CONTENT=$(echo $RESP | jq -c '.f')
+if [ "$CONTENT" != '{"price":7,"title":"Uploaded shape"}' ]
+then
+      echo -e "${RED}Failure:${NC} uploaded shape content!\n$RESP"
+fi
+RESP=$(curl --silent --output /dev/null --write-out "%{http_code}" --header "Authorization: Bearer $OIDCTOKEN" $API/collections/shapes/uploads/$UPLOAD_ID)
+if [ "$RESP" != "404" ]
+then
+      echo -e "${RED}Failure:${NC} finalized upload was not discarded!\n$RESP"
+fi
+
+echo "- Reader lists shapes that match one of two pfilter conditions"
+authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes?sort=title%2B&pfilter=title%3DEvent%20shape%7Ctitle%3DUploaded%20shape")
+TITLES=$(echo $RESP | jq -r '[.items[].f.title] | join(",")')
+if [ "$TITLES" != "Event shape,Uploaded shape" ]
+then
+      echo -e "${RED}Failure:${NC} shapes of the or-filter!\n$RESP"
+fi
+
+echo "- Collections admin rebuilds the grants of shapes as a dry run"
+authorize_client $COLADMIN_CLIENT $COLADMIN_SECRET
+BEFORE=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes/6d0c3f4e-8a1b-4c2d-9e7f-0a1b2c3d4e51/grants" | jq -c 'sort_by(tostring)')
+RESP=$(curl --silent --write-out "\n%{http_code}" \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  "$API/maintenance/shapes/rebuild-grants?dryRun=true")
+DRYRUN=$(echo "$RESP" | head -n 1)
+CONTENT=$(echo "$DRYRUN" | jq -r '[(.dryRun | tostring), (.documents > 0 | tostring), (.documentsAffected | type), (.grantsAdded | type), (.grantsRemoved | type)] | join(" ")')
+if [ "$CONTENT" != "true true number number number" ] || [ "$(echo "$RESP" | tail -n 1)" != "200" ]
+then
+      echo -e "${RED}Failure:${NC} summary of the dry run!\n$RESP"
+fi
+AFTER=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes/6d0c3f4e-8a1b-4c2d-9e7f-0a1b2c3d4e51/grants" | jq -c 'sort_by(tostring)')
+if [ "$BEFORE" != "$AFTER" ]
+then
+      echo -e "${RED}Failure:${NC} dry run changed the grants!\n$BEFORE\n$AFTER"
+fi
+RESP=$(curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  "$API/maintenance/shapes/rebuild-grants")
+if [ "$(echo "$RESP" | jq -c 'del(.dryRun)')" != "$(echo "$DRYRUN" | jq -c 'del(.dryRun)')" ]
+then
+      echo -e "${RED}Failure:${NC} rebuild differs from the dry run!\n$DRYRUN\n$RESP"
+fi
+
+echo "- Reader lists shapes with an exact title in another field"
+authorize_client $SHAPES_EDITOR_CLIENT $SHAPES_EDITOR_SECRET
+RESP=$(curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"id": "7e4d2c1b-3a5f-4b6c-9d8e-0f1a2b3c4d72","f": {"title": "Named shape", "name": "Rhombus", "price": 4}}' \
+  $API/collections/shapes)
+if [ "$RESP" != "Document saved" ]
+then
+      echo -e "${RED}Failure:${NC} named shape was not saved!\n$RESP"
+fi
+authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes?exactTitle=Rhombus&titleField=name")
+TITLES=$(echo $RESP | jq -r '[.items[].f.title] | join(",")')
+if [ "$TITLES" != "Named shape" ]
+then
+      echo -e "${RED}Failure:${NC} shapes with the exact name!\n$RESP"
+fi
+RESP=$(curl --silent --output /dev/null --write-out "%{http_code}" --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes?exactTitle=Rhombus&titleField=name%27--")
+if [ "$RESP" != "400" ]
+then
+      echo -e "${RED}Failure:${NC} invalid title field is accepted!\n$RESP"
+fi
+
+echo "- Readiness probe reaches the database"
+RESP=$(curl --silent --write-out " %{http_code}" http://localhost:3002/healthz)
+if [ "$RESP" != '{"db":"ok"} 200' ]
+then
+      echo -e "${RED}Failure:${NC} readiness probe!\n$RESP"
+fi
+
+echo "- Reader searches shapes with a regex"
+authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
+RESP=$(curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"filter": {"f": "title", "o": "regexi", "v": "^(named|uploaded) SHAPE$"}}' \
+  "$API/collections/shapes/search?sort=title%2B")
+TITLES=$(echo $RESP | jq -r '[.items[].f.title] | join(",")')
+if [ "$TITLES" != "Named shape,Uploaded shape" ]
+then
+      echo -e "${RED}Failure:${NC} shapes of the regex search!\n$RESP"
+fi
+RESP=$(curl --silent --output /dev/null --write-out "%{http_code}" \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"filter": {"f": "title", "o": "regex", "v": "(shape"}}' \
+  "$API/collections/shapes/search")
+if [ "$RESP" != "400" ]
+then
+      echo -e "${RED}Failure:${NC} invalid regex is accepted!\n$RESP"
+fi
+
+echo "- Editor receives the stored shape with Accept: application/json"
+authorize_client $SHAPES_EDITOR_CLIENT $SHAPES_EDITOR_SECRET
+RESP=$(curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --header "Accept: application/json" \
+  --data '{"id": "8a5e3d2c-4b6f-4c7d-8e9f-1a2b3c4d5e83","f": {"title": "Returned shape", "price": 6}}' \
+  $API/collections/shapes)
+CONTENT=$(echo $RESP | jq -c '[.id, .f.title, .f.price]')
+if [ "$CONTENT" != '["8a5e3d2c-4b6f-4c7d-8e9f-1a2b3c4d5e83","Returned shape",6]' ]
+then
+      echo -e "${RED}Failure:${NC} stored shape is not returned!\n$RESP"
+fi
+
+echo "- Reader lists shapes that have a name"
+authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes?pfilter=name%3D*")
+TITLES=$(echo $RESP | jq -r '[.items[].f.title] | join(",")')
+if [ "$TITLES" != "Named shape" ]
+then
+      echo -e "${RED}Failure:${NC} shapes with a name!\n$RESP"
+fi
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes?pfilter=name%3D!*&exactTitle=Named%20shape")
+CONTENT=$(echo $RESP | jq -r '.total')
+if [ "$CONTENT" != "0" ]
+then
+      echo -e "${RED}Failure:${NC} shape with a name is listed as without name!\n$RESP"
+fi
+
+echo "- Editor repeats a create request with the same Idempotency-Key"
+authorize_client $SHAPES_EDITOR_CLIENT $SHAPES_EDITOR_SECRET
+for DOCID in 9b6f4e3d-5c7a-4d8e-9f0a-2b3c4d5e6f94 9b6f4e3d-5c7a-4d8e-9f0a-2b3c4d5e6f95
+do
+  RESP=$(curl --silent \
+    --request POST \
+    --header "Authorization: Bearer $OIDCTOKEN" \
+    --header "Content-Type: application/json" \
+    --header "Accept: application/json" \
+    --header "Idempotency-Key: retried-shape-1" \
+    --data '{"id": "'$DOCID'","f": {"title": "Retried shape"}}' \
+    $API/collections/shapes)
+done
+ID=$(echo $RESP | jq -r '.id')
+if [ "$ID" != "9b6f4e3d-5c7a-4d8e-9f0a-2b3c4d5e6f94" ]
+then
+      echo -e "${RED}Failure:${NC} repeated request does not return the original document!\n$RESP"
+fi
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes?exactTitle=Retried%20shape")
+TOTAL=$(echo $RESP | jq -r '.total')
+if [ "$TOTAL" != "1" ]
+then
+      echo -e "${RED}Failure:${NC} repeated request created another document!\n$RESP"
+fi
+
+echo "- Search rejects deeply nested filter groups"
+authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
+FILTER='{"f": "title", "o": "eq", "v": "Circle"}'
+for LEVEL in $(seq 1 9)
+do
+  FILTER='{"and": ['$FILTER']}'
+done
+RESP=$(curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"filter": '"$FILTER"'}' \
+  $API/collections/shapes/search)
+CONTENT=$(echo $RESP | jq -r '.error.message | contains("Filter groups are nested deeper than 8 levels")')
+if [ "$CONTENT" != "true" ]
+then
+      echo -e "${RED}Failure:${NC} deeply nested filter is accepted!\n$RESP"
+fi
+
+echo "- Collections admin changes the title and locks a collection"
+authorize_client $COLADMIN_CLIENT $COLADMIN_SECRET
+RESP=$(curl --silent \
+  --request PUT \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"title": "Liquids", "locked": true}' \
+  $API/collections/fluids/properties)
+CONTENT=$(echo $RESP | jq -c '[.name, .title, .oao, .locked]')
+if [ "$CONTENT" != '["fluids","Liquids",false,true]' ]
+then
+      echo -e "${RED}Failure:${NC} collection is not updated!\n$RESP"
+fi
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/fluids/effective-config)
+CONTENT=$(echo $RESP | jq -c '[.title, .locked]')
+if [ "$CONTENT" != '["Liquids",true]' ]
+then
+      echo -e "${RED}Failure:${NC} collection update is not stored!\n$RESP"
+fi
+
+
+echo "- Collections admin cannot rename a collection"
+RESP=$(curl --silent \
+  --request PUT \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"name": "liquids"}' \
+  $API/collections/fluids/properties)
+if [ "$RESP" != '{"error":{"code":"bad_request","message":"The name of a collection cannot be changed"}}' ]
+then
+      echo -e "${RED}Failure:${NC} collection name can be changed!\n$RESP"
+fi
+
+
+echo "- Collections admin unlocks a collection"
+RESP=$(curl --silent \
+  --request PUT \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"title": "Fluids", "locked": false}' \
+  $API/collections/fluids/properties)
+CONTENT=$(echo $RESP | jq -c '[.title, .locked]')
+if [ "$CONTENT" != '["Fluids",false]' ]
+then
+      echo -e "${RED}Failure:${NC} collection is not unlocked!\n$RESP"
+fi
+
+
+echo "- Changing the owner access rebuilds the grants of the documents"
+function set_shapes_oao {
+  authorize_client $COLADMIN_CLIENT $COLADMIN_SECRET
+  curl --silent \
+    --request PUT \
+    --header "Authorization: Bearer $OIDCTOKEN" \
+    --header "Content-Type: application/json" \
+    --data "{\"oao\": $1}" \
+    $API/collections/shapes/properties > /dev/null
+}
+set_shapes_oao true
+authorize_client $SHAPES_EDITOR_CLIENT $SHAPES_EDITOR_SECRET
+curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"id": "7a4c2e91-5b3d-4f6a-8e1c-9d2b4f6a8c31","f": {"title": "Private pentagon"}}' \
+  $API/collections/shapes > /dev/null
+authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/shapes/7a4c2e91-5b3d-4f6a-8e1c-9d2b4f6a8c31)
+CONTENT=$(echo $RESP | jq -r '.f.title')
+if [ "$CONTENT" == "Private pentagon" ]
+then
+      echo -e "${RED}Failure:${NC} reader can read a document of an owner access only collection!\n$RESP"
+fi
+set_shapes_oao false
+authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/shapes/7a4c2e91-5b3d-4f6a-8e1c-9d2b4f6a8c31)
+CONTENT=$(echo $RESP | jq -r '.f.title')
+if [ "$CONTENT" != "Private pentagon" ]
+then
+      echo -e "${RED}Failure:${NC} reader cannot read the document after the collection became public!\n$RESP"
+fi
+
+
+echo "- Editor cannot update a collection"
+authorize_client $SHAPES_EDITOR_CLIENT $SHAPES_EDITOR_SECRET
+RESP=$(curl --silent \
+  --request PUT \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"locked": true}' \
+  $API/collections/shapes/properties)
+if [ "$RESP" != "$UNAUTHORIZED" ]
+then
+      echo -e "${RED}Failure:${NC} editor can update a collection!\n$RESP"
+fi
+
+echo "- Editor touches a shape without changing its fields"
+authorize_client $SHAPES_EDITOR_CLIENT $SHAPES_EDITOR_SECRET
+UPDATED=""
+for TOUCH in 1 2
+do
+  RESP=$(curl --silent \
+    --request POST \
+    --header "Authorization: Bearer $OIDCTOKEN" \
+    $API/collections/shapes/8a5e3d2c-4b6f-4c7d-8e9f-1a2b3c4d5e83/touch)
+  if [ "$RESP" != "Document updated" ]
+  then
+        echo -e "${RED}Failure:${NC} shape cannot be touched!\n$RESP"
+  fi
+  RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/shapes/8a5e3d2c-4b6f-4c7d-8e9f-1a2b3c4d5e83)
+  CONTENT=$(echo $RESP | jq -c '[.f.title, .f.price]')
+  TOUCHED=$(echo $RESP | jq -r '.f.updated')
+  if [ "$CONTENT" != '["Returned shape",6]' ] || [ "$TOUCHED" == "null" ] || [[ ! "$TOUCHED" > "$UPDATED" ]]
+  then
+        echo -e "${RED}Failure:${NC} touch did not advance the updated field!\n$UPDATED\n$RESP"
+  fi
+  UPDATED=$TOUCHED
+done
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes/8a5e3d2c-4b6f-4c7d-8e9f-1a2b3c4d5e83/events?category=1")
+CONTENT=$(echo $RESP | jq -c '[.items[] | select(.e.touched)] | length')
+if [ "$CONTENT" != "2" ]
+then
+      echo -e "${RED}Failure:${NC} touch did not create update events!\n$RESP"
+fi
+
+echo "- Documents can be listed by a creation window"
+authorize_client $FLUIDS_EDITOR_CLIENT $FLUIDS_EDITOR_SECRET
+for DOC in 3c7d1e5a-9b2f-4e6c-8a1d-5f3b7c9e2a10:2024-01-15T08:00:00Z 3c7d1e5a-9b2f-4e6c-8a1d-5f3b7c9e2a11:2024-02-15T08:00:00Z 3c7d1e5a-9b2f-4e6c-8a1d-5f3b7c9e2a12:2024-03-15T08:00:00Z
+do
+  CREATED=${DOC#*:}
+  RESP=$(curl --silent \
+    --request POST \
+    --header "Authorization: Bearer $OIDCTOKEN" \
+    --header "Content-Type: application/json" \
+    --data '{"id": "'${DOC%%:*}'","f": {"title": "Brine '${CREATED:0:7}'", "created": "'$CREATED'"}}' \
+    $API/collections/fluids)
+  if [ "$RESP" != "Document saved" ]
+  then
+        echo -e "${RED}Failure:${NC} user is not allowed to save brine document!\n$RESP"
+  fi
+done
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/fluids?extraFields=title&createdAfter=2024-02-01T00:00:00Z&createdBefore=2024-04-01T00:00:00%2B01:00")
+CONTENT=$(echo $RESP | jq -r '[.items[].f.title] | join(",")')
+if [ "$CONTENT" != "Brine 2024-02,Brine 2024-03" ]
+then
+      echo -e "${RED}Failure:${NC} creation window is not applied!\n$RESP"
+fi
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/fluids?createdAfter=2024-02-01")
+CONTENT=$(echo $RESP | jq -r '.error.field')
+if [ "$CONTENT" != "created_after" ]
+then
+      echo -e "${RED}Failure:${NC} invalid creation timestamp is accepted!\n$RESP"
+fi
+
+echo "- Reader can list shapes with all fields"
+authorize_client $SHAPES_READER_CLIENT $SHAPES_READER_SECRET
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes?extraFields=*&exactTitle=Circle")
+CONTENT=$(echo $RESP | jq -c '[.items[].f | .title, .price]')
+if [ "$CONTENT" != '["Circle",9]' ]
+then
+      echo -e "${RED}Failure:${NC} list does not contain all fields!\n$RESP"
+fi
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes?extraFields=*,price")
+CONTENT=$(echo $RESP | jq -r '.error.field')
+if [ "$CONTENT" != "extra_fields" ]
+then
+      echo -e "${RED}Failure:${NC} wildcard can be mixed with field names!\n$RESP"
+fi
+
+echo "- Editor updates a shape with the expected version"
+authorize_client $SHAPES_EDITOR_CLIENT $SHAPES_EDITOR_SECRET
+RESP=$(curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '{"id": "4d2b8f6a-1c3e-4a5b-9d7f-2e4c6a8b0d21","f": {"title": "Versioned shape", "price": 3}}' \
+  $API/collections/shapes)
+if [ "$RESP" != "Document saved" ]
+then
+      echo -e "${RED}Failure:${NC} versioned shape was not saved!\n$RESP"
+fi
+RESP=$(curl --silent \
+  --request PUT \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --header "If-Match: 0" \
+  --data '{"id": "4d2b8f6a-1c3e-4a5b-9d7f-2e4c6a8b0d21","f": {"title": "Versioned shape", "price": 4}}' \
+  $API/collections/shapes)
+if [ "$RESP" != "Document updated" ]
+then
+      echo -e "${RED}Failure:${NC} update with the expected version was rejected!\n$RESP"
+fi
+RESP=$(curl --silent \
+  --request PUT \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --header "If-Match: 0" \
+  --data '{"id": "4d2b8f6a-1c3e-4a5b-9d7f-2e4c6a8b0d21","f": {"title": "Versioned shape", "price": 5}}' \
+  $API/collections/shapes)
+CONTENT=$(echo $RESP | jq -r '.error.code')
+if [ "$CONTENT" != "conflict" ]
+then
+      echo -e "${RED}Failure:${NC} update with a stale version was accepted!\n$RESP"
+fi
+RESP=$(curl --silent \
+  --request PATCH \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/merge-patch+json" \
+  --header 'If-Match: "1"' \
+  --data '{"price": 6}' \
+  $API/collections/shapes/4d2b8f6a-1c3e-4a5b-9d7f-2e4c6a8b0d21)
+if [ "$RESP" != "Document updated" ]
+then
+      echo -e "${RED}Failure:${NC} patch with the expected version was rejected!\n$RESP"
+fi
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" $API/collections/shapes/4d2b8f6a-1c3e-4a5b-9d7f-2e4c6a8b0d21)
+CONTENT=$(echo $RESP | jq -c '[.f.price, .f.version]')
+if [ "$CONTENT" != "[6,2]" ]
+then
+      echo -e "${RED}Failure:${NC} version of the shape was not incremented!\n$RESP"
+fi
+
+echo "- Remover creates a batch of events"
+authorize_client $SHAPES_REMOVER_CLIENT $SHAPES_REMOVER_SECRET
+RESP=$(curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '[{"category": 2,"collection": "shapes", "document": "4d2b8f6a-1c3e-4a5b-9d7f-2e4c6a8b0d21","e": {}}, {"category": 3,"collection": "shapes", "document": "4d2b8f6a-1c3e-4a5b-9d7f-2e4c6a8b0d21","e": {}}]' \
+  $API/events/batch)
+CONTENT=$(echo $RESP | jq -c '[.events, .duplicates]')
+if [ "$CONTENT" != "[2,0]" ]
+then
+      echo -e "${RED}Failure:${NC} batch of events was not created!\n$RESP"
+fi
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes/4d2b8f6a-1c3e-4a5b-9d7f-2e4c6a8b0d21/events")
+CONTENT=$(echo $RESP | jq -c '[.items[].category | select(. == 2 or . == 3)]')
+if [ "$CONTENT" != "[3,2]" ]
+then
+      echo -e "${RED}Failure:${NC} events of the batch are missing!\n$RESP"
+fi
+RESP=$(curl --silent \
+  --request POST \
+  --header "Authorization: Bearer $OIDCTOKEN" \
+  --header "Content-Type: application/json" \
+  --data '[{"category": 2,"collection": "shapes", "document": "4d2b8f6a-1c3e-4a5b-9d7f-2e4c6a8b0d21","e": {}}, {"category": 2,"collection": "shapes", "document": "4d2b8f6a-1c3e-4a5b-9d7f-2e4c6a8b0d21","e": {}}]' \
+  $API/events/batch)
+CONTENT=$(echo $RESP | jq -c '[.error.details.index, .error.message]')
+if [ "$CONTENT" != '[1,"Document already deleted"]' ]
+then
+      echo -e "${RED}Failure:${NC} rejected event of the batch is not reported!\n$RESP"
+fi
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes/4d2b8f6a-1c3e-4a5b-9d7f-2e4c6a8b0d21/events?category=2")
+CONTENT=$(echo $RESP | jq -r '.total')
+if [ "$CONTENT" != "1" ]
+then
+      echo -e "${RED}Failure:${NC} rejected batch of events was not rolled back!\n$RESP"
+fi
+
+echo "- Malformed request bodies and ids are rejected with a JSON error"
+function expect_json_error {
+  CONTENT=$(echo "$2" | jq -r '.error.code' 2>/dev/null)
+  if [ "$CONTENT" != "bad_request" ]
+  then
+        echo -e "${RED}Failure:${NC} $1 is not rejected with a JSON error!\n$2"
+  fi
+}
+authorize_client $SHAPES_EDITOR_CLIENT $SHAPES_EDITOR_SECRET
+for ENDPOINT in "POST collections/shapes" "PUT collections/shapes" "POST collections/shapes/batch" "POST collections/shapes/validate" "POST collections/shapes/uploads" "POST events" "POST events/batch"
+do
+  RESP=$(curl --silent \
+    --request ${ENDPOINT% *} \
+    --header "Authorization: Bearer $OIDCTOKEN" \
+    --header "Content-Type: application/json" \
+    --data '{"f": ' \
+    $API/${ENDPOINT#* })
+  expect_json_error "$ENDPOINT" "$RESP"
+done
+RESP=$(curl --silent --header "Authorization: Bearer $OIDCTOKEN" "$API/collections/shapes/not-a-uuid/events")
+expect_json_error "Invalid document id" "$RESP"
+authorize_client $COLADMIN_CLIENT $COLADMIN_SECRET
+for ENDPOINT in "POST collections" "PUT collections/shapes/config" "PUT collections/shapes/properties"
+do
+  RESP=$(curl --silent \
+    --request ${ENDPOINT% *} \
+    --header "Authorization: Bearer $OIDCTOKEN" \
+    --header "Content-Type: application/json" \
+    --data '{"f": ' \
+    $API/${ENDPOINT#* })
+  expect_json_error "$ENDPOINT" "$RESP"
+done
 
 kill $serverPID
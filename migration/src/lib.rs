@@ -3,6 +3,12 @@ pub use sea_orm_migration::prelude::*;
 mod m20220101_000001_basic;
 mod m20230623_190444_events;
 mod m20231203_180149_grants;
+mod m20261015_120000_event_outbox;
+mod m20261015_130000_collection_config;
+mod m20261015_140000_document_sequence;
+mod m20261015_150000_document_lock;
+mod m20261015_160000_idempotency_key;
+mod m20261015_170000_document_sequence_order;
 
 pub struct Migrator;
 pub use m20220101_000001_basic::CollectionDocument;
@@ -15,6 +21,12 @@ impl MigratorTrait for Migrator {
             Box::new(m20220101_000001_basic::Migration),
             Box::new(m20230623_190444_events::Migration),
             Box::new(m20231203_180149_grants::Migration),
+            Box::new(m20261015_120000_event_outbox::Migration),
+            Box::new(m20261015_130000_collection_config::Migration),
+            Box::new(m20261015_140000_document_sequence::Migration),
+            Box::new(m20261015_150000_document_lock::Migration),
+            Box::new(m20261015_160000_idempotency_key::Migration),
+            Box::new(m20261015_170000_document_sequence_order::Migration),
         ]
     }
 }
@@ -0,0 +1,68 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(r#"CREATE SEQUENCE "collection_document_change_seq""#)
+            .await?;
+        // Existing documents receive a sequence number when the column is added
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(CollectionDocument::Table)
+                    .add_column(
+                        ColumnDef::new(CollectionDocument::Seq)
+                            .big_integer()
+                            .default(Expr::cust("nextval('collection_document_change_seq')"))
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-collection_document-collection_id-seq")
+                    .table(CollectionDocument::Table)
+                    .col(CollectionDocument::CollectionId)
+                    .col(CollectionDocument::Seq)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-collection_document-collection_id-seq")
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(CollectionDocument::Table)
+                    .drop_column(CollectionDocument::Seq)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .get_connection()
+            .execute_unprepared(r#"DROP SEQUENCE "collection_document_change_seq""#)
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum CollectionDocument {
+    Table,
+    CollectionId,
+    Seq,
+}
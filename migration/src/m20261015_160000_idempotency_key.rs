@@ -0,0 +1,60 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // The key is recorded before the document is stored, hence there is
+        // no foreign key on the document id
+        manager
+            .create_table(
+                Table::create()
+                    .table(IdempotencyKey::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(IdempotencyKey::Owner).uuid().not_null())
+                    .col(
+                        ColumnDef::new(IdempotencyKey::CollectionId)
+                            .uuid()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(IdempotencyKey::Key)
+                            .string_len(255)
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(IdempotencyKey::DocumentId).uuid().not_null())
+                    .col(
+                        ColumnDef::new(IdempotencyKey::CreatedAt)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .name("pk-idempotency_key")
+                            .col(IdempotencyKey::Owner)
+                            .col(IdempotencyKey::CollectionId)
+                            .col(IdempotencyKey::Key),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(IdempotencyKey::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum IdempotencyKey {
+    Table,
+    Owner,
+    CollectionId,
+    Key,
+    DocumentId,
+    CreatedAt,
+}
@@ -0,0 +1,59 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Writers of a collection take the sequence number under a lock that
+        // is held until they commit, so the numbers of a collection become
+        // visible in ascending order and the changes feed has no gaps
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"CREATE FUNCTION "collection_document_next_seq"() RETURNS trigger AS $$
+BEGIN
+    PERFORM pg_advisory_xact_lock(hashtextextended(NEW."collection_id"::text, 0));
+    NEW."seq" := nextval('collection_document_change_seq');
+    RETURN NEW;
+END
+$$ LANGUAGE plpgsql"#,
+            )
+            .await?;
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"CREATE TRIGGER "collection_document_seq" BEFORE INSERT OR UPDATE OF "f" ON "collection_document" FOR EACH ROW EXECUTE FUNCTION "collection_document_next_seq"()"#,
+            )
+            .await?;
+        // The trigger assigns the number of new documents as well
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"ALTER TABLE "collection_document" ALTER COLUMN "seq" DROP DEFAULT"#,
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"ALTER TABLE "collection_document" ALTER COLUMN "seq" SET DEFAULT nextval('collection_document_change_seq')"#,
+            )
+            .await?;
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"DROP TRIGGER IF EXISTS "collection_document_seq" ON "collection_document""#,
+            )
+            .await?;
+        manager
+            .get_connection()
+            .execute_unprepared(r#"DROP FUNCTION IF EXISTS "collection_document_next_seq"()"#)
+            .await?;
+        Ok(())
+    }
+}
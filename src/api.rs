@@ -1,22 +1,49 @@
 mod auth;
+mod claim_document;
+pub(crate) mod collection_config;
+mod collection_settings;
+mod count_documents;
 mod create_collection;
 mod create_document;
+mod create_documents;
 mod create_event;
 pub mod data_service;
 pub(crate) mod db;
+mod distinct_values;
+mod document_version;
 pub mod dto;
+mod envelope;
+pub(crate) mod error_response;
+mod event_schema;
 mod get_document;
 mod grants;
+mod hook_summary;
 pub mod hooks;
+mod idempotency;
+mod import_documents;
+mod list_ancestors;
+mod list_changes;
 mod list_collections;
 mod list_documents;
+mod list_events;
+mod list_grants;
+mod list_references;
+mod lock_document;
 mod maintenance;
+mod parent_fields;
+mod read_projection;
+mod search_collections;
 mod search_documents;
+mod server_time;
 pub(crate) mod types;
 mod update_document;
+mod upload_document;
+mod upload_sessions;
+mod validate_document;
+mod validation_webhook;
+mod write_rate;
 pub use entity::collection::Model as Collection;
 use entity::collection_document::Entity as Documents;
-use serde_json::json;
 use utoipa::OpenApi;
 
 use std::sync::Arc;
@@ -30,38 +57,80 @@ use std::{
 
 use anyhow::Context;
 use axum::{
-    body::Body,
-    http::StatusCode,
-    response::{IntoResponse, Response},
-    routing::{get, post},
+    http::{header, HeaderValue, StatusCode},
+    response::IntoResponse,
+    routing::{get, post, put},
     Router,
 };
 use axum_macros::FromRef;
 use jwt_authorizer::{authorizer::IntoLayer, Authorizer, JwtAuthorizer, Validation};
 use sea_orm::{DatabaseConnection, DatabaseTransaction, DbErr, EntityTrait};
 use thiserror::Error;
-use tower_http::trace::TraceLayer;
 use tracing::{debug, error};
 
 use crate::api::hooks::staged_delete;
 use crate::{
+    axumext::layers::request_layers,
     mail,
-    monitoring::{health_routes, HealthMonitor},
+    monitoring::{health_routes, readiness_routes, HealthMonitor},
+    publisher::EventPublisher,
 };
 
 use self::{
     auth::{cert_loader, User},
+    claim_document::{__path_api_claim_document, api_claim_document},
+    collection_settings::{
+        __path_api_read_collection_config, __path_api_read_effective_collection_config,
+        __path_api_update_collection, __path_api_update_collection_config,
+        api_read_collection_config, api_read_effective_collection_config, api_update_collection,
+        api_update_collection_config,
+    },
+    count_documents::{__path_api_count_documents, api_count_documents},
     create_collection::{__path_api_create_collection, api_create_collection},
     create_document::{__path_api_create_document, api_create_document},
-    create_event::{__path_api_create_event, api_create_event},
+    create_documents::{__path_api_create_documents, api_create_documents},
+    create_event::{
+        __path_api_create_event, __path_api_create_events, api_create_event, api_create_events,
+    },
     data_service::FolivafyDataService,
+    distinct_values::{__path_api_distinct_values, api_distinct_values},
+    error_response::ErrorDetails,
     get_document::{__path_api_read_document, api_read_document},
     hooks::Hooks,
+    import_documents::{__path_api_import_documents, api_import_documents},
+    list_ancestors::{__path_api_list_ancestors, api_list_ancestors},
+    list_changes::{__path_api_list_changes, api_list_changes},
     list_collections::{__path_api_list_collections, api_list_collections},
     list_documents::{__path_api_list_documents, api_list_documents},
+    list_events::{
+        __path_api_list_collection_events, __path_api_list_document_events,
+        api_list_collection_events, api_list_document_events,
+    },
+    list_grants::{__path_api_list_document_grants, api_list_document_grants},
+    list_references::{__path_api_list_references, api_list_references},
+    lock_document::{
+        __path_api_lock_document, __path_api_unlock_document, api_lock_document,
+        api_unlock_document,
+    },
+    maintenance::api_backup::{__path_api_backup, __path_api_restore, api_backup, api_restore},
+    maintenance::api_orphans::{__path_api_list_orphans, api_list_orphans},
     maintenance::api_rebuild_grants::{self, __path_api_rebuild_grants},
+    maintenance::api_recompute::{__path_api_recompute, api_recompute},
+    search_collections::{__path_api_search_collections, api_search_collections},
     search_documents::{__path_api_search_documents, api_search_documents},
-    update_document::{__path_api_update_document, api_update_document},
+    server_time::{__path_api_server_time, api_server_time},
+    update_document::{
+        __path_api_patch_document, __path_api_touch_document, __path_api_update_document,
+        api_patch_document, api_touch_document, api_update_document,
+    },
+    upload_document::{
+        __path_api_cancel_upload, __path_api_finalize_upload, __path_api_get_upload,
+        __path_api_start_upload, __path_api_upload_chunk, api_cancel_upload, api_finalize_upload,
+        api_get_upload, api_start_upload, api_upload_chunk,
+    },
+    upload_sessions::UploadSessions,
+    validate_document::{__path_api_validate_document, api_validate_document},
+    write_rate::WriteRateLimiter,
 };
 
 pub const CATEGORY_DOCUMENT_UPDATES: i32 = 1;
@@ -85,16 +154,49 @@ const TAG_MAINTENANCE: &str = "maintenance";
     ),
     servers((url = "/api")),
     paths(
+        api_backup,
+        api_claim_document,
+        api_count_documents,
         api_create_collection,
         api_create_document,
+        api_create_documents,
         api_create_event,
+        api_create_events,
+        api_distinct_values,
+        api_import_documents,
+        api_list_ancestors,
+        api_list_changes,
         api_list_collections,
         api_list_documents,
+        api_list_collection_events,
+        api_list_document_events,
+        api_list_document_grants,
+        api_list_references,
+        api_lock_document,
+        api_unlock_document,
+        api_read_collection_config,
+        api_update_collection_config,
+        api_read_effective_collection_config,
+        api_update_collection,
         api_read_document,
         api_rebuild_grants,
+        api_recompute,
+        api_list_orphans,
+        api_restore,
+        api_search_collections,
         api_search_documents,
+        api_server_time,
         api_update_document,
+        api_patch_document,
+        api_touch_document,
+        api_validate_document,
+        api_start_upload,
+        api_get_upload,
+        api_upload_chunk,
+        api_cancel_upload,
+        api_finalize_upload,
         staged_delete::get_recoverables,
+        staged_delete::restore_recoverable,
     ),
     tags(
         (name = TAG_ADMINISTRATION, description = "Administrative tasks"),
@@ -111,9 +213,23 @@ pub(crate) struct ApiContext {
     hooks: Arc<Hooks>,
     data_service: Arc<FolivafyDataService>,
     immediate_cron_signal: tokio::sync::mpsc::Sender<()>,
+    #[from_ref(skip)]
+    event_relay_signal: tokio::sync::mpsc::Sender<()>,
+    #[from_ref(skip)]
+    write_rates: Arc<WriteRateLimiter>,
+    #[from_ref(skip)]
+    uploads: Arc<UploadSessions>,
+    /// Client for the validation webhooks, shares the connections
+    #[from_ref(skip)]
+    webhook_client: reqwest::Client,
 }
 
 impl ApiContext {
+    /// Wakes up the event relay, call after a transaction with new events was committed.
+    pub(crate) fn trigger_event_relay(&self) {
+        let _ = self.event_relay_signal.try_send(());
+    }
+
     pub(crate) async fn trigger_cron_with_condition(&self, condition: bool) {
         if condition {
             debug!("Triggering immediate cron");
@@ -148,62 +264,119 @@ pub enum ApiErrors {
     #[error("Unauthorized")]
     /// A 401 error
     PermissionDenied,
+    #[error("Forbidden: {0}")]
+    /// A 403 error, the user is known but not permitted to do this
+    Forbidden(String),
+    #[error("Rejected by hook: {0}")]
+    /// A hook rejected the request with a specific status code
+    HookRejection(hooks::HookRejection),
+    #[error("Transaction conflict")]
+    /// The transaction was aborted by a serialization failure or a deadlock
+    TransactionConflict,
+    #[error("Locked: {0}")]
+    /// A 423 error, the collection or the document is locked
+    Locked(String),
+    #[error("Conflict: {0}")]
+    /// A 409 error, the request does not match the current state of the document
+    Conflict(String),
+    #[error("Too many requests: {message}")]
+    /// A 429 error, the client may retry after `retry_after` seconds if known
+    TooManyRequests {
+        message: String,
+        retry_after: Option<u64>,
+    },
+    #[error("Payload too large: {0}")]
+    /// A 413 error, the request body cannot be accepted
+    PayloadTooLarge(String),
 }
 
 impl IntoResponse for ApiErrors {
+    /// Client errors respond with a JSON body, e. g.
+    /// `{"error": {"code": "bad_request", "message": "Duplicate document"}}`.
     fn into_response(self) -> axum::response::Response {
-        match self {
+        let (status, details) = match self {
+            ApiErrors::InternalServerError => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Internal Server Error".to_string(),
+                )
+                    .into_response()
+            }
             ApiErrors::PermissionDenied => (
                 StatusCode::UNAUTHORIZED,
-                ApiErrors::PermissionDenied.to_string(),
-            )
-                .into_response(),
-            ApiErrors::InternalServerError => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Internal Server Error".to_string(),
-            )
-                .into_response(),
-            ApiErrors::BadRequestJsonSimpleMsg(msg) => {
-                let body = json!({ "message": msg });
-                let body = serde_json::to_string(&body).unwrap_or_else(|e| {
-                    tracing::error!("Error serializing json: {}", e);
-                    r#"{"msg":"Bad request"}"#.to_string()
-                });
-
-                Response::builder()
-                    .status(StatusCode::BAD_REQUEST)
-                    .header("Content-Type", "application/json")
-                    .body(Body::from(body))
-                    .unwrap()
-                    .into_response()
+                ErrorDetails::new(
+                    StatusCode::UNAUTHORIZED,
+                    ApiErrors::PermissionDenied.to_string(),
+                ),
+            ),
+            ApiErrors::BadRequestJsonSimpleMsg(msg) | ApiErrors::BadRequest(msg) => (
+                StatusCode::BAD_REQUEST,
+                ErrorDetails::new(StatusCode::BAD_REQUEST, msg),
+            ),
+            ApiErrors::BadRequestJsonMsg(body) => (
+                StatusCode::BAD_REQUEST,
+                ErrorDetails::from_json_text(StatusCode::BAD_REQUEST, body),
+            ),
+            ApiErrors::BadRequestJson(jsonvalue) => (
+                StatusCode::BAD_REQUEST,
+                ErrorDetails::from_json(StatusCode::BAD_REQUEST, jsonvalue),
+            ),
+            ApiErrors::NotFound(msg) => (
+                StatusCode::NOT_FOUND,
+                ErrorDetails::new(StatusCode::NOT_FOUND, msg),
+            ),
+            ApiErrors::Forbidden(msg) => (
+                StatusCode::FORBIDDEN,
+                ErrorDetails::new(StatusCode::FORBIDDEN, msg),
+            ),
+            ApiErrors::HookRejection(rejection) => {
+                return ErrorDetails::new(rejection.status(), rejection.message())
+                    .into_response_with(rejection.status(), rejection.headers());
             }
-            ApiErrors::BadRequestJsonMsg(body) => Response::builder()
-                .status(StatusCode::BAD_REQUEST)
-                .header("Content-Type", "application/json")
-                .body(Body::from(body))
-                .unwrap()
-                .into_response(),
-            ApiErrors::BadRequestJson(jsonvalue) => {
-                let body = serde_json::to_string(&jsonvalue).unwrap_or_else(|e| {
-                    tracing::error!("Error serializing json: {}", e);
-                    r#"{"msg":"Bad request"}"#.to_string()
-                });
-
-                Response::builder()
-                    .status(StatusCode::BAD_REQUEST)
-                    .header("Content-Type", "application/json")
-                    .body(Body::from(body))
-                    .unwrap()
-                    .into_response()
+            ApiErrors::Locked(msg) => (
+                StatusCode::LOCKED,
+                ErrorDetails::new(StatusCode::LOCKED, msg),
+            ),
+            ApiErrors::Conflict(msg) => (
+                StatusCode::CONFLICT,
+                ErrorDetails::new(StatusCode::CONFLICT, msg),
+            ),
+            ApiErrors::PayloadTooLarge(msg) => (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                ErrorDetails::new(StatusCode::PAYLOAD_TOO_LARGE, msg),
+            ),
+            ApiErrors::TooManyRequests {
+                message,
+                retry_after,
+            } => {
+                let headers: Vec<_> = retry_after
+                    .map(|seconds| (header::RETRY_AFTER, HeaderValue::from(seconds)))
+                    .into_iter()
+                    .collect();
+                return ErrorDetails::new(StatusCode::TOO_MANY_REQUESTS, message)
+                    .into_response_with(StatusCode::TOO_MANY_REQUESTS, &headers);
             }
-            ApiErrors::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg).into_response(),
-            ApiErrors::NotFound(msg) => (StatusCode::NOT_FOUND, msg).into_response(),
-        }
+            ApiErrors::TransactionConflict => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                ErrorDetails::new(
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    "Concurrent write, please retry".to_string(),
+                ),
+            ),
+        };
+        details.into_response_with(status, &[])
     }
 }
 
+/// SQLSTATE of a regular expression that Postgres cannot compile
+const INVALID_REGULAR_EXPRESSION: &str = "2201B";
+
 impl From<DbErr> for ApiErrors {
     fn from(value: DbErr) -> Self {
+        if db::is_transaction_conflict(&value) {
+            debug!("Transaction conflict: {:?}", value);
+            return ApiErrors::TransactionConflict;
+        }
         match value {
             DbErr::Exec(sea_orm::RuntimeErr::SqlxError(error)) => match error {
                 sqlx::error::Error::Database(e) => {
@@ -219,6 +392,12 @@ impl From<DbErr> for ApiErrors {
                     ApiErrors::InternalServerError
                 }
             },
+            DbErr::Query(sea_orm::RuntimeErr::SqlxError(sqlx::error::Error::Database(e)))
+                if e.code().is_some_and(|c| c == INVALID_REGULAR_EXPRESSION) =>
+            {
+                debug!("Invalid regular expression: {}", e);
+                ApiErrors::BadRequestJsonSimpleMsg(format!("Invalid regex: {}", e.message()))
+            }
             DbErr::RecordNotFound(t) => ApiErrors::NotFound(t),
             _ => {
                 error!("Database error: {:?}", value);
@@ -266,6 +445,7 @@ pub async fn serve(
     db: DatabaseConnection,
     hooks: Hooks,
     cron_interval: std::time::Duration,
+    event_publisher: Arc<dyn EventPublisher>,
 ) -> anyhow::Result<()> {
     let hooks = Arc::new(hooks);
     mail::insert_mail_cron_hook(&hooks, &db).await?;
@@ -278,16 +458,23 @@ pub async fn serve(
         cron_interval,
         data_service.clone(),
     );
+    let (relaybt, event_relay_signal) =
+        crate::publisher::setup_event_relay(db.clone(), event_publisher);
     let monitor = Arc::new(HealthMonitor::new());
     // build our application with a route
-    let app = api_routes(db, hooks, data_service, immediate_cron_signal)
+    let readiness = readiness_routes(db.clone());
+    let app = request_layers(
+        api_routes(
+            db,
+            hooks,
+            data_service,
+            immediate_cron_signal,
+            event_relay_signal,
+        )
         .await?
         .nest("/app", health_routes(monitor))
-        // `TraceLayer` is provided by tower-http so you have to add that as a dependency.
-        // It provides good defaults but is also very customizable.
-        //
-        // See https://docs.rs/tower-http/0.1.1/tower_http/trace/index.html for more details.
-        .layer(TraceLayer::new_for_http());
+        .merge(readiness),
+    );
 
     tracing::debug!("Initializing service...");
     // run it
@@ -309,6 +496,7 @@ pub async fn serve(
         .context("error running server")?;
 
     cronbt.shutdown().await;
+    relaybt.shutdown().await;
     user_service_task.shutdown().await;
     debug!("Shutdown complete");
     Ok(())
@@ -319,6 +507,7 @@ async fn api_routes(
     hooks: Arc<Hooks>,
     data_service: Arc<FolivafyDataService>,
     immediate_cron_signal: tokio::sync::mpsc::Sender<()>,
+    event_relay_signal: tokio::sync::mpsc::Sender<()>,
 ) -> anyhow::Result<Router> {
     let issuer = env::var("FOLIVAFY_JWT_ISSUER").context("FOLIVAFY_JWT_ISSUER is not set")?;
     let danger_accept_invalid_certs = env::var("FOLIVAFY_DANGEROUS_ACCEPT_INVALID_CERTS")
@@ -348,30 +537,133 @@ async fn api_routes(
                     .post(api_create_document)
                     .put(api_update_document),
             )
+            .route(
+                "/collections/:collection_name/count",
+                get(api_count_documents),
+            )
+            .route(
+                "/collections/:collection_name/batch",
+                post(api_create_documents),
+            )
+            .route(
+                "/collections/:collection_name/import",
+                post(api_import_documents),
+            )
             .route(
                 "/collections/:collection_name/search",
                 post(api_search_documents),
             )
+            .route(
+                "/collections/:collection_name/changes",
+                get(api_list_changes),
+            )
+            .route(
+                "/collections/:collection_name/distinct",
+                get(api_distinct_values),
+            )
+            .route(
+                "/collections/:collection_name/claim",
+                post(api_claim_document),
+            )
+            .route(
+                "/collections/:collection_name/validate",
+                post(api_validate_document),
+            )
+            .route(
+                "/collections/:collection_name/uploads",
+                post(api_start_upload),
+            )
+            .route(
+                "/collections/:collection_name/uploads/:upload_id",
+                get(api_get_upload)
+                    .patch(api_upload_chunk)
+                    .delete(api_cancel_upload),
+            )
+            .route(
+                "/collections/:collection_name/uploads/:upload_id/finalize",
+                post(api_finalize_upload),
+            )
+            .route(
+                "/collections/:collection_name/config",
+                get(api_read_collection_config).put(api_update_collection_config),
+            )
+            .route(
+                "/collections/:collection_name/properties",
+                put(api_update_collection),
+            )
+            .route(
+                "/collections/:collection_name/events",
+                get(api_list_collection_events),
+            )
+            .route(
+                "/collections/:collection_name/effective-config",
+                get(api_read_effective_collection_config),
+            )
             .route(
                 "/collections/:collection_name/:document_id",
-                get(api_read_document),
+                get(api_read_document).patch(api_patch_document),
+            )
+            .route(
+                "/collections/:collection_name/:document_id/touch",
+                post(api_touch_document),
             )
+            .route(
+                "/collections/:collection_name/:document_id/ancestors",
+                get(api_list_ancestors),
+            )
+            .route(
+                "/collections/:collection_name/:document_id/references",
+                get(api_list_references),
+            )
+            .route(
+                "/collections/:collection_name/:document_id/events",
+                get(api_list_document_events),
+            )
+            .route(
+                "/collections/:collection_name/:document_id/grants",
+                get(api_list_document_grants),
+            )
+            .route(
+                "/collections/:collection_name/:document_id/lock",
+                put(api_lock_document).delete(api_unlock_document),
+            )
+            .route("/backup", get(api_backup))
             .route("/events", post(api_create_event))
+            .route("/events/batch", post(api_create_events))
+            .route("/search", post(api_search_collections))
             .route(
                 "/maintenance/:collection_name/rebuild-grants",
                 post(api_rebuild_grants::api_rebuild_grants),
             )
+            .route(
+                "/maintenance/:collection_name/recompute",
+                post(api_recompute),
+            )
+            .route(
+                "/maintenance/:collection_name/orphans",
+                get(api_list_orphans),
+            )
+            .route("/restore", post(api_restore))
             .route(
                 "/recoverables/:collection_name",
                 get(staged_delete::get_recoverables),
             )
+            .route(
+                "/recoverables/:collection_name/:document_id/restore",
+                post(staged_delete::restore_recoverable),
+            )
             .with_state(ApiContext {
                 db,
                 hooks,
                 data_service,
                 immediate_cron_signal,
+                event_relay_signal,
+                write_rates: Arc::new(WriteRateLimiter::default()),
+                uploads: Arc::new(UploadSessions::default()),
+                webhook_client: reqwest::Client::new(),
             })
-            .layer(jwt_auth.into_layer()),
+            .layer(jwt_auth.into_layer())
+            .route("/time", get(api_server_time)),
     ))
 }
 
@@ -54,6 +54,13 @@ impl User {
         self.realm_access.roles.contains(&role_name)
     }
 
+    /// Checks whether a user has the "C_COLLECTION_UNMASKED" or the "C_COLLECTION_ADMIN" role
+    /// for a specific collection and can read masked fields in clear text.
+    pub(crate) fn can_read_unmasked(&self, collection_name: &str) -> bool {
+        let role_name = format!("C_{}_UNMASKED", collection_name.to_ascii_uppercase());
+        self.realm_access.roles.contains(&role_name) || self.is_collection_admin(collection_name)
+    }
+
     /// Checks whether a user has the "C_COLLECTION_EDITOR" role for a specific collection.
     pub(crate) fn is_collection_editor(&self, collection_name: &str) -> bool {
         let role_name = format!("C_{}_EDITOR", collection_name.to_ascii_uppercase());
@@ -76,6 +83,26 @@ impl User {
                 || self.can_access_all_documents(collection_name))
     }
 
+    /// Checks whether the user has at least one of the given roles.
+    pub(crate) fn has_any_role(&self, roles: &[String]) -> bool {
+        roles
+            .iter()
+            .any(|role| self.realm_access.roles.contains(role))
+    }
+
+    /// Returns the names of all collections where the user has the "C_COLLECTION_EDITOR" role.
+    pub(crate) fn editable_collection_names(&self) -> Vec<String> {
+        self.realm_access
+            .roles
+            .iter()
+            .filter_map(|role| {
+                role.strip_prefix("C_")
+                    .and_then(|r| r.strip_suffix("_EDITOR"))
+                    .map(|name| name.to_ascii_lowercase())
+            })
+            .collect()
+    }
+
     pub(crate) fn name_and_sub(&self) -> String {
         format!("{} ({})", self.preferred_username, self.sub)
     }
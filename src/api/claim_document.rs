@@ -0,0 +1,324 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use axum_macros::debug_handler;
+use chrono::{SecondsFormat, Utc};
+use jwt_authorizer::JwtClaims;
+use lazy_static::lazy_static;
+use regex::Regex;
+use sea_orm::{ConnectionTrait, EntityTrait};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tracing::{debug, error, warn};
+use validator::Validate;
+
+use crate::{
+    api::{
+        auth::User,
+        collection_config::CollectionConfig,
+        db::{
+            claim_document_sql, get_collection_by_name, save_document_events_mails,
+            transaction_with_retry, DbGrantUpdate, DbListDocumentParams, ListDocumentGrants,
+            DELETED_AT_FIELD,
+        },
+        document_version::{next_version, VERSION_FIELD},
+        dto,
+        grants::{hook_or_default_user_grants, GrantCollection},
+        list_documents::{reject_author_id_sort, RE_SORT_FIELDS},
+        lock_document::ensure_collection_unlocked,
+        read_projection::ReadProjection,
+        search_documents::{
+            Operation, OperationWithValue, SearchFilter, SearchFilterFieldOp,
+            SearchFilterFieldOpValue, SearchGroup,
+        },
+        ApiContext, ApiErrors, CATEGORY_DOCUMENT_UPDATES,
+    },
+    axumext::extractors::JsonBody,
+    models::CollectionItem,
+};
+
+lazy_static! {
+    static ref RE_CLAIM_FIELD: Regex = Regex::new(r"^[a-zA-Z0-9_]+$").unwrap();
+}
+
+#[derive(Debug, Deserialize, Validate, utoipa::ToSchema)]
+#[schema(
+    description = "Claim the next available document",
+    examples(json!({"filter": {"f": "status", "o": "eq", "v": "open"}, "field": "claim", "ttl": 300}))
+)]
+pub(crate) struct ClaimDocumentBody {
+    /// Only documents matching this filter are claimed
+    filter: Option<SearchFilter>,
+
+    /// Name of the document field that stores the claim
+    #[validate(regex(path = *RE_CLAIM_FIELD))]
+    #[schema(pattern = r"^[a-zA-Z0-9_]+$", examples("claim"))]
+    field: String,
+
+    /// Number of seconds until the claim expires
+    #[validate(range(min = 1, max = 86400))]
+    #[schema(minimum = 1, maximum = 86400, examples(300))]
+    ttl: u32,
+
+    /// Order of the documents, see `sort` parameter of the list endpoint
+    #[validate(regex(path = *RE_SORT_FIELDS))]
+    #[schema(examples("created+"))]
+    sort: Option<String>,
+}
+
+/// Claim item
+///
+/// Selects the first document that matches the filter and is not claimed,
+/// marks it as claimed by the user and returns it. A document is not claimed
+/// if the claim field is missing or the claim has expired.
+///
+/// The claim is stored in the document field given by `field`:
+/// `{"by": "<user id>", "until": "<expiry timestamp>"}`.
+///
+/// Documents that are claimed at the same time by another request are skipped,
+/// i. e. concurrent requests never return the same document.
+#[debug_handler]
+#[utoipa::path(
+    post,
+    path = "/collections/{collection_name}/claim",
+    operation_id = "claimCollectionItem",
+    params(
+        (
+            "collection_name" = String,
+            Path,
+            description = "Name of the collection",
+            min_length = 1,
+            max_length = 32,
+            pattern = r"^[a-z][-a-z0-9]*$",
+        ),
+    ),
+    responses(
+        (status = OK, description = "Claimed document", body = CollectionItem ),
+        (status = UNAUTHORIZED, description = "User is not a collection editor" ),
+        (status = NOT_FOUND, description = "Collection not found or no document available" ),
+        (status = BAD_REQUEST, description = "Invalid request" ),
+        (status = LOCKED, description = "Collection is read only" ),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
+    ),
+    request_body(content = ClaimDocumentBody, description = "Claim settings", content_type = "application/json"),
+    tag = super::TAG_COLLECTION,
+)]
+pub(crate) async fn api_claim_document(
+    State(ctx): State<ApiContext>,
+    Path(collection_name): Path<String>,
+    JwtClaims(user): JwtClaims<User>,
+    JsonBody(payload): JsonBody<ClaimDocumentBody>,
+) -> Result<Json<CollectionItem>, ApiErrors> {
+    payload.validate().map_err(ApiErrors::from)?;
+
+    let collection = get_collection_by_name(&ctx.db, &collection_name)
+        .await
+        .ok_or_else(|| ApiErrors::NotFound(collection_name.clone()))?;
+
+    if !user.is_collection_editor(&collection_name) {
+        warn!("User {} is not a collection editor", user.name_and_sub());
+        return Err(ApiErrors::PermissionDenied);
+    }
+
+    ensure_collection_unlocked(&user, &collection)?;
+
+    CollectionConfig::from(&collection).validate_sort(payload.sort.as_deref())?;
+    reject_author_id_sort(payload.sort.as_deref())?;
+
+    let dto_collection: GrantCollection = (&collection).into();
+    let user_grants =
+        hook_or_default_user_grants(&ctx.hooks, &dto_collection, &user, ctx.data_service.clone())
+            .await?;
+
+    let now = Utc::now();
+    let claim = json!({
+        "by": user.subuuid(),
+        "until": (now + chrono::Duration::seconds(payload.ttl.into()))
+            .to_rfc3339_opts(SecondsFormat::Secs, true),
+    });
+    let params = DbListDocumentParams::builder()
+        .collection(collection.id)
+        .grants(ListDocumentGrants::Restricted(user_grants))
+        .extra_fields(vec![])
+        .sort_fields(payload.sort.clone())
+        .filters(claimable_documents_filter(
+            &payload.field,
+            &now.to_rfc3339_opts(SecondsFormat::Secs, true),
+            payload.filter,
+        ))
+        .include_author_id(false)
+        .build();
+    let claim_field = payload.field;
+    let projection = ReadProjection::new(&ctx.hooks, &collection, &user);
+    let event_relay_ctx = ctx.clone();
+
+    transaction_with_retry(&ctx.db, move |txn| {
+        let params = params.clone();
+        let claim_field = claim_field.clone();
+        let claim = claim.clone();
+        let user = user.clone();
+        let projection = projection.clone();
+        let collection_name = collection_name.clone();
+        Box::pin(async move {
+            let stmt = txn
+                .get_database_backend()
+                .build(&claim_document_sql(&params));
+            let document = entity::collection_document::Entity::find()
+                .from_raw_sql(stmt)
+                .one(txn)
+                .await?
+                .ok_or_else(|| ApiErrors::NotFound("No document available".to_string()))?;
+
+            let mut after_document: dto::CollectionDocument = (&document).into();
+            after_document.set_field(&claim_field, claim.clone());
+            after_document.set_field(VERSION_FIELD, json!(next_version(&document.f)));
+
+            let event = dto::Event::new(
+                document.id,
+                CATEGORY_DOCUMENT_UPDATES,
+                json!({
+                    "user": {
+                        "id": user.subuuid(),
+                        "name": user.preferred_username(),
+                    },
+                    "claim": claim,
+                }),
+            );
+            let dtouser = dto::User::read_from(&user);
+            save_document_events_mails(
+                txn,
+                &dtouser,
+                Some(after_document.clone()),
+                None,
+                vec![event],
+                DbGrantUpdate::Keep,
+                vec![],
+            )
+            .await
+            .map_err(|e| {
+                error!("Claim document error: {:?}", e);
+                ApiErrors::InternalServerError
+            })?;
+            debug!(
+                "Document {} in {} claimed by {}",
+                document.id,
+                collection_name,
+                user.name_and_sub()
+            );
+            Ok(Json(claimed_item(after_document, &projection)))
+        })
+    })
+    .await
+    .inspect(|_| event_relay_ctx.trigger_event_relay())
+}
+
+/// The claimed document with the fields that the user can read.
+fn claimed_item(document: dto::CollectionDocument, projection: &ReadProjection) -> CollectionItem {
+    let id = *document.id();
+    let mut f = document.into_fields();
+    projection.apply(id, &mut f);
+    CollectionItem::new(id, f)
+}
+
+/// Matches documents that are not deleted, not claimed (or the claim has
+/// expired) and match the optional request filter.
+fn claimable_documents_filter(
+    claim_field: &str,
+    now: &str,
+    filter: Option<SearchFilter>,
+) -> SearchFilter {
+    let until_field = format!("{claim_field}.until");
+    let mut filters = vec![
+        SearchFilter::FieldOp(
+            SearchFilterFieldOp::builder()
+                .field(DELETED_AT_FIELD.to_string())
+                .operation(Operation::Null)
+                .build(),
+        ),
+        SearchFilter::Group(SearchGroup::OrGroup(vec![
+            SearchFilter::FieldOp(
+                SearchFilterFieldOp::builder()
+                    .field(until_field.clone())
+                    .operation(Operation::Null)
+                    .build(),
+            ),
+            SearchFilter::FieldOpValue(
+                SearchFilterFieldOpValue::builder()
+                    .field(until_field)
+                    .operation(OperationWithValue::Lt)
+                    .value(Value::String(now.to_string()))
+                    .build(),
+            ),
+        ])),
+    ];
+    if let Some(filter) = filter {
+        filters.push(filter);
+    }
+    SearchFilter::Group(SearchGroup::AndGroup(filters))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use uuid::Uuid;
+
+    use super::*;
+
+    #[test]
+    fn it_selects_unclaimed_or_expired_documents() {
+        // Arrange
+        let filter: SearchFilter =
+            serde_json::from_value(json!({"f": "status", "o": "eq", "v": "open"})).unwrap();
+
+        // Act
+        let f = claimable_documents_filter("claim", "2024-03-01T10:00:00Z", Some(filter));
+
+        // Assert
+        assert_eq!(
+            serde_json::to_value(f).unwrap(),
+            json!({"and": [
+                {"f": "folivafy_deleted_at", "o": "null"},
+                {"or": [
+                    {"f": "claim.until", "o": "null"},
+                    {"f": "claim.until", "o": "lt", "v": "2024-03-01T10:00:00Z"}
+                ]},
+                {"f": "status", "o": "eq", "v": "open"}
+            ]})
+        );
+    }
+
+    #[test]
+    fn it_runs_the_read_hook_on_the_claimed_document() {
+        // Arrange
+        let document = dto::CollectionDocument::new(
+            Uuid::new_v4(),
+            json!({"title": "Application", "salary": 52000, "claim": {"until": "2024-03-01T10:00:00Z"}}),
+        );
+
+        // Act
+        let item = claimed_item(
+            document,
+            &crate::api::read_projection::redacting(&["salary"]),
+        );
+
+        // Assert
+        assert_eq!(
+            item.f,
+            json!({"title": "Application", "claim": {"until": "2024-03-01T10:00:00Z"}})
+        );
+    }
+
+    #[test]
+    fn it_rejects_invalid_claim_fields() {
+        // Arrange
+        let body: ClaimDocumentBody =
+            serde_json::from_value(json!({"field": "claim'; --", "ttl": 60})).unwrap();
+
+        // Act
+        let result = body.validate();
+
+        // Assert
+        assert!(result.is_err());
+    }
+}
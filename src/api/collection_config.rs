@@ -0,0 +1,1331 @@
+use std::{collections::BTreeMap, fmt::Write};
+
+use chrono::{DateTime, NaiveDate};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::warn;
+use uuid::Uuid;
+use validator::{Validate, ValidationError, ValidationErrors};
+
+use super::{event_schema::EventSchema, search_documents::SearchFilter, ApiErrors};
+
+/// Suffix of the field that contains the formatted value
+pub(crate) const FORMATTED_FIELD_SUFFIX: &str = "_formatted";
+
+/// Settings of a collection
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, utoipa::ToSchema, Validate)]
+#[serde(default, rename_all = "camelCase")]
+#[schema(examples(json!({
+    "eventCategories": {"1": "Document updated", "1020": "Approved"},
+    "fieldFormats": {"price": {"type": "currency", "divisor": 100, "currency": "EUR"}},
+    "maskedFields": {"iban": {"type": "keepLast", "visible": 4}},
+    "immutableFields": ["order_number"],
+    "textSearchFields": ["title", "customer.name"],
+    "fieldTypes": {"title": "text", "price": "number"},
+    "idVersions": [4, 7],
+    "requiredWhen": [{"field": "vat_id", "when": "type", "equals": "company"}],
+    "deduplicateEvents": [1020],
+    "eventSchemas": {"1020": {"type": "object", "required": ["approved"], "properties": {"approved": {"type": "boolean"}}}},
+    "validationWebhook": {"url": "http://validator:8080/orders", "timeoutMs": 2000, "failOpen": false},
+    "parent": {"collection": "projects", "field": "project_id"},
+    "parentFields": {"project_title": "title"},
+    "deletedDocumentAccess": "owner",
+    "writeRateLimit": 600,
+    "maxFieldDepth": 3,
+    "maxResponseItems": 10
+})))]
+pub struct CollectionConfig {
+    /// Human readable names for event categories
+    #[schema(value_type = BTreeMap<String, String>)]
+    event_categories: BTreeMap<i32, String>,
+
+    /// Formatting rules for document fields. The formatted value is returned
+    /// in an additional field with the suffix `_formatted`, the stored value
+    /// is not changed. Formatted fields cannot be used in filters or sorting.
+    #[validate(custom(function = "validate_field_formats"))]
+    field_formats: BTreeMap<String, FieldFormat>,
+
+    /// Masking rules for fields with personal data. The field is returned
+    /// with a masked value, unless the user is a collection admin or has the
+    /// role `C_<COLLECTION>_UNMASKED`. The stored value is not changed.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    masked_fields: BTreeMap<String, FieldMask>,
+
+    /// Fields that cannot be changed once they are set. Updates that change
+    /// the value of these fields are rejected.
+    immutable_fields: Vec<String>,
+
+    /// Fields that can be searched with the text operators `startswith` and
+    /// `containstext` and their case-sensitive variants. Text search is
+    /// permitted for every field if empty.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    text_search_fields: Vec<String>,
+
+    /// Declared types of document fields in dotted notation, e. g.
+    /// `{"price": "number"}`. Sorting a field with a direction that does not
+    /// match its type is rejected. Fields without a type can be sorted in
+    /// every direction.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    field_types: BTreeMap<String, FieldType>,
+
+    /// Permitted UUID versions of document ids, e. g. `[4, 7]`. Every version
+    /// is permitted if empty. The nil UUID is never permitted.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[validate(custom(function = "validate_id_versions"))]
+    id_versions: Vec<usize>,
+
+    /// Fields that are required if another field has a given value. Documents
+    /// that miss a required field are rejected.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    required_when: Vec<RequiredWhen>,
+
+    /// Fields that contain a copy of a field of the parent document, e. g.
+    /// `{"project_title": "title"}`. The copies are updated when the document
+    /// is written or recomputed.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    parent_fields: BTreeMap<String, String>,
+
+    /// External service that validates documents before they are written
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[validate(nested)]
+    validation_webhook: Option<ValidationWebhook>,
+
+    /// Event categories that are deduplicated. An event is not recorded if it
+    /// equals the latest event of the same category of the document.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    deduplicate_events: Vec<i32>,
+
+    /// Schemas of the event payloads by category, a subset of JSON Schema
+    /// with the keywords `type`, `properties`, `required`,
+    /// `additionalProperties`, `items`, `enum`, `minimum`, `maximum`,
+    /// `minLength` and `maxLength`. Events whose payload does not match the
+    /// schema of their category are rejected.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    #[schema(value_type = BTreeMap<String, Object>)]
+    event_schemas: BTreeMap<i32, EventSchema>,
+
+    /// Reference to a parent document. Documents inherit the grants of their
+    /// parent document.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[validate(nested)]
+    parent: Option<ParentReference>,
+
+    /// Users that can still read deleted documents with `includeDeleted`
+    #[serde(skip_serializing_if = "DeletedDocumentAccess::is_hidden")]
+    deleted_document_access: DeletedDocumentAccess,
+
+    /// Maximum number of documents that all users together can create or
+    /// update within a minute. Further writes are rejected with status 429.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[validate(range(min = 1))]
+    write_rate_limit: Option<u32>,
+
+    /// Maximum nesting depth of the document fields, objects and arrays count
+    /// as a level, e. g. `2` permits `{"customer": {"name": "Jane"}}`. Deeper
+    /// documents are rejected. The depth is unlimited if not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[validate(range(min = 1))]
+    max_field_depth: Option<usize>,
+
+    /// Maximum number of documents in a response, below the maximum page size
+    /// of the server. A larger `limit` is reduced to this number, the response
+    /// contains the reduced limit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[validate(range(min = 1, max = 250))]
+    max_response_items: Option<u16>,
+}
+
+/// Declared type of a document field
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum FieldType {
+    /// Sorted as text with `+` or `-`
+    Text,
+    /// Sorted by its JSON value with `f` or `b`
+    Number,
+}
+
+/// Read access to deleted documents
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum DeletedDocumentAccess {
+    /// Deleted documents cannot be read
+    #[default]
+    Hidden,
+    /// The owner of the document can read it
+    Owner,
+    /// The owner and collection administrators can read it
+    OwnerAndAdmins,
+}
+
+impl DeletedDocumentAccess {
+    fn is_hidden(&self) -> bool {
+        *self == DeletedDocumentAccess::Hidden
+    }
+}
+
+/// External HTTP endpoint that validates documents
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, utoipa::ToSchema, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationWebhook {
+    /// URL that receives the document with a `POST` request
+    #[validate(url)]
+    url: String,
+
+    /// Milliseconds to wait for the response
+    #[serde(default = "default_webhook_timeout_ms")]
+    #[validate(range(min = 1, max = 60000))]
+    timeout_ms: u64,
+
+    /// Accept the write if the service cannot be reached
+    #[serde(default)]
+    fail_open: bool,
+}
+
+fn default_webhook_timeout_ms() -> u64 {
+    5000
+}
+
+impl ValidationWebhook {
+    pub(crate) fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub(crate) fn timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.timeout_ms)
+    }
+
+    pub(crate) fn fail_open(&self) -> bool {
+        self.fail_open
+    }
+}
+
+/// Requires `field` if the field `when` has the value `equals`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct RequiredWhen {
+    /// Name of the required field
+    field: String,
+
+    /// Name of the field that is compared
+    when: String,
+
+    /// Value of the compared field that makes `field` required
+    equals: Value,
+}
+
+impl RequiredWhen {
+    /// Returns `true` if the condition applies and the field is missing, `null`
+    /// or an empty string.
+    fn is_unmet(&self, f: &Value) -> bool {
+        if f.get(&self.when) != Some(&self.equals) {
+            return false;
+        }
+        match f.get(&self.field) {
+            None | Some(Value::Null) => true,
+            Some(Value::String(s)) => s.is_empty(),
+            Some(_) => false,
+        }
+    }
+
+    fn validation_error(&self) -> ValidationError {
+        let mut error = ValidationError::new("required");
+        error.add_param("when".into(), &self.when);
+        error.add_param("equals".into(), &self.equals);
+        error
+    }
+}
+
+/// Reference from a document to its parent document
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, utoipa::ToSchema, Validate)]
+pub struct ParentReference {
+    /// Name of the collection that contains the parent documents
+    #[validate(length(min = 1, max = 32))]
+    collection: String,
+
+    /// Document field that contains the id of the parent document
+    #[validate(length(min = 1))]
+    field: String,
+}
+
+impl ParentReference {
+    pub(crate) fn collection(&self) -> &str {
+        &self.collection
+    }
+
+    /// Returns the id of the parent document or `None` if the field is
+    /// missing or does not contain a document id.
+    pub(crate) fn parent_id(&self, f: &Value) -> Option<Uuid> {
+        f.get(&self.field)
+            .and_then(|value| value.as_str())
+            .and_then(|value| Uuid::parse_str(value).ok())
+    }
+}
+
+/// Formatting rule for a document field
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum FieldFormat {
+    /// Divides a number by `divisor` and appends the currency, e. g. `12.34 EUR`
+    Currency {
+        #[serde(default = "default_divisor")]
+        divisor: u32,
+        #[serde(default = "default_decimals")]
+        decimals: u8,
+        currency: String,
+    },
+    /// Formats an ISO 8601 date or timestamp with a `strftime` pattern, e. g. `%d.%m.%Y`
+    Date { format: String },
+}
+
+fn default_divisor() -> u32 {
+    100
+}
+
+fn default_decimals() -> u8 {
+    2
+}
+
+impl FieldFormat {
+    /// Returns the formatted value or `None` if the value cannot be formatted.
+    fn format(&self, value: &Value) -> Option<String> {
+        match self {
+            FieldFormat::Currency {
+                divisor,
+                decimals,
+                currency,
+            } => {
+                let amount = value.as_f64()? / f64::from((*divisor).max(1));
+                Some(format!(
+                    "{:.*} {}",
+                    usize::from(*decimals),
+                    amount,
+                    currency
+                ))
+            }
+            FieldFormat::Date { format } => {
+                let s = value.as_str()?;
+                let mut formatted = String::new();
+                let result = match DateTime::parse_from_rfc3339(s) {
+                    Ok(timestamp) => write!(formatted, "{}", timestamp.format(format)),
+                    Err(_) => {
+                        let date = NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()?;
+                        write!(formatted, "{}", date.format(format))
+                    }
+                };
+                result.ok().map(|_| formatted)
+            }
+        }
+    }
+}
+
+/// Masking rule for a document field with personal data
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum FieldMask {
+    /// Replaces all but the last `visible` characters with `*`, e. g.
+    /// `******1234`. Values with at most `visible` characters are masked
+    /// completely.
+    KeepLast {
+        #[serde(default = "default_visible")]
+        visible: usize,
+    },
+    /// Replaces the value with `text`
+    Replace {
+        #[serde(default = "default_mask_text")]
+        text: String,
+    },
+}
+
+fn default_visible() -> usize {
+    4
+}
+
+fn default_mask_text() -> String {
+    "***".to_string()
+}
+
+impl FieldMask {
+    fn mask(&self, value: &Value) -> Value {
+        match self {
+            FieldMask::KeepLast { visible } => {
+                let s = match value {
+                    Value::String(s) => s.clone(),
+                    Value::Number(n) => n.to_string(),
+                    Value::Bool(b) => b.to_string(),
+                    _ => return Value::String(default_mask_text()),
+                };
+                let length = s.chars().count();
+                let masked = if length <= *visible {
+                    length
+                } else {
+                    length - visible
+                };
+                Value::String(
+                    std::iter::repeat_n('*', masked)
+                        .chain(s.chars().skip(masked))
+                        .collect(),
+                )
+            }
+            FieldMask::Replace { text } => Value::String(text.clone()),
+        }
+    }
+}
+
+impl CollectionConfig {
+    /// Reads the configuration stored with the collection. Unknown or invalid
+    /// settings are replaced by their defaults.
+    pub(crate) fn from_json(value: &serde_json::Value) -> Self {
+        serde_json::from_value(value.clone()).unwrap_or_else(|e| {
+            warn!("Invalid collection configuration: {}", e);
+            Self::default()
+        })
+    }
+
+    pub(crate) fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).expect("Failed to serialize collection configuration")
+    }
+
+    /// Returns the settings like [`CollectionConfig::to_json`], but settings
+    /// that are omitted because they have their default value are included.
+    pub(crate) fn to_effective_json(&self) -> serde_json::Value {
+        let mut value = self.to_json();
+        if let Value::Object(settings) = &mut value {
+            let defaults = [
+                ("maskedFields", json!({})),
+                ("textSearchFields", json!([])),
+                ("fieldTypes", json!({})),
+                ("idVersions", json!([])),
+                ("requiredWhen", json!([])),
+                ("parentFields", json!({})),
+                ("validationWebhook", Value::Null),
+                ("deduplicateEvents", json!([])),
+                ("eventSchemas", json!({})),
+                ("parent", Value::Null),
+                (
+                    "deletedDocumentAccess",
+                    json!(DeletedDocumentAccess::default()),
+                ),
+                ("writeRateLimit", Value::Null),
+                ("maxFieldDepth", Value::Null),
+                ("maxResponseItems", Value::Null),
+            ];
+            for (key, default) in defaults {
+                settings.entry(key).or_insert(default);
+            }
+        }
+        value
+    }
+
+    /// Returns the configured name of an event category.
+    pub(crate) fn category_name(&self, category: i32) -> Option<&str> {
+        self.event_categories.get(&category).map(|s| s.as_str())
+    }
+
+    /// Returns `true` if identical consecutive events of the category are
+    /// recorded only once.
+    pub(crate) fn deduplicates_events(&self, category: i32) -> bool {
+        self.deduplicate_events.contains(&category)
+    }
+
+    /// Rejects an event payload that does not match the schema of its
+    /// category. The response lists the errors by path like other validation
+    /// errors, e. g. `e.amount`.
+    pub(crate) fn validate_event_payload(&self, category: i32, e: &Value) -> Result<(), ApiErrors> {
+        let Some(schema) = self.event_schemas.get(&category) else {
+            return Ok(());
+        };
+        let errors = schema.errors("e", e);
+        if errors.is_empty() {
+            return Ok(());
+        }
+        Err(ApiErrors::BadRequestJson(
+            serde_json::to_value(errors).unwrap_or_default(),
+        ))
+    }
+
+    /// Returns the immutable fields that are set in `before` and have a
+    /// different value (or are missing) in `after`.
+    pub(crate) fn changed_immutable_fields(&self, before: &Value, after: &Value) -> Vec<String> {
+        self.immutable_fields
+            .iter()
+            .filter(|field| match before.get(field.as_str()) {
+                Some(value) => after.get(field.as_str()) != Some(value),
+                None => false,
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Rejects filters that use a text operator on a field that is not
+    /// permitted for text search.
+    pub(crate) fn validate_text_search(&self, filter: &SearchFilter) -> Result<(), ApiErrors> {
+        if self.text_search_fields.is_empty() {
+            return Ok(());
+        }
+        match filter
+            .text_search_fields()
+            .into_iter()
+            .find(|field| !self.text_search_fields.iter().any(|f| f == field))
+        {
+            Some(field) => Err(ApiErrors::BadRequestJsonSimpleMsg(format!(
+                "Text search is not permitted for field {field}, use an exact match (eq or in) instead"
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Rejects sort fields whose direction does not match the declared type of
+    /// the field. The text directions `+` and `-` order numbers like `10`
+    /// before `9`, numbers must use `f` or `b` instead.
+    pub(crate) fn validate_sort(&self, sort_fields: Option<&str>) -> Result<(), ApiErrors> {
+        let Some(sort_fields) = sort_fields else {
+            return Ok(());
+        };
+        for sort_field in sort_fields.split(',') {
+            let sort_field = sort_field.strip_suffix('!').unwrap_or(sort_field);
+            let Some(direction) = sort_field.chars().last() else {
+                continue;
+            };
+            let field = &sort_field[..sort_field.len() - direction.len_utf8()];
+            match (self.field_types.get(field), direction) {
+                (Some(FieldType::Number), '+' | '-') => {
+                    return Err(ApiErrors::BadRequestJsonSimpleMsg(format!(
+                        "Field {field} is a number, sort it with f or b"
+                    )))
+                }
+                (Some(FieldType::Text), 'f' | 'b') => {
+                    return Err(ApiErrors::BadRequestJsonSimpleMsg(format!(
+                        "Field {field} is text, sort it with + or -"
+                    )))
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Rejects the nil UUID and UUIDs with a version that is not permitted.
+    pub(crate) fn validate_document_id(&self, id: &Uuid) -> Result<(), ApiErrors> {
+        self.document_id_errors(id).map_err(ApiErrors::from)
+    }
+
+    fn document_id_errors(&self, id: &Uuid) -> Result<(), ValidationErrors> {
+        let error = if id.is_nil() {
+            ValidationError::new("nil")
+        } else if !self.id_versions.is_empty() && !self.id_versions.contains(&id.get_version_num())
+        {
+            let mut error = ValidationError::new("uuid_version");
+            error.add_param("value".into(), &id.get_version_num());
+            error.add_param("versions".into(), &self.id_versions);
+            error
+        } else {
+            return Ok(());
+        };
+        let mut errors = ValidationErrors::new();
+        errors.add("id", error);
+        Err(errors)
+    }
+
+    /// Rejects document fields that miss a required field. The response lists
+    /// the missing fields like other validation errors.
+    pub(crate) fn validate_required_fields(&self, f: &Value) -> Result<(), ApiErrors> {
+        let unmet = self.unmet_requirements(f);
+        if unmet.is_empty() {
+            return Ok(());
+        }
+        Err(ApiErrors::BadRequestJson(
+            serde_json::to_value(unmet).unwrap_or_default(),
+        ))
+    }
+
+    /// Returns the validation errors of the required fields whose condition
+    /// applies to the document fields but that are missing.
+    fn unmet_requirements(&self, f: &Value) -> BTreeMap<String, Vec<ValidationError>> {
+        let mut errors: BTreeMap<String, Vec<ValidationError>> = BTreeMap::new();
+        for requirement in self.required_when.iter().filter(|r| r.is_unmet(f)) {
+            errors
+                .entry(requirement.field.clone())
+                .or_default()
+                .push(requirement.validation_error());
+        }
+        errors
+    }
+
+    /// Rejects document fields that are nested deeper than permitted.
+    pub(crate) fn validate_field_depth(&self, f: &Value) -> Result<(), ApiErrors> {
+        match self.exceeded_field_depth(f) {
+            Some(max_depth) => Err(ApiErrors::BadRequestJsonSimpleMsg(format!(
+                "Document fields are nested deeper than {max_depth} levels"
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the maximum depth if the document fields are nested deeper.
+    fn exceeded_field_depth(&self, f: &Value) -> Option<usize> {
+        self.max_field_depth
+            .filter(|max_depth| field_depth(f) > *max_depth)
+    }
+
+    /// Collects the errors of all rules of the collection for a new document,
+    /// i. e. the id, the required fields and the nesting depth, by field.
+    pub(crate) fn new_document_errors(
+        &self,
+        id: &Uuid,
+        f: &Value,
+    ) -> BTreeMap<String, Vec<ValidationError>> {
+        let mut errors = self.unmet_requirements(f);
+        if let Err(id_errors) = self.document_id_errors(id) {
+            for (field, field_errors) in id_errors.field_errors() {
+                errors
+                    .entry(field.to_string())
+                    .or_default()
+                    .extend(field_errors.iter().cloned());
+            }
+        }
+        if let Some(max_depth) = self.exceeded_field_depth(f) {
+            let mut error = ValidationError::new("max_depth").with_message(
+                format!("Document fields are nested deeper than {max_depth} levels").into(),
+            );
+            error.add_param("max".into(), &max_depth);
+            errors.entry("f".to_string()).or_default().push(error);
+        }
+        errors
+    }
+
+    pub(crate) fn validation_webhook(&self) -> Option<&ValidationWebhook> {
+        self.validation_webhook.as_ref()
+    }
+
+    pub(crate) fn parent(&self) -> Option<&ParentReference> {
+        self.parent.as_ref()
+    }
+
+    /// Returns the copied parent fields that differ from the document fields.
+    /// A field is removed (`None`) if the parent or the parent field is missing.
+    pub(crate) fn parent_field_changes(
+        &self,
+        f: &Value,
+        parent_f: Option<&Value>,
+    ) -> Vec<(String, Option<Value>)> {
+        if self.parent.is_none() {
+            return vec![];
+        }
+        self.parent_fields
+            .iter()
+            .filter_map(|(field, parent_field)| {
+                let value = parent_f
+                    .and_then(|parent_f| parent_f.get(parent_field))
+                    .cloned();
+                (f.get(field) != value.as_ref()).then(|| (field.clone(), value))
+            })
+            .collect()
+    }
+
+    pub(crate) fn max_response_items(&self) -> Option<u16> {
+        self.max_response_items
+    }
+
+    pub(crate) fn write_rate_limit(&self) -> Option<usize> {
+        self.write_rate_limit.map(|limit| limit as usize)
+    }
+
+    /// Returns `true` if a user with the given relation to a deleted document
+    /// can still read it.
+    pub(crate) fn can_read_deleted(&self, is_owner: bool, is_admin: bool) -> bool {
+        match self.deleted_document_access {
+            DeletedDocumentAccess::Hidden => false,
+            DeletedDocumentAccess::Owner => is_owner,
+            DeletedDocumentAccess::OwnerAndAdmins => is_owner || is_admin,
+        }
+    }
+
+    /// Prepares the document fields for the response: masks the fields with
+    /// personal data, unless the user can read them `unmasked`, and adds the
+    /// formatted companion fields.
+    pub(crate) fn project_fields(&self, f: &mut Value, unmasked: bool) {
+        if !unmasked {
+            self.apply_field_masks(f);
+        }
+        self.apply_field_formats(f);
+    }
+
+    /// Returns `true` if the field or its top-level parent field is masked.
+    pub(crate) fn is_masked(&self, field: &str) -> bool {
+        let top_level_field = field.split('.').next().unwrap_or(field);
+        self.masked_fields.contains_key(top_level_field)
+    }
+
+    /// Replaces the values of the masked fields. Missing fields and `null`
+    /// values are kept.
+    pub(crate) fn apply_field_masks(&self, f: &mut Value) {
+        let Some(fields) = f.as_object_mut() else {
+            return;
+        };
+        for (field, field_mask) in &self.masked_fields {
+            if let Some(value) = fields.get_mut(field).filter(|value| !value.is_null()) {
+                *value = field_mask.mask(value);
+            }
+        }
+    }
+
+    /// Adds the formatted companion fields to the document fields.
+    fn apply_field_formats(&self, f: &mut Value) {
+        let Some(fields) = f.as_object_mut() else {
+            return;
+        };
+        for (field, field_format) in &self.field_formats {
+            let formatted = fields
+                .get(field)
+                .and_then(|value| field_format.format(value));
+            if let Some(formatted) = formatted {
+                fields.insert(
+                    format!("{field}{FORMATTED_FIELD_SUFFIX}"),
+                    Value::String(formatted),
+                );
+            }
+        }
+    }
+}
+
+/// Returns the number of nested objects and arrays, `0` for other values.
+fn field_depth(value: &Value) -> usize {
+    let children_depth = match value {
+        Value::Object(fields) => fields.values().map(field_depth).max(),
+        Value::Array(items) => items.iter().map(field_depth).max(),
+        _ => return 0,
+    };
+    1 + children_depth.unwrap_or_default()
+}
+
+/// Rejects UUID versions that do not exist.
+fn validate_id_versions(versions: &[usize]) -> Result<(), ValidationError> {
+    match versions.iter().find(|version| !(1..=8).contains(*version)) {
+        Some(version) => {
+            let mut error = ValidationError::new("uuid_version");
+            error.add_param("value".into(), version);
+            Err(error)
+        }
+        None => Ok(()),
+    }
+}
+
+/// Rejects currency formats that divide by zero.
+fn validate_field_formats(formats: &BTreeMap<String, FieldFormat>) -> Result<(), ValidationError> {
+    match formats.iter().find(
+        |(_, format)| matches!(format, FieldFormat::Currency { divisor, .. } if *divisor == 0),
+    ) {
+        Some((field, _)) => {
+            let mut error = ValidationError::new("divisor");
+            error.add_param("field".into(), field);
+            Err(error)
+        }
+        None => Ok(()),
+    }
+}
+
+impl From<&entity::collection::Model> for CollectionConfig {
+    fn from(model: &entity::collection::Model) -> Self {
+        Self::from_json(&model.config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn it_reads_event_category_names() {
+        // Arrange
+        let value = json!({"eventCategories": {"1": "Document updated", "1020": "Approved"}});
+
+        // Act
+        let config = CollectionConfig::from_json(&value);
+
+        // Assert
+        assert_eq!(config.category_name(1020), Some("Approved"));
+        assert_eq!(config.category_name(2), None);
+    }
+
+    #[test]
+    fn it_accepts_a_valid_config() {
+        // Arrange
+        let config = CollectionConfig::from_json(&json!({
+            "fieldFormats": {"price": {"type": "currency", "currency": "EUR"}},
+            "idVersions": [4, 7],
+            "validationWebhook": {"url": "http://validator:8080/orders", "timeoutMs": 2000},
+            "parent": {"collection": "projects", "field": "project_id"},
+            "writeRateLimit": 600,
+            "maxResponseItems": 10
+        }));
+
+        // Act
+        let result = config.validate();
+
+        // Assert
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn it_rejects_an_invalid_config() {
+        // Arrange
+        let config: CollectionConfig = serde_json::from_value(json!({
+            "fieldFormats": {"price": {"type": "currency", "currency": "EUR", "divisor": 0}},
+            "idVersions": [4, 9],
+            "validationWebhook": {"url": "validator", "timeoutMs": 0},
+            "parent": {"collection": "", "field": "project_id"},
+            "writeRateLimit": 0,
+            "maxResponseItems": 251
+        }))
+        .unwrap();
+
+        // Act
+        let errors = config.validate().unwrap_err();
+
+        // Assert
+        let fields = errors.errors().keys().copied().collect::<Vec<_>>();
+        assert_eq!(fields.len(), 6);
+        for field in [
+            "field_formats",
+            "id_versions",
+            "validation_webhook",
+            "parent",
+            "write_rate_limit",
+            "max_response_items",
+        ] {
+            assert!(fields.contains(&field), "missing error for {field}");
+        }
+    }
+
+    #[test]
+    fn it_uses_defaults_for_empty_config() {
+        // Act
+        let config = CollectionConfig::from_json(&json!({}));
+
+        // Assert
+        assert_eq!(config, CollectionConfig::default());
+        assert_eq!(
+            config.to_json(),
+            json!({"eventCategories": {}, "fieldFormats": {}, "immutableFields": []})
+        );
+    }
+
+    #[test]
+    fn it_detects_changed_immutable_fields() {
+        // Arrange
+        let config = CollectionConfig::from_json(&json!({
+            "immutableFields": ["order_number", "created_by", "approved_by"]
+        }));
+        let before = json!({"order_number": 1042, "created_by": "alpaca", "title": "Order"});
+        let after = json!({"order_number": 1043, "title": "Order"});
+
+        // Act
+        let changed = config.changed_immutable_fields(&before, &after);
+
+        // Assert
+        assert_eq!(changed, vec!["order_number", "created_by"]);
+    }
+
+    #[test]
+    fn it_accepts_unchanged_immutable_fields() {
+        // Arrange
+        let config = CollectionConfig::from_json(&json!({
+            "immutableFields": ["order_number", "approved_by"]
+        }));
+        let before = json!({"order_number": 1042, "title": "Order"});
+        let after = json!({"order_number": 1042, "title": "Renamed", "approved_by": "bear"});
+
+        // Act
+        let changed = config.changed_immutable_fields(&before, &after);
+
+        // Assert
+        assert!(changed.is_empty());
+    }
+
+    #[test]
+    fn it_formats_cents_as_currency() {
+        // Arrange
+        let config = CollectionConfig::from_json(&json!({
+            "fieldFormats": {"price": {"type": "currency", "currency": "EUR"}}
+        }));
+        let mut f = json!({"title": "Rectangle", "price": 1234});
+
+        // Act
+        config.apply_field_formats(&mut f);
+
+        // Assert
+        assert_eq!(
+            f,
+            json!({"title": "Rectangle", "price": 1234, "price_formatted": "12.34 EUR"})
+        );
+    }
+
+    #[test]
+    fn it_formats_dates() {
+        // Arrange
+        let config = CollectionConfig::from_json(&json!({
+            "fieldFormats": {
+                "due": {"type": "date", "format": "%d.%m.%Y"},
+                "created": {"type": "date", "format": "%d.%m.%Y %H:%M"}
+            }
+        }));
+        let mut f = json!({"due": "2024-03-01", "created": "2024-02-28T13:45:00Z"});
+
+        // Act
+        config.apply_field_formats(&mut f);
+
+        // Assert
+        assert_eq!(f["due_formatted"], json!("01.03.2024"));
+        assert_eq!(f["created_formatted"], json!("28.02.2024 13:45"));
+    }
+
+    #[test]
+    fn it_skips_values_that_cannot_be_formatted() {
+        // Arrange
+        let config = CollectionConfig::from_json(&json!({
+            "fieldFormats": {
+                "price": {"type": "currency", "currency": "EUR"},
+                "due": {"type": "date", "format": "%Q"}
+            }
+        }));
+        let mut f = json!({"price": "free", "due": "2024-03-01"});
+
+        // Act
+        config.apply_field_formats(&mut f);
+
+        // Assert
+        assert_eq!(f, json!({"price": "free", "due": "2024-03-01"}));
+    }
+
+    #[test]
+    fn it_reads_the_parent_document_id() {
+        // Arrange
+        let config = CollectionConfig::from_json(&json!({
+            "parent": {"collection": "projects", "field": "project_id"}
+        }));
+        let parent = config.parent().unwrap();
+
+        // Act
+        let id = parent.parent_id(&json!({"project_id": "0f5c7a3e-2b1d-4c8e-9f6a-7d3b2e1c0a94"}));
+        let missing = parent.parent_id(&json!({"title": "Task"}));
+        let invalid = parent.parent_id(&json!({"project_id": "P-1042"}));
+
+        // Assert
+        assert_eq!(parent.collection(), "projects");
+        assert_eq!(
+            id,
+            Some(Uuid::parse_str("0f5c7a3e-2b1d-4c8e-9f6a-7d3b2e1c0a94").unwrap())
+        );
+        assert_eq!(missing, None);
+        assert_eq!(invalid, None);
+    }
+
+    #[test]
+    fn it_permits_reading_deleted_documents_as_configured() {
+        // Arrange
+        let hidden = CollectionConfig::default();
+        let owner = CollectionConfig::from_json(&json!({"deletedDocumentAccess": "owner"}));
+        let admins =
+            CollectionConfig::from_json(&json!({"deletedDocumentAccess": "ownerAndAdmins"}));
+
+        // Act & Assert
+        assert!(!hidden.can_read_deleted(true, true));
+        assert!(owner.can_read_deleted(true, false));
+        assert!(!owner.can_read_deleted(false, true));
+        assert!(admins.can_read_deleted(false, true));
+        assert!(!admins.can_read_deleted(false, false));
+        assert_eq!(owner.to_json()["deletedDocumentAccess"], json!("owner"));
+    }
+
+    #[test]
+    fn it_requires_fields_when_the_condition_is_met() {
+        // Arrange
+        let config = CollectionConfig::from_json(&json!({
+            "requiredWhen": [
+                {"field": "vat_id", "when": "type", "equals": "company"},
+                {"field": "company_name", "when": "type", "equals": "company"}
+            ]
+        }));
+        let f = json!({"type": "company", "company_name": ""});
+
+        // Act
+        let errors = config.unmet_requirements(&f);
+
+        // Assert
+        assert_eq!(
+            serde_json::to_value(errors).unwrap(),
+            json!({
+                "company_name": [{"code": "required", "message": null, "params": {"when": "type", "equals": "company"}}],
+                "vat_id": [{"code": "required", "message": null, "params": {"when": "type", "equals": "company"}}]
+            })
+        );
+    }
+
+    #[test]
+    fn it_ignores_requirements_when_the_condition_is_not_met() {
+        // Arrange
+        let config = CollectionConfig::from_json(&json!({
+            "requiredWhen": [{"field": "vat_id", "when": "type", "equals": "company"}]
+        }));
+
+        // Act
+        let person = config.unmet_requirements(&json!({"type": "person"}));
+        let untyped = config.unmet_requirements(&json!({"title": "Customer"}));
+        let company = config.unmet_requirements(&json!({"type": "company", "vat_id": "DE123"}));
+
+        // Assert
+        assert!(person.is_empty());
+        assert!(untyped.is_empty());
+        assert!(company.is_empty());
+    }
+
+    #[test]
+    fn it_accepts_an_event_payload_that_matches_the_schema_of_its_category() {
+        // Arrange
+        let config = CollectionConfig::from_json(&json!({
+            "eventSchemas": {"1020": {"type": "object", "required": ["approved"], "properties": {"approved": {"type": "boolean"}}}}
+        }));
+
+        // Act
+        let approved = config.validate_event_payload(1020, &json!({"approved": true}));
+        let other_category = config.validate_event_payload(1, &json!("anything"));
+
+        // Assert
+        assert!(approved.is_ok());
+        assert!(other_category.is_ok());
+    }
+
+    #[test]
+    fn it_rejects_an_event_payload_that_does_not_match_the_schema_of_its_category() {
+        // Arrange
+        let config = CollectionConfig::from_json(&json!({
+            "eventSchemas": {"1020": {"type": "object", "required": ["approved"], "properties": {"approved": {"type": "boolean"}}}}
+        }));
+
+        // Act
+        let result = config.validate_event_payload(1020, &json!({"approved": "yes"}));
+
+        // Assert
+        assert!(matches!(
+            result,
+            Err(ApiErrors::BadRequestJson(errors))
+                if errors["e.approved"][0]["code"] == json!("type")
+                    && errors["e.approved"][0]["params"]["type"] == json!("boolean")
+        ));
+    }
+
+    #[test]
+    fn it_rejects_the_nil_id() {
+        // Arrange
+        let config = CollectionConfig::default();
+
+        // Act
+        let nil = config.document_id_errors(&Uuid::nil());
+        let v4 = config.document_id_errors(&Uuid::new_v4());
+
+        // Assert
+        assert_eq!(
+            serde_json::to_value(nil.unwrap_err()).unwrap(),
+            json!({"id": [{"code": "nil", "message": null, "params": {}}]})
+        );
+        assert!(v4.is_ok());
+    }
+
+    #[test]
+    fn it_permits_text_search_on_configured_fields() {
+        // Arrange
+        let config = CollectionConfig::from_json(&json!({"textSearchFields": ["title"]}));
+        let filter: SearchFilter = serde_json::from_value(json!({"and": [
+            {"f": "title", "o": "containstext", "v": "irc"},
+            {"f": "customer", "o": "eq", "v": "C-1042"}
+        ]}))
+        .unwrap();
+
+        // Act
+        let result = config.validate_text_search(&filter);
+
+        // Assert
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn it_rejects_text_search_on_other_fields() {
+        // Arrange
+        let config = CollectionConfig::from_json(&json!({"textSearchFields": ["title"]}));
+        let filter: SearchFilter = serde_json::from_value(json!({"or": [
+            {"f": "title", "o": "startswith", "v": "C"},
+            {"and": [{"f": "customer", "o": "startswith", "v": "C-10"}]}
+        ]}))
+        .unwrap();
+
+        // Act
+        let result = config.validate_text_search(&filter);
+
+        // Assert
+        match result {
+            Err(ApiErrors::BadRequestJsonSimpleMsg(msg)) => assert_eq!(
+                msg,
+                "Text search is not permitted for field customer, use an exact match (eq or in) instead"
+            ),
+            _ => panic!("Unexpected result {result:?}"),
+        }
+        assert!(CollectionConfig::default()
+            .validate_text_search(&filter)
+            .is_ok());
+    }
+
+    #[test]
+    fn it_rejects_a_numeric_sort_on_a_text_field() {
+        // Arrange
+        let config = CollectionConfig::from_json(&json!({
+            "fieldTypes": {"title": "text", "price": "number"}
+        }));
+
+        // Act
+        let result = config.validate_sort(Some("price-!,titlef"));
+
+        // Assert
+        match result {
+            Err(ApiErrors::BadRequestJsonSimpleMsg(msg)) => {
+                assert_eq!(msg, "Field price is a number, sort it with f or b")
+            }
+            _ => panic!("Unexpected result {result:?}"),
+        }
+        match config.validate_sort(Some("titleb")) {
+            Err(ApiErrors::BadRequestJsonSimpleMsg(msg)) => {
+                assert_eq!(msg, "Field title is text, sort it with + or -")
+            }
+            result => panic!("Unexpected result {result:?}"),
+        }
+    }
+
+    #[test]
+    fn it_permits_sorts_that_match_the_field_types() {
+        // Arrange
+        let config = CollectionConfig::from_json(&json!({
+            "fieldTypes": {"title": "text", "price": "number"}
+        }));
+
+        // Act
+        let result = config.validate_sort(Some("title+,priceb!,length-,created+"));
+
+        // Assert
+        assert!(result.is_ok());
+        assert!(config.validate_sort(None).is_ok());
+        assert!(CollectionConfig::default()
+            .validate_sort(Some("title+,pricef"))
+            .is_ok());
+    }
+
+    #[test]
+    fn it_accepts_documents_within_the_field_depth() {
+        // Arrange
+        let config = CollectionConfig::from_json(&json!({"maxFieldDepth": 3}));
+        let f = json!({
+            "title": "Order",
+            "customer": {"name": "Jane", "tags": ["vip"]},
+            "items": [{"sku": "A-1"}]
+        });
+
+        // Act
+        let result = config.validate_field_depth(&f);
+
+        // Assert
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn it_rejects_documents_beyond_the_field_depth() {
+        // Arrange
+        let config = CollectionConfig::from_json(&json!({"maxFieldDepth": 3}));
+        let f = json!({"title": "Order", "items": [{"options": {"color": "red"}}]});
+
+        // Act
+        let result = config.validate_field_depth(&f);
+
+        // Assert
+        match result {
+            Err(ApiErrors::BadRequestJsonSimpleMsg(msg)) => {
+                assert_eq!(msg, "Document fields are nested deeper than 3 levels")
+            }
+            _ => panic!("Unexpected result {result:?}"),
+        }
+        assert!(CollectionConfig::default().validate_field_depth(&f).is_ok());
+    }
+
+    #[test]
+    fn it_collects_the_errors_of_a_new_document() {
+        // Arrange
+        let config = CollectionConfig::from_json(&json!({
+            "idVersions": [7],
+            "maxFieldDepth": 1,
+            "requiredWhen": [{"field": "reason", "when": "status", "equals": "rejected"}]
+        }));
+        let id = Uuid::new_v4();
+        let f = json!({"status": "rejected", "notes": {"text": "late"}});
+
+        // Act
+        let errors = config.new_document_errors(&id, &f);
+
+        // Assert
+        let codes: Vec<(&str, Vec<&str>)> = errors
+            .iter()
+            .map(|(field, errors)| {
+                (
+                    field.as_str(),
+                    errors.iter().map(|error| error.code.as_ref()).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            codes,
+            vec![
+                ("f", vec!["max_depth"]),
+                ("id", vec!["uuid_version"]),
+                ("reason", vec!["required"]),
+            ]
+        );
+        assert!(config
+            .new_document_errors(
+                &Uuid::parse_str("018f2b3c-4d5e-7f60-8a1b-2c3d4e5f6a7b").unwrap(),
+                &json!({"status": "open"})
+            )
+            .is_empty());
+    }
+
+    #[test]
+    fn it_restricts_the_id_versions() {
+        // Arrange
+        let config = CollectionConfig::from_json(&json!({"idVersions": [4, 7]}));
+        let v1 = Uuid::parse_str("c232ab00-9414-11ec-b3c8-9f6bdeced846").unwrap();
+        let v7 = Uuid::parse_str("018e0b52-6d3c-7c2a-9f0e-3b5d7a1c9e42").unwrap();
+
+        // Act
+        let v1_result = config.document_id_errors(&v1);
+        let v4_result = config.document_id_errors(&Uuid::new_v4());
+        let v7_result = config.document_id_errors(&v7);
+
+        // Assert
+        assert_eq!(
+            serde_json::to_value(v1_result.unwrap_err()).unwrap(),
+            json!({"id": [{"code": "uuid_version", "message": null, "params": {"value": 1, "versions": [4, 7]}}]})
+        );
+        assert!(v4_result.is_ok());
+        assert!(v7_result.is_ok());
+    }
+
+    #[test]
+    fn it_detects_stale_parent_fields() {
+        // Arrange
+        let config = CollectionConfig::from_json(&json!({
+            "parent": {"collection": "projects", "field": "project_id"},
+            "parentFields": {"project_title": "title", "project_state": "state"}
+        }));
+        let f = json!({"title": "Task", "project_title": "Old name", "project_state": "open"});
+        let parent_f = json!({"title": "New name", "state": "open"});
+
+        // Act
+        let changes = config.parent_field_changes(&f, Some(&parent_f));
+        let orphan_changes = config.parent_field_changes(&f, None);
+
+        // Assert
+        assert_eq!(
+            changes,
+            vec![("project_title".to_string(), Some(json!("New name")))]
+        );
+        assert_eq!(
+            orphan_changes,
+            vec![
+                ("project_state".to_string(), None),
+                ("project_title".to_string(), None)
+            ]
+        );
+    }
+
+    #[test]
+    fn it_ignores_parent_fields_without_parent_reference() {
+        // Arrange
+        let config =
+            CollectionConfig::from_json(&json!({"parentFields": {"project_title": "title"}}));
+
+        // Act
+        let changes = config.parent_field_changes(&json!({}), Some(&json!({"title": "Project"})));
+
+        // Assert
+        assert!(changes.is_empty());
+    }
+
+    fn masking_config() -> CollectionConfig {
+        CollectionConfig::from_json(&json!({
+            "fieldFormats": {"salary": {"type": "currency", "currency": "EUR"}},
+            "maskedFields": {
+                "iban": {"type": "keepLast", "visible": 4},
+                "pin": {"type": "keepLast"},
+                "salary": {"type": "replace"}
+            }
+        }))
+    }
+
+    #[test]
+    fn it_masks_fields_by_default() {
+        // Arrange
+        let config = masking_config();
+        let mut f = json!({
+            "title": "Alpaca",
+            "iban": "DE02120300000000202051",
+            "pin": 1234,
+            "salary": 420000
+        });
+
+        // Act
+        config.project_fields(&mut f, false);
+
+        // Assert
+        assert_eq!(
+            f,
+            json!({
+                "title": "Alpaca",
+                "iban": "******************2051",
+                "pin": "****",
+                "salary": "***"
+            })
+        );
+    }
+
+    #[test]
+    fn it_returns_unmasked_fields_to_privileged_users() {
+        // Arrange
+        let config = masking_config();
+        let mut f = json!({"iban": "DE02120300000000202051", "pin": null, "salary": 420000});
+
+        // Act
+        config.project_fields(&mut f, true);
+
+        // Assert
+        assert_eq!(
+            f,
+            json!({
+                "iban": "DE02120300000000202051",
+                "pin": null,
+                "salary": 420000,
+                "salary_formatted": "4200.00 EUR"
+            })
+        );
+    }
+
+    #[test]
+    fn it_keeps_missing_and_null_masked_fields() {
+        // Arrange
+        let config = masking_config();
+        let mut f = json!({"title": "Bear", "pin": null});
+
+        // Act
+        config.apply_field_masks(&mut f);
+
+        // Assert
+        assert_eq!(f, json!({"title": "Bear", "pin": null}));
+    }
+}
@@ -0,0 +1,521 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use axum_macros::debug_handler;
+use jwt_authorizer::JwtClaims;
+use sea_orm::{TransactionError, TransactionTrait};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::{info, warn};
+use validator::Validate;
+
+use crate::api::{
+    auth::User,
+    collection_config::CollectionConfig,
+    db::{get_collection_by_name, set_collection_config, set_collection_properties},
+    hooks::Hooks,
+    maintenance::api_rebuild_grants::rebuild_collection_grants,
+    types::{page_limit_settings, PageLimitSettings},
+    ApiContext, ApiErrors,
+};
+use crate::axumext::extractors::JsonBody;
+use crate::models::Collection;
+
+/// Settings that are in effect for a collection
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct EffectiveCollectionConfig {
+    /// Name of the collection
+    name: String,
+
+    /// Human readable name of the collection
+    title: String,
+
+    /// Owner access only
+    oao: bool,
+
+    /// New documents cannot be created
+    locked: bool,
+
+    /// Settings of the collection, settings that are not set have their
+    /// default value
+    #[schema(value_type = CollectionConfig)]
+    config: Value,
+
+    /// Maximum page sizes of the server
+    page_limits: PageLimitSettings,
+
+    /// Hooks of the server for the collection
+    hooks: EffectiveHooks,
+}
+
+/// Hooks that are registered for a collection
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct EffectiveHooks {
+    /// A hook runs when a document is created
+    create: bool,
+
+    /// A hook runs when a document is updated
+    update: bool,
+
+    /// Event categories with a hook
+    event_categories: Vec<i32>,
+
+    /// A hook changes the documents before they are read
+    read: bool,
+}
+
+impl EffectiveCollectionConfig {
+    fn new(collection: &entity::collection::Model, hooks: &Hooks) -> Self {
+        Self {
+            name: collection.name.clone(),
+            title: collection.title.clone(),
+            oao: collection.oao,
+            locked: collection.locked,
+            config: CollectionConfig::from(collection).to_effective_json(),
+            page_limits: page_limit_settings(),
+            hooks: EffectiveHooks {
+                create: hooks.get_create_hook(&collection.name).is_some(),
+                update: hooks.get_update_hook(&collection.name).is_some(),
+                event_categories: hooks.get_event_hook_categories(&collection.name),
+                read: hooks.get_read_hook(&collection.name).is_some(),
+            },
+        }
+    }
+}
+
+/// Get collection settings
+///
+/// Returns the settings of the collection.
+#[debug_handler]
+#[utoipa::path(
+    get,
+    path = "/collections/{collection_name}/config",
+    operation_id = "getCollectionConfig",
+    params(
+        (
+            "collection_name" = String,
+            Path,
+            description = "Name of the collection",
+            min_length = 1,
+            max_length = 32,
+            pattern = r"^[a-z][-a-z0-9]*$",
+        ),
+    ),
+    responses(
+        (status = OK, description = "Settings of the collection", body = CollectionConfig ),
+        (status = UNAUTHORIZED, description = "User is not a collection admin" ),
+        (status = NOT_FOUND, description = "Collection not found" ),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
+    ),
+    tag = super::TAG_ADMINISTRATION,
+)]
+pub(crate) async fn api_read_collection_config(
+    State(ctx): State<ApiContext>,
+    Path(collection_name): Path<String>,
+    JwtClaims(user): JwtClaims<User>,
+) -> Result<Json<CollectionConfig>, ApiErrors> {
+    let collection = get_collection_by_name(&ctx.db, &collection_name)
+        .await
+        .ok_or_else(|| ApiErrors::NotFound(collection_name.clone()))?;
+
+    if !(user.is_collection_admin(&collection_name) || user.is_collections_administrator()) {
+        warn!(
+            "User {} is not permitted to read the settings of collection {collection_name}",
+            user.name_and_sub()
+        );
+        return Err(ApiErrors::PermissionDenied);
+    }
+
+    Ok(Json((&collection).into()))
+}
+
+/// Get effective collection settings
+///
+/// Returns the settings that are in effect for the collection: the collection
+/// itself, its settings including the defaults of settings that are not set,
+/// the page limits of the server and the hooks of the collection.
+#[debug_handler]
+#[utoipa::path(
+    get,
+    path = "/collections/{collection_name}/effective-config",
+    operation_id = "getEffectiveCollectionConfig",
+    params(
+        (
+            "collection_name" = String,
+            Path,
+            description = "Name of the collection",
+            min_length = 1,
+            max_length = 32,
+            pattern = r"^[a-z][-a-z0-9]*$",
+        ),
+    ),
+    responses(
+        (status = OK, description = "Effective settings of the collection", body = EffectiveCollectionConfig ),
+        (status = UNAUTHORIZED, description = "User is not a collection admin" ),
+        (status = NOT_FOUND, description = "Collection not found" ),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
+    ),
+    tag = super::TAG_ADMINISTRATION,
+)]
+pub(crate) async fn api_read_effective_collection_config(
+    State(ctx): State<ApiContext>,
+    Path(collection_name): Path<String>,
+    JwtClaims(user): JwtClaims<User>,
+) -> Result<Json<EffectiveCollectionConfig>, ApiErrors> {
+    let collection = get_collection_by_name(&ctx.db, &collection_name)
+        .await
+        .ok_or_else(|| ApiErrors::NotFound(collection_name.clone()))?;
+
+    if !(user.is_collection_admin(&collection_name) || user.is_collections_administrator()) {
+        warn!(
+            "User {} is not permitted to read the settings of collection {collection_name}",
+            user.name_and_sub()
+        );
+        return Err(ApiErrors::PermissionDenied);
+    }
+
+    Ok(Json(EffectiveCollectionConfig::new(
+        &collection,
+        &ctx.hooks,
+    )))
+}
+
+/// Replace collection settings
+///
+/// Validates and stores the settings of the collection. The settings replace
+/// the previous settings completely, settings that are not given are reset to
+/// their defaults.
+#[debug_handler]
+#[utoipa::path(
+    put,
+    path = "/collections/{collection_name}/config",
+    operation_id = "replaceCollectionConfig",
+    params(
+        (
+            "collection_name" = String,
+            Path,
+            description = "Name of the collection",
+            min_length = 1,
+            max_length = 32,
+            pattern = r"^[a-z][-a-z0-9]*$",
+        ),
+    ),
+    request_body(content = CollectionConfig, description = "Settings of the collection", content_type = "application/json"),
+    responses(
+        (status = NO_CONTENT, description = "Settings stored" ),
+        (status = UNAUTHORIZED, description = "User is not a collections admin" ),
+        (status = NOT_FOUND, description = "Collection not found" ),
+        (status = BAD_REQUEST, description = "Invalid settings" ),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
+    ),
+    tag = super::TAG_ADMINISTRATION,
+)]
+pub(crate) async fn api_update_collection_config(
+    State(ctx): State<ApiContext>,
+    Path(collection_name): Path<String>,
+    JwtClaims(user): JwtClaims<User>,
+    JsonBody(config): JsonBody<CollectionConfig>,
+) -> Result<StatusCode, ApiErrors> {
+    if !user.is_collections_administrator() {
+        warn!("User {} is not a collections admin", user.name_and_sub());
+        return Err(ApiErrors::PermissionDenied);
+    }
+    config.validate().map_err(ApiErrors::from)?;
+
+    let collection = get_collection_by_name(&ctx.db, &collection_name)
+        .await
+        .ok_or_else(|| ApiErrors::NotFound(collection_name.clone()))?;
+
+    set_collection_config(&ctx.db, collection.id, &config).await?;
+    info!(
+        "User {} replaced the settings of collection {collection_name}",
+        user.name_and_sub()
+    );
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Properties of a collection to change, properties that are not given keep
+/// their value
+#[derive(Debug, Default, Deserialize, Validate, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct UpdateCollectionRequest {
+    /// Path name of the collection, it cannot be changed
+    #[schema(examples("shapes"))]
+    name: Option<String>,
+
+    /// Human readable name of the collection
+    #[validate(length(min = 1, max = 150))]
+    #[schema(examples("Shapes"), min_length = 1, max_length = 150)]
+    title: Option<String>,
+
+    /// Owner access only
+    oao: Option<bool>,
+
+    /// New documents cannot be created
+    locked: Option<bool>,
+}
+
+impl UpdateCollectionRequest {
+    /// Applies the changed properties to the collection. A different name is
+    /// rejected.
+    fn apply(
+        self,
+        collection: entity::collection::Model,
+    ) -> Result<entity::collection::Model, ApiErrors> {
+        if self
+            .name
+            .as_ref()
+            .is_some_and(|name| *name != collection.name)
+        {
+            return Err(ApiErrors::BadRequestJsonSimpleMsg(
+                "The name of a collection cannot be changed".to_string(),
+            ));
+        }
+        Ok(entity::collection::Model {
+            title: self.title.unwrap_or(collection.title),
+            oao: self.oao.unwrap_or(collection.oao),
+            locked: self.locked.unwrap_or(collection.locked),
+            ..collection
+        })
+    }
+}
+
+/// Update a collection
+///
+/// Changes the title of the collection, whether it is owner access only and
+/// whether it is locked. The name of a collection cannot be changed. A change
+/// of the owner access rebuilds the grants of all documents.
+#[debug_handler]
+#[utoipa::path(
+    put,
+    path = "/collections/{collection_name}/properties",
+    operation_id = "updateCollection",
+    params(
+        (
+            "collection_name" = String,
+            Path,
+            description = "Name of the collection",
+            min_length = 1,
+            max_length = 32,
+            pattern = r"^[a-z][-a-z0-9]*$",
+        ),
+    ),
+    request_body(content = UpdateCollectionRequest, description = "Properties of the collection", content_type = "application/json"),
+    responses(
+        (status = OK, description = "Updated collection", body = Collection ),
+        (status = UNAUTHORIZED, description = "User is not a collections admin" ),
+        (status = NOT_FOUND, description = "Collection not found" ),
+        (status = BAD_REQUEST, description = "Invalid properties or a changed name" ),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
+    ),
+    tag = super::TAG_ADMINISTRATION,
+)]
+pub(crate) async fn api_update_collection(
+    State(ctx): State<ApiContext>,
+    Path(collection_name): Path<String>,
+    JwtClaims(user): JwtClaims<User>,
+    JsonBody(payload): JsonBody<UpdateCollectionRequest>,
+) -> Result<Json<Collection>, ApiErrors> {
+    if !user.is_collections_administrator() {
+        warn!("User {} is not a collections admin", user.name_and_sub());
+        return Err(ApiErrors::PermissionDenied);
+    }
+    payload.validate().map_err(ApiErrors::from)?;
+
+    let collection = get_collection_by_name(&ctx.db, &collection_name)
+        .await
+        .ok_or_else(|| ApiErrors::NotFound(collection_name.clone()))?;
+
+    let oao_changed = payload.oao.is_some_and(|oao| oao != collection.oao);
+    let collection = payload.apply(collection)?;
+    let stored = collection.clone();
+    ctx.db
+        .transaction::<_, (), ApiErrors>(|txn| {
+            Box::pin(async move {
+                set_collection_properties(txn, &stored).await?;
+                if oao_changed {
+                    // The documents keep the grants of the previous visibility
+                    // otherwise, e. g. readers of a public collection
+                    let summary = rebuild_collection_grants(
+                        txn,
+                        &ctx.hooks,
+                        &stored,
+                        ctx.data_service.clone(),
+                        false,
+                    )
+                    .await?;
+                    info!("Rebuilt grants of collection {}: {summary:?}", stored.name);
+                }
+                Ok(())
+            })
+        })
+        .await
+        .map_err(|err| match err {
+            TransactionError::Connection(c) => Into::<ApiErrors>::into(c),
+            TransactionError::Transaction(t) => t,
+        })?;
+    info!(
+        "User {} updated collection {collection_name}: title {:?}, oao {}, locked {}",
+        user.name_and_sub(),
+        collection.title,
+        collection.oao,
+        collection.locked
+    );
+    Ok(Json(Collection::new(
+        collection.name,
+        collection.title,
+        collection.oao,
+        collection.locked,
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::api::hooks::webhook::WebhookHook;
+
+    #[test]
+    fn it_returns_defaulted_and_overridden_settings() {
+        // Arrange
+        let collection = entity::collection::Model {
+            id: Uuid::new_v4(),
+            name: "orders".to_string(),
+            title: "Orders".to_string(),
+            oao: false,
+            locked: false,
+            config: json!({
+                "deletedDocumentAccess": "owner",
+                "validationWebhook": {"url": "http://validator:8080/orders"}
+            }),
+        };
+        let hooks = Hooks::new();
+        hooks.put_event_hook(
+            "orders".to_string(),
+            1020,
+            Arc::new(WebhookHook::new("http://erp:8080/events", None)),
+        );
+
+        // Act
+        let effective = EffectiveCollectionConfig::new(&collection, &hooks);
+
+        // Assert
+        let effective = serde_json::to_value(effective).unwrap();
+        assert_eq!(effective["config"]["deletedDocumentAccess"], json!("owner"));
+        assert_eq!(
+            effective["config"]["validationWebhook"],
+            json!({"url": "http://validator:8080/orders", "timeoutMs": 5000, "failOpen": false})
+        );
+        assert_eq!(effective["config"]["textSearchFields"], json!([]));
+        assert_eq!(effective["config"]["writeRateLimit"], json!(null));
+        assert_eq!(effective["pageLimits"]["maxLimit"], json!(250));
+        assert_eq!(
+            effective["hooks"],
+            json!({"create": false, "update": false, "eventCategories": [1020], "read": false})
+        );
+    }
+
+    #[test]
+    fn it_fills_the_defaults_of_an_empty_config() {
+        // Arrange
+        let config = CollectionConfig::default();
+
+        // Act
+        let effective = config.to_effective_json();
+
+        // Assert
+        assert_eq!(effective["deletedDocumentAccess"], json!("hidden"));
+        assert_eq!(effective["eventCategories"], json!({}));
+        assert_eq!(effective["maxFieldDepth"], json!(null));
+    }
+
+    fn shapes_collection() -> entity::collection::Model {
+        entity::collection::Model {
+            id: Uuid::new_v4(),
+            name: "shapes".to_string(),
+            title: "Shapes".to_string(),
+            oao: false,
+            locked: false,
+            config: json!({}),
+        }
+    }
+
+    #[test]
+    fn it_toggles_the_lock_of_a_collection() {
+        // Arrange
+        let request: UpdateCollectionRequest =
+            serde_json::from_value(json!({"locked": true})).unwrap();
+
+        // Act
+        let collection = request.apply(shapes_collection()).unwrap();
+
+        // Assert
+        assert_eq!(
+            (collection.title.as_str(), collection.oao, collection.locked),
+            ("Shapes", false, true)
+        );
+    }
+
+    #[test]
+    fn it_changes_the_title_of_a_collection() {
+        // Arrange
+        let request: UpdateCollectionRequest = serde_json::from_value(
+            json!({"name": "shapes", "title": "Geometric shapes", "oao": true}),
+        )
+        .unwrap();
+
+        // Act
+        let collection = request.apply(shapes_collection()).unwrap();
+
+        // Assert
+        assert_eq!(
+            (
+                collection.name.as_str(),
+                collection.title.as_str(),
+                collection.oao,
+                collection.locked
+            ),
+            ("shapes", "Geometric shapes", true, false)
+        );
+    }
+
+    #[test]
+    fn it_rejects_a_changed_collection_name() {
+        // Arrange
+        let request: UpdateCollectionRequest =
+            serde_json::from_value(json!({"name": "figures", "title": "Figures"})).unwrap();
+
+        // Act
+        let result = request.apply(shapes_collection());
+
+        // Assert
+        assert_eq!(
+            result.unwrap_err(),
+            ApiErrors::BadRequestJsonSimpleMsg(
+                "The name of a collection cannot be changed".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn it_rejects_an_empty_title() {
+        // Arrange
+        let request: UpdateCollectionRequest =
+            serde_json::from_value(json!({"title": ""})).unwrap();
+
+        // Act
+        let result = request.validate();
+
+        // Assert
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,246 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use jwt_authorizer::JwtClaims;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use validator::Validate;
+
+use crate::axumext::extractors::ValidatedQueryParams;
+
+use super::{
+    auth::User,
+    db::{get_unlocked_collection_by_name, FieldFilter, ListDocumentGrants},
+    grants::{hook_or_default_user_grants, GrantCollection},
+    list_documents::{
+        count_mode, created_filters, exact_title_filter, generic_count_documents, parse_pfilter,
+        validate_timestamp, DeletedDocuments, RE_TITLE_FIELD,
+    },
+    read_projection::ReadProjection,
+    ApiContext, ApiErrors,
+};
+
+#[derive(Debug, Default, Deserialize, Validate, utoipa::IntoParams)]
+#[serde(default)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct CountDocumentParams {
+    /// Only count the documents that match the filter, see `pfilter` of the
+    /// list endpoint
+    #[serde(rename = "pfilter")]
+    #[param(example = "title='Rectangle'&price=[12,13,14]")]
+    pfilter: Option<String>,
+
+    /// Only count the documents with this title, see `exactTitle` of the
+    /// list endpoint
+    #[serde(rename = "exactTitle")]
+    exact_title: Option<String>,
+
+    /// Field that `exactTitle` is compared with, see `titleField` of the
+    /// list endpoint
+    #[validate(regex(path = *RE_TITLE_FIELD))]
+    #[serde(rename = "titleField")]
+    #[param(
+        default = "title",
+        example = "name",
+        pattern = r#"^[a-zA-Z0-9_]+(\.[a-zA-Z0-9_]+)*$"#
+    )]
+    title_field: Option<String>,
+
+    /// Only count the documents whose field `created` is after this RFC 3339
+    /// timestamp, see `createdAfter` of the list endpoint
+    #[validate(custom(function = "validate_timestamp"))]
+    #[serde(rename = "createdAfter")]
+    #[param(example = "2024-02-01T00:00:00Z")]
+    created_after: Option<String>,
+
+    /// Only count the documents whose field `created` is before this RFC 3339
+    /// timestamp, see `createdBefore` of the list endpoint
+    #[validate(custom(function = "validate_timestamp"))]
+    #[serde(rename = "createdBefore")]
+    #[param(example = "2024-03-01T00:00:00Z")]
+    created_before: Option<String>,
+
+    /// Count with a time limit and return the estimate of the database if
+    /// counting takes too long, see `approximateCount` of the list endpoint
+    #[serde(rename = "approximateCount")]
+    approximate_count: Option<bool>,
+}
+
+impl CountDocumentParams {
+    /// Filters for `pfilter`, `exactTitle` and the `created` window
+    fn filters(&self) -> Result<Vec<FieldFilter>, ApiErrors> {
+        let mut filters = parse_pfilter(self.pfilter.clone())?;
+        filters.extend(exact_title_filter(
+            self.exact_title.as_deref(),
+            self.title_field.as_deref(),
+        ));
+        filters.extend(created_filters(
+            self.created_after.as_deref(),
+            self.created_before.as_deref(),
+        )?);
+        Ok(filters)
+    }
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+#[schema(examples(json!({"total": 1248})))]
+pub(crate) struct DocumentCount {
+    /// Number of matching documents
+    total: u32,
+
+    /// Whether `total` is an estimate, only set with `approximateCount`
+    #[serde(rename = "totalApproximate", skip_serializing_if = "Option::is_none")]
+    total_approximate: Option<bool>,
+}
+
+/// Count collection items
+///
+/// Count the documents of the collection that the user can access, without
+/// returning them. The total is the same as the total of the list endpoint
+/// for the same filter.
+#[utoipa::path(
+    get,
+    path = "/collections/{collection_name}/count",
+    operation_id = "countCollectionItems",
+    params(
+        CountDocumentParams,
+        (
+            "collection_name" = String,
+            Path,
+            description = "Name of the collection",
+            min_length = 1,
+            max_length = 32,
+            pattern = r"^[a-z][-a-z0-9]*$",
+        ),
+    ),
+    responses(
+        (status = OK, description = "Number of documents", body = DocumentCount ),
+        (status = UNAUTHORIZED, description = "User is not a collection reader" ),
+        (status = NOT_FOUND, description = "Collection not found" ),
+        (status = BAD_REQUEST, description = "Invalid request" ),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
+    ),
+    tag = super::TAG_COLLECTION,
+)]
+pub(crate) async fn api_count_documents(
+    State(ctx): State<ApiContext>,
+    ValidatedQueryParams(params): ValidatedQueryParams<CountDocumentParams>,
+    Path(collection_name): Path<String>,
+    JwtClaims(user): JwtClaims<User>,
+) -> Result<Json<DocumentCount>, ApiErrors> {
+    let collection = get_unlocked_collection_by_name(&ctx.db, &collection_name)
+        .await
+        .ok_or_else(|| ApiErrors::NotFound(collection_name.clone()))?;
+
+    let user_is_permitted = user.is_collection_admin(&collection_name)
+        || user.can_access_all_documents(&collection_name)
+        || user.is_collection_reader(&collection_name);
+    if !user_is_permitted {
+        warn!("User {} is not a collection reader", user.name_and_sub());
+        return Err(ApiErrors::PermissionDenied);
+    }
+
+    let dto_collection: GrantCollection = (&collection).into();
+    let user_grants =
+        hook_or_default_user_grants(&ctx.hooks, &dto_collection, &user, ctx.data_service.clone())
+            .await?;
+
+    let request_filters = params.filters()?;
+
+    let count = count_mode(params.approximate_count, None);
+    let total = generic_count_documents(
+        &ctx.db,
+        collection.id,
+        DeletedDocuments::Exclude,
+        if request_filters.is_empty() {
+            None
+        } else {
+            Some(request_filters.into())
+        },
+        &ReadProjection::new(&ctx.hooks, &collection, &user),
+        ListDocumentGrants::Restricted(user_grants),
+        count,
+    )
+    .await?;
+
+    Ok(Json(DocumentCount {
+        total: total.count,
+        total_approximate: params.approximate_count.map(|_| total.approximate),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+    use crate::api::search_documents::SearchFilter;
+
+    #[test]
+    fn it_counts_the_documents_created_within_the_window() {
+        // Arrange
+        let params = CountDocumentParams {
+            exact_title: Some("Square".to_string()),
+            created_after: Some("2024-02-01T00:00:00Z".to_string()),
+            created_before: Some("2024-03-01T00:00:00Z".to_string()),
+            ..Default::default()
+        };
+
+        // Act
+        let filter: SearchFilter = params.filters().unwrap().into();
+
+        // Assert
+        assert!(params.validate().is_ok());
+        assert_eq!(
+            serde_json::to_value(filter).unwrap(),
+            json!({"and": [
+                {"f": "title", "o": "eq", "v": "Square"},
+                {"f": "created", "o": "gt", "v": "2024-02-01T00:00:00Z"},
+                {"f": "created", "o": "lt", "v": "2024-03-01T00:00:00Z"}
+            ]})
+        );
+    }
+
+    #[test]
+    fn it_counts_the_exact_title_on_the_title_field() {
+        // Arrange
+        let params = CountDocumentParams {
+            exact_title: Some("Square".to_string()),
+            title_field: Some("label.name".to_string()),
+            ..Default::default()
+        };
+        let invalid = CountDocumentParams {
+            exact_title: Some("Square".to_string()),
+            title_field: Some("name'--".to_string()),
+            ..Default::default()
+        };
+
+        // Act
+        let filter: SearchFilter = params.filters().unwrap().into();
+
+        // Assert
+        assert!(params.validate().is_ok());
+        assert!(invalid.validate().is_err());
+        assert_eq!(
+            serde_json::to_value(filter).unwrap(),
+            json!({"and": [{"f": "label.name", "o": "eq", "v": "Square"}]})
+        );
+    }
+
+    #[test]
+    fn it_rejects_invalid_created_timestamps() {
+        // Arrange
+        let params = CountDocumentParams {
+            created_before: Some("yesterday".to_string()),
+            ..Default::default()
+        };
+
+        // Act
+        let result = params.validate();
+
+        // Assert
+        assert!(result.is_err());
+    }
+}
@@ -1,14 +1,41 @@
-use axum::{extract::State, http::StatusCode, Json};
+use std::collections::BTreeSet;
+
+use axum::{extract::State, http::StatusCode};
 use axum_macros::debug_handler;
 use entity::collection;
 use jwt_authorizer::JwtClaims;
-use sea_orm::{DbErr, EntityTrait, RuntimeErr, Set};
+use lazy_static::lazy_static;
+use regex::Regex;
+use sea_orm::{DbErr, EntityTrait, RuntimeErr, Set, TransactionTrait};
 use tracing::{error, info, warn};
 use validator::Validate;
 
-use crate::api::{auth::User, ApiContext, ApiErrors};
+use crate::api::{auth::User, db::create_field_indexes, ApiContext, ApiErrors};
+use crate::axumext::extractors::JsonBody;
 use crate::models::CreateCollectionRequest;
 
+/// Maximum number of indexed fields of a collection
+const MAX_INDEXED_FIELDS: usize = 16;
+
+/// Names that collide with route segments of the API or with internal
+/// collections, e. g. the mail queue
+pub(crate) const RESERVED_COLLECTION_NAMES: [&str; 10] = [
+    "backup",
+    "collections",
+    "events",
+    "folivafy-mail",
+    "maintenance",
+    "recoverables",
+    "restore",
+    "search",
+    "searches",
+    "time",
+];
+
+lazy_static! {
+    static ref RE_INDEXED_FIELD: Regex = Regex::new(r"^[a-zA-Z0-9_]+(\.[a-zA-Z0-9_]+)*$").unwrap();
+}
+
 /// Create a collection
 ///
 /// Create a new collection on this server
@@ -20,7 +47,7 @@ use crate::models::CreateCollectionRequest;
     responses(
         (status = CREATED, description = "Collection created successfully" ),
         (status = UNAUTHORIZED, description = "User is not a collections admin" ),
-        (status = BAD_REQUEST, description = "Invalid request" ),
+        (status = BAD_REQUEST, description = "Invalid request or reserved collection name" ),
         (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
     ),
     request_body(content = CreateCollectionRequest, description = "Create a new collection", content_type = "application/json"),
@@ -29,22 +56,29 @@ use crate::models::CreateCollectionRequest;
 pub(crate) async fn api_create_collection(
     State(ctx): State<ApiContext>,
     JwtClaims(user): JwtClaims<User>,
-    Json(payload): Json<CreateCollectionRequest>,
+    JsonBody(payload): JsonBody<CreateCollectionRequest>,
 ) -> Result<(StatusCode, String), ApiErrors> {
     if !user.is_collections_administrator() {
         warn!("User {} is not a collections admin", user.name_and_sub());
         return Err(ApiErrors::PermissionDenied);
     }
     payload.validate().map_err(ApiErrors::from)?;
+    check_collection_name(&payload.name)?;
+    if let Some(config) = &payload.config {
+        config.validate().map_err(ApiErrors::from)?;
+    }
+    let indexed_fields = indexed_fields(payload.indexed_fields.as_deref())?;
     let mut collection = collection::ActiveModel {
         ..Default::default()
     };
     collection.name = Set(payload.name.clone());
     collection.title = Set(payload.title.clone());
     collection.oao = Set(payload.oao);
+    collection.config = Set(payload.config.clone().unwrap_or_default().to_json());
 
+    let txn = ctx.db.begin().await?;
     let res = entity::collection::Entity::insert(collection)
-        .exec(&ctx.db)
+        .exec(&txn)
         .await
         .map_err(|err| match err {
             DbErr::Exec(RuntimeErr::SqlxError(error)) => match error {
@@ -72,6 +106,24 @@ pub(crate) async fn api_create_collection(
                 ApiErrors::InternalServerError
             }
         })?;
+    txn.commit().await?;
+
+    // The indexes are built concurrently, which is not possible inside the
+    // transaction. Without its indexes the collection is removed again, so
+    // the request can be repeated.
+    if let Err(err) = create_field_indexes(&ctx.db, res.last_insert_id, &indexed_fields).await {
+        error!(
+            "Cannot create indexes of collection {}: {}",
+            payload.name, err
+        );
+        if let Err(err) = entity::collection::Entity::delete_by_id(res.last_insert_id)
+            .exec(&ctx.db)
+            .await
+        {
+            error!("Cannot remove collection {}: {}", payload.name, err);
+        }
+        return Err(ApiErrors::InternalServerError);
+    }
 
     info!(
         "Created new collection: {} {}",
@@ -82,3 +134,96 @@ pub(crate) async fn api_create_collection(
         format!("Collection {} created", payload.name),
     ))
 }
+
+/// Rejects the reserved collection names.
+fn check_collection_name(name: &str) -> Result<(), ApiErrors> {
+    if RESERVED_COLLECTION_NAMES.contains(&name) {
+        return Err(ApiErrors::BadRequestJsonSimpleMsg(format!(
+            "Collection name {name} is reserved"
+        )));
+    }
+    Ok(())
+}
+
+/// Checks the names of the indexed fields, a field that is listed more than
+/// once is indexed once.
+fn indexed_fields(fields: Option<&[String]>) -> Result<BTreeSet<String>, ApiErrors> {
+    let fields = fields.unwrap_or_default();
+    if fields.len() > MAX_INDEXED_FIELDS {
+        return Err(ApiErrors::BadRequestJsonSimpleMsg(format!(
+            "At most {MAX_INDEXED_FIELDS} fields can be indexed"
+        )));
+    }
+    if let Some(field) = fields.iter().find(|f| !RE_INDEXED_FIELD.is_match(f)) {
+        return Err(ApiErrors::BadRequestJsonSimpleMsg(format!(
+            "Invalid indexed field {field}"
+        )));
+    }
+    Ok(fields.iter().cloned().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn it_indexes_a_duplicate_field_once() {
+        // Arrange
+        let fields = vec![
+            "status".to_string(),
+            "customer.name".to_string(),
+            "status".to_string(),
+        ];
+
+        // Act
+        let indexed = indexed_fields(Some(&fields)).unwrap();
+
+        // Assert
+        assert_eq!(
+            indexed.into_iter().collect::<Vec<_>>(),
+            vec!["customer.name".to_string(), "status".to_string()]
+        );
+    }
+
+    #[test]
+    fn it_rejects_invalid_field_names() {
+        // Arrange
+        let fields = vec!["status')); DROP TABLE grant; --".to_string()];
+
+        // Act
+        let result = indexed_fields(Some(&fields));
+
+        // Assert
+        match result {
+            Err(ApiErrors::BadRequestJsonSimpleMsg(msg)) => {
+                assert_eq!(msg, "Invalid indexed field status')); DROP TABLE grant; --")
+            }
+            _ => panic!("Unexpected result {result:?}"),
+        }
+    }
+
+    #[test]
+    fn it_rejects_the_reserved_collection_names() {
+        for name in RESERVED_COLLECTION_NAMES {
+            // Act
+            let result = check_collection_name(name);
+
+            // Assert
+            assert!(
+                matches!(
+                    &result,
+                    Err(ApiErrors::BadRequestJsonSimpleMsg(msg)) if *msg == format!("Collection name {name} is reserved")
+                ),
+                "Unexpected result for {name}: {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn it_accepts_a_normal_collection_name() {
+        assert!(check_collection_name("shapes").is_ok());
+        assert!(check_collection_name("event-log").is_ok());
+    }
+}
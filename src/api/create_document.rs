@@ -1,12 +1,13 @@
 use axum::{
     extract::{Path, State},
     http::StatusCode,
-    Json,
+    response::Response,
 };
 use axum_macros::debug_handler;
 use jwt_authorizer::JwtClaims;
-use sea_orm::{DbErr, RuntimeErr, TransactionError, TransactionTrait};
+use sea_orm::{DatabaseTransaction, DbErr, RuntimeErr};
 use serde_json::json;
+use sqlx::postgres::PgDatabaseError;
 use std::sync::Arc;
 use tracing::{debug, error, warn};
 use uuid::Uuid;
@@ -14,14 +15,26 @@ use validator::Validate;
 
 use crate::api::{
     auth,
-    db::{get_collection_by_name, save_document_events_mails},
+    collection_config::CollectionConfig,
+    db::{
+        get_collection_by_name, get_document_by_id, get_document_by_id_in_trx,
+        save_document_events_mails, store_error, transaction_with_retry,
+    },
     dto::{self, GrantForDocument},
     hooks::{HookCreateContext, RequestContext},
-    ApiContext, ApiErrors,
+    idempotency::{claim_key, recorded_document, IdempotencyKey, KeyClaim},
+    lock_document::ensure_collection_unlocked,
+    read_projection::ReadProjection,
+    ApiContext, ApiErrors, Collection,
 };
+use crate::axumext::extractors::JsonBody;
 use crate::models::CollectionItem;
 
-use super::grants::default_document_grants;
+use super::document_version::without_version;
+use super::grants::{default_document_grants, inherited_document_grants, with_inherited_grants};
+use super::hook_summary::{created_response, AcceptDocument, HookSummary, PreferHookSummary};
+use super::parent_fields::inherit_parent_fields;
+use super::validation_webhook::{validate_with_webhook, WriteOperation};
 
 /// Create new item
 ///
@@ -40,12 +53,30 @@ use super::grants::default_document_grants;
             max_length = 32,
             pattern = r"^[a-z][-a-z0-9]*$",
         ),
+        (
+            "Prefer" = Option<String>,
+            Header,
+            description = "`hook-summary` returns a `HookSummary` with the events produced by hooks instead of the plain message",
+        ),
+        (
+            "Accept" = Option<String>,
+            Header,
+            description = "`application/json` without `text/plain` returns the stored `CollectionItem`, including the fields set by hooks, instead of the plain message",
+        ),
+        (
+            "Idempotency-Key" = Option<String>,
+            Header,
+            description = "Client generated key of 1 to 255 visible ASCII characters. A repeated request with the same key responds with the document of the first request instead of creating another one.",
+        ),
     ),
     responses(
-        (status = CREATED, description = "Document created successfully" ),
+        (status = CREATED, description = "Document created successfully, with `Prefer: hook-summary` a summary of the hook results, with `Accept: application/json` the stored `CollectionItem`", body = HookSummary ),
         (status = UNAUTHORIZED, description = "User is not a collection editor" ),
         (status = NOT_FOUND, description = "Collection not found" ),
+        (status = CONFLICT, description = "The document of the `Idempotency-Key` was deleted" ),
         (status = BAD_REQUEST, description = "Invalid request" ),
+        (status = LOCKED, description = "Collection is read only" ),
+        (status = TOO_MANY_REQUESTS, description = "Write rate limit of the collection exceeded" ),
         (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
     ),
     request_body(content = CollectionItem, description = "Create a new document", content_type = "application/json"),
@@ -55,17 +86,40 @@ pub(crate) async fn api_create_document(
     State(ctx): State<ApiContext>,
     JwtClaims(user): JwtClaims<auth::User>,
     Path(collection_name): Path<String>,
-    Json(payload): Json<CollectionItem>,
-) -> Result<(StatusCode, String), ApiErrors> {
+    prefer: PreferHookSummary,
+    accept: AcceptDocument,
+    idempotency_key: IdempotencyKey,
+    JsonBody(payload): JsonBody<CollectionItem>,
+) -> Result<Response, ApiErrors> {
     // Check if user is allowed to create a document within the collection
     if !user.is_collection_editor(&collection_name) {
         warn!("User {} is not a collection editor", user.name_and_sub());
         return Err(ApiErrors::PermissionDenied);
     }
 
-    // Validate the payload
-    payload.validate().map_err(ApiErrors::from)?;
+    store_new_document(
+        &ctx,
+        user,
+        collection_name,
+        prefer,
+        accept,
+        idempotency_key,
+        payload,
+    )
+    .await
+}
 
+/// Stores a new document of an editor of the collection, like it was sent to
+/// the create endpoint. A known idempotency key returns the original document.
+pub(crate) async fn store_new_document(
+    ctx: &ApiContext,
+    user: auth::User,
+    collection_name: String,
+    prefer: PreferHookSummary,
+    accept: AcceptDocument,
+    idempotency_key: IdempotencyKey,
+    payload: CollectionItem,
+) -> Result<Response, ApiErrors> {
     let collection = get_collection_by_name(&ctx.db, &collection_name).await;
 
     if collection.is_none() {
@@ -73,17 +127,155 @@ pub(crate) async fn api_create_document(
     }
     let collection = collection.unwrap();
 
-    // Check if collection is locked
-    if collection.locked {
-        warn!(
-            "User {} tried to add document to locked collection {}",
-            user.name_and_sub(),
-            collection_name
-        );
-        return Err(ApiErrors::BadRequestJsonSimpleMsg(
-            "Read only collection".into(),
-        ));
+    ensure_collection_unlocked(&user, &collection)?;
+
+    let owner = user.subuuid();
+    let projection = ReadProjection::new(&ctx.hooks, &collection, &user);
+    if let Some(key) = &idempotency_key.0 {
+        if let Some(original_id) = recorded_document(&ctx.db, owner, collection.id, key).await? {
+            debug!("Idempotency key {key} was used for document {original_id}");
+            let original = get_document_by_id(original_id, &ctx.db).await?;
+            let summary = HookSummary::new(original_id, &[]);
+            return replayed_item(original_id, original)
+                .map(|stored| create_response(prefer, accept, stored, summary, &projection));
+        }
+    }
+
+    let config = CollectionConfig::from(&collection);
+    ctx.write_rates.check(collection.id, &config, 1)?;
+    let new_document = prepare_new_document(ctx, &user, &collection, &config, payload).await?;
+    let document_id = *new_document.document.id();
+    let trigger_cron = new_document.trigger_cron;
+    let collection_id = collection.id;
+    let trigger_cron_ctx = ctx.clone();
+
+    let summary = HookSummary::new(document_id, &new_document.events);
+    transaction_with_retry(&ctx.db, move |txn| {
+        let idempotency_key = idempotency_key.clone();
+        let new_document = new_document.clone();
+        let config = config.clone();
+        let collection_name = collection_name.clone();
+        let user = user.clone();
+        let trigger_cron_ctx = trigger_cron_ctx.clone();
+        Box::pin(async move {
+            // A concurrent request with the same key may have stored its
+            // document since the key was looked up
+            if let Some(key) = idempotency_key.0 {
+                if let KeyClaim::Replay(original_id) =
+                    claim_key(txn, owner, collection_id, &key, document_id).await?
+                {
+                    debug!("Idempotency key {key} was used for document {original_id}");
+                    return replayed_document(txn, original_id).await;
+                }
+            }
+            let mut new_document = new_document;
+            complete_new_document(txn, &config, &mut new_document).await?;
+            let stored = CollectionItem::new(
+                *new_document.document.id(),
+                new_document.document.fields().clone(),
+            );
+            let dtouser = dto::User::read_from(&user);
+            save_document_events_mails(
+                txn,
+                &dtouser,
+                Some(new_document.document),
+                Some(crate::api::db::InsertDocumentData { collection_id }),
+                new_document.events,
+                crate::api::db::DbGrantUpdate::Replace(new_document.grants),
+                new_document.mails,
+            )
+            .await
+            .map_err(save_error)?;
+            debug!("Document {:?} saved to {collection_name}", document_id,);
+            trigger_cron_ctx
+                .trigger_cron_with_condition(trigger_cron)
+                .await;
+            Ok((
+                StatusCode::CREATED,
+                "Document saved".to_string(),
+                stored,
+                false,
+            ))
+        })
+    })
+    .await
+    .inspect(|_| ctx.trigger_event_relay())
+    .map(|stored| create_response(prefer, accept, stored, summary, &projection))
+}
+
+/// Responds to the create request with the fields of the stored document that
+/// the user can read.
+fn create_response(
+    prefer: PreferHookSummary,
+    accept: AcceptDocument,
+    (status, message, mut stored, replayed): (StatusCode, String, CollectionItem, bool),
+    summary: HookSummary,
+    projection: &ReadProjection,
+) -> Response {
+    projection.apply(stored.id, &mut stored.f);
+    // The hooks of a repeated request have no effect
+    let summary = if replayed {
+        HookSummary::new(stored.id, &[])
+    } else {
+        summary
+    };
+    created_response(prefer, accept, status, message, summary, stored)
+}
+
+/// Responds to a repeated create request like to the first one, with the
+/// current fields of the original document.
+async fn replayed_document(
+    txn: &DatabaseTransaction,
+    original_id: Uuid,
+) -> Result<(StatusCode, String, CollectionItem, bool), ApiErrors> {
+    let original = get_document_by_id_in_trx(original_id, txn).await?;
+    replayed_item(original_id, original)
+}
+
+/// The original document of the idempotency key cannot be returned once it
+/// was deleted, the repeated request is rejected with status 409 instead.
+fn replayed_item(
+    original_id: Uuid,
+    original: Option<entity::collection_document::Model>,
+) -> Result<(StatusCode, String, CollectionItem, bool), ApiErrors> {
+    match original {
+        Some(document) if !document.is_deleted() => Ok((
+            StatusCode::CREATED,
+            "Document saved".to_string(),
+            CollectionItem::new(original_id, document.f),
+            true,
+        )),
+        _ => Err(ApiErrors::Conflict(format!(
+            "Document {original_id} of the idempotency key no longer exists"
+        ))),
     }
+}
+
+/// A validated document with the events, grants and mails of the create hook
+#[derive(Clone)]
+pub(crate) struct NewDocument {
+    pub(crate) document: dto::CollectionDocument,
+    pub(crate) events: Vec<dto::Event>,
+    pub(crate) grants: Vec<GrantForDocument>,
+    pub(crate) mails: Vec<dto::MailMessage>,
+    pub(crate) trigger_cron: bool,
+}
+
+/// Validates the payload, runs the create hook of the collection and asks the
+/// validation webhook.
+pub(crate) async fn prepare_new_document(
+    ctx: &ApiContext,
+    user: &auth::User,
+    collection: &Collection,
+    config: &CollectionConfig,
+    mut payload: CollectionItem,
+) -> Result<NewDocument, ApiErrors> {
+    // Validate the payload
+    payload.validate().map_err(ApiErrors::from)?;
+    config.validate_document_id(&payload.id)?;
+    config.validate_required_fields(&payload.f)?;
+    config.validate_field_depth(&payload.f)?;
+    payload.f = without_version(payload.f);
 
     let collection_id = collection.id;
     let hook_processor = ctx.hooks.get_create_hook(&collection.name);
@@ -93,15 +285,15 @@ pub(crate) async fn api_create_document(
     let mut mails: Vec<dto::MailMessage> = vec![];
     let mut grants: Vec<GrantForDocument> = vec![];
     let mut trigger_cron = false;
-    let trigger_cron_ctx = ctx.clone();
     if let Some(ref hook) = hook_processor {
         let request_context = Arc::new(RequestContext::new(
             &collection.name,
             collection_id,
-            dto::UserWithRoles::read_from(&user),
+            dto::UserWithRoles::read_from(user),
         ));
 
-        let ctx = HookCreateContext::new((payload).into(), ctx.data_service, request_context);
+        let ctx =
+            HookCreateContext::new((payload).into(), ctx.data_service.clone(), request_context);
         let hook_result = hook.on_creating(&ctx).await?;
         trigger_cron = hook_result.trigger_cron;
         match hook_result.document {
@@ -141,51 +333,83 @@ pub(crate) async fn api_create_document(
         );
     };
 
-    ctx.db
-        .transaction::<_, (StatusCode, String), ApiErrors>(|txn| {
-            Box::pin(async move {
-                let dtouser = dto::User::read_from(&user);
-                save_document_events_mails(
-                    txn,
-                    &dtouser,
-                    Some(after_document),
-                    Some(crate::api::db::InsertDocumentData { collection_id }),
-                    events,
-                    crate::api::db::DbGrantUpdate::Replace(grants),
-                    mails,
-                )
-                .await
-                .map_err(|e| {
-                    error!("Create document error: {:?}", e);
-                    // Check if anyhow contains a DbErr
-                    let d = e.downcast_ref::<DbErr>().unwrap();
-                    debug!("DB error: {:?}", d);
-                    if let Some(DbErr::Query(RuntimeErr::SqlxError(sqlx::Error::Database(e)))) =
-                        e.downcast_ref::<DbErr>()
-                    {
-                        let code = e.code().unwrap_or_default().to_string();
-                        debug!("DB error code: {}", code);
-                        if code == "23505" {
-                            return ApiErrors::BadRequestJsonSimpleMsg(
-                                "Duplicate document".to_string(),
-                            );
-                        }
-                    }
-
-                    ApiErrors::InternalServerError
-                })?;
-                debug!("Document {:?} saved to {collection_name}", document_id,);
-                trigger_cron_ctx
-                    .trigger_cron_with_condition(trigger_cron)
-                    .await;
-                Ok((StatusCode::CREATED, "Document saved".to_string()))
-            })
-        })
-        .await
-        .map_err(|err| match err {
-            TransactionError::Connection(c) => Into::<ApiErrors>::into(c),
-            TransactionError::Transaction(t) => t,
-        })
+    // The webhook sees the document with the parent fields, which are copied
+    // again within the transaction
+    inherit_parent_fields(&ctx.db, config, &mut after_document).await?;
+    validate_with_webhook(
+        &ctx.webhook_client,
+        config,
+        &collection.name,
+        WriteOperation::Create,
+        &after_document,
+    )
+    .await?;
+
+    Ok(NewDocument {
+        document: after_document,
+        events,
+        grants,
+        mails,
+        trigger_cron,
+    })
+}
+
+/// Copies the parent fields and adds the inherited grants within the
+/// transaction that stores the document.
+pub(crate) async fn complete_new_document(
+    txn: &DatabaseTransaction,
+    config: &CollectionConfig,
+    new_document: &mut NewDocument,
+) -> Result<(), ApiErrors> {
+    inherit_parent_fields(txn, config, &mut new_document.document).await?;
+    let inherited_grants = inherited_document_grants(txn, config, &new_document.document).await?;
+    new_document.grants = with_inherited_grants(
+        std::mem::take(&mut new_document.grants),
+        inherited_grants,
+        *new_document.document.id(),
+    );
+    Ok(())
+}
+
+/// Maps the error of storing new documents, an existing id is a bad request.
+pub(crate) fn save_error(e: anyhow::Error) -> ApiErrors {
+    if unique_violation(&e).is_some() {
+        debug!("Duplicate document: {:?}", e);
+        return ApiErrors::BadRequestJsonSimpleMsg("Duplicate document".to_string());
+    }
+    store_error(e)
+}
+
+/// Returns the id of the document that already exists, if storing new
+/// documents failed because of it.
+pub(crate) fn duplicate_document_id(e: &anyhow::Error) -> Option<Uuid> {
+    unique_violation(e)?
+        .try_downcast_ref::<PgDatabaseError>()?
+        .detail()
+        .and_then(duplicate_key)
+}
+
+fn unique_violation(e: &anyhow::Error) -> Option<&(dyn sqlx::error::DatabaseError + 'static)> {
+    match e.downcast_ref::<DbErr>()? {
+        DbErr::Exec(RuntimeErr::SqlxError(sqlx::Error::Database(e)))
+        | DbErr::Query(RuntimeErr::SqlxError(sqlx::Error::Database(e)))
+            if e.code().is_some_and(|c| c == UNIQUE_VIOLATION) =>
+        {
+            Some(e.as_ref())
+        }
+        _ => None,
+    }
+}
+
+const UNIQUE_VIOLATION: &str = "23505";
+
+/// Reads the id of the detail message of a unique violation, e. g.
+/// `Key (id)=(9f818bff-a1b4-487a-9706-29a5ac1cf898) already exists.`
+fn duplicate_key(detail: &str) -> Option<Uuid> {
+    detail
+        .strip_prefix("Key (id)=(")?
+        .split_once(')')
+        .and_then(|(id, _)| id.parse().ok())
 }
 
 pub(crate) fn create_document_event(document_id: Uuid, user: &dto::User) -> dto::Event {
@@ -205,3 +429,110 @@ pub(crate) fn create_document_event(document_id: Uuid, user: &dto::User) -> dto:
         }),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use axum::body::to_bytes;
+    use pretty_assertions::assert_eq;
+    use serde_json::Value;
+
+    use super::*;
+    use crate::api::read_projection;
+
+    #[tokio::test]
+    async fn it_responds_with_the_readable_fields_of_the_stored_document() {
+        // Arrange
+        let stored = CollectionItem::new(
+            Uuid::new_v4(),
+            json!({"title": "Application", "salary": 52000}),
+        );
+        let summary = HookSummary::new(stored.id, &[]);
+
+        // Act
+        let response = create_response(
+            PreferHookSummary(false),
+            AcceptDocument(true),
+            (
+                StatusCode::CREATED,
+                "Document saved".to_string(),
+                stored,
+                false,
+            ),
+            summary,
+            &read_projection::redacting(&["salary"]),
+        );
+
+        // Assert
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let item: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(item["f"], json!({"title": "Application"}));
+    }
+
+    fn original(f: Value) -> entity::collection_document::Model {
+        entity::collection_document::Model {
+            id: Uuid::new_v4(),
+            collection_id: Uuid::new_v4(),
+            owner: Uuid::new_v4(),
+            f,
+            seq: 1,
+            locked: false,
+        }
+    }
+
+    #[test]
+    fn it_replays_the_original_document() {
+        // Arrange
+        let document = original(json!({"title": "Application"}));
+        let original_id = document.id;
+
+        // Act
+        let replayed = replayed_item(original_id, Some(document));
+
+        // Assert
+        assert!(matches!(
+            replayed,
+            Ok((StatusCode::CREATED, _, item, true))
+                if item.id == original_id && item.f == json!({"title": "Application"})
+        ));
+    }
+
+    #[test]
+    fn it_rejects_the_replay_of_a_vanished_document() {
+        // Arrange
+        let deleted = original(
+            json!({"title": "Application", "folivafy_deleted_at": "2024-02-01T00:00:00Z"}),
+        );
+        let deleted_id = deleted.id;
+
+        // Act
+        let removed = replayed_item(Uuid::new_v4(), None);
+        let deleted = replayed_item(deleted_id, Some(deleted));
+
+        // Assert
+        assert!(matches!(removed, Err(ApiErrors::Conflict(_))));
+        assert!(matches!(deleted, Err(ApiErrors::Conflict(_))));
+    }
+
+    #[test]
+    fn it_maps_other_errors_to_an_internal_error() {
+        // Act
+        let error = save_error(anyhow::anyhow!("Mail queue is full"));
+
+        // Assert
+        assert!(matches!(error, ApiErrors::InternalServerError));
+    }
+
+    #[test]
+    fn it_reads_the_id_of_the_duplicate_document() {
+        // Act
+        let id = duplicate_key("Key (id)=(9f818bff-a1b4-487a-9706-29a5ac1cf898) already exists.");
+
+        // Assert
+        assert_eq!(
+            id,
+            Some(Uuid::parse_str("9f818bff-a1b4-487a-9706-29a5ac1cf898").unwrap())
+        );
+        assert_eq!(duplicate_key("Key (name)=(shapes) already exists."), None);
+    }
+}
@@ -0,0 +1,259 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use axum_macros::debug_handler;
+use jwt_authorizer::JwtClaims;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+use crate::api::{
+    auth,
+    collection_config::CollectionConfig,
+    db::{
+        get_collection_by_name, save_documents_events_mails, transaction_with_retry, DbGrantUpdate,
+    },
+    dto,
+    hooks::{
+        HookRejection, StoreDocument, StoreNewDocument, StoreNewDocumentCollection,
+        StoreNewDocumentOwner,
+    },
+    lock_document::ensure_collection_unlocked,
+    ApiContext, ApiErrors,
+};
+use crate::axumext::extractors::JsonBody;
+use crate::models::CollectionItem;
+
+use super::create_document::{
+    complete_new_document, duplicate_document_id, prepare_new_document, save_error,
+};
+
+/// Maximum number of items in one batch
+pub(crate) const MAX_BATCH_ITEMS: usize = 1000;
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+#[schema(examples(json!({"documents": 100})))]
+pub(crate) struct BatchStoreResult {
+    /// Number of stored documents
+    documents: usize,
+}
+
+/// Create many items
+///
+/// Create up to 1000 new items in this collection within one transaction.
+/// Every item is processed like a single new item, including hooks and
+/// creation events. If one item is rejected, no item is stored and the
+/// response contains the `index` of the first rejected item.
+#[debug_handler]
+#[utoipa::path(
+    post,
+    path = "/collections/{collection_name}/batch",
+    operation_id = "storeManyIntoCollection",
+    params(
+        (
+            "collection_name" = String,
+            Path,
+            description = "Name of the collection",
+            min_length = 1,
+            max_length = 32,
+            pattern = r"^[a-z][-a-z0-9]*$",
+        ),
+    ),
+    responses(
+        (status = CREATED, description = "Documents created successfully", body = BatchStoreResult ),
+        (status = UNAUTHORIZED, description = "User is not a collection editor" ),
+        (status = NOT_FOUND, description = "Collection not found" ),
+        (status = BAD_REQUEST, description = "Invalid request, e. g. `{\"index\": 3, \"message\": \"Duplicate document\"}`" ),
+        (status = LOCKED, description = "Collection is read only" ),
+        (status = TOO_MANY_REQUESTS, description = "Write rate limit of the collection exceeded" ),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
+    ),
+    request_body(content = Vec<CollectionItem>, description = "Create new documents", content_type = "application/json"),
+    tag = super::TAG_COLLECTION,
+)]
+pub(crate) async fn api_create_documents(
+    State(ctx): State<ApiContext>,
+    JwtClaims(user): JwtClaims<auth::User>,
+    Path(collection_name): Path<String>,
+    JsonBody(payload): JsonBody<Vec<CollectionItem>>,
+) -> Result<(StatusCode, Json<BatchStoreResult>), ApiErrors> {
+    // Check if user is allowed to create documents within the collection
+    if !user.is_collection_editor(&collection_name) {
+        warn!("User {} is not a collection editor", user.name_and_sub());
+        return Err(ApiErrors::PermissionDenied);
+    }
+
+    if payload.is_empty() || payload.len() > MAX_BATCH_ITEMS {
+        return Err(ApiErrors::BadRequestJsonSimpleMsg(format!(
+            "A batch must contain 1 to {MAX_BATCH_ITEMS} items"
+        )));
+    }
+    if let Some(index) = first_duplicate_id(&payload) {
+        return Err(item_error(
+            index,
+            ApiErrors::BadRequestJsonSimpleMsg("Duplicate document".to_string()),
+        ));
+    }
+
+    let collection = get_collection_by_name(&ctx.db, &collection_name)
+        .await
+        .ok_or_else(|| ApiErrors::NotFound(collection_name.clone()))?;
+
+    ensure_collection_unlocked(&user, &collection)?;
+
+    let config = CollectionConfig::from(&collection);
+    ctx.write_rates
+        .check(collection.id, &config, payload.len())?;
+    let mut new_documents = Vec::with_capacity(payload.len());
+    for (index, item) in payload.into_iter().enumerate() {
+        new_documents.push(
+            prepare_new_document(&ctx, &user, &collection, &config, item)
+                .await
+                .map_err(|e| item_error(index, e))?,
+        );
+    }
+    let trigger_cron = new_documents.iter().any(|d| d.trigger_cron);
+    let collection_id = collection.id;
+    let trigger_cron_ctx = ctx.clone();
+
+    transaction_with_retry(&ctx.db, move |txn| {
+        let new_documents = new_documents.clone();
+        let config = config.clone();
+        let collection_name = collection_name.clone();
+        let user = user.clone();
+        let trigger_cron_ctx = trigger_cron_ctx.clone();
+        Box::pin(async move {
+            let dtouser = dto::User::read_from(&user);
+            let count = new_documents.len();
+            let mut documents = Vec::with_capacity(count);
+            let mut events = vec![];
+            let mut grants = vec![];
+            let mut mails = vec![];
+            let ids: Vec<Uuid> = new_documents.iter().map(|d| *d.document.id()).collect();
+            for (index, mut new_document) in new_documents.into_iter().enumerate() {
+                complete_new_document(txn, &config, &mut new_document)
+                    .await
+                    .map_err(|e| item_error(index, e))?;
+                documents.push(StoreDocument::as_new(StoreNewDocument {
+                    owner: StoreNewDocumentOwner::User(dtouser.clone()),
+                    collection: StoreNewDocumentCollection::Id(collection_id),
+                    document: new_document.document,
+                }));
+                events.extend(new_document.events);
+                grants.extend(new_document.grants);
+                mails.extend(new_document.mails);
+            }
+            save_documents_events_mails(
+                txn,
+                &dtouser,
+                documents,
+                events,
+                DbGrantUpdate::Replace(grants),
+                mails,
+            )
+            .await
+            .map_err(|e| {
+                match duplicate_document_id(&e).and_then(|id| ids.iter().position(|i| *i == id)) {
+                    Some(index) => item_error(index, save_error(e)),
+                    None => save_error(e),
+                }
+            })?;
+            debug!("{count} documents saved to {collection_name}");
+            trigger_cron_ctx
+                .trigger_cron_with_condition(trigger_cron)
+                .await;
+            Ok(count)
+        })
+    })
+    .await
+    .inspect(|_| ctx.trigger_event_relay())
+    .map(|documents| (StatusCode::CREATED, Json(BatchStoreResult { documents })))
+}
+
+/// Returns the index of the first item that repeats the id of a previous item.
+fn first_duplicate_id(items: &[CollectionItem]) -> Option<usize> {
+    let mut ids = HashSet::with_capacity(items.len());
+    items.iter().position(|item| !ids.insert(item.id))
+}
+
+/// Adds the index of the rejected item to the error.
+pub(crate) fn item_error(index: usize, err: ApiErrors) -> ApiErrors {
+    match err {
+        ApiErrors::BadRequestJsonSimpleMsg(message) => {
+            ApiErrors::BadRequestJson(json!({ "index": index, "message": message }))
+        }
+        ApiErrors::BadRequestJsonMsg(errors) => ApiErrors::BadRequestJson(json!({
+            "index": index,
+            "errors": serde_json::from_str::<Value>(&errors).unwrap_or(Value::String(errors)),
+        })),
+        ApiErrors::BadRequestJson(errors) => {
+            ApiErrors::BadRequestJson(json!({ "index": index, "errors": errors }))
+        }
+        ApiErrors::HookRejection(rejection) => HookRejection::new(
+            rejection.status(),
+            &format!("Item {index}: {}", rejection.message()),
+        )
+        .into(),
+        err => err,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn it_finds_the_first_duplicate_id() {
+        // Arrange
+        let id = Uuid::new_v4();
+        let items = vec![
+            CollectionItem::new(id, json!({})),
+            CollectionItem::new(Uuid::new_v4(), json!({})),
+            CollectionItem::new(id, json!({})),
+        ];
+
+        // Act
+        let index = first_duplicate_id(&items);
+
+        // Assert
+        assert_eq!(index, Some(2));
+        assert_eq!(first_duplicate_id(&items[..2]), None);
+    }
+
+    #[test]
+    fn it_reports_the_index_of_the_rejected_item() {
+        // Arrange
+        let validation_errors = json!({"id": [{"code": "nil"}]});
+
+        // Act
+        let simple = item_error(
+            4,
+            ApiErrors::BadRequestJsonSimpleMsg("Not accepted for storage".to_string()),
+        );
+        let validation = item_error(
+            7,
+            ApiErrors::BadRequestJsonMsg(validation_errors.to_string()),
+        );
+        let hook = item_error(9, HookRejection::validation("Missing VAT id").into());
+
+        // Assert
+        assert!(matches!(
+            simple,
+            ApiErrors::BadRequestJson(v) if v == json!({"index": 4, "message": "Not accepted for storage"})
+        ));
+        assert!(matches!(
+            validation,
+            ApiErrors::BadRequestJson(v) if v == json!({"index": 7, "errors": validation_errors})
+        ));
+        assert!(matches!(
+            hook,
+            ApiErrors::HookRejection(r) if r.status() == StatusCode::UNPROCESSABLE_ENTITY && r.message() == "Item 9: Missing VAT id"
+        ));
+    }
+}
@@ -1,20 +1,37 @@
 use axum::{extract::State, http::StatusCode, Json};
 use axum_macros::debug_handler;
 use jwt_authorizer::JwtClaims;
-use sea_orm::{TransactionError, TransactionTrait};
+use sea_orm::{prelude::Uuid, DatabaseTransaction};
+use serde::Serialize;
 use std::sync::Arc;
-use tracing::{debug, error, warn};
+use tracing::{debug, error, warn, Instrument};
 use validator::Validate;
 
 use crate::api::{
     auth,
-    db::{get_collection_by_name, save_documents_events_mails, DbGrantUpdate},
-    dto::{self, Event},
-    hooks::{DocumentResult, HookCreatedEventContext, HookCreatingEventContext, RequestContext},
+    collection_config::CollectionConfig,
+    data_service::{DataService, FolivafyDataService},
+    db::{
+        get_collection_by_name, save_documents_events_mails, store_error, transaction_with_retry,
+        DbGrantUpdate,
+    },
+    dto::{self, Event, ExistingEvent, GrantForDocument},
+    grants::{
+        hook_or_default_document_grants, inherited_document_grants, with_inherited_grants,
+        GrantCollection,
+    },
+    hooks::{
+        DocumentResult, EventCreatingHook, HookCreatedEventContext, HookCreatingEventContext,
+        Hooks, RequestContext, StoreDocument,
+    },
+    lock_document::{ensure_collection_unlocked, ensure_unlocked},
     select_document_for_update, ApiContext, ApiErrors,
 };
+use crate::axumext::extractors::JsonBody;
 use crate::models::CreateEventBody;
 
+use super::create_documents::{item_error, MAX_BATCH_ITEMS};
+
 /// Create a new event.
 ///
 /// Create an event for the given document in a given collection. The collection must not be locked.
@@ -25,6 +42,12 @@ use crate::models::CreateEventBody;
 ///
 /// * `C_COLLECTIONNAME_READER`
 /// * `C_COLLECTIONNAME_ALLREADER`
+///
+/// ### Deduplication
+///
+/// If the collection configuration lists the category in `deduplicateEvents`,
+/// an event that equals the latest event of the same category of the document
+/// is not recorded. If no event remains, the response has status 200.
 #[debug_handler]
 #[utoipa::path(
     post,
@@ -32,9 +55,11 @@ use crate::models::CreateEventBody;
     operation_id = "createEvent",
     responses(
         (status = CREATED, description = "Event created successfully" ),
+        (status = OK, description = "Event equals the latest event and was not recorded" ),
         (status = UNAUTHORIZED, description = "User is not a collection reader" ),
         (status = NOT_FOUND, description = "Document not found" ),
         (status = BAD_REQUEST, description = "Invalid request" ),
+        (status = LOCKED, description = "Document or collection is locked" ),
         (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
     ),
     request_body(content = CreateEventBody, description = "Create a new event", content_type = "application/json"),
@@ -43,26 +68,208 @@ use crate::models::CreateEventBody;
 pub(crate) async fn api_create_event(
     State(ctx): State<ApiContext>,
     JwtClaims(user): JwtClaims<auth::User>,
-    Json(payload): Json<CreateEventBody>,
+    JsonBody(payload): JsonBody<CreateEventBody>,
 ) -> Result<(StatusCode, String), ApiErrors> {
-    let post_payload = payload.clone();
+    create_event(ctx, user, payload).await
+}
+
+/// Create many events
+///
+/// Create up to 1000 events for documents of one or more collections within
+/// one transaction. Every event is processed like a single new event, in the
+/// order of the request, including the event hooks. If one event is rejected,
+/// no event is stored and the response contains the `index` of the first
+/// rejected event.
+///
+/// ### Required permissions
+///
+/// The user must be permitted to create every single event.
+#[debug_handler]
+#[utoipa::path(
+    post,
+    path = "/events/batch",
+    operation_id = "createEvents",
+    responses(
+        (status = CREATED, description = "Events created successfully", body = BatchEventsResult ),
+        (status = UNAUTHORIZED, description = "User is not a collection reader" ),
+        (status = NOT_FOUND, description = "Document not found" ),
+        (status = BAD_REQUEST, description = "Invalid request, e. g. `{\"index\": 3, \"message\": \"Event not accepted\"}`" ),
+        (status = LOCKED, description = "Document or collection is locked" ),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
+    ),
+    request_body(content = Vec<CreateEventBody>, description = "Create new events", content_type = "application/json"),
+    tag = super::TAG_EVENT,
+)]
+pub(crate) async fn api_create_events(
+    State(ctx): State<ApiContext>,
+    JwtClaims(user): JwtClaims<auth::User>,
+    JsonBody(payload): JsonBody<Vec<CreateEventBody>>,
+) -> Result<(StatusCode, Json<BatchEventsResult>), ApiErrors> {
+    if payload.is_empty() || payload.len() > MAX_BATCH_ITEMS {
+        return Err(ApiErrors::BadRequestJsonSimpleMsg(format!(
+            "A batch must contain 1 to {MAX_BATCH_ITEMS} events"
+        )));
+    }
+
+    let mut prepared_events = Vec::with_capacity(payload.len());
+    for (index, event) in payload.into_iter().enumerate() {
+        prepared_events.push(
+            prepare_event(&ctx, &user, event)
+                .await
+                .map_err(|e| item_error(index, e))?,
+        );
+    }
+
+    let txn_ctx = ctx.clone();
+    let outcomes = transaction_with_retry(&ctx.db, move |txn| {
+        let prepared_events = prepared_events.clone();
+        let txn_ctx = txn_ctx.clone();
+        let user = user.clone();
+        Box::pin(async move {
+            let mut outcomes = Vec::with_capacity(prepared_events.len());
+            let mut stored_in_transaction = vec![];
+            for (index, event) in prepared_events.into_iter().enumerate() {
+                let stored = store_event(txn, &txn_ctx, &user, &event, &mut stored_in_transaction)
+                    .await
+                    .map_err(|e| item_error(index, e))?;
+                outcomes.push((event, stored));
+            }
+            Ok(outcomes)
+        })
+    })
+    .await?;
+
+    let result = BatchEventsResult::from(&outcomes);
+    debug!(
+        "{} events created, {} duplicates ignored",
+        result.events, result.duplicates
+    );
+    ctx.trigger_event_relay();
+    finish_stored_events(&ctx, outcomes).await;
+    Ok((StatusCode::CREATED, Json(result)))
+}
 
+#[derive(Debug, Default, Serialize, utoipa::ToSchema)]
+#[schema(examples(json!({"events": 12, "duplicates": 1})))]
+pub(crate) struct BatchEventsResult {
+    /// Number of requests whose events were stored
+    events: usize,
+
+    /// Number of requests that equal the latest event and were not recorded
+    duplicates: usize,
+}
+
+impl<T> From<&Vec<(T, StoredEvent)>> for BatchEventsResult {
+    fn from(outcomes: &Vec<(T, StoredEvent)>) -> Self {
+        let duplicates = outcomes
+            .iter()
+            .filter(|(_, stored)| matches!(stored, StoredEvent::Duplicate))
+            .count();
+        Self {
+            events: outcomes.len() - duplicates,
+            duplicates,
+        }
+    }
+}
+
+/// Passes the event to the event hook of the collection and stores the
+/// result.
+pub(crate) async fn create_event(
+    ctx: ApiContext,
+    user: auth::User,
+    payload: CreateEventBody,
+) -> Result<(StatusCode, String), ApiErrors> {
+    let event = prepare_event(&ctx, &user, payload).await?;
+
+    let txn_ctx = ctx.clone();
+    let (event, stored) = transaction_with_retry(&ctx.db, move |txn| {
+        let event = event.clone();
+        let txn_ctx = txn_ctx.clone();
+        let user = user.clone();
+        Box::pin(async move {
+            let stored = store_event(txn, &txn_ctx, &user, &event, &mut vec![]).await?;
+            Ok((event, stored))
+        })
+    })
+    .await?;
+
+    ctx.trigger_event_relay();
+    let response = match stored {
+        StoredEvent::Created { .. } => (StatusCode::CREATED, "Done".to_string()),
+        StoredEvent::Duplicate => (StatusCode::OK, "Duplicate event ignored".to_string()),
+    };
+    finish_stored_events(&ctx, vec![(event, stored)]).await;
+    Ok(response)
+}
+
+/// Triggers the cron and runs the `on_created` hooks of the stored events.
+/// Duplicates were not recorded and have no effect.
+async fn finish_stored_events(ctx: &ApiContext, outcomes: Vec<(PreparedEvent, StoredEvent)>) {
+    let (stored_events, trigger_cron) = stored_events(outcomes);
+    ctx.trigger_cron_with_condition(trigger_cron).await;
+    for event in stored_events {
+        run_created_hook(ctx, event);
+    }
+}
+
+/// Returns the stored events and whether one of them triggers the cron.
+fn stored_events<T>(outcomes: Vec<(T, StoredEvent)>) -> (Vec<T>, bool) {
+    let mut trigger_cron = false;
+    let events = outcomes
+        .into_iter()
+        .filter_map(|(event, stored)| match stored {
+            StoredEvent::Created {
+                trigger_cron: event_trigger_cron,
+            } => {
+                trigger_cron |= event_trigger_cron;
+                Some(event)
+            }
+            StoredEvent::Duplicate => None,
+        })
+        .collect();
+    (events, trigger_cron)
+}
+
+/// An event of the request whose collection, permissions and event hook are
+/// checked.
+#[derive(Clone)]
+struct PreparedEvent {
+    payload: CreateEventBody,
+    collection: entity::collection::Model,
+    config: CollectionConfig,
+    hook: Arc<dyn EventCreatingHook + Send + Sync>,
+    request_context: Arc<RequestContext>,
+}
+
+/// Outcome of storing an event
+enum StoredEvent {
+    /// The events of the hook were stored
+    Created { trigger_cron: bool },
+    /// The event equals the latest event and was not recorded
+    Duplicate,
+}
+
+/// Checks the collection of the event, the permissions of the user, the
+/// payload schema of the category and that the collection has an event hook
+/// for the category.
+async fn prepare_event(
+    ctx: &ApiContext,
+    user: &auth::User,
+    payload: CreateEventBody,
+) -> Result<PreparedEvent, ApiErrors> {
     // Validate the payload
     payload.validate().map_err(ApiErrors::from)?;
-    let unchecked_collection_name = payload.collection;
-    let unchecked_document_id = payload.document;
+    let unchecked_collection_name = &payload.collection;
 
-    let trigger_cron_ctx = ctx.clone();
-    let trigger_cron_post_ctx = ctx.clone();
-    let collection = get_collection_by_name(&ctx.db, &unchecked_collection_name).await;
+    let collection = get_collection_by_name(&ctx.db, unchecked_collection_name).await;
     if collection.is_none() {
         debug!("Collection {} not found", unchecked_collection_name);
         return Err(ApiErrors::PermissionDenied);
     }
     let collection_name = unchecked_collection_name;
 
-    if !(user.is_collection_reader(&collection_name)
-        || user.can_access_all_documents(&collection_name))
+    if !(user.is_collection_reader(collection_name)
+        || user.can_access_all_documents(collection_name))
     {
         debug!(
             "User {} is not allowed to read documents in collection {collection_name}",
@@ -72,17 +279,9 @@ pub(crate) async fn api_create_event(
     }
 
     let collection = collection.unwrap();
-    // Check if collection is locked
-    if collection.locked {
-        warn!(
-            "User {} tried to add events to document in locked collection {}",
-            user.name_and_sub(),
-            collection_name
-        );
-        return Err(ApiErrors::BadRequestJsonSimpleMsg(
-            "Read only collection".into(),
-        ));
-    }
+    ensure_collection_unlocked(user, &collection)?;
+    let config = CollectionConfig::from(&collection);
+    config.validate_event_payload(payload.category, &payload.e)?;
     let hook = ctx.hooks.get_event_hook(&collection.name, payload.category);
 
     if hook.is_none() {
@@ -91,99 +290,371 @@ pub(crate) async fn api_create_event(
             "Event not accepted".to_string(),
         ));
     }
-    let hook = hook.unwrap();
-    let post_hook = hook.clone();
-
-    let data_service1 = ctx.data_service.clone();
-    let data_service2 = ctx.data_service.clone();
-
-    let request_context1 = Arc::new(RequestContext::new(
-        &collection.name,
-        collection.id,
-        dto::UserWithRoles::read_from(&user),
-    ));
-    let request_context2 = request_context1.clone();
-
-    ctx.db
-        .transaction::<_, (StatusCode, String), ApiErrors>(|txn| {
-            Box::pin(async move {
-                let document = select_document_for_update(unchecked_document_id, txn).await?;
-                if document.is_none() {
-                    debug!("Document {} not found", unchecked_document_id);
-                    return Err(ApiErrors::PermissionDenied);
-                }
-                let document = document.unwrap();
-                let before_document: dto::CollectionDocument = (&document).into();
-                let after_document: dto::CollectionDocument = (&document).into();
-
-                let cdctx = HookCreatingEventContext::new(
-                    Event::new(document.id, payload.category, payload.e.clone()),
-                    after_document,
-                    before_document,
-                    data_service1,
-                    request_context1,
-                );
-
-                let result = hook.on_creating(&cdctx).await?;
-                let events = result.events;
-                let mails = result.mails;
-                if events.is_empty() {
-                    debug!("No events were permitted");
-                    return Err(ApiErrors::PermissionDenied);
-                }
-                let grants = match result.grants {
-                    crate::api::hooks::GrantSettingsOnEvents::NoChange => DbGrantUpdate::Keep,
-                    crate::api::hooks::GrantSettingsOnEvents::Replace(new_grants) => {
-                        DbGrantUpdate::Replace(new_grants)
+
+    Ok(PreparedEvent {
+        config,
+        hook: hook.unwrap(),
+        request_context: Arc::new(RequestContext::new(
+            &collection.name,
+            collection.id,
+            dto::UserWithRoles::read_from(user),
+        )),
+        collection,
+        payload,
+    })
+}
+
+/// Passes the event to the event hook and stores the events, documents,
+/// grants and mails of the hook result. The stored events are appended to
+/// `stored_in_transaction`, which holds the events that were stored earlier
+/// in the same transaction and are not yet visible to the data service.
+async fn store_event(
+    txn: &DatabaseTransaction,
+    ctx: &ApiContext,
+    user: &auth::User,
+    event: &PreparedEvent,
+    stored_in_transaction: &mut Vec<Event>,
+) -> Result<StoredEvent, ApiErrors> {
+    let payload = &event.payload;
+    let config = &event.config;
+    let unchecked_document_id = payload.document;
+    let document = select_document_for_update(unchecked_document_id, txn).await?;
+    if document.is_none() {
+        debug!("Document {} not found", unchecked_document_id);
+        return Err(ApiErrors::PermissionDenied);
+    }
+    let document = document.unwrap();
+    ensure_unlocked(&document)?;
+    let before_document: dto::CollectionDocument = (&document).into();
+    let after_document: dto::CollectionDocument = (&document).into();
+
+    let cdctx = HookCreatingEventContext::new(
+        Event::new(document.id, payload.category, payload.e.clone()),
+        after_document,
+        before_document,
+        ctx.data_service.clone(),
+        event.request_context.clone(),
+    );
+
+    let result = event.hook.on_creating(&cdctx).await?;
+    let events = result.events;
+    let mails = result.mails;
+    if events.is_empty() {
+        debug!("No events were permitted");
+        return Err(ApiErrors::PermissionDenied);
+    }
+    let events = if events
+        .iter()
+        .any(|event| config.deduplicates_events(event.category()))
+    {
+        let existing_events = ctx
+            .data_service
+            .get_document_events(document.id)
+            .await
+            .map_err(|e| {
+                error!("Error while loading events: {:?}", e);
+                ApiErrors::InternalServerError
+            })?;
+        without_duplicate_events(config, events, &existing_events, stored_in_transaction)
+    } else {
+        events
+    };
+    if events.is_empty() {
+        debug!("Event equals the latest event of document {}", document.id);
+        return Ok(StoredEvent::Duplicate);
+    }
+    let grants = match result.grants {
+        crate::api::hooks::GrantSettingsOnEvents::NoChange => DbGrantUpdate::Keep,
+        crate::api::hooks::GrantSettingsOnEvents::Replace(new_grants) => {
+            DbGrantUpdate::Replace(new_grants)
+        }
+        crate::api::hooks::GrantSettingsOnEvents::Default => {
+            let stored_document = result
+                .documents
+                .iter()
+                .find_map(|stored| match stored {
+                    StoreDocument::Update { document: d } if *d.id() == document.id => {
+                        Some(d.clone())
                     }
-                };
+                    _ => None,
+                })
+                .unwrap_or_else(|| (&document).into());
+            DbGrantUpdate::Replace(
+                document_grants(
+                    txn,
+                    &ctx.hooks,
+                    (&event.collection).into(),
+                    config,
+                    stored_document,
+                    ctx.data_service.clone(),
+                    document.owner,
+                )
+                .await?,
+            )
+        }
+    };
 
-                let dtouser = dto::User::read_from(&user);
-                save_documents_events_mails(txn, &dtouser, result.documents, events, grants, mails)
-                    .await
-                    .map_err(|e| {
-                        error!("Error while creating event: {:?}", e);
-                        ApiErrors::InternalServerError
-                    })?;
-
-                trigger_cron_ctx
-                    .trigger_cron_with_condition(result.trigger_cron)
-                    .await;
-                Ok((StatusCode::CREATED, "Done".to_string()))
-            })
-        })
+    let dtouser = dto::User::read_from(user);
+    stored_in_transaction.extend(events.iter().cloned());
+    save_documents_events_mails(txn, &dtouser, result.documents, events, grants, mails)
         .await
-        .map_err(|err| match err {
-            TransactionError::Connection(c) => Into::<ApiErrors>::into(c),
-            TransactionError::Transaction(t) => t,
-        })
-        .inspect(|_res| {
-            // Start thread for background task
-            tokio::spawn(async move {
-                let cdctx = HookCreatedEventContext::new(
-                    Event::new(
-                        unchecked_document_id,
-                        post_payload.category,
-                        post_payload.e.clone(),
-                    ),
-                    data_service2,
-                    request_context2,
-                );
-
-                let post_result = post_hook.on_created(&cdctx).await;
-                if let Ok(r) = post_result {
-                    match r.document {
-                        DocumentResult::Store(_) => todo!("Document update not implemented!"),
-                        DocumentResult::NoUpdate => {}
-                        DocumentResult::Err(_) => todo!("Document update not implemented!"),
+        .map_err(store_error)?;
+
+    Ok(StoredEvent::Created {
+        trigger_cron: result.trigger_cron,
+    })
+}
+
+/// Passes the stored event to the event hook in a background task.
+fn run_created_hook(ctx: &ApiContext, event: PreparedEvent) {
+    let ctx = ctx.clone();
+    tokio::spawn(
+        async move {
+            let cdctx = HookCreatedEventContext::new(
+                Event::new(
+                    event.payload.document,
+                    event.payload.category,
+                    event.payload.e.clone(),
+                ),
+                ctx.data_service.clone(),
+                event.request_context,
+            );
+
+            // The response is already sent, a document or a rejection returned
+            // by the hook cannot be applied anymore.
+            let post_result = event.hook.on_created(&cdctx).await;
+            if let Ok(r) = post_result {
+                match r.document {
+                    DocumentResult::Store(document) => {
+                        warn!(
+                            "Created event hook returned document {}, which is not stored",
+                            document.id()
+                        );
                     }
-                    if !r.events.is_empty() {
-                        error!("Not implemented");
+                    DocumentResult::NoUpdate => {}
+                    DocumentResult::Err(err) => {
+                        warn!("Created event hook returned an error after the response: {err}");
                     }
-                    trigger_cron_post_ctx
-                        .trigger_cron_with_condition(r.trigger_cron)
-                        .await;
                 }
-            });
+                if !r.events.is_empty() {
+                    error!("Not implemented");
+                }
+                ctx.trigger_cron_with_condition(r.trigger_cron).await;
+            }
+        }
+        .in_current_span(),
+    );
+}
+
+/// Runs the grant hook for the document, including the grants inherited from
+/// the parent document.
+async fn document_grants(
+    txn: &DatabaseTransaction,
+    hooks: &Hooks,
+    collection: GrantCollection,
+    config: &CollectionConfig,
+    document: dto::CollectionDocument,
+    data_service: Arc<FolivafyDataService>,
+    author_id: Uuid,
+) -> Result<Vec<GrantForDocument>, ApiErrors> {
+    let document_id = *document.id();
+    let inherited_grants = inherited_document_grants(txn, config, &document).await?;
+    let grants =
+        hook_or_default_document_grants(hooks, collection, document, data_service, author_id)
+            .await?;
+    Ok(with_inherited_grants(
+        grants
+            .into_iter()
+            .map(|grant| GrantForDocument::new(grant, document_id))
+            .collect(),
+        inherited_grants,
+        document_id,
+    ))
+}
+
+/// Removes the events that equal the latest existing event of the same
+/// document and category, if the category is deduplicated. The events stored
+/// earlier in the transaction, oldest first, are newer than the existing
+/// events.
+fn without_duplicate_events(
+    config: &CollectionConfig,
+    events: Vec<Event>,
+    existing_events_newest_first: &[ExistingEvent],
+    stored_in_transaction: &[Event],
+) -> Vec<Event> {
+    events
+        .into_iter()
+        .filter(|event| {
+            if !config.deduplicates_events(event.category()) {
+                return true;
+            }
+            let is_same = |document_id: Uuid, category: i32| {
+                document_id == event.document_id() && category == event.category()
+            };
+            let latest = stored_in_transaction
+                .iter()
+                .rev()
+                .find(|stored| is_same(stored.document_id(), stored.category()))
+                .map(|stored| stored.payload())
+                .or_else(|| {
+                    existing_events_newest_first
+                        .iter()
+                        .find(|existing| is_same(existing.document_id(), existing.category()))
+                        .map(|existing| existing.payload())
+                });
+            latest.is_none_or(|latest| latest != event.payload())
         })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    use super::*;
+
+    fn existing_event(event_id: i32, category: i32, payload: serde_json::Value) -> ExistingEvent {
+        ExistingEvent::new(
+            event_id,
+            Uuid::nil(),
+            category,
+            payload,
+            Uuid::nil(),
+            1700000000,
+        )
+    }
+
+    #[test]
+    fn it_runs_the_created_hook_of_stored_events_only() {
+        // Arrange
+        let outcomes = vec![
+            (
+                "approved",
+                StoredEvent::Created {
+                    trigger_cron: false,
+                },
+            ),
+            ("approved again", StoredEvent::Duplicate),
+        ];
+
+        // Act
+        let (events, trigger_cron) = stored_events(outcomes);
+
+        // Assert
+        assert_eq!(events, vec!["approved"]);
+        assert!(!trigger_cron);
+    }
+
+    #[test]
+    fn it_ignores_a_duplicate_event_after_the_transaction() {
+        // Act
+        let (events, trigger_cron) = stored_events(vec![((), StoredEvent::Duplicate)]);
+
+        // Assert
+        assert!(events.is_empty());
+        assert!(!trigger_cron);
+    }
+
+    #[test]
+    fn it_suppresses_a_duplicate_event() {
+        // Arrange
+        let config = CollectionConfig::from_json(&json!({"deduplicateEvents": [1020]}));
+        let existing = vec![
+            existing_event(9, 1, json!({"title": "Circle"})),
+            existing_event(8, 1020, json!({"status": "approved"})),
+            existing_event(7, 1020, json!({"status": "open"})),
+        ];
+        let events = vec![Event::new(Uuid::nil(), 1020, json!({"status": "approved"}))];
+
+        // Act
+        let events = without_duplicate_events(&config, events, &existing, &[]);
+
+        // Assert
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn it_records_a_changed_event() {
+        // Arrange
+        let config = CollectionConfig::from_json(&json!({"deduplicateEvents": [1020]}));
+        let existing = vec![existing_event(8, 1020, json!({"status": "approved"}))];
+        let events = vec![Event::new(Uuid::nil(), 1020, json!({"status": "open"}))];
+
+        // Act
+        let result = without_duplicate_events(&config, events.clone(), &existing, &[]);
+
+        // Assert
+        assert_eq!(result, events);
+    }
+
+    #[test]
+    fn it_suppresses_a_duplicate_event_of_the_same_batch() {
+        // Arrange
+        let config = CollectionConfig::from_json(&json!({"deduplicateEvents": [1020]}));
+        let existing = vec![existing_event(8, 1020, json!({"status": "open"}))];
+        let batch = [
+            Event::new(Uuid::nil(), 1020, json!({"status": "approved"})),
+            Event::new(Uuid::nil(), 1020, json!({"status": "approved"})),
+        ];
+        let mut stored_in_transaction = vec![];
+
+        // Act
+        let first = without_duplicate_events(
+            &config,
+            vec![batch[0].clone()],
+            &existing,
+            &stored_in_transaction,
+        );
+        stored_in_transaction.extend(first.iter().cloned());
+        let second = without_duplicate_events(
+            &config,
+            vec![batch[1].clone()],
+            &existing,
+            &stored_in_transaction,
+        );
+
+        // Assert
+        assert_eq!(first, vec![batch[0].clone()]);
+        assert!(second.is_empty());
+    }
+
+    #[test]
+    fn it_counts_the_stored_events_and_the_duplicates_of_a_batch() {
+        // Arrange
+        let outcomes = vec![
+            (
+                (),
+                StoredEvent::Created {
+                    trigger_cron: false,
+                },
+            ),
+            ((), StoredEvent::Duplicate),
+            ((), StoredEvent::Created { trigger_cron: true }),
+        ];
+
+        // Act
+        let result = BatchEventsResult::from(&outcomes);
+        let (stored, trigger_cron) = stored_events(outcomes);
+
+        // Assert
+        assert_eq!((result.events, result.duplicates), (2, 1));
+        assert_eq!(stored.len(), 2);
+        assert!(trigger_cron);
+    }
+
+    #[test]
+    fn it_records_duplicates_of_other_categories() {
+        // Arrange
+        let config = CollectionConfig::from_json(&json!({"deduplicateEvents": [1020]}));
+        let existing = vec![existing_event(8, 1021, json!({"status": "approved"}))];
+        let events = vec![Event::new(Uuid::nil(), 1021, json!({"status": "approved"}))];
+
+        // Act
+        let result = without_duplicate_events(&config, events.clone(), &existing, &[]);
+
+        // Assert
+        assert_eq!(result, events);
+    }
 }
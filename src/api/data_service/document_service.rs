@@ -1,7 +1,7 @@
 use std::{collections::HashMap, sync::RwLock};
 
 use anyhow::anyhow;
-use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Select};
 use uuid::Uuid;
 
 use crate::api::{
@@ -48,6 +48,32 @@ impl DocumentService {
         Some((&document.unwrap()).into())
     }
 
+    /// Loads the documents of the collection with a single query, in the
+    /// order of the ids.
+    pub(crate) async fn get_documents(
+        &self,
+        db: &DatabaseConnection,
+        collection_name: &str,
+        ids: &[Uuid],
+    ) -> Vec<dto::CollectionDocument> {
+        if ids.is_empty() {
+            return vec![];
+        }
+        let Some(collection) = get_collection_by_name(db, collection_name).await else {
+            return vec![];
+        };
+
+        let mut documents = match documents_by_ids(collection.id, ids).all(db).await {
+            Ok(documents) => documents,
+            Err(e) => {
+                warn!("Could not load documents of {collection_name}: {e:?}");
+                return vec![];
+            }
+        };
+        documents.sort_by_key(|doc| ids.iter().position(|id| *id == doc.id));
+        documents.iter().map(|doc| doc.into()).collect()
+    }
+
     async fn lookup_get_collection_by_name(
         &self,
         db: &DatabaseConnection,
@@ -109,12 +135,22 @@ impl DocumentService {
     }
 }
 
+/// Selects the documents of the collection with the given ids
+fn documents_by_ids(collection_id: Uuid, ids: &[Uuid]) -> Select<Documents> {
+    Documents::find()
+        .filter(DocumentsColumns::CollectionId.eq(collection_id))
+        .filter(DocumentsColumns::Id.is_in(ids.iter().copied()))
+}
+
 #[cfg(test)]
 mod tests {
+    use pretty_assertions::assert_eq;
     use sea_orm::{DbBackend, EntityTrait, QueryFilter, QueryTrait};
     use sea_query::Expr;
     use uuid::Uuid;
 
+    use super::documents_by_ids;
+
     #[test]
     fn it_works() {
         // Arrange
@@ -130,7 +166,28 @@ mod tests {
         assert_eq!(
             sql,
             format!(
-                r#"SELECT "collection_document"."id", "collection_document"."collection_id", "collection_document"."owner", "collection_document"."f" FROM "collection_document" WHERE ("f"->'user'->>'id') = '{uid}'"#
+                r#"SELECT "collection_document"."id", "collection_document"."collection_id", "collection_document"."owner", "collection_document"."f", "collection_document"."seq", "collection_document"."locked" FROM "collection_document" WHERE ("f"->'user'->>'id') = '{uid}'"#
+            )
+        );
+    }
+
+    #[test]
+    fn it_selects_the_documents_by_ids_in_one_query() {
+        // Arrange
+        let collection = Uuid::new_v4();
+        let ids: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+
+        // Act
+        let sql = documents_by_ids(collection, &ids)
+            .build(DbBackend::Postgres)
+            .to_string();
+
+        // Assert
+        assert_eq!(
+            sql,
+            format!(
+                r#"SELECT "collection_document"."id", "collection_document"."collection_id", "collection_document"."owner", "collection_document"."f", "collection_document"."seq", "collection_document"."locked" FROM "collection_document" WHERE "collection_document"."collection_id" = '{collection}' AND "collection_document"."id" IN ('{}', '{}', '{}')"#,
+                ids[0], ids[1], ids[2]
             )
         );
     }
@@ -1,5 +1,9 @@
 use entity::event::{self, Entity as Events};
-use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder};
+use sea_orm::{
+    ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, QueryTrait,
+    Select,
+};
+use sea_query::SelectStatement;
 use uuid::Uuid;
 
 use crate::api::dto;
@@ -16,13 +20,148 @@ impl DocumentEventService {
         db: &sea_orm::DatabaseConnection,
         document_id: Uuid,
     ) -> Result<Vec<crate::api::dto::ExistingEvent>, anyhow::Error> {
-        Ok(Events::find()
-            .filter(event::Column::DocumentId.eq(document_id))
-            .order_by_desc(event::Column::Id)
+        Ok(document_events_newest_first(document_id)
             .all(db)
             .await?
             .into_iter()
             .map(|event| dto::ExistingEvent::from(&event))
             .collect())
     }
+
+    /// Returns a page of the events of the document, the latest event first,
+    /// and the number of all events, optionally of a single category.
+    pub(crate) async fn get_document_events_paged(
+        &self,
+        db: &sea_orm::DatabaseConnection,
+        document_id: Uuid,
+        category: Option<i32>,
+        limit: u64,
+        offset: u64,
+    ) -> Result<(Vec<crate::api::dto::ExistingEvent>, u64), anyhow::Error> {
+        let select = document_events_newest_first(document_id).apply_if(category, |q, category| {
+            q.filter(event::Column::CategoryId.eq(category))
+        });
+        events_page(db, select, limit, offset).await
+    }
+
+    /// Returns a page of the events of the documents, the latest event first,
+    /// and the number of all events within the time window, optionally of a
+    /// single category.
+    pub(crate) async fn get_collection_events_paged(
+        &self,
+        db: &sea_orm::DatabaseConnection,
+        document_ids: SelectStatement,
+        category: Option<i32>,
+        window: dto::EventWindow,
+        limit: u64,
+        offset: u64,
+    ) -> Result<(Vec<crate::api::dto::ExistingEvent>, u64), anyhow::Error> {
+        let select = collection_events_newest_first(document_ids, category, window);
+        events_page(db, select, limit, offset).await
+    }
+}
+
+async fn events_page(
+    db: &sea_orm::DatabaseConnection,
+    select: Select<Events>,
+    limit: u64,
+    offset: u64,
+) -> Result<(Vec<crate::api::dto::ExistingEvent>, u64), anyhow::Error> {
+    let total = select.clone().count(db).await?;
+    let events = select
+        .offset(offset)
+        .limit(limit)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|event| dto::ExistingEvent::from(&event))
+        .collect();
+    Ok((events, total))
+}
+
+/// Selects the events of the document, the latest event first.
+///
+/// The order is determined by the event id alone, which increases with every
+/// stored event. Events with the same timestamp, e. g. the events stored by
+/// one hook, keep the order in which they were stored, so the events can be
+/// replayed in the same order every time.
+pub(crate) fn document_events_newest_first(document_id: Uuid) -> Select<Events> {
+    Events::find()
+        .filter(event::Column::DocumentId.eq(document_id))
+        .order_by_desc(event::Column::Id)
+}
+
+/// Selects the events of the documents within the time window, the latest
+/// event first, see `document_events_newest_first`.
+pub(crate) fn collection_events_newest_first(
+    document_ids: SelectStatement,
+    category: Option<i32>,
+    window: dto::EventWindow,
+) -> Select<Events> {
+    Events::find()
+        .filter(event::Column::DocumentId.in_subquery(document_ids))
+        .apply_if(category, |q, category| {
+            q.filter(event::Column::CategoryId.eq(category))
+        })
+        .apply_if(window.from, |q, from| {
+            q.filter(event::Column::Timestamp.gte(from.naive_utc()))
+        })
+        .apply_if(window.to, |q, to| {
+            q.filter(event::Column::Timestamp.lt(to.naive_utc()))
+        })
+        .order_by_desc(event::Column::Id)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Utc};
+    use pretty_assertions::assert_eq;
+    use sea_orm::{DbBackend, QueryTrait};
+    use sea_query::{Alias, Expr, Query};
+
+    use super::*;
+
+    #[test]
+    fn it_orders_events_with_the_same_timestamp_by_id() {
+        // Arrange
+        let document_id = Uuid::new_v4();
+
+        // Act
+        let sql = document_events_newest_first(document_id)
+            .build(DbBackend::Postgres)
+            .to_string();
+
+        // Assert
+        assert_eq!(
+            sql,
+            format!(
+                r#"SELECT "event"."id", "event"."timestamp", "event"."document_id", "event"."user", "event"."category_id", "event"."payload", "event"."published" FROM "event" WHERE "event"."document_id" = '{document_id}' ORDER BY "event"."id" DESC"#
+            )
+        );
+    }
+
+    #[test]
+    fn it_includes_the_start_and_excludes_the_end_of_the_window() {
+        // Arrange
+        let document_ids = Query::select()
+            .column(Alias::new("id"))
+            .from(Alias::new("collection_document"))
+            .and_where(Expr::col(Alias::new("collection_id")).eq(Uuid::nil()))
+            .to_owned();
+        let window = dto::EventWindow {
+            from: Some("2024-02-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap()),
+            to: Some("2024-03-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap()),
+        };
+
+        // Act
+        let sql = collection_events_newest_first(document_ids, Some(1), window)
+            .build(DbBackend::Postgres)
+            .to_string();
+
+        // Assert
+        assert_eq!(
+            sql,
+            r#"SELECT "event"."id", "event"."timestamp", "event"."document_id", "event"."user", "event"."category_id", "event"."payload", "event"."published" FROM "event" WHERE "event"."document_id" IN (SELECT "id" FROM "collection_document" WHERE "collection_id" = '00000000-0000-0000-0000-000000000000') AND "event"."category_id" = 1 AND "event"."timestamp" >= '2024-02-01 00:00:00' AND "event"."timestamp" < '2024-03-01 00:00:00' ORDER BY "event"."id" DESC"#
+        );
+    }
 }
@@ -3,6 +3,7 @@ use std::collections::HashMap;
 use async_trait::async_trait;
 use reqwest::StatusCode;
 use sea_orm::DatabaseConnection;
+use sea_query::SelectStatement;
 use serde::{Deserialize, Serialize};
 use tracing::debug;
 use uuid::Uuid;
@@ -12,7 +13,7 @@ pub use self::user_service::User;
 use crate::api::dto::{self, ExistingEvent};
 
 mod document_service;
-mod event_service;
+pub(crate) mod event_service;
 pub(crate) mod user_service;
 
 pub(crate) struct ClientCredentials {
@@ -29,12 +30,46 @@ pub struct TokenResponse {
 #[async_trait]
 pub trait DataService: Sync + Send {
     async fn get_document_events(&self, document_id: Uuid) -> anyhow::Result<Vec<ExistingEvent>>;
+    /// Returns a page of the events of the document, the latest event first,
+    /// and the number of all events, optionally of a single category.
+    async fn get_document_events_paged(
+        &self,
+        document_id: Uuid,
+        category: Option<i32>,
+        limit: u64,
+        offset: u64,
+    ) -> anyhow::Result<(Vec<ExistingEvent>, u64)>;
+    /// Returns a page of the events of the selected documents, the latest
+    /// event first, and the number of all events within the time window,
+    /// optionally of a single category.
+    async fn get_collection_events_paged(
+        &self,
+        document_ids: SelectStatement,
+        category: Option<i32>,
+        window: dto::EventWindow,
+        limit: u64,
+        offset: u64,
+    ) -> anyhow::Result<(Vec<ExistingEvent>, u64)>;
     async fn get_user_by_id(&self, user_id: Uuid) -> anyhow::Result<User>;
     async fn get_document(
         &self,
         collection_name: &str,
         document_id: Uuid,
     ) -> Option<dto::CollectionDocument>;
+    /// Returns the documents of the collection with the given ids, in the
+    /// order of the ids. Unknown ids are skipped. Like `get_document`, deleted
+    /// documents are returned as well, see `is_deleted`.
+    async fn get_documents(
+        &self,
+        collection_name: &str,
+        ids: &[Uuid],
+    ) -> Vec<dto::CollectionDocument> {
+        let mut documents = Vec::with_capacity(ids.len());
+        for id in ids {
+            documents.extend(self.get_document(collection_name, *id).await);
+        }
+        documents
+    }
     async fn get_collection_by_name(&self, collection_name: &str) -> Option<dto::Collection>;
     async fn get_collection_documents(
         &self,
@@ -68,6 +103,31 @@ impl DataService for FolivafyDataService {
             .await
     }
 
+    async fn get_document_events_paged(
+        &self,
+        document_id: Uuid,
+        category: Option<i32>,
+        limit: u64,
+        offset: u64,
+    ) -> anyhow::Result<(Vec<ExistingEvent>, u64)> {
+        self.event_service
+            .get_document_events_paged(&self.db, document_id, category, limit, offset)
+            .await
+    }
+
+    async fn get_collection_events_paged(
+        &self,
+        document_ids: SelectStatement,
+        category: Option<i32>,
+        window: dto::EventWindow,
+        limit: u64,
+        offset: u64,
+    ) -> anyhow::Result<(Vec<ExistingEvent>, u64)> {
+        self.event_service
+            .get_collection_events_paged(&self.db, document_ids, category, window, limit, offset)
+            .await
+    }
+
     async fn get_user_by_id(&self, user_id: Uuid) -> anyhow::Result<User> {
         self.user_service.get_user_by_id(user_id).await
     }
@@ -82,6 +142,16 @@ impl DataService for FolivafyDataService {
             .await
     }
 
+    async fn get_documents(
+        &self,
+        collection_name: &str,
+        ids: &[Uuid],
+    ) -> Vec<dto::CollectionDocument> {
+        self.document_service
+            .get_documents(&self.db, collection_name, ids)
+            .await
+    }
+
     async fn get_collection_by_name(&self, collection_name: &str) -> Option<dto::Collection> {
         self.document_service
             .get_collection_by_name(&self.db, collection_name)
@@ -128,3 +198,99 @@ pub(crate) async fn get_token(client_credentials: &ClientCredentials) -> anyhow:
         Err(e) => anyhow::bail!(e.to_string()),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    /// Knows the documents of the collection `shapes`
+    struct ShapesDataService {
+        documents: Vec<dto::CollectionDocument>,
+    }
+
+    #[async_trait]
+    impl DataService for ShapesDataService {
+        async fn get_document_events(
+            &self,
+            _document_id: Uuid,
+        ) -> anyhow::Result<Vec<ExistingEvent>> {
+            Ok(vec![])
+        }
+
+        async fn get_document_events_paged(
+            &self,
+            _document_id: Uuid,
+            _category: Option<i32>,
+            _limit: u64,
+            _offset: u64,
+        ) -> anyhow::Result<(Vec<ExistingEvent>, u64)> {
+            Ok((vec![], 0))
+        }
+
+        async fn get_collection_events_paged(
+            &self,
+            _document_ids: SelectStatement,
+            _category: Option<i32>,
+            _window: dto::EventWindow,
+            _limit: u64,
+            _offset: u64,
+        ) -> anyhow::Result<(Vec<ExistingEvent>, u64)> {
+            Ok((vec![], 0))
+        }
+
+        async fn get_user_by_id(&self, user_id: Uuid) -> anyhow::Result<User> {
+            anyhow::bail!("Unknown user {user_id}")
+        }
+
+        async fn get_document(
+            &self,
+            collection_name: &str,
+            document_id: Uuid,
+        ) -> Option<dto::CollectionDocument> {
+            self.documents
+                .iter()
+                .find(|document| collection_name == "shapes" && *document.id() == document_id)
+                .cloned()
+        }
+
+        async fn get_collection_by_name(&self, _collection_name: &str) -> Option<dto::Collection> {
+            None
+        }
+
+        async fn get_collection_documents(
+            &self,
+            _collection_name: &str,
+        ) -> anyhow::Result<Vec<dto::CollectionDocument>> {
+            Ok(self.documents.clone())
+        }
+    }
+
+    #[tokio::test]
+    async fn it_fetches_three_of_five_documents() {
+        // Arrange
+        let documents: Vec<dto::CollectionDocument> = (0..5)
+            .map(|i| dto::CollectionDocument::new(Uuid::new_v4(), json!({"title": i})))
+            .collect();
+        let ids = [
+            *documents[3].id(),
+            *documents[0].id(),
+            Uuid::new_v4(),
+            *documents[4].id(),
+        ];
+        let service = ShapesDataService { documents };
+
+        // Act
+        let fetched = service.get_documents("shapes", &ids).await;
+        let other_collection = service.get_documents("fluids", &ids).await;
+
+        // Assert
+        assert_eq!(
+            fetched.iter().map(|d| *d.id()).collect::<Vec<_>>(),
+            vec![ids[0], ids[1], ids[3]]
+        );
+        assert!(other_collection.is_empty());
+    }
+}
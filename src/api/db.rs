@@ -2,43 +2,215 @@ use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use entity::collection::Model;
 pub(crate) use entity::{DELETED_AT_FIELD, DELETED_BY_FIELD};
+use lazy_static::lazy_static;
 use migration::CollectionDocument;
 use migration::Grant;
 use sea_orm::QueryResult;
 use sea_orm::{
     ActiveModelTrait, ActiveValue::NotSet, ColumnTrait, ConnectionTrait, DatabaseConnection,
     DatabaseTransaction, EntityTrait, FromQueryResult, JsonValue, QueryFilter, Set, Statement,
+    TransactionError, TransactionTrait,
 };
-use sea_orm::{DbErr, ModelTrait, QuerySelect};
+use sea_orm::{DbErr, ModelTrait, QuerySelect, RuntimeErr};
+use sea_query::extension::postgres::PgBinOper;
 use sea_query::{
-    all, Alias, Cond, Condition, Expr, Func, JoinType, Order, Query, SelectStatement, SimpleExpr,
+    all, Alias, Asterisk, Cond, Condition, Expr, Func, JoinType, LockBehavior, LockType,
+    NullOrdering, Order, Query, SelectStatement, SimpleExpr, UnionType,
 };
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::future::Future;
 use std::ops::Sub;
-use tracing::{debug, error, info};
+use std::pin::Pin;
+use std::time::Duration;
+use tracing::{debug, error, info, warn};
 use typed_builder::TypedBuilder;
 use uuid::Uuid;
 
 use crate::api::{
+    collection_config::CollectionConfig,
     create_document::create_document_event,
+    document_version::{without_version, VERSION_FIELD},
     dto::{self, Event, MailMessage},
     hooks::CronDocumentSelector,
     types::Pagination,
     ApiContext, ApiErrors, CATEGORY_DOCUMENT_UPDATES,
 };
+use crate::monitoring::EVENT_METRICS;
 use entity::collection_document::Column as DocumentsColumns;
 use entity::collection_document::Entity as Documents;
 use entity::event::Column as DbEventsColumns;
 use entity::event::Entity as DbEventsEntity;
 use std::result;
 
-use super::hooks::GrantSettingsOnEvents;
 use super::hooks::{
     StoreDocument, StoreNewDocument, StoreNewDocumentCollection, StoreNewDocumentOwner,
 };
 use super::search_documents::SearchFilter;
 use super::search_documents::SearchGroup;
 
+lazy_static! {
+    static ref DB_RETRY_POLICY: RetryPolicy = RetryPolicy::from_env();
+}
+
+/// Defines how often a failed database operation is repeated.
+#[derive(Clone, Debug)]
+pub(crate) struct RetryPolicy {
+    max_retries: u32,
+    base_delay: Duration,
+}
+
+impl RetryPolicy {
+    pub(crate) fn new(max_retries: u32, base_delay: Duration) -> Self {
+        Self {
+            max_retries,
+            base_delay,
+        }
+    }
+
+    /// Reads the number of retries from `FOLIVAFY_DB_RETRIES`, defaults to 3.
+    fn from_env() -> Self {
+        let max_retries = std::env::var("FOLIVAFY_DB_RETRIES")
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(3);
+        Self::new(max_retries, Duration::from_millis(50))
+    }
+
+    /// The delay before the given retry, doubled for every attempt
+    fn delay(&self, retry: u32) -> Duration {
+        self.base_delay.saturating_mul(2u32.saturating_pow(retry))
+    }
+}
+
+/// Checks whether a database error is transient, i. e. the operation might
+/// succeed when it is repeated.
+pub(crate) fn is_retryable_error(err: &DbErr) -> bool {
+    match err {
+        DbErr::ConnectionAcquire(_) => true,
+        DbErr::Conn(RuntimeErr::SqlxError(e))
+        | DbErr::Exec(RuntimeErr::SqlxError(e))
+        | DbErr::Query(RuntimeErr::SqlxError(e)) => match e {
+            sqlx::Error::Io(_) | sqlx::Error::PoolTimedOut | sqlx::Error::WorkerCrashed => true,
+            sqlx::Error::Database(e) => e.code().is_some_and(|c| is_retryable_sqlstate(&c)),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Serialization failures, deadlocks and connection exceptions (class 08)
+fn is_retryable_sqlstate(code: &str) -> bool {
+    code == "40001" || code == "40P01" || code.starts_with("08")
+}
+
+/// Runs the operation and repeats it on transient errors.
+///
+/// Statements inside a transaction must not be wrapped, because a failed
+/// statement aborts the whole transaction.
+pub(crate) async fn with_retry<T, F, Fut>(
+    policy: &RetryPolicy,
+    mut operation: F,
+) -> Result<T, DbErr>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, DbErr>>,
+{
+    let mut retry = 0;
+    loop {
+        match operation().await {
+            Err(err) if retry < policy.max_retries && is_retryable_error(&err) => {
+                let delay = policy.delay(retry);
+                retry += 1;
+                warn!(
+                    "Transient database error, retry {retry}/{} in {delay:?}: {err}",
+                    policy.max_retries
+                );
+                tokio::time::sleep(delay).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Checks whether the transaction was aborted by a serialization failure or a
+/// deadlock, the whole transaction might succeed when it is repeated.
+pub(crate) fn is_transaction_conflict(err: &DbErr) -> bool {
+    match err {
+        DbErr::Conn(RuntimeErr::SqlxError(sqlx::Error::Database(e)))
+        | DbErr::Exec(RuntimeErr::SqlxError(sqlx::Error::Database(e)))
+        | DbErr::Query(RuntimeErr::SqlxError(sqlx::Error::Database(e))) => e
+            .code()
+            .is_some_and(|c| c == SERIALIZATION_FAILURE || c == DEADLOCK_DETECTED),
+        _ => false,
+    }
+}
+
+const SERIALIZATION_FAILURE: &str = "40001";
+const DEADLOCK_DETECTED: &str = "40P01";
+
+/// Runs the write transaction and repeats it when it was aborted by a
+/// serialization failure or a deadlock. The callback is called for every
+/// attempt, it must not consume the data it stores.
+pub(crate) async fn transaction_with_retry<F, T>(
+    db: &DatabaseConnection,
+    callback: F,
+) -> Result<T, ApiErrors>
+where
+    F: for<'c> Fn(
+            &'c DatabaseTransaction,
+        ) -> Pin<Box<dyn Future<Output = Result<T, ApiErrors>> + Send + 'c>>
+        + Send
+        + Sync,
+    T: Send,
+{
+    retry_transaction(&DB_RETRY_POLICY, || async {
+        db.transaction::<_, T, ApiErrors>(|txn| callback(txn))
+            .await
+            .map_err(|err| match err {
+                TransactionError::Connection(c) => Into::<ApiErrors>::into(c),
+                TransactionError::Transaction(t) => t,
+            })
+    })
+    .await
+}
+
+/// Repeats the attempt while it fails with a transaction conflict.
+async fn retry_transaction<T, F, Fut>(policy: &RetryPolicy, mut attempt: F) -> Result<T, ApiErrors>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, ApiErrors>>,
+{
+    let mut retry = 0;
+    loop {
+        match attempt().await {
+            Err(ApiErrors::TransactionConflict) if retry < policy.max_retries => {
+                let delay = policy.delay(retry);
+                retry += 1;
+                warn!(
+                    "Transaction conflict, retry {retry}/{} in {delay:?}",
+                    policy.max_retries
+                );
+                tokio::time::sleep(delay).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Maps the error of storing documents, events and mails within a write
+/// transaction, a transaction conflict is passed on to repeat the transaction.
+pub(crate) fn store_error(e: anyhow::Error) -> ApiErrors {
+    if e.downcast_ref::<DbErr>()
+        .is_some_and(is_transaction_conflict)
+    {
+        debug!("Transaction conflict: {:?}", e);
+        return ApiErrors::TransactionConflict;
+    }
+    error!("Store error: {:?}", e);
+    ApiErrors::InternalServerError
+}
+
 pub(crate) async fn get_unlocked_collection_by_name(
     db: &DatabaseConnection,
     collection_name: &str,
@@ -52,10 +224,12 @@ pub(crate) async fn get_collection_by_name(
     db: &DatabaseConnection,
     collection_name: &str,
 ) -> Option<Model> {
-    let query_result = entity::collection::Entity::find()
-        .filter(entity::collection::Column::Name.eq(collection_name))
-        .one(db)
-        .await;
+    let query_result = with_retry(&DB_RETRY_POLICY, || {
+        entity::collection::Entity::find()
+            .filter(entity::collection::Column::Name.eq(collection_name))
+            .one(db)
+    })
+    .await;
 
     match query_result {
         Ok(Some(col)) => {
@@ -101,11 +275,17 @@ pub(crate) enum FieldFilter {
         field_name: String,
         values: Vec<String>,
     },
-    #[allow(dead_code)]
+    FieldNotEqual {
+        field_name: String,
+        value: String,
+    },
+    FieldValueNotInMatch {
+        field_name: String,
+        values: Vec<String>,
+    },
     FieldIsNull {
         field_name: String,
     },
-    #[allow(dead_code)]
     FieldIsNotNull {
         field_name: String,
     },
@@ -113,6 +293,20 @@ pub(crate) enum FieldFilter {
         field_name: String,
         value: DateTime<Utc>,
     },
+    /// The field holds an RFC 3339 timestamp before the value
+    TimestampFieldLessThan {
+        field_name: String,
+        value: DateTime<Utc>,
+    },
+    /// The field holds an RFC 3339 timestamp after the value
+    TimestampFieldGreaterThan {
+        field_name: String,
+        value: DateTime<Utc>,
+    },
+    /// Matches if one of the filters matches
+    AnyOf(Vec<FieldFilter>),
+    /// Matches if all filters match
+    AllOf(Vec<FieldFilter>),
 }
 
 impl FieldFilter {
@@ -128,6 +322,29 @@ impl FieldFilter {
         // Split at first equal sign
         let (field_name, value) = s.split_once('=')?;
 
+        // `field=*` matches documents with the field, `field=!*` without it
+        match value {
+            "*" => {
+                return Some(FieldFilter::FieldIsNotNull {
+                    field_name: field_name.to_string(),
+                })
+            }
+            "!*" => {
+                return Some(FieldFilter::FieldIsNull {
+                    field_name: field_name.to_string(),
+                })
+            }
+            _ => {}
+        }
+
+        // `field!=value` excludes a single value
+        if let Some(field_name) = field_name.strip_suffix('!') {
+            return Some(FieldFilter::FieldNotEqual {
+                field_name: field_name.to_string(),
+                value: value_trimmer(value),
+            });
+        }
+
         if value.starts_with('~') {
             let value = value.trim_start_matches('~');
             return Some(FieldFilter::FieldContains {
@@ -144,6 +361,21 @@ impl FieldFilter {
             });
         }
 
+        // `field=![v1,v2]` excludes a list of values
+        if let Some(value) = value.strip_prefix('!') {
+            if !(value.starts_with('[') && value.ends_with(']')) {
+                return None;
+            }
+            let values: Vec<String> = value[1..value.len() - 1]
+                .split(',')
+                .map(|v| value_trimmer(v.trim()))
+                .collect();
+            return Some(FieldFilter::FieldValueNotInMatch {
+                field_name: field_name.to_string(),
+                values,
+            });
+        }
+
         // If value is inside square brackets, then it's a list of values
         if value.starts_with('[') && value.ends_with(']') {
             let values: Vec<String> = value[1..value.len() - 1]
@@ -183,6 +415,9 @@ impl From<CronDocumentSelector> for FieldFilter {
                     value: chrono::Utc::now().sub(value),
                 }
             }
+            CronDocumentSelector::AllOf(selectors) => {
+                FieldFilter::AllOf(selectors.into_iter().map(FieldFilter::from).collect())
+            }
         }
     }
 }
@@ -192,8 +427,69 @@ pub(crate) enum ListDocumentGrants {
     IgnoredForCron,
     IgnoredForAdmin,
     Restricted(Vec<dto::Grant>),
+    /// Only documents without any grant rows, for maintenance
+    Orphans,
+}
+
+/// How the total number of matching documents is determined
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum CountMode {
+    #[default]
+    Exact,
+    /// Count with a time limit and fall back to the estimate of the query
+    /// planner if counting takes too long
+    Approximate,
+    /// Do not count, select one more document than the page to tell whether
+    /// more documents follow
+    Skip,
 }
 
+/// Total number of matching documents
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct DocumentTotal {
+    pub(crate) count: u32,
+    /// The count is an estimate of the query planner
+    pub(crate) approximate: bool,
+    /// Whether more documents follow the page, only determined with
+    /// `CountMode::Skip`
+    pub(crate) has_more: Option<bool>,
+}
+
+/// Column of the list query with the sort key for the cursor of the next page
+pub(crate) const CURSOR_KEY_COLUMN: &str = "cursor_key";
+
+/// Extra field that selects all fields of the documents
+pub(crate) const ALL_FIELDS: &str = "*";
+
+/// Sort field that orders by the author of the document, i. e. the user of
+/// the creation event
+pub(crate) const AUTHOR_ID_SORT_FIELD: &str = "author_id";
+
+/// The joined author of the document, `NULL` without creation event
+const AUTHOR_ID_SORT_EXPR: &str = r#""e"."user""#;
+
+/// Last document of a page, the next page starts after it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct CursorPosition {
+    /// Value of the sort field
+    pub(crate) key: JsonValue,
+    pub(crate) id: Uuid,
+}
+
+/// Keyset pagination: the documents are ordered by a single sort field and
+/// their id, a page starts after the last document of the previous page
+/// instead of skipping rows with `OFFSET`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Keyset {
+    /// First page
+    Start,
+    /// Page after the document
+    After(CursorPosition),
+}
+
+/// Time limit in milliseconds of an approximate count before the estimate is used
+const APPROXIMATE_COUNT_TIMEOUT_MS: u32 = 500;
+
 #[derive(Debug, Clone, TypedBuilder)]
 pub(crate) struct DbListDocumentParams {
     pub(crate) collection: Uuid,
@@ -204,40 +500,304 @@ pub(crate) struct DbListDocumentParams {
     pub(crate) include_author_id: bool,
     #[builder(default)]
     pub(crate) pagination: Pagination,
+    /// Skip the documents of this owner
+    #[builder(default)]
+    pub(crate) exclude_owner: Option<Uuid>,
+    #[builder(default)]
+    pub(crate) count: CountMode,
+    /// Keyset pagination instead of `OFFSET`, requires a single sort field
+    #[builder(default)]
+    pub(crate) keyset: Option<Keyset>,
+}
+
+/// Parameters for a search across several collections. The grants are
+/// applied per collection.
+#[derive(Debug, Clone, TypedBuilder)]
+pub(crate) struct DbListMultiCollectionParams {
+    pub(crate) collections: Vec<(Uuid, ListDocumentGrants)>,
+    pub(crate) extra_fields: Vec<String>,
+    pub(crate) sort_fields: Option<String>,
+    pub(crate) filters: SearchFilter,
+    #[builder(default)]
+    pub(crate) pagination: Pagination,
+}
+
+impl DbListMultiCollectionParams {
+    fn collection_params(&self) -> impl Iterator<Item = DbListDocumentParams> + '_ {
+        self.collections
+            .iter()
+            .map(|(collection, grants)| DbListDocumentParams {
+                collection: *collection,
+                grants: grants.clone(),
+                extra_fields: self.extra_fields.clone(),
+                sort_fields: self.sort_fields.clone(),
+                filters: self.filters.clone(),
+                include_author_id: false,
+                pagination: self.pagination.clone(),
+                exclude_owner: None,
+                count: CountMode::Exact,
+                keyset: None,
+            })
+    }
 }
 
 pub(crate) async fn list_documents(
     db: &DatabaseConnection,
     params: &DbListDocumentParams,
-) -> Result<(u32, Vec<JsonValue>), ApiErrors> {
+) -> Result<(DocumentTotal, Vec<JsonValue>), ApiErrors> {
+    let skip_count = params.count == CountMode::Skip;
+    let total = if skip_count {
+        None
+    } else {
+        Some(count_documents(db, params).await?)
+    };
+
+    let limit = u64::from(params.pagination.limit());
+    let mut sql = select_documents_sql(params);
+    sql.limit(if skip_count { limit + 1 } else { limit });
+    if params.keyset.is_none() {
+        sql.offset(params.pagination.offset().into());
+    }
+    let builder = db.get_database_backend();
+    let stmt: Statement = builder.build(&sql);
+
+    let mut items: Vec<JsonValue> = with_retry(&DB_RETRY_POLICY, || {
+        JsonValue::find_by_statement(stmt.clone()).all(db)
+    })
+    .await
+    .map_err(ApiErrors::from)?;
+
+    let total = match total {
+        Some(total) => total,
+        None => {
+            let has_more = trim_page(&mut items, params.pagination.limit().into());
+            DocumentTotal {
+                count: params
+                    .pagination
+                    .offset()
+                    .saturating_add(items.len() as u32 + u32::from(has_more)),
+                approximate: false,
+                has_more: Some(has_more),
+            }
+        }
+    };
+
+    Ok((total, items))
+}
+
+/// Removes the extra document beyond the page and returns whether it existed.
+fn trim_page<T>(items: &mut Vec<T>, limit: usize) -> bool {
+    let has_more = items.len() > limit;
+    items.truncate(limit);
+    has_more
+}
+
+/// Counts the documents that match the parameters without selecting them.
+pub(crate) async fn count_documents(
+    db: &DatabaseConnection,
+    params: &DbListDocumentParams,
+) -> Result<DocumentTotal, ApiErrors> {
     let count_sql = count_documents_sql(params);
     let count_stmt = db.get_database_backend().build(&count_sql);
-    let query_res: Option<QueryResult> = db.query_one(count_stmt).await?;
-    let query_res = query_res.unwrap();
-    let total = query_res
+    match params.count {
+        CountMode::Exact | CountMode::Skip => {
+            let query_res: Option<QueryResult> =
+                with_retry(&DB_RETRY_POLICY, || db.query_one(count_stmt.clone())).await?;
+            Ok(DocumentTotal {
+                count: count_from_result(query_res)?,
+                approximate: false,
+                has_more: None,
+            })
+        }
+        CountMode::Approximate => approximate_count(db, params, count_stmt).await,
+    }
+}
+
+fn count_from_result(query_res: Option<QueryResult>) -> Result<u32, DbErr> {
+    let query_res = query_res.ok_or_else(|| DbErr::RecordNotFound("count".to_string()))?;
+    query_res
         .try_get_by(0)
-        .map(|count: i64| u32::try_from(count).unwrap_or(u32::MAX))?;
+        .map(|count: i64| u32::try_from(count).unwrap_or(u32::MAX))
+}
+
+/// Counts the documents with a statement timeout. If the timeout is exceeded,
+/// the number of rows estimated by the query planner is returned.
+async fn approximate_count(
+    db: &DatabaseConnection,
+    params: &DbListDocumentParams,
+    count_stmt: Statement,
+) -> Result<DocumentTotal, ApiErrors> {
+    let txn = db.begin().await?;
+    txn.execute_unprepared(&format!(
+        "SET LOCAL statement_timeout = {APPROXIMATE_COUNT_TIMEOUT_MS}"
+    ))
+    .await?;
+    let counted = txn.query_one(count_stmt).await;
+    txn.rollback().await?;
+    match counted {
+        Ok(query_res) => Ok(DocumentTotal {
+            count: count_from_result(query_res)?,
+            approximate: false,
+            has_more: None,
+        }),
+        Err(err) if is_statement_timeout(&err) => {
+            info!("Count exceeded {APPROXIMATE_COUNT_TIMEOUT_MS} ms, using the estimate");
+            let mut stmt = db
+                .get_database_backend()
+                .build(&matching_document_ids_sql(params));
+            stmt.sql = format!("EXPLAIN (FORMAT JSON) {}", stmt.sql);
+            let plan: JsonValue = db
+                .query_one(stmt)
+                .await?
+                .ok_or_else(|| DbErr::RecordNotFound("query plan".to_string()))?
+                .try_get_by(0)?;
+            Ok(DocumentTotal {
+                count: planned_rows(&plan),
+                approximate: true,
+                has_more: None,
+            })
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Query canceled because of the statement timeout
+fn is_statement_timeout(err: &DbErr) -> bool {
+    match err {
+        DbErr::Query(RuntimeErr::SqlxError(sqlx::Error::Database(e))) => {
+            e.code().is_some_and(|c| c == "57014")
+        }
+        _ => false,
+    }
+}
+
+/// Reads the estimated number of rows from the output of `EXPLAIN (FORMAT JSON)`.
+fn planned_rows(plan: &JsonValue) -> u32 {
+    plan[0]["Plan"]["Plan Rows"]
+        .as_f64()
+        .map(|rows| rows.round().clamp(0.0, u32::MAX as f64) as u32)
+        .unwrap_or_default()
+}
+
+/// Selects only the first document in sort order. The document id breaks
+/// ties, so the same document is returned for equal sort values.
+pub(crate) async fn first_document(
+    db: &DatabaseConnection,
+    params: &DbListDocumentParams,
+) -> Result<Option<JsonValue>, ApiErrors> {
+    let sql = first_document_sql(params);
+    let stmt: Statement = db.get_database_backend().build(&sql);
+
+    with_retry(&DB_RETRY_POLICY, || {
+        JsonValue::find_by_statement(stmt.clone()).one(db)
+    })
+    .await
+    .map_err(ApiErrors::from)
+}
+
+/// Lists the documents of several collections as one result. Every item
+/// contains the name of its collection in the `collection` field.
+pub(crate) async fn list_multi_collection_documents(
+    db: &DatabaseConnection,
+    params: &DbListMultiCollectionParams,
+) -> Result<(u32, Vec<JsonValue>), ApiErrors> {
+    let builder = db.get_database_backend();
+    let count_stmt = builder.build(&count_multi_collection_documents_sql(params));
+    let query_res: Option<QueryResult> =
+        with_retry(&DB_RETRY_POLICY, || db.query_one(count_stmt.clone())).await?;
+    let total = query_res
+        .map(|row| row.try_get_by(0))
+        .transpose()?
+        .map(|count: i64| u32::try_from(count).unwrap_or(u32::MAX))
+        .unwrap_or_default();
 
-    let sql = select_documents_sql(params)
+    let sql = select_multi_collection_documents_sql(params)
         .limit(params.pagination.limit().into())
         .offset(params.pagination.offset().into())
         .to_owned();
-    let builder = db.get_database_backend();
     let stmt: Statement = builder.build(&sql);
 
-    let items: Vec<JsonValue> = JsonValue::find_by_statement(stmt)
-        .all(db)
-        .await
-        .map_err(ApiErrors::from)?;
+    let items: Vec<JsonValue> = with_retry(&DB_RETRY_POLICY, || {
+        JsonValue::find_by_statement(stmt.clone()).all(db)
+    })
+    .await
+    .map_err(ApiErrors::from)?;
 
     Ok((total, items))
 }
 
+/// A value of a document field and the number of documents with this value
+#[derive(FromQueryResult, Debug, Clone, PartialEq)]
+pub(crate) struct DistinctValue {
+    pub(crate) value: Option<String>,
+    pub(crate) count: i64,
+}
+
+/// Lists the distinct values of the field with the number of matching
+/// documents per value, the most frequent values first. Returns whether the
+/// field has more values than `limit`.
+pub(crate) async fn list_distinct_values(
+    db: &DatabaseConnection,
+    params: &DbListDocumentParams,
+    field_name: &str,
+    limit: u64,
+) -> Result<(Vec<DistinctValue>, bool), ApiErrors> {
+    let stmt = db
+        .get_database_backend()
+        .build(&distinct_values_sql(params, field_name, limit + 1));
+    let mut items = with_retry(&DB_RETRY_POLICY, || {
+        DistinctValue::find_by_statement(stmt.clone()).all(db)
+    })
+    .await?;
+    let has_more = trim_page(&mut items, limit as usize);
+    Ok((items, has_more))
+}
+
+/// A document as it is returned by the changes feed
+#[derive(FromQueryResult, Debug, Clone, PartialEq)]
+pub(crate) struct DocumentChange {
+    pub(crate) id: Uuid,
+    pub(crate) seq: i64,
+    pub(crate) f: JsonValue,
+}
+
+/// Lists the documents that were written after the sequence number `since`,
+/// ordered by their sequence number.
+pub(crate) async fn list_document_changes(
+    db: &DatabaseConnection,
+    params: &DbListDocumentParams,
+    since: i64,
+) -> Result<Vec<DocumentChange>, ApiErrors> {
+    let stmt = db
+        .get_database_backend()
+        .build(&document_changes_sql(params, since));
+    with_retry(&DB_RETRY_POLICY, || {
+        DocumentChange::find_by_statement(stmt.clone()).all(db)
+    })
+    .await
+    .map_err(ApiErrors::from)
+}
+
 #[derive(FromQueryResult, Debug, Deserialize)]
 struct IdOnly {
     pub(crate) id: Uuid,
 }
 
+/// Lists the ids of all documents that match the filters, ordered by id.
+pub(crate) async fn list_matching_document_ids(
+    db: &DatabaseConnection,
+    params: &DbListDocumentParams,
+) -> Result<Vec<Uuid>, ApiErrors> {
+    let sql = matching_document_ids_sql(params);
+    let stmt = db.get_database_backend().build(&sql);
+    let items = with_retry(&DB_RETRY_POLICY, || {
+        IdOnly::find_by_statement(stmt.clone()).all(db)
+    })
+    .await?;
+    debug!("Found {} matching documents", items.len());
+    Ok(items.into_iter().map(|item| item.id).collect())
+}
+
 pub(crate) async fn list_document_ids(
     db: &DatabaseTransaction,
     collection_id: Uuid,
@@ -250,7 +810,7 @@ pub(crate) async fn list_document_ids(
         .all(db)
         .await?;
     debug!("Found {} documents", items.len());
-    Ok(items.into_iter().map(|item| (item.id)).collect())
+    Ok(items.into_iter().map(|item| item.id).collect())
 }
 
 fn grants_conditions(user_grants: &Vec<dto::Grant>) -> Condition {
@@ -265,6 +825,14 @@ fn grants_conditions(user_grants: &Vec<dto::Grant>) -> Condition {
     grant_conditions
 }
 
+/// Selects the ids of the listed documents, e. g. to select the rows of other
+/// tables that belong to the documents a user can access.
+pub(crate) fn document_ids_sql(params: &DbListDocumentParams) -> SelectStatement {
+    let (mut q, documents_alias) = base_documents_sql(params);
+    q.column((documents_alias, CollectionDocument::Id));
+    q
+}
+
 fn base_documents_sql(params: &DbListDocumentParams) -> (SelectStatement, Alias) {
     let documents_alias = Alias::new("d");
     let mut b = Query::select();
@@ -288,6 +856,25 @@ fn base_documents_sql(params: &DbListDocumentParams) -> (SelectStatement, Alias)
                 )
                 .cond_where(grants_conditions(user_grants));
         }
+        ListDocumentGrants::Orphans => {
+            q = q.and_where(
+                Expr::exists(
+                    Query::select()
+                        .expr(Expr::val(1))
+                        .from(Grant::Table)
+                        .and_where(
+                            Expr::col((Grant::Table, Grant::DocumentId))
+                                .equals((documents_alias.clone(), CollectionDocument::Id)),
+                        )
+                        .to_owned(),
+                )
+                .not(),
+            );
+        }
+    }
+
+    if let Some(owner) = params.exclude_owner {
+        q = q.and_where(Expr::col((documents_alias.clone(), DocumentsColumns::Owner)).ne(owner));
     }
 
     q = modify_query(q, &params.filters);
@@ -356,15 +943,9 @@ fn fov_value_to_expr(val: &serde_json::Value) -> Option<SimpleExpr> {
     match val {
         JsonValue::Null => None,
         JsonValue::Bool(b) => Some(Expr::value(*b)),
-        JsonValue::Number(n) => {
-            if n.is_i64() {
-                Some(Expr::value(n.as_i64().unwrap_or_default()))
-            } else if n.is_f64() {
-                Some(Expr::value(n.as_f64().unwrap_or_default()))
-            } else {
-                None
-            }
-        }
+        // Numbers are compared as text for artificial fields, see
+        // fov_number_to_condition for document fields
+        JsonValue::Number(n) => Some(Expr::value(n.to_string())),
         JsonValue::String(s) => {
             if s.is_empty() {
                 None
@@ -373,41 +954,135 @@ fn fov_value_to_expr(val: &serde_json::Value) -> Option<SimpleExpr> {
             }
         }
         JsonValue::Array(a) => {
-            let all_items_are_integers = a.iter().all(|v| v.is_i64());
-            if all_items_are_integers {
-                Some(SimpleExpr::Tuple(
-                    a.iter()
-                        .map(|v| v.as_i64().unwrap_or_default())
-                        .map(|v| v.into())
-                        .collect::<Vec<_>>(),
-                ))
+            let v = a
+                .iter()
+                .map(|v| {
+                    if v.is_string() {
+                        v.as_str().unwrap_or_default().to_string()
+                    } else {
+                        v.to_string()
+                    }
+                })
+                .map(|v| v.into())
+                .collect::<Vec<_>>();
+            if v.is_empty() {
+                None
             } else {
-                let v = a
-                    .iter()
-                    .map(|v| {
-                        if v.is_string() {
-                            v.as_str().unwrap_or_default().to_string()
-                        } else {
-                            v.to_string()
-                        }
-                    })
-                    .map(|v| v.into())
-                    .collect::<Vec<_>>();
-                if v.is_empty() {
-                    None
-                } else {
-                    // Convert all values to strings
-                    Some(SimpleExpr::Tuple(v))
-                }
+                // Convert all values to strings
+                Some(SimpleExpr::Tuple(v))
             }
         }
         JsonValue::Object(_) => None,
     }
 }
 
+/// Numbers are passed to the database in their textual JSON representation,
+/// i. e. integers are never rounded through a floating point value.
+fn jsonb_number_expr(n: &serde_json::Number) -> SimpleExpr {
+    Expr::cust_with_values("CAST($1 AS jsonb)", [n.to_string()])
+}
+
+fn fov_number_value_to_expr(val: &serde_json::Value) -> Option<SimpleExpr> {
+    match val {
+        JsonValue::Number(n) => Some(jsonb_number_expr(n)),
+        JsonValue::Array(a) if !a.is_empty() && a.iter().all(|v| v.is_number()) => {
+            Some(SimpleExpr::Tuple(
+                a.iter()
+                    .filter_map(|v| v.as_number())
+                    .map(jsonb_number_expr)
+                    .collect(),
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Compares the JSON value of a document field with a number. The database
+/// compares jsonb numbers as arbitrary precision numerics, so neither large
+/// integers nor fractions lose their precision. Range comparisons only match
+/// fields that hold a number.
+fn fov_number_to_condition(
+    field_name: &str,
+    operation: super::search_documents::OperationWithValue,
+    value: SimpleExpr,
+) -> SimpleExpr {
+    let field_sql = format!(r#""d"."f"{}"#, field_path_json_native(field_name));
+    let field = Expr::expr(Expr::cust(field_sql.clone()));
+    let is_number = Expr::cust(format!("jsonb_typeof({field_sql}) = 'number'"));
+    match operation {
+        super::search_documents::OperationWithValue::Eq => field.eq(value),
+        super::search_documents::OperationWithValue::Ne => field.ne(value),
+        super::search_documents::OperationWithValue::Lt => is_number.and(field.lt(value)),
+        super::search_documents::OperationWithValue::Le => is_number.and(field.lte(value)),
+        super::search_documents::OperationWithValue::Gt => is_number.and(field.gt(value)),
+        super::search_documents::OperationWithValue::Ge => is_number.and(field.gte(value)),
+        super::search_documents::OperationWithValue::In => {
+            field.binary(sea_query::BinOper::In, value)
+        }
+        super::search_documents::OperationWithValue::NotIn => {
+            field.binary(sea_query::BinOper::NotIn, value)
+        }
+        super::search_documents::OperationWithValue::StartsWith
+        | super::search_documents::OperationWithValue::ContainsText
+        | super::search_documents::OperationWithValue::StartsWithCs
+        | super::search_documents::OperationWithValue::ContainsTextCs
+        | super::search_documents::OperationWithValue::ArrayContains
+        | super::search_documents::OperationWithValue::Regex
+        | super::search_documents::OperationWithValue::RegexI => Expr::cust("1 = 0"),
+    }
+}
+
+/// Matches the documents whose field is an array that contains the value.
+/// Only a single string or number is accepted, numbers match equal numbers
+/// regardless of their notation, e. g. `2` matches `2.0`.
+fn array_contains_condition(field_name: &str, value: &JsonValue) -> SimpleExpr {
+    let is_single_value = value.is_number() || value.as_str().is_some_and(|s| !s.is_empty());
+    if field_name == "author_id" || !is_single_value {
+        return Expr::cust("1 = 0");
+    }
+    let field_sql = format!(r#""d"."f"{}"#, field_path_json_native(field_name));
+    Expr::expr(Expr::cust(field_sql)).binary(
+        sea_query::BinOper::Custom("@>"),
+        Expr::cust_with_values(
+            "CAST($1 AS jsonb)",
+            [JsonValue::Array(vec![value.clone()]).to_string()],
+        ),
+    )
+}
+
+/// Matches the documents of the given owners. The owner column is compared
+/// without a cast to use its index, values that are no UUIDs cannot match.
+fn owner_in_condition(value: &JsonValue) -> SimpleExpr {
+    let owners: Vec<Uuid> = match value {
+        JsonValue::Array(values) => values
+            .iter()
+            .filter_map(|v| v.as_str())
+            .filter_map(|v| Uuid::parse_str(v).ok())
+            .collect(),
+        _ => vec![],
+    };
+    if owners.is_empty() {
+        return Expr::cust("1 = 0");
+    }
+    Expr::col((Alias::new("d"), Alias::new("owner"))).is_in(owners)
+}
+
 fn fov_to_condition(fov: &super::search_documents::SearchFilterFieldOpValue) -> SimpleExpr {
     let kill_clause = || Expr::cust("1 = 0");
     let field_name = fov.field();
+    if fov.operation() == super::search_documents::OperationWithValue::ArrayContains {
+        return array_contains_condition(field_name, fov.value());
+    }
+    if field_name == "author_id"
+        && fov.operation() == super::search_documents::OperationWithValue::In
+    {
+        return owner_in_condition(fov.value());
+    }
+    if field_name != "author_id" {
+        if let Some(value) = fov_number_value_to_expr(fov.value()) {
+            return fov_number_to_condition(field_name, fov.operation(), value);
+        }
+    }
     let value = fov_value_to_expr(fov.value());
     if value.is_none() {
         return kill_clause();
@@ -435,9 +1110,43 @@ fn fov_to_condition(fov: &super::search_documents::SearchFilterFieldOpValue) ->
             }
             Expr::expr(Func::lower(field)).like(format!("%{}%", value.to_lowercase()))
         }
+        super::search_documents::OperationWithValue::StartsWithCs => {
+            let value = fov.value().as_str().unwrap_or_default();
+            if value.is_empty() {
+                return kill_clause();
+            }
+            field.like(format!("{}%", value))
+        }
+        super::search_documents::OperationWithValue::ContainsTextCs => {
+            let value = fov.value().as_str().unwrap_or_default();
+            if value.is_empty() {
+                return kill_clause();
+            }
+            field.like(format!("%{}%", value))
+        }
+        super::search_documents::OperationWithValue::Regex => {
+            let value = fov.value().as_str().unwrap_or_default();
+            if value.is_empty() {
+                return kill_clause();
+            }
+            field.binary(PgBinOper::Regex, value)
+        }
+        super::search_documents::OperationWithValue::RegexI => {
+            let value = fov.value().as_str().unwrap_or_default();
+            if value.is_empty() {
+                return kill_clause();
+            }
+            field.binary(PgBinOper::RegexCaseInsensitive, value)
+        }
         super::search_documents::OperationWithValue::In => {
             field.binary(sea_query::BinOper::In, value)
         }
+        super::search_documents::OperationWithValue::NotIn => {
+            field.binary(sea_query::BinOper::NotIn, value)
+        }
+        super::search_documents::OperationWithValue::ArrayContains => {
+            array_contains_condition(field_name, fov.value())
+        }
     }
 }
 
@@ -450,40 +1159,111 @@ fn count_documents_sql(params: &DbListDocumentParams) -> SelectStatement {
     .to_owned()
 }
 
-fn select_documents_sql(params: &DbListDocumentParams) -> SelectStatement {
-    let j: SelectStatement = Query::select()
-        .expr(Expr::cust_with_expr(
-            r#"jsonb_object_agg("key", "value") as "new_f" from jsonb_each("f") as x("key", "value") WHERE "key" in $1"#,
-            SimpleExpr::Tuple(params.extra_fields.iter().cloned().map(|s| s.into()).collect()),
-        ))
-        .to_owned();
-    let (mut id_select, documents_alias) = base_documents_sql(params);
-    id_select
-        .distinct()
-        .column((documents_alias, DocumentsColumns::Id));
-
+/// Selects the requested fields of the documents whose ids are returned by
+/// `id_select`, all fields if `extra_fields` is [`ALL_FIELDS`].
+fn documents_projection_sql(
+    id_select: SelectStatement,
+    extra_fields: &[String],
+    sort_fields: &Option<String>,
+) -> SelectStatement {
     let documents_alias = Alias::new("d");
     let mut document_select = Query::select();
     document_select
         .column((documents_alias.clone(), CollectionDocument::Id))
-        .from_as(CollectionDocument::Table, documents_alias.clone())
-        .expr_as(Expr::cust(r#""t"."new_f""#), Alias::new("f"))
-        .join_lateral(
-            JoinType::InnerJoin,
-            j,
-            sea_orm::IntoIdentity::into_identity("t"),
-            Condition::all(),
-        )
-        .and_where(
-            Expr::col((documents_alias.clone(), CollectionDocument::Id)).in_subquery(id_select),
-        );
+        .from_as(CollectionDocument::Table, documents_alias.clone());
 
-    let sort_fields = sort_fields_parser(params.sort_fields.as_ref().cloned());
-    for sort_field in sort_fields {
-        document_select.order_by_expr(Expr::cust(sort_field.0), sort_field.1);
+    if extra_fields == [ALL_FIELDS] {
+        document_select.column((documents_alias.clone(), CollectionDocument::F));
+    } else {
+        let j: SelectStatement = Query::select()
+            .expr(Expr::cust_with_expr(
+                r#"jsonb_object_agg("key", "value") as "new_f" from jsonb_each("f") as x("key", "value") WHERE "key" in $1"#,
+                SimpleExpr::Tuple(extra_fields.iter().cloned().map(|s| s.into()).collect()),
+            ))
+            .to_owned();
+        document_select
+            .expr_as(Expr::cust(r#""t"."new_f""#), Alias::new("f"))
+            .join_lateral(
+                JoinType::InnerJoin,
+                j,
+                sea_orm::IntoIdentity::into_identity("t"),
+                Condition::all(),
+            );
+    }
+
+    document_select.and_where(
+        Expr::col((documents_alias.clone(), CollectionDocument::Id)).in_subquery(id_select),
+    );
+
+    let sort_fields = sort_fields_parser(sort_fields.as_ref().cloned());
+    for (sort_expr, order, nulls) in sort_fields {
+        match nulls {
+            Some(nulls) => {
+                document_select.order_by_expr_with_nulls(Expr::cust(sort_expr), order, nulls)
+            }
+            None => document_select.order_by_expr(Expr::cust(sort_expr), order),
+        };
+    }
+
+    document_select.to_owned()
+}
+
+/// Combines the document ids of all collections, each restricted by its own grants.
+fn multi_collection_ids_sql(params: &DbListMultiCollectionParams) -> SelectStatement {
+    let mut id_selects = params.collection_params().map(|collection_params| {
+        let (mut id_select, documents_alias) = base_documents_sql(&collection_params);
+        id_select.column((documents_alias, DocumentsColumns::Id));
+        id_select
+    });
+    let mut ids = id_selects
+        .next()
+        .expect("At least one collection is required");
+    for id_select in id_selects {
+        ids.union(UnionType::Distinct, id_select);
     }
+    ids
+}
+
+fn count_multi_collection_documents_sql(params: &DbListMultiCollectionParams) -> SelectStatement {
+    Query::select()
+        .expr(Func::count(Expr::col(Asterisk)))
+        .from_subquery(multi_collection_ids_sql(params), Alias::new("u"))
+        .to_owned()
+}
+
+fn select_multi_collection_documents_sql(params: &DbListMultiCollectionParams) -> SelectStatement {
+    let mut document_select = documents_projection_sql(
+        multi_collection_ids_sql(params),
+        &params.extra_fields,
+        &params.sort_fields,
+    );
+    let collection_alias = Alias::new("c");
+    document_select
+        .join_as(
+            JoinType::InnerJoin,
+            entity::collection::Entity,
+            collection_alias.clone(),
+            Expr::col((collection_alias.clone(), entity::collection::Column::Id))
+                .equals((Alias::new("d"), DocumentsColumns::CollectionId)),
+        )
+        .expr_as(
+            Expr::col((collection_alias, entity::collection::Column::Name)),
+            Alias::new("collection"),
+        )
+        .to_owned()
+}
+
+fn select_documents_sql(params: &DbListDocumentParams) -> SelectStatement {
+    let (mut id_select, documents_alias) = base_documents_sql(params);
+    id_select
+        .distinct()
+        .column((documents_alias, DocumentsColumns::Id));
+
+    let mut document_select =
+        documents_projection_sql(id_select, &params.extra_fields, &params.sort_fields);
+    let documents_alias = Alias::new("d");
 
-    if params.include_author_id {
+    if params.include_author_id || sorts_by_author_id(params.sort_fields.as_deref()) {
         let events_alias_name = "e";
         let events_alias = Alias::new(events_alias_name);
         document_select
@@ -511,34 +1291,249 @@ fn select_documents_sql(params: &DbListDocumentParams) -> SelectStatement {
             );
     }
 
+    if let Some(keyset) = params.keyset.as_ref() {
+        apply_keyset(&mut document_select, &params.sort_fields, keyset);
+    }
+
     document_select.to_owned()
 }
 
-fn sort_fields_parser(fields: Option<String>) -> Vec<(String, Order)> {
+/// Selects the sort key for the cursor, orders by the document id after the
+/// sort field and skips the documents up to the cursor position.
+fn apply_keyset(
+    document_select: &mut SelectStatement,
+    sort_fields: &Option<String>,
+    keyset: &Keyset,
+) {
+    let (sort_expr, order, nulls) = sort_fields_parser(sort_fields.clone())
+        .into_iter()
+        .next()
+        .expect("At least one sort field is required");
+    let id = Expr::col((Alias::new("d"), DocumentsColumns::Id));
+    document_select
+        .expr_as(Expr::cust(sort_expr.clone()), Alias::new(CURSOR_KEY_COLUMN))
+        .order_by_expr(id.clone().into(), order.clone());
+    if let Keyset::After(position) = keyset {
+        // Documents without the sort field come last in ascending order,
+        // unless `!` moves them to the end in descending order as well
+        let nulls_last = matches!(nulls, Some(NullOrdering::Last)) || order == Order::Asc;
+        let field = Expr::expr(Expr::cust(sort_expr.clone()));
+        let after_id = match order {
+            Order::Desc => id.lt(position.id),
+            _ => id.gt(position.id),
+        };
+        let condition = match cursor_key_expr(&sort_expr, &position.key) {
+            Some(key) => {
+                let after_key = match order {
+                    Order::Desc => field.clone().lt(key.clone()),
+                    _ => field.clone().gt(key.clone()),
+                };
+                let condition = Condition::any()
+                    .add(after_key)
+                    .add(field.clone().eq(key).and(after_id));
+                if nulls_last {
+                    condition.add(field.is_null())
+                } else {
+                    condition
+                }
+            }
+            None => {
+                let condition = Condition::any().add(field.clone().is_null().and(after_id));
+                if nulls_last {
+                    condition
+                } else {
+                    condition.add(field.is_not_null())
+                }
+            }
+        };
+        document_select.cond_where(condition);
+    }
+}
+
+/// Returns the sort key of the cursor as comparable value: text sort fields
+/// (`+`, `-`) are compared as text, JSON sort fields (`f`, `b`) as jsonb and
+/// the author as uuid. `None` for documents without the sort field.
+fn cursor_key_expr(sort_expr: &str, key: &JsonValue) -> Option<SimpleExpr> {
+    match key {
+        JsonValue::Null => None,
+        JsonValue::String(s) if sort_expr == AUTHOR_ID_SORT_EXPR => {
+            Some(Expr::cust_with_values("CAST($1 AS uuid)", [s.to_string()]))
+        }
+        _ if !sort_expr.contains("->>") => Some(Expr::cust_with_values(
+            "CAST($1 AS jsonb)",
+            [key.to_string()],
+        )),
+        JsonValue::String(s) => Some(Expr::value(s.to_string())),
+        _ => Some(Expr::value(key.to_string())),
+    }
+}
+
+fn matching_document_ids_sql(params: &DbListDocumentParams) -> SelectStatement {
+    let (mut id_select, documents_alias) = base_documents_sql(params);
+    id_select
+        .distinct()
+        .column((documents_alias.clone(), DocumentsColumns::Id))
+        .order_by((documents_alias, DocumentsColumns::Id), Order::Asc)
+        .to_owned()
+}
+
+/// Groups the matching documents by the value of the field. Documents
+/// without the field are counted with the value `NULL`.
+fn distinct_values_sql(
+    params: &DbListDocumentParams,
+    field_name: &str,
+    limit: u64,
+) -> SelectStatement {
+    let (mut id_select, documents_alias) = base_documents_sql(params);
+    id_select.column((documents_alias, DocumentsColumns::Id));
+
+    let values_alias = Alias::new("v");
+    let value_expr = Expr::cust(format!(r#""v"."f"{}"#, field_path_json(field_name)));
+    Query::select()
+        .expr_as(value_expr.clone(), Alias::new("value"))
+        .expr_as(Func::count(Expr::col(Asterisk)), Alias::new("count"))
+        .from_as(Documents, values_alias.clone())
+        .and_where(Expr::col((values_alias, DocumentsColumns::Id)).in_subquery(id_select))
+        .add_group_by([value_expr.clone()])
+        .order_by_expr(Expr::cust(r#""count""#), Order::Desc)
+        .order_by_expr(value_expr, Order::Asc)
+        .limit(limit)
+        .to_owned()
+}
+
+fn first_document_sql(params: &DbListDocumentParams) -> SelectStatement {
+    select_documents_sql(params)
+        .order_by((Alias::new("d"), DocumentsColumns::Id), Order::Asc)
+        .limit(1)
+        .to_owned()
+}
+
+fn document_changes_sql(params: &DbListDocumentParams, since: i64) -> SelectStatement {
+    let (mut id_select, documents_alias) = base_documents_sql(params);
+    id_select
+        .distinct()
+        .column((documents_alias.clone(), DocumentsColumns::Id));
+
+    Query::select()
+        .columns([
+            (documents_alias.clone(), DocumentsColumns::Id),
+            (documents_alias.clone(), DocumentsColumns::Seq),
+            (documents_alias.clone(), DocumentsColumns::F),
+        ])
+        .from_as(CollectionDocument::Table, documents_alias.clone())
+        .and_where(Expr::col((documents_alias.clone(), DocumentsColumns::Seq)).gt(since))
+        .and_where(
+            Expr::col((documents_alias.clone(), DocumentsColumns::Id)).in_subquery(id_select),
+        )
+        .order_by((documents_alias, DocumentsColumns::Seq), Order::Asc)
+        .limit(params.pagination.limit().into())
+        .to_owned()
+}
+
+/// Selects the first matching document and locks it for the current
+/// transaction. Documents that are locked by other transactions are skipped.
+pub(crate) fn claim_document_sql(params: &DbListDocumentParams) -> SelectStatement {
+    let (mut id_select, documents_alias) = base_documents_sql(params);
+    id_select
+        .distinct()
+        .column((documents_alias.clone(), DocumentsColumns::Id));
+
+    let mut document_select = Query::select();
+    document_select
+        .columns([
+            (documents_alias.clone(), DocumentsColumns::Id),
+            (documents_alias.clone(), DocumentsColumns::CollectionId),
+            (documents_alias.clone(), DocumentsColumns::Owner),
+            (documents_alias.clone(), DocumentsColumns::F),
+            (documents_alias.clone(), DocumentsColumns::Seq),
+            (documents_alias.clone(), DocumentsColumns::Locked),
+        ])
+        .from_as(CollectionDocument::Table, documents_alias.clone())
+        .and_where(
+            Expr::col((documents_alias.clone(), CollectionDocument::Id)).in_subquery(id_select),
+        )
+        // Claiming changes the document, locked documents are never claimed
+        .and_where(Expr::col((documents_alias.clone(), DocumentsColumns::Locked)).eq(false));
+    let sort_fields = sort_fields_parser(params.sort_fields.as_ref().cloned());
+    for (sort_expr, order, nulls) in sort_fields {
+        match nulls {
+            Some(nulls) => {
+                document_select.order_by_expr_with_nulls(Expr::cust(sort_expr), order, nulls)
+            }
+            None => document_select.order_by_expr(Expr::cust(sort_expr), order),
+        };
+    }
+    document_select
+        .limit(1)
+        .lock_with_tables_behavior(
+            LockType::Update,
+            [documents_alias],
+            LockBehavior::SkipLocked,
+        )
+        .to_owned()
+}
+
+/// Returns whether the sort fields contain `author_id+` or `author_id-`,
+/// which require the join with the creation events.
+pub(crate) fn sorts_by_author_id(sort_fields: Option<&str>) -> bool {
+    sort_fields.is_some_and(|sort_fields| {
+        sort_fields.split(',').any(|s| {
+            let s = s.strip_suffix('!').unwrap_or(s);
+            s.strip_suffix(['+', '-']) == Some(AUTHOR_ID_SORT_FIELD)
+        })
+    })
+}
+
+/// Parses the sort fields, e. g. `title+,price-!,item.priceb`.
+///
+/// The last character of a field is the direction (`+`, `-`, `f`, `b`), see
+/// `ListDocumentParams::sort_fields`. A `!` after the direction sorts the
+/// documents without the field at the end (`NULLS LAST`), for both directions.
+/// Without `!` the database default applies: `NULLS LAST` for ascending and
+/// `NULLS FIRST` for descending order.
+///
+/// `author_id+` and `author_id-` order by the joined author of the document,
+/// documents without creation event are treated like documents without the
+/// field.
+fn sort_fields_parser(fields: Option<String>) -> Vec<(String, Order, Option<NullOrdering>)> {
     fields
         .unwrap_or_else(|| "created+".to_string())
         .split(',')
         .map(|s| {
+            let (s, nulls) = match s.strip_suffix('!') {
+                Some(s) => (s, Some(NullOrdering::Last)),
+                None => (s, None),
+            };
             let mut char_vec_from_s = s.chars().collect::<Vec<char>>();
             let last_character = char_vec_from_s.pop().unwrap();
             let field_name = char_vec_from_s.into_iter().collect::<String>();
 
             match last_character {
+                '+' if field_name == AUTHOR_ID_SORT_FIELD => {
+                    (AUTHOR_ID_SORT_EXPR.to_string(), Order::Asc, nulls)
+                }
+                '-' if field_name == AUTHOR_ID_SORT_FIELD => {
+                    (AUTHOR_ID_SORT_EXPR.to_string(), Order::Desc, nulls)
+                }
                 '+' => (
                     format!(r#""d"."f"{}"#, field_path_json(&field_name)),
                     Order::Asc,
+                    nulls,
                 ),
                 '-' => (
                     format!(r#""d"."f"{}"#, field_path_json(&field_name)),
                     Order::Desc,
+                    nulls,
                 ),
                 'f' => (
                     format!(r#""d"."f"{}"#, field_path_json_native(&field_name)),
                     Order::Asc,
+                    nulls,
                 ),
                 'b' => (
                     format!(r#""d"."f"{}"#, field_path_json_native(&field_name)),
                     Order::Desc,
+                    nulls,
                 ),
                 _ => unreachable!(),
             }
@@ -570,24 +1565,72 @@ fn field_path_json(field_name: &str) -> String {
     format!(r#"->{field_path}->>{field_name}"#)
 }
 
+/// Returns the statement that creates an index on a document field of the
+/// collection. The indexed expression equals the filter expression of the
+/// field, see `field_path_json`. An existing index is kept. The index is built
+/// concurrently, so the statement must not run inside a transaction.
+pub(crate) fn field_index_sql(collection_id: Uuid, field_name: &str) -> String {
+    format!(
+        r#"CREATE INDEX CONCURRENTLY IF NOT EXISTS "{}" ON "collection_document" (("f"{})) WHERE "collection_id" = '{}'"#,
+        field_index_name(collection_id, field_name),
+        field_path_json(field_name),
+        collection_id,
+    )
+}
+
+/// The name is derived from the collection and the field, so the same field
+/// always maps to the same index. The field is hashed to keep the name within
+/// the 63 characters of a Postgres identifier, 8 bytes of the hash make a
+/// collision of two fields of a collection practically impossible.
+fn field_index_name(collection_id: Uuid, field_name: &str) -> String {
+    let digest = ring::digest::digest(&ring::digest::SHA256, field_name.as_bytes());
+    let field_hash: String = digest.as_ref()[..8]
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    format!("idx-document-{}-{field_hash}", collection_id.simple())
+}
+
+/// Creates the indexes of the document fields of a new collection. The
+/// indexes are built concurrently and must be created after the collection
+/// was committed. A failed build leaves an invalid index behind, which is
+/// dropped, otherwise `IF NOT EXISTS` would keep it.
+pub(crate) async fn create_field_indexes(
+    db: &DatabaseConnection,
+    collection_id: Uuid,
+    field_names: &BTreeSet<String>,
+) -> core::result::Result<(), DbErr> {
+    for field_name in field_names {
+        debug!("Creating index on {field_name} of collection {collection_id}");
+        if let Err(err) = db
+            .execute_unprepared(&field_index_sql(collection_id, field_name))
+            .await
+        {
+            let drop_sql = format!(
+                r#"DROP INDEX CONCURRENTLY IF EXISTS "{}""#,
+                field_index_name(collection_id, field_name)
+            );
+            if let Err(drop_err) = db.execute_unprepared(&drop_sql).await {
+                error!(
+                    "Cannot drop index on {field_name} of collection {collection_id}: {drop_err}"
+                );
+            }
+            return Err(err);
+        }
+    }
+    Ok(())
+}
+
 pub(crate) struct InsertDocumentData {
     pub(crate) collection_id: Uuid,
 }
 
+#[derive(Clone)]
 pub(crate) enum DbGrantUpdate {
     Keep,
     Replace(Vec<dto::GrantForDocument>),
 }
 
-impl From<GrantSettingsOnEvents> for DbGrantUpdate {
-    fn from(value: GrantSettingsOnEvents) -> Self {
-        match value {
-            GrantSettingsOnEvents::NoChange => Self::Keep,
-            GrantSettingsOnEvents::Replace(grants) => Self::Replace(grants),
-        }
-    }
-}
-
 pub(crate) async fn save_document_events_mails(
     txn: &DatabaseTransaction,
     user: &dto::User,
@@ -612,6 +1655,18 @@ pub(crate) async fn save_document_events_mails(
     save_documents_events_mails(txn, user, documents, events, grants, mails).await
 }
 
+/// The fields of an updated document with the next version: every write
+/// increments the stored version, whether it is an update by the user, a
+/// claim or an update by a hook, cron or recompute.
+fn with_next_version(f: JsonValue) -> SimpleExpr {
+    Expr::cust_with_values(
+        format!(
+            r#"$1 || jsonb_build_object('{VERSION_FIELD}', CASE WHEN jsonb_typeof("f"->'{VERSION_FIELD}') = 'number' THEN trunc(("f"->>'{VERSION_FIELD}')::numeric)::bigint ELSE 0 END + 1)"#
+        ),
+        [f],
+    )
+}
+
 pub(crate) async fn save_documents_events_mails(
     txn: &DatabaseTransaction,
     user: &dto::User,
@@ -651,22 +1706,29 @@ pub(crate) async fn save_documents_events_mails(
                     id: Set(*n.document.id()),
                     owner: Set(owner.id()),
                     collection_id: Set(collection_id),
-                    f: Set(n.document.fields().clone()),
+                    f: Set(without_version(n.document.fields().clone())),
+                    seq: NotSet,
+                    locked: NotSet,
                 }
                 .insert(txn)
                 .await
                 .context("Saving new document")?;
             }
             StoreDocument::Update { document } => {
-                entity::collection_document::ActiveModel {
-                    id: Set(*document.id()),
-                    owner: NotSet,
-                    collection_id: NotSet,
-                    f: Set(document.fields().clone()),
+                let result = Documents::update_many()
+                    // The trigger `collection_document_seq` assigns the next
+                    // sequence number in commit order
+                    .col_expr(
+                        DocumentsColumns::F,
+                        with_next_version(document.fields().clone()),
+                    )
+                    .filter(DocumentsColumns::Id.eq(*document.id()))
+                    .exec(txn)
+                    .await
+                    .context("Updating document")?;
+                if result.rows_affected == 0 {
+                    anyhow::bail!("Document {} not found for update", document.id());
                 }
-                .save(txn)
-                .await
-                .context("Updating document")?;
             }
         };
     }
@@ -709,12 +1771,14 @@ pub(crate) async fn save_documents_events_mails(
     }
 
     debug!("Try to create {} event(s)", all_events.len());
+    let event_collections = event_collection_names(txn, &all_events).await?;
     for event in all_events {
         // Create the event in the database
         let dbevent = entity::event::ActiveModel {
             id: NotSet,
             category_id: Set(event.category()),
             timestamp: NotSet,
+            published: NotSet,
             document_id: Set(event.document_id()),
             user: Set(user.id()),
             payload: Set(event.payload().clone()),
@@ -722,6 +1786,9 @@ pub(crate) async fn save_documents_events_mails(
         let res = dbevent.save(txn).await.context("Saving event")?;
 
         debug!("Event {} saved", res.id.unwrap());
+        if let Some(collection_name) = event_collections.get(&event.document_id()) {
+            EVENT_METRICS.count_created(collection_name, event.category());
+        }
     }
 
     debug!("Trying to store {} mail(s) in queue", mails.len());
@@ -733,6 +1800,8 @@ pub(crate) async fn save_documents_events_mails(
             owner: Set(*crate::cron::CRON_USER_ID),
             collection_id: Set(*crate::mail::FOLIVAFY_MAIL_COLLECTION_ID),
             f: Set(document_fields),
+            seq: NotSet,
+            locked: NotSet,
         }
         .insert(txn)
         .await
@@ -741,6 +1810,30 @@ pub(crate) async fn save_documents_events_mails(
     Ok(())
 }
 
+/// Loads the collection names of the documents the events belong to.
+async fn event_collection_names(
+    txn: &DatabaseTransaction,
+    events: &[Event],
+) -> Result<HashMap<Uuid, String>> {
+    let mut document_ids: Vec<Uuid> = events.iter().map(|e| e.document_id()).collect();
+    document_ids.sort();
+    document_ids.dedup();
+    if document_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let names: Vec<(Uuid, String)> = Documents::find()
+        .select_only()
+        .column(DocumentsColumns::Id)
+        .column(entity::collection::Column::Name)
+        .inner_join(entity::collection::Entity)
+        .filter(DocumentsColumns::Id.is_in(document_ids))
+        .into_tuple()
+        .all(txn)
+        .await
+        .context("Loading collections of events")?;
+    Ok(names.into_iter().collect())
+}
+
 pub(crate) async fn replace_grants(
     txn: &DatabaseTransaction,
     grants: Vec<dto::GrantForDocument>,
@@ -778,11 +1871,105 @@ pub(crate) async fn replace_grants(
     Ok(())
 }
 
+/// Loads the grants of a document.
+pub(crate) async fn get_document_grants<C: ConnectionTrait>(
+    db: &C,
+    document: &entity::collection_document::Model,
+) -> core::result::Result<Vec<dto::Grant>, DbErr> {
+    let grants = document.find_related(entity::grant::Entity).all(db).await?;
+    Ok(grants.iter().map(dto::Grant::from).collect())
+}
+
+/// Loads a document of the named collection. Returns `None` if the collection
+/// does not contain the document or the document is deleted.
+pub(crate) async fn get_parent_document<C: ConnectionTrait>(
+    db: &C,
+    collection_name: &str,
+    document_uuid: Uuid,
+) -> core::result::Result<Option<entity::collection_document::Model>, DbErr> {
+    let document = Documents::find_by_id(document_uuid)
+        .inner_join(entity::collection::Entity)
+        .filter(entity::collection::Column::Name.eq(collection_name))
+        .one(db)
+        .await?;
+    Ok(document.filter(|document| !document.is_deleted()))
+}
+
+/// Loads the grants of a document in the named collection. Returns `None` if
+/// the collection does not contain the document or the document is deleted.
+pub(crate) async fn get_parent_document_grants<C: ConnectionTrait>(
+    db: &C,
+    collection_name: &str,
+    document_uuid: Uuid,
+) -> core::result::Result<Option<Vec<dto::Grant>>, DbErr> {
+    match get_parent_document(db, collection_name, document_uuid).await? {
+        Some(document) => get_document_grants(db, &document).await.map(Some),
+        None => Ok(None),
+    }
+}
+
 pub(crate) async fn get_document_by_id(
     document_uuid: Uuid,
     db: &DatabaseConnection,
 ) -> core::result::Result<Option<entity::collection_document::Model>, DbErr> {
-    Documents::find_by_id(document_uuid).one(db).await
+    with_retry(&DB_RETRY_POLICY, || {
+        Documents::find_by_id(document_uuid).one(db)
+    })
+    .await
+}
+
+/// Sets the lock of the document, returns `false` if the collection does not
+/// contain the document.
+pub(crate) async fn set_document_locked(
+    db: &DatabaseConnection,
+    collection_id: Uuid,
+    document_uuid: Uuid,
+    locked: bool,
+) -> core::result::Result<bool, DbErr> {
+    let result = Documents::update_many()
+        .col_expr(DocumentsColumns::Locked, Expr::value(locked))
+        .filter(DocumentsColumns::Id.eq(document_uuid))
+        .filter(DocumentsColumns::CollectionId.eq(collection_id))
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected > 0)
+}
+
+pub(crate) async fn set_collection_config(
+    db: &DatabaseConnection,
+    collection_id: Uuid,
+    config: &CollectionConfig,
+) -> core::result::Result<(), DbErr> {
+    entity::collection::Entity::update_many()
+        .col_expr(
+            entity::collection::Column::Config,
+            Expr::value(config.to_json()),
+        )
+        .filter(entity::collection::Column::Id.eq(collection_id))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/// Stores the title, the owner access only flag and the lock of the collection.
+pub(crate) async fn set_collection_properties<C: ConnectionTrait>(
+    db: &C,
+    collection: &Model,
+) -> core::result::Result<(), DbErr> {
+    entity::collection::Entity::update_many()
+        .col_expr(
+            entity::collection::Column::Title,
+            Expr::value(collection.title.clone()),
+        )
+        .col_expr(entity::collection::Column::Oao, Expr::value(collection.oao))
+        .col_expr(
+            entity::collection::Column::Locked,
+            Expr::value(collection.locked),
+        )
+        .filter(entity::collection::Column::Id.eq(collection.id))
+        .exec(db)
+        .await?;
+    Ok(())
 }
 
 pub(crate) async fn get_document_by_id_in_trx(
@@ -798,6 +1985,7 @@ pub(crate) async fn get_accessible_document(
     user_id: Uuid,
     collection: &Model,
     document_uuid: Uuid,
+    include_deleted: bool,
 ) -> result::Result<Option<entity::collection_document::Model>, ApiErrors> {
     let doc = get_document_by_id(document_uuid, &ctx.db)
         .await?
@@ -809,14 +1997,14 @@ pub(crate) async fn get_accessible_document(
     let doc = doc.unwrap();
 
     // Load referenced document grants:
-    let document_grants = doc
-        .find_related(entity::grant::Entity)
-        .all(&ctx.db)
-        .await
-        .map_err(|e| {
-            error!("Error loading document ({document_uuid}) grants: {}", e);
-            ApiErrors::InternalServerError
-        })?;
+    let document_grants = with_retry(&DB_RETRY_POLICY, || {
+        doc.find_related(entity::grant::Entity).all(&ctx.db)
+    })
+    .await
+    .map_err(|e| {
+        error!("Error loading document ({document_uuid}) grants: {}", e);
+        ApiErrors::InternalServerError
+    })?;
 
     // Compare user grants with document grants
     let intersection = user_grants.iter().any(|user_grant| {
@@ -830,7 +2018,7 @@ pub(crate) async fn get_accessible_document(
     }
 
     // Do not provide document if it has been deleted
-    if doc.is_deleted() {
+    if doc.is_deleted() && !include_deleted {
         debug!("Document ({document_uuid}) is deleted",);
         return Ok(None);
     }
@@ -851,7 +2039,7 @@ mod tests {
     };
     use crate::api::{
         grants::{default_user_grants, DefaultUserGrantsParameters},
-        list_documents::ListDocumentParams,
+        list_documents::{parse_pfilter, ListDocumentParams},
     };
 
     use super::*;
@@ -860,34 +2048,70 @@ mod tests {
     fn param_validation_test() {
         let all_fields_empty = ListDocumentParams {
             exact_title: None,
+            title_field: None,
             extra_fields: None,
             sort_fields: None,
             pfilter: None,
+            created_after: None,
+            created_before: None,
+            include_deleted: None,
+            shared_with_me: None,
+            approximate_count: None,
+            explain_filter: None,
+            cursor: None,
+            with_total: None,
         };
 
         assert!(all_fields_empty.validate().is_ok());
 
         let valid_sort_fields = ListDocumentParams {
             exact_title: None,
+            title_field: None,
             extra_fields: None,
-            sort_fields: Some("title+,price-,length-".to_string()),
+            sort_fields: Some("title+,price-!,length-".to_string()),
             pfilter: None,
+            created_after: None,
+            created_before: None,
+            include_deleted: None,
+            shared_with_me: None,
+            approximate_count: None,
+            explain_filter: None,
+            cursor: None,
+            with_total: None,
         };
         assert!(valid_sort_fields.validate().is_ok());
 
         let invalid_sort_fields = ListDocumentParams {
             exact_title: None,
+            title_field: None,
             extra_fields: None,
             sort_fields: Some("title,price-".to_string()),
             pfilter: None,
+            created_after: None,
+            created_before: None,
+            include_deleted: None,
+            shared_with_me: None,
+            approximate_count: None,
+            explain_filter: None,
+            cursor: None,
+            with_total: None,
         };
         assert!(invalid_sort_fields.validate().is_err());
 
         let invalid_extra_fields = ListDocumentParams {
             exact_title: None,
+            title_field: None,
             extra_fields: Some("title📣".to_string()),
             sort_fields: None,
             pfilter: None,
+            created_after: None,
+            created_before: None,
+            include_deleted: None,
+            shared_with_me: None,
+            approximate_count: None,
+            explain_filter: None,
+            cursor: None,
+            with_total: None,
         };
         assert!(invalid_extra_fields.validate().is_err());
     }
@@ -904,9 +2128,9 @@ mod tests {
         assert_eq!(
             sql,
             vec![
-                ("\"d\".\"f\"->>'title'".to_string(), Order::Asc),
-                ("\"d\".\"f\"->'price'".to_string(), Order::Desc),
-                ("\"d\".\"f\"->'length'".to_string(), Order::Asc),
+                ("\"d\".\"f\"->>'title'".to_string(), Order::Asc, None),
+                ("\"d\".\"f\"->'price'".to_string(), Order::Desc, None),
+                ("\"d\".\"f\"->'length'".to_string(), Order::Asc, None),
             ]
         );
     }
@@ -923,9 +2147,9 @@ mod tests {
         assert_eq!(
             sql,
             vec![
-                ("\"d\".\"f\"->>'title'".to_string(), Order::Asc),
-                ("\"d\".\"f\"->>'price'".to_string(), Order::Desc),
-                ("\"d\".\"f\"->>'length'".to_string(), Order::Desc),
+                ("\"d\".\"f\"->>'title'".to_string(), Order::Asc, None),
+                ("\"d\".\"f\"->>'price'".to_string(), Order::Desc, None),
+                ("\"d\".\"f\"->>'length'".to_string(), Order::Desc, None),
             ]
         );
     }
@@ -942,9 +2166,17 @@ mod tests {
         assert_eq!(
             sql,
             vec![
-                ("\"d\".\"f\"->>'title'".to_string(), Order::Asc),
-                ("\"d\".\"f\"->'company'->>'title'".to_string(), Order::Desc),
-                ("\"d\".\"f\"->'supplier'->>'city'".to_string(), Order::Asc),
+                ("\"d\".\"f\"->>'title'".to_string(), Order::Asc, None),
+                (
+                    "\"d\".\"f\"->'company'->>'title'".to_string(),
+                    Order::Desc,
+                    None
+                ),
+                (
+                    "\"d\".\"f\"->'supplier'->>'city'".to_string(),
+                    Order::Asc,
+                    None
+                ),
             ]
         );
     }
@@ -961,9 +2193,45 @@ mod tests {
         assert_eq!(
             sql,
             vec![
-                ("\"d\".\"f\"->>'title'".to_string(), Order::Asc),
-                ("\"d\".\"f\"->'item'->'price'".to_string(), Order::Desc),
-                ("\"d\".\"f\"->'m'->'length'".to_string(), Order::Asc),
+                ("\"d\".\"f\"->>'title'".to_string(), Order::Asc, None),
+                (
+                    "\"d\".\"f\"->'item'->'price'".to_string(),
+                    Order::Desc,
+                    None
+                ),
+                ("\"d\".\"f\"->'m'->'length'".to_string(), Order::Asc, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn sort_fields_sql_test_nulls_last() {
+        // Arrange
+        let sort_fields = "price-!,title+!,item.priceb!,length-".to_string();
+
+        // Act
+        let sql = sort_fields_parser(Some(sort_fields));
+
+        // Assert
+        assert_eq!(
+            sql,
+            vec![
+                (
+                    "\"d\".\"f\"->>'price'".to_string(),
+                    Order::Desc,
+                    Some(NullOrdering::Last)
+                ),
+                (
+                    "\"d\".\"f\"->>'title'".to_string(),
+                    Order::Asc,
+                    Some(NullOrdering::Last)
+                ),
+                (
+                    "\"d\".\"f\"->'item'->'price'".to_string(),
+                    Order::Desc,
+                    Some(NullOrdering::Last)
+                ),
+                ("\"d\".\"f\"->>'length'".to_string(), Order::Desc, None),
             ]
         );
     }
@@ -1034,45 +2302,45 @@ mod tests {
     }
 
     #[test]
-    fn test_count_documents_query3() {
+    fn it_groups_distinct_values_of_accessible_documents() {
         // Arrange
         let collection = Uuid::new_v4();
-        let sort_fields = "created+".to_string();
         let grants = default_user_grants(
             DefaultUserGrantsParameters::builder()
-                .visibility(CollectionDocumentVisibility::PrivateAndUserCanAccessAllDocuments)
+                .visibility(CollectionDocumentVisibility::PublicAndUserIsReader)
                 .collection_uuid(collection)
                 .build(),
         );
         let params = DbListDocumentParams::builder()
             .collection(collection)
-            .extra_fields(vec!["title".to_string()])
-            .sort_fields(Some(sort_fields))
+            .extra_fields(vec![])
+            .sort_fields(None)
             .filters(vec![].into())
             .grants(Restricted(grants))
             .include_author_id(false)
             .build();
 
         // Act
-        let sql = count_documents_sql(&params).to_string(PostgresQueryBuilder);
+        let sql =
+            distinct_values_sql(&params, "workflow.status", 11).to_string(PostgresQueryBuilder);
+
         // Assert
         assert_eq!(
             sql,
             format!(
-                r#"SELECT COUNT(DISTINCT "d"."id") FROM "collection_document" AS "d" JOIN "grant" ON "d"."id" = "grant"."document_id" WHERE "collection_id" = '{collection}' AND ("grant"."realm" = 'read-all-collection' AND "grant"."grant" = '{collection}')"#
+                r#"SELECT "v"."f"->'workflow'->>'status' AS "value", COUNT(*) AS "count" FROM "collection_document" AS "v" WHERE "v"."id" IN (SELECT "d"."id" FROM "collection_document" AS "d" JOIN "grant" ON "d"."id" = "grant"."document_id" WHERE "collection_id" = '{collection}' AND ("grant"."realm" = 'read-collection' AND "grant"."grant" = '{collection}')) GROUP BY "v"."f"->'workflow'->>'status' ORDER BY "count" DESC, "v"."f"->'workflow'->>'status' ASC LIMIT 11"#
             )
         );
     }
 
     #[test]
-    fn test_select_documents_sql_basic_query() {
+    fn test_count_documents_query3() {
         // Arrange
         let collection = Uuid::new_v4();
-        let userid = Uuid::new_v4();
         let sort_fields = "created+".to_string();
         let grants = default_user_grants(
             DefaultUserGrantsParameters::builder()
-                .visibility(CollectionDocumentVisibility::PrivateAndUserIs(userid))
+                .visibility(CollectionDocumentVisibility::PrivateAndUserCanAccessAllDocuments)
                 .collection_uuid(collection)
                 .build(),
         );
@@ -1086,35 +2354,25 @@ mod tests {
             .build();
 
         // Act
-        let sql = select_documents_sql(&params).to_string(PostgresQueryBuilder);
-
+        let sql = count_documents_sql(&params).to_string(PostgresQueryBuilder);
         // Assert
         assert_eq!(
             sql,
             format!(
-                r#"SELECT "d"."id", "t"."new_f" AS "f" FROM "collection_document" AS "d" INNER JOIN LATERAL (SELECT jsonb_object_agg("key", "value") as "new_f" from jsonb_each("f") as x("key", "value") WHERE "key" in ('title')) AS "t" ON TRUE WHERE "d"."id" IN (SELECT DISTINCT "d"."id" FROM "collection_document" AS "d" JOIN "grant" ON "d"."id" = "grant"."document_id" WHERE "collection_id" = '{collection}' AND ("grant"."realm" = 'author' AND "grant"."grant" = '{userid}')) ORDER BY "d"."f"->>'created' ASC"#
+                r#"SELECT COUNT(DISTINCT "d"."id") FROM "collection_document" AS "d" JOIN "grant" ON "d"."id" = "grant"."document_id" WHERE "collection_id" = '{collection}' AND ("grant"."realm" = 'read-all-collection' AND "grant"."grant" = '{collection}')"#
             )
         );
     }
 
     #[test]
-    fn test_select_documents_sql_query2() {
+    fn test_select_documents_sql_basic_query() {
         // Arrange
         let collection = Uuid::new_v4();
+        let userid = Uuid::new_v4();
         let sort_fields = "created+".to_string();
-        let filters = vec![
-            FieldFilter::ExactFieldMatch {
-                field_name: "orgaddr.zip".to_string(),
-                value: "11101".to_string(),
-            },
-            FieldFilter::FieldValueInMatch {
-                field_name: "wf1.seq".to_string(),
-                values: vec!["1".to_string(), "2".to_string()],
-            },
-        ];
         let grants = default_user_grants(
             DefaultUserGrantsParameters::builder()
-                .visibility(CollectionDocumentVisibility::PublicAndUserIsReader)
+                .visibility(CollectionDocumentVisibility::PrivateAndUserIs(userid))
                 .collection_uuid(collection)
                 .build(),
         );
@@ -1122,7 +2380,7 @@ mod tests {
             .collection(collection)
             .extra_fields(vec!["title".to_string()])
             .sort_fields(Some(sort_fields))
-            .filters(filters.into())
+            .filters(vec![].into())
             .grants(Restricted(grants))
             .include_author_id(false)
             .build();
@@ -1134,34 +2392,21 @@ mod tests {
         assert_eq!(
             sql,
             format!(
-                r#"SELECT "d"."id", "t"."new_f" AS "f" FROM "collection_document" AS "d" INNER JOIN LATERAL (SELECT jsonb_object_agg("key", "value") as "new_f" from jsonb_each("f") as x("key", "value") WHERE "key" in ('title')) AS "t" ON TRUE WHERE "d"."id" IN (SELECT DISTINCT "d"."id" FROM "collection_document" AS "d" JOIN "grant" ON "d"."id" = "grant"."document_id" WHERE "collection_id" = '{collection}' AND ("grant"."realm" = 'read-collection' AND "grant"."grant" = '{collection}') AND (("d"."f"->'orgaddr'->>'zip') = '11101' AND ("d"."f"->'wf1'->>'seq') IN ('1', '2'))) ORDER BY "d"."f"->>'created' ASC"#
+                r#"SELECT "d"."id", "t"."new_f" AS "f" FROM "collection_document" AS "d" INNER JOIN LATERAL (SELECT jsonb_object_agg("key", "value") as "new_f" from jsonb_each("f") as x("key", "value") WHERE "key" in ('title')) AS "t" ON TRUE WHERE "d"."id" IN (SELECT DISTINCT "d"."id" FROM "collection_document" AS "d" JOIN "grant" ON "d"."id" = "grant"."document_id" WHERE "collection_id" = '{collection}' AND ("grant"."realm" = 'author' AND "grant"."grant" = '{userid}')) ORDER BY "d"."f"->>'created' ASC"#
             )
         );
     }
 
     #[test]
-    fn test_select_documents_sql_query1() {
+    fn it_sorts_missing_values_last() {
         // Arrange
         let collection = Uuid::new_v4();
-        let userid = Uuid::new_v4();
-        let sort_fields = "created+".to_string();
-        let filters = vec![CronDocumentSelector::ByFieldEqualsValue {
-            field: "orgaddr.zip".to_string(),
-            value: "11101".to_string(),
-        }
-        .into()];
-        let grants = default_user_grants(
-            DefaultUserGrantsParameters::builder()
-                .visibility(CollectionDocumentVisibility::PrivateAndUserIs(userid))
-                .collection_uuid(collection)
-                .build(),
-        );
         let params = DbListDocumentParams::builder()
             .collection(collection)
             .extra_fields(vec!["title".to_string()])
-            .sort_fields(Some(sort_fields))
-            .filters(filters.into())
-            .grants(Restricted(grants))
+            .sort_fields(Some("price-!,title+".to_string()))
+            .filters(vec![].into())
+            .grants(ListDocumentGrants::IgnoredForAdmin)
             .include_author_id(false)
             .build();
 
@@ -1172,14 +2417,444 @@ mod tests {
         assert_eq!(
             sql,
             format!(
-                r#"SELECT "d"."id", "t"."new_f" AS "f" FROM "collection_document" AS "d" INNER JOIN LATERAL (SELECT jsonb_object_agg("key", "value") as "new_f" from jsonb_each("f") as x("key", "value") WHERE "key" in ('title')) AS "t" ON TRUE WHERE "d"."id" IN (SELECT DISTINCT "d"."id" FROM "collection_document" AS "d" JOIN "grant" ON "d"."id" = "grant"."document_id" WHERE "collection_id" = '{collection}' AND ("grant"."realm" = 'author' AND "grant"."grant" = '{userid}') AND ("d"."f"->'orgaddr'->>'zip') = '11101') ORDER BY "d"."f"->>'created' ASC"#
+                r#"SELECT "d"."id", "t"."new_f" AS "f" FROM "collection_document" AS "d" INNER JOIN LATERAL (SELECT jsonb_object_agg("key", "value") as "new_f" from jsonb_each("f") as x("key", "value") WHERE "key" in ('title')) AS "t" ON TRUE WHERE "d"."id" IN (SELECT DISTINCT "d"."id" FROM "collection_document" AS "d" WHERE "collection_id" = '{collection}') ORDER BY "d"."f"->>'price' DESC NULLS LAST, "d"."f"->>'title' ASC"#
             )
         );
     }
 
-    #[test]
-    fn test_select_documents_sql_query3() {
-        // Arrange
+    fn keyset_sql(sort_fields: &str, keyset: Keyset) -> String {
+        let params = DbListDocumentParams::builder()
+            .collection(Uuid::nil())
+            .extra_fields(vec!["title".to_string()])
+            .sort_fields(Some(sort_fields.to_string()))
+            .filters(vec![].into())
+            .grants(ListDocumentGrants::IgnoredForAdmin)
+            .include_author_id(false)
+            .keyset(Some(keyset))
+            .build();
+        select_documents_sql(&params)
+            .to_string(PostgresQueryBuilder)
+            .replace(
+                r#" INNER JOIN LATERAL (SELECT jsonb_object_agg("key", "value") as "new_f" from jsonb_each("f") as x("key", "value") WHERE "key" in ('title')) AS "t" ON TRUE WHERE "d"."id" IN (SELECT DISTINCT "d"."id" FROM "collection_document" AS "d" WHERE "collection_id" = '00000000-0000-0000-0000-000000000000')"#,
+                " ...",
+            )
+    }
+
+    #[test]
+    fn it_orders_the_first_keyset_page_by_id() {
+        assert_eq!(
+            keyset_sql("title+", Keyset::Start),
+            r#"SELECT "d"."id", "t"."new_f" AS "f", "d"."f"->>'title' AS "cursor_key" FROM "collection_document" AS "d" ... ORDER BY "d"."f"->>'title' ASC, "d"."id" ASC"#
+        );
+    }
+
+    #[test]
+    fn it_continues_after_the_cursor_position() {
+        // Arrange
+        let id = Uuid::parse_str("3a8ed6a6-9cd7-4a4e-a0b5-42bfbd8e0c01").unwrap();
+        let after = |key: JsonValue| Keyset::After(CursorPosition { key, id });
+
+        // Act
+        let ascending = keyset_sql("title+", after(serde_json::json!("Circle")));
+        let descending = keyset_sql("title-", after(serde_json::json!("Circle")));
+        let nulls_last = keyset_sql("title-!", after(serde_json::json!("Circle")));
+        let missing_value = keyset_sql("title-", after(JsonValue::Null));
+        let native = keyset_sql("size.widthf", after(serde_json::json!(12)));
+
+        // Assert
+        assert_eq!(
+            ascending,
+            r#"SELECT "d"."id", "t"."new_f" AS "f", "d"."f"->>'title' AS "cursor_key" FROM "collection_document" AS "d" ... AND (("d"."f"->>'title') > 'Circle' OR (("d"."f"->>'title') = 'Circle' AND "d"."id" > '3a8ed6a6-9cd7-4a4e-a0b5-42bfbd8e0c01') OR ("d"."f"->>'title') IS NULL) ORDER BY "d"."f"->>'title' ASC, "d"."id" ASC"#
+        );
+        assert_eq!(
+            descending,
+            r#"SELECT "d"."id", "t"."new_f" AS "f", "d"."f"->>'title' AS "cursor_key" FROM "collection_document" AS "d" ... AND (("d"."f"->>'title') < 'Circle' OR (("d"."f"->>'title') = 'Circle' AND "d"."id" < '3a8ed6a6-9cd7-4a4e-a0b5-42bfbd8e0c01')) ORDER BY "d"."f"->>'title' DESC, "d"."id" DESC"#
+        );
+        assert_eq!(
+            nulls_last,
+            r#"SELECT "d"."id", "t"."new_f" AS "f", "d"."f"->>'title' AS "cursor_key" FROM "collection_document" AS "d" ... AND (("d"."f"->>'title') < 'Circle' OR (("d"."f"->>'title') = 'Circle' AND "d"."id" < '3a8ed6a6-9cd7-4a4e-a0b5-42bfbd8e0c01') OR ("d"."f"->>'title') IS NULL) ORDER BY "d"."f"->>'title' DESC NULLS LAST, "d"."id" DESC"#
+        );
+        assert_eq!(
+            missing_value,
+            r#"SELECT "d"."id", "t"."new_f" AS "f", "d"."f"->>'title' AS "cursor_key" FROM "collection_document" AS "d" ... AND ((("d"."f"->>'title') IS NULL AND "d"."id" < '3a8ed6a6-9cd7-4a4e-a0b5-42bfbd8e0c01') OR ("d"."f"->>'title') IS NOT NULL) ORDER BY "d"."f"->>'title' DESC, "d"."id" DESC"#
+        );
+        assert_eq!(
+            native,
+            r#"SELECT "d"."id", "t"."new_f" AS "f", "d"."f"->'size'->'width' AS "cursor_key" FROM "collection_document" AS "d" ... AND (("d"."f"->'size'->'width') > (CAST('12' AS jsonb)) OR (("d"."f"->'size'->'width') = (CAST('12' AS jsonb)) AND "d"."id" > '3a8ed6a6-9cd7-4a4e-a0b5-42bfbd8e0c01') OR ("d"."f"->'size'->'width') IS NULL) ORDER BY "d"."f"->'size'->'width' ASC, "d"."id" ASC"#
+        );
+    }
+
+    #[test]
+    fn it_selects_the_first_document_with_id_tiebreaker() {
+        // Arrange
+        let collection = Uuid::new_v4();
+        let params = DbListDocumentParams::builder()
+            .collection(collection)
+            .extra_fields(vec!["title".to_string()])
+            .sort_fields(Some("priority-".to_string()))
+            .filters(vec![].into())
+            .grants(ListDocumentGrants::IgnoredForAdmin)
+            .include_author_id(false)
+            .build();
+
+        // Act
+        let sql = first_document_sql(&params).to_string(PostgresQueryBuilder);
+
+        // Assert
+        assert_eq!(
+            sql,
+            format!(
+                r#"SELECT "d"."id", "t"."new_f" AS "f" FROM "collection_document" AS "d" INNER JOIN LATERAL (SELECT jsonb_object_agg("key", "value") as "new_f" from jsonb_each("f") as x("key", "value") WHERE "key" in ('title')) AS "t" ON TRUE WHERE "d"."id" IN (SELECT DISTINCT "d"."id" FROM "collection_document" AS "d" WHERE "collection_id" = '{collection}') ORDER BY "d"."f"->>'priority' DESC, "d"."id" ASC LIMIT 1"#
+            )
+        );
+    }
+
+    #[test]
+    fn it_reads_the_estimated_rows_of_the_query_plan() {
+        // Arrange
+        let plan = serde_json::json!([{"Plan": {"Node Type": "Unique", "Plan Rows": 48213.0}}]);
+
+        // Act
+        let rows = planned_rows(&plan);
+
+        // Assert
+        assert_eq!(rows, 48213);
+        assert_eq!(planned_rows(&serde_json::json!([])), 0);
+    }
+
+    #[test]
+    fn it_tells_whether_more_documents_follow_the_page() {
+        // Arrange
+        let mut with_extra_row = vec![1, 2, 3, 4];
+        let mut full_page = vec![1, 2, 3];
+        let mut short_page = vec![1, 2];
+
+        // Act
+        let more_after_extra_row = trim_page(&mut with_extra_row, 3);
+        let more_after_full_page = trim_page(&mut full_page, 3);
+        let more_after_short_page = trim_page(&mut short_page, 3);
+
+        // Assert
+        assert!(more_after_extra_row);
+        assert_eq!(with_extra_row, vec![1, 2, 3]);
+        assert!(!more_after_full_page);
+        assert_eq!(full_page, vec![1, 2, 3]);
+        assert!(!more_after_short_page);
+        assert_eq!(short_page, vec![1, 2]);
+    }
+
+    #[test]
+    fn it_only_falls_back_to_the_estimate_on_statement_timeouts() {
+        assert!(!is_statement_timeout(&DbErr::RecordNotFound(
+            "count".to_string()
+        )));
+        assert!(!is_statement_timeout(&DbErr::Query(RuntimeErr::Internal(
+            "connection reset".to_string()
+        ))));
+    }
+
+    #[test]
+    fn it_counts_only_documents_without_grants() {
+        // Arrange
+        let collection = Uuid::new_v4();
+        let params = DbListDocumentParams::builder()
+            .collection(collection)
+            .extra_fields(vec!["title".to_string()])
+            .sort_fields(None)
+            .filters(vec![].into())
+            .grants(ListDocumentGrants::Orphans)
+            .include_author_id(false)
+            .build();
+
+        // Act
+        let sql = count_documents_sql(&params).to_string(PostgresQueryBuilder);
+
+        // Assert
+        assert_eq!(
+            sql,
+            format!(
+                r#"SELECT COUNT(DISTINCT "d"."id") FROM "collection_document" AS "d" WHERE "collection_id" = '{collection}' AND (NOT EXISTS(SELECT 1 FROM "grant" WHERE "grant"."document_id" = "d"."id"))"#
+            )
+        );
+    }
+
+    #[test]
+    fn it_selects_the_matching_document_ids() {
+        // Arrange
+        let collection = Uuid::new_v4();
+        let params = DbListDocumentParams::builder()
+            .collection(collection)
+            .extra_fields(vec![])
+            .sort_fields(None)
+            .filters(
+                vec![FieldFilter::ExactFieldMatch {
+                    field_name: "state".to_string(),
+                    value: "open".to_string(),
+                }]
+                .into(),
+            )
+            .grants(ListDocumentGrants::IgnoredForAdmin)
+            .include_author_id(false)
+            .build();
+
+        // Act
+        let sql = matching_document_ids_sql(&params).to_string(PostgresQueryBuilder);
+
+        // Assert
+        assert_eq!(
+            sql,
+            format!(
+                r#"SELECT DISTINCT "d"."id" FROM "collection_document" AS "d" WHERE "collection_id" = '{collection}' AND ("d"."f"->>'state') = 'open' ORDER BY "d"."id" ASC"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_select_documents_sql_query2() {
+        // Arrange
+        let collection = Uuid::new_v4();
+        let sort_fields = "created+".to_string();
+        let filters = vec![
+            FieldFilter::ExactFieldMatch {
+                field_name: "orgaddr.zip".to_string(),
+                value: "11101".to_string(),
+            },
+            FieldFilter::FieldValueInMatch {
+                field_name: "wf1.seq".to_string(),
+                values: vec!["1".to_string(), "2".to_string()],
+            },
+        ];
+        let grants = default_user_grants(
+            DefaultUserGrantsParameters::builder()
+                .visibility(CollectionDocumentVisibility::PublicAndUserIsReader)
+                .collection_uuid(collection)
+                .build(),
+        );
+        let params = DbListDocumentParams::builder()
+            .collection(collection)
+            .extra_fields(vec!["title".to_string()])
+            .sort_fields(Some(sort_fields))
+            .filters(filters.into())
+            .grants(Restricted(grants))
+            .include_author_id(false)
+            .build();
+
+        // Act
+        let sql = select_documents_sql(&params).to_string(PostgresQueryBuilder);
+
+        // Assert
+        assert_eq!(
+            sql,
+            format!(
+                r#"SELECT "d"."id", "t"."new_f" AS "f" FROM "collection_document" AS "d" INNER JOIN LATERAL (SELECT jsonb_object_agg("key", "value") as "new_f" from jsonb_each("f") as x("key", "value") WHERE "key" in ('title')) AS "t" ON TRUE WHERE "d"."id" IN (SELECT DISTINCT "d"."id" FROM "collection_document" AS "d" JOIN "grant" ON "d"."id" = "grant"."document_id" WHERE "collection_id" = '{collection}' AND ("grant"."realm" = 'read-collection' AND "grant"."grant" = '{collection}') AND (("d"."f"->'orgaddr'->>'zip') = '11101' AND ("d"."f"->'wf1'->>'seq') IN ('1', '2'))) ORDER BY "d"."f"->>'created' ASC"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_select_documents_sql_query1() {
+        // Arrange
+        let collection = Uuid::new_v4();
+        let userid = Uuid::new_v4();
+        let sort_fields = "created+".to_string();
+        let filters = vec![CronDocumentSelector::ByFieldEqualsValue {
+            field: "orgaddr.zip".to_string(),
+            value: "11101".to_string(),
+        }
+        .into()];
+        let grants = default_user_grants(
+            DefaultUserGrantsParameters::builder()
+                .visibility(CollectionDocumentVisibility::PrivateAndUserIs(userid))
+                .collection_uuid(collection)
+                .build(),
+        );
+        let params = DbListDocumentParams::builder()
+            .collection(collection)
+            .extra_fields(vec!["title".to_string()])
+            .sort_fields(Some(sort_fields))
+            .filters(filters.into())
+            .grants(Restricted(grants))
+            .include_author_id(false)
+            .build();
+
+        // Act
+        let sql = select_documents_sql(&params).to_string(PostgresQueryBuilder);
+
+        // Assert
+        assert_eq!(
+            sql,
+            format!(
+                r#"SELECT "d"."id", "t"."new_f" AS "f" FROM "collection_document" AS "d" INNER JOIN LATERAL (SELECT jsonb_object_agg("key", "value") as "new_f" from jsonb_each("f") as x("key", "value") WHERE "key" in ('title')) AS "t" ON TRUE WHERE "d"."id" IN (SELECT DISTINCT "d"."id" FROM "collection_document" AS "d" JOIN "grant" ON "d"."id" = "grant"."document_id" WHERE "collection_id" = '{collection}' AND ("grant"."realm" = 'author' AND "grant"."grant" = '{userid}') AND ("d"."f"->'orgaddr'->>'zip') = '11101') ORDER BY "d"."f"->>'created' ASC"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_select_documents_sql_all_fields() {
+        // Arrange
+        let collection = Uuid::new_v4();
+        let params = DbListDocumentParams::builder()
+            .collection(collection)
+            .extra_fields(vec![ALL_FIELDS.to_string()])
+            .sort_fields(None)
+            .filters(SearchFilter::Group(SearchGroup::AndGroup(vec![])))
+            .grants(ListDocumentGrants::IgnoredForCron)
+            .include_author_id(false)
+            .build();
+
+        // Act
+        let sql = select_documents_sql(&params).to_string(PostgresQueryBuilder);
+
+        // Assert
+        assert_eq!(
+            sql,
+            format!(
+                r#"SELECT "d"."id", "d"."f" FROM "collection_document" AS "d" WHERE "d"."id" IN (SELECT DISTINCT "d"."id" FROM "collection_document" AS "d" WHERE "collection_id" = '{collection}') ORDER BY "d"."f"->>'created' ASC"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_select_documents_sql_sorted_by_author_id() {
+        // Arrange
+        let collection = Uuid::new_v4();
+        let params = DbListDocumentParams::builder()
+            .collection(collection)
+            .extra_fields(vec!["title".to_string()])
+            .sort_fields(Some("author_id-!,title+".to_string()))
+            .filters(SearchFilter::Group(SearchGroup::AndGroup(vec![])))
+            .grants(ListDocumentGrants::IgnoredForAdmin)
+            .include_author_id(false)
+            .build();
+
+        // Act
+        let sql = select_documents_sql(&params).to_string(PostgresQueryBuilder);
+
+        // Assert
+        assert_eq!(
+            sql,
+            format!(
+                r#"SELECT "d"."id", "t"."new_f" AS "f", "e"."user" AS "author_id" FROM "collection_document" AS "d" INNER JOIN LATERAL (SELECT jsonb_object_agg("key", "value") as "new_f" from jsonb_each("f") as x("key", "value") WHERE "key" in ('title')) AS "t" ON TRUE LEFT JOIN "event" AS "e" ON "e"."category_id" = 1 AND "e"."document_id" = "d"."id" AND ("e"."payload"->'new'='true'::JSONB) WHERE "d"."id" IN (SELECT DISTINCT "d"."id" FROM "collection_document" AS "d" WHERE "collection_id" = '{collection}') ORDER BY "e"."user" DESC NULLS LAST, "d"."f"->>'title' ASC"#
+            )
+        );
+    }
+
+    #[test]
+    fn it_detects_the_author_id_sort_field() {
+        // Act
+        let ascending = sorts_by_author_id(Some("title+,author_id+"));
+        let nulls_last = sorts_by_author_id(Some("author_id-!"));
+        let native = sorts_by_author_id(Some("author_idf"));
+        let nested = sorts_by_author_id(Some("meta.author_id+"));
+        let unsorted = sorts_by_author_id(None);
+
+        // Assert
+        assert!(ascending);
+        assert!(nulls_last);
+        assert!(!native);
+        assert!(!nested);
+        assert!(!unsorted);
+    }
+
+    #[test]
+    fn it_continues_after_the_cursor_author() {
+        // Arrange
+        let id = Uuid::parse_str("3a8ed6a6-9cd7-4a4e-a0b5-42bfbd8e0c01").unwrap();
+        let key = serde_json::json!("5c1e9b7a-2d3f-4a6b-8c0d-1e2f3a4b5c6d");
+
+        // Act
+        let sql = keyset_sql("author_id+", Keyset::After(CursorPosition { key, id }));
+
+        // Assert
+        assert!(sql.contains(r#""e"."user" AS "author_id", "e"."user" AS "cursor_key""#));
+        assert!(sql
+            .contains(r#"("e"."user") > (CAST('5c1e9b7a-2d3f-4a6b-8c0d-1e2f3a4b5c6d' AS uuid))"#));
+        assert!(sql.ends_with(r#"ORDER BY "e"."user" ASC, "d"."id" ASC"#));
+    }
+
+    #[test]
+    fn test_select_documents_sql_all_of_cron_selectors() {
+        // Arrange
+        let collection = Uuid::new_v4();
+        let filters = vec![CronDocumentSelector::AllOf(vec![
+            CronDocumentSelector::ByDateFieldOlderThan {
+                field: DELETED_AT_FIELD.to_string(),
+                value: chrono::Duration::days(30),
+            },
+            CronDocumentSelector::ByFieldEqualsValue {
+                field: "status".to_string(),
+                value: "archived".to_string(),
+            },
+        ])
+        .into()];
+        let params = DbListDocumentParams::builder()
+            .collection(collection)
+            .extra_fields(vec!["title".to_string()])
+            .sort_fields(None)
+            .filters(filters.into())
+            .grants(ListDocumentGrants::IgnoredForCron)
+            .include_author_id(false)
+            .build();
+
+        // Act
+        let sql = select_documents_sql(&params).to_string(PostgresQueryBuilder);
+
+        // Assert
+        let date = (Utc::now() - chrono::Duration::days(30)).format("%Y-%m-%d");
+        assert_eq!(
+            sql,
+            format!(
+                r#"SELECT "d"."id", "t"."new_f" AS "f" FROM "collection_document" AS "d" INNER JOIN LATERAL (SELECT jsonb_object_agg("key", "value") as "new_f" from jsonb_each("f") as x("key", "value") WHERE "key" in ('title')) AS "t" ON TRUE WHERE "d"."id" IN (SELECT DISTINCT "d"."id" FROM "collection_document" AS "d" WHERE "collection_id" = '{collection}' AND (("d"."f"->>'folivafy_deleted_at') < '{date}' AND ("d"."f"->>'status') = 'archived')) ORDER BY "d"."f"->>'created' ASC"#
+            )
+        );
+    }
+
+    #[test]
+    fn it_applies_grants_per_collection_in_multi_collection_search() {
+        // Arrange
+        let orders = Uuid::new_v4();
+        let invoices = Uuid::new_v4();
+        let userid = Uuid::new_v4();
+        let grants = default_user_grants(
+            DefaultUserGrantsParameters::builder()
+                .visibility(CollectionDocumentVisibility::PrivateAndUserIs(userid))
+                .collection_uuid(orders)
+                .build(),
+        );
+        let params = DbListMultiCollectionParams::builder()
+            .collections(vec![
+                (orders, Restricted(grants)),
+                (invoices, ListDocumentGrants::IgnoredForAdmin),
+            ])
+            .extra_fields(vec!["title".to_string()])
+            .sort_fields(None)
+            .filters(SearchFilter::Group(SearchGroup::AndGroup(vec![])))
+            .build();
+
+        // Act
+        let count_sql =
+            count_multi_collection_documents_sql(&params).to_string(PostgresQueryBuilder);
+        let sql = select_multi_collection_documents_sql(&params).to_string(PostgresQueryBuilder);
+
+        // Assert
+        let ids_sql = format!(
+            r#"SELECT "d"."id" FROM "collection_document" AS "d" JOIN "grant" ON "d"."id" = "grant"."document_id" WHERE "collection_id" = '{orders}' AND ("grant"."realm" = 'author' AND "grant"."grant" = '{userid}') UNION (SELECT "d"."id" FROM "collection_document" AS "d" WHERE "collection_id" = '{invoices}')"#
+        );
+        assert_eq!(
+            count_sql,
+            format!(r#"SELECT COUNT(*) FROM ({ids_sql}) AS "u""#)
+        );
+        assert_eq!(
+            sql,
+            format!(
+                r#"SELECT "d"."id", "t"."new_f" AS "f", "c"."name" AS "collection" FROM "collection_document" AS "d" INNER JOIN LATERAL (SELECT jsonb_object_agg("key", "value") as "new_f" from jsonb_each("f") as x("key", "value") WHERE "key" in ('title')) AS "t" ON TRUE INNER JOIN "collection" AS "c" ON "c"."id" = "d"."collection_id" WHERE "d"."id" IN ({ids_sql}) ORDER BY "d"."f"->>'created' ASC"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_select_documents_sql_query3() {
+        // Arrange
         let collection = Uuid::new_v4();
         let userid = Uuid::new_v4();
         let sort_fields = "created+".to_string();
@@ -1208,20 +2883,302 @@ mod tests {
 
         // Assert
         assert_eq!(
-            sql,
-            format!(
-                r#"SELECT "d"."id", "t"."new_f" AS "f", "e"."user" AS "author_id" FROM "collection_document" AS "d" INNER JOIN LATERAL (SELECT jsonb_object_agg("key", "value") as "new_f" from jsonb_each("f") as x("key", "value") WHERE "key" in ('title')) AS "t" ON TRUE LEFT JOIN "event" AS "e" ON "e"."category_id" = 1 AND "e"."document_id" = "d"."id" AND ("e"."payload"->'new'='true'::JSONB) WHERE "d"."id" IN (SELECT DISTINCT "d"."id" FROM "collection_document" AS "d" JOIN "grant" ON "d"."id" = "grant"."document_id" WHERE "collection_id" = '{collection}' AND ("grant"."realm" = 'author' AND "grant"."grant" = '{userid}') AND ("d"."f"->'orgaddr'->>'zip') = '11101') ORDER BY "d"."f"->>'created' ASC"#
-            )
+            sql,
+            format!(
+                r#"SELECT "d"."id", "t"."new_f" AS "f", "e"."user" AS "author_id" FROM "collection_document" AS "d" INNER JOIN LATERAL (SELECT jsonb_object_agg("key", "value") as "new_f" from jsonb_each("f") as x("key", "value") WHERE "key" in ('title')) AS "t" ON TRUE LEFT JOIN "event" AS "e" ON "e"."category_id" = 1 AND "e"."document_id" = "d"."id" AND ("e"."payload"->'new'='true'::JSONB) WHERE "d"."id" IN (SELECT DISTINCT "d"."id" FROM "collection_document" AS "d" JOIN "grant" ON "d"."id" = "grant"."document_id" WHERE "collection_id" = '{collection}' AND ("grant"."realm" = 'author' AND "grant"."grant" = '{userid}') AND ("d"."f"->'orgaddr'->>'zip') = '11101') ORDER BY "d"."f"->>'created' ASC"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_fov_to_cond_eq() {
+        // Arrange
+        let fov = SearchFilterFieldOpValue::builder()
+            .field("a".to_string())
+            .operation(OperationWithValue::Eq)
+            .value(json!("b"))
+            .build();
+
+        // Act
+        let query = Query::select()
+            .column(CollectionDocument::Id)
+            .from(CollectionDocument::Table)
+            .and_where(fov_to_condition(&fov))
+            .to_owned()
+            .to_string(PostgresQueryBuilder);
+
+        // Assert
+        assert_eq!(
+            query,
+            format!(r#"SELECT "id" FROM "collection_document" WHERE ("d"."f"->>'a') = 'b'"#)
+        );
+    }
+
+    #[test]
+    fn test_fov_to_cond_eq_author_id() {
+        // Arrange
+        let owner_guid = Uuid::new_v4().to_string();
+        let fov1 = SearchFilter::FieldOpValue(
+            SearchFilterFieldOpValue::builder()
+                .field("a".to_string())
+                .operation(OperationWithValue::Eq)
+                .value(json!("b"))
+                .build(),
+        );
+        let fov2 = SearchFilter::FieldOpValue(
+            SearchFilterFieldOpValue::builder()
+                .field("author_id".to_string())
+                .operation(OperationWithValue::Eq)
+                .value(json!(owner_guid))
+                .build(),
+        );
+        let fov = SearchFilter::Group(SearchGroup::AndGroup(vec![fov1, fov2]));
+
+        // Act
+        let query = Query::select()
+            .column(CollectionDocument::Id)
+            .from(CollectionDocument::Table)
+            .cond_where(condition_for_filter(Condition::all(), &fov))
+            .to_owned()
+            .to_string(PostgresQueryBuilder);
+
+        // Assert
+        assert_eq!(
+            query,
+            format!(
+                r#"SELECT "id" FROM "collection_document" WHERE ("d"."f"->>'a') = 'b' AND ("d"."owner"::text) = '{owner_guid}'"#
+            )
+        );
+    }
+
+    #[test]
+    fn it_indexes_a_nested_field_like_the_filter() {
+        // Arrange
+        let collection_id = Uuid::parse_str("5c1e9b7a-2d3f-4a6b-8c0d-1e2f3a4b5c6d").unwrap();
+
+        // Act
+        let sql = field_index_sql(collection_id, "customer.address.city");
+
+        // Assert
+        assert_eq!(
+            sql,
+            r#"CREATE INDEX CONCURRENTLY IF NOT EXISTS "idx-document-5c1e9b7a2d3f4a6b8c0d1e2f3a4b5c6d-ebea69e232e24f86" ON "collection_document" (("f"->'customer'->'address'->>'city')) WHERE "collection_id" = '5c1e9b7a-2d3f-4a6b-8c0d-1e2f3a4b5c6d'"#
+        );
+    }
+
+    #[test]
+    fn test_fov_to_cond_ne_author_id() {
+        // Arrange
+        let owner_guid = Uuid::new_v4().to_string();
+        let fov = SearchFilterFieldOpValue::builder()
+            .field("author_id".to_string())
+            .operation(OperationWithValue::Ne)
+            .value(json!(owner_guid))
+            .build();
+
+        // Act
+        let query = Query::select()
+            .column(CollectionDocument::Id)
+            .from(CollectionDocument::Table)
+            .and_where(fov_to_condition(&fov))
+            .to_owned()
+            .to_string(PostgresQueryBuilder);
+
+        // Assert
+        assert_eq!(
+            query,
+            format!(
+                r#"SELECT "id" FROM "collection_document" WHERE ("d"."owner"::text) <> '{owner_guid}'"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_fov_to_cond_in_author_id() {
+        // Arrange
+        let filter: SearchFilter = parse_pfilter(Some(
+            "author_id=[3ef7a1d2-0b6c-4e5f-9a8b-7c6d5e4f3a21,'5c1e9b7a-2d3f-4a6b-8c0d-1e2f3a4b5c6d']"
+                .to_string(),
+        ))
+        .unwrap()
+        .into();
+
+        // Act
+        let query = Query::select()
+            .column(CollectionDocument::Id)
+            .from(CollectionDocument::Table)
+            .cond_where(condition_for_filter(Condition::all(), &filter))
+            .to_owned()
+            .to_string(PostgresQueryBuilder);
+
+        // Assert
+        assert_eq!(
+            query,
+            r#"SELECT "id" FROM "collection_document" WHERE "d"."owner" IN ('3ef7a1d2-0b6c-4e5f-9a8b-7c6d5e4f3a21', '5c1e9b7a-2d3f-4a6b-8c0d-1e2f3a4b5c6d')"#
+        );
+    }
+
+    #[test]
+    fn test_fov_to_cond_in_author_id_skips_invalid_ids() {
+        // Arrange
+        let only_invalid: SearchFilter = parse_pfilter(Some("author_id=[jane,42]".to_string()))
+            .unwrap()
+            .into();
+        let partly_invalid: SearchFilter = parse_pfilter(Some(
+            "author_id=[jane,3ef7a1d2-0b6c-4e5f-9a8b-7c6d5e4f3a21]".to_string(),
+        ))
+        .unwrap()
+        .into();
+
+        // Act
+        let [only_invalid, partly_invalid] = [only_invalid, partly_invalid].map(|filter| {
+            Query::select()
+                .column(CollectionDocument::Id)
+                .from(CollectionDocument::Table)
+                .cond_where(condition_for_filter(Condition::all(), &filter))
+                .to_owned()
+                .to_string(PostgresQueryBuilder)
+        });
+
+        // Assert
+        assert_eq!(
+            only_invalid,
+            r#"SELECT "id" FROM "collection_document" WHERE 1 = 0"#
+        );
+        assert_eq!(
+            partly_invalid,
+            r#"SELECT "id" FROM "collection_document" WHERE "d"."owner" IN ('3ef7a1d2-0b6c-4e5f-9a8b-7c6d5e4f3a21')"#
+        );
+    }
+
+    #[test]
+    fn test_fov_to_cond_not_in_author_id() {
+        // Arrange
+        let filter: SearchFilter = parse_pfilter(Some(
+            "author_id=![3ef7a1d2-0b6c-4e5f-9a8b-7c6d5e4f3a21,'5c1e9b7a-2d3f-4a6b-8c0d-1e2f3a4b5c6d']"
+                .to_string(),
+        ))
+        .unwrap()
+        .into();
+
+        // Act
+        let query = Query::select()
+            .column(CollectionDocument::Id)
+            .from(CollectionDocument::Table)
+            .cond_where(condition_for_filter(Condition::all(), &filter))
+            .to_owned()
+            .to_string(PostgresQueryBuilder);
+
+        // Assert
+        assert_eq!(
+            query,
+            r#"SELECT "id" FROM "collection_document" WHERE ("d"."owner"::text) NOT IN ('3ef7a1d2-0b6c-4e5f-9a8b-7c6d5e4f3a21', '5c1e9b7a-2d3f-4a6b-8c0d-1e2f3a4b5c6d')"#
+        );
+    }
+
+    #[test]
+    fn test_fov_to_cond_ne() {
+        // Arrange
+        let fov = SearchFilterFieldOpValue::builder()
+            .field("a.b".to_string())
+            .operation(OperationWithValue::Ne)
+            .value(json!("ninja"))
+            .build();
+
+        // Act
+        let query = Query::select()
+            .column(CollectionDocument::Id)
+            .from(CollectionDocument::Table)
+            .and_where(fov_to_condition(&fov))
+            .to_owned()
+            .to_string(PostgresQueryBuilder);
+
+        // Assert
+        assert_eq!(
+            query,
+            format!(
+                r#"SELECT "id" FROM "collection_document" WHERE ("d"."f"->'a'->>'b') <> 'ninja'"#
+            )
+        );
+    }
+
+    fn fov_number_query(operation: OperationWithValue, value: &str) -> String {
+        let fov = SearchFilterFieldOpValue::builder()
+            .field("a.b".to_string())
+            .operation(operation)
+            .value(serde_json::from_str(value).unwrap())
+            .build();
+        Query::select()
+            .column(CollectionDocument::Id)
+            .from(CollectionDocument::Table)
+            .and_where(fov_to_condition(&fov))
+            .to_owned()
+            .to_string(PostgresQueryBuilder)
+    }
+
+    #[test]
+    fn test_fov_to_cond_eq_large_integers() {
+        // Act
+        let query1 = fov_number_query(OperationWithValue::Eq, "9007199254740993");
+        let query2 = fov_number_query(OperationWithValue::Eq, "18446744073709551615");
+        let query3 = fov_number_query(OperationWithValue::Eq, "-9223372036854775808");
+
+        // Assert
+        assert_eq!(
+            query1,
+            r#"SELECT "id" FROM "collection_document" WHERE ("d"."f"->'a'->'b') = (CAST('9007199254740993' AS jsonb))"#
+        );
+        assert_eq!(
+            query2,
+            r#"SELECT "id" FROM "collection_document" WHERE ("d"."f"->'a'->'b') = (CAST('18446744073709551615' AS jsonb))"#
+        );
+        assert_eq!(
+            query3,
+            r#"SELECT "id" FROM "collection_document" WHERE ("d"."f"->'a'->'b') = (CAST('-9223372036854775808' AS jsonb))"#
+        );
+    }
+
+    #[test]
+    fn test_fov_to_cond_gt_floats() {
+        // Act
+        let query1 = fov_number_query(OperationWithValue::Gt, "1e20");
+        let query2 = fov_number_query(OperationWithValue::Le, "1.0");
+
+        // Assert
+        assert_eq!(
+            query1,
+            r#"SELECT "id" FROM "collection_document" WHERE (jsonb_typeof("d"."f"->'a'->'b') = 'number') AND ("d"."f"->'a'->'b') > (CAST('1e20' AS jsonb))"#
+        );
+        assert_eq!(
+            query2,
+            r#"SELECT "id" FROM "collection_document" WHERE (jsonb_typeof("d"."f"->'a'->'b') = 'number') AND ("d"."f"->'a'->'b') <= (CAST('1.0' AS jsonb))"#
+        );
+    }
+
+    #[test]
+    fn test_fov_to_cond_in_numbers() {
+        // Act
+        let query1 = fov_number_query(OperationWithValue::In, "[1, 9007199254740993, 2.5]");
+        let query2 = fov_number_query(OperationWithValue::In, r#"[1, "b"]"#);
+
+        // Assert
+        assert_eq!(
+            query1,
+            r#"SELECT "id" FROM "collection_document" WHERE ("d"."f"->'a'->'b') IN (CAST('1' AS jsonb), CAST('9007199254740993' AS jsonb), CAST('2.5' AS jsonb))"#
+        );
+        assert_eq!(
+            query2,
+            r#"SELECT "id" FROM "collection_document" WHERE ("d"."f"->'a'->>'b') IN ('1', 'b')"#
         );
     }
 
     #[test]
-    fn test_fov_to_cond_eq() {
+    fn test_fov_to_cond_startswith() {
         // Arrange
         let fov = SearchFilterFieldOpValue::builder()
-            .field("a".to_string())
-            .operation(OperationWithValue::Eq)
-            .value(json!("b"))
+            .field("b.g".to_string())
+            .operation(OperationWithValue::StartsWith)
+            .value(json!("Fol"))
             .build();
 
         // Act
@@ -1235,35 +3192,26 @@ mod tests {
         // Assert
         assert_eq!(
             query,
-            format!(r#"SELECT "id" FROM "collection_document" WHERE ("d"."f"->>'a') = 'b'"#)
+            format!(
+                r#"SELECT "id" FROM "collection_document" WHERE LOWER("d"."f"->'b'->>'g') LIKE 'fol%'"#
+            )
         );
     }
 
     #[test]
-    fn test_fov_to_cond_eq_author_id() {
+    fn test_fov_to_cond_containstext() {
         // Arrange
-        let owner_guid = Uuid::new_v4().to_string();
-        let fov1 = SearchFilter::FieldOpValue(
-            SearchFilterFieldOpValue::builder()
-                .field("a".to_string())
-                .operation(OperationWithValue::Eq)
-                .value(json!("b"))
-                .build(),
-        );
-        let fov2 = SearchFilter::FieldOpValue(
-            SearchFilterFieldOpValue::builder()
-                .field("author_id".to_string())
-                .operation(OperationWithValue::Eq)
-                .value(json!(owner_guid))
-                .build(),
-        );
-        let fov = SearchFilter::Group(SearchGroup::AndGroup(vec![fov1, fov2]));
+        let fov = SearchFilterFieldOpValue::builder()
+            .field("g".to_string())
+            .operation(OperationWithValue::ContainsText)
+            .value(json!("olid"))
+            .build();
 
         // Act
         let query = Query::select()
             .column(CollectionDocument::Id)
             .from(CollectionDocument::Table)
-            .cond_where(condition_for_filter(Condition::all(), &fov))
+            .and_where(fov_to_condition(&fov))
             .to_owned()
             .to_string(PostgresQueryBuilder);
 
@@ -1271,18 +3219,18 @@ mod tests {
         assert_eq!(
             query,
             format!(
-                r#"SELECT "id" FROM "collection_document" WHERE ("d"."f"->>'a') = 'b' AND ("d"."owner"::text) = '{owner_guid}'"#
+                r#"SELECT "id" FROM "collection_document" WHERE LOWER("d"."f"->>'g') LIKE '%olid%'"#
             )
         );
     }
 
     #[test]
-    fn test_fov_to_cond_ne() {
+    fn test_fov_to_cond_startswith_case_sensitive() {
         // Arrange
         let fov = SearchFilterFieldOpValue::builder()
-            .field("a.b".to_string())
-            .operation(OperationWithValue::Ne)
-            .value(json!("ninja"))
+            .field("b.g".to_string())
+            .operation(OperationWithValue::StartsWithCs)
+            .value(json!("Fol"))
             .build();
 
         // Act
@@ -1297,18 +3245,51 @@ mod tests {
         assert_eq!(
             query,
             format!(
-                r#"SELECT "id" FROM "collection_document" WHERE ("d"."f"->'a'->>'b') <> 'ninja'"#
+                r#"SELECT "id" FROM "collection_document" WHERE ("d"."f"->'b'->>'g') LIKE 'Fol%'"#
             )
         );
     }
 
     #[test]
-    fn test_fov_to_cond_startswith() {
+    fn test_fov_to_cond_array_contains() {
+        // Act
+        let text = fov_number_query(OperationWithValue::ArrayContains, r#""sale""#);
+        let number = fov_number_query(OperationWithValue::ArrayContains, "42");
+        let quoted = fov_number_query(OperationWithValue::ArrayContains, r#""O'Neil""#);
+
+        // Assert
+        assert_eq!(
+            text,
+            r#"SELECT "id" FROM "collection_document" WHERE ("d"."f"->'a'->'b') @> (CAST(E'[\"sale\"]' AS jsonb))"#
+        );
+        assert_eq!(
+            number,
+            r#"SELECT "id" FROM "collection_document" WHERE ("d"."f"->'a'->'b') @> (CAST('[42]' AS jsonb))"#
+        );
+        assert_eq!(
+            quoted,
+            r#"SELECT "id" FROM "collection_document" WHERE ("d"."f"->'a'->'b') @> (CAST(E'[\"O\'Neil\"]' AS jsonb))"#
+        );
+    }
+
+    #[test]
+    fn test_fov_to_cond_array_contains_rejects_other_values() {
+        for value in [r#"["sale", "new"]"#, r#""""#, "true", "null", r#"{"a": 1}"#] {
+            assert_eq!(
+                fov_number_query(OperationWithValue::ArrayContains, value),
+                r#"SELECT "id" FROM "collection_document" WHERE 1 = 0"#,
+                "{value}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_fov_to_cond_containstext_case_sensitive() {
         // Arrange
         let fov = SearchFilterFieldOpValue::builder()
-            .field("b.g".to_string())
-            .operation(OperationWithValue::StartsWith)
-            .value(json!("Fol"))
+            .field("sku".to_string())
+            .operation(OperationWithValue::ContainsTextCs)
+            .value(json!("Ab-1"))
             .build();
 
         // Act
@@ -1323,37 +3304,68 @@ mod tests {
         assert_eq!(
             query,
             format!(
-                r#"SELECT "id" FROM "collection_document" WHERE LOWER("d"."f"->'b'->>'g') LIKE 'fol%'"#
+                r#"SELECT "id" FROM "collection_document" WHERE ("d"."f"->>'sku') LIKE '%Ab-1%'"#
             )
         );
     }
 
     #[test]
-    fn test_fov_to_cond_containstext() {
+    fn test_exists_filters() {
         // Arrange
-        let fov = SearchFilterFieldOpValue::builder()
-            .field("g".to_string())
-            .operation(OperationWithValue::ContainsText)
-            .value(json!("olid"))
-            .build();
+        let filter: SearchFilter = vec![
+            FieldFilter::from_str("completed_at=*").unwrap(),
+            FieldFilter::from_str("cancelled_at=!*").unwrap(),
+        ]
+        .into();
 
         // Act
         let query = Query::select()
             .column(CollectionDocument::Id)
             .from(CollectionDocument::Table)
-            .and_where(fov_to_condition(&fov))
+            .cond_where(condition_for_filter(Condition::all(), &filter))
             .to_owned()
             .to_string(PostgresQueryBuilder);
 
         // Assert
         assert_eq!(
             query,
-            format!(
-                r#"SELECT "id" FROM "collection_document" WHERE LOWER("d"."f"->>'g') LIKE '%olid%'"#
-            )
+            r#"SELECT "id" FROM "collection_document" WHERE ("d"."f"->>'completed_at') IS NOT NULL AND ("d"."f"->>'cancelled_at') IS NULL"#
         );
     }
 
+    #[test]
+    fn test_fov_to_cond_regex() {
+        // Arrange
+        let fovs = [
+            (OperationWithValue::Regex, "~"),
+            (OperationWithValue::RegexI, "~*"),
+        ];
+
+        for (operation, operator) in fovs {
+            let fov = SearchFilterFieldOpValue::builder()
+                .field("title".to_string())
+                .operation(operation)
+                .value(json!(r"\d{3}-\d{4}"))
+                .build();
+
+            // Act
+            let query = Query::select()
+                .column(CollectionDocument::Id)
+                .from(CollectionDocument::Table)
+                .and_where(fov_to_condition(&fov))
+                .to_owned()
+                .to_string(PostgresQueryBuilder);
+
+            // Assert
+            assert_eq!(
+                query,
+                format!(
+                    r#"SELECT "id" FROM "collection_document" WHERE ("d"."f"->>'title') {operator} E'\\d{{3}}-\\d{{4}}'"#
+                )
+            );
+        }
+    }
+
     #[test]
     fn test_fov_to_cond_group1() {
         // Arrange
@@ -1432,4 +3444,298 @@ mod tests {
             )
         );
     }
+
+    #[test]
+    fn it_classifies_retryable_errors() {
+        assert!(is_retryable_error(&DbErr::ConnectionAcquire(
+            sea_orm::error::ConnAcquireErr::Timeout
+        )));
+        assert!(is_retryable_error(&DbErr::Query(RuntimeErr::SqlxError(
+            sqlx::Error::PoolTimedOut
+        ))));
+        assert!(!is_retryable_error(&DbErr::RecordNotFound(
+            "Document".to_string()
+        )));
+        assert!(!is_retryable_error(&DbErr::Query(RuntimeErr::SqlxError(
+            sqlx::Error::RowNotFound
+        ))));
+    }
+
+    #[test]
+    fn it_increments_the_version_of_an_updated_document() {
+        // Arrange
+        let f = serde_json::json!({"title": "Circle", "version": 1});
+
+        // Act
+        let sql = Query::update()
+            .table(CollectionDocument::Table)
+            .value(CollectionDocument::F, with_next_version(f))
+            .to_owned()
+            .to_string(PostgresQueryBuilder);
+
+        // Assert
+        assert_eq!(
+            sql,
+            r#"UPDATE "collection_document" SET "f" = E'{\"title\":\"Circle\",\"version\":1}' || jsonb_build_object('version', CASE WHEN jsonb_typeof("f"->'version') = 'number' THEN trunc(("f"->>'version')::numeric)::bigint ELSE 0 END + 1)"#
+        );
+    }
+
+    #[test]
+    fn it_classifies_retryable_sqlstates() {
+        assert!(is_retryable_sqlstate("40001"));
+        assert!(is_retryable_sqlstate("40P01"));
+        assert!(is_retryable_sqlstate("08006"));
+        assert!(!is_retryable_sqlstate("23505"));
+        assert!(!is_retryable_sqlstate("42P01"));
+    }
+
+    #[tokio::test]
+    async fn it_retries_transient_errors_until_success() {
+        // Arrange
+        let policy = RetryPolicy::new(3, Duration::ZERO);
+        let attempts = std::sync::atomic::AtomicU32::new(0);
+
+        // Act
+        let result = with_retry(&policy, || async {
+            if attempts.fetch_add(1, std::sync::atomic::Ordering::SeqCst) < 2 {
+                Err(DbErr::ConnectionAcquire(
+                    sea_orm::error::ConnAcquireErr::Timeout,
+                ))
+            } else {
+                Ok(42)
+            }
+        })
+        .await;
+
+        // Assert
+        assert_eq!(result, Ok(42));
+        assert_eq!(attempts.load(std::sync::atomic::Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn it_fails_immediately_on_permanent_errors() {
+        // Arrange
+        let policy = RetryPolicy::new(3, Duration::ZERO);
+        let attempts = std::sync::atomic::AtomicU32::new(0);
+
+        // Act
+        let result: Result<u32, DbErr> = with_retry(&policy, || async {
+            attempts.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Err(DbErr::RecordNotFound("Document".to_string()))
+        })
+        .await;
+
+        // Assert
+        assert!(result.is_err());
+        assert_eq!(attempts.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    /// A database error with the SQLSTATE
+    #[derive(Debug)]
+    struct SqlState(&'static str);
+
+    impl std::fmt::Display for SqlState {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "SQLSTATE {}", self.0)
+        }
+    }
+
+    impl std::error::Error for SqlState {}
+
+    impl sqlx::error::DatabaseError for SqlState {
+        fn message(&self) -> &str {
+            "could not serialize access"
+        }
+
+        fn code(&self) -> Option<std::borrow::Cow<'_, str>> {
+            Some(self.0.into())
+        }
+
+        fn as_error(&self) -> &(dyn std::error::Error + Send + Sync + 'static) {
+            self
+        }
+
+        fn as_error_mut(&mut self) -> &mut (dyn std::error::Error + Send + Sync + 'static) {
+            self
+        }
+
+        fn into_error(self: Box<Self>) -> Box<dyn std::error::Error + Send + Sync + 'static> {
+            self
+        }
+
+        fn kind(&self) -> sqlx::error::ErrorKind {
+            sqlx::error::ErrorKind::Other
+        }
+    }
+
+    fn database_error(code: &'static str) -> DbErr {
+        DbErr::Exec(RuntimeErr::SqlxError(sqlx::Error::Database(Box::new(
+            SqlState(code),
+        ))))
+    }
+
+    #[tokio::test]
+    async fn it_repeats_the_transaction_after_a_serialization_failure() {
+        // Arrange
+        let policy = RetryPolicy::new(3, Duration::ZERO);
+        let attempts = std::sync::atomic::AtomicU32::new(0);
+
+        // Act
+        let result = retry_transaction(&policy, || async {
+            if attempts.fetch_add(1, std::sync::atomic::Ordering::SeqCst) == 0 {
+                Err(ApiErrors::from(database_error("40001")))
+            } else {
+                Ok(42)
+            }
+        })
+        .await;
+
+        // Assert
+        assert!(matches!(result, Ok(42)));
+        assert_eq!(attempts.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn it_does_not_repeat_the_transaction_after_other_errors() {
+        // Arrange
+        let policy = RetryPolicy::new(3, Duration::ZERO);
+        let attempts = std::sync::atomic::AtomicU32::new(0);
+
+        // Act
+        let result: Result<u32, ApiErrors> = retry_transaction(&policy, || async {
+            attempts.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Err(ApiErrors::from(database_error("23505")))
+        })
+        .await;
+
+        // Assert
+        assert!(result.is_err());
+        assert_eq!(attempts.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn it_detects_transaction_conflicts() {
+        assert!(is_transaction_conflict(&database_error("40001")));
+        assert!(is_transaction_conflict(&database_error("40P01")));
+        assert!(!is_transaction_conflict(&database_error("08006")));
+        assert!(!is_transaction_conflict(&DbErr::RecordNotFound(
+            "Document".to_string()
+        )));
+    }
+
+    #[tokio::test]
+    async fn it_gives_up_after_max_retries() {
+        // Arrange
+        let policy = RetryPolicy::new(2, Duration::ZERO);
+        let attempts = std::sync::atomic::AtomicU32::new(0);
+
+        // Act
+        let result: Result<u32, DbErr> = with_retry(&policy, || async {
+            attempts.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Err(DbErr::ConnectionAcquire(
+                sea_orm::error::ConnAcquireErr::Timeout,
+            ))
+        })
+        .await;
+
+        // Assert
+        assert!(result.is_err());
+        assert_eq!(attempts.load(std::sync::atomic::Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn it_excludes_documents_of_the_owner() {
+        // Arrange
+        let collection = Uuid::new_v4();
+        let userid = Uuid::new_v4();
+        let grants = default_user_grants(
+            DefaultUserGrantsParameters::builder()
+                .visibility(CollectionDocumentVisibility::PublicAndUserIsReader)
+                .collection_uuid(collection)
+                .build(),
+        );
+        let params = DbListDocumentParams::builder()
+            .collection(collection)
+            .extra_fields(vec![])
+            .sort_fields(None)
+            .filters(SearchFilter::Group(SearchGroup::AndGroup(vec![])))
+            .grants(Restricted(grants))
+            .include_author_id(false)
+            .exclude_owner(Some(userid))
+            .build();
+
+        // Act
+        let sql = count_documents_sql(&params).to_string(PostgresQueryBuilder);
+
+        // Assert
+        assert_eq!(
+            sql,
+            format!(
+                r#"SELECT COUNT(DISTINCT "d"."id") FROM "collection_document" AS "d" JOIN "grant" ON "d"."id" = "grant"."document_id" WHERE "collection_id" = '{collection}' AND ("grant"."realm" = 'read-collection' AND "grant"."grant" = '{collection}') AND "d"."owner" <> '{userid}'"#
+            )
+        );
+    }
+
+    #[test]
+    fn it_selects_changes_after_sequence_number() {
+        // Arrange
+        let collection = Uuid::new_v4();
+        let userid = Uuid::new_v4();
+        let grants = default_user_grants(
+            DefaultUserGrantsParameters::builder()
+                .visibility(CollectionDocumentVisibility::PrivateAndUserIs(userid))
+                .collection_uuid(collection)
+                .build(),
+        );
+        let params = DbListDocumentParams::builder()
+            .collection(collection)
+            .extra_fields(vec![])
+            .sort_fields(None)
+            .filters(SearchFilter::Group(SearchGroup::AndGroup(vec![])))
+            .grants(Restricted(grants))
+            .include_author_id(false)
+            .pagination(Pagination::new(20, 0))
+            .build();
+
+        // Act
+        let sql = document_changes_sql(&params, 1042).to_string(PostgresQueryBuilder);
+
+        // Assert
+        assert_eq!(
+            sql,
+            format!(
+                r#"SELECT "d"."id", "d"."seq", "d"."f" FROM "collection_document" AS "d" WHERE "d"."seq" > 1042 AND "d"."id" IN (SELECT DISTINCT "d"."id" FROM "collection_document" AS "d" JOIN "grant" ON "d"."id" = "grant"."document_id" WHERE "collection_id" = '{collection}' AND ("grant"."realm" = 'author' AND "grant"."grant" = '{userid}')) ORDER BY "d"."seq" ASC LIMIT 20"#
+            )
+        );
+    }
+
+    #[test]
+    fn it_locks_the_claimed_document() {
+        // Arrange
+        let collection = Uuid::new_v4();
+        let params = DbListDocumentParams::builder()
+            .collection(collection)
+            .extra_fields(vec![])
+            .sort_fields(Some("created+".to_string()))
+            .filters(SearchFilter::FieldOp(
+                SearchFilterFieldOp::builder()
+                    .field("claim.until".to_string())
+                    .operation(Operation::Null)
+                    .build(),
+            ))
+            .grants(ListDocumentGrants::IgnoredForAdmin)
+            .include_author_id(false)
+            .build();
+
+        // Act
+        let sql = claim_document_sql(&params).to_string(PostgresQueryBuilder);
+
+        // Assert
+        assert_eq!(
+            sql,
+            format!(
+                r#"SELECT "d"."id", "d"."collection_id", "d"."owner", "d"."f", "d"."seq", "d"."locked" FROM "collection_document" AS "d" WHERE "d"."id" IN (SELECT DISTINCT "d"."id" FROM "collection_document" AS "d" WHERE "collection_id" = '{collection}' AND ("d"."f"->'claim'->>'until') IS NULL) AND "d"."locked" = FALSE ORDER BY "d"."f"->>'created' ASC LIMIT 1 FOR UPDATE OF "d" SKIP LOCKED"#
+            )
+        );
+    }
 }
@@ -0,0 +1,265 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use jwt_authorizer::JwtClaims;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::warn;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::axumext::extractors::ValidatedQueryParams;
+
+use super::{
+    auth::User,
+    collection_config::CollectionConfig,
+    db::{get_unlocked_collection_by_name, DistinctValue, FieldFilter, ListDocumentGrants},
+    grants::{hook_or_default_user_grants, GrantCollection},
+    list_documents::{generic_distinct_values, parse_pfilter},
+    read_projection::ReadProjection,
+    ApiContext, ApiErrors,
+};
+
+/// Number of values if the request has no limit
+const DEFAULT_DISTINCT_VALUES_LIMIT: u32 = 100;
+
+/// Maximum number of values of a single request
+const MAX_DISTINCT_VALUES_LIMIT: u32 = 1000;
+
+lazy_static! {
+    static ref RE_DISTINCT_FIELD: Regex = Regex::new(r"^[a-zA-Z0-9_]+(\.[a-zA-Z0-9_]+)*$").unwrap();
+}
+
+#[derive(Debug, Deserialize, Validate, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct DistinctValuesParams {
+    /// Field whose values are returned, nested fields in dotted notation,
+    /// e. g. `workflow.status`
+    #[validate(regex(path = *RE_DISTINCT_FIELD))]
+    #[param(example = "status", pattern = r#"^[a-zA-Z0-9_]+(\.[a-zA-Z0-9_]+)*$"#)]
+    field: String,
+
+    /// Maximum number of values, the most frequent values are returned
+    #[validate(range(min = 1, max = MAX_DISTINCT_VALUES_LIMIT))]
+    #[param(default = 100, minimum = 1, maximum = 1000)]
+    limit: Option<u32>,
+
+    /// Only count the documents that match the filter, see `pfilter` of the
+    /// list endpoint
+    #[serde(rename = "pfilter")]
+    #[param(example = "title='Rectangle'&price=[12,13,14]")]
+    pfilter: Option<String>,
+
+    /// Only count the documents with this title, see `exactTitle` of the
+    /// list endpoint
+    #[serde(rename = "exactTitle")]
+    exact_title: Option<String>,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub(crate) struct DistinctValueCount {
+    /// Value of the field as text, `null` for documents without the field
+    value: Option<String>,
+
+    /// Number of documents with this value
+    count: u32,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+#[schema(examples(json!({
+    "items": [{"value": "open", "count": 12}, {"value": "done", "count": 7}],
+    "hasMore": false
+})))]
+pub(crate) struct DistinctValues {
+    /// Values with their number of documents, the most frequent values first
+    items: Vec<DistinctValueCount>,
+
+    /// Whether the field has more values than `limit`
+    #[serde(rename = "hasMore")]
+    has_more: bool,
+}
+
+/// List distinct values
+///
+/// Lists the distinct values of a document field with the number of
+/// documents per value, e. g. for faceted filters. Only documents that the
+/// user can access are counted.
+#[utoipa::path(
+    get,
+    path = "/collections/{collection_name}/distinct",
+    operation_id = "listDistinctValues",
+    params(
+        DistinctValuesParams,
+        (
+            "collection_name" = String,
+            Path,
+            description = "Name of the collection",
+            min_length = 1,
+            max_length = 32,
+            pattern = r"^[a-z][-a-z0-9]*$",
+        ),
+    ),
+    responses(
+        (status = OK, description = "Distinct values", body = DistinctValues ),
+        (status = UNAUTHORIZED, description = "User is not a collection reader" ),
+        (status = NOT_FOUND, description = "Collection not found" ),
+        (status = BAD_REQUEST, description = "Invalid request" ),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
+    ),
+    tag = super::TAG_COLLECTION,
+)]
+pub(crate) async fn api_distinct_values(
+    State(ctx): State<ApiContext>,
+    ValidatedQueryParams(params): ValidatedQueryParams<DistinctValuesParams>,
+    Path(collection_name): Path<String>,
+    JwtClaims(user): JwtClaims<User>,
+) -> Result<Json<DistinctValues>, ApiErrors> {
+    let collection = get_unlocked_collection_by_name(&ctx.db, &collection_name)
+        .await
+        .ok_or_else(|| ApiErrors::NotFound(collection_name.clone()))?;
+
+    let user_is_permitted = user.is_collection_admin(&collection_name)
+        || user.can_access_all_documents(&collection_name)
+        || user.is_collection_reader(&collection_name);
+    if !user_is_permitted {
+        warn!("User {} is not a collection reader", user.name_and_sub());
+        return Err(ApiErrors::PermissionDenied);
+    }
+
+    let config = CollectionConfig::from(&collection);
+    if config.is_masked(&params.field) && !user.can_read_unmasked(&collection_name) {
+        return Err(ApiErrors::BadRequestJsonSimpleMsg(format!(
+            "Field {} is masked",
+            params.field
+        )));
+    }
+
+    let dto_collection: GrantCollection = (&collection).into();
+    let user_grants =
+        hook_or_default_user_grants(&ctx.hooks, &dto_collection, &user, ctx.data_service.clone())
+            .await?;
+
+    let mut request_filters = parse_pfilter(params.pfilter)?;
+    if let Some(title) = params.exact_title {
+        request_filters.push(FieldFilter::ExactFieldMatch {
+            field_name: "title".to_string(),
+            value: title,
+        });
+    }
+
+    let limit = params.limit.unwrap_or(DEFAULT_DISTINCT_VALUES_LIMIT);
+    let projection = ReadProjection::new(&ctx.hooks, &collection, &user);
+    let (values, has_more) = generic_distinct_values(
+        &ctx.db,
+        collection.id,
+        &params.field,
+        if request_filters.is_empty() {
+            None
+        } else {
+            Some(request_filters.into())
+        },
+        &projection,
+        ListDocumentGrants::Restricted(user_grants),
+        limit,
+    )
+    .await?;
+    let values = readable_values(&params.field, values, &projection)?;
+
+    Ok(Json(DistinctValues {
+        items: values
+            .into_iter()
+            .map(|value| DistinctValueCount {
+                value: value.value,
+                count: u32::try_from(value.count).unwrap_or(u32::MAX),
+            })
+            .collect(),
+        has_more,
+    }))
+}
+
+/// Runs the values through the projection of the collection like the field
+/// of a document. A field that the read hook removes is not readable, values
+/// that the hook replaces are counted together.
+fn readable_values(
+    field: &str,
+    values: Vec<DistinctValue>,
+    projection: &ReadProjection,
+) -> Result<Vec<DistinctValue>, ApiErrors> {
+    let path: Vec<&str> = field.split('.').collect();
+    let pointer = format!("/{}", path.join("/"));
+    let mut readable: Vec<DistinctValue> = vec![];
+    for value in values {
+        let readable_value = match value.value {
+            Some(text) => {
+                let mut f = path
+                    .iter()
+                    .rev()
+                    .fold(Value::String(text), |f, key| json!({ *key: f }));
+                projection.apply(Uuid::nil(), &mut f);
+                match f.pointer(&pointer) {
+                    None => {
+                        return Err(ApiErrors::BadRequestJsonSimpleMsg(format!(
+                            "Field {field} is not readable"
+                        )))
+                    }
+                    Some(Value::Null) => None,
+                    Some(Value::String(s)) => Some(s.clone()),
+                    Some(other) => Some(other.to_string()),
+                }
+            }
+            None => None,
+        };
+        match readable.iter_mut().find(|r| r.value == readable_value) {
+            Some(r) => r.count += value.count,
+            None => readable.push(DistinctValue {
+                value: readable_value,
+                count: value.count,
+            }),
+        }
+    }
+    readable.sort_by_key(|value| std::cmp::Reverse(value.count));
+    Ok(readable)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::api::{collection_config::CollectionConfig, read_projection};
+
+    fn value(value: Option<&str>, count: i64) -> DistinctValue {
+        DistinctValue {
+            value: value.map(str::to_string),
+            count,
+        }
+    }
+
+    #[test]
+    fn it_keeps_readable_values() {
+        // Arrange
+        let projection = ReadProjection::from_parts(CollectionConfig::default(), false, None);
+        let values = vec![value(Some("open"), 12), value(None, 3)];
+
+        // Act
+        let result = readable_values("workflow.status", values.clone(), &projection).unwrap();
+
+        // Assert
+        assert_eq!(result, values);
+    }
+
+    #[test]
+    fn it_rejects_fields_removed_by_the_read_hook() {
+        // Arrange
+        let values = vec![value(Some("52000"), 1)];
+
+        // Act
+        let result = readable_values("salary", values, &read_projection::redacting(&["salary"]));
+
+        // Assert
+        assert!(matches!(result, Err(ApiErrors::BadRequestJsonSimpleMsg(_))));
+    }
+}
@@ -0,0 +1,170 @@
+use axum::{async_trait, extract::FromRequestParts, http::header::IF_MATCH, http::request::Parts};
+use serde_json::Value;
+
+use super::ApiErrors;
+
+/// Server managed field with the number of updates of a document
+pub(crate) const VERSION_FIELD: &str = "version";
+
+/// The optional `If-Match` header of an update with the expected version of
+/// the document, e. g. `3` or `"3"`. `*` matches every version.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct IfMatchVersion(pub Option<i64>);
+
+impl IfMatchVersion {
+    fn parse(value: Option<&str>) -> Result<Self, ApiErrors> {
+        let Some(value) = value.map(str::trim) else {
+            return Ok(IfMatchVersion(None));
+        };
+        if value == "*" {
+            return Ok(IfMatchVersion(None));
+        }
+        value
+            .trim_matches('"')
+            .parse::<i64>()
+            .ok()
+            .filter(|version| *version >= 0)
+            .map(|version| IfMatchVersion(Some(version)))
+            .ok_or_else(|| {
+                ApiErrors::BadRequestJsonSimpleMsg(
+                    "If-Match must be the version of the document".to_string(),
+                )
+            })
+    }
+
+    /// Returns the version for the update of a document with the stored
+    /// fields, rejects the update if the expected version is not stored.
+    pub(crate) fn next_version(&self, stored: &Value) -> Result<i64, ApiErrors> {
+        let version = stored_version(stored);
+        match self.0 {
+            Some(expected) if expected != version => Err(ApiErrors::Conflict(format!(
+                "Document has version {version}"
+            ))),
+            _ => Ok(next_version(stored)),
+        }
+    }
+}
+
+/// Returns the version of the document after the next write, see
+/// `save_documents_events_mails` which stores it.
+pub(crate) fn next_version(stored: &Value) -> i64 {
+    stored_version(stored) + 1
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for IfMatchVersion
+where
+    S: Send + Sync,
+{
+    type Rejection = ApiErrors;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        match parts.headers.get(IF_MATCH) {
+            Some(value) => IfMatchVersion::parse(Some(value.to_str().map_err(|_| {
+                ApiErrors::BadRequestJsonSimpleMsg("Invalid If-Match".to_string())
+            })?)),
+            None => IfMatchVersion::parse(None),
+        }
+    }
+}
+
+/// Removes the version from the fields of a new document. The version is
+/// managed by the server, a document without version has version 0.
+pub(crate) fn without_version(mut f: Value) -> Value {
+    if let Value::Object(fields) = &mut f {
+        fields.remove(VERSION_FIELD);
+    }
+    f
+}
+
+/// Version of the stored fields, documents that were never updated have
+/// version 0. A fractional number is truncated and other values count as 0,
+/// like in `with_next_version` which increments the stored version.
+fn stored_version(f: &Value) -> i64 {
+    f.get(VERSION_FIELD)
+        .and_then(|version| {
+            version
+                .as_i64()
+                .or_else(|| version.as_f64().map(|v| v.trunc() as i64))
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn it_parses_the_expected_version() {
+        // Act
+        let missing = IfMatchVersion::parse(None).unwrap();
+        let any = IfMatchVersion::parse(Some("*")).unwrap();
+        let plain = IfMatchVersion::parse(Some("3")).unwrap();
+        let quoted = IfMatchVersion::parse(Some(r#" "4" "#)).unwrap();
+        let negative = IfMatchVersion::parse(Some("-1"));
+        let text = IfMatchVersion::parse(Some(r#""a1b2""#));
+
+        // Assert
+        assert_eq!(missing, IfMatchVersion(None));
+        assert_eq!(any, IfMatchVersion(None));
+        assert_eq!(plain, IfMatchVersion(Some(3)));
+        assert_eq!(quoted, IfMatchVersion(Some(4)));
+        assert!(negative.is_err());
+        assert!(text.is_err());
+    }
+
+    #[test]
+    fn it_increments_the_matching_version() {
+        // Arrange
+        let stored = json!({"title": "Application", "version": 3});
+
+        // Act
+        let unconditional = IfMatchVersion(None).next_version(&stored).unwrap();
+        let versioned = IfMatchVersion(Some(3)).next_version(&stored).unwrap();
+        let first = IfMatchVersion(Some(0))
+            .next_version(&json!({"title": "Application"}))
+            .unwrap();
+
+        // Assert
+        assert_eq!(unconditional, 4);
+        assert_eq!(versioned, 4);
+        assert_eq!(first, 1);
+    }
+
+    #[test]
+    fn it_rejects_a_stale_version_with_a_conflict() {
+        // Arrange
+        let stored = json!({"title": "Application", "version": 5});
+
+        // Act
+        let result = IfMatchVersion(Some(4)).next_version(&stored);
+
+        // Assert
+        assert!(matches!(result, Err(ApiErrors::Conflict(_))));
+    }
+
+    #[test]
+    fn it_reads_the_stored_version_like_the_database() {
+        // Act
+        let fractional = next_version(&json!({"version": 1.7}));
+        let text = next_version(&json!({"version": "abc"}));
+        let versioned = IfMatchVersion(Some(1)).next_version(&json!({"version": 1.7}));
+
+        // Assert
+        assert_eq!(fractional, 2);
+        assert_eq!(text, 1);
+        assert_eq!(versioned.unwrap(), 2);
+    }
+
+    #[test]
+    fn it_removes_the_version_of_a_new_document() {
+        // Act
+        let f = without_version(json!({"title": "Application", "version": 99}));
+
+        // Assert
+        assert_eq!(f, json!({"title": "Application"}));
+    }
+}
@@ -15,6 +15,7 @@ use uuid::Uuid;
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct Collection {
+    id: Uuid,
     name: String,
     title: String,
     oao: bool,
@@ -22,8 +23,9 @@ pub struct Collection {
 }
 
 impl Collection {
-    pub fn new(name: String, title: String, oao: bool, locked: bool) -> Self {
+    pub fn new(id: Uuid, name: String, title: String, oao: bool, locked: bool) -> Self {
         Self {
+            id,
             name,
             title,
             oao,
@@ -31,14 +33,23 @@ impl Collection {
         }
     }
 
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
     pub fn name(&self) -> &str {
         self.name.as_ref()
     }
+
+    pub fn oao(&self) -> bool {
+        self.oao
+    }
 }
 
 impl From<&entity::collection::Model> for Collection {
     fn from(model: &entity::collection::Model) -> Self {
         Self {
+            id: model.id,
             name: model.name.clone(),
             title: model.title.clone(),
             oao: model.oao,
@@ -128,7 +139,7 @@ impl From<&entity::grant::Model> for Grant {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct GrantForDocument {
     grant: Grant,
     document_id: Uuid,
@@ -187,6 +198,11 @@ impl CollectionDocument {
         let _ = self.fields.as_object_mut().and_then(|obj| obj.remove(key));
     }
 
+    /// Returns the fields of the document.
+    pub fn into_fields(self) -> serde_json::Value {
+        self.fields
+    }
+
     /// Returns `true` if the document has been marked as deleted, `false` otherwise.
     pub fn is_deleted(&self) -> bool {
         // The field that stores the deletion timestamp.
@@ -289,7 +305,8 @@ impl ExistingEvent {
     }
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Event {
     document_id: uuid::Uuid,
     category: i32,
@@ -331,7 +348,15 @@ impl From<&entity::event::Model> for ExistingEvent {
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+/// Time window of events, `from` is inclusive and `to` is exclusive. A missing
+/// bound does not limit the events.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct EventWindow {
+    pub from: Option<chrono::DateTime<chrono::Utc>>,
+    pub to: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum MailMessageStatus {
     Pending,
     Sent(u64),
@@ -348,6 +373,12 @@ pub struct MailMessage {
     status: MailMessageStatus,
     #[serde(default)]
     attachments: Vec<MailMessageAttachment>,
+    /// Number of send attempts
+    #[serde(default)]
+    attempts: u32,
+    /// Unix timestamp before which the mail is not sent again
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    next_attempt_at: Option<u64>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, TypedBuilder)]
@@ -393,6 +424,8 @@ impl MailMessage {
     }
 
     pub fn set_sent(&mut self) {
+        self.attempts += 1;
+        self.next_attempt_at = None;
         self.status = MailMessageStatus::Sent(
             SystemTime::now()
                 .duration_since(SystemTime::UNIX_EPOCH)
@@ -401,6 +434,37 @@ impl MailMessage {
         );
     }
 
+    /// Records a failed send attempt. The next attempt is delayed by
+    /// `retry_delay` seconds, doubled for every further attempt. After
+    /// `max_retries` retries the message is marked as failed.
+    pub fn set_failed_attempt(&mut self, now: u64, max_retries: u32, retry_delay: u64) {
+        self.attempts += 1;
+        if self.attempts > max_retries {
+            self.next_attempt_at = None;
+            self.status = MailMessageStatus::Failed(now);
+            return;
+        }
+        let backoff = retry_delay.saturating_mul(1 << (self.attempts - 1).min(16));
+        self.next_attempt_at = Some(now.saturating_add(backoff));
+    }
+
+    /// Returns `true` if the message can be sent at `now`.
+    pub fn is_due(&self, now: u64) -> bool {
+        self.next_attempt_at.is_none_or(|at| at <= now)
+    }
+
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    pub fn next_attempt_at(&self) -> Option<u64> {
+        self.next_attempt_at
+    }
+
+    pub fn status(&self) -> &MailMessageStatus {
+        &self.status
+    }
+
     pub fn to(&self) -> &str {
         self.to.as_ref()
     }
@@ -486,6 +550,8 @@ impl MailMessageBuilder {
                 body_html,
                 status: MailMessageStatus::Pending,
                 attachments: self.attachments,
+                attempts: 0,
+                next_attempt_at: None,
             })
         } else {
             Err("Recipient, subject and body are required".to_string())
@@ -0,0 +1,211 @@
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+use super::error_response::client_error;
+use crate::models::CollectionItemsList;
+
+/// Name of the request header that selects the response shape
+pub(crate) const ACCEPT_VERSION_HEADER: &str = "Accept-Version";
+
+/// Shape of the responses, selected with the header `Accept-Version`
+///
+/// - `v1` (default): the legacy flat shape, e. g. `{"limit": 50, "items": [...]}`
+/// - `v2`: the data wrapped in an envelope with metadata, e. g.
+///   `{"data": [...], "meta": {"limit": 50}}`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum ApiVersion {
+    #[default]
+    V1,
+    V2,
+}
+
+impl ApiVersion {
+    fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "1" | "v1" => Some(ApiVersion::V1),
+            "2" | "v2" => Some(ApiVersion::V2),
+            _ => None,
+        }
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ApiVersion
+where
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Some(value) = parts.headers.get(ACCEPT_VERSION_HEADER) else {
+            return Ok(ApiVersion::default());
+        };
+        value
+            .to_str()
+            .ok()
+            .and_then(ApiVersion::parse)
+            .ok_or_else(|| {
+                client_error(
+                    StatusCode::BAD_REQUEST,
+                    "Unsupported Accept-Version, supported versions are: v1, v2",
+                )
+            })
+    }
+}
+
+/// Serializes a list of items in the shape of the API version.
+pub(crate) fn list_response(version: ApiVersion, list: CollectionItemsList) -> Response {
+    match version {
+        ApiVersion::V1 => Json(list).into_response(),
+        ApiVersion::V2 => Json(list_envelope(list)).into_response(),
+    }
+}
+
+/// Serializes a single item in the shape of the API version.
+pub(crate) fn item_response<T: Serialize>(version: ApiVersion, item: T) -> Response {
+    match version {
+        ApiVersion::V1 => Json(item).into_response(),
+        ApiVersion::V2 => Json(json!({ "data": item, "meta": {} })).into_response(),
+    }
+}
+
+fn list_envelope(list: CollectionItemsList) -> Value {
+    let mut meta = Map::new();
+    meta.insert("limit".to_string(), list.limit.into());
+    meta.insert("offset".to_string(), list.offset.into());
+    meta.insert("total".to_string(), list.total.into());
+    if let Some(total_approximate) = list.total_approximate {
+        meta.insert("totalApproximate".to_string(), total_approximate.into());
+    }
+    if let Some(applied_filter) = list.applied_filter {
+        meta.insert("appliedFilter".to_string(), applied_filter);
+    }
+    if let Some(next_cursor) = list.next_cursor {
+        meta.insert("nextCursor".to_string(), next_cursor.into());
+    }
+    if let Some(has_more) = list.has_more {
+        meta.insert("hasMore".to_string(), has_more.into());
+    }
+    json!({ "data": list.items, "meta": meta })
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{body::to_bytes, http::Request};
+    use pretty_assertions::assert_eq;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::models::CollectionItem;
+
+    async fn version_of(header: Option<&str>) -> Result<ApiVersion, StatusCode> {
+        let mut request = Request::builder();
+        if let Some(header) = header {
+            request = request.header(ACCEPT_VERSION_HEADER, header);
+        }
+        let (mut parts, _) = request.body(()).unwrap().into_parts();
+        ApiVersion::from_request_parts(&mut parts, &())
+            .await
+            .map_err(|response| response.status())
+    }
+
+    async fn body_of(response: Response) -> Value {
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    fn list() -> CollectionItemsList {
+        let id = Uuid::parse_str("3a8ed6a6-9cd7-4a4e-a0b5-42bfbd8e0c01").unwrap();
+        CollectionItemsList {
+            limit: 50,
+            offset: 0,
+            total: 1,
+            ..CollectionItemsList::new(vec![CollectionItem::new(id, json!({"title": "Circle"}))])
+        }
+    }
+
+    #[tokio::test]
+    async fn it_reads_the_requested_version() {
+        assert_eq!(version_of(None).await, Ok(ApiVersion::V1));
+        assert_eq!(version_of(Some("v1")).await, Ok(ApiVersion::V1));
+        assert_eq!(version_of(Some("2")).await, Ok(ApiVersion::V2));
+        assert_eq!(version_of(Some("V2")).await, Ok(ApiVersion::V2));
+        assert_eq!(version_of(Some("v3")).await, Err(StatusCode::BAD_REQUEST));
+    }
+
+    #[tokio::test]
+    async fn it_returns_the_flat_list_for_v1() {
+        // Act
+        let body = body_of(list_response(ApiVersion::V1, list())).await;
+
+        // Assert
+        assert_eq!(
+            body,
+            json!({
+                "limit": 50,
+                "offset": 0,
+                "total": 1,
+                "items": [{"id": "3a8ed6a6-9cd7-4a4e-a0b5-42bfbd8e0c01", "f": {"title": "Circle"}}]
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn it_returns_the_enveloped_list_for_v2() {
+        // Act
+        let body = body_of(list_response(ApiVersion::V2, list())).await;
+
+        // Assert
+        assert_eq!(
+            body,
+            json!({
+                "data": [{"id": "3a8ed6a6-9cd7-4a4e-a0b5-42bfbd8e0c01", "f": {"title": "Circle"}}],
+                "meta": {"limit": 50, "offset": 0, "total": 1}
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn it_flags_an_approximate_total() {
+        // Arrange
+        let approximate = CollectionItemsList {
+            total_approximate: Some(true),
+            ..list()
+        };
+
+        // Act
+        let v1 = body_of(list_response(ApiVersion::V1, approximate.clone())).await;
+        let v2 = body_of(list_response(ApiVersion::V2, approximate)).await;
+
+        // Assert
+        assert_eq!(v1["totalApproximate"], json!(true));
+        assert_eq!(v2["meta"]["totalApproximate"], json!(true));
+        assert_eq!(
+            body_of(list_response(ApiVersion::V1, list()))
+                .await
+                .get("totalApproximate"),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn it_returns_the_item_in_the_shape_of_the_version() {
+        // Arrange
+        let item = json!({"id": "3a8ed6a6-9cd7-4a4e-a0b5-42bfbd8e0c01", "f": {}});
+
+        // Act
+        let v1 = body_of(item_response(ApiVersion::V1, item.clone())).await;
+        let v2 = body_of(item_response(ApiVersion::V2, item.clone())).await;
+
+        // Assert
+        assert_eq!(v1, item);
+        assert_eq!(v2, json!({"data": item, "meta": {}}));
+    }
+}
@@ -0,0 +1,282 @@
+use axum::{
+    http::{HeaderName, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use serde_json::Value;
+
+/// Body of all client errors, e. g.
+/// `{"error": {"code": "not_found", "message": "shapes"}}`
+#[derive(Debug, Serialize)]
+struct ErrorResponse {
+    error: ErrorDetails,
+}
+
+/// Description of a client error
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct ErrorDetails {
+    /// Machine readable code, the validation rule of a field or derived from
+    /// the status, e. g. `bad_request`
+    code: String,
+
+    /// Human readable description
+    message: String,
+
+    /// The field that caused the error
+    #[serde(skip_serializing_if = "Option::is_none")]
+    field: Option<String>,
+
+    /// The complete error, e. g. all validation errors by field
+    #[serde(skip_serializing_if = "Option::is_none")]
+    details: Option<Value>,
+}
+
+impl ErrorDetails {
+    /// An error with a message and a code derived from the status.
+    pub(crate) fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            code: status_code_name(status),
+            message: message.into(),
+            field: None,
+            details: None,
+        }
+    }
+
+    /// An error from a JSON value, i. e. `{"message": ...}`, validation
+    /// errors by field (optionally nested in `errors`) or both.
+    pub(crate) fn from_json(status: StatusCode, value: Value) -> Self {
+        let message = value
+            .get("message")
+            .and_then(|m| m.as_str())
+            .map(str::to_string);
+        let field_error = first_field_error(value.get("errors").unwrap_or(&value));
+        let only_message = value.as_object().is_some_and(|o| o.len() == 1) && message.is_some();
+        let (code, field, field_message) = match field_error {
+            Some((field, code, field_message)) => (code, Some(field), field_message),
+            None => (status_code_name(status), None, None),
+        };
+        let field = field.or_else(|| {
+            value
+                .get("field")
+                .and_then(|f| f.as_str())
+                .map(str::to_string)
+        });
+        let message = message.or(field_message).unwrap_or_else(|| match &field {
+            Some(field) => format!("Invalid value of {field}"),
+            None => "Invalid request".to_string(),
+        });
+        Self {
+            code,
+            message,
+            field,
+            details: (!only_message).then_some(value),
+        }
+    }
+
+    /// An error from a JSON text, plain text becomes the message.
+    pub(crate) fn from_json_text(status: StatusCode, text: String) -> Self {
+        match serde_json::from_str::<Value>(&text) {
+            Ok(value) if value.is_object() => Self::from_json(status, value),
+            _ => Self::new(status, text),
+        }
+    }
+
+    /// Responds with the error as JSON body.
+    pub(crate) fn into_response_with(
+        self,
+        status: StatusCode,
+        headers: &[(HeaderName, HeaderValue)],
+    ) -> Response {
+        let mut response = (status, Json(ErrorResponse { error: self })).into_response();
+        for (name, value) in headers {
+            response.headers_mut().append(name.clone(), value.clone());
+        }
+        response
+    }
+}
+
+/// Responds with a client error whose code is derived from the status.
+pub(crate) fn client_error(status: StatusCode, message: impl Into<String>) -> Response {
+    ErrorDetails::new(status, message).into_response_with(status, &[])
+}
+
+/// Converts the reason of the status to snake case, e. g. `not_found`.
+fn status_code_name(status: StatusCode) -> String {
+    status
+        .canonical_reason()
+        .unwrap_or("error")
+        .to_ascii_lowercase()
+        .replace([' ', '-'], "_")
+}
+
+/// Returns the field, code and message of the first validation error by
+/// field name, e. g. of `{"limit": [{"code": "range", "message": null}]}`.
+fn first_field_error(value: &Value) -> Option<(String, String, Option<String>)> {
+    value
+        .as_object()?
+        .iter()
+        .filter_map(|(field, errors)| {
+            let error = errors.as_array()?.first()?;
+            let code = error.get("code")?.as_str()?;
+            let message = error.get("message").and_then(|m| m.as_str());
+            Some((field.clone(), code.to_string(), message.map(str::to_string)))
+        })
+        .min_by(|a, b| a.0.cmp(&b.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::to_bytes;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use validator::{ValidationError, ValidationErrors};
+
+    use super::*;
+    use crate::api::{hooks::HookRejection, ApiErrors};
+
+    async fn response_of(error: ApiErrors) -> (StatusCode, Value) {
+        let response = error.into_response();
+        let status = response.status();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn it_responds_to_validation_errors_with_the_error_envelope() {
+        // Arrange
+        let mut error = ValidationError::new("range");
+        error.add_param("min".into(), &1);
+        let mut errors = ValidationErrors::new();
+        errors.add("limit", error);
+
+        // Act
+        let (status, body) = response_of(ApiErrors::from(errors)).await;
+
+        // Assert
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body,
+            json!({"error": {
+                "code": "range",
+                "message": "Invalid value of limit",
+                "field": "limit",
+                "details": {"limit": [{"code": "range", "message": null, "params": {"min": 1}}]}
+            }})
+        );
+    }
+
+    #[tokio::test]
+    async fn it_responds_to_not_found_with_the_error_envelope() {
+        // Act
+        let (status, body) = response_of(ApiErrors::NotFound("shapes".to_string())).await;
+
+        // Assert
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(
+            body,
+            json!({"error": {"code": "not_found", "message": "shapes"}})
+        );
+    }
+
+    #[tokio::test]
+    async fn it_keeps_the_status_and_headers_of_hook_rejections() {
+        // Arrange
+        let rejection =
+            HookRejection::new(StatusCode::TOO_MANY_REQUESTS, "Slow down").retry_after(3);
+
+        // Act
+        let response = ApiErrors::from(rejection).into_response();
+
+        // Assert
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()["retry-after"], "3");
+    }
+
+    #[tokio::test]
+    async fn it_sets_retry_after_for_too_many_requests() {
+        // Arrange
+        let error = ApiErrors::TooManyRequests {
+            message: "Write rate limit of the collection exceeded".to_string(),
+            retry_after: Some(12),
+        };
+
+        // Act
+        let response = error.into_response();
+
+        // Assert
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()["retry-after"], "12");
+    }
+
+    #[test]
+    fn it_derives_the_code_from_the_status() {
+        // Act
+        let names: Vec<String> = [
+            StatusCode::BAD_REQUEST,
+            StatusCode::NOT_FOUND,
+            StatusCode::LOCKED,
+            StatusCode::TOO_MANY_REQUESTS,
+        ]
+        .into_iter()
+        .map(status_code_name)
+        .collect();
+
+        // Assert
+        assert_eq!(
+            names,
+            vec!["bad_request", "not_found", "locked", "too_many_requests"]
+        );
+    }
+
+    #[test]
+    fn it_describes_a_message() {
+        // Act
+        let details = ErrorDetails::from_json(
+            StatusCode::BAD_REQUEST,
+            json!({"message": "Duplicate document"}),
+        );
+
+        // Assert
+        assert_eq!(
+            details,
+            ErrorDetails::new(StatusCode::BAD_REQUEST, "Duplicate document")
+        );
+    }
+
+    #[test]
+    fn it_describes_the_first_field_of_validation_errors() {
+        // Arrange
+        let errors = json!({
+            "vat_id": [{"code": "required", "message": null, "params": {}}],
+            "id": [{"code": "nil", "message": "Nil ids are not allowed", "params": {}}]
+        });
+
+        // Act
+        let details = ErrorDetails::from_json(StatusCode::BAD_REQUEST, errors.clone());
+
+        // Assert
+        assert_eq!(
+            serde_json::to_value(details).unwrap(),
+            json!({
+                "code": "nil",
+                "message": "Nil ids are not allowed",
+                "field": "id",
+                "details": errors
+            })
+        );
+    }
+
+    #[test]
+    fn it_keeps_plain_text_as_message() {
+        // Act
+        let details =
+            ErrorDetails::from_json_text(StatusCode::BAD_REQUEST, "Validation error".into());
+
+        // Assert
+        assert_eq!(
+            details,
+            ErrorDetails::new(StatusCode::BAD_REQUEST, "Validation error")
+        );
+    }
+}
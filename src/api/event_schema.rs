@@ -0,0 +1,273 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use validator::ValidationError;
+
+/// Schema of an event payload, a subset of JSON Schema. Keywords that are not
+/// supported are rejected when the collection configuration is stored.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase", deny_unknown_fields)]
+pub struct EventSchema {
+    /// Type of the value
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    kind: Option<SchemaType>,
+
+    /// Schemas of the properties of an object
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    properties: BTreeMap<String, EventSchema>,
+
+    /// Properties that an object must contain
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    required: Vec<String>,
+
+    /// Permits properties of an object that are not listed in `properties`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    additional_properties: Option<bool>,
+
+    /// Schema of the items of an array
+    #[serde(skip_serializing_if = "Option::is_none")]
+    items: Option<Box<EventSchema>>,
+
+    /// Permitted values
+    #[serde(rename = "enum", skip_serializing_if = "Vec::is_empty")]
+    permitted: Vec<Value>,
+
+    /// Smallest permitted number
+    #[serde(skip_serializing_if = "Option::is_none")]
+    minimum: Option<f64>,
+
+    /// Largest permitted number
+    #[serde(skip_serializing_if = "Option::is_none")]
+    maximum: Option<f64>,
+
+    /// Minimum number of characters of a string
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_length: Option<usize>,
+
+    /// Maximum number of characters of a string
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_length: Option<usize>,
+}
+
+/// JSON type of a value
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SchemaType {
+    Object,
+    Array,
+    String,
+    Number,
+    Integer,
+    Boolean,
+    Null,
+}
+
+impl SchemaType {
+    fn matches(&self, value: &Value) -> bool {
+        match self {
+            SchemaType::Object => value.is_object(),
+            SchemaType::Array => value.is_array(),
+            SchemaType::String => value.is_string(),
+            SchemaType::Number => value.is_number(),
+            SchemaType::Integer => value.as_f64().is_some_and(|n| n.fract() == 0.0),
+            SchemaType::Boolean => value.is_boolean(),
+            SchemaType::Null => value.is_null(),
+        }
+    }
+}
+
+impl EventSchema {
+    /// Returns the validation errors of the value by path, e. g. `e.amount`
+    /// or `e.items.1`. The value is valid if there are no errors.
+    pub(crate) fn errors(
+        &self,
+        path: &str,
+        value: &Value,
+    ) -> BTreeMap<String, Vec<ValidationError>> {
+        let mut errors = BTreeMap::new();
+        self.collect_errors(path, value, &mut errors);
+        errors
+    }
+
+    fn collect_errors(
+        &self,
+        path: &str,
+        value: &Value,
+        errors: &mut BTreeMap<String, Vec<ValidationError>>,
+    ) {
+        let mut add = |path: String, error: ValidationError| {
+            errors.entry(path).or_default().push(error);
+        };
+        if let Some(kind) = self.kind {
+            if !kind.matches(value) {
+                let mut error = ValidationError::new("type");
+                error.add_param("type".into(), &kind);
+                add(path.to_string(), error);
+                return;
+            }
+        }
+        if !self.permitted.is_empty() && !self.permitted.contains(value) {
+            let mut error = ValidationError::new("enum");
+            error.add_param("values".into(), &self.permitted);
+            add(path.to_string(), error);
+        }
+        if let Some(number) = value.as_f64() {
+            if self.minimum.is_some_and(|minimum| number < minimum) {
+                let mut error = ValidationError::new("range");
+                error.add_param("min".into(), &self.minimum);
+                add(path.to_string(), error);
+            }
+            if self.maximum.is_some_and(|maximum| number > maximum) {
+                let mut error = ValidationError::new("range");
+                error.add_param("max".into(), &self.maximum);
+                add(path.to_string(), error);
+            }
+        }
+        if let Some(s) = value.as_str() {
+            let length = s.chars().count();
+            if self.min_length.is_some_and(|min| length < min)
+                || self.max_length.is_some_and(|max| length > max)
+            {
+                let mut error = ValidationError::new("length");
+                if let Some(min) = self.min_length {
+                    error.add_param("min".into(), &min);
+                }
+                if let Some(max) = self.max_length {
+                    error.add_param("max".into(), &max);
+                }
+                add(path.to_string(), error);
+            }
+        }
+        match value {
+            Value::Object(properties) => {
+                for name in self
+                    .required
+                    .iter()
+                    .filter(|name| !properties.contains_key(*name))
+                {
+                    add(format!("{path}.{name}"), ValidationError::new("required"));
+                }
+                for (name, property) in properties {
+                    let property_path = format!("{path}.{name}");
+                    match self.properties.get(name) {
+                        Some(schema) => schema.collect_errors(&property_path, property, errors),
+                        None if self.additional_properties == Some(false) => {
+                            errors
+                                .entry(property_path)
+                                .or_default()
+                                .push(ValidationError::new("additional_properties"));
+                        }
+                        None => {}
+                    }
+                }
+            }
+            Value::Array(items) => {
+                if let Some(schema) = &self.items {
+                    for (index, item) in items.iter().enumerate() {
+                        schema.collect_errors(&format!("{path}.{index}"), item, errors);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    fn approval_schema() -> EventSchema {
+        serde_json::from_value(json!({
+            "type": "object",
+            "required": ["approved", "reason"],
+            "additionalProperties": false,
+            "properties": {
+                "approved": {"type": "boolean"},
+                "reason": {"type": "string", "minLength": 3},
+                "level": {"type": "integer", "minimum": 1, "maximum": 3},
+                "tags": {"type": "array", "items": {"enum": ["urgent", "late"]}}
+            }
+        }))
+        .unwrap()
+    }
+
+    fn error_codes(errors: &BTreeMap<String, Vec<ValidationError>>) -> Vec<(String, String)> {
+        errors
+            .iter()
+            .flat_map(|(path, errors)| {
+                errors
+                    .iter()
+                    .map(|error| (path.clone(), error.code.to_string()))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn it_accepts_a_conforming_payload() {
+        // Arrange
+        let schema = approval_schema();
+        let payload = json!({"approved": true, "reason": "Budget", "level": 2, "tags": ["late"]});
+
+        // Act
+        let errors = schema.errors("e", &payload);
+
+        // Assert
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn it_reports_the_errors_of_a_non_conforming_payload() {
+        // Arrange
+        let schema = approval_schema();
+        let payload = json!({"approved": "yes", "level": 1.5, "tags": ["late", "soon"], "note": 1});
+
+        // Act
+        let errors = schema.errors("e", &payload);
+
+        // Assert
+        assert_eq!(
+            error_codes(&errors),
+            vec![
+                ("e.approved".to_string(), "type".to_string()),
+                ("e.level".to_string(), "type".to_string()),
+                ("e.note".to_string(), "additional_properties".to_string()),
+                ("e.reason".to_string(), "required".to_string()),
+                ("e.tags.1".to_string(), "enum".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_checks_ranges_and_lengths() {
+        // Arrange
+        let schema = approval_schema();
+        let payload = json!({"approved": false, "reason": "no", "level": 4});
+
+        // Act
+        let errors = schema.errors("e", &payload);
+
+        // Assert
+        assert_eq!(
+            error_codes(&errors),
+            vec![
+                ("e.level".to_string(), "range".to_string()),
+                ("e.reason".to_string(), "length".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_rejects_unsupported_keywords() {
+        // Act
+        let schema =
+            serde_json::from_value::<EventSchema>(json!({"type": "string", "pattern": "^[A-Z]+$"}));
+
+        // Assert
+        assert!(schema.is_err());
+    }
+}
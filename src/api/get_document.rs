@@ -1,22 +1,44 @@
 use axum::{
     extract::{Path, State},
-    Json,
+    response::Response,
 };
 use axum_macros::debug_handler;
-use entity::event::Entity as Events;
 use jwt_authorizer::JwtClaims;
-use sea_orm::{prelude::Uuid, ColumnTrait, EntityTrait, QueryFilter, QueryOrder};
+use sea_orm::prelude::Uuid;
+use serde::Deserialize;
 use sqlx::types::chrono::DateTime;
 use tracing::warn;
+use validator::Validate;
 
 use crate::api::{
     auth::User,
-    db::{get_accessible_document, get_collection_by_name},
+    db::{get_accessible_document, get_collection_by_name, DELETED_AT_FIELD},
     ApiContext, ApiErrors,
 };
+use crate::axumext::extractors::ValidatedQueryParams;
 use crate::models::{CollectionItemDetails, CollectionItemEvent};
 
-use super::grants::{hook_or_default_user_grants, GrantCollection};
+use super::{
+    collection_config::CollectionConfig,
+    data_service::event_service::document_events_newest_first,
+    envelope::{item_response, ApiVersion},
+    grants::{hook_or_default_user_grants, GrantCollection},
+    read_projection::ReadProjection,
+};
+
+#[derive(Debug, Default, Deserialize, Validate, utoipa::IntoParams)]
+#[serde(default)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct ReadDocumentParams {
+    /// Read the document even if it has been deleted. The response then
+    /// contains the fields `deleted` and `deletedAt`.
+    ///
+    /// Only permitted as configured with `deletedDocumentAccess` of the
+    /// collection, other users receive status 404.
+    #[serde(rename = "includeDeleted")]
+    #[param(example = true, default = false)]
+    include_deleted: Option<bool>,
+}
 
 /// Get item
 ///
@@ -27,6 +49,7 @@ use super::grants::{hook_or_default_user_grants, GrantCollection};
     path = "/collections/{collection_name}/{document_id}",
     operation_id = "getItemById",
     params(
+        ReadDocumentParams,
         (
             "collection_name" = String,
             Path,
@@ -35,7 +58,12 @@ use super::grants::{hook_or_default_user_grants, GrantCollection};
             max_length = 32,
             pattern = r"^[a-z][-a-z0-9]*$",
         ),
-        ("document_id" = String, Path, description = "UUID of the document", format = Uuid )
+        ("document_id" = String, Path, description = "UUID of the document", format = Uuid ),
+        (
+            "Accept-Version" = Option<String>,
+            Header,
+            description = "Shape of the response: `v1` (default) or `v2` with the data in an envelope `{\"data\": ..., \"meta\": {...}}`",
+        ),
     ),
     responses(
         (status = OK, description = "Document data", body = CollectionItemDetails ),
@@ -49,8 +77,10 @@ use super::grants::{hook_or_default_user_grants, GrantCollection};
 pub(crate) async fn api_read_document(
     State(ctx): State<ApiContext>,
     Path((collection_name, document_id)): Path<(String, String)>,
+    ValidatedQueryParams(params): ValidatedQueryParams<ReadDocumentParams>,
+    version: ApiVersion,
     JwtClaims(user): JwtClaims<User>,
-) -> Result<Json<CollectionItemDetails>, ApiErrors> {
+) -> Result<Response, ApiErrors> {
     let document_uuid = Uuid::parse_str(&document_id)
         .map_err(|_| ApiErrors::BadRequestJsonSimpleMsg("Invalid uuid".to_string()))?;
 
@@ -80,33 +110,149 @@ pub(crate) async fn api_read_document(
         user.subuuid(),
         &collection,
         document_uuid,
+        params.include_deleted.unwrap_or(false),
     )
     .await?;
 
+    let config = CollectionConfig::from(&collection);
+    let document = document.filter(|document| {
+        !document.is_deleted()
+            || config.can_read_deleted(
+                document.owner == user.subuuid(),
+                user.is_collection_admin(&collection_name),
+            )
+    });
     if document.is_none() {
         return Err(ApiErrors::NotFound(format!(
             "Document {document_id} not found"
         )));
     }
     let document = document.unwrap();
-
-    let events = Events::find()
-        .filter(entity::event::Column::DocumentId.eq(Uuid::parse_str(document_id.as_ref()).ok()))
-        .order_by_desc(entity::event::Column::Id)
+    let events = document_events_newest_first(document.id)
         .all(&ctx.db)
         .await?
         .into_iter()
-        .map(|event| CollectionItemEvent {
-            id: u32::try_from(event.id).unwrap(),
-            category: event.category_id,
-            e: event.payload,
-            ts: DateTime::from_naive_utc_and_offset(event.timestamp.unwrap(), chrono::Utc),
-        })
+        .map(|event| collection_item_event(event, &config))
         .collect();
 
-    Ok(Json(CollectionItemDetails {
-        id: document.id,
-        f: document.f,
-        e: events,
-    }))
+    let deleted = document.is_deleted();
+    let mut f = document.f;
+    ReadProjection::new(&ctx.hooks, &collection, &user).apply(document.id, &mut f);
+
+    let mut item = CollectionItemDetails::new(document.id, f, events);
+    if deleted {
+        set_deleted_state(&mut item);
+    }
+    Ok(item_response(version, item))
+}
+
+/// Marks the item as deleted.
+fn set_deleted_state(item: &mut CollectionItemDetails) {
+    item.deleted = Some(true);
+    item.deleted_at = item
+        .f
+        .get(DELETED_AT_FIELD)
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+}
+
+fn collection_item_event(
+    event: entity::event::Model,
+    config: &CollectionConfig,
+) -> CollectionItemEvent {
+    CollectionItemEvent {
+        id: u32::try_from(event.id).unwrap(),
+        category: event.category_id,
+        category_name: config
+            .category_name(event.category_id)
+            .map(|name| name.to_string()),
+        e: event.payload,
+        ts: DateTime::from_naive_utc_and_offset(event.timestamp.unwrap(), chrono::Utc),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    fn event(category_id: i32) -> entity::event::Model {
+        entity::event::Model {
+            id: 5,
+            timestamp: DateTime::from_timestamp(1700000000, 0).map(|t| t.naive_utc()),
+            document_id: Uuid::nil(),
+            user: Uuid::nil(),
+            category_id,
+            payload: json!({"approved": true}),
+            published: true,
+        }
+    }
+
+    #[test]
+    fn it_adds_the_configured_category_name() {
+        // Arrange
+        let config = CollectionConfig::from_json(&json!({"eventCategories": {"1020": "Approved"}}));
+
+        // Act
+        let e = collection_item_event(event(1020), &config);
+
+        // Assert
+        assert_eq!(
+            serde_json::to_value(e).unwrap(),
+            json!({
+                "id": 5,
+                "ts": "2023-11-14T22:13:20Z",
+                "category": 1020,
+                "categoryName": "Approved",
+                "e": {"approved": true}
+            })
+        );
+    }
+
+    #[test]
+    fn it_omits_unknown_category_names() {
+        // Arrange
+        let config = CollectionConfig::from_json(&json!({"eventCategories": {"1020": "Approved"}}));
+
+        // Act
+        let e = collection_item_event(event(7), &config);
+
+        // Assert
+        assert_eq!(e.category_name, None);
+        assert!(serde_json::to_value(e)
+            .unwrap()
+            .get("categoryName")
+            .is_none());
+    }
+
+    #[test]
+    fn it_marks_deleted_documents() {
+        // Arrange
+        let mut item = CollectionItemDetails::new(
+            Uuid::nil(),
+            json!({"title": "Circle", "folivafy_deleted_at": "2023-11-14T22:13:20Z"}),
+            vec![],
+        );
+
+        // Act
+        set_deleted_state(&mut item);
+
+        // Assert
+        let value = serde_json::to_value(item).unwrap();
+        assert_eq!(value["deleted"], json!(true));
+        assert_eq!(value["deletedAt"], json!("2023-11-14T22:13:20Z"));
+    }
+
+    #[test]
+    fn it_omits_the_deleted_state_of_active_documents() {
+        // Act
+        let item = CollectionItemDetails::new(Uuid::nil(), json!({"title": "Circle"}), vec![]);
+
+        // Assert
+        let value = serde_json::to_value(item).unwrap();
+        assert!(value.get("deleted").is_none());
+        assert!(value.get("deletedAt").is_none());
+    }
 }
@@ -1,12 +1,15 @@
 use crate::api::hooks::grants::HookDocumentGrantContext;
+use sea_orm::ConnectionTrait;
+use tracing::{debug, warn};
 use typed_builder::TypedBuilder;
 use uuid::Uuid;
 
 use super::{
     auth::User,
+    collection_config::CollectionConfig,
     data_service::DataService,
-    db::CollectionDocumentVisibility,
-    dto::{self, Grant},
+    db::{get_document_grants, get_parent_document_grants, CollectionDocumentVisibility},
+    dto::{self, Grant, GrantForDocument},
     hooks::{grants::HookUserGrantContext, Hooks},
     ApiErrors,
 };
@@ -50,6 +53,7 @@ pub struct GrantCollection {
     name: String,
     id: Uuid,
     oao: bool,
+    parent: Option<String>,
 }
 
 impl GrantCollection {
@@ -72,6 +76,20 @@ impl From<&entity::collection::Model> for GrantCollection {
             name: model.name.clone(),
             id: model.id,
             oao: model.oao,
+            parent: CollectionConfig::from(model)
+                .parent()
+                .map(|parent| parent.collection().to_string()),
+        }
+    }
+}
+
+impl From<&dto::Collection> for GrantCollection {
+    fn from(collection: &dto::Collection) -> Self {
+        Self {
+            name: collection.name().to_string(),
+            id: collection.id(),
+            oao: collection.oao(),
+            parent: None,
         }
     }
 }
@@ -81,6 +99,42 @@ pub(crate) async fn hook_or_default_user_grants(
     collection: &GrantCollection,
     user: &User,
     data_service: std::sync::Arc<dyn DataService>,
+) -> Result<Vec<Grant>, ApiErrors> {
+    let mut user_grants =
+        collection_user_grants(hooks, collection, user, data_service.clone()).await?;
+    if let Some(parent_name) = collection.parent.as_deref() {
+        user_grants.extend(parent_user_grants(hooks, parent_name, user, data_service).await?);
+    }
+    Ok(user_grants)
+}
+
+/// Readers of the parent collection receive their grants of the parent
+/// collection, too. This gives them access to documents that inherited
+/// the grants of a parent document.
+async fn parent_user_grants(
+    hooks: &Hooks,
+    parent_name: &str,
+    user: &User,
+    data_service: std::sync::Arc<dyn DataService>,
+) -> Result<Vec<Grant>, ApiErrors> {
+    let user_is_parent_reader = user.is_collection_admin(parent_name)
+        || user.can_access_all_documents(parent_name)
+        || user.is_collection_reader(parent_name);
+    if !user_is_parent_reader {
+        return Ok(vec![]);
+    }
+    let Some(parent) = data_service.get_collection_by_name(parent_name).await else {
+        warn!("Parent collection {} not found", parent_name);
+        return Ok(vec![]);
+    };
+    collection_user_grants(hooks, &(&parent).into(), user, data_service).await
+}
+
+async fn collection_user_grants(
+    hooks: &Hooks,
+    collection: &GrantCollection,
+    user: &User,
+    data_service: std::sync::Arc<dyn DataService>,
 ) -> Result<Vec<Grant>, ApiErrors> {
     let hook = hooks.get_grant_hook(&collection.name);
     let dug = {
@@ -127,13 +181,133 @@ pub(crate) async fn hook_or_default_document_grants(
     Ok(document_grants)
 }
 
+/// Returns the grants of the parent document if the collection has a parent
+/// reference. Documents without a parent or with a parent that does not
+/// exist do not inherit grants.
+pub(crate) async fn inherited_document_grants<C: ConnectionTrait>(
+    db: &C,
+    config: &CollectionConfig,
+    document: &dto::CollectionDocument,
+) -> Result<Vec<Grant>, ApiErrors> {
+    let Some(parent) = config.parent() else {
+        return Ok(vec![]);
+    };
+    let Some(parent_id) = parent.parent_id(document.fields()) else {
+        debug!("Document {} has no parent", document.id());
+        return Ok(vec![]);
+    };
+    let grants = get_parent_document_grants(db, parent.collection(), parent_id)
+        .await?
+        .unwrap_or_else(|| {
+            warn!(
+                "Parent document {} of document {} not found",
+                parent_id,
+                document.id()
+            );
+            vec![]
+        });
+    Ok(grants)
+}
+
+/// Adds the inherited grants to the document grants, skipping duplicates.
+pub(crate) fn with_inherited_grants(
+    mut grants: Vec<GrantForDocument>,
+    inherited: Vec<Grant>,
+    document_id: Uuid,
+) -> Vec<GrantForDocument> {
+    for grant in inherited {
+        let exists = grants
+            .iter()
+            .any(|g| g.document_id() == document_id && g.grant() == &grant);
+        if !exists {
+            grants.push(GrantForDocument::new(grant, document_id));
+        }
+    }
+    grants
+}
+
+/// Computes the grants of a stored document from scratch: the grants of the
+/// grant hook or the default grants and the grants inherited from the parent
+/// document.
+pub(crate) async fn rebuilt_document_grants<C: ConnectionTrait>(
+    db: &C,
+    hooks: &Hooks,
+    collection: &entity::collection::Model,
+    config: &CollectionConfig,
+    document: &entity::collection_document::Model,
+    data_service: std::sync::Arc<dyn DataService>,
+) -> Result<Vec<GrantForDocument>, ApiErrors> {
+    let grants = hook_or_default_document_grants(
+        hooks,
+        collection.into(),
+        document.into(),
+        data_service,
+        document.owner,
+    )
+    .await?;
+    let inherited_grants = inherited_document_grants(db, config, &document.into()).await?;
+    Ok(with_inherited_grants(
+        grants
+            .into_iter()
+            .map(|grant| GrantForDocument::new(grant, document.id))
+            .collect(),
+        inherited_grants,
+        document.id,
+    ))
+}
+
+/// Number of grants of a document that a rebuild adds and removes
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct GrantChanges {
+    pub(crate) added: usize,
+    pub(crate) removed: usize,
+}
+
+impl GrantChanges {
+    /// Compares the stored grants of a document with its rebuilt grants.
+    pub(crate) fn between(current: &[Grant], rebuilt: &[GrantForDocument]) -> Self {
+        let mut remaining: Vec<&Grant> = current.iter().collect();
+        let mut added = 0;
+        for grant in rebuilt {
+            match remaining.iter().position(|g| *g == grant.grant()) {
+                Some(index) => {
+                    remaining.swap_remove(index);
+                }
+                None => added += 1,
+            }
+        }
+        Self {
+            added,
+            removed: remaining.len(),
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.added == 0 && self.removed == 0
+    }
+}
+
+/// Loads the stored grants of a document and compares them with its rebuilt
+/// grants.
+pub(crate) async fn document_grant_changes<C: ConnectionTrait>(
+    db: &C,
+    document: &entity::collection_document::Model,
+    rebuilt: &[GrantForDocument],
+) -> Result<GrantChanges, ApiErrors> {
+    let current = get_document_grants(db, document).await?;
+    Ok(GrantChanges::between(&current, rebuilt))
+}
+
 #[cfg(test)]
 mod tests {
     use uuid::Uuid;
 
     use crate::api::{db::CollectionDocumentVisibility, grants::DefaultUserGrantsParameters};
 
-    use super::{default_document_grants, default_user_grants};
+    use super::{
+        default_document_grants, default_user_grants, with_inherited_grants, GrantChanges,
+    };
+    use crate::api::dto::{Grant, GrantForDocument};
 
     #[test]
     fn it_has_required_default_document_grants_for_public_collection() {
@@ -281,4 +455,67 @@ mod tests {
             grants
         );
     }
+
+    #[test]
+    fn it_adds_inherited_grants_once() {
+        // Arrange
+        let document_id = Uuid::new_v4();
+        let collection_uuid = Uuid::new_v4();
+        let parent_collection_uuid = Uuid::new_v4();
+        let grants = vec![GrantForDocument::new(
+            Grant::read_collection(collection_uuid),
+            document_id,
+        )];
+        let inherited = vec![
+            Grant::read_collection(parent_collection_uuid),
+            Grant::read_collection(collection_uuid),
+        ];
+
+        // Act
+        let grants = with_inherited_grants(grants, inherited, document_id);
+
+        // Assert
+        assert_eq!(2, grants.len(), "Provides 2 grants");
+        assert!(
+            grants.iter().any(|g| g.grant().realm() == "read-collection"
+                && g.grant().grant_id() == parent_collection_uuid
+                && g.document_id() == document_id),
+            "Grants {:?} has no inherited read-collection for {parent_collection_uuid}",
+            grants
+        );
+    }
+
+    #[test]
+    fn it_counts_the_added_and_removed_grants() {
+        // Arrange
+        let document_id = Uuid::new_v4();
+        let collection_id = Uuid::new_v4();
+        let author_id = Uuid::new_v4();
+        let current = default_document_grants(false, collection_id, author_id);
+        let rebuilt: Vec<GrantForDocument> =
+            default_document_grants(true, collection_id, author_id)
+                .into_iter()
+                .map(|grant| GrantForDocument::new(grant, document_id))
+                .collect();
+
+        // Act
+        let changes = GrantChanges::between(&current, &rebuilt);
+        let unchanged = GrantChanges::between(
+            &current,
+            &[GrantForDocument::new(
+                Grant::read_collection(collection_id),
+                document_id,
+            )],
+        );
+
+        // Assert
+        assert_eq!(
+            changes,
+            GrantChanges {
+                added: 2,
+                removed: 1
+            }
+        );
+        assert!(unchanged.is_empty());
+    }
 }
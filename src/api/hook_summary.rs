@@ -0,0 +1,314 @@
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{header, request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::dto;
+use crate::models::CollectionItem;
+
+/// Request header with the preferences of the client (RFC 7240)
+const PREFER_HEADER: &str = "Prefer";
+
+/// Response header that confirms the applied preference
+const PREFERENCE_APPLIED_HEADER: &str = "Preference-Applied";
+
+/// Preference of the `Prefer` request header that requests the summary
+pub(crate) const HOOK_SUMMARY_PREFERENCE: &str = "hook-summary";
+
+/// Is the summary of the hook results requested with `Prefer: hook-summary`?
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct PreferHookSummary(pub bool);
+
+impl PreferHookSummary {
+    /// Checks the preferences of all `Prefer` headers, e. g.
+    /// `respond-async, hook-summary`. Parameters of a preference are ignored.
+    fn from_headers<'a>(values: impl Iterator<Item = &'a str>) -> Self {
+        PreferHookSummary(values.flat_map(|value| value.split(',')).any(|preference| {
+            preference
+                .split([';', '='])
+                .next()
+                .is_some_and(|token| token.trim().eq_ignore_ascii_case(HOOK_SUMMARY_PREFERENCE))
+        }))
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for PreferHookSummary
+where
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(PreferHookSummary::from_headers(
+            parts
+                .headers
+                .get_all(PREFER_HEADER)
+                .iter()
+                .filter_map(|value| value.to_str().ok()),
+        ))
+    }
+}
+
+/// Is the stored document requested with `Accept: application/json`?
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct AcceptDocument(pub bool);
+
+impl AcceptDocument {
+    /// Checks the media ranges of all `Accept` headers. Clients that also
+    /// accept `text/plain`, e. g. `application/json, text/plain, */*`, keep
+    /// receiving the plain message.
+    fn from_headers<'a>(values: impl Iterator<Item = &'a str>) -> Self {
+        let media_types: Vec<String> = values
+            .flat_map(|value| value.split(','))
+            .filter_map(|range| range.split(';').next())
+            .map(|media_type| media_type.trim().to_ascii_lowercase())
+            .collect();
+        AcceptDocument(
+            media_types.iter().any(|t| t == "application/json")
+                && !media_types.iter().any(|t| t == "text/plain"),
+        )
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for AcceptDocument
+where
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(AcceptDocument::from_headers(
+            parts
+                .headers
+                .get_all(header::ACCEPT)
+                .iter()
+                .filter_map(|value| value.to_str().ok()),
+        ))
+    }
+}
+
+/// Events that the hooks produced while a document was written
+#[derive(Debug, Default, Serialize, utoipa::ToSchema)]
+#[schema(examples(json!({
+    "message": "Document saved",
+    "events": [{"document": "9f818bff-a1b4-487a-9706-29a5ac1cf898", "category": 1020}],
+    "documents": ["9f818bff-a1b4-487a-9706-29a5ac1cf898"]
+})))]
+pub(crate) struct HookSummary {
+    /// Result message, e. g. `Document saved`
+    message: String,
+
+    /// Events produced by the hooks
+    events: Vec<HookSummaryEvent>,
+
+    /// The written document and the documents of the produced events
+    documents: Vec<Uuid>,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub(crate) struct HookSummaryEvent {
+    /// Document of the event
+    document: Uuid,
+
+    /// Event category
+    category: i32,
+}
+
+impl HookSummary {
+    pub(crate) fn new(document_id: Uuid, hook_events: &[dto::Event]) -> Self {
+        let mut documents = vec![document_id];
+        for event in hook_events {
+            if !documents.contains(&event.document_id()) {
+                documents.push(event.document_id());
+            }
+        }
+        Self {
+            message: String::new(),
+            events: hook_events
+                .iter()
+                .map(|event| HookSummaryEvent {
+                    document: event.document_id(),
+                    category: event.category(),
+                })
+                .collect(),
+            documents,
+        }
+    }
+}
+
+/// Returns the plain message, or the summary if it was requested.
+pub(crate) fn write_response(
+    prefer: PreferHookSummary,
+    status: StatusCode,
+    message: String,
+    summary: HookSummary,
+) -> Response {
+    if !prefer.0 {
+        return (status, message).into_response();
+    }
+    (
+        status,
+        [(PREFERENCE_APPLIED_HEADER, HOOK_SUMMARY_PREFERENCE)],
+        Json(HookSummary { message, ..summary }),
+    )
+        .into_response()
+}
+
+/// Returns the stored document if it was requested with `Accept`, otherwise
+/// the plain message or the summary. `Prefer: hook-summary` takes precedence.
+pub(crate) fn created_response(
+    prefer: PreferHookSummary,
+    accept: AcceptDocument,
+    status: StatusCode,
+    message: String,
+    summary: HookSummary,
+    document: CollectionItem,
+) -> Response {
+    if accept.0 && !prefer.0 {
+        return (status, Json(document)).into_response();
+    }
+    write_response(prefer, status, message, summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::to_bytes;
+    use pretty_assertions::assert_eq;
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    #[test]
+    fn it_reads_the_preference() {
+        assert_eq!(
+            PreferHookSummary::from_headers(["hook-summary"].into_iter()),
+            PreferHookSummary(true)
+        );
+        assert_eq!(
+            PreferHookSummary::from_headers(["respond-async, Hook-Summary; lang=en"].into_iter()),
+            PreferHookSummary(true)
+        );
+        assert_eq!(
+            PreferHookSummary::from_headers(["return=minimal", "wait=5"].into_iter()),
+            PreferHookSummary(false)
+        );
+        assert_eq!(
+            PreferHookSummary::from_headers([].into_iter()),
+            PreferHookSummary(false)
+        );
+    }
+
+    #[tokio::test]
+    async fn it_summarizes_the_hook_events() {
+        // Arrange
+        let document = Uuid::parse_str("9f818bff-a1b4-487a-9706-29a5ac1cf898").unwrap();
+        let invoice = Uuid::parse_str("3a8ed6a6-9cd7-4a4e-a0b5-42bfbd8e0c01").unwrap();
+        let hook_events = vec![
+            dto::Event::new(document, 1020, json!({"approved": true})),
+            dto::Event::new(invoice, 1001, json!({"order": document})),
+            dto::Event::new(invoice, 1002, json!({})),
+        ];
+
+        // Act
+        let response = write_response(
+            PreferHookSummary(true),
+            StatusCode::CREATED,
+            "Document saved".to_string(),
+            HookSummary::new(document, &hook_events),
+        );
+
+        // Assert
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(
+            response.headers().get(PREFERENCE_APPLIED_HEADER).unwrap(),
+            "hook-summary"
+        );
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(
+            serde_json::from_slice::<Value>(&body).unwrap(),
+            json!({
+                "message": "Document saved",
+                "events": [
+                    {"document": document, "category": 1020},
+                    {"document": invoice, "category": 1001},
+                    {"document": invoice, "category": 1002}
+                ],
+                "documents": [document, invoice]
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn it_keeps_the_plain_message_by_default() {
+        // Act
+        let response = write_response(
+            PreferHookSummary(false),
+            StatusCode::CREATED,
+            "Document updated".to_string(),
+            HookSummary::new(Uuid::new_v4(), &[]),
+        );
+
+        // Assert
+        assert!(response.headers().get(PREFERENCE_APPLIED_HEADER).is_none());
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"Document updated");
+    }
+
+    #[test]
+    fn it_reads_the_accepted_media_types() {
+        assert_eq!(
+            AcceptDocument::from_headers(["Application/JSON; charset=utf-8"].into_iter()),
+            AcceptDocument(true)
+        );
+        assert_eq!(
+            AcceptDocument::from_headers(["application/json, text/plain, */*"].into_iter()),
+            AcceptDocument(false)
+        );
+        assert_eq!(
+            AcceptDocument::from_headers(["*/*"].into_iter()),
+            AcceptDocument(false)
+        );
+        assert_eq!(
+            AcceptDocument::from_headers([].into_iter()),
+            AcceptDocument(false)
+        );
+    }
+
+    #[tokio::test]
+    async fn it_returns_the_stored_document() {
+        // Arrange
+        let id = Uuid::parse_str("9f818bff-a1b4-487a-9706-29a5ac1cf898").unwrap();
+        let stored = CollectionItem::new(
+            id,
+            json!({"title": "Rectangle", "created": "2024-02-28T13:45:00Z"}),
+        );
+
+        // Act
+        let response = created_response(
+            PreferHookSummary(false),
+            AcceptDocument(true),
+            StatusCode::CREATED,
+            "Document saved".to_string(),
+            HookSummary::new(id, &[]),
+            stored,
+        );
+
+        // Assert
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(
+            serde_json::from_slice::<Value>(&body).unwrap(),
+            json!({
+                "id": id,
+                "f": {"title": "Rectangle", "created": "2024-02-28T13:45:00Z"}
+            })
+        );
+    }
+}
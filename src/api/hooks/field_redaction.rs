@@ -0,0 +1,104 @@
+use std::sync::Arc;
+
+use tracing::debug;
+
+use super::{DocumentReadHook, HookReadContext, Hooks};
+use crate::api::dto;
+
+/// Removes the fields from the documents of the collection that are read by
+/// users without the role.
+pub fn add_field_redaction_hook(hooks: &Hooks, collection: &str, fields: &[&str], role: &str) {
+    debug!("Adding field_redaction_hook {collection},{fields:?},{role}");
+    hooks.put_read_hook(
+        collection.to_string(),
+        Arc::new(FieldRedactionHook::new(fields, role)),
+    );
+}
+
+/// Removes top-level fields, e. g. a salary, unless the reader has the role.
+pub struct FieldRedactionHook {
+    fields: Vec<String>,
+    role: String,
+}
+
+impl FieldRedactionHook {
+    pub fn new(fields: &[&str], role: &str) -> Self {
+        Self {
+            fields: fields.iter().map(|field| field.to_string()).collect(),
+            role: role.to_string(),
+        }
+    }
+}
+
+impl DocumentReadHook for FieldRedactionHook {
+    fn on_read(&self, document: &mut dto::CollectionDocument, context: &HookReadContext) {
+        if context.user().has_role(&self.role) {
+            return;
+        }
+        for field in &self.fields {
+            document.remove_field(field);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::api::dto::UserWithRoles;
+
+    fn application() -> dto::CollectionDocument {
+        dto::CollectionDocument::new(
+            Uuid::new_v4(),
+            json!({"title": "Application", "salary": 52000, "position": "Developer"}),
+        )
+    }
+
+    #[test]
+    fn it_removes_the_fields_for_a_reviewer() {
+        // Arrange
+        let hook = FieldRedactionHook::new(&["salary"], "C_APPLICATIONS_HR");
+        let context = HookReadContext::new(UserWithRoles::new(
+            Uuid::new_v4(),
+            "Reviewer".to_string(),
+            vec!["C_APPLICATIONS_READER".to_string()],
+        ));
+        let mut document = application();
+
+        // Act
+        hook.on_read(&mut document, &context);
+
+        // Assert
+        assert_eq!(
+            document.fields(),
+            &json!({"title": "Application", "position": "Developer"})
+        );
+    }
+
+    #[test]
+    fn it_keeps_the_fields_for_a_privileged_reader() {
+        // Arrange
+        let hook = FieldRedactionHook::new(&["salary"], "C_APPLICATIONS_HR");
+        let context = HookReadContext::new(UserWithRoles::new(
+            Uuid::new_v4(),
+            "Recruiter".to_string(),
+            vec![
+                "C_APPLICATIONS_READER".to_string(),
+                "C_APPLICATIONS_HR".to_string(),
+            ],
+        ));
+        let mut document = application();
+
+        // Act
+        hook.on_read(&mut document, &context);
+
+        // Assert
+        assert_eq!(
+            document.fields(),
+            &json!({"title": "Application", "salary": 52000, "position": "Developer"})
+        );
+    }
+}
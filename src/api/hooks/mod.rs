@@ -1,5 +1,7 @@
+pub mod field_redaction;
 pub mod grants;
 pub mod staged_delete;
+pub mod webhook;
 
 use std::{
     collections::HashMap,
@@ -8,6 +10,7 @@ use std::{
 };
 
 use async_trait::async_trait;
+use axum::http::{header, HeaderName, HeaderValue, StatusCode};
 use chrono::Duration;
 use uuid::Uuid;
 
@@ -70,6 +73,8 @@ pub enum GrantSettings {
 pub enum GrantSettingsOnEvents {
     NoChange,
     Replace(Vec<GrantForDocument>),
+    /// Runs the grant hook of the collection for the stored document again
+    Default,
 }
 
 pub struct HookSuccessResult {
@@ -107,13 +112,95 @@ impl Debug for HookSuccessResult {
     }
 }
 
+/// A rejection returned by a hook that determines the response sent to the client.
+///
+/// Return it from a hook as `Err(HookRejection::conflict("...").into())` or as
+/// `DocumentResult::Err(...)`. The status code, message and headers are passed
+/// through to the client unchanged by the hooks that run before the response
+/// is sent. `on_created` of an event hook runs after the response, its
+/// rejections are only logged.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HookRejection {
+    status: StatusCode,
+    message: String,
+    headers: Vec<(HeaderName, HeaderValue)>,
+}
+
+impl HookRejection {
+    pub fn new(status: StatusCode, message: &str) -> Self {
+        Self {
+            status,
+            message: message.to_string(),
+            headers: vec![],
+        }
+    }
+
+    /// The document did not pass validation (422).
+    pub fn validation(message: &str) -> Self {
+        Self::new(StatusCode::UNPROCESSABLE_ENTITY, message)
+    }
+
+    /// The request conflicts with the current state of the document (409).
+    pub fn conflict(message: &str) -> Self {
+        Self::new(StatusCode::CONFLICT, message)
+    }
+
+    /// The user is authenticated, but the hook does not permit the operation (403).
+    pub fn forbidden(message: &str) -> Self {
+        Self::new(StatusCode::FORBIDDEN, message)
+    }
+
+    /// Add a header to the response.
+    pub fn with_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.push((name, value));
+        self
+    }
+
+    /// Add a `Retry-After` header with the given number of seconds.
+    pub fn retry_after(self, seconds: u64) -> Self {
+        self.with_header(header::RETRY_AFTER, HeaderValue::from(seconds))
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    pub fn message(&self) -> &str {
+        self.message.as_ref()
+    }
+
+    pub fn headers(&self) -> &[(HeaderName, HeaderValue)] {
+        &self.headers
+    }
+}
+
+impl std::fmt::Display for HookRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.status, self.message)
+    }
+}
+
+impl From<HookRejection> for ApiErrors {
+    fn from(value: HookRejection) -> Self {
+        ApiErrors::HookRejection(value)
+    }
+}
+
 pub type EventHookResult = Result<MultiDocumentsSuccessResult, ApiErrors>;
 pub type HookResult = Result<HookSuccessResult, ApiErrors>;
 
 #[derive(Eq, Hash, PartialEq, Clone, Debug)]
 pub enum CronDocumentSelector {
-    ByFieldEqualsValue { field: String, value: String },
-    ByDateFieldOlderThan { field: String, value: Duration },
+    ByFieldEqualsValue {
+        field: String,
+        value: String,
+    },
+    ByDateFieldOlderThan {
+        field: String,
+        value: Duration,
+    },
+    /// Matches if all selectors match
+    AllOf(Vec<CronDocumentSelector>),
 }
 
 pub struct HookCreateContext {
@@ -297,6 +384,21 @@ impl HookCronContext {
     }
 }
 
+pub struct HookReadContext {
+    user: UserWithRoles,
+}
+
+impl HookReadContext {
+    pub fn new(user: UserWithRoles) -> Self {
+        Self { user }
+    }
+
+    /// The user who reads the document
+    pub fn user(&self) -> &UserWithRoles {
+        &self.user
+    }
+}
+
 #[async_trait]
 pub trait DocumentCreatingHook {
     async fn on_creating(&self, context: &HookCreateContext) -> HookResult;
@@ -320,6 +422,36 @@ pub trait CronDefaultIntervalHook {
     async fn on_default_interval(&self, context: &HookCronContext) -> HookResult;
 }
 
+/// Changes a document before it is returned to a reader, e. g. to remove
+/// fields. The stored document is not changed.
+pub trait DocumentReadHook {
+    fn on_read(&self, document: &mut dto::CollectionDocument, context: &HookReadContext);
+}
+
+/// The read hook of a collection with the context of the reading user
+#[derive(Clone)]
+pub(crate) struct BoundReadHook {
+    hook: Arc<dyn DocumentReadHook + Send + Sync>,
+    context: Arc<HookReadContext>,
+}
+
+impl BoundReadHook {
+    /// Runs the hook on the fields of the document.
+    pub(crate) fn apply(&self, id: Uuid, f: &mut serde_json::Value) {
+        let mut document = dto::CollectionDocument::new(id, f.take());
+        self.hook.on_read(&mut document, &self.context);
+        *f = document.into_fields();
+    }
+}
+
+impl Debug for BoundReadHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BoundReadHook")
+            .field("user", &self.context.user().name())
+            .finish()
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Hash)]
 struct HookCollection {
     collection_name: String,
@@ -336,9 +468,24 @@ pub struct CronDefaultIntervalHookData {
     job_name: String,
     collection_name: String,
     document_selector: CronDocumentSelector,
+    interval: Option<std::time::Duration>,
 }
 
 impl CronDefaultIntervalHookData {
+    pub(crate) fn new(
+        job_name: &str,
+        collection_name: &str,
+        document_selector: CronDocumentSelector,
+        interval: Option<std::time::Duration>,
+    ) -> Self {
+        Self {
+            job_name: job_name.to_string(),
+            collection_name: collection_name.to_string(),
+            document_selector,
+            interval,
+        }
+    }
+
     pub fn job_name(&self) -> &str {
         self.job_name.as_ref()
     }
@@ -350,6 +497,11 @@ impl CronDefaultIntervalHookData {
     pub fn document_selector(&self) -> &CronDocumentSelector {
         &self.document_selector
     }
+
+    /// Interval of the hook, `None` runs the hook on every cron tick
+    pub fn interval(&self) -> Option<std::time::Duration> {
+        self.interval
+    }
 }
 
 #[derive(Clone)]
@@ -364,6 +516,7 @@ pub struct Hooks {
         >,
     >,
     grant_hooks: Arc<RwLock<HashMap<HookCollection, Arc<dyn grants::GrantHook + Send + Sync>>>>,
+    read_hooks: Arc<RwLock<HashMap<HookCollection, Arc<dyn DocumentReadHook + Send + Sync>>>>,
 }
 
 impl Hooks {
@@ -374,6 +527,7 @@ impl Hooks {
             event_hooks: Arc::new(RwLock::new(HashMap::new())),
             cron_default_interval_hooks: Arc::new(RwLock::new(HashMap::new())),
             grant_hooks: Arc::new(RwLock::new(HashMap::new())),
+            read_hooks: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
@@ -452,6 +606,19 @@ impl Hooks {
         value.cloned()
     }
 
+    /// Returns the event categories with a hook in the collection, ordered by
+    /// category.
+    pub fn get_event_hook_categories(&self, collection_name: &str) -> Vec<i32> {
+        let map = self.event_hooks.read().unwrap();
+        let mut categories: Vec<i32> = map
+            .keys()
+            .filter(|key| key.collection_name == collection_name)
+            .map(|key| key.category)
+            .collect();
+        categories.sort_unstable();
+        categories
+    }
+
     pub fn insert_cron_default_interval_hook(
         &self,
         job_name: &str,
@@ -459,11 +626,29 @@ impl Hooks {
         document_selector: CronDocumentSelector,
         hook: Arc<dyn CronDefaultIntervalHook + Send + Sync>,
     ) {
-        let key = CronDefaultIntervalHookData {
-            job_name: job_name.to_string(),
-            collection_name: collection_name.to_string(),
+        let key =
+            CronDefaultIntervalHookData::new(job_name, collection_name, document_selector, None);
+        let mut map = self.cron_default_interval_hooks.write().unwrap();
+        map.insert(key, hook);
+    }
+
+    /// Registers a cron hook that runs at most once per `interval`. The hook
+    /// runs on the first cron tick after the interval has elapsed, so an
+    /// interval shorter than `FOLIVAFY_CRON_INTERVAL` has no effect.
+    pub fn insert_cron_interval_hook(
+        &self,
+        job_name: &str,
+        collection_name: &str,
+        document_selector: CronDocumentSelector,
+        interval: std::time::Duration,
+        hook: Arc<dyn CronDefaultIntervalHook + Send + Sync>,
+    ) {
+        let key = CronDefaultIntervalHookData::new(
+            job_name,
+            collection_name,
             document_selector,
-        };
+            Some(interval),
+        );
         let mut map = self.cron_default_interval_hooks.write().unwrap();
         map.insert(key, hook);
     }
@@ -502,6 +687,42 @@ impl Hooks {
         let value = map.get(&key);
         value.cloned()
     }
+
+    pub fn put_read_hook(
+        &self,
+        collection_name: String,
+        hook: Arc<dyn DocumentReadHook + Send + Sync>,
+    ) {
+        self.read_hooks
+            .write()
+            .unwrap()
+            .insert(HookCollection { collection_name }, hook);
+    }
+
+    pub fn get_read_hook(
+        &self,
+        collection_name: &str,
+    ) -> Option<Arc<dyn DocumentReadHook + Send + Sync>> {
+        let key = HookCollection {
+            collection_name: collection_name.to_string(),
+        };
+        let map = self.read_hooks.read().unwrap();
+        let value = map.get(&key);
+        value.cloned()
+    }
+
+    /// Returns the read hook of the collection for the reading user.
+    pub(crate) fn bound_read_hook(
+        &self,
+        collection_name: &str,
+        user: UserWithRoles,
+    ) -> Option<BoundReadHook> {
+        self.get_read_hook(collection_name)
+            .map(|hook| BoundReadHook {
+                hook,
+                context: Arc::new(HookReadContext::new(user)),
+            })
+    }
 }
 
 impl Default for Hooks {
@@ -647,3 +868,55 @@ impl Debug for HookContext {
             .finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use axum::response::IntoResponse;
+
+    use super::*;
+
+    fn response_for(result: HookResult) -> axum::response::Response {
+        match result {
+            Ok(_) => panic!("Expected a rejection"),
+            Err(e) => e.into_response(),
+        }
+    }
+
+    #[test]
+    fn validation_rejection_maps_to_422() {
+        let response = response_for(Err(HookRejection::validation("Missing field").into()));
+
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[test]
+    fn conflict_rejection_maps_to_409() {
+        let response = response_for(Err(HookRejection::conflict("Already approved").into()));
+
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+    }
+
+    #[test]
+    fn forbidden_rejection_maps_to_403() {
+        let response = response_for(Err(HookRejection::forbidden("Not the reviewer").into()));
+
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[test]
+    fn rejection_carries_headers() {
+        let response = response_for(Err(HookRejection::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Try again later",
+        )
+        .retry_after(30)
+        .into()));
+
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers().get(header::RETRY_AFTER).unwrap(), "30");
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/json"
+        );
+    }
+}
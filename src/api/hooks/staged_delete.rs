@@ -1,28 +1,34 @@
 use async_trait::async_trait;
 use axum::extract::{Path, State};
+use axum::http::StatusCode;
 use axum::Json;
 use chrono::{DateTime, Duration};
 use jwt_authorizer::JwtClaims;
-use sea_orm::DatabaseConnection;
+use sea_orm::prelude::Uuid;
 use serde_json::{json, Value};
 use std::sync::Arc;
 use tracing::{debug, error, info, warn};
 
 use crate::api::auth::User;
-use crate::api::db::{get_unlocked_collection_by_name, FieldFilter, ListDocumentGrants};
+use crate::api::create_event::create_event;
+use crate::api::db::{
+    get_collection_by_name, get_document_by_id, get_unlocked_collection_by_name, ListDocumentGrants,
+};
 use crate::api::list_documents::{
-    generic_list_documents, parse_pfilter, DeletedDocuments, GenericListDocumentsParams,
-    ListDocumentParams,
+    count_mode, generic_list_documents, parse_pfilter, DeletedDocuments,
+    GenericListDocumentsParams, ListDocumentParams,
 };
+use crate::api::lock_document::ensure_collection_unlocked;
+use crate::api::read_projection::ReadProjection;
 use crate::api::types::Pagination;
 use crate::api::{
     db::{DELETED_AT_FIELD, DELETED_BY_FIELD},
     dto::UserWithRoles,
     hooks::StoreDocument,
-    ApiErrors, CATEGORY_DOCUMENT_DELETE, CATEGORY_DOCUMENT_RECOVER,
+    ApiContext, ApiErrors, CATEGORY_DOCUMENT_DELETE, CATEGORY_DOCUMENT_RECOVER,
 };
-use crate::axumext::extractors::ValidatedQueryParams;
-use crate::models::CollectionItemsList;
+use crate::axumext::extractors::{PathParams, ValidatedQueryParams};
+use crate::models::{CollectionItemsList, CreateEventBody};
 
 use super::{
     CronDefaultIntervalHook, CronDocumentSelector, EventCreatingHook, EventHookResult,
@@ -36,7 +42,20 @@ pub fn add_staged_delete_hook(
     stage1days: u16,
     stage2days: u16,
 ) {
-    debug!("Adding staged_delete_hook {collection},{stage1days},{stage2days}");
+    add_staged_delete_hook_with_selector(hooks, collection, stage1days, stage2days, None);
+}
+
+/// Adds the staged delete hook, the cron job removes only the documents
+/// that are older than both stages and match the `selector`, e. g.
+/// `status` equals `archived`.
+pub fn add_staged_delete_hook_with_selector(
+    hooks: &mut Hooks,
+    collection: &str,
+    stage1days: u16,
+    stage2days: u16,
+    selector: Option<CronDocumentSelector>,
+) {
+    debug!("Adding staged_delete_hook {collection},{stage1days},{stage2days},{selector:?}");
     let sd = Arc::new(StagedDelete { stage1days });
     hooks.put_event_hook(collection.to_string(), CATEGORY_DOCUMENT_DELETE, sd.clone());
     hooks.put_event_hook(
@@ -45,11 +64,23 @@ pub fn add_staged_delete_hook(
         sd.clone(),
     );
     let job_name = format!("{collection} staged_delete");
-    let document_selector = CronDocumentSelector::ByDateFieldOlderThan {
+    let document_selector = staged_delete_selector(stage1days, stage2days, selector);
+    hooks.insert_cron_default_interval_hook(&job_name, collection, document_selector, sd);
+}
+
+fn staged_delete_selector(
+    stage1days: u16,
+    stage2days: u16,
+    selector: Option<CronDocumentSelector>,
+) -> CronDocumentSelector {
+    let older_than = CronDocumentSelector::ByDateFieldOlderThan {
         field: DELETED_AT_FIELD.to_string(),
         value: Duration::days((stage1days + stage2days) as i64),
     };
-    hooks.insert_cron_default_interval_hook(&job_name, collection, document_selector, sd);
+    match selector {
+        Some(selector) => CronDocumentSelector::AllOf(vec![older_than, selector]),
+        None => older_than,
+    }
 }
 
 struct StagedDelete {
@@ -169,7 +200,7 @@ impl StagedDelete {
             events: vec![context.event().clone()],
             mails: vec![],
             trigger_cron: false,
-            grants: crate::api::hooks::GrantSettingsOnEvents::NoChange,
+            grants: crate::api::hooks::GrantSettingsOnEvents::Default,
         })
     }
 }
@@ -245,13 +276,14 @@ fn has_remover_role(user: &UserWithRoles, collection_name: &str) -> bool {
     tags = [crate::api::TAG_COLLECTION, crate::api::TAG_MAINTENANCE],
 )]
 pub(crate) async fn get_recoverables(
-    State(db): State<DatabaseConnection>,
+    State(ctx): State<ApiContext>,
     Path(collection_name): Path<String>,
     ValidatedQueryParams(pagination): ValidatedQueryParams<Pagination>,
     ValidatedQueryParams(list_params): ValidatedQueryParams<ListDocumentParams>,
     JwtClaims(user): JwtClaims<User>,
 ) -> Result<Json<CollectionItemsList>, ApiErrors> {
-    let collection = get_unlocked_collection_by_name(&db, &collection_name)
+    let pagination = pagination.limited_for(&user);
+    let collection = get_unlocked_collection_by_name(&ctx.db, &collection_name)
         .await
         .ok_or_else(|| ApiErrors::NotFound(collection_name.clone()))?;
 
@@ -267,16 +299,14 @@ pub(crate) async fn get_recoverables(
     }
 
     let grants = ListDocumentGrants::IgnoredForAdmin;
-    let mut request_filters = parse_pfilter(list_params.pfilter);
-    if let Some(title) = list_params.exact_title {
-        request_filters.push(FieldFilter::ExactFieldMatch {
-            field_name: "title".to_string(),
-            value: title,
-        });
-    }
+    let title_filter = list_params.exact_title_filter();
+    let created_filters = list_params.created_filters()?;
+    let mut request_filters = parse_pfilter(list_params.pfilter)?;
+    request_filters.extend(title_filter);
+    request_filters.extend(created_filters);
 
     generic_list_documents(
-        &db,
+        &ctx.db,
         collection.id,
         DeletedDocuments::LimitToDeletedDocuments,
         GenericListDocumentsParams::builder()
@@ -287,9 +317,93 @@ pub(crate) async fn get_recoverables(
             } else {
                 Some(request_filters.into())
             })
+            .projection(ReadProjection::new(&ctx.hooks, &collection, &user))
+            .count(count_mode(
+                list_params.approximate_count,
+                list_params.with_total,
+            ))
             .build(),
         grants,
         pagination,
     )
     .await
 }
+
+/// Restore a deleted document
+///
+/// Recovers a document from the staged deletion. The document is listed
+/// within the collection again and its grants are recalculated.
+///
+/// ### Required permissions
+///
+/// * `C_COLLECTIONNAME_READER` and `C_COLLECTIONNAME_REMOVER` to
+/// restore documents from the first stage.
+/// * `C_COLLECTIONNAME_ADMIN` to restore documents from the second stage.
+#[utoipa::path(
+    post,
+    path = "/recoverables/{collection_name}/{document_id}/restore",
+    operation_id = "restoreRecoverable",
+    params(
+        (
+            "collection_name" = String,
+            Path,
+            description = "Name of the collection",
+            min_length = 1,
+            max_length = 32,
+            pattern = r"^[a-z][-a-z0-9]*$",
+        ),
+        ("document_id" = Uuid, Path, description = "Id of the document"),
+    ),
+    responses(
+        (status = CREATED, description = "Document restored" ),
+        (status = UNAUTHORIZED, description = "User is not a collection remover or admin" ),
+        (status = NOT_FOUND, description = "Collection or document not found" ),
+        (status = BAD_REQUEST, description = "Document is not deleted" ),
+        (status = LOCKED, description = "Document or collection is locked" ),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
+    ),
+    tags = [crate::api::TAG_COLLECTION, crate::api::TAG_MAINTENANCE],
+)]
+pub(crate) async fn restore_recoverable(
+    State(ctx): State<ApiContext>,
+    PathParams((collection_name, document_id)): PathParams<(String, Uuid)>,
+    JwtClaims(user): JwtClaims<User>,
+) -> Result<(StatusCode, String), ApiErrors> {
+    let collection = get_collection_by_name(&ctx.db, &collection_name)
+        .await
+        .ok_or_else(|| ApiErrors::NotFound(collection_name.clone()))?;
+
+    let user_is_permitted = user.is_collection_admin(&collection_name)
+        || (user.is_collection_remover(&collection_name)
+            && user.is_collection_reader(&collection_name));
+    if !user_is_permitted {
+        warn!(
+            "User {} is not permitted for restore_recoverable",
+            user.name_and_sub()
+        );
+        return Err(ApiErrors::PermissionDenied);
+    }
+    ensure_collection_unlocked(&user, &collection)?;
+
+    let document = get_document_by_id(document_id, &ctx.db)
+        .await
+        .map_err(|e| {
+            error!("Failed to load document {document_id}: {e:?}");
+            ApiErrors::InternalServerError
+        })?;
+    if document.is_none_or(|document| document.collection_id != collection.id) {
+        return Err(ApiErrors::NotFound(document_id.to_string()));
+    }
+
+    create_event(
+        ctx,
+        user,
+        CreateEventBody {
+            category: CATEGORY_DOCUMENT_RECOVER,
+            collection: collection_name,
+            document: document_id,
+            e: json!({}),
+        },
+    )
+    .await
+}
@@ -0,0 +1,288 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use ring::hmac;
+use tracing::{debug, warn, Span};
+
+use super::{
+    EventCreatingHook, EventHookResult, GrantSettingsOnEvents, HookCreatedEventContext,
+    HookCreatingEventContext, HookResult, HookSuccessResult, Hooks, MultiDocumentsSuccessResult,
+};
+
+/// Header with the id of the tracing span that created the event
+pub const SPAN_ID_HEADER: &str = "X-Span-Id";
+/// Header with the HMAC-SHA256 signature of the body, e. g. `sha256=<hex>`
+pub const SIGNATURE_HEADER: &str = "X-Signature";
+/// Time limit of a webhook request
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Sends the events of the category to the URL after they were created.
+pub fn add_webhook_hook(
+    hooks: &Hooks,
+    collection: &str,
+    category: i32,
+    url: &str,
+    secret: Option<&str>,
+) {
+    debug!("Adding webhook_hook {collection},{category},{url}");
+    hooks.put_event_hook(
+        collection.to_string(),
+        category,
+        Arc::new(WebhookHook::new(url, secret)),
+    );
+}
+
+/// Accepts every event and posts it as JSON to an external system once it is
+/// stored. The body is signed if a secret is set, a failed request is logged
+/// and does not affect the stored event.
+pub struct WebhookHook {
+    url: String,
+    key: Option<hmac::Key>,
+    client: reqwest::Client,
+}
+
+impl WebhookHook {
+    pub fn new(url: &str, secret: Option<&str>) -> Self {
+        Self {
+            url: url.to_string(),
+            key: secret.map(|secret| hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes())),
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+/// Returns the value of the signature header for the body.
+fn signature(key: &hmac::Key, body: &[u8]) -> String {
+    let tag = hmac::sign(key, body);
+    let hex: String = tag
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    format!("sha256={hex}")
+}
+
+#[async_trait]
+impl EventCreatingHook for WebhookHook {
+    async fn on_creating(&self, context: &HookCreatingEventContext) -> EventHookResult {
+        Ok(MultiDocumentsSuccessResult {
+            documents: vec![],
+            events: vec![context.event().clone()],
+            mails: vec![],
+            grants: GrantSettingsOnEvents::NoChange,
+            trigger_cron: false,
+        })
+    }
+
+    async fn on_created(&self, context: &HookCreatedEventContext) -> HookResult {
+        let event = context.event();
+        let body = match serde_json::to_vec(event) {
+            Ok(body) => body,
+            Err(e) => {
+                warn!("Could not serialize event for webhook {}: {}", self.url, e);
+                return Ok(HookSuccessResult::empty());
+            }
+        };
+        let mut request = self
+            .client
+            .post(&self.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .timeout(WEBHOOK_TIMEOUT);
+        if let Some(span_id) = Span::current().id() {
+            request = request.header(SPAN_ID_HEADER, span_id.into_u64().to_string());
+        }
+        if let Some(key) = &self.key {
+            request = request.header(SIGNATURE_HEADER, signature(key, &body));
+        }
+        match request.body(body).send().await {
+            Ok(response) if response.status().is_success() => {
+                debug!(
+                    "Event of document {} sent to webhook {}",
+                    event.document_id(),
+                    self.url
+                );
+            }
+            Ok(response) => warn!(
+                "Webhook {} responded with status {} to event of document {}",
+                self.url,
+                response.status(),
+                event.document_id()
+            ),
+            Err(e) => warn!(
+                "Webhook {} failed for event of document {}: {}",
+                self.url,
+                event.document_id(),
+                e
+            ),
+        }
+        Ok(HookSuccessResult::empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Bytes,
+        http::{HeaderMap, StatusCode},
+        routing::post,
+        Router,
+    };
+    use pretty_assertions::assert_eq;
+    use serde_json::{json, Value};
+    use tokio::sync::mpsc;
+    use tracing::Instrument;
+    use tracing_subscriber::util::SubscriberInitExt;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::api::{
+        data_service::{DataService, User as DataServiceUser},
+        dto,
+        hooks::RequestContext,
+    };
+
+    struct EmptyDataService;
+
+    #[async_trait]
+    impl DataService for EmptyDataService {
+        async fn get_document_events(
+            &self,
+            _document_id: Uuid,
+        ) -> anyhow::Result<Vec<dto::ExistingEvent>> {
+            Ok(vec![])
+        }
+
+        async fn get_document_events_paged(
+            &self,
+            _document_id: Uuid,
+            _category: Option<i32>,
+            _limit: u64,
+            _offset: u64,
+        ) -> anyhow::Result<(Vec<dto::ExistingEvent>, u64)> {
+            Ok((vec![], 0))
+        }
+
+        async fn get_collection_events_paged(
+            &self,
+            _document_ids: sea_query::SelectStatement,
+            _category: Option<i32>,
+            _window: dto::EventWindow,
+            _limit: u64,
+            _offset: u64,
+        ) -> anyhow::Result<(Vec<dto::ExistingEvent>, u64)> {
+            Ok((vec![], 0))
+        }
+
+        async fn get_user_by_id(&self, user_id: Uuid) -> anyhow::Result<DataServiceUser> {
+            anyhow::bail!("Unknown user {user_id}")
+        }
+
+        async fn get_document(
+            &self,
+            _collection_name: &str,
+            _document_id: Uuid,
+        ) -> Option<dto::CollectionDocument> {
+            None
+        }
+
+        async fn get_collection_by_name(&self, _collection_name: &str) -> Option<dto::Collection> {
+            None
+        }
+
+        async fn get_collection_documents(
+            &self,
+            _collection_name: &str,
+        ) -> anyhow::Result<Vec<dto::CollectionDocument>> {
+            Ok(vec![])
+        }
+    }
+
+    /// Forwards the headers and the body of every request to the receiver.
+    async fn mock_webhook_server() -> (String, mpsc::UnboundedReceiver<(HeaderMap, Bytes)>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let app = Router::new().route(
+            "/events",
+            post(move |headers: HeaderMap, body: Bytes| async move {
+                sender.send((headers, body)).unwrap();
+                StatusCode::NO_CONTENT
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (format!("http://{addr}/events"), receiver)
+    }
+
+    fn created_context(event: dto::Event) -> HookCreatedEventContext {
+        HookCreatedEventContext::new(
+            event,
+            Arc::new(EmptyDataService),
+            Arc::new(RequestContext::new(
+                "orders",
+                Uuid::new_v4(),
+                dto::UserWithRoles::new(Uuid::new_v4(), "Jane".to_string(), vec![]),
+            )),
+        )
+    }
+
+    #[test]
+    fn it_signs_the_body_with_hmac_sha256() {
+        // Arrange
+        let key = hmac::Key::new(hmac::HMAC_SHA256, b"key");
+
+        // Act
+        let signature = signature(&key, b"The quick brown fox jumps over the lazy dog");
+
+        // Assert
+        assert_eq!(
+            signature,
+            "sha256=f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+    }
+
+    #[tokio::test]
+    async fn it_posts_the_signed_event() {
+        // Arrange
+        let _subscriber = tracing_subscriber::registry().set_default();
+        let (url, mut receiver) = mock_webhook_server().await;
+        let hook = WebhookHook::new(&url, Some("s3cr3t"));
+        let document_id = Uuid::new_v4();
+        let context = created_context(dto::Event::new(
+            document_id,
+            101,
+            json!({"status": "shipped"}),
+        ));
+        let span = tracing::info_span!("request");
+        let span_id = span.id().unwrap().into_u64().to_string();
+
+        // Act
+        let result = hook.on_created(&context).instrument(span).await;
+
+        // Assert
+        assert!(result.is_ok());
+        let (headers, body) = receiver.recv().await.unwrap();
+        assert_eq!(
+            serde_json::from_slice::<Value>(&body).unwrap(),
+            json!({"documentId": document_id, "category": 101, "payload": {"status": "shipped"}})
+        );
+        let key = hmac::Key::new(hmac::HMAC_SHA256, b"s3cr3t");
+        assert_eq!(headers[SIGNATURE_HEADER], signature(&key, &body));
+        assert_eq!(headers[SPAN_ID_HEADER], span_id);
+    }
+
+    #[tokio::test]
+    async fn it_ignores_an_unreachable_webhook() {
+        // Arrange
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/events", listener.local_addr().unwrap());
+        drop(listener);
+        let hook = WebhookHook::new(&url, None);
+        let context = created_context(dto::Event::new(Uuid::new_v4(), 101, json!({})));
+
+        // Act
+        let result = hook.on_created(&context).await;
+
+        // Assert
+        assert!(result.is_ok());
+    }
+}
@@ -0,0 +1,174 @@
+use axum::{async_trait, extract::FromRequestParts, http::request::Parts};
+use chrono::Utc;
+use entity::idempotency_key;
+use lazy_static::lazy_static;
+use sea_orm::{
+    sea_query::OnConflict, ColumnTrait, DatabaseConnection, DatabaseTransaction, DbErr,
+    EntityTrait, QueryFilter, Set,
+};
+use uuid::Uuid;
+
+use super::ApiErrors;
+
+/// Header with the client generated key of a create request
+pub(crate) const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// Maximum length of an idempotency key
+const MAX_KEY_LENGTH: usize = 255;
+
+lazy_static! {
+    static ref KEY_LIFETIME: chrono::Duration = key_lifetime_from_env();
+}
+
+/// Reads the number of hours a key is remembered from
+/// `FOLIVAFY_IDEMPOTENCY_KEY_HOURS`, defaults to 24.
+fn key_lifetime_from_env() -> chrono::Duration {
+    let hours = std::env::var("FOLIVAFY_IDEMPOTENCY_KEY_HOURS")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(24);
+    chrono::Duration::hours(hours)
+}
+
+/// The optional `Idempotency-Key` of a create request
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct IdempotencyKey(pub Option<String>);
+
+impl IdempotencyKey {
+    /// Accepts keys of 1 to 255 visible ASCII characters.
+    fn parse(value: Option<&str>) -> Result<Self, ApiErrors> {
+        let Some(value) = value else {
+            return Ok(IdempotencyKey(None));
+        };
+        let key = value.trim();
+        if key.is_empty()
+            || key.len() > MAX_KEY_LENGTH
+            || !key.chars().all(|c| c.is_ascii_graphic())
+        {
+            return Err(ApiErrors::BadRequestJsonSimpleMsg(format!(
+                "Idempotency-Key must have 1 to {MAX_KEY_LENGTH} visible ASCII characters"
+            )));
+        }
+        Ok(IdempotencyKey(Some(key.to_string())))
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for IdempotencyKey
+where
+    S: Send + Sync,
+{
+    type Rejection = ApiErrors;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        match parts.headers.get(IDEMPOTENCY_KEY_HEADER) {
+            Some(value) => IdempotencyKey::parse(Some(value.to_str().map_err(|_| {
+                ApiErrors::BadRequestJsonSimpleMsg("Invalid Idempotency-Key".to_string())
+            })?)),
+            None => IdempotencyKey::parse(None),
+        }
+    }
+}
+
+/// Outcome of recording an idempotency key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum KeyClaim {
+    /// The key is new, the document has to be stored
+    Claimed,
+    /// The key was used before for the document with this id
+    Replay(Uuid),
+}
+
+/// Returns the id of the document that was created with the key of the owner,
+/// if the key is recorded and has not expired. A repeated request is answered
+/// with this document before any checks or hooks of a new document run.
+pub(crate) async fn recorded_document(
+    db: &DatabaseConnection,
+    owner: Uuid,
+    collection_id: Uuid,
+    key: &str,
+) -> Result<Option<Uuid>, DbErr> {
+    let expired_before = Utc::now().naive_utc() - *KEY_LIFETIME;
+    Ok(
+        idempotency_key::Entity::find_by_id((owner, collection_id, key.to_string()))
+            .filter(idempotency_key::Column::CreatedAt.gte(expired_before))
+            .one(db)
+            .await?
+            .map(|recorded| recorded.document_id),
+    )
+}
+
+/// Records the key of the owner for the new document. A key that has been
+/// recorded before and has not expired yields the id of the original document.
+///
+/// Must run in the transaction that stores the document: a concurrent request
+/// with the same key waits for this transaction and then sees its key.
+pub(crate) async fn claim_key(
+    txn: &DatabaseTransaction,
+    owner: Uuid,
+    collection_id: Uuid,
+    key: &str,
+    document_id: Uuid,
+) -> Result<KeyClaim, DbErr> {
+    let now = Utc::now().naive_utc();
+    idempotency_key::Entity::delete_many()
+        .filter(idempotency_key::Column::Owner.eq(owner))
+        .filter(idempotency_key::Column::CreatedAt.lt(now - *KEY_LIFETIME))
+        .exec(txn)
+        .await?;
+
+    let inserted = idempotency_key::Entity::insert(idempotency_key::ActiveModel {
+        owner: Set(owner),
+        collection_id: Set(collection_id),
+        key: Set(key.to_string()),
+        document_id: Set(document_id),
+        created_at: Set(now),
+    })
+    .on_conflict(
+        OnConflict::columns([
+            idempotency_key::Column::Owner,
+            idempotency_key::Column::CollectionId,
+            idempotency_key::Column::Key,
+        ])
+        .do_nothing()
+        .to_owned(),
+    )
+    .exec_without_returning(txn)
+    .await?;
+    if inserted > 0 {
+        return Ok(KeyClaim::Claimed);
+    }
+
+    idempotency_key::Entity::find_by_id((owner, collection_id, key.to_string()))
+        .one(txn)
+        .await?
+        .map(|recorded| KeyClaim::Replay(recorded.document_id))
+        .ok_or_else(|| DbErr::RecordNotFound(format!("Idempotency key {key}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn it_parses_idempotency_keys() {
+        // Arrange
+        let long_key = "k".repeat(MAX_KEY_LENGTH + 1);
+
+        // Act
+        let missing = IdempotencyKey::parse(None).unwrap();
+        let key = IdempotencyKey::parse(Some(" 4f1c-retry-1 ")).unwrap();
+        let empty = IdempotencyKey::parse(Some(" "));
+        let too_long = IdempotencyKey::parse(Some(&long_key));
+        let with_space = IdempotencyKey::parse(Some("retry 1"));
+
+        // Assert
+        assert_eq!(missing, IdempotencyKey(None));
+        assert_eq!(key, IdempotencyKey(Some("4f1c-retry-1".to_string())));
+        assert!(empty.is_err());
+        assert!(too_long.is_err());
+        assert!(with_space.is_err());
+    }
+}
@@ -0,0 +1,446 @@
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::header,
+    response::IntoResponse,
+};
+use axum_macros::debug_handler;
+use futures_util::{stream, StreamExt};
+use jwt_authorizer::JwtClaims;
+use sea_orm::{DatabaseTransaction, DbErr, TransactionTrait};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, warn};
+
+use crate::api::{
+    auth::User,
+    collection_config::CollectionConfig,
+    create_document::{complete_new_document, prepare_new_document, save_error},
+    db::{get_collection_by_name, save_document_events_mails, DbGrantUpdate, InsertDocumentData},
+    dto,
+    lock_document::ensure_collection_unlocked,
+    maintenance::api_backup::NdjsonLines,
+    ApiContext, ApiErrors, Collection,
+};
+use crate::models::CollectionItem;
+
+/// Number of lines after which the imported documents are committed and the
+/// progress is reported
+const IMPORT_BATCH_SIZE: u64 = 100;
+
+#[derive(Debug, Default, Deserialize, utoipa::IntoParams)]
+#[serde(default)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct ImportParams {
+    /// Store all lines in one transaction, the first rejected line aborts the
+    /// import and nothing is stored
+    #[param(example = false, default = false)]
+    atomic: Option<bool>,
+}
+
+/// A line of the import response
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub(crate) enum ImportReport {
+    /// A line of the import was rejected
+    Error {
+        line: u64,
+        message: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        errors: Option<Value>,
+    },
+    /// Sent after every batch of lines
+    Progress {
+        processed: u64,
+        imported: u64,
+        failed: u64,
+    },
+    /// Last line of the response, an import without it was interrupted
+    End {
+        processed: u64,
+        imported: u64,
+        failed: u64,
+        aborted: bool,
+    },
+}
+
+impl ImportReport {
+    fn to_line(&self) -> String {
+        let mut line = serde_json::to_string(self).expect("Failed to serialize import report");
+        line.push('\n');
+        line
+    }
+}
+
+/// Import items
+///
+/// Reads new items of the collection as newline delimited JSON, one item
+/// (`{"id": ..., "f": {...}}`) per line. Every item is processed like a single
+/// new item, including hooks and creation events. The lines are processed
+/// while they are received and committed in batches of 100.
+///
+/// The response is newline delimited JSON that is streamed during the import:
+/// an `error` record for every rejected line, a `progress` record after every
+/// batch and an `end` record with the totals. Rejected lines do not stop the
+/// import, unless `atomic=true` is set: then all lines are stored in one
+/// transaction and the first rejected line aborts the import. Closing the
+/// connection aborts the import, the batches committed before are kept.
+#[debug_handler]
+#[utoipa::path(
+    post,
+    path = "/collections/{collection_name}/import",
+    operation_id = "importIntoCollection",
+    params(
+        ImportParams,
+        (
+            "collection_name" = String,
+            Path,
+            description = "Name of the collection",
+            min_length = 1,
+            max_length = 32,
+            pattern = r"^[a-z][-a-z0-9]*$",
+        ),
+    ),
+    request_body(content = String, description = "Items as newline delimited JSON", content_type = "application/x-ndjson"),
+    responses(
+        (status = OK, description = "Progress of the import as newline delimited JSON", content_type = "application/x-ndjson", body = String),
+        (status = UNAUTHORIZED, description = "User is not a collection editor" ),
+        (status = NOT_FOUND, description = "Collection not found" ),
+        (status = BAD_REQUEST, description = "Invalid request" ),
+        (status = LOCKED, description = "Collection is read only" ),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
+    ),
+    tag = super::TAG_COLLECTION,
+)]
+pub(crate) async fn api_import_documents(
+    State(ctx): State<ApiContext>,
+    JwtClaims(user): JwtClaims<User>,
+    Path(collection_name): Path<String>,
+    Query(params): Query<ImportParams>,
+    body: Body,
+) -> Result<impl IntoResponse, ApiErrors> {
+    if !user.is_collection_editor(&collection_name) {
+        warn!("User {} is not a collection editor", user.name_and_sub());
+        return Err(ApiErrors::PermissionDenied);
+    }
+
+    let collection = get_collection_by_name(&ctx.db, &collection_name)
+        .await
+        .ok_or_else(|| ApiErrors::NotFound(collection_name.clone()))?;
+
+    ensure_collection_unlocked(&user, &collection)?;
+    info!(
+        "Import into {} started by {}",
+        collection_name,
+        user.name_and_sub()
+    );
+
+    let (sender, receiver) = mpsc::channel::<Result<String, std::io::Error>>(16);
+    let mut import = Import {
+        config: CollectionConfig::from(&collection),
+        ctx,
+        user,
+        collection,
+        atomic: params.atomic.unwrap_or_default(),
+        sender,
+        processed: 0,
+        imported: 0,
+        pending: 0,
+        failed: 0,
+        trigger_cron: false,
+    };
+    tokio::spawn(async move {
+        match import.run(body).await {
+            Ok(()) => {}
+            Err(ImportStop::Disconnected) => info!("Import aborted by the client"),
+            Err(stop) => {
+                if let ImportStop::Failed(e) = stop {
+                    error!("Import failed: {e}");
+                }
+                let _ = import.report(import.end(true)).await;
+            }
+        }
+        import
+            .ctx
+            .trigger_cron_with_condition(import.trigger_cron)
+            .await;
+    });
+
+    let body = Body::from_stream(stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|line| (line, receiver))
+    }));
+    Ok(([(header::CONTENT_TYPE, "application/x-ndjson")], body))
+}
+
+/// Reason to stop an import before the end of the input
+#[derive(Debug)]
+enum ImportStop {
+    /// The client closed the connection
+    Disconnected,
+    /// A line was rejected in an atomic import
+    Rejected,
+    Failed(String),
+}
+
+impl From<DbErr> for ImportStop {
+    fn from(e: DbErr) -> Self {
+        ImportStop::Failed(e.to_string())
+    }
+}
+
+/// State of a running import
+struct Import {
+    ctx: ApiContext,
+    user: User,
+    collection: Collection,
+    config: CollectionConfig,
+    atomic: bool,
+    sender: mpsc::Sender<Result<String, std::io::Error>>,
+    processed: u64,
+    /// Committed documents
+    imported: u64,
+    /// Documents of the current transaction
+    pending: u64,
+    failed: u64,
+    trigger_cron: bool,
+}
+
+impl Import {
+    async fn run(&mut self, body: Body) -> Result<(), ImportStop> {
+        let mut txn = self.ctx.db.begin().await?;
+        let mut lines = NdjsonLines::default();
+        let mut data = body.into_data_stream();
+        while let Some(chunk) = data.next().await {
+            let chunk =
+                chunk.map_err(|e| ImportStop::Failed(format!("Reading import failed: {e}")))?;
+            for line in lines.push(&chunk) {
+                txn = self.apply(txn, &line).await?;
+            }
+        }
+        if let Some(line) = lines.finish() {
+            txn = self.apply(txn, &line).await?;
+        }
+        self.commit(txn).await?;
+        debug!(
+            "Import into {} finished: {} of {} line(s) imported",
+            self.collection.name, self.imported, self.processed
+        );
+        self.report(self.end(false)).await
+    }
+
+    /// Stores the item of the line and commits after every batch.
+    async fn apply(
+        &mut self,
+        txn: DatabaseTransaction,
+        line: &str,
+    ) -> Result<DatabaseTransaction, ImportStop> {
+        self.processed += 1;
+        match self.store_line(&txn, line).await? {
+            Ok(()) => self.pending += 1,
+            Err(err) => {
+                self.failed += 1;
+                self.report(line_error(self.processed, err)).await?;
+                if self.atomic {
+                    return Err(ImportStop::Rejected);
+                }
+            }
+        }
+        if !self.processed.is_multiple_of(IMPORT_BATCH_SIZE) {
+            return Ok(txn);
+        }
+        let txn = if self.atomic {
+            txn
+        } else {
+            self.commit(txn).await?;
+            self.ctx.db.begin().await?
+        };
+        self.report(ImportReport::Progress {
+            processed: self.processed,
+            imported: self.imported,
+            failed: self.failed,
+        })
+        .await?;
+        Ok(txn)
+    }
+
+    /// Stores the item within a savepoint, a rejected item is rolled back
+    /// without affecting the other items of the transaction.
+    async fn store_line(
+        &mut self,
+        txn: &DatabaseTransaction,
+        line: &str,
+    ) -> Result<Result<(), ApiErrors>, DbErr> {
+        let item = match serde_json::from_str::<CollectionItem>(line) {
+            Ok(item) => item,
+            Err(e) => {
+                return Ok(Err(ApiErrors::BadRequestJsonSimpleMsg(format!(
+                    "Invalid item: {e}"
+                ))))
+            }
+        };
+        if let Err(err) = self
+            .ctx
+            .write_rates
+            .check(self.collection.id, &self.config, 1)
+        {
+            return Ok(Err(err));
+        }
+        let new_document =
+            match prepare_new_document(&self.ctx, &self.user, &self.collection, &self.config, item)
+                .await
+            {
+                Ok(new_document) => new_document,
+                Err(err) => return Ok(Err(err)),
+            };
+        let trigger_cron = new_document.trigger_cron;
+        let savepoint = txn.begin().await?;
+        match self.store(&savepoint, new_document).await {
+            Ok(()) => {
+                savepoint.commit().await?;
+                self.trigger_cron |= trigger_cron;
+                Ok(Ok(()))
+            }
+            Err(err) => {
+                savepoint.rollback().await?;
+                Ok(Err(err))
+            }
+        }
+    }
+
+    async fn store(
+        &self,
+        txn: &DatabaseTransaction,
+        mut new_document: super::create_document::NewDocument,
+    ) -> Result<(), ApiErrors> {
+        complete_new_document(txn, &self.config, &mut new_document).await?;
+        save_document_events_mails(
+            txn,
+            &dto::User::read_from(&self.user),
+            Some(new_document.document),
+            Some(InsertDocumentData {
+                collection_id: self.collection.id,
+            }),
+            new_document.events,
+            DbGrantUpdate::Replace(new_document.grants),
+            new_document.mails,
+        )
+        .await
+        .map_err(save_error)
+    }
+
+    async fn commit(&mut self, txn: DatabaseTransaction) -> Result<(), ImportStop> {
+        txn.commit().await?;
+        if self.pending > 0 {
+            self.imported += self.pending;
+            self.pending = 0;
+            self.ctx.trigger_event_relay();
+        }
+        Ok(())
+    }
+
+    async fn report(&self, report: ImportReport) -> Result<(), ImportStop> {
+        self.sender
+            .send(Ok(report.to_line()))
+            .await
+            .map_err(|_| ImportStop::Disconnected)
+    }
+
+    fn end(&self, aborted: bool) -> ImportReport {
+        ImportReport::End {
+            processed: self.processed,
+            imported: self.imported,
+            failed: self.failed,
+            aborted,
+        }
+    }
+}
+
+/// Reports the reason why the line was rejected.
+fn line_error(line: u64, err: ApiErrors) -> ImportReport {
+    let (message, errors) = match err {
+        ApiErrors::BadRequestJsonSimpleMsg(message)
+        | ApiErrors::BadRequest(message)
+        | ApiErrors::NotFound(message) => (message, None),
+        ApiErrors::BadRequestJsonMsg(errors) => (
+            "Invalid item".to_string(),
+            Some(serde_json::from_str(&errors).unwrap_or(Value::String(errors))),
+        ),
+        ApiErrors::BadRequestJson(errors) => ("Invalid item".to_string(), Some(errors)),
+        ApiErrors::HookRejection(rejection) => (rejection.message().to_string(), None),
+        err => (err.to_string(), None),
+    };
+    ImportReport::Error {
+        line,
+        message,
+        errors,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn it_serializes_the_report_as_lines() {
+        // Arrange
+        let progress = ImportReport::Progress {
+            processed: 200,
+            imported: 198,
+            failed: 2,
+        };
+        let end = ImportReport::End {
+            processed: 250,
+            imported: 0,
+            failed: 1,
+            aborted: true,
+        };
+
+        // Act
+        let progress = progress.to_line();
+        let end = end.to_line();
+
+        // Assert
+        assert_eq!(
+            progress,
+            "{\"type\":\"progress\",\"processed\":200,\"imported\":198,\"failed\":2}\n"
+        );
+        assert_eq!(
+            end,
+            "{\"type\":\"end\",\"processed\":250,\"imported\":0,\"failed\":1,\"aborted\":true}\n"
+        );
+    }
+
+    #[test]
+    fn it_reports_the_reason_of_a_rejected_line() {
+        // Arrange
+        let validation_errors = json!({"id": [{"code": "nil"}]});
+
+        // Act
+        let duplicate = line_error(
+            3,
+            ApiErrors::BadRequestJsonSimpleMsg("Duplicate document".to_string()),
+        );
+        let invalid = line_error(
+            7,
+            ApiErrors::BadRequestJsonMsg(validation_errors.to_string()),
+        );
+
+        // Assert
+        assert_eq!(
+            serde_json::to_value(duplicate).unwrap(),
+            json!({"type": "error", "line": 3, "message": "Duplicate document"})
+        );
+        assert_eq!(
+            serde_json::to_value(invalid).unwrap(),
+            json!({"type": "error", "line": 7, "message": "Invalid item", "errors": validation_errors})
+        );
+    }
+}
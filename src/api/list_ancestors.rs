@@ -0,0 +1,235 @@
+use std::collections::HashSet;
+
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use jwt_authorizer::JwtClaims;
+use sea_orm::prelude::Uuid;
+use tracing::{debug, warn};
+
+use crate::models::{CollectionItem, CollectionItemAncestors};
+
+use super::{
+    auth::User,
+    collection_config::CollectionConfig,
+    db::{get_accessible_document, get_collection_by_name},
+    grants::{hook_or_default_user_grants, GrantCollection},
+    read_projection::ReadProjection,
+    ApiContext, ApiErrors, Collection,
+};
+
+/// Maximum number of ancestors, protects against long or cyclic chains
+const MAX_ANCESTOR_DEPTH: usize = 20;
+
+/// List ancestors
+///
+/// Follows the `parent` reference of the collection configuration and returns
+/// the parent documents, starting with the direct parent of the document.
+///
+/// The list ends at a document without a parent, or before the first parent
+/// that is missing, deleted or not accessible to the user. At most 20
+/// ancestors are returned.
+#[utoipa::path(
+    get,
+    path = "/collections/{collection_name}/{document_id}/ancestors",
+    operation_id = "listItemAncestors",
+    params(
+        (
+            "collection_name" = String,
+            Path,
+            description = "Name of the collection",
+            min_length = 1,
+            max_length = 32,
+            pattern = r"^[a-z][-a-z0-9]*$",
+        ),
+        ("document_id" = String, Path, description = "UUID of the document", format = Uuid )
+    ),
+    responses(
+        (status = OK, description = "Ancestor documents", body = CollectionItemAncestors ),
+        (status = UNAUTHORIZED, description = "User is not a collection reader" ),
+        (status = NOT_FOUND, description = "Document not found" ),
+        (status = BAD_REQUEST, description = "Invalid request" ),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
+    ),
+    tag = super::TAG_COLLECTION,
+)]
+pub(crate) async fn api_list_ancestors(
+    State(ctx): State<ApiContext>,
+    Path((collection_name, document_id)): Path<(String, String)>,
+    JwtClaims(user): JwtClaims<User>,
+) -> Result<Json<CollectionItemAncestors>, ApiErrors> {
+    let document_uuid = Uuid::parse_str(&document_id)
+        .map_err(|_| ApiErrors::BadRequestJsonSimpleMsg("Invalid uuid".to_string()))?;
+
+    let collection = get_collection_by_name(&ctx.db, &collection_name)
+        .await
+        .ok_or_else(|| ApiErrors::NotFound(collection_name.clone()))?;
+
+    if !is_collection_reader(&user, &collection_name) {
+        warn!("User {} is not a collection reader", user.name_and_sub());
+        return Err(ApiErrors::PermissionDenied);
+    }
+
+    let mut current = accessible_document(&ctx, &user, &collection, document_uuid)
+        .await?
+        .ok_or_else(|| ApiErrors::NotFound(format!("Document {document_id} not found")))?;
+
+    let mut config = CollectionConfig::from(&collection);
+    let mut walk = AncestorWalk::new(document_uuid);
+    let mut items = vec![];
+    while let Some(parent) = config.parent() {
+        let Some(parent_id) = parent.parent_id(&current.f) else {
+            break;
+        };
+        if !walk.visit(parent_id) {
+            warn!("Ancestors of document {document_uuid} contain a cycle or are too deep");
+            break;
+        }
+        let parent_collection_name = parent.collection().to_string();
+        let Some(parent_collection) = get_collection_by_name(&ctx.db, &parent_collection_name)
+            .await
+            .filter(|_| is_collection_reader(&user, &parent_collection_name))
+        else {
+            debug!("Parent collection {parent_collection_name} is not accessible");
+            break;
+        };
+        let Some(parent_document) =
+            accessible_document(&ctx, &user, &parent_collection, parent_id).await?
+        else {
+            debug!("Parent document {parent_id} is not accessible");
+            break;
+        };
+
+        config = CollectionConfig::from(&parent_collection);
+        let projection = ReadProjection::new(&ctx.hooks, &parent_collection, &user);
+        items.push(ancestor_item(
+            parent_collection_name,
+            &parent_document,
+            &projection,
+        ));
+        current = parent_document;
+    }
+
+    Ok(Json(CollectionItemAncestors::new(items)))
+}
+
+/// The item of an ancestor with the fields that the user can read.
+fn ancestor_item(
+    collection_name: String,
+    document: &entity::collection_document::Model,
+    projection: &ReadProjection,
+) -> CollectionItem {
+    let mut item = CollectionItem {
+        collection: Some(collection_name),
+        ..CollectionItem::new(document.id, document.f.clone())
+    };
+    projection.apply(item.id, &mut item.f);
+    item
+}
+
+pub(crate) fn is_collection_reader(user: &User, collection_name: &str) -> bool {
+    user.is_collection_admin(collection_name)
+        || user.can_access_all_documents(collection_name)
+        || user.is_collection_reader(collection_name)
+}
+
+/// Reads the document with the grants of the user in the collection.
+pub(crate) async fn accessible_document(
+    ctx: &ApiContext,
+    user: &User,
+    collection: &Collection,
+    document_uuid: Uuid,
+) -> Result<Option<entity::collection_document::Model>, ApiErrors> {
+    let dto_collection: GrantCollection = collection.into();
+    let user_grants =
+        hook_or_default_user_grants(&ctx.hooks, &dto_collection, user, ctx.data_service.clone())
+            .await?;
+    get_accessible_document(
+        ctx,
+        &user_grants,
+        user.subuuid(),
+        collection,
+        document_uuid,
+        false,
+    )
+    .await
+}
+
+/// Tracks the visited documents while walking up the parent chain.
+struct AncestorWalk {
+    visited: HashSet<Uuid>,
+}
+
+impl AncestorWalk {
+    fn new(document_uuid: Uuid) -> Self {
+        Self {
+            visited: HashSet::from([document_uuid]),
+        }
+    }
+
+    /// Returns `false` if the document has been visited before or the
+    /// maximum depth is reached.
+    fn visit(&mut self, document_uuid: Uuid) -> bool {
+        self.visited.len() <= MAX_ANCESTOR_DEPTH && self.visited.insert(document_uuid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_stops_at_cycles() {
+        // Arrange
+        let document = Uuid::new_v4();
+        let parent = Uuid::new_v4();
+        let mut walk = AncestorWalk::new(document);
+
+        // Act
+        let first = walk.visit(parent);
+        let cycle = walk.visit(document);
+
+        // Assert
+        assert!(first);
+        assert!(!cycle);
+    }
+
+    #[test]
+    fn it_stops_at_the_maximum_depth() {
+        // Arrange
+        let mut walk = AncestorWalk::new(Uuid::new_v4());
+
+        // Act
+        let visits = (0..MAX_ANCESTOR_DEPTH + 5)
+            .take_while(|_| walk.visit(Uuid::new_v4()))
+            .count();
+
+        // Assert
+        assert_eq!(visits, MAX_ANCESTOR_DEPTH);
+    }
+
+    #[test]
+    fn it_runs_the_read_hook_on_ancestors() {
+        // Arrange
+        let parent = entity::collection_document::Model {
+            id: Uuid::new_v4(),
+            collection_id: Uuid::new_v4(),
+            owner: Uuid::new_v4(),
+            f: serde_json::json!({"title": "Application", "salary": 52000}),
+            seq: 1,
+            locked: false,
+        };
+
+        // Act
+        let item = ancestor_item(
+            "applications".to_string(),
+            &parent,
+            &crate::api::read_projection::redacting(&["salary"]),
+        );
+
+        // Assert
+        assert_eq!(item.collection.as_deref(), Some("applications"));
+        assert_eq!(item.f, serde_json::json!({"title": "Application"}));
+    }
+}
@@ -0,0 +1,250 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use jwt_authorizer::JwtClaims;
+use serde::Deserialize;
+use tracing::warn;
+use validator::Validate;
+
+use crate::{
+    axumext::extractors::ValidatedQueryParams,
+    models::{CollectionChange, CollectionChangesList},
+};
+
+use super::{
+    auth::User,
+    db::{
+        get_unlocked_collection_by_name, list_document_changes, DbListDocumentParams,
+        DocumentChange, ListDocumentGrants,
+    },
+    dto,
+    grants::{hook_or_default_user_grants, GrantCollection},
+    read_projection::ReadProjection,
+    search_documents::{SearchFilter, SearchGroup},
+    types::{Pagination, DEFAULT_MAX_LIMIT},
+    ApiContext, ApiErrors,
+};
+
+#[derive(Debug, Deserialize, Validate, utoipa::IntoParams)]
+#[serde(default)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct ListChangesParams {
+    /// Return changes with a sequence number greater than this value
+    #[validate(range(min = 0))]
+    #[param(minimum = 0, example = 1042, default = 0)]
+    since: i64,
+
+    /// Maximum number of changes, up to 250
+    #[validate(range(min = 1, max = "DEFAULT_MAX_LIMIT"))]
+    #[param(minimum = 1, maximum = 250, example = 50, default = 50)]
+    limit: u16,
+}
+
+impl Default for ListChangesParams {
+    fn default() -> Self {
+        Self {
+            since: 0,
+            limit: 50,
+        }
+    }
+}
+
+/// List changes
+///
+/// Lists the documents of the collection that were created or changed after
+/// the sequence number `since`, ordered by their sequence number. Every write
+/// to a document assigns a new sequence number to it, so a document is listed
+/// only with its latest change. Deleted documents are listed without fields.
+///
+/// To synchronize a collection, start with `since=0` and pass `lastSeq` of the
+/// response as `since` of the next request.
+#[utoipa::path(
+    get,
+    path = "/collections/{collection_name}/changes",
+    operation_id = "listCollectionChanges",
+    params(
+        ListChangesParams,
+        (
+            "collection_name" = String,
+            Path,
+            description = "Name of the collection",
+            min_length = 1,
+            max_length = 32,
+            pattern = r"^[a-z][-a-z0-9]*$",
+        ),
+    ),
+    responses(
+        (status = OK, description = "List of changes", body = CollectionChangesList ),
+        (status = UNAUTHORIZED, description = "User is not a collection reader" ),
+        (status = NOT_FOUND, description = "Collection not found" ),
+        (status = BAD_REQUEST, description = "Invalid request" ),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
+    ),
+    tag = super::TAG_COLLECTION,
+)]
+pub(crate) async fn api_list_changes(
+    State(ctx): State<ApiContext>,
+    ValidatedQueryParams(params): ValidatedQueryParams<ListChangesParams>,
+    Path(collection_name): Path<String>,
+    JwtClaims(user): JwtClaims<User>,
+) -> Result<Json<CollectionChangesList>, ApiErrors> {
+    let collection = get_unlocked_collection_by_name(&ctx.db, &collection_name)
+        .await
+        .ok_or_else(|| ApiErrors::NotFound(collection_name.clone()))?;
+
+    let user_is_permitted = user.is_collection_admin(&collection_name)
+        || user.can_access_all_documents(&collection_name)
+        || user.is_collection_reader(&collection_name);
+    if !user_is_permitted {
+        warn!("User {} is not a collection reader", user.name_and_sub());
+        return Err(ApiErrors::PermissionDenied);
+    }
+
+    let dto_collection: GrantCollection = (&collection).into();
+    let user_grants =
+        hook_or_default_user_grants(&ctx.hooks, &dto_collection, &user, ctx.data_service.clone())
+            .await?;
+
+    let db_params = DbListDocumentParams::builder()
+        .collection(collection.id)
+        .grants(ListDocumentGrants::Restricted(user_grants))
+        .extra_fields(vec![])
+        .sort_fields(None)
+        .filters(SearchFilter::Group(SearchGroup::AndGroup(vec![])))
+        .include_author_id(false)
+        .pagination(Pagination::new(params.limit, 0))
+        .build();
+    let changes = list_document_changes(&ctx.db, &db_params, params.since).await?;
+
+    let projection = ReadProjection::new(&ctx.hooks, &collection, &user);
+    Ok(Json(changes_list(
+        params.since,
+        params.limit,
+        changes,
+        &projection,
+    )))
+}
+
+fn changes_list(
+    since: i64,
+    limit: u16,
+    changes: Vec<DocumentChange>,
+    projection: &ReadProjection,
+) -> CollectionChangesList {
+    let more = changes.len() >= usize::from(limit);
+    let last_seq = changes.last().map(|change| change.seq).unwrap_or(since);
+    let items = changes
+        .into_iter()
+        .map(|change| collection_change(change, projection))
+        .collect();
+    CollectionChangesList {
+        since,
+        last_seq,
+        more,
+        items,
+    }
+}
+
+/// Deleted documents are returned as tombstones without fields.
+fn collection_change(change: DocumentChange, projection: &ReadProjection) -> CollectionChange {
+    let document = dto::CollectionDocument::new(change.id, change.f);
+    if document.is_deleted() {
+        CollectionChange::new(change.seq, change.id, true)
+    } else {
+        let mut f = document.into_fields();
+        projection.apply(change.id, &mut f);
+        CollectionChange {
+            f: Some(f),
+            ..CollectionChange::new(change.seq, change.id, false)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::api::{collection_config::CollectionConfig, read_projection};
+
+    fn unchanged() -> ReadProjection {
+        ReadProjection::from_parts(CollectionConfig::default(), true, None)
+    }
+
+    fn change(seq: i64, f: serde_json::Value) -> DocumentChange {
+        DocumentChange {
+            id: Uuid::parse_str("a4a0ab26-3a6f-4e4a-9a43-34f6b8a9c1f0").unwrap(),
+            seq,
+            f,
+        }
+    }
+
+    #[test]
+    fn it_returns_deleted_documents_as_tombstones() {
+        // Arrange
+        let changes = vec![
+            change(7, json!({"title": "Circle"})),
+            change(
+                9,
+                json!({"title": "Square", "folivafy_deleted_at": "2024-03-01T10:00:00Z"}),
+            ),
+        ];
+
+        // Act
+        let list = changes_list(5, 50, changes, &unchanged());
+
+        // Assert
+        assert_eq!(
+            serde_json::to_value(list).unwrap(),
+            json!({
+                "since": 5,
+                "lastSeq": 9,
+                "more": false,
+                "items": [
+                    {"seq": 7, "id": "a4a0ab26-3a6f-4e4a-9a43-34f6b8a9c1f0", "deleted": false, "f": {"title": "Circle"}},
+                    {"seq": 9, "id": "a4a0ab26-3a6f-4e4a-9a43-34f6b8a9c1f0", "deleted": true}
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn it_keeps_the_position_without_changes() {
+        // Act
+        let list = changes_list(42, 50, vec![], &unchanged());
+
+        // Assert
+        assert_eq!(list.last_seq, 42);
+        assert!(!list.more);
+    }
+
+    #[test]
+    fn it_signals_more_changes_for_full_pages() {
+        // Act
+        let list = changes_list(
+            0,
+            2,
+            vec![change(1, json!({})), change(3, json!({}))],
+            &unchanged(),
+        );
+
+        // Assert
+        assert_eq!(list.last_seq, 3);
+        assert!(list.more);
+    }
+
+    #[test]
+    fn it_runs_the_read_hook_on_changed_documents() {
+        // Arrange
+        let changes = vec![change(4, json!({"title": "Application", "salary": 52000}))];
+
+        // Act
+        let list = changes_list(0, 50, changes, &read_projection::redacting(&["salary"]));
+
+        // Assert
+        assert_eq!(list.items[0].f, Some(json!({"title": "Application"})));
+    }
+}
@@ -2,8 +2,13 @@ use axum::{extract::State, Json};
 use axum_macros::debug_handler;
 use entity::collection::Entity as Collection;
 use jwt_authorizer::JwtClaims;
-use sea_orm::{EntityTrait, PaginatorTrait, QueryOrder, QuerySelect};
+use sea_orm::{
+    ColumnTrait, Condition, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect,
+    Select,
+};
+use serde::Deserialize;
 use tracing::warn;
+use validator::Validate;
 
 use crate::{
     api::{auth::User, types::Pagination, ApiContext, ApiErrors},
@@ -11,6 +16,22 @@ use crate::{
     models::{self, CollectionsList},
 };
 
+#[derive(Debug, Default, Deserialize, Validate, utoipa::IntoParams)]
+#[serde(default)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct ListCollectionsParams {
+    /// Only list collections with this locked state
+    pub(crate) locked: Option<bool>,
+
+    /// Only list collections with this owner access only (oao) state
+    pub(crate) oao: Option<bool>,
+
+    /// Only list collections where the caller can (`true`) or cannot (`false`) create documents
+    ///
+    /// A collection is writable if it is not locked and the caller has the editor role for it.
+    pub(crate) writable: Option<bool>,
+}
+
 /// List available collections
 ///
 /// List all available collections on this server
@@ -21,6 +42,7 @@ use crate::{
     operation_id = "getCollections",
     params(
         Pagination,
+        ListCollectionsParams,
     ),
     responses(
         (status = OK, description = "List of collections", body = CollectionsList ),
@@ -32,21 +54,22 @@ use crate::{
 pub(crate) async fn api_list_collections(
     State(ctx): State<ApiContext>,
     ValidatedQueryParams(pagination): ValidatedQueryParams<Pagination>,
+    ValidatedQueryParams(params): ValidatedQueryParams<ListCollectionsParams>,
     JwtClaims(user): JwtClaims<User>,
 ) -> Result<Json<CollectionsList>, ApiErrors> {
+    let pagination = pagination.limited_for(&user);
     if !user.is_collections_administrator() {
         warn!("User {} is not a collections admin", user.name_and_sub());
         return Err(ApiErrors::PermissionDenied);
     }
+    let condition = list_collections_condition(&params, &user.editable_collection_names());
     let total = Collection::find()
+        .filter(condition.clone())
         .count(&ctx.db)
         .await
         .map_err(ApiErrors::from)
         .map(|t| u32::try_from(t).unwrap_or_default())?;
-    let items = Collection::find()
-        .order_by_asc(entity::collection::Column::Name)
-        .limit(Some(pagination.limit().into()))
-        .offset(Some(pagination.offset().into()))
+    let items = list_collections_query(&pagination, condition)
         .all(&ctx.db)
         .await
         .map_err(ApiErrors::from)?;
@@ -65,3 +88,146 @@ pub(crate) async fn api_list_collections(
             .collect(),
     }))
 }
+
+fn list_collections_condition(
+    params: &ListCollectionsParams,
+    editable_collection_names: &[String],
+) -> Condition {
+    let mut condition = Condition::all();
+    if let Some(locked) = params.locked {
+        condition = condition.add(entity::collection::Column::Locked.eq(locked));
+    }
+    if let Some(oao) = params.oao {
+        condition = condition.add(entity::collection::Column::Oao.eq(oao));
+    }
+    if let Some(writable) = params.writable {
+        let writable_condition = Condition::all()
+            .add(entity::collection::Column::Locked.eq(false))
+            .add(entity::collection::Column::Name.is_in(editable_collection_names.to_vec()));
+        condition = condition.add(if writable {
+            writable_condition
+        } else {
+            writable_condition.not()
+        });
+    }
+    condition
+}
+
+fn list_collections_query(pagination: &Pagination, condition: Condition) -> Select<Collection> {
+    let query = if condition.is_empty() {
+        Collection::find()
+    } else {
+        Collection::find().filter(condition)
+    };
+    query
+        .order_by_asc(entity::collection::Column::Name)
+        .limit(Some(pagination.limit().into()))
+        .offset(Some(pagination.offset().into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::{DbBackend, QueryTrait};
+
+    use super::*;
+
+    #[test]
+    fn it_applies_pagination_to_collections_query() {
+        // Arrange
+        let pagination = Pagination::new(2, 4);
+
+        // Act
+        let sql = list_collections_query(&pagination, Condition::all())
+            .build(DbBackend::Postgres)
+            .to_string();
+
+        // Assert
+        assert_eq!(
+            sql,
+            r#"SELECT "collection"."id", "collection"."name", "collection"."title", "collection"."oao", "collection"."locked", "collection"."config" FROM "collection" ORDER BY "collection"."name" ASC LIMIT 2 OFFSET 4"#
+        );
+    }
+
+    fn collections_sql(params: &ListCollectionsParams, editable: &[&str]) -> String {
+        let editable: Vec<String> = editable.iter().map(|s| s.to_string()).collect();
+        list_collections_query(
+            &Pagination::default(),
+            list_collections_condition(params, &editable),
+        )
+        .build(DbBackend::Postgres)
+        .to_string()
+    }
+
+    #[test]
+    fn it_filters_collections_by_locked_flag() {
+        // Arrange
+        let params = ListCollectionsParams {
+            locked: Some(false),
+            ..Default::default()
+        };
+
+        // Act
+        let sql = collections_sql(&params, &[]);
+
+        // Assert
+        assert_eq!(
+            sql,
+            r#"SELECT "collection"."id", "collection"."name", "collection"."title", "collection"."oao", "collection"."locked", "collection"."config" FROM "collection" WHERE "collection"."locked" = FALSE ORDER BY "collection"."name" ASC LIMIT 50 OFFSET 0"#
+        );
+    }
+
+    #[test]
+    fn it_combines_collection_filters() {
+        // Arrange
+        let params = ListCollectionsParams {
+            locked: Some(false),
+            oao: Some(true),
+            ..Default::default()
+        };
+
+        // Act
+        let sql = collections_sql(&params, &[]);
+
+        // Assert
+        assert_eq!(
+            sql,
+            r#"SELECT "collection"."id", "collection"."name", "collection"."title", "collection"."oao", "collection"."locked", "collection"."config" FROM "collection" WHERE "collection"."locked" = FALSE AND "collection"."oao" = TRUE ORDER BY "collection"."name" ASC LIMIT 50 OFFSET 0"#
+        );
+    }
+
+    #[test]
+    fn it_filters_writable_collections_by_editor_roles() {
+        // Arrange
+        let params = ListCollectionsParams {
+            writable: Some(true),
+            ..Default::default()
+        };
+
+        // Act
+        let sql = collections_sql(&params, &["shapes", "letters"]);
+
+        // Assert
+        assert_eq!(
+            sql,
+            r#"SELECT "collection"."id", "collection"."name", "collection"."title", "collection"."oao", "collection"."locked", "collection"."config" FROM "collection" WHERE "collection"."locked" = FALSE AND "collection"."name" IN ('shapes', 'letters') ORDER BY "collection"."name" ASC LIMIT 50 OFFSET 0"#
+        );
+    }
+
+    #[test]
+    fn it_filters_non_writable_collections() {
+        // Arrange
+        let params = ListCollectionsParams {
+            writable: Some(false),
+            ..Default::default()
+        };
+
+        // Act
+        let sql = collections_sql(&params, &["shapes"]);
+
+        // Assert
+        assert_eq!(
+            sql,
+            r#"SELECT "collection"."id", "collection"."name", "collection"."title", "collection"."oao", "collection"."locked", "collection"."config" FROM "collection" WHERE NOT ("collection"."locked" = FALSE AND "collection"."name" IN ('shapes')) ORDER BY "collection"."name" ASC LIMIT 50 OFFSET 0"#
+        );
+    }
+}
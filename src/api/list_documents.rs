@@ -2,27 +2,35 @@ use std::str::FromStr;
 
 use axum::{
     extract::{Path, State},
+    response::Response,
     Json,
 };
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
 
 use entity::DELETED_AT_FIELD;
 use jwt_authorizer::JwtClaims;
 use lazy_static::lazy_static;
 use regex::Regex;
 use sea_orm::prelude::Uuid;
-use sea_orm::DatabaseConnection;
+use sea_orm::{DatabaseConnection, JsonValue};
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use tracing::warn;
 use typed_builder::TypedBuilder;
-use validator::Validate;
+use validator::{Validate, ValidationError};
 
 use crate::api::grants::{hook_or_default_user_grants, GrantCollection};
 use crate::models::{CollectionItem, CollectionItemsList};
 use crate::{
     api::{
         auth::User,
-        db::{list_documents, FieldFilter},
+        db::{
+            count_documents, first_document, list_distinct_values, list_documents,
+            sorts_by_author_id, CountMode, CursorPosition, DistinctValue, DocumentTotal,
+            FieldFilter, Keyset, ALL_FIELDS, AUTHOR_ID_SORT_FIELD, CURSOR_KEY_COLUMN,
+        },
+        dto,
         types::Pagination,
         ApiContext, ApiErrors,
     },
@@ -31,21 +39,44 @@ use crate::{
 
 use super::{
     db::{get_unlocked_collection_by_name, DbListDocumentParams, ListDocumentGrants},
+    envelope::{item_response, list_response, ApiVersion},
+    read_projection::ReadProjection,
     search_documents::{SearchFilter, SearchFilterFieldOp},
 };
 
 lazy_static! {
     pub(crate) static ref RE_EXTRA_FIELDS: Regex =
-        Regex::new(r"^[a-zA-Z0-9_]+(,[a-zA-Z0-9_]+)*$").unwrap();
+        Regex::new(r"^(\*|[a-zA-Z0-9_]+(,[a-zA-Z0-9_]+)*)$").unwrap();
     pub(crate) static ref RE_SORT_FIELDS: Regex = Regex::new(
-        r"^[a-zA-Z0-9_]+(\.[a-zA-Z0-9_]+)*[\+\-fb](,[a-zA-Z0-9_]+(\.[a-zA-Z0-9_]+)*[\+\-fb])*$"
+        r"^[a-zA-Z0-9_]+(\.[a-zA-Z0-9_]+)*[\+\-fb]!?(,[a-zA-Z0-9_]+(\.[a-zA-Z0-9_]+)*[\+\-fb]!?)*$"
     )
     .unwrap();
+    pub(crate) static ref RE_TITLE_FIELD: Regex =
+        Regex::new(r"^[a-zA-Z0-9_]+(\.[a-zA-Z0-9_]+)*$").unwrap();
 }
 
+/// Sort fields if the request has none
+const DEFAULT_SORT_FIELDS: &str = "created+";
+
+/// Field with the creation time of a document, used by `createdAfter` and
+/// `createdBefore`
+const CREATED_FIELD: &str = "created";
+
+/// Content of the `cursor` parameter
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Cursor {
+    /// Sort fields of the list, the cursor is only valid for the same order
+    sort: String,
+    #[serde(flatten)]
+    position: CursorPosition,
+}
+
+#[allow(clippy::enum_variant_names)]
 pub(crate) enum DeletedDocuments {
     LimitToDeletedDocuments,
     Exclude,
+    /// List deleted and live documents, the items carry their deletion state
+    Include,
 }
 
 #[derive(Debug, Default, Deserialize, Validate, utoipa::IntoParams)]
@@ -56,12 +87,24 @@ pub(crate) struct ListDocumentParams {
     #[serde(rename = "exactTitle")]
     pub(crate) exact_title: Option<String>,
 
+    /// Field that `exactTitle` is compared with, nested fields in dotted
+    /// notation, e. g. `subject` or `address.name`
+    #[validate(regex(path = *RE_TITLE_FIELD))]
+    #[serde(rename = "titleField")]
+    #[param(
+        default = "title",
+        example = "name",
+        pattern = r#"^[a-zA-Z0-9_]+(\.[a-zA-Z0-9_]+)*$"#
+    )]
+    pub(crate) title_field: Option<String>,
+
     /// A comma separated list of document fields that should be contained in the response
+    /// or `*` for all fields
     #[validate(regex(path= *RE_EXTRA_FIELDS))]
     #[serde(rename = "extraFields")]
     #[param(
         example = "price,length",
-        pattern = r#"^[a-zA-Z0-9_]+(,[a-zA-Z0-9_]+)*$"#
+        pattern = r#"^(\*|[a-zA-Z0-9_]+(,[a-zA-Z0-9_]+)*)$"#
     )]
     pub(crate) extra_fields: Option<String>,
 
@@ -73,13 +116,18 @@ pub(crate) struct ListDocumentParams {
     ///  - `f` for ascending order, the field is in dotted notation, e. g. `my_workflow.signature.date`
     ///  - `b` for descending order, the field is in dotted notation, e. g. `my_workflow.signature.date`
     ///
+    /// A `!` after the direction character puts documents without a value at the end (`NULLS LAST`), e. g. `price-!`.
+    /// Without `!`, these documents come last in ascending and first in descending order.
+    ///
+    /// `author_id+` and `author_id-` order by the author of the document. Documents without a creation event have no author.
+    ///
     /// Example: `price+,length-,my_workflow.signature.dateb` will order documents by price (ascending), length (descending) and signature date (descending).
     #[validate(regex(path= *RE_SORT_FIELDS))]
     #[serde(rename = "sort")]
     #[param(
         default = "created+",
         example = "email+,created-",
-        pattern = r#"^[a-zA-Z0-9_]+(\.[a-zA-Z0-9_]+)*[\+\-fb](,[a-zA-Z0-9_]+(\.[a-zA-Z0-9_]+)*[\+\-fb])*$"#
+        pattern = r#"^[a-zA-Z0-9_]+(\.[a-zA-Z0-9_]+)*[\+\-fb]!?(,[a-zA-Z0-9_]+(\.[a-zA-Z0-9_]+)*[\+\-fb]!?)*$"#
     )]
     pub(crate) sort_fields: Option<String>,
 
@@ -93,8 +141,169 @@ pub(crate) struct ListDocumentParams {
     ///  - `a='k'&f3=['p1','p4','p9']` matches documents where field `a` equals `"k"` and field `f3` is one of the values `"p1"`, `"p4"`, or `"p9"`
     ///  - `az=@'kl'` matches documents where field `az` starts with `"kl"`
     ///  - `pt=~'imi'` matches documents where field `pt` contains the substring `"imi"`
+    ///  - `author_id!='5c1e9b7a-2d3f-4a6b-8c0d-1e2f3a4b5c6d'` matches documents that are not owned by the given user
+    ///  - `f3=!['p1','p4']` matches documents where field `f3` is neither `"p1"` nor `"p4"`
+    ///  - `completed_at=*` matches documents that have the field `completed_at`, `completed_at=!*` documents without it
+    ///  - `status='open'|status='pending'&a='k'` matches documents where field `status` equals `"open"` or `"pending"` and field `a` equals `"k"`
     #[serde(rename = "pfilter")]
     pub(crate) pfilter: Option<String>,
+
+    /// Only list documents whose field `created` is after this RFC 3339
+    /// timestamp. The field must hold an RFC 3339 timestamp in UTC.
+    #[validate(custom(function = "validate_timestamp"))]
+    #[serde(rename = "createdAfter")]
+    #[param(example = "2024-02-01T00:00:00Z")]
+    pub(crate) created_after: Option<String>,
+
+    /// Only list documents whose field `created` is before this RFC 3339
+    /// timestamp. The field must hold an RFC 3339 timestamp in UTC.
+    #[validate(custom(function = "validate_timestamp"))]
+    #[serde(rename = "createdBefore")]
+    #[param(example = "2024-03-01T00:00:00Z")]
+    pub(crate) created_before: Option<String>,
+
+    /// Include deleted documents in the list. Each item then contains the fields
+    /// `deleted` and `deletedAt`.
+    ///
+    /// Only permitted for collection administrators.
+    #[serde(rename = "includeDeleted")]
+    #[param(example = true, default = false)]
+    pub(crate) include_deleted: Option<bool>,
+
+    /// Only list documents that the user can access but does not own
+    #[serde(rename = "sharedWithMe")]
+    #[param(example = true, default = false)]
+    pub(crate) shared_with_me: Option<bool>,
+
+    /// Count the matching documents with a time limit. If counting takes too
+    /// long, `total` is an estimate and `totalApproximate` is `true`.
+    #[serde(rename = "approximateCount")]
+    #[param(example = true, default = false)]
+    pub(crate) approximate_count: Option<bool>,
+
+    /// Count the matching documents. With `false`, the documents are not
+    /// counted and `hasMore` tells whether more documents follow the page,
+    /// which is faster for large collections. `total` is then only the number
+    /// of documents up to this page plus one if `hasMore` is `true`.
+    #[serde(rename = "withTotal")]
+    #[param(example = false, default = true)]
+    pub(crate) with_total: Option<bool>,
+
+    /// Include the parsed filter (`pfilter` and `exactTitle`) in the response
+    /// (`appliedFilter`), e. g. to check how a `pfilter` was interpreted.
+    ///
+    /// Only permitted for collection administrators.
+    #[serde(rename = "explainFilter")]
+    #[param(example = true, default = false)]
+    pub(crate) explain_filter: Option<bool>,
+
+    /// Page with a cursor instead of `offset`: an empty `cursor` requests the
+    /// first page, `nextCursor` of the response the following page. Deep
+    /// pages are as fast as the first one, but only a single sort field is
+    /// permitted. `offset` is ignored.
+    #[param(example = "")]
+    pub(crate) cursor: Option<String>,
+}
+
+impl ListDocumentParams {
+    /// Filter for `exactTitle` on the field `titleField`
+    pub(crate) fn exact_title_filter(&self) -> Option<FieldFilter> {
+        exact_title_filter(self.exact_title.as_deref(), self.title_field.as_deref())
+    }
+
+    /// Filters for `createdAfter` and `createdBefore` on the field `created`
+    pub(crate) fn created_filters(&self) -> Result<Vec<FieldFilter>, ApiErrors> {
+        created_filters(
+            self.created_after.as_deref(),
+            self.created_before.as_deref(),
+        )
+    }
+}
+
+/// Filter for the exact title on the title field, `title` by default
+pub(crate) fn exact_title_filter(
+    exact_title: Option<&str>,
+    title_field: Option<&str>,
+) -> Option<FieldFilter> {
+    exact_title.map(|title| FieldFilter::ExactFieldMatch {
+        field_name: title_field.unwrap_or("title").to_string(),
+        value: title.to_string(),
+    })
+}
+
+/// Filters for a window of the field `created`, invalid timestamps are
+/// ignored and must be rejected by `validate_timestamp`
+pub(crate) fn created_filters(
+    created_after: Option<&str>,
+    created_before: Option<&str>,
+) -> Result<Vec<FieldFilter>, ApiErrors> {
+    let after = created_after.and_then(parse_timestamp);
+    let before = created_before.and_then(parse_timestamp);
+    if let (Some(after), Some(before)) = (after, before) {
+        if after >= before {
+            return Err(ApiErrors::BadRequestJsonSimpleMsg(
+                "createdAfter must be before createdBefore".to_string(),
+            ));
+        }
+    }
+    Ok(after
+        .map(|value| FieldFilter::TimestampFieldGreaterThan {
+            field_name: CREATED_FIELD.to_string(),
+            value,
+        })
+        .into_iter()
+        .chain(before.map(|value| FieldFilter::TimestampFieldLessThan {
+            field_name: CREATED_FIELD.to_string(),
+            value,
+        }))
+        .collect())
+}
+
+pub(crate) fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+}
+
+pub(crate) fn validate_timestamp(value: &str) -> Result<(), ValidationError> {
+    match parse_timestamp(value) {
+        Some(_) => Ok(()),
+        None => Err(ValidationError::new("rfc3339")
+            .with_message("The value is not an RFC 3339 timestamp".into())),
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Validate, utoipa::IntoParams)]
+#[serde(default)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct FirstMatchParams {
+    /// Only return the first document in sort order instead of a list. The
+    /// response is the document or status 404 if no document matches.
+    /// `limit` and `offset` are ignored.
+    #[param(example = true, default = false)]
+    pub(crate) first: Option<bool>,
+}
+
+impl FirstMatchParams {
+    pub(crate) fn first(&self) -> bool {
+        self.first.unwrap_or_default()
+    }
+}
+
+/// Returns the list, or its only item if only the first match was requested.
+pub(crate) fn list_or_first_response(
+    list: CollectionItemsList,
+    first: bool,
+    version: ApiVersion,
+) -> Result<Response, ApiErrors> {
+    if !first {
+        return Ok(list_response(version, list));
+    }
+    list.items
+        .into_iter()
+        .next()
+        .map(|item| item_response(version, item))
+        .ok_or_else(|| ApiErrors::NotFound("No matching document".to_string()))
 }
 
 /// List collection items (documents).
@@ -107,6 +316,7 @@ pub(crate) struct ListDocumentParams {
     params(
         Pagination,
         ListDocumentParams,
+        FirstMatchParams,
         (
             "collection_name" = String,
             Path,
@@ -115,11 +325,17 @@ pub(crate) struct ListDocumentParams {
             max_length = 32,
             pattern = r"^[a-z][-a-z0-9]*$",
         ),
+        (
+            "Accept-Version" = Option<String>,
+            Header,
+            description = "Shape of the response: `v1` (default) or `v2` with the data in an envelope `{\"data\": ..., \"meta\": {...}}`",
+        ),
     ),
     responses(
-        (status = OK, description = "List of documents", body = CollectionItemsList ),
+        (status = OK, description = "List of documents, or a single `CollectionItem` with `first=true`", body = CollectionItemsList ),
         (status = UNAUTHORIZED, description = "User is not a collection reader" ),
-        (status = NOT_FOUND, description = "Collection not found" ),
+        (status = FORBIDDEN, description = "User is not permitted to list deleted documents" ),
+        (status = NOT_FOUND, description = "Collection not found or no document matches with `first=true`" ),
         (status = BAD_REQUEST, description = "Invalid request" ),
         (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
     ),
@@ -129,9 +345,12 @@ pub(crate) async fn api_list_documents(
     State(ctx): State<ApiContext>,
     ValidatedQueryParams(pagination): ValidatedQueryParams<Pagination>,
     ValidatedQueryParams(list_params): ValidatedQueryParams<ListDocumentParams>,
+    ValidatedQueryParams(first_params): ValidatedQueryParams<FirstMatchParams>,
+    version: ApiVersion,
     Path(collection_name): Path<String>,
     JwtClaims(user): JwtClaims<User>,
-) -> Result<Json<CollectionItemsList>, ApiErrors> {
+) -> Result<Response, ApiErrors> {
+    let pagination = pagination.limited_for(&user);
     let collection = get_unlocked_collection_by_name(&ctx.db, &collection_name)
         .await
         .ok_or_else(|| ApiErrors::NotFound(collection_name.clone()))?;
@@ -149,19 +368,23 @@ pub(crate) async fn api_list_documents(
         hook_or_default_user_grants(&ctx.hooks, &dto_collection, &user, ctx.data_service.clone())
             .await?;
 
+    let explain_filter =
+        explain_filter_permitted(list_params.explain_filter, &user, &collection_name)?;
+
+    let deleted_documents =
+        deleted_documents_permitted(list_params.include_deleted, &user, &collection_name)?;
+
     let grants = ListDocumentGrants::Restricted(user_grants);
-    let mut request_filters = parse_pfilter(list_params.pfilter);
-    if let Some(title) = list_params.exact_title {
-        request_filters.push(FieldFilter::ExactFieldMatch {
-            field_name: "title".to_string(),
-            value: title,
-        });
-    }
+    let title_filter = list_params.exact_title_filter();
+    let created_filters = list_params.created_filters()?;
+    let mut request_filters = parse_pfilter(list_params.pfilter)?;
+    request_filters.extend(title_filter);
+    request_filters.extend(created_filters);
 
-    generic_list_documents(
+    let list = generic_list_documents(
         &ctx.db,
         collection.id,
-        DeletedDocuments::Exclude,
+        deleted_documents,
         GenericListDocumentsParams::builder()
             .sort_fields(list_params.sort_fields.clone())
             .extra_fields(list_params.extra_fields.clone())
@@ -170,17 +393,158 @@ pub(crate) async fn api_list_documents(
             } else {
                 Some(request_filters.into())
             })
+            .projection(ReadProjection::new(&ctx.hooks, &collection, &user))
+            .count(count_mode(
+                list_params.approximate_count,
+                list_params.with_total,
+            ))
+            .exclude_owner(
+                list_params
+                    .shared_with_me
+                    .unwrap_or_default()
+                    .then(|| user.subuuid()),
+            )
+            .first(first_params.first())
+            .explain_filter(explain_filter)
+            .cursor(list_params.cursor.clone())
             .build(),
         grants,
         pagination,
     )
-    .await
+    .await?;
+
+    list_or_first_response(list.0, first_params.first(), version)
 }
 
-pub(crate) fn parse_pfilter(s: Option<String>) -> Vec<FieldFilter> {
-    // Split s by ampersand
-    s.map(|s| s.split('&').filter_map(FieldFilter::from_str).collect())
-        .unwrap_or_default()
+/// Returns whether the parsed filter is requested in the response. Only
+/// collection administrators may request it.
+pub(crate) fn explain_filter_permitted(
+    explain_filter: Option<bool>,
+    user: &User,
+    collection_name: &str,
+) -> Result<bool, ApiErrors> {
+    let explain_filter = explain_filter.unwrap_or_default();
+    if explain_filter && !user.is_collection_admin(collection_name) {
+        warn!(
+            "User {} is not permitted to explain filters",
+            user.name_and_sub()
+        );
+        return Err(ApiErrors::PermissionDenied);
+    }
+    Ok(explain_filter)
+}
+
+/// Returns which deleted documents are listed. Only collection administrators
+/// may include deleted documents, other users are rejected with 403.
+pub(crate) fn deleted_documents_permitted(
+    include_deleted: Option<bool>,
+    user: &User,
+    collection_name: &str,
+) -> Result<DeletedDocuments, ApiErrors> {
+    if !include_deleted.unwrap_or_default() {
+        return Ok(DeletedDocuments::Exclude);
+    }
+    if !user.is_collection_admin(collection_name) {
+        warn!(
+            "User {} is not permitted to list deleted documents",
+            user.name_and_sub()
+        );
+        return Err(ApiErrors::Forbidden(
+            "Only administrators can list deleted documents".into(),
+        ));
+    }
+    Ok(DeletedDocuments::Include)
+}
+
+/// The filter of the request as JSON, in the notation of the search endpoint.
+pub(crate) fn applied_filter(filter: Option<&SearchFilter>) -> JsonValue {
+    serde_json::to_value(filter).unwrap_or_default()
+}
+
+pub(crate) fn count_mode(approximate_count: Option<bool>, with_total: Option<bool>) -> CountMode {
+    if !with_total.unwrap_or(true) {
+        CountMode::Skip
+    } else if approximate_count.unwrap_or_default() {
+        CountMode::Approximate
+    } else {
+        CountMode::Exact
+    }
+}
+
+pub(crate) fn parse_pfilter(s: Option<String>) -> Result<Vec<FieldFilter>, ApiErrors> {
+    let Some(s) = s else {
+        return Ok(vec![]);
+    };
+    // Split s by ampersand, the conditions are joined with AND
+    let mut filters = vec![];
+    for term in split_pfilter(&s, '&')? {
+        filters.extend(parse_pfilter_alternatives(term)?);
+    }
+    Ok(filters)
+}
+
+/// Parses the conditions of a `pfilter` term that are separated by a pipe
+/// (`|`) and joined with OR. Every alternative must be a valid condition.
+///
+/// A term without a pipe keeps the behavior from before OR groups existed: a
+/// malformed condition is ignored, e. g. `bogus` lists every document.
+/// Rejecting it would break clients that send such filters today.
+fn parse_pfilter_alternatives(s: &str) -> Result<Option<FieldFilter>, ApiErrors> {
+    let terms = split_pfilter(s, '|')?;
+    if terms.len() == 1 {
+        return Ok(FieldFilter::from_str(s));
+    }
+    let mut alternatives = terms
+        .into_iter()
+        .filter(|term| !term.is_empty())
+        .map(|term| {
+            FieldFilter::from_str(term).ok_or_else(|| {
+                ApiErrors::BadRequestJsonSimpleMsg(format!("Invalid pfilter condition {term}"))
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(match alternatives.len() {
+        0 => None,
+        1 => alternatives.pop(),
+        _ => Some(FieldFilter::AnyOf(alternatives)),
+    })
+}
+
+/// Splits the `pfilter` at the separator outside of quoted values and value
+/// lists, e. g. `title='a|b'|f3=['p1','p4']`. A quote starts a value after
+/// `=`, `~`, `@`, `!`, `[` or `,`.
+fn split_pfilter(s: &str, separator: char) -> Result<Vec<&str>, ApiErrors> {
+    let mut terms = vec![];
+    let mut start = 0;
+    let mut quote: Option<char> = None;
+    let mut in_list = false;
+    let mut previous: Option<char> = None;
+    for (index, c) in s.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"')
+                if previous.is_none_or(|p| matches!(p, '=' | '~' | '@' | '!' | '[' | ',')) =>
+            {
+                quote = Some(c)
+            }
+            (None, '[') => in_list = true,
+            (None, ']') => in_list = false,
+            (None, c) if c == separator && !in_list => {
+                terms.push(&s[start..index]);
+                start = index + c.len_utf8();
+            }
+            _ => {}
+        }
+        previous = Some(c);
+    }
+    if quote.is_some() || in_list {
+        return Err(ApiErrors::BadRequestJsonSimpleMsg(format!(
+            "Unclosed quote or list in pfilter {s}"
+        )));
+    }
+    terms.push(&s[start..]);
+    Ok(terms)
 }
 
 #[derive(Debug, TypedBuilder)]
@@ -188,17 +552,44 @@ pub(crate) struct GenericListDocumentsParams {
     extra_fields: Option<String>,
     sort_fields: Option<String>,
     filter: Option<SearchFilter>,
+    /// Projection of the listed fields for the reading user
+    projection: ReadProjection,
+    /// Skip the documents of this owner
+    #[builder(default)]
+    exclude_owner: Option<Uuid>,
+    /// Only select the first document, the total is not counted
+    #[builder(default)]
+    first: bool,
+    /// Return the filter in the response
+    #[builder(default)]
+    explain_filter: bool,
+    #[builder(default)]
+    count: CountMode,
+    /// Cursor of the page, see `ListDocumentParams::cursor`
+    #[builder(default)]
+    cursor: Option<String>,
 }
 
-pub(crate) async fn generic_list_documents(
-    db: &DatabaseConnection,
-    collection_id: Uuid,
-    deleted_documents: DeletedDocuments,
-    list_params: GenericListDocumentsParams,
-    grants: ListDocumentGrants,
-    pagination: Pagination,
-) -> Result<Json<CollectionItemsList>, ApiErrors> {
-    let extra_fields = list_params.extra_fields.unwrap_or("title".to_string());
+/// Rejects `author_id+` and `author_id-` for queries without the creation
+/// events of the documents.
+pub(crate) fn reject_author_id_sort(sort_fields: Option<&str>) -> Result<(), ApiErrors> {
+    if sorts_by_author_id(sort_fields) {
+        return Err(ApiErrors::BadRequestJsonSimpleMsg(format!(
+            "Sort field {AUTHOR_ID_SORT_FIELD} is not supported"
+        )));
+    }
+    Ok(())
+}
+
+/// Returns the document fields that are contained in the list response and
+/// whether the author id is requested.
+///
+/// The `title` field is always contained, `*` selects all fields.
+pub(crate) fn projected_fields(extra_fields: Option<String>) -> (Vec<String>, bool) {
+    let extra_fields = extra_fields.unwrap_or("title".to_string());
+    if extra_fields == ALL_FIELDS {
+        return (vec![ALL_FIELDS.to_string()], false);
+    }
     let mut extra_fields: Vec<String> = extra_fields.split(',').map(|s| s.to_string()).collect();
     let extra_field_author = "author_id".to_string();
 
@@ -212,24 +603,56 @@ pub(crate) async fn generic_list_documents(
         extra_fields.push(title);
     }
 
-    let deleted_documents_condition = SearchFilter::FieldOp(
-        SearchFilterFieldOp::builder()
-            .field(DELETED_AT_FIELD.to_string())
-            .operation(match deleted_documents {
-                DeletedDocuments::LimitToDeletedDocuments => {
-                    super::search_documents::Operation::NotNull
-                }
-                DeletedDocuments::Exclude => super::search_documents::Operation::Null,
-            })
-            .build(),
-    );
-
-    let filters = match list_params.filter {
-        Some(filters) => SearchFilter::Group(super::search_documents::SearchGroup::AndGroup(vec![
-            deleted_documents_condition,
-            filters,
-        ])),
-        None => deleted_documents_condition,
+    (extra_fields, include_author)
+}
+
+pub(crate) async fn generic_list_documents(
+    db: &DatabaseConnection,
+    collection_id: Uuid,
+    deleted_documents: DeletedDocuments,
+    list_params: GenericListDocumentsParams,
+    grants: ListDocumentGrants,
+    pagination: Pagination,
+) -> Result<Json<CollectionItemsList>, ApiErrors> {
+    let pagination = pagination.clamped(list_params.projection.config().max_response_items());
+    let (mut extra_fields, include_author) = projected_fields(list_params.extra_fields);
+
+    let include_deleted_state = matches!(deleted_documents, DeletedDocuments::Include);
+    let requested_deleted_at = extra_fields
+        .iter()
+        .any(|f| f == DELETED_AT_FIELD || f == ALL_FIELDS);
+    if include_deleted_state && !requested_deleted_at {
+        extra_fields.push(DELETED_AT_FIELD.to_string());
+    }
+
+    if let Some(filter) = list_params.filter.as_ref() {
+        list_params
+            .projection
+            .config()
+            .validate_text_search(filter)?;
+    }
+    list_params
+        .projection
+        .config()
+        .validate_sort(list_params.sort_fields.as_deref())?;
+    list_params.projection.validate_query(
+        list_params.filter.as_ref(),
+        list_params.sort_fields.as_deref(),
+    )?;
+
+    let applied_filter = list_params
+        .explain_filter
+        .then(|| applied_filter(list_params.filter.as_ref()));
+
+    let filters = documents_filter(deleted_documents, list_params.filter);
+
+    let sort = list_params
+        .sort_fields
+        .clone()
+        .unwrap_or_else(|| DEFAULT_SORT_FIELDS.to_string());
+    let keyset = match list_params.cursor.as_deref() {
+        Some(cursor) if !list_params.first => Some(keyset_from_cursor(cursor, &sort)?),
+        _ => None,
     };
 
     let db_params = DbListDocumentParams::builder()
@@ -240,42 +663,242 @@ pub(crate) async fn generic_list_documents(
         .filters(filters)
         .pagination(pagination.clone())
         .include_author_id(include_author)
+        .exclude_owner(list_params.exclude_owner)
+        .count(list_params.count)
+        .keyset(keyset)
         .build();
 
-    let (total, items) = list_documents(db, &db_params)
-        .await
-        .map_err(ApiErrors::from)?;
+    let (total, items) = if list_params.first {
+        let items: Vec<JsonValue> = first_document(db, &db_params).await?.into_iter().collect();
+        let total = DocumentTotal {
+            count: items.len() as u32,
+            approximate: false,
+            has_more: None,
+        };
+        (total, items)
+    } else {
+        list_documents(db, &db_params).await?
+    };
+    let next_cursor = items
+        .last()
+        .filter(|_| {
+            db_params.keyset.is_some()
+                && total
+                    .has_more
+                    .unwrap_or(items.len() >= usize::from(pagination.limit()))
+        })
+        .and_then(|last| next_cursor(&sort, last));
 
     let items = items
         .into_iter()
         .map(|i| {
-            let mut f = i["f"].clone();
-            if include_author {
-                f["author_id"] = i["author_id"].clone();
-            }
-            CollectionItem {
-                id: Uuid::from_str(i["id"].as_str().unwrap()).unwrap(),
-                f,
-            }
+            listed_item(
+                &i,
+                include_author,
+                include_deleted_state.then_some(!requested_deleted_at),
+                &list_params.projection,
+            )
         })
         .collect();
 
     Ok(Json(CollectionItemsList {
         limit: pagination.limit(),
         offset: pagination.offset(),
-        total,
+        total: total.count,
         items,
+        total_approximate: (list_params.count == CountMode::Approximate)
+            .then_some(total.approximate),
+        applied_filter,
+        next_cursor,
+        has_more: total.has_more,
     }))
 }
 
+/// Reads the position of the `cursor` parameter, an empty cursor starts at
+/// the first page.
+fn keyset_from_cursor(cursor: &str, sort: &str) -> Result<Keyset, ApiErrors> {
+    if sort.contains(',') {
+        return Err(ApiErrors::BadRequestJsonSimpleMsg(
+            "Cursor pagination requires a single sort field".to_string(),
+        ));
+    }
+    if cursor.is_empty() {
+        return Ok(Keyset::Start);
+    }
+    let cursor: Cursor = URL_SAFE_NO_PAD
+        .decode(cursor)
+        .ok()
+        .and_then(|json| serde_json::from_slice(&json).ok())
+        .ok_or_else(|| ApiErrors::BadRequestJsonSimpleMsg("Invalid cursor".to_string()))?;
+    if cursor.sort != sort {
+        return Err(ApiErrors::BadRequestJsonSimpleMsg(
+            "Cursor does not match the sort fields".to_string(),
+        ));
+    }
+    Ok(Keyset::After(cursor.position))
+}
+
+/// Returns the cursor of the page after the document row.
+fn next_cursor(sort: &str, row: &JsonValue) -> Option<String> {
+    let cursor = Cursor {
+        sort: sort.to_string(),
+        position: CursorPosition {
+            key: row[CURSOR_KEY_COLUMN].clone(),
+            id: Uuid::from_str(row["id"].as_str()?).ok()?,
+        },
+    };
+    serde_json::to_vec(&cursor)
+        .ok()
+        .map(|json| URL_SAFE_NO_PAD.encode(json))
+}
+
+/// Counts the documents like `generic_list_documents` without selecting them.
+pub(crate) async fn generic_count_documents(
+    db: &DatabaseConnection,
+    collection_id: Uuid,
+    deleted_documents: DeletedDocuments,
+    filter: Option<SearchFilter>,
+    projection: &ReadProjection,
+    grants: ListDocumentGrants,
+    count: CountMode,
+) -> Result<DocumentTotal, ApiErrors> {
+    if let Some(filter) = filter.as_ref() {
+        projection.config().validate_text_search(filter)?;
+    }
+    projection.validate_query(filter.as_ref(), None)?;
+
+    let db_params = DbListDocumentParams::builder()
+        .collection(collection_id)
+        .grants(grants)
+        .extra_fields(vec![])
+        .sort_fields(None)
+        .filters(documents_filter(deleted_documents, filter))
+        .include_author_id(false)
+        .count(count)
+        .build();
+
+    count_documents(db, &db_params).await
+}
+
+/// Lists the distinct values of a field like `generic_count_documents` counts
+/// the documents.
+pub(crate) async fn generic_distinct_values(
+    db: &DatabaseConnection,
+    collection_id: Uuid,
+    field_name: &str,
+    filter: Option<SearchFilter>,
+    projection: &ReadProjection,
+    grants: ListDocumentGrants,
+    limit: u32,
+) -> Result<(Vec<DistinctValue>, bool), ApiErrors> {
+    if let Some(filter) = filter.as_ref() {
+        projection.config().validate_text_search(filter)?;
+    }
+    projection.validate_query(filter.as_ref(), None)?;
+
+    let db_params = DbListDocumentParams::builder()
+        .collection(collection_id)
+        .grants(grants)
+        .extra_fields(vec![])
+        .sort_fields(None)
+        .filters(documents_filter(DeletedDocuments::Exclude, filter))
+        .include_author_id(false)
+        .build();
+
+    list_distinct_values(db, &db_params, field_name, limit.into()).await
+}
+
+/// Combines the filter of the request with the condition on deleted documents.
+pub(crate) fn documents_filter(
+    deleted_documents: DeletedDocuments,
+    filter: Option<SearchFilter>,
+) -> SearchFilter {
+    let deleted_documents_condition = match deleted_documents {
+        DeletedDocuments::LimitToDeletedDocuments => {
+            Some(super::search_documents::Operation::NotNull)
+        }
+        DeletedDocuments::Exclude => Some(super::search_documents::Operation::Null),
+        DeletedDocuments::Include => None,
+    }
+    .map(|operation| {
+        SearchFilter::FieldOp(
+            SearchFilterFieldOp::builder()
+                .field(DELETED_AT_FIELD.to_string())
+                .operation(operation)
+                .build(),
+        )
+    });
+
+    match (deleted_documents_condition, filter) {
+        (Some(condition), Some(filters)) => {
+            SearchFilter::Group(super::search_documents::SearchGroup::AndGroup(vec![
+                condition, filters,
+            ]))
+        }
+        (Some(condition), None) => condition,
+        (None, Some(filters)) => filters,
+        (None, None) => SearchFilter::Group(super::search_documents::SearchGroup::AndGroup(vec![])),
+    }
+}
+
+pub(crate) fn collection_item(i: &JsonValue, include_author: bool) -> CollectionItem {
+    let mut f = i["f"].clone();
+    if include_author {
+        f["author_id"] = i["author_id"].clone();
+    }
+    CollectionItem::new(Uuid::from_str(i["id"].as_str().unwrap()).unwrap(), f)
+}
+
+/// The item of a listed document with the fields that the user can read.
+/// With `remove_deleted_marker` the item carries its deletion state.
+fn listed_item(
+    i: &JsonValue,
+    include_author: bool,
+    remove_deleted_marker: Option<bool>,
+    projection: &ReadProjection,
+) -> CollectionItem {
+    let mut item = collection_item(i, include_author);
+    projection.apply(item.id, &mut item.f);
+    if let Some(remove_marker) = remove_deleted_marker {
+        set_deleted_state(&mut item, remove_marker);
+    }
+    item
+}
+
+/// Adds the deletion state of the document to the item. The deletion marker is
+/// removed from the fields if it was not requested.
+fn set_deleted_state(item: &mut CollectionItem, remove_marker: bool) {
+    let document = dto::CollectionDocument::new(item.id, item.f.clone());
+    let deleted = document.is_deleted();
+    item.deleted = Some(deleted);
+    item.deleted_at = if deleted {
+        item.f
+            .get(DELETED_AT_FIELD)
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+    } else {
+        None
+    };
+    if remove_marker {
+        let _ = item
+            .f
+            .as_object_mut()
+            .and_then(|obj| obj.remove(DELETED_AT_FIELD));
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     pub fn test_empty_pfilter() {
-        assert_eq!(parse_pfilter(Some("".to_string())).len(), 0, "Empty string");
-        assert_eq!(parse_pfilter(None).len(), 0, "None value");
+        assert_eq!(
+            parse_pfilter(Some("".to_string())).unwrap().len(),
+            0,
+            "Empty string"
+        );
+        assert_eq!(parse_pfilter(None).unwrap().len(), 0, "None value");
     }
 
     #[test]
@@ -284,7 +907,7 @@ mod tests {
         let s = "f1='v12'";
 
         // Act
-        let r = parse_pfilter(Some(s.to_string()));
+        let r = parse_pfilter(Some(s.to_string())).unwrap();
 
         // Assert
         assert_eq!(r.len(), 1);
@@ -303,7 +926,7 @@ mod tests {
         let s = "a='k'&f1=4";
 
         // Act
-        let r = parse_pfilter(Some(s.to_string()));
+        let r = parse_pfilter(Some(s.to_string())).unwrap();
 
         // Assert
         assert_eq!(r.len(), 2);
@@ -333,7 +956,7 @@ mod tests {
             .collect::<Vec<_>>();
 
         // Act
-        let r = parse_pfilter(Some(s.to_string()));
+        let r = parse_pfilter(Some(s.to_string())).unwrap();
 
         // Assert
         assert_eq!(r.len(), 2);
@@ -359,7 +982,7 @@ mod tests {
         let s = "az=@'kl'";
 
         // Act
-        let r = parse_pfilter(Some(s.to_string()));
+        let r = parse_pfilter(Some(s.to_string())).unwrap();
 
         // Assert
         assert_eq!(r.len(), 1);
@@ -378,7 +1001,7 @@ mod tests {
         let s = "pt=~'imi'";
 
         // Act
-        let r = parse_pfilter(Some(s.to_string()));
+        let r = parse_pfilter(Some(s.to_string())).unwrap();
 
         // Assert
         assert_eq!(r.len(), 1);
@@ -390,4 +1013,457 @@ mod tests {
             _ => panic!("Unexpected value"),
         }
     }
+
+    #[test]
+    pub fn test_negations() {
+        // Arrange
+        let s = "author_id!='5c1e9b7a-2d3f-4a6b-8c0d-1e2f3a4b5c6d'&f3=!['p1','p4']&f4=!'p1'";
+
+        // Act
+        let r = parse_pfilter(Some(s.to_string())).unwrap();
+
+        // Assert
+        assert_eq!(r.len(), 2);
+        match r.first().unwrap() {
+            FieldFilter::FieldNotEqual { field_name, value } => {
+                assert_eq!(field_name, "author_id");
+                assert_eq!(value, "5c1e9b7a-2d3f-4a6b-8c0d-1e2f3a4b5c6d");
+            }
+            _ => panic!("Unexpected value"),
+        }
+        match r.get(1).unwrap() {
+            FieldFilter::FieldValueNotInMatch { field_name, values } => {
+                assert_eq!(field_name, "f3");
+                assert_eq!(values, &["p1".to_string(), "p4".to_string()]);
+            }
+            _ => panic!("Unexpected value"),
+        }
+    }
+
+    #[test]
+    pub fn test_exists() {
+        // Arrange
+        let s = "completed_at=*&cancelled_at=!*&title='*'";
+
+        // Act
+        let r = parse_pfilter(Some(s.to_string())).unwrap();
+
+        // Assert
+        assert_eq!(r.len(), 3);
+        match r.first().unwrap() {
+            FieldFilter::FieldIsNotNull { field_name } => assert_eq!(field_name, "completed_at"),
+            _ => panic!("Unexpected value"),
+        }
+        match r.get(1).unwrap() {
+            FieldFilter::FieldIsNull { field_name } => assert_eq!(field_name, "cancelled_at"),
+            _ => panic!("Unexpected value"),
+        }
+        match r.get(2).unwrap() {
+            FieldFilter::ExactFieldMatch { field_name, value } => {
+                assert_eq!(field_name, "title");
+                assert_eq!(value, "*");
+            }
+            _ => panic!("Unexpected value"),
+        }
+    }
+
+    #[test]
+    fn it_marks_deleted_and_live_documents() {
+        // Arrange
+        let rows = [
+            serde_json::json!({
+                "id": "3a8ed6a6-9cd7-4a4e-a0b5-42bfbd8e0c01",
+                "f": {"title": "Circle", "folivafy_deleted_at": "2023-11-14T22:13:20Z"}
+            }),
+            serde_json::json!({
+                "id": "6b5a3f0e-8a0c-4b43-9b0a-f5e1f1cbd502",
+                "f": {"title": "Square", "folivafy_deleted_at": null}
+            }),
+        ];
+
+        // Act
+        let items: Vec<serde_json::Value> = rows
+            .iter()
+            .map(|row| {
+                let mut item = collection_item(row, false);
+                set_deleted_state(&mut item, true);
+                serde_json::to_value(item).unwrap()
+            })
+            .collect();
+
+        // Assert
+        assert_eq!(
+            items,
+            vec![
+                serde_json::json!({
+                    "id": "3a8ed6a6-9cd7-4a4e-a0b5-42bfbd8e0c01",
+                    "f": {"title": "Circle"},
+                    "deleted": true,
+                    "deletedAt": "2023-11-14T22:13:20Z"
+                }),
+                serde_json::json!({
+                    "id": "6b5a3f0e-8a0c-4b43-9b0a-f5e1f1cbd502",
+                    "f": {"title": "Square"},
+                    "deleted": false
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_keeps_a_requested_deletion_marker() {
+        // Arrange
+        let row = serde_json::json!({
+            "id": "3a8ed6a6-9cd7-4a4e-a0b5-42bfbd8e0c01",
+            "f": {"title": "Circle", "folivafy_deleted_at": "2023-11-14T22:13:20Z"}
+        });
+        let mut item = collection_item(&row, false);
+
+        // Act
+        set_deleted_state(&mut item, false);
+
+        // Assert
+        assert_eq!(item.deleted, Some(true));
+        assert_eq!(item.f["folivafy_deleted_at"], "2023-11-14T22:13:20Z");
+    }
+
+    #[test]
+    fn it_matches_the_exact_title_on_the_title_field() {
+        // Arrange
+        let default_field = ListDocumentParams {
+            exact_title: Some("Square".to_string()),
+            ..Default::default()
+        };
+        let name_field = ListDocumentParams {
+            exact_title: Some("Square".to_string()),
+            title_field: Some("name".to_string()),
+            ..Default::default()
+        };
+        let invalid_field = ListDocumentParams {
+            exact_title: Some("Square".to_string()),
+            title_field: Some("name'--".to_string()),
+            ..Default::default()
+        };
+
+        // Act
+        let default_filter = default_field.exact_title_filter();
+        let name_filter = name_field.exact_title_filter();
+
+        // Assert
+        assert!(matches!(
+            default_filter,
+            Some(FieldFilter::ExactFieldMatch { field_name, value }) if field_name == "title" && value == "Square"
+        ));
+        assert!(matches!(
+            name_filter,
+            Some(FieldFilter::ExactFieldMatch { field_name, value }) if field_name == "name" && value == "Square"
+        ));
+        assert!(name_field.validate().is_ok());
+        assert!(invalid_field.validate().is_err());
+    }
+
+    #[test]
+    fn it_filters_the_created_field_by_a_window() {
+        // Arrange
+        let params = ListDocumentParams {
+            created_after: Some("2024-02-01T01:00:00+01:00".to_string()),
+            created_before: Some("2024-03-01T00:00:00Z".to_string()),
+            ..Default::default()
+        };
+
+        // Act
+        let filter: SearchFilter = params.created_filters().unwrap().into();
+
+        // Assert
+        assert!(params.validate().is_ok());
+        assert_eq!(
+            serde_json::to_value(filter).unwrap(),
+            serde_json::json!({"and": [
+                {"f": "created", "o": "gt", "v": "2024-02-01T00:00:00Z"},
+                {"f": "created", "o": "lt", "v": "2024-03-01T00:00:00Z"}
+            ]})
+        );
+    }
+
+    #[test]
+    fn it_rejects_invalid_created_timestamps() {
+        // Arrange
+        let invalid = ListDocumentParams {
+            created_after: Some("2024-02-01".to_string()),
+            ..Default::default()
+        };
+        let reversed = ListDocumentParams {
+            created_after: Some("2024-03-01T00:00:00Z".to_string()),
+            created_before: Some("2024-02-01T00:00:00Z".to_string()),
+            ..Default::default()
+        };
+
+        // Act
+        let invalid_result = invalid.validate();
+        let reversed_result = reversed.created_filters();
+
+        // Assert
+        assert!(invalid_result.is_err());
+        assert!(matches!(
+            reversed_result,
+            Err(ApiErrors::BadRequestJsonSimpleMsg(message)) if message == "createdAfter must be before createdBefore"
+        ));
+    }
+
+    #[test]
+    fn it_explains_the_parsed_pfilter() {
+        // Arrange
+        let s = "state='open'&f3=['p1','p4']&az=@'kl'&pt=~'imi'";
+        let filter: SearchFilter = parse_pfilter(Some(s.to_string())).unwrap().into();
+
+        // Act
+        let explained = applied_filter(Some(&filter));
+
+        // Assert
+        assert_eq!(
+            explained,
+            serde_json::json!({"and": [
+                {"f": "state", "o": "eq", "v": "open"},
+                {"f": "f3", "o": "in", "v": ["p1", "p4"]},
+                {"f": "az", "o": "startswith", "v": "kl"},
+                {"f": "pt", "o": "containstext", "v": "imi"}
+            ]})
+        );
+    }
+
+    #[test]
+    fn it_parses_alternatives_into_an_or_group() {
+        // Arrange
+        let s = "status='open'|status='pending'";
+        let filter: SearchFilter = parse_pfilter(Some(s.to_string())).unwrap().into();
+
+        // Act
+        let explained = applied_filter(Some(&filter));
+
+        // Assert
+        assert_eq!(
+            explained,
+            serde_json::json!({"and": [
+                {"or": [
+                    {"f": "status", "o": "eq", "v": "open"},
+                    {"f": "status", "o": "eq", "v": "pending"}
+                ]}
+            ]})
+        );
+    }
+
+    #[test]
+    fn it_keeps_a_quoted_pipe_in_the_value() {
+        // Arrange
+        let s = "title='a|b'|tags=['x|y','z']&kind=\"c&d\"";
+        let filter: SearchFilter = parse_pfilter(Some(s.to_string())).unwrap().into();
+
+        // Act
+        let explained = applied_filter(Some(&filter));
+
+        // Assert
+        assert_eq!(
+            explained,
+            serde_json::json!({"and": [
+                {"or": [
+                    {"f": "title", "o": "eq", "v": "a|b"},
+                    {"f": "tags", "o": "in", "v": ["x|y", "z"]}
+                ]},
+                {"f": "kind", "o": "eq", "v": "c&d"}
+            ]})
+        );
+    }
+
+    #[test]
+    fn it_rejects_a_malformed_alternative() {
+        for s in ["status='open'|pending", "title='a|b", "f3=['p1'|'p4'"] {
+            // Act
+            let result = parse_pfilter(Some(s.to_string()));
+
+            // Assert
+            assert!(
+                matches!(result, Err(ApiErrors::BadRequestJsonSimpleMsg(_))),
+                "Unexpected result for {s}: {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn it_ignores_a_malformed_single_condition() {
+        // Arrange
+        let s = "bogus&status='open'";
+
+        // Act
+        let r = parse_pfilter(Some(s.to_string())).unwrap();
+
+        // Assert
+        assert_eq!(r.len(), 1);
+        assert!(matches!(
+            r.first(),
+            Some(FieldFilter::ExactFieldMatch { field_name, value })
+                if field_name == "status" && value == "open"
+        ));
+    }
+
+    #[test]
+    fn it_parses_and_joined_alternatives() {
+        // Arrange
+        let s = "a='k'&status='open'|status='pending'|f3=['p1','p4']&pt=~'imi'|";
+        let filter: SearchFilter = parse_pfilter(Some(s.to_string())).unwrap().into();
+
+        // Act
+        let explained = applied_filter(Some(&filter));
+
+        // Assert
+        assert_eq!(
+            explained,
+            serde_json::json!({"and": [
+                {"f": "a", "o": "eq", "v": "k"},
+                {"or": [
+                    {"f": "status", "o": "eq", "v": "open"},
+                    {"f": "status", "o": "eq", "v": "pending"},
+                    {"f": "f3", "o": "in", "v": ["p1", "p4"]}
+                ]},
+                {"f": "pt", "o": "containstext", "v": "imi"}
+            ]})
+        );
+    }
+
+    #[test]
+    fn it_explains_a_missing_filter_as_null() {
+        assert_eq!(applied_filter(None), JsonValue::Null);
+    }
+
+    #[test]
+    fn it_skips_counting_without_total() {
+        assert_eq!(count_mode(None, None), CountMode::Exact);
+        assert_eq!(count_mode(Some(true), None), CountMode::Approximate);
+        assert_eq!(count_mode(None, Some(true)), CountMode::Exact);
+        assert_eq!(count_mode(None, Some(false)), CountMode::Skip);
+        assert_eq!(count_mode(Some(true), Some(false)), CountMode::Skip);
+    }
+
+    #[test]
+    fn it_continues_with_the_next_cursor() {
+        // Arrange
+        let row = serde_json::json!({
+            "id": "3a8ed6a6-9cd7-4a4e-a0b5-42bfbd8e0c01",
+            "f": {"title": "Circle"},
+            "cursor_key": "Circle"
+        });
+
+        // Act
+        let cursor = next_cursor("title+", &row).unwrap();
+        let keyset = keyset_from_cursor(&cursor, "title+").unwrap();
+
+        // Assert
+        assert_eq!(
+            keyset,
+            Keyset::After(CursorPosition {
+                key: serde_json::json!("Circle"),
+                id: Uuid::parse_str("3a8ed6a6-9cd7-4a4e-a0b5-42bfbd8e0c01").unwrap(),
+            })
+        );
+        assert_eq!(keyset_from_cursor("", "title+").unwrap(), Keyset::Start);
+    }
+
+    #[test]
+    fn it_rejects_invalid_cursors() {
+        // Arrange
+        let row =
+            serde_json::json!({"id": "3a8ed6a6-9cd7-4a4e-a0b5-42bfbd8e0c01", "cursor_key": null});
+        let cursor = next_cursor("title+", &row).unwrap();
+        let message = |result: Result<Keyset, ApiErrors>| match result {
+            Err(ApiErrors::BadRequestJsonSimpleMsg(message)) => message,
+            other => panic!("Unexpected result {other:?}"),
+        };
+
+        // Assert
+        assert_eq!(
+            message(keyset_from_cursor(&cursor, "title-")),
+            "Cursor does not match the sort fields"
+        );
+        assert_eq!(
+            message(keyset_from_cursor("not a cursor", "title+")),
+            "Invalid cursor"
+        );
+        assert_eq!(
+            message(keyset_from_cursor("", "title+,price-")),
+            "Cursor pagination requires a single sort field"
+        );
+    }
+
+    fn user_with_roles(roles: &[&str]) -> User {
+        serde_json::from_value(serde_json::json!({
+            "sub": "9d1ef2b4-c2c6-4d52-8f5c-1c3e6a7d8e90",
+            "preferred_username": "tester",
+            "realm_access": { "roles": roles }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn it_includes_deleted_documents_for_administrators() {
+        // Arrange
+        let admin = user_with_roles(&["C_SHAPES_ADMIN"]);
+
+        // Act
+        let deleted_documents = deleted_documents_permitted(Some(true), &admin, "shapes");
+
+        // Assert
+        assert!(matches!(deleted_documents, Ok(DeletedDocuments::Include)));
+    }
+
+    #[test]
+    fn it_rejects_deleted_documents_for_other_users() {
+        // Arrange
+        let reader = user_with_roles(&["C_SHAPES_READER", "C_FLUIDS_ADMIN"]);
+
+        // Act
+        let deleted_documents = deleted_documents_permitted(Some(true), &reader, "shapes");
+
+        // Assert
+        assert!(matches!(deleted_documents, Err(ApiErrors::Forbidden(_))));
+    }
+
+    #[test]
+    fn it_excludes_deleted_documents_by_default() {
+        // Arrange
+        let admin = user_with_roles(&["C_SHAPES_ADMIN"]);
+        let reader = user_with_roles(&["C_SHAPES_READER"]);
+
+        // Act
+        let unset = deleted_documents_permitted(None, &reader, "shapes");
+        let disabled = deleted_documents_permitted(Some(false), &admin, "shapes");
+
+        // Assert
+        assert!(matches!(unset, Ok(DeletedDocuments::Exclude)));
+        assert!(matches!(disabled, Ok(DeletedDocuments::Exclude)));
+    }
+
+    #[test]
+    fn it_runs_the_read_hook_on_listed_deleted_documents() {
+        // Arrange
+        let row = serde_json::json!({
+            "id": "3a8ed6a6-9cd7-4a4e-a0b5-42bfbd8e0c01",
+            "f": {
+                "title": "Application",
+                "salary": 52000,
+                "folivafy_deleted_at": "2024-03-01T10:00:00Z"
+            }
+        });
+
+        // Act
+        let item = listed_item(
+            &row,
+            false,
+            Some(true),
+            &crate::api::read_projection::redacting(&["salary"]),
+        );
+
+        // Assert
+        assert_eq!(item.f, serde_json::json!({"title": "Application"}));
+        assert_eq!(item.deleted, Some(true));
+        assert_eq!(item.deleted_at.as_deref(), Some("2024-03-01T10:00:00Z"));
+    }
 }
@@ -0,0 +1,383 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use chrono::DateTime;
+use jwt_authorizer::JwtClaims;
+use serde::{Deserialize, Serialize};
+use tracing::{error, warn};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::axumext::extractors::{PathParams, ValidatedQueryParams};
+use crate::models::CollectionItemEvent;
+
+use super::{
+    auth::User,
+    collection_config::CollectionConfig,
+    data_service::DataService,
+    db::{
+        document_ids_sql, get_accessible_document, get_collection_by_name, DbListDocumentParams,
+        ListDocumentGrants,
+    },
+    dto,
+    grants::{hook_or_default_user_grants, GrantCollection},
+    list_documents::{documents_filter, parse_timestamp, validate_timestamp, DeletedDocuments},
+    types::Pagination,
+    ApiContext, ApiErrors,
+};
+
+#[derive(Debug, Default, Deserialize, Validate, utoipa::IntoParams)]
+#[serde(default)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct ListEventsParams {
+    /// Only list the events of this category
+    #[param(example = 1)]
+    category: Option<i32>,
+}
+
+#[derive(Debug, Default, Deserialize, Validate, utoipa::IntoParams)]
+#[serde(default)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct ListCollectionEventsParams {
+    /// Only list the events of this category
+    #[param(example = 1)]
+    category: Option<i32>,
+
+    /// Only list the events at or after this RFC 3339 timestamp
+    #[validate(custom(function = "validate_timestamp"))]
+    #[param(example = "2024-02-01T00:00:00Z")]
+    from: Option<String>,
+
+    /// Only list the events before this RFC 3339 timestamp
+    #[validate(custom(function = "validate_timestamp"))]
+    #[param(example = "2024-03-01T00:00:00Z")]
+    to: Option<String>,
+}
+
+impl ListCollectionEventsParams {
+    /// The timestamps were checked by `validate_timestamp`.
+    fn window(&self) -> dto::EventWindow {
+        dto::EventWindow {
+            from: self.from.as_deref().and_then(parse_timestamp),
+            to: self.to.as_deref().and_then(parse_timestamp),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub(crate) struct CollectionItemEventsList {
+    limit: u16,
+
+    offset: u32,
+
+    /// Number of events of the document, or of the category if `category` is set
+    total: u32,
+
+    /// Events of the document, the latest event first
+    items: Vec<CollectionItemEvent>,
+}
+
+/// List document events
+///
+/// Lists the events of a document page by page, the latest event first,
+/// optionally only the events of a single category.
+#[utoipa::path(
+    get,
+    path = "/collections/{collection_name}/{document_id}/events",
+    operation_id = "listDocumentEvents",
+    params(
+        Pagination,
+        ListEventsParams,
+        (
+            "collection_name" = String,
+            Path,
+            description = "Name of the collection",
+            min_length = 1,
+            max_length = 32,
+            pattern = r"^[a-z][-a-z0-9]*$",
+        ),
+        ("document_id" = Uuid, Path, description = "UUID of the document"),
+    ),
+    responses(
+        (status = OK, description = "Events of the document", body = CollectionItemEventsList ),
+        (status = UNAUTHORIZED, description = "User is not a collection reader" ),
+        (status = NOT_FOUND, description = "Document not found" ),
+        (status = BAD_REQUEST, description = "Invalid request" ),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
+    ),
+    tag = super::TAG_EVENT,
+)]
+pub(crate) async fn api_list_document_events(
+    State(ctx): State<ApiContext>,
+    ValidatedQueryParams(pagination): ValidatedQueryParams<Pagination>,
+    ValidatedQueryParams(params): ValidatedQueryParams<ListEventsParams>,
+    PathParams((collection_name, document_id)): PathParams<(String, Uuid)>,
+    JwtClaims(user): JwtClaims<User>,
+) -> Result<Json<CollectionItemEventsList>, ApiErrors> {
+    let pagination = pagination.limited_for(&user);
+    let collection = get_collection_by_name(&ctx.db, &collection_name)
+        .await
+        .ok_or_else(|| ApiErrors::NotFound(collection_name.clone()))?;
+
+    let user_is_permitted = user.is_collection_admin(&collection_name)
+        || user.can_access_all_documents(&collection_name)
+        || user.is_collection_reader(&collection_name);
+    if !user_is_permitted {
+        warn!("User {} is not a collection reader", user.name_and_sub());
+        return Err(ApiErrors::PermissionDenied);
+    }
+
+    let dto_collection: GrantCollection = (&collection).into();
+    let user_grants =
+        hook_or_default_user_grants(&ctx.hooks, &dto_collection, &user, ctx.data_service.clone())
+            .await?;
+    get_accessible_document(
+        &ctx,
+        &user_grants,
+        user.subuuid(),
+        &collection,
+        document_id,
+        false,
+    )
+    .await?
+    .ok_or_else(|| ApiErrors::NotFound(format!("Document {document_id} not found")))?;
+
+    let config = CollectionConfig::from(&collection);
+    let pagination = pagination.clamped(config.max_response_items());
+    let (events, total) = ctx
+        .data_service
+        .get_document_events_paged(
+            document_id,
+            params.category,
+            pagination.limit().into(),
+            pagination.offset().into(),
+        )
+        .await
+        .map_err(|e| {
+            error!("Error while loading events of {document_id}: {:?}", e);
+            ApiErrors::InternalServerError
+        })?;
+
+    Ok(Json(CollectionItemEventsList {
+        limit: pagination.limit(),
+        offset: pagination.offset(),
+        total: u32::try_from(total).unwrap_or(u32::MAX),
+        items: events
+            .iter()
+            .map(|event| collection_item_event(event, &config))
+            .collect(),
+    }))
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub(crate) struct CollectionEventsList {
+    limit: u16,
+
+    offset: u32,
+
+    /// Number of events within the time window, or of the category if
+    /// `category` is set
+    total: u32,
+
+    /// Events of the documents, the latest event first
+    items: Vec<CollectionEvent>,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CollectionEvent {
+    /// UUID of the document of the event
+    document_id: Uuid,
+
+    #[serde(flatten)]
+    event: CollectionItemEvent,
+}
+
+/// List collection events
+///
+/// Lists the events of all documents of a collection that the user can
+/// access page by page, the latest event first. The events can be limited to
+/// a time window, `from` is inclusive and `to` is exclusive, and to a single
+/// category. Events of deleted documents are not listed.
+#[utoipa::path(
+    get,
+    path = "/collections/{collection_name}/events",
+    operation_id = "listCollectionEvents",
+    params(
+        Pagination,
+        ListCollectionEventsParams,
+        (
+            "collection_name" = String,
+            Path,
+            description = "Name of the collection",
+            min_length = 1,
+            max_length = 32,
+            pattern = r"^[a-z][-a-z0-9]*$",
+        ),
+    ),
+    responses(
+        (status = OK, description = "Events of the collection", body = CollectionEventsList ),
+        (status = UNAUTHORIZED, description = "User is not a collection reader" ),
+        (status = NOT_FOUND, description = "Collection not found" ),
+        (status = BAD_REQUEST, description = "Invalid request" ),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
+    ),
+    tag = super::TAG_EVENT,
+)]
+pub(crate) async fn api_list_collection_events(
+    State(ctx): State<ApiContext>,
+    ValidatedQueryParams(pagination): ValidatedQueryParams<Pagination>,
+    ValidatedQueryParams(params): ValidatedQueryParams<ListCollectionEventsParams>,
+    Path(collection_name): Path<String>,
+    JwtClaims(user): JwtClaims<User>,
+) -> Result<Json<CollectionEventsList>, ApiErrors> {
+    let pagination = pagination.limited_for(&user);
+    let collection = get_collection_by_name(&ctx.db, &collection_name)
+        .await
+        .ok_or_else(|| ApiErrors::NotFound(collection_name.clone()))?;
+
+    let user_is_permitted = user.is_collection_admin(&collection_name)
+        || user.can_access_all_documents(&collection_name)
+        || user.is_collection_reader(&collection_name);
+    if !user_is_permitted {
+        warn!("User {} is not a collection reader", user.name_and_sub());
+        return Err(ApiErrors::PermissionDenied);
+    }
+
+    let dto_collection: GrantCollection = (&collection).into();
+    let user_grants =
+        hook_or_default_user_grants(&ctx.hooks, &dto_collection, &user, ctx.data_service.clone())
+            .await?;
+    let document_ids = document_ids_sql(
+        &DbListDocumentParams::builder()
+            .collection(collection.id)
+            .grants(ListDocumentGrants::Restricted(user_grants))
+            .extra_fields(vec![])
+            .sort_fields(None)
+            .filters(documents_filter(DeletedDocuments::Exclude, None))
+            .include_author_id(false)
+            .build(),
+    );
+
+    let config = CollectionConfig::from(&collection);
+    let pagination = pagination.clamped(config.max_response_items());
+    let (events, total) = ctx
+        .data_service
+        .get_collection_events_paged(
+            document_ids,
+            params.category,
+            params.window(),
+            pagination.limit().into(),
+            pagination.offset().into(),
+        )
+        .await
+        .map_err(|e| {
+            error!("Error while loading events of {collection_name}: {:?}", e);
+            ApiErrors::InternalServerError
+        })?;
+
+    Ok(Json(CollectionEventsList {
+        limit: pagination.limit(),
+        offset: pagination.offset(),
+        total: u32::try_from(total).unwrap_or(u32::MAX),
+        items: events
+            .iter()
+            .map(|event| CollectionEvent {
+                document_id: event.document_id(),
+                event: collection_item_event(event, &config),
+            })
+            .collect(),
+    }))
+}
+
+fn collection_item_event(
+    event: &dto::ExistingEvent,
+    config: &CollectionConfig,
+) -> CollectionItemEvent {
+    CollectionItemEvent {
+        id: u32::try_from(event.event_id()).unwrap_or_default(),
+        ts: DateTime::from_timestamp(event.timestamp(), 0).unwrap_or_default(),
+        category: event.category(),
+        category_name: config
+            .category_name(event.category())
+            .map(|name| name.to_string()),
+        e: event.payload().clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn it_converts_the_event_with_its_category_name() {
+        // Arrange
+        let config = CollectionConfig::from_json(&json!({"eventCategories": {"1020": "Approved"}}));
+        let event = dto::ExistingEvent::new(
+            7,
+            Uuid::nil(),
+            1020,
+            json!({"approved": true}),
+            Uuid::nil(),
+            1700000000,
+        );
+
+        // Act
+        let item = collection_item_event(&event, &config);
+
+        // Assert
+        assert_eq!(
+            serde_json::to_value(item).unwrap(),
+            json!({
+                "id": 7,
+                "ts": "2023-11-14T22:13:20Z",
+                "category": 1020,
+                "categoryName": "Approved",
+                "e": {"approved": true}
+            })
+        );
+    }
+
+    #[test]
+    fn it_reads_the_time_window_in_utc() {
+        // Arrange
+        let params = ListCollectionEventsParams {
+            category: None,
+            from: Some("2024-02-01T01:00:00+01:00".to_string()),
+            to: None,
+        };
+
+        // Act
+        let window = params.window();
+
+        // Assert
+        assert_eq!(
+            window,
+            dto::EventWindow {
+                from: Some(DateTime::from_timestamp(1706745600, 0).unwrap()),
+                to: None,
+            }
+        );
+        assert!(params.validate().is_ok());
+    }
+
+    #[test]
+    fn it_rejects_a_window_that_is_not_rfc3339() {
+        // Arrange
+        let params = ListCollectionEventsParams {
+            category: None,
+            from: None,
+            to: Some("yesterday".to_string()),
+        };
+
+        // Act
+        let result = params.validate();
+
+        // Assert
+        assert!(result.is_err());
+    }
+}
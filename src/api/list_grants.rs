@@ -0,0 +1,310 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use jwt_authorizer::JwtClaims;
+use sea_orm::prelude::Uuid;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use validator::Validate;
+
+use crate::axumext::extractors::ValidatedQueryParams;
+
+use super::{
+    auth::User,
+    data_service::DataService,
+    db::{get_collection_by_name, get_document_by_id, get_document_grants},
+    dto, ApiContext, ApiErrors,
+};
+
+#[derive(Debug, Default, Deserialize, Validate, utoipa::IntoParams)]
+#[serde(default)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct ListGrantsParams {
+    /// Add a description of the realm and the name of the user or role to
+    /// every grant
+    readable: Option<bool>,
+}
+
+#[derive(Debug, PartialEq, Serialize, utoipa::ToSchema)]
+#[schema(examples(json!({
+    "realm": "author",
+    "grant": "5f2b0a43-6c8d-4e1f-9a7b-3d2c1e0f9a8b",
+    "view": true,
+    "description": "Author of the document",
+    "name": "Jane Doe"
+})))]
+pub(crate) struct DocumentGrant {
+    /// Realm of the grant, e. g. `author` or `read-collection`
+    realm: String,
+
+    /// Id within the realm, e. g. the id of the author
+    grant: Uuid,
+
+    /// Whether the grant permits to read the document
+    view: bool,
+
+    /// Description of the realm, only with `readable`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+
+    /// Name of the user or role of the grant, only with `readable`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+}
+
+impl From<&dto::Grant> for DocumentGrant {
+    fn from(grant: &dto::Grant) -> Self {
+        Self {
+            realm: grant.realm().to_string(),
+            grant: grant.grant_id(),
+            view: grant.view(),
+            description: None,
+            name: None,
+        }
+    }
+}
+
+/// List item grants
+///
+/// Lists the grants of the document that decide which users can read it, e.g.
+/// to find out why a user cannot see a document. With `readable=true` the
+/// realms are described and the grants are resolved to user or role names.
+#[utoipa::path(
+    get,
+    path = "/collections/{collection_name}/{document_id}/grants",
+    operation_id = "listItemGrants",
+    params(
+        ListGrantsParams,
+        (
+            "collection_name" = String,
+            Path,
+            description = "Name of the collection",
+            min_length = 1,
+            max_length = 32,
+            pattern = r"^[a-z][-a-z0-9]*$",
+        ),
+        ("document_id" = String, Path, description = "UUID of the document", format = Uuid )
+    ),
+    responses(
+        (status = OK, description = "Grants of the document", body = Vec<DocumentGrant> ),
+        (status = UNAUTHORIZED, description = "User is not a collection admin" ),
+        (status = NOT_FOUND, description = "Collection or document not found" ),
+        (status = BAD_REQUEST, description = "Invalid request" ),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
+    ),
+    tag = super::TAG_ADMINISTRATION,
+)]
+pub(crate) async fn api_list_document_grants(
+    State(ctx): State<ApiContext>,
+    ValidatedQueryParams(params): ValidatedQueryParams<ListGrantsParams>,
+    Path((collection_name, document_id)): Path<(String, String)>,
+    JwtClaims(user): JwtClaims<User>,
+) -> Result<Json<Vec<DocumentGrant>>, ApiErrors> {
+    let document_uuid = Uuid::parse_str(&document_id)
+        .map_err(|_| ApiErrors::BadRequestJsonSimpleMsg("Invalid uuid".to_string()))?;
+
+    let collection = get_collection_by_name(&ctx.db, &collection_name)
+        .await
+        .ok_or_else(|| ApiErrors::NotFound(collection_name.clone()))?;
+
+    if !(user.is_collection_admin(&collection_name) || user.is_collections_administrator()) {
+        warn!(
+            "User {} is not permitted to list document grants",
+            user.name_and_sub()
+        );
+        return Err(ApiErrors::PermissionDenied);
+    }
+
+    let document = get_document_by_id(document_uuid, &ctx.db)
+        .await?
+        .filter(|document| document.collection_id == collection.id)
+        .ok_or_else(|| ApiErrors::NotFound(format!("Document {document_id} not found")))?;
+    let grants = get_document_grants(&ctx.db, &document).await?;
+
+    let mut document_grants = Vec::with_capacity(grants.len());
+    for grant in &grants {
+        document_grants.push(if params.readable.unwrap_or_default() {
+            readable_grant(grant, &collection, ctx.data_service.as_ref()).await
+        } else {
+            grant.into()
+        });
+    }
+    Ok(Json(document_grants))
+}
+
+/// Describes the realm of the grant and resolves the grant id to the name of
+/// the user or role. Unknown realms are returned without description.
+async fn readable_grant(
+    grant: &dto::Grant,
+    collection: &entity::collection::Model,
+    data_service: &dyn DataService,
+) -> DocumentGrant {
+    let role_name = |suffix: &str| {
+        (grant.grant_id() == collection.id)
+            .then(|| format!("C_{}_{suffix}", collection.name.to_ascii_uppercase()))
+    };
+    let (description, name) = match grant.realm() {
+        "author" => (
+            "Author of the document",
+            match data_service.get_user_by_id(grant.grant_id()).await {
+                Ok(author) => Some(user_display_name(&author)),
+                Err(e) => {
+                    warn!("Cannot resolve author {}: {e}", grant.grant_id());
+                    None
+                }
+            },
+        ),
+        "read-collection" => ("Readers of the collection", role_name("READER")),
+        "read-all-collection" => (
+            "Users who can read all documents of the collection",
+            role_name("ALLREADER"),
+        ),
+        "cron-access" => ("Scheduled jobs of the server", None),
+        _ => return grant.into(),
+    };
+    DocumentGrant {
+        description: Some(description.to_string()),
+        name,
+        ..grant.into()
+    }
+}
+
+/// Full name of the user, the email address or the id if the user has no name
+fn user_display_name(user: &super::data_service::User) -> String {
+    let full_name = [user.first_name(), user.last_name()]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" ");
+    if !full_name.is_empty() {
+        return full_name;
+    }
+    user.email().unwrap_or_else(|| user.id().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use pretty_assertions::assert_eq;
+
+    use super::super::data_service::User as DataServiceUser;
+    use super::*;
+
+    struct OwnerDataService {
+        owner: Uuid,
+    }
+
+    #[async_trait]
+    impl DataService for OwnerDataService {
+        async fn get_document_events(
+            &self,
+            _document_id: Uuid,
+        ) -> anyhow::Result<Vec<dto::ExistingEvent>> {
+            Ok(vec![])
+        }
+
+        async fn get_document_events_paged(
+            &self,
+            _document_id: Uuid,
+            _category: Option<i32>,
+            _limit: u64,
+            _offset: u64,
+        ) -> anyhow::Result<(Vec<dto::ExistingEvent>, u64)> {
+            Ok((vec![], 0))
+        }
+
+        async fn get_collection_events_paged(
+            &self,
+            _document_ids: sea_query::SelectStatement,
+            _category: Option<i32>,
+            _window: dto::EventWindow,
+            _limit: u64,
+            _offset: u64,
+        ) -> anyhow::Result<(Vec<dto::ExistingEvent>, u64)> {
+            Ok((vec![], 0))
+        }
+
+        async fn get_user_by_id(&self, user_id: Uuid) -> anyhow::Result<DataServiceUser> {
+            anyhow::ensure!(user_id == self.owner, "Unknown user {user_id}");
+            Ok(DataServiceUser::new(
+                user_id.to_string(),
+                Some("jane.doe@example.com".to_string()),
+                Some("Jane".to_string()),
+                Some("Doe".to_string()),
+            ))
+        }
+
+        async fn get_document(
+            &self,
+            _collection_name: &str,
+            _document_id: Uuid,
+        ) -> Option<dto::CollectionDocument> {
+            None
+        }
+
+        async fn get_collection_by_name(&self, _collection_name: &str) -> Option<dto::Collection> {
+            None
+        }
+
+        async fn get_collection_documents(
+            &self,
+            _collection_name: &str,
+        ) -> anyhow::Result<Vec<dto::CollectionDocument>> {
+            Ok(vec![])
+        }
+    }
+
+    fn collection() -> entity::collection::Model {
+        entity::collection::Model {
+            id: Uuid::new_v4(),
+            name: "shapes".to_string(),
+            title: "Shapes".to_string(),
+            oao: false,
+            locked: false,
+            config: serde_json::json!({}),
+        }
+    }
+
+    #[tokio::test]
+    async fn it_resolves_the_author_grant_to_the_owner_name() {
+        // Arrange
+        let owner = Uuid::new_v4();
+        let data_service = OwnerDataService { owner };
+        let grant = dto::Grant::author_grant(owner);
+
+        // Act
+        let readable = readable_grant(&grant, &collection(), &data_service).await;
+
+        // Assert
+        assert_eq!(
+            readable,
+            DocumentGrant {
+                realm: "author".to_string(),
+                grant: owner,
+                view: true,
+                description: Some("Author of the document".to_string()),
+                name: Some("Jane Doe".to_string()),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn it_resolves_collection_grants_to_role_names() {
+        // Arrange
+        let collection = collection();
+        let data_service = OwnerDataService {
+            owner: Uuid::new_v4(),
+        };
+        let grant = dto::Grant::read_all_collection(collection.id);
+        let custom_grant = dto::Grant::new("department".to_string(), Uuid::new_v4(), true);
+
+        // Act
+        let readable = readable_grant(&grant, &collection, &data_service).await;
+        let custom = readable_grant(&custom_grant, &collection, &data_service).await;
+
+        // Assert
+        assert_eq!(readable.name, Some("C_SHAPES_ALLREADER".to_string()));
+        assert_eq!(custom, DocumentGrant::from(&custom_grant));
+    }
+}
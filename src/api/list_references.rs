@@ -0,0 +1,197 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use jwt_authorizer::JwtClaims;
+use lazy_static::lazy_static;
+use regex::Regex;
+use sea_orm::prelude::Uuid;
+use serde::Deserialize;
+use tracing::warn;
+use validator::Validate;
+
+use crate::axumext::extractors::ValidatedQueryParams;
+use crate::models::CollectionItemsList;
+
+use super::{
+    auth::User,
+    db::{
+        get_collection_by_name, get_unlocked_collection_by_name, FieldFilter, ListDocumentGrants,
+    },
+    grants::{hook_or_default_user_grants, GrantCollection},
+    list_ancestors::{accessible_document, is_collection_reader},
+    list_documents::{
+        generic_list_documents, DeletedDocuments, GenericListDocumentsParams, RE_EXTRA_FIELDS,
+    },
+    read_projection::ReadProjection,
+    types::Pagination,
+    ApiContext, ApiErrors,
+};
+
+lazy_static! {
+    static ref RE_REFERENCE_FIELD: Regex =
+        Regex::new(r"^[a-zA-Z0-9_]+(\.[a-zA-Z0-9_]+)*$").unwrap();
+}
+
+#[derive(Debug, Deserialize, Validate, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct ListReferencesParams {
+    /// Name of the collection with the referencing documents
+    #[param(example = "tasks")]
+    from: String,
+
+    /// Field of the referencing documents that contains the id of this
+    /// document, nested fields in dotted notation, e. g. `project.id`
+    #[validate(regex(path = *RE_REFERENCE_FIELD))]
+    #[param(example = "project", pattern = r#"^[a-zA-Z0-9_]+(\.[a-zA-Z0-9_]+)*$"#)]
+    field: String,
+
+    /// A comma separated list of document fields that should be contained in the response
+    /// or `*` for all fields
+    #[validate(regex(path = *RE_EXTRA_FIELDS))]
+    #[serde(rename = "extraFields")]
+    #[param(
+        example = "price,length",
+        pattern = r#"^(\*|[a-zA-Z0-9_]+(,[a-zA-Z0-9_]+)*)$"#
+    )]
+    extra_fields: Option<String>,
+}
+
+/// List referencing documents
+///
+/// Lists the documents of the collection `from` whose field `field` contains
+/// the id of this document, e. g. all tasks of a project. Only documents that
+/// are accessible to the user are returned.
+#[utoipa::path(
+    get,
+    path = "/collections/{collection_name}/{document_id}/references",
+    operation_id = "listItemReferences",
+    params(
+        Pagination,
+        ListReferencesParams,
+        (
+            "collection_name" = String,
+            Path,
+            description = "Name of the collection",
+            min_length = 1,
+            max_length = 32,
+            pattern = r"^[a-z][-a-z0-9]*$",
+        ),
+        ("document_id" = String, Path, description = "UUID of the document", format = Uuid )
+    ),
+    responses(
+        (status = OK, description = "Referencing documents", body = CollectionItemsList ),
+        (status = UNAUTHORIZED, description = "User is not a reader of both collections" ),
+        (status = NOT_FOUND, description = "Collection or document not found" ),
+        (status = BAD_REQUEST, description = "Invalid request" ),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
+    ),
+    tag = super::TAG_COLLECTION,
+)]
+pub(crate) async fn api_list_references(
+    State(ctx): State<ApiContext>,
+    Path((collection_name, document_id)): Path<(String, String)>,
+    ValidatedQueryParams(pagination): ValidatedQueryParams<Pagination>,
+    ValidatedQueryParams(params): ValidatedQueryParams<ListReferencesParams>,
+    JwtClaims(user): JwtClaims<User>,
+) -> Result<Json<CollectionItemsList>, ApiErrors> {
+    let pagination = pagination.limited_for(&user);
+    let document_uuid = Uuid::parse_str(&document_id)
+        .map_err(|_| ApiErrors::BadRequestJsonSimpleMsg("Invalid uuid".to_string()))?;
+
+    let collection = get_collection_by_name(&ctx.db, &collection_name)
+        .await
+        .ok_or_else(|| ApiErrors::NotFound(collection_name.clone()))?;
+
+    if !is_collection_reader(&user, &collection_name) {
+        warn!("User {} is not a collection reader", user.name_and_sub());
+        return Err(ApiErrors::PermissionDenied);
+    }
+
+    accessible_document(&ctx, &user, &collection, document_uuid)
+        .await?
+        .ok_or_else(|| ApiErrors::NotFound(format!("Document {document_id} not found")))?;
+
+    let from_collection = get_unlocked_collection_by_name(&ctx.db, &params.from)
+        .await
+        .ok_or_else(|| ApiErrors::NotFound(params.from.clone()))?;
+
+    if !is_collection_reader(&user, &params.from) {
+        warn!(
+            "User {} is not a reader of collection {}",
+            user.name_and_sub(),
+            params.from
+        );
+        return Err(ApiErrors::PermissionDenied);
+    }
+
+    let dto_collection: GrantCollection = (&from_collection).into();
+    let user_grants =
+        hook_or_default_user_grants(&ctx.hooks, &dto_collection, &user, ctx.data_service.clone())
+            .await?;
+
+    generic_list_documents(
+        &ctx.db,
+        from_collection.id,
+        DeletedDocuments::Exclude,
+        GenericListDocumentsParams::builder()
+            .sort_fields(None)
+            .extra_fields(params.extra_fields)
+            .filter(Some(reference_filter(params.field, document_uuid).into()))
+            .projection(ReadProjection::new(&ctx.hooks, &from_collection, &user))
+            .build(),
+        ListDocumentGrants::Restricted(user_grants),
+        pagination,
+    )
+    .await
+}
+
+/// Matches the documents whose field contains the id of the document.
+fn reference_filter(field_name: String, document_uuid: Uuid) -> Vec<FieldFilter> {
+    vec![FieldFilter::ExactFieldMatch {
+        field_name,
+        value: document_uuid.to_string(),
+    }]
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn params(field: &str) -> ListReferencesParams {
+        ListReferencesParams {
+            from: "tasks".to_string(),
+            field: field.to_string(),
+            extra_fields: None,
+        }
+    }
+
+    #[test]
+    fn it_validates_the_referencing_field() {
+        assert!(params("project").validate().is_ok());
+        assert!(params("project.id").validate().is_ok());
+        assert!(params("project'--").validate().is_err());
+        assert!(params("project.").validate().is_err());
+        assert!(params("").validate().is_err());
+    }
+
+    #[test]
+    fn it_matches_the_document_id() {
+        // Arrange
+        let document_uuid = Uuid::new_v4();
+
+        // Act
+        let filter = reference_filter("project.id".to_string(), document_uuid);
+
+        // Assert
+        match filter.as_slice() {
+            [FieldFilter::ExactFieldMatch { field_name, value }] => {
+                assert_eq!(field_name, "project.id");
+                assert_eq!(value, &document_uuid.to_string());
+            }
+            _ => panic!("Unexpected filter {filter:?}"),
+        }
+    }
+}
@@ -0,0 +1,211 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+};
+use axum_macros::debug_handler;
+use jwt_authorizer::JwtClaims;
+use sea_orm::prelude::Uuid;
+use tracing::{debug, warn};
+
+use crate::api::{
+    auth::User,
+    db::{get_collection_by_name, set_document_locked},
+    ApiContext, ApiErrors,
+};
+
+/// Lock item
+///
+/// Locks the document, e. g. a finalized invoice. A locked document cannot be
+/// updated and rejects new events, including deletion, with status 423 until
+/// it is unlocked. Other documents of the collection remain editable.
+#[debug_handler]
+#[utoipa::path(
+    put,
+    path = "/collections/{collection_name}/{document_id}/lock",
+    operation_id = "lockItemById",
+    params(
+        (
+            "collection_name" = String,
+            Path,
+            description = "Name of the collection",
+            min_length = 1,
+            max_length = 32,
+            pattern = r"^[a-z][-a-z0-9]*$",
+        ),
+        ("document_id" = String, Path, description = "UUID of the document", format = Uuid )
+    ),
+    responses(
+        (status = NO_CONTENT, description = "Document locked" ),
+        (status = UNAUTHORIZED, description = "User is not a collection admin" ),
+        (status = NOT_FOUND, description = "Collection or document not found" ),
+        (status = BAD_REQUEST, description = "Invalid request" ),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
+    ),
+    tag = super::TAG_COLLECTION,
+)]
+pub(crate) async fn api_lock_document(
+    State(ctx): State<ApiContext>,
+    Path((collection_name, document_id)): Path<(String, String)>,
+    JwtClaims(user): JwtClaims<User>,
+) -> Result<StatusCode, ApiErrors> {
+    lock_document(ctx, user, collection_name, document_id, true).await
+}
+
+/// Unlock item
+///
+/// Unlocks the document, it can be updated again.
+#[debug_handler]
+#[utoipa::path(
+    delete,
+    path = "/collections/{collection_name}/{document_id}/lock",
+    operation_id = "unlockItemById",
+    params(
+        (
+            "collection_name" = String,
+            Path,
+            description = "Name of the collection",
+            min_length = 1,
+            max_length = 32,
+            pattern = r"^[a-z][-a-z0-9]*$",
+        ),
+        ("document_id" = String, Path, description = "UUID of the document", format = Uuid )
+    ),
+    responses(
+        (status = NO_CONTENT, description = "Document unlocked" ),
+        (status = UNAUTHORIZED, description = "User is not a collection admin" ),
+        (status = NOT_FOUND, description = "Collection or document not found" ),
+        (status = BAD_REQUEST, description = "Invalid request" ),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
+    ),
+    tag = super::TAG_COLLECTION,
+)]
+pub(crate) async fn api_unlock_document(
+    State(ctx): State<ApiContext>,
+    Path((collection_name, document_id)): Path<(String, String)>,
+    JwtClaims(user): JwtClaims<User>,
+) -> Result<StatusCode, ApiErrors> {
+    lock_document(ctx, user, collection_name, document_id, false).await
+}
+
+async fn lock_document(
+    ctx: ApiContext,
+    user: User,
+    collection_name: String,
+    document_id: String,
+    locked: bool,
+) -> Result<StatusCode, ApiErrors> {
+    let document_uuid = Uuid::parse_str(&document_id)
+        .map_err(|_| ApiErrors::BadRequestJsonSimpleMsg("Invalid uuid".to_string()))?;
+
+    let collection = get_collection_by_name(&ctx.db, &collection_name)
+        .await
+        .ok_or_else(|| ApiErrors::NotFound(collection_name.clone()))?;
+
+    if !(user.is_collection_admin(&collection_name) || user.is_collections_administrator()) {
+        warn!(
+            "User {} is not permitted to lock documents",
+            user.name_and_sub()
+        );
+        return Err(ApiErrors::PermissionDenied);
+    }
+
+    if !set_document_locked(&ctx.db, collection.id, document_uuid, locked).await? {
+        return Err(ApiErrors::NotFound(format!(
+            "Document {document_id} not found"
+        )));
+    }
+    debug!(
+        "User {} set lock of document {document_id} to {locked}",
+        user.name_and_sub()
+    );
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Rejects changes of a locked document with status 423.
+pub(crate) fn ensure_unlocked(
+    document: &entity::collection_document::Model,
+) -> Result<(), ApiErrors> {
+    if document.locked {
+        debug!("Document {} is locked", document.id);
+        return Err(ApiErrors::Locked("Document is locked".to_string()));
+    }
+    Ok(())
+}
+
+/// Rejects writes into a locked collection with status 423.
+pub(crate) fn ensure_collection_unlocked(
+    user: &User,
+    collection: &entity::collection::Model,
+) -> Result<(), ApiErrors> {
+    if collection.locked {
+        warn!(
+            "User {} tried to write into locked collection {}",
+            user.name_and_sub(),
+            collection.name
+        );
+        return Err(ApiErrors::Locked("Read only collection".to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn document(locked: bool) -> entity::collection_document::Model {
+        entity::collection_document::Model {
+            id: Uuid::new_v4(),
+            collection_id: Uuid::new_v4(),
+            owner: Uuid::new_v4(),
+            f: json!({"title": "Invoice 2026-17"}),
+            seq: 1,
+            locked,
+        }
+    }
+
+    #[test]
+    fn it_rejects_changes_of_a_locked_document() {
+        // Act
+        let locked = ensure_unlocked(&document(true));
+        let sibling = ensure_unlocked(&document(false));
+
+        // Assert
+        assert!(matches!(
+            locked,
+            Err(ApiErrors::Locked(message)) if message == "Document is locked"
+        ));
+        assert!(sibling.is_ok());
+    }
+
+    #[test]
+    fn it_rejects_writes_into_a_locked_collection() {
+        // Arrange
+        let user: User = serde_json::from_value(json!({
+            "sub": "9d1ef2b4-c2c6-4d52-8f5c-1c3e6a7d8e90",
+            "preferred_username": "tester",
+            "realm_access": { "roles": ["C_INVOICES_EDITOR"] }
+        }))
+        .unwrap();
+        let collection = |locked| entity::collection::Model {
+            id: Uuid::new_v4(),
+            name: "invoices".to_string(),
+            title: "Invoices".to_string(),
+            oao: false,
+            locked,
+            config: json!({}),
+        };
+
+        // Act
+        let locked = ensure_collection_unlocked(&user, &collection(true));
+        let unlocked = ensure_collection_unlocked(&user, &collection(false));
+
+        // Assert
+        assert!(matches!(
+            locked,
+            Err(ApiErrors::Locked(message)) if message == "Read only collection"
+        ));
+        assert!(unlocked.is_ok());
+    }
+}
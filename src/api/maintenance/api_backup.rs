@@ -0,0 +1,636 @@
+use std::collections::HashMap;
+
+use anyhow::Context;
+use axum::{
+    body::Body,
+    extract::State,
+    http::{header, StatusCode},
+    response::IntoResponse,
+};
+use axum_macros::debug_handler;
+use chrono::Utc;
+use entity::{collection, collection_document, event, grant};
+use futures_util::{stream, StreamExt};
+use jwt_authorizer::JwtClaims;
+use sea_orm::{
+    AccessMode, ActiveModelTrait, ActiveValue::NotSet, ColumnTrait, DatabaseTransaction,
+    EntityTrait, IsolationLevel, QueryFilter, QueryOrder, QuerySelect, QueryTrait, Set,
+    TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tracing::{debug, error, info};
+use uuid::Uuid;
+
+use crate::api::{auth::User, ApiContext, ApiErrors};
+
+/// Version of the backup format
+const BACKUP_VERSION: u32 = 1;
+
+/// Number of rows that are read from the database at once
+const BACKUP_BATCH_SIZE: u64 = 500;
+
+/// A line of the backup file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub(crate) enum BackupRecord {
+    /// First line of the backup
+    Manifest { version: u32, created: String },
+    Collection {
+        id: Uuid,
+        name: String,
+        title: String,
+        oao: bool,
+        locked: bool,
+        config: serde_json::Value,
+    },
+    Document {
+        collection: String,
+        id: Uuid,
+        owner: Uuid,
+        f: serde_json::Value,
+        /// Locked documents only, missing in older backups
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        locked: bool,
+    },
+    Event {
+        document_id: Uuid,
+        timestamp: Option<chrono::NaiveDateTime>,
+        user: Uuid,
+        category_id: i32,
+        payload: serde_json::Value,
+    },
+    Grant {
+        document_id: Uuid,
+        realm: String,
+        grant: Uuid,
+        view: bool,
+    },
+    /// Last line of the backup, a backup without it is incomplete
+    End { collections: u64, documents: u64 },
+}
+
+impl BackupRecord {
+    fn to_line(&self) -> String {
+        let mut line = serde_json::to_string(self).expect("Failed to serialize backup record");
+        line.push('\n');
+        line
+    }
+}
+
+impl From<collection::Model> for BackupRecord {
+    fn from(model: collection::Model) -> Self {
+        BackupRecord::Collection {
+            id: model.id,
+            name: model.name,
+            title: model.title,
+            oao: model.oao,
+            locked: model.locked,
+            config: model.config,
+        }
+    }
+}
+
+impl From<event::Model> for BackupRecord {
+    fn from(model: event::Model) -> Self {
+        BackupRecord::Event {
+            document_id: model.document_id,
+            timestamp: model.timestamp,
+            user: model.user,
+            category_id: model.category_id,
+            payload: model.payload,
+        }
+    }
+}
+
+impl From<grant::Model> for BackupRecord {
+    fn from(model: grant::Model) -> Self {
+        BackupRecord::Grant {
+            document_id: model.document_id,
+            realm: model.realm,
+            grant: model.grant,
+            view: model.view,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize, utoipa::IntoParams)]
+#[serde(default)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct BackupParams {
+    /// Include the events of the documents
+    #[serde(rename = "includeEvents")]
+    #[param(example = true, default = false)]
+    include_events: Option<bool>,
+
+    /// Include the grants of the documents
+    #[serde(rename = "includeGrants")]
+    #[param(example = true, default = false)]
+    include_grants: Option<bool>,
+}
+
+/// Export all collections
+///
+/// Streams all collections with their documents as newline delimited JSON.
+/// Every line is a record with a `type`: the `manifest` comes first, then every
+/// `collection` followed by its `document`s (and `event`s and `grant`s, if
+/// requested). The last line is the `end` record.
+///
+/// The export reads from a single database snapshot, documents that are
+/// written during the export are not contained.
+#[debug_handler]
+#[utoipa::path(
+    get,
+    path = "/backup",
+    operation_id = "backup",
+    params(BackupParams),
+    responses(
+        (status = OK, description = "Backup as newline delimited JSON", content_type = "application/x-ndjson", body = String),
+        (status = UNAUTHORIZED, description = "User is not a collection admin" ),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
+    ),
+    tag = crate::api::TAG_MAINTENANCE,
+)]
+pub(crate) async fn api_backup(
+    State(ctx): State<ApiContext>,
+    JwtClaims(user): JwtClaims<User>,
+    axum::extract::Query(params): axum::extract::Query<BackupParams>,
+) -> Result<impl IntoResponse, ApiErrors> {
+    if !user.is_collections_administrator() {
+        debug!("User {} is not a collection admin", user.name_and_sub());
+        return Err(ApiErrors::PermissionDenied);
+    }
+
+    let txn = ctx
+        .db
+        .begin_with_config(
+            Some(IsolationLevel::RepeatableRead),
+            Some(AccessMode::ReadOnly),
+        )
+        .await?;
+    info!("Backup started by {}", user.name_and_sub());
+
+    let (sender, receiver) = mpsc::channel::<Result<String, std::io::Error>>(16);
+    tokio::spawn(async move {
+        let include_events = params.include_events.unwrap_or_default();
+        let include_grants = params.include_grants.unwrap_or_default();
+        if let Err(e) = write_backup(&txn, &sender, include_events, include_grants).await {
+            error!("Backup failed: {:?}", e);
+            let _ = sender
+                .send(Err(std::io::Error::other("Backup failed")))
+                .await;
+        }
+    });
+
+    let body = Body::from_stream(stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|line| (line, receiver))
+    }));
+    Ok(([(header::CONTENT_TYPE, "application/x-ndjson")], body))
+}
+
+type BackupSender = mpsc::Sender<Result<String, std::io::Error>>;
+
+async fn send(sender: &BackupSender, record: BackupRecord) -> anyhow::Result<()> {
+    sender
+        .send(Ok(record.to_line()))
+        .await
+        .context("Backup receiver closed")
+}
+
+async fn write_backup(
+    txn: &DatabaseTransaction,
+    sender: &BackupSender,
+    include_events: bool,
+    include_grants: bool,
+) -> anyhow::Result<()> {
+    send(
+        sender,
+        BackupRecord::Manifest {
+            version: BACKUP_VERSION,
+            created: Utc::now().to_rfc3339(),
+        },
+    )
+    .await?;
+
+    let collections = collection::Entity::find()
+        .order_by_asc(collection::Column::Name)
+        .all(txn)
+        .await?;
+    let collections_count = collections.len() as u64;
+    let mut documents_count = 0;
+    for collection in collections {
+        let collection_id = collection.id;
+        let collection_name = collection.name.clone();
+        send(sender, collection.into()).await?;
+
+        let mut last_id: Option<Uuid> = None;
+        loop {
+            let documents = collection_document::Entity::find()
+                .filter(collection_document::Column::CollectionId.eq(collection_id))
+                .apply_if(last_id, |q, id| {
+                    q.filter(collection_document::Column::Id.gt(id))
+                })
+                .order_by_asc(collection_document::Column::Id)
+                .limit(BACKUP_BATCH_SIZE)
+                .all(txn)
+                .await?;
+            let Some(last) = documents.last() else {
+                break;
+            };
+            last_id = Some(last.id);
+            documents_count += documents.len() as u64;
+            for document in documents {
+                send(
+                    sender,
+                    BackupRecord::Document {
+                        collection: collection_name.clone(),
+                        id: document.id,
+                        owner: document.owner,
+                        f: document.f,
+                        locked: document.locked,
+                    },
+                )
+                .await?;
+            }
+        }
+
+        if include_events {
+            let mut last_id: Option<i32> = None;
+            loop {
+                let events = event::Entity::find()
+                    .inner_join(collection_document::Entity)
+                    .filter(collection_document::Column::CollectionId.eq(collection_id))
+                    .apply_if(last_id, |q, id| q.filter(event::Column::Id.gt(id)))
+                    .order_by_asc(event::Column::Id)
+                    .limit(BACKUP_BATCH_SIZE)
+                    .all(txn)
+                    .await?;
+                let Some(last) = events.last() else {
+                    break;
+                };
+                last_id = Some(last.id);
+                for event in events {
+                    send(sender, event.into()).await?;
+                }
+            }
+        }
+
+        if include_grants {
+            let mut last_id: Option<i32> = None;
+            loop {
+                let grants = grant::Entity::find()
+                    .inner_join(collection_document::Entity)
+                    .filter(collection_document::Column::CollectionId.eq(collection_id))
+                    .apply_if(last_id, |q, id| q.filter(grant::Column::Id.gt(id)))
+                    .order_by_asc(grant::Column::Id)
+                    .limit(BACKUP_BATCH_SIZE)
+                    .all(txn)
+                    .await?;
+                let Some(last) = grants.last() else {
+                    break;
+                };
+                last_id = Some(last.id);
+                for grant in grants {
+                    send(sender, grant.into()).await?;
+                }
+            }
+        }
+    }
+
+    send(
+        sender,
+        BackupRecord::End {
+            collections: collections_count,
+            documents: documents_count,
+        },
+    )
+    .await?;
+    debug!("Backup finished: {collections_count} collection(s), {documents_count} document(s)");
+    Ok(())
+}
+
+/// Restore a backup
+///
+/// Reads a backup that was created by `GET /backup` and stores its content.
+/// Collections that already exist are kept, the documents are added to them.
+/// The restore is done in one transaction: if a document already exists or the
+/// backup is incomplete, nothing is stored.
+///
+/// Restored documents receive new sequence numbers, restored events are marked
+/// as published.
+#[debug_handler]
+#[utoipa::path(
+    post,
+    path = "/restore",
+    operation_id = "restore",
+    request_body(content = String, description = "Backup as newline delimited JSON", content_type = "application/x-ndjson"),
+    responses(
+        (status = CREATED, description = "Backup restored" ),
+        (status = BAD_REQUEST, description = "Invalid or incomplete backup" ),
+        (status = UNAUTHORIZED, description = "User is not a collection admin" ),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
+    ),
+    tag = crate::api::TAG_MAINTENANCE,
+)]
+pub(crate) async fn api_restore(
+    State(ctx): State<ApiContext>,
+    JwtClaims(user): JwtClaims<User>,
+    body: Body,
+) -> Result<(StatusCode, String), ApiErrors> {
+    if !user.is_collections_administrator() {
+        debug!("User {} is not a collection admin", user.name_and_sub());
+        return Err(ApiErrors::PermissionDenied);
+    }
+
+    let txn = ctx.db.begin().await?;
+    let mut restore = Restore::default();
+    let mut lines = NdjsonLines::default();
+    let mut data = body.into_data_stream();
+    while let Some(chunk) = data.next().await {
+        let chunk = chunk.map_err(|e| {
+            error!("Reading backup failed: {:?}", e);
+            ApiErrors::BadRequestJsonSimpleMsg("Reading backup failed".to_string())
+        })?;
+        for line in lines.push(&chunk) {
+            restore.apply(&txn, &line).await?;
+        }
+    }
+    if let Some(line) = lines.finish() {
+        restore.apply(&txn, &line).await?;
+    }
+    if !restore.complete {
+        return Err(ApiErrors::BadRequestJsonSimpleMsg(
+            "Backup is incomplete".to_string(),
+        ));
+    }
+    txn.commit().await?;
+
+    info!(
+        "Backup restored by {}: {} collection(s), {} document(s)",
+        user.name_and_sub(),
+        restore.collections.len(),
+        restore.documents
+    );
+    Ok((
+        StatusCode::CREATED,
+        format!(
+            "Restored {} collection(s) with {} document(s)",
+            restore.collections.len(),
+            restore.documents
+        ),
+    ))
+}
+
+/// Splits a byte stream into lines
+#[derive(Debug, Default)]
+pub(crate) struct NdjsonLines {
+    buffer: Vec<u8>,
+}
+
+impl NdjsonLines {
+    /// Returns the complete lines, the remainder is kept for the next chunk.
+    pub(crate) fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(chunk);
+        let mut lines = vec![];
+        while let Some(pos) = self.buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line).trim().to_string();
+            if !line.is_empty() {
+                lines.push(line);
+            }
+        }
+        lines
+    }
+
+    pub(crate) fn finish(self) -> Option<String> {
+        let line = String::from_utf8_lossy(&self.buffer).trim().to_string();
+        (!line.is_empty()).then_some(line)
+    }
+}
+
+/// State of a running restore
+#[derive(Debug, Default)]
+struct Restore {
+    /// Maps the collection names of the backup to the ids in the database
+    collections: HashMap<String, Uuid>,
+    documents: u64,
+    has_manifest: bool,
+    complete: bool,
+}
+
+impl Restore {
+    async fn apply(&mut self, txn: &DatabaseTransaction, line: &str) -> Result<(), ApiErrors> {
+        let record: BackupRecord = serde_json::from_str(line).map_err(|e| {
+            ApiErrors::BadRequestJsonSimpleMsg(format!("Invalid backup record: {e}"))
+        })?;
+        self.check_order(&record)?;
+        match record {
+            BackupRecord::Manifest { version, .. } => {
+                if version != BACKUP_VERSION {
+                    return Err(ApiErrors::BadRequestJsonSimpleMsg(format!(
+                        "Unsupported backup version {version}"
+                    )));
+                }
+                self.has_manifest = true;
+            }
+            BackupRecord::Collection {
+                id,
+                name,
+                title,
+                oao,
+                locked,
+                config,
+            } => {
+                let existing = collection::Entity::find()
+                    .filter(collection::Column::Name.eq(name.clone()))
+                    .one(txn)
+                    .await?;
+                let collection_id = match existing {
+                    Some(existing) => {
+                        debug!("Collection {name} exists, adding documents");
+                        existing.id
+                    }
+                    None => {
+                        collection::ActiveModel {
+                            id: Set(id),
+                            name: Set(name.clone()),
+                            title: Set(title),
+                            oao: Set(oao),
+                            locked: Set(locked),
+                            config: Set(config),
+                        }
+                        .insert(txn)
+                        .await?
+                        .id
+                    }
+                };
+                self.collections.insert(name, collection_id);
+            }
+            BackupRecord::Document {
+                collection,
+                id,
+                owner,
+                f,
+                locked,
+            } => {
+                let collection_id = *self.collections.get(&collection).ok_or_else(|| {
+                    ApiErrors::BadRequestJsonSimpleMsg(format!(
+                        "Document {id} belongs to unknown collection {collection}"
+                    ))
+                })?;
+                collection_document::ActiveModel {
+                    id: Set(id),
+                    collection_id: Set(collection_id),
+                    owner: Set(owner),
+                    f: Set(f),
+                    seq: NotSet,
+                    locked: Set(locked),
+                }
+                .insert(txn)
+                .await?;
+                self.documents += 1;
+            }
+            BackupRecord::Event {
+                document_id,
+                timestamp,
+                user,
+                category_id,
+                payload,
+            } => {
+                event::ActiveModel {
+                    id: NotSet,
+                    timestamp: Set(timestamp),
+                    document_id: Set(document_id),
+                    user: Set(user),
+                    category_id: Set(category_id),
+                    payload: Set(payload),
+                    published: Set(true),
+                }
+                .insert(txn)
+                .await?;
+            }
+            BackupRecord::Grant {
+                document_id,
+                realm,
+                grant,
+                view,
+            } => {
+                grant::ActiveModel {
+                    id: NotSet,
+                    document_id: Set(document_id),
+                    realm: Set(realm),
+                    grant: Set(grant),
+                    view: Set(view),
+                }
+                .insert(txn)
+                .await?;
+            }
+            BackupRecord::End { documents, .. } => {
+                if documents != self.documents {
+                    return Err(ApiErrors::BadRequestJsonSimpleMsg(format!(
+                        "Backup contains {} of {documents} documents",
+                        self.documents
+                    )));
+                }
+                self.complete = true;
+            }
+        }
+        Ok(())
+    }
+
+    /// The manifest must be the first record and the end record the last one.
+    fn check_order(&self, record: &BackupRecord) -> Result<(), ApiErrors> {
+        let is_manifest = matches!(record, BackupRecord::Manifest { .. });
+        if self.complete || is_manifest == self.has_manifest {
+            return Err(ApiErrors::BadRequestJsonSimpleMsg(
+                "Invalid order of backup records".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn it_serializes_records_as_lines() {
+        // Arrange
+        let record = BackupRecord::Document {
+            collection: "shapes".to_string(),
+            id: Uuid::parse_str("a4a0ab26-3a6f-4e4a-9a43-34f6b8a9c1f0").unwrap(),
+            owner: Uuid::parse_str("9d1ef2b4-c2c6-4d52-8f5c-1c3e6a7d8e90").unwrap(),
+            f: json!({"title": "Circle"}),
+            locked: false,
+        };
+
+        // Act
+        let line = record.to_line();
+
+        // Assert
+        assert_eq!(
+            line,
+            "{\"type\":\"document\",\"collection\":\"shapes\",\"id\":\"a4a0ab26-3a6f-4e4a-9a43-34f6b8a9c1f0\",\"owner\":\"9d1ef2b4-c2c6-4d52-8f5c-1c3e6a7d8e90\",\"f\":{\"title\":\"Circle\"}}\n"
+        );
+    }
+
+    #[test]
+    fn it_reads_records_with_camel_case_fields() {
+        // Arrange
+        let line = r#"{"type":"event","documentId":"a4a0ab26-3a6f-4e4a-9a43-34f6b8a9c1f0","timestamp":null,"user":"9d1ef2b4-c2c6-4d52-8f5c-1c3e6a7d8e90","categoryId":1,"payload":{}}"#;
+
+        // Act
+        let record: BackupRecord = serde_json::from_str(line).unwrap();
+
+        // Assert
+        assert_eq!(
+            record,
+            BackupRecord::Event {
+                document_id: Uuid::parse_str("a4a0ab26-3a6f-4e4a-9a43-34f6b8a9c1f0").unwrap(),
+                timestamp: None,
+                user: Uuid::parse_str("9d1ef2b4-c2c6-4d52-8f5c-1c3e6a7d8e90").unwrap(),
+                category_id: 1,
+                payload: json!({}),
+            }
+        );
+    }
+
+    #[test]
+    fn it_splits_chunks_into_lines() {
+        // Arrange
+        let mut lines = NdjsonLines::default();
+
+        // Act
+        let first = lines.push(b"{\"a\":1}\n{\"b\"");
+        let second = lines.push(b":2}\n\n{\"c\":3}");
+        let last = lines.finish();
+
+        // Assert
+        assert_eq!(first, vec!["{\"a\":1}".to_string()]);
+        assert_eq!(second, vec!["{\"b\":2}".to_string()]);
+        assert_eq!(last, Some("{\"c\":3}".to_string()));
+    }
+
+    #[test]
+    fn it_requires_the_manifest_first() {
+        // Arrange
+        let restore = Restore::default();
+
+        // Act
+        let result = restore.check_order(&BackupRecord::End {
+            collections: 0,
+            documents: 0,
+        });
+
+        // Assert
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,82 @@
+use crate::api::auth::User;
+use crate::api::db::{get_collection_by_name, ListDocumentGrants};
+use crate::api::list_documents::{
+    generic_list_documents, DeletedDocuments, GenericListDocumentsParams,
+};
+use crate::api::read_projection::ReadProjection;
+use crate::api::types::Pagination;
+use crate::api::{ApiContext, ApiErrors};
+use crate::axumext::extractors::ValidatedQueryParams;
+use crate::models::CollectionItemsList;
+use axum::extract::{Path, State};
+use axum::Json;
+use axum_macros::debug_handler;
+use jwt_authorizer::JwtClaims;
+use tracing::debug;
+
+/// List documents without grants
+///
+/// List the documents of the collection that have no grants at all. Such
+/// documents are invisible to every user except administrators, e. g. after a
+/// failed import. Use `rebuild-grants` to restore their grants. Deleted
+/// documents are included, the items contain the fields `deleted` and
+/// `deletedAt`.
+#[debug_handler]
+#[utoipa::path(
+    get,
+    path = "/maintenance/{collection_name}/orphans",
+    operation_id = "listOrphanedDocuments",
+    params(
+        Pagination,
+        (
+            "collection_name" = String,
+            Path,
+            description = "Name of the collection",
+            min_length = 1,
+            max_length = 32,
+            pattern = r"^[a-z][-a-z0-9]*$",
+        ),
+    ),
+    responses(
+        (status = OK, description = "List of documents without grants", body = CollectionItemsList ),
+        (status = UNAUTHORIZED, description = "User is not a collection admin" ),
+        (status = NOT_FOUND, description = "Collection not found" ),
+        (status = BAD_REQUEST, description = "Invalid request" ),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
+    ),
+    tag = crate::api::TAG_MAINTENANCE,
+)]
+pub(crate) async fn api_list_orphans(
+    State(ctx): State<ApiContext>,
+    JwtClaims(user): JwtClaims<User>,
+    Path(collection_name): Path<String>,
+    ValidatedQueryParams(pagination): ValidatedQueryParams<Pagination>,
+) -> Result<Json<CollectionItemsList>, ApiErrors> {
+    let pagination = pagination.limited_for(&user);
+    let collection = get_collection_by_name(&ctx.db, &collection_name)
+        .await
+        .ok_or_else(|| {
+            debug!("Collection {} not found", collection_name);
+            ApiErrors::NotFound(format!("Collection {} not found", collection_name))
+        })?;
+
+    if !user.is_collections_administrator() {
+        debug!("User {} is not a collection admin", user.name_and_sub());
+        return Err(ApiErrors::PermissionDenied);
+    }
+
+    generic_list_documents(
+        &ctx.db,
+        collection.id,
+        DeletedDocuments::Include,
+        GenericListDocumentsParams::builder()
+            .sort_fields(None)
+            .extra_fields(None)
+            .filter(None)
+            .projection(ReadProjection::new(&ctx.hooks, &collection, &user))
+            .build(),
+        ListDocumentGrants::Orphans,
+        pagination,
+    )
+    .await
+}
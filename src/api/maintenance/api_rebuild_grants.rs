@@ -1,24 +1,64 @@
+use std::sync::Arc;
+
 use crate::api::auth::User;
+use crate::api::collection_config::CollectionConfig;
+use crate::api::data_service::DataService;
 use crate::api::db::{self, get_collection_by_name, get_document_by_id_in_trx, list_document_ids};
-use crate::api::dto::GrantForDocument;
-use crate::api::grants::hook_or_default_document_grants;
+use crate::api::grants::{document_grant_changes, rebuilt_document_grants};
+use crate::api::hooks::Hooks;
 use crate::api::{ApiContext, ApiErrors};
-use axum::extract::{Path, State};
+use axum::extract::{Path, Query, State};
 use axum::http::StatusCode;
+use axum::Json;
 use axum_macros::debug_handler;
 use jwt_authorizer::JwtClaims;
-use sea_orm::{TransactionError, TransactionTrait};
+use sea_orm::{DatabaseTransaction, TransactionError, TransactionTrait};
+use serde::{Deserialize, Serialize};
 use tracing::{debug, error};
 
+#[derive(Debug, Default, Deserialize, utoipa::IntoParams)]
+#[serde(default, rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub(crate) struct RebuildGrantsParams {
+    /// Compute the changes without storing the rebuilt grants
+    #[param(example = false, default = false)]
+    dry_run: Option<bool>,
+}
+
+/// Changes of a grant rebuild
+#[derive(Debug, Default, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RebuildGrantsSummary {
+    /// The grants were not stored
+    dry_run: bool,
+
+    /// Number of documents in the collection
+    documents: usize,
+
+    /// Number of documents with changed grants
+    documents_affected: usize,
+
+    /// Number of grants added to the documents
+    grants_added: usize,
+
+    /// Number of grants removed from the documents
+    grants_removed: usize,
+}
+
 /// Rebuild grants for a collection
 ///
-/// Iterate over all documents and refresh grants.
+/// Iterate over all documents and refresh grants. Documents of a collection
+/// with a parent reference inherit the current grants of their parent.
+/// Returns the number of changed documents and grants. A dry run computes
+/// the same summary without storing anything, inherited grants are then
+/// taken from the stored grants of the parent documents.
 #[debug_handler]
 #[utoipa::path(
     post,
     path = "/maintenance/{collection_name}/rebuild-grants",
     operation_id = "rebuildGrants",
     params(
+        RebuildGrantsParams,
         (
             "collection_name" = String,
             Path,
@@ -29,7 +69,8 @@ use tracing::{debug, error};
         ),
     ),
     responses(
-        (status = CREATED, description = "Grants rebuilt successfully" ),
+        (status = CREATED, description = "Grants rebuilt successfully", body = RebuildGrantsSummary ),
+        (status = OK, description = "Dry run, grants not changed", body = RebuildGrantsSummary ),
         (status = UNAUTHORIZED, description = "User is not a collection admin" ),
         (status = NOT_FOUND, description = "Collection not found" ),
         (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
@@ -40,7 +81,8 @@ pub(crate) async fn api_rebuild_grants(
     State(ctx): State<ApiContext>,
     JwtClaims(user): JwtClaims<User>,
     Path(collection_name): Path<String>,
-) -> Result<(StatusCode, String), ApiErrors> {
+    Query(params): Query<RebuildGrantsParams>,
+) -> Result<(StatusCode, Json<RebuildGrantsSummary>), ApiErrors> {
     let collection = get_collection_by_name(&ctx.db, &collection_name).await;
     if collection.is_none() {
         debug!("Collection {} not found", collection_name);
@@ -56,41 +98,25 @@ pub(crate) async fn api_rebuild_grants(
         return Err(ApiErrors::PermissionDenied);
     }
 
+    let dry_run = params.dry_run.unwrap_or(false);
+
     ctx.db
-        .transaction::<_, (StatusCode, String), ApiErrors>(|txn| {
+        .transaction::<_, (StatusCode, Json<RebuildGrantsSummary>), ApiErrors>(|txn| {
             Box::pin(async move {
-                let ids = list_document_ids(txn, collection.id).await?;
-                for id in ids {
-                    debug!("Rebuilding grants for document {id} in collection {collection_name}");
-                    let document = get_document_by_id_in_trx(id, txn).await?;
-                    if document.is_none() {
-                        continue;
-                    }
-                    let document = document.unwrap();
-                    let author_id = document.owner;
-
-                    let grants = hook_or_default_document_grants(
-                        &ctx.hooks,
-                        (&collection).into(),
-                        (&document).into(),
-                        ctx.data_service.clone(),
-                        author_id,
-                    )
-                    .await?;
-                    db::replace_grants(
-                        txn,
-                        grants
-                            .into_iter()
-                            .map(|grant| GrantForDocument::new(grant, id))
-                            .collect(),
-                    )
-                    .await
-                    .map_err(|e| {
-                        error!("Failed to replace grants: {:?}", e);
-                        ApiErrors::InternalServerError
-                    })?;
-                }
-                Ok((StatusCode::CREATED, "OK".to_string()))
+                let summary = rebuild_collection_grants(
+                    txn,
+                    &ctx.hooks,
+                    &collection,
+                    ctx.data_service.clone(),
+                    dry_run,
+                )
+                .await?;
+                let status = if dry_run {
+                    StatusCode::OK
+                } else {
+                    StatusCode::CREATED
+                };
+                Ok((status, Json(summary)))
             })
         })
         .await
@@ -99,3 +125,58 @@ pub(crate) async fn api_rebuild_grants(
             TransactionError::Transaction(t) => t,
         })
 }
+
+/// Rebuilds the grants of all documents of the collection within the
+/// transaction, e. g. after the collection became owner access only.
+pub(crate) async fn rebuild_collection_grants(
+    txn: &DatabaseTransaction,
+    hooks: &Hooks,
+    collection: &entity::collection::Model,
+    data_service: Arc<dyn DataService>,
+    dry_run: bool,
+) -> Result<RebuildGrantsSummary, ApiErrors> {
+    let config = CollectionConfig::from(collection);
+    let mut summary = RebuildGrantsSummary {
+        dry_run,
+        ..Default::default()
+    };
+    let ids = list_document_ids(txn, collection.id).await?;
+    for id in ids {
+        debug!(
+            "Rebuilding grants for document {id} in collection {}",
+            collection.name
+        );
+        let document = get_document_by_id_in_trx(id, txn).await?;
+        if document.is_none() {
+            continue;
+        }
+        let document = document.unwrap();
+        summary.documents += 1;
+
+        let grants = rebuilt_document_grants(
+            txn,
+            hooks,
+            collection,
+            &config,
+            &document,
+            data_service.clone(),
+        )
+        .await?;
+        let changes = document_grant_changes(txn, &document, &grants).await?;
+        if changes.is_empty() {
+            continue;
+        }
+        summary.documents_affected += 1;
+        summary.grants_added += changes.added;
+        summary.grants_removed += changes.removed;
+        if dry_run {
+            continue;
+        }
+
+        db::replace_grants(txn, grants).await.map_err(|e| {
+            error!("Failed to replace grants: {:?}", e);
+            ApiErrors::InternalServerError
+        })?;
+    }
+    Ok(summary)
+}
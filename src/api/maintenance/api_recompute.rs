@@ -0,0 +1,183 @@
+use crate::api::auth::User;
+use crate::api::collection_config::CollectionConfig;
+use crate::api::db::{
+    get_collection_by_name, list_matching_document_ids, save_document_events_mails, DbGrantUpdate,
+    DbListDocumentParams, ListDocumentGrants,
+};
+use crate::api::dto;
+use crate::api::list_documents::parse_pfilter;
+use crate::api::parent_fields::inherit_parent_fields;
+use crate::api::search_documents::{SearchFilter, SearchGroup};
+use crate::api::{select_document_for_update, ApiContext, ApiErrors, CATEGORY_DOCUMENT_UPDATES};
+use crate::axumext::extractors::ValidatedQueryParams;
+use axum::extract::{Path, State};
+use axum::Json;
+use axum_macros::debug_handler;
+use jwt_authorizer::JwtClaims;
+use sea_orm::{TransactionError, TransactionTrait};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::{debug, error};
+use validator::Validate;
+
+/// Number of documents that are recomputed in one transaction
+const RECOMPUTE_BATCH_SIZE: usize = 100;
+
+#[derive(Debug, Default, Deserialize, Validate, utoipa::IntoParams)]
+#[serde(default)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct RecomputeParams {
+    /// Only recompute the documents that match the filter, see `pfilter` of
+    /// the list endpoint
+    #[serde(rename = "pfilter")]
+    pfilter: Option<String>,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+#[schema(examples(json!({"documents": 120, "updated": 3})))]
+pub(crate) struct RecomputeResult {
+    /// Number of checked documents
+    documents: usize,
+
+    /// Number of documents with changed fields
+    updated: usize,
+}
+
+/// Recompute derived fields
+///
+/// Iterate over the documents of the collection and update the copies of
+/// parent fields (setting `parentFields`). Documents are processed in batches,
+/// every changed document receives an update event.
+#[debug_handler]
+#[utoipa::path(
+    post,
+    path = "/maintenance/{collection_name}/recompute",
+    operation_id = "recomputeDerivedFields",
+    params(
+        RecomputeParams,
+        (
+            "collection_name" = String,
+            Path,
+            description = "Name of the collection",
+            min_length = 1,
+            max_length = 32,
+            pattern = r"^[a-z][-a-z0-9]*$",
+        ),
+    ),
+    responses(
+        (status = OK, description = "Derived fields recomputed", body = RecomputeResult ),
+        (status = UNAUTHORIZED, description = "User is not a collection admin" ),
+        (status = NOT_FOUND, description = "Collection not found" ),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
+    ),
+    tag = crate::api::TAG_MAINTENANCE,
+)]
+pub(crate) async fn api_recompute(
+    State(ctx): State<ApiContext>,
+    JwtClaims(user): JwtClaims<User>,
+    Path(collection_name): Path<String>,
+    ValidatedQueryParams(params): ValidatedQueryParams<RecomputeParams>,
+) -> Result<Json<RecomputeResult>, ApiErrors> {
+    let collection = get_collection_by_name(&ctx.db, &collection_name)
+        .await
+        .ok_or_else(|| {
+            debug!("Collection {} not found", collection_name);
+            ApiErrors::NotFound(format!("Collection {} not found", collection_name))
+        })?;
+
+    if !user.is_collections_administrator() {
+        debug!("User {} is not a collection admin", user.name_and_sub());
+        return Err(ApiErrors::PermissionDenied);
+    }
+
+    let config = CollectionConfig::from(&collection);
+    let request_filters = parse_pfilter(params.pfilter)?;
+    let filters = if request_filters.is_empty() {
+        SearchFilter::Group(SearchGroup::AndGroup(vec![]))
+    } else {
+        request_filters.into()
+    };
+    let db_params = DbListDocumentParams::builder()
+        .collection(collection.id)
+        .grants(ListDocumentGrants::IgnoredForAdmin)
+        .extra_fields(vec![])
+        .sort_fields(None)
+        .filters(filters)
+        .include_author_id(false)
+        .build();
+    let ids = list_matching_document_ids(&ctx.db, &db_params).await?;
+
+    let mut updated = 0;
+    for batch in ids.chunks(RECOMPUTE_BATCH_SIZE) {
+        let batch = batch.to_vec();
+        let config = config.clone();
+        let user = user.clone();
+        updated += ctx
+            .db
+            .transaction::<_, usize, ApiErrors>(|txn| {
+                Box::pin(async move {
+                    let mut updated = 0;
+                    for id in batch {
+                        let Some(document) = select_document_for_update(id, txn).await? else {
+                            continue;
+                        };
+                        if document.is_deleted() {
+                            continue;
+                        }
+                        let mut after_document: dto::CollectionDocument = (&document).into();
+                        let changed_fields =
+                            inherit_parent_fields(txn, &config, &mut after_document).await?;
+                        if changed_fields.is_empty() {
+                            continue;
+                        }
+                        let event = dto::Event::new(
+                            id,
+                            CATEGORY_DOCUMENT_UPDATES,
+                            json!({
+                                "user": {
+                                    "id": user.subuuid(),
+                                    "name": user.preferred_username(),
+                                },
+                                "recomputed": changed_fields,
+                            }),
+                        );
+                        save_document_events_mails(
+                            txn,
+                            &dto::User::read_from(&user),
+                            Some(after_document),
+                            None,
+                            vec![event],
+                            DbGrantUpdate::Keep,
+                            vec![],
+                        )
+                        .await
+                        .map_err(|e| {
+                            error!("Recompute document error: {:?}", e);
+                            ApiErrors::InternalServerError
+                        })?;
+                        updated += 1;
+                    }
+                    Ok(updated)
+                })
+            })
+            .await
+            .map_err(|err| match err {
+                TransactionError::Connection(c) => Into::<ApiErrors>::into(c),
+                TransactionError::Transaction(t) => t,
+            })?;
+    }
+    debug!(
+        "Recomputed {} of {} documents in collection {}",
+        updated,
+        ids.len(),
+        collection_name
+    );
+    if updated > 0 {
+        ctx.trigger_event_relay();
+    }
+
+    Ok(Json(RecomputeResult {
+        documents: ids.len(),
+        updated,
+    }))
+}
@@ -1 +1,4 @@
+pub(crate) mod api_backup;
+pub(crate) mod api_orphans;
 pub(crate) mod api_rebuild_grants;
+pub(crate) mod api_recompute;
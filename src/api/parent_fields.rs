@@ -0,0 +1,38 @@
+use sea_orm::ConnectionTrait;
+use tracing::debug;
+
+use super::{collection_config::CollectionConfig, db::get_parent_document, dto, ApiErrors};
+
+/// Updates the copies of the parent fields in the document and returns the
+/// names of the changed fields.
+pub(crate) async fn inherit_parent_fields<C: ConnectionTrait>(
+    db: &C,
+    config: &CollectionConfig,
+    document: &mut dto::CollectionDocument,
+) -> Result<Vec<String>, ApiErrors> {
+    let Some(parent) = config.parent() else {
+        return Ok(vec![]);
+    };
+    let parent_document = match parent.parent_id(document.fields()) {
+        Some(parent_id) => get_parent_document(db, parent.collection(), parent_id).await?,
+        None => None,
+    };
+    let changes =
+        config.parent_field_changes(document.fields(), parent_document.as_ref().map(|p| &p.f));
+    let mut changed_fields = vec![];
+    for (field, value) in changes {
+        match value {
+            Some(value) => document.set_field(&field, value),
+            None => document.remove_field(&field),
+        }
+        changed_fields.push(field);
+    }
+    if !changed_fields.is_empty() {
+        debug!(
+            "Parent fields {:?} of document {} changed",
+            changed_fields,
+            document.id()
+        );
+    }
+    Ok(changed_fields)
+}
@@ -0,0 +1,194 @@
+use serde_json::Value;
+use uuid::Uuid;
+
+use super::{
+    auth::User,
+    collection_config::CollectionConfig,
+    dto,
+    hooks::{BoundReadHook, Hooks},
+    search_documents::SearchFilter,
+    ApiErrors, Collection,
+};
+
+/// Prepares the stored fields of a document for the reading user: masks the
+/// masked fields, adds the formatted fields and runs the read hook of the
+/// collection. Every response with document fields passes through it.
+#[derive(Debug, Clone)]
+pub(crate) struct ReadProjection {
+    config: CollectionConfig,
+    /// Return the masked fields in clear text
+    unmasked: bool,
+    read_hook: Option<BoundReadHook>,
+}
+
+impl ReadProjection {
+    /// The projection of the collection for the user.
+    pub(crate) fn new(hooks: &Hooks, collection: &Collection, user: &User) -> Self {
+        Self::from_parts(
+            collection.into(),
+            user.can_read_unmasked(&collection.name),
+            hooks.bound_read_hook(&collection.name, dto::UserWithRoles::read_from(user)),
+        )
+    }
+
+    pub(crate) fn from_parts(
+        config: CollectionConfig,
+        unmasked: bool,
+        read_hook: Option<BoundReadHook>,
+    ) -> Self {
+        Self {
+            config,
+            unmasked,
+            read_hook,
+        }
+    }
+
+    /// The configuration of the collection, e. g. to validate the request.
+    pub(crate) fn config(&self) -> &CollectionConfig {
+        &self.config
+    }
+
+    /// Rejects filters and sort fields on masked fields for users who read
+    /// the masked values, the order and the matching documents would reveal
+    /// the values.
+    pub(crate) fn validate_query(
+        &self,
+        filter: Option<&SearchFilter>,
+        sort_fields: Option<&str>,
+    ) -> Result<(), ApiErrors> {
+        if self.unmasked {
+            return Ok(());
+        }
+        let filter_fields = filter.map(|filter| filter.fields()).unwrap_or_default();
+        let sorted_fields = sort_fields.into_iter().flat_map(|s| s.split(',')).map(|s| {
+            let s = s.strip_suffix('!').unwrap_or(s);
+            s.char_indices().last().map_or(s, |(index, _)| &s[..index])
+        });
+        match filter_fields
+            .into_iter()
+            .chain(sorted_fields)
+            .find(|field| self.config.is_masked(field))
+        {
+            Some(field) => Err(ApiErrors::BadRequestJsonSimpleMsg(format!(
+                "Field {field} is masked"
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Changes the fields of the document for the reading user.
+    pub(crate) fn apply(&self, id: Uuid, f: &mut Value) {
+        self.config.project_fields(f, self.unmasked);
+        if let Some(read_hook) = &self.read_hook {
+            read_hook.apply(id, f);
+        }
+    }
+}
+
+/// A projection that removes the fields for users without the role `HR`.
+#[cfg(test)]
+pub(crate) fn redacting(fields: &[&str]) -> ReadProjection {
+    let hooks = Hooks::new();
+    super::hooks::field_redaction::add_field_redaction_hook(&hooks, "applications", fields, "HR");
+    let user = dto::UserWithRoles::new(Uuid::new_v4(), "reader".to_string(), vec![]);
+    ReadProjection::from_parts(
+        CollectionConfig::default(),
+        false,
+        hooks.bound_read_hook("applications", user),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn it_masks_the_fields_before_the_read_hook() {
+        // Arrange
+        let config = CollectionConfig::from_json(&json!({
+            "maskedFields": {"iban": {"type": "keepLast", "visible": 4}}
+        }));
+        let projection =
+            ReadProjection::from_parts(config, false, redacting(&["salary"]).read_hook);
+        let mut f =
+            json!({"title": "Application", "salary": 52000, "iban": "DE89370400440532013000"});
+
+        // Act
+        projection.apply(Uuid::new_v4(), &mut f);
+
+        // Assert
+        assert_eq!(
+            f,
+            json!({"title": "Application", "iban": "******************3000"})
+        );
+    }
+
+    #[test]
+    fn it_runs_the_read_hook() {
+        // Arrange
+        let projection = redacting(&["salary"]);
+        let mut f = json!({"title": "Application", "salary": 52000});
+
+        // Act
+        projection.apply(Uuid::new_v4(), &mut f);
+
+        // Assert
+        assert_eq!(f, json!({"title": "Application"}));
+    }
+
+    fn masking(unmasked: bool) -> ReadProjection {
+        let config = CollectionConfig::from_json(&json!({
+            "maskedFields": {"iban": {"type": "keepLast", "visible": 4}}
+        }));
+        ReadProjection::from_parts(config, unmasked, None)
+    }
+
+    #[test]
+    fn it_rejects_filters_on_masked_fields() {
+        // Arrange
+        let filter: SearchFilter = serde_json::from_value(json!({
+            "or": [
+                {"f": "title", "o": "eq", "v": "Application"},
+                {"f": "iban.bank", "o": "startswith", "v": "DE89"}
+            ]
+        }))
+        .unwrap();
+
+        // Act
+        let result = masking(false).validate_query(Some(&filter), None);
+
+        // Assert
+        assert!(matches!(
+            result,
+            Err(ApiErrors::BadRequestJsonSimpleMsg(message)) if message == "Field iban.bank is masked"
+        ));
+    }
+
+    #[test]
+    fn it_rejects_sorting_by_masked_fields() {
+        // Act
+        let result = masking(false).validate_query(None, Some("title+,iban-!"));
+
+        // Assert
+        assert!(matches!(
+            result,
+            Err(ApiErrors::BadRequestJsonSimpleMsg(message)) if message == "Field iban is masked"
+        ));
+    }
+
+    #[test]
+    fn it_permits_masked_fields_to_unmasked_readers() {
+        // Arrange
+        let filter: SearchFilter =
+            serde_json::from_value(json!({"f": "iban", "o": "eq", "v": "DE89"})).unwrap();
+
+        // Act
+        let result = masking(true).validate_query(Some(&filter), Some("iban+"));
+
+        // Assert
+        assert!(result.is_ok());
+    }
+}
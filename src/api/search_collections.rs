@@ -0,0 +1,256 @@
+use std::collections::HashMap;
+
+use axum::{extract::State, response::Response};
+use jwt_authorizer::JwtClaims;
+use sea_orm::JsonValue;
+use serde::Deserialize;
+use tracing::warn;
+use validator::Validate;
+
+use crate::{
+    axumext::extractors::{JsonBody, ValidatedQueryParams},
+    models::{CollectionItem, CollectionItemsList},
+};
+
+use super::{
+    auth::User,
+    db::{
+        get_unlocked_collection_by_name, list_multi_collection_documents,
+        DbListMultiCollectionParams, ListDocumentGrants, DELETED_AT_FIELD,
+    },
+    envelope::{list_response, ApiVersion},
+    grants::{hook_or_default_user_grants, GrantCollection},
+    list_documents::{
+        applied_filter, collection_item, explain_filter_permitted, projected_fields,
+        reject_author_id_sort,
+    },
+    read_projection::ReadProjection,
+    search_documents::{
+        Operation, SearchDocumentParams, SearchFilter, SearchFilterFieldOp, SearchGroup,
+    },
+    types::Pagination,
+    ApiContext, ApiErrors,
+};
+
+#[derive(Debug, Default, Deserialize, Validate, utoipa::ToSchema)]
+#[schema(
+    description = "Search filters for multiple collections",
+    examples(json!({"collections": ["orders", "invoices"], "filter": {"f": "customer", "o": "eq", "v": "C-1042"}}))
+)]
+pub(crate) struct SearchCollectionsBody {
+    /// Names of the collections to search
+    #[validate(length(min = 1, max = 10))]
+    #[schema(min_items = 1, max_items = 10)]
+    collections: Vec<String>,
+
+    filter: Option<SearchFilter>,
+}
+
+/// Search items in multiple collections
+///
+/// Search a list of items within several collections. Every item contains the
+/// name of its collection. The user must be permitted to read every collection,
+/// the grants are checked per collection.
+#[utoipa::path(
+    post,
+    path = "/search",
+    operation_id = "searchCollections",
+    params(
+        Pagination,
+        SearchDocumentParams,
+        (
+            "Accept-Version" = Option<String>,
+            Header,
+            description = "Shape of the response: `v1` (default) or `v2` with the data in an envelope `{\"data\": ..., \"meta\": {...}}`",
+        ),
+    ),
+    responses(
+        (status = OK, description = "List of documents", body = CollectionItemsList ),
+        (status = UNAUTHORIZED, description = "User is not a reader of a collection" ),
+        (status = NOT_FOUND, description = "Collection not found" ),
+        (status = BAD_REQUEST, description = "Invalid request" ),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
+    ),
+    request_body(content = SearchCollectionsBody, description = "Collections and search filters", content_type = "application/json"),
+    tag = super::TAG_COLLECTION,
+)
+]
+pub(crate) async fn api_search_collections(
+    State(ctx): State<ApiContext>,
+    ValidatedQueryParams(pagination): ValidatedQueryParams<Pagination>,
+    ValidatedQueryParams(search_params): ValidatedQueryParams<SearchDocumentParams>,
+    version: ApiVersion,
+    JwtClaims(user): JwtClaims<User>,
+    JsonBody(payload): JsonBody<SearchCollectionsBody>,
+) -> Result<Response, ApiErrors> {
+    let pagination = pagination.limited_for(&user);
+    payload.validate().map_err(ApiErrors::from)?;
+
+    let mut collection_names = payload.collections;
+    collection_names.sort();
+    collection_names.dedup();
+
+    let mut collections = vec![];
+    let mut projections: HashMap<String, ReadProjection> = HashMap::new();
+    for collection_name in collection_names {
+        let collection = get_unlocked_collection_by_name(&ctx.db, &collection_name)
+            .await
+            .ok_or_else(|| ApiErrors::NotFound(collection_name.clone()))?;
+
+        let user_is_permitted = user.is_collection_admin(&collection_name)
+            || user.can_access_all_documents(&collection_name)
+            || user.is_collection_reader(&collection_name);
+        if !user_is_permitted {
+            warn!(
+                "User {} is not a reader of collection {}",
+                user.name_and_sub(),
+                collection_name
+            );
+            return Err(ApiErrors::PermissionDenied);
+        }
+        explain_filter_permitted(search_params.explain_filter, &user, &collection_name)?;
+
+        let dto_collection: GrantCollection = (&collection).into();
+        let user_grants = hook_or_default_user_grants(
+            &ctx.hooks,
+            &dto_collection,
+            &user,
+            ctx.data_service.clone(),
+        )
+        .await?;
+        collections.push((collection.id, ListDocumentGrants::Restricted(user_grants)));
+        let projection = ReadProjection::new(&ctx.hooks, &collection, &user);
+        if let Some(filter) = payload.filter.as_ref() {
+            projection.config().validate_text_search(filter)?;
+        }
+        projection
+            .config()
+            .validate_sort(search_params.sort_fields.as_deref())?;
+        reject_author_id_sort(search_params.sort_fields.as_deref())?;
+        projections.insert(collection_name, projection);
+    }
+
+    let applied_filter = search_params
+        .explain_filter
+        .unwrap_or_default()
+        .then(|| applied_filter(payload.filter.as_ref()));
+    let (extra_fields, _) = projected_fields(search_params.extra_fields());
+    let db_params = DbListMultiCollectionParams::builder()
+        .collections(collections)
+        .extra_fields(extra_fields)
+        .sort_fields(search_params.sort_fields.clone())
+        .filters(active_documents_filter(payload.filter))
+        .pagination(pagination.clone())
+        .build();
+
+    let (total, items) = list_multi_collection_documents(&ctx.db, &db_params).await?;
+
+    let list = CollectionItemsList {
+        total_approximate: None,
+        applied_filter,
+        next_cursor: None,
+        has_more: None,
+        limit: pagination.limit(),
+        offset: pagination.offset(),
+        total,
+        items: items
+            .iter()
+            .map(|i| collection_item_of(i, &projections))
+            .collect(),
+    };
+    Ok(list_response(version, list))
+}
+
+/// The item of a search result with the projection of its collection.
+fn collection_item_of(
+    i: &JsonValue,
+    projections: &HashMap<String, ReadProjection>,
+) -> CollectionItem {
+    let mut item = CollectionItem {
+        collection: i["collection"].as_str().map(|s| s.to_string()),
+        ..collection_item(i, false)
+    };
+    if let Some(projection) = item
+        .collection
+        .as_ref()
+        .and_then(|collection| projections.get(collection))
+    {
+        projection.apply(item.id, &mut item.f);
+    }
+    item
+}
+
+fn active_documents_filter(filter: Option<SearchFilter>) -> SearchFilter {
+    let not_deleted = SearchFilter::FieldOp(
+        SearchFilterFieldOp::builder()
+            .field(DELETED_AT_FIELD.to_string())
+            .operation(Operation::Null)
+            .build(),
+    );
+    match filter {
+        Some(filter) => SearchFilter::Group(SearchGroup::AndGroup(vec![not_deleted, filter])),
+        None => not_deleted,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+    use crate::api::read_projection;
+
+    #[test]
+    fn it_requires_a_collection() {
+        // Arrange
+        let body: SearchCollectionsBody =
+            serde_json::from_value(json!({"collections": []})).unwrap();
+
+        // Act
+        let result = body.validate();
+
+        // Assert
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn it_projects_the_items_with_the_read_hook_of_their_collection() {
+        // Arrange
+        let projections = HashMap::from([(
+            "applications".to_string(),
+            read_projection::redacting(&["salary"]),
+        )]);
+        let row = json!({
+            "id": "3a8ed6a6-9cd7-4a4e-a0b5-42bfbd8e0c01",
+            "collection": "applications",
+            "f": {"title": "Application", "salary": 52000}
+        });
+
+        // Act
+        let item = collection_item_of(&row, &projections);
+
+        // Assert
+        assert_eq!(item.collection.as_deref(), Some("applications"));
+        assert_eq!(item.f, json!({"title": "Application"}));
+    }
+
+    #[test]
+    fn it_excludes_deleted_documents() {
+        // Arrange
+        let filter: SearchFilter =
+            serde_json::from_value(json!({"f": "title", "o": "eq", "v": "Circle"})).unwrap();
+
+        // Act
+        let f = active_documents_filter(Some(filter));
+
+        // Assert
+        assert_eq!(
+            serde_json::to_value(f).unwrap(),
+            json!({"and": [
+                {"f": "folivafy_deleted_at", "o": "null"},
+                {"f": "title", "o": "eq", "v": "Circle"}
+            ]})
+        );
+    }
+}
@@ -1,24 +1,32 @@
 use axum::{
     extract::{Path, State},
-    Json,
+    response::Response,
 };
+use chrono::SecondsFormat;
 use jwt_authorizer::JwtClaims;
+use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use tracing::warn;
 use typed_builder::TypedBuilder;
 use validator::Validate;
 
-use crate::{axumext::extractors::ValidatedQueryParams, models::CollectionItemsList};
+use crate::{
+    axumext::extractors::{JsonBody, ValidatedQueryParams},
+    models::CollectionItemsList,
+};
 
 use super::{
     auth::User,
     db::{get_unlocked_collection_by_name, FieldFilter, ListDocumentGrants},
+    envelope::ApiVersion,
     grants::{hook_or_default_user_grants, GrantCollection},
     list_documents::{
-        generic_list_documents, DeletedDocuments, GenericListDocumentsParams, RE_EXTRA_FIELDS,
+        count_mode, explain_filter_permitted, generic_list_documents, list_or_first_response,
+        DeletedDocuments, FirstMatchParams, GenericListDocumentsParams, RE_EXTRA_FIELDS,
         RE_SORT_FIELDS,
     },
+    read_projection::ReadProjection,
     types::Pagination,
     ApiContext, ApiErrors,
 };
@@ -34,7 +42,19 @@ pub(crate) enum OperationWithValue {
     Ge,
     StartsWith,
     ContainsText,
+    /// Case-sensitive `startswith`
+    StartsWithCs,
+    /// Case-sensitive `containstext`
+    ContainsTextCs,
     In,
+    /// The field is none of the values
+    NotIn,
+    /// The field is an array that contains the value, e. g. a tag
+    ArrayContains,
+    /// The field matches the regular expression
+    Regex,
+    /// Case-insensitive `regex`
+    RegexI,
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize, Clone, TypedBuilder, utoipa::ToSchema)]
@@ -108,8 +128,29 @@ pub(crate) enum SearchGroup {
     OrGroup(Vec<SearchFilter>),
 }
 
+/// Operators that can be used in the field `o` of a search filter
+pub(crate) const SEARCH_OPERATORS: [&str; 17] = [
+    "eq",
+    "ne",
+    "lt",
+    "le",
+    "gt",
+    "ge",
+    "startswith",
+    "containstext",
+    "startswithcs",
+    "containstextcs",
+    "in",
+    "notin",
+    "arraycontains",
+    "regex",
+    "regexi",
+    "null",
+    "notnull",
+];
+
 #[derive(Debug, PartialEq, Serialize, Deserialize, Clone, utoipa::ToSchema)]
-#[serde(untagged)]
+#[serde(untagged, try_from = "Value")]
 #[schema(description = "A search filter")]
 pub(crate) enum SearchFilter {
     FieldOpValue(SearchFilterFieldOpValue),
@@ -117,6 +158,229 @@ pub(crate) enum SearchFilter {
     Group(SearchGroup),
 }
 
+impl SearchFilter {
+    /// Returns the fields that are compared with a text operator
+    /// (`startswith`, `containstext`, their case-sensitive variants and
+    /// `regex`), including nested filter groups.
+    pub(crate) fn text_search_fields(&self) -> Vec<&str> {
+        match self {
+            SearchFilter::FieldOpValue(f) => match f.operation {
+                OperationWithValue::StartsWith
+                | OperationWithValue::ContainsText
+                | OperationWithValue::StartsWithCs
+                | OperationWithValue::ContainsTextCs
+                | OperationWithValue::Regex
+                | OperationWithValue::RegexI => {
+                    vec![f.field()]
+                }
+                _ => vec![],
+            },
+            SearchFilter::FieldOp(_) => vec![],
+            SearchFilter::Group(SearchGroup::AndGroup(filters))
+            | SearchFilter::Group(SearchGroup::OrGroup(filters)) => filters
+                .iter()
+                .flat_map(|filter| filter.text_search_fields())
+                .collect(),
+        }
+    }
+
+    /// Returns the fields of all conditions, including nested filter groups.
+    pub(crate) fn fields(&self) -> Vec<&str> {
+        match self {
+            SearchFilter::FieldOpValue(f) => vec![f.field()],
+            SearchFilter::FieldOp(f) => vec![f.field()],
+            SearchFilter::Group(SearchGroup::AndGroup(filters))
+            | SearchFilter::Group(SearchGroup::OrGroup(filters)) => {
+                filters.iter().flat_map(|filter| filter.fields()).collect()
+            }
+        }
+    }
+}
+
+/// Deserialization target of [`SearchFilter`] after the operator has been
+/// checked.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum UncheckedSearchFilter {
+    FieldOpValue(SearchFilterFieldOpValue),
+    FieldOp(SearchFilterFieldOp),
+    Group(SearchGroup),
+}
+
+/// Default maximum nesting depth of `and` and `or` groups
+const DEFAULT_MAX_FILTER_DEPTH: usize = 8;
+
+lazy_static! {
+    /// Reads the maximum nesting depth from `FOLIVAFY_MAX_FILTER_DEPTH`.
+    static ref MAX_FILTER_DEPTH: usize = std::env::var("FOLIVAFY_MAX_FILTER_DEPTH")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(DEFAULT_MAX_FILTER_DEPTH);
+}
+
+/// Checks that `and` and `or` groups are nested at most `max_depth` levels
+/// deep. The check stops at the limit, so it cannot exhaust the stack.
+fn check_depth(value: &Value, max_depth: usize) -> Result<(), String> {
+    fn nested_within(value: &Value, remaining: usize) -> bool {
+        ["and", "or"]
+            .iter()
+            .filter_map(|group| value.get(group).and_then(|v| v.as_array()))
+            .all(|filters| {
+                remaining > 0
+                    && filters
+                        .iter()
+                        .all(|filter| nested_within(filter, remaining - 1))
+            })
+    }
+    if nested_within(value, max_depth) {
+        Ok(())
+    } else {
+        Err(format!(
+            "Filter groups are nested deeper than {max_depth} levels"
+        ))
+    }
+}
+
+/// Checks the operators of the filter and of all nested filter groups.
+fn check_operators(value: &Value) -> Result<(), String> {
+    if let Some(operator) = value.get("o") {
+        let is_known = operator
+            .as_str()
+            .is_some_and(|o| SEARCH_OPERATORS.contains(&o));
+        if !is_known {
+            return Err(format!(
+                "Unknown filter operator {}, valid operators are: {}",
+                operator,
+                SEARCH_OPERATORS.join(", ")
+            ));
+        }
+        if matches!(operator.as_str(), Some("regex" | "regexi")) {
+            check_pattern(value.get("v"))?;
+        }
+    }
+    for group in ["and", "or"] {
+        if let Some(filters) = value.get(group).and_then(|v| v.as_array()) {
+            filters.iter().try_for_each(check_operators)?;
+        }
+    }
+    Ok(())
+}
+
+/// Maximum length of a `regex` pattern in bytes
+const MAX_PATTERN_LENGTH: usize = 1024;
+
+/// Checks the pattern of a `regex` filter before it is sent to the database.
+/// Postgres runs advanced regular expressions (ARE), the check accepts their
+/// escapes, bracket expressions and non-capturing groups. Backreferences and
+/// lookarounds are rejected, they allow catastrophic backtracking. Remaining
+/// syntax errors are reported by the database.
+fn check_pattern(pattern: Option<&Value>) -> Result<(), String> {
+    let Some(pattern) = pattern.and_then(|v| v.as_str()) else {
+        return Ok(());
+    };
+    check_are_syntax(pattern).map_err(|e| format!("Invalid regex {pattern:?}: {e}"))
+}
+
+fn check_are_syntax(pattern: &str) -> Result<(), String> {
+    if pattern.len() > MAX_PATTERN_LENGTH {
+        return Err(format!("longer than {MAX_PATTERN_LENGTH} bytes"));
+    }
+    let mut chars = pattern.chars().peekable();
+    let mut open_groups = 0usize;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => check_escape(chars.next())?,
+            '[' => skip_bracket_expression(&mut chars)?,
+            '(' => {
+                if chars.next_if_eq(&'?').is_some() {
+                    match chars.next() {
+                        Some(':') => {}
+                        Some('=' | '!' | '<') => return Err("lookarounds are not supported".into()),
+                        _ => return Err("only non-capturing groups (?:...) are supported".into()),
+                    }
+                }
+                open_groups += 1;
+            }
+            ')' => {
+                open_groups = open_groups
+                    .checked_sub(1)
+                    .ok_or_else(|| "unmatched )".to_string())?
+            }
+            _ => {}
+        }
+    }
+    if open_groups > 0 {
+        return Err("unclosed group".into());
+    }
+    Ok(())
+}
+
+/// Checks the character after a backslash outside of a bracket expression.
+fn check_escape(escape: Option<char>) -> Result<(), String> {
+    match escape {
+        None => Err("trailing backslash".into()),
+        Some('b') => Err(r"\b is a backspace, use \y for a word boundary".into()),
+        Some('B') => {
+            Err(r"\B is a backslash, use \Y for a position that is not a word boundary".into())
+        }
+        Some('1'..='9') => Err("backreferences are not supported".into()),
+        // Classes, constraints and character entries of ARE
+        Some(
+            'd' | 'D' | 's' | 'S' | 'w' | 'W' | 'y' | 'Y' | 'm' | 'M' | 'A' | 'Z' | 'a' | 'c' | 'e'
+            | 'f' | 'n' | 'r' | 't' | 'v' | 'u' | 'U' | 'x' | '0',
+        ) => Ok(()),
+        Some(c) if c.is_ascii_alphanumeric() => Err(format!(r"unknown escape \{c}")),
+        Some(_) => Ok(()),
+    }
+}
+
+/// Skips a bracket expression after its `[`, e. g. `[^]a-z[:digit:]]`.
+fn skip_bracket_expression(
+    chars: &mut std::iter::Peekable<std::str::Chars<'_>>,
+) -> Result<(), String> {
+    chars.next_if_eq(&'^');
+    // A `]` at the start is part of the list
+    chars.next_if_eq(&']');
+    while let Some(c) = chars.next() {
+        match c {
+            ']' => return Ok(()),
+            '\\' => {
+                chars.next();
+            }
+            '[' => {
+                if let Some(delimiter) = chars.next_if(|c| matches!(c, ':' | '.' | '=')) {
+                    let mut previous = None;
+                    for c in chars.by_ref() {
+                        if c == ']' && previous == Some(delimiter) {
+                            break;
+                        }
+                        previous = Some(c);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Err("unclosed [".into())
+}
+
+impl TryFrom<Value> for SearchFilter {
+    type Error = String;
+
+    /// Rejects unknown operators with a message that lists the valid ones,
+    /// the untagged enum would only report that no variant matches.
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        check_depth(&value, *MAX_FILTER_DEPTH)?;
+        check_operators(&value)?;
+        let filter = serde_json::from_value(value).map_err(|e| e.to_string())?;
+        Ok(match filter {
+            UncheckedSearchFilter::FieldOpValue(f) => SearchFilter::FieldOpValue(f),
+            UncheckedSearchFilter::FieldOp(f) => SearchFilter::FieldOp(f),
+            UncheckedSearchFilter::Group(g) => SearchFilter::Group(g),
+        })
+    }
+}
+
 impl From<&FieldFilter> for SearchFilter {
     fn from(value: &FieldFilter) -> Self {
         match value {
@@ -148,6 +412,20 @@ impl From<&FieldFilter> for SearchFilter {
                     value: Value::Array(values.iter().cloned().map(Value::String).collect()),
                 })
             }
+            FieldFilter::FieldNotEqual { field_name, value } => {
+                SearchFilter::FieldOpValue(SearchFilterFieldOpValue {
+                    field: field_name.clone(),
+                    operation: OperationWithValue::Ne,
+                    value: Value::String(value.clone()),
+                })
+            }
+            FieldFilter::FieldValueNotInMatch { field_name, values } => {
+                SearchFilter::FieldOpValue(SearchFilterFieldOpValue {
+                    field: field_name.clone(),
+                    operation: OperationWithValue::NotIn,
+                    value: Value::Array(values.iter().cloned().map(Value::String).collect()),
+                })
+            }
             FieldFilter::FieldIsNull { field_name } => SearchFilter::FieldOp(SearchFilterFieldOp {
                 field: field_name.clone(),
                 operation: Operation::Null,
@@ -165,6 +443,26 @@ impl From<&FieldFilter> for SearchFilter {
                     value: Value::String(value.format("%Y-%m-%d").to_string()),
                 })
             }
+            FieldFilter::TimestampFieldLessThan { field_name, value } => {
+                SearchFilter::FieldOpValue(SearchFilterFieldOpValue {
+                    field: field_name.clone(),
+                    operation: OperationWithValue::Lt,
+                    value: Value::String(value.to_rfc3339_opts(SecondsFormat::AutoSi, true)),
+                })
+            }
+            FieldFilter::TimestampFieldGreaterThan { field_name, value } => {
+                SearchFilter::FieldOpValue(SearchFilterFieldOpValue {
+                    field: field_name.clone(),
+                    operation: OperationWithValue::Gt,
+                    value: Value::String(value.to_rfc3339_opts(SecondsFormat::AutoSi, true)),
+                })
+            }
+            FieldFilter::AnyOf(filters) => SearchFilter::Group(SearchGroup::OrGroup(
+                filters.iter().map(|filter| filter.into()).collect(),
+            )),
+            FieldFilter::AllOf(filters) => SearchFilter::Group(SearchGroup::AndGroup(
+                filters.iter().map(|filter| filter.into()).collect(),
+            )),
         }
     }
 }
@@ -182,11 +480,12 @@ impl From<Vec<FieldFilter>> for SearchFilter {
 #[into_params(parameter_in = Query)]
 pub(crate) struct SearchDocumentParams {
     /// A comma separated list of document fields that should be contained in the response
+    /// or `*` for all fields
     #[validate(regex(path= *RE_EXTRA_FIELDS))]
     #[serde(rename = "extraFields")]
     #[param(
         example = "price,length",
-        pattern = r#"^[a-zA-Z0-9_]+(,[a-zA-Z0-9_]+)*$"#
+        pattern = r#"^(\*|[a-zA-Z0-9_]+(,[a-zA-Z0-9_]+)*)$"#
     )]
     pub(crate) extra_fields: Option<String>,
 
@@ -198,6 +497,34 @@ pub(crate) struct SearchDocumentParams {
         pattern = r#"^[a-zA-Z0-9_]+(,[a-zA-Z0-9_]+)*$"#
     )]
     pub(crate) sort_fields: Option<String>,
+
+    /// Only return the `title` field of the documents, `extraFields` is ignored
+    #[serde(rename = "titleOnly")]
+    #[param(example = true, default = false)]
+    pub(crate) title_only: Option<bool>,
+
+    /// Include the parsed filter in the response (`appliedFilter`)
+    ///
+    /// Only permitted for collection administrators.
+    #[serde(rename = "explainFilter")]
+    #[param(example = true, default = false)]
+    pub(crate) explain_filter: Option<bool>,
+
+    /// Count the matching documents, see `withTotal` of the list endpoint
+    #[serde(rename = "withTotal")]
+    #[param(example = false, default = true)]
+    pub(crate) with_total: Option<bool>,
+}
+
+impl SearchDocumentParams {
+    /// The fields that are requested for the response
+    pub(crate) fn extra_fields(&self) -> Option<String> {
+        if self.title_only.unwrap_or_default() {
+            Some("title".to_string())
+        } else {
+            self.extra_fields.clone()
+        }
+    }
 }
 
 #[derive(Debug, Default, Deserialize, Validate, utoipa::ToSchema)]
@@ -216,6 +543,7 @@ pub(crate) struct SearchDocumentsBody {
     params(
         Pagination,
         SearchDocumentParams,
+        FirstMatchParams,
         (
             "collection_name" = String,
             Path,
@@ -224,11 +552,16 @@ pub(crate) struct SearchDocumentsBody {
             max_length = 32,
             pattern = r"^[a-z][-a-z0-9]*$",
         ),
+        (
+            "Accept-Version" = Option<String>,
+            Header,
+            description = "Shape of the response: `v1` (default) or `v2` with the data in an envelope `{\"data\": ..., \"meta\": {...}}`",
+        ),
     ),
     responses(
-        (status = OK, description = "List of documents", body = CollectionItemsList ),
+        (status = OK, description = "List of documents, or a single `CollectionItem` with `first=true`", body = CollectionItemsList ),
         (status = UNAUTHORIZED, description = "User is not a collection reader" ),
-        (status = NOT_FOUND, description = "Collection not found" ),
+        (status = NOT_FOUND, description = "Collection not found or no document matches with `first=true`" ),
         (status = BAD_REQUEST, description = "Invalid request" ),
         (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
     ),
@@ -236,14 +569,18 @@ pub(crate) struct SearchDocumentsBody {
     tag = super::TAG_COLLECTION,
 )
 ]
+#[allow(clippy::too_many_arguments)]
 pub(crate) async fn api_search_documents(
     State(ctx): State<ApiContext>,
     ValidatedQueryParams(pagination): ValidatedQueryParams<Pagination>,
     ValidatedQueryParams(search_params): ValidatedQueryParams<SearchDocumentParams>,
+    ValidatedQueryParams(first_params): ValidatedQueryParams<FirstMatchParams>,
+    version: ApiVersion,
     Path(collection_name): Path<String>,
     JwtClaims(user): JwtClaims<User>,
-    Json(payload): Json<SearchDocumentsBody>,
-) -> Result<Json<CollectionItemsList>, ApiErrors> {
+    JsonBody(payload): JsonBody<SearchDocumentsBody>,
+) -> Result<Response, ApiErrors> {
+    let pagination = pagination.limited_for(&user);
     let collection = get_unlocked_collection_by_name(&ctx.db, &collection_name)
         .await
         .ok_or_else(|| ApiErrors::NotFound(collection_name.clone()))?;
@@ -261,21 +598,29 @@ pub(crate) async fn api_search_documents(
         hook_or_default_user_grants(&ctx.hooks, &dto_collection, &user, ctx.data_service.clone())
             .await?;
 
+    let explain_filter =
+        explain_filter_permitted(search_params.explain_filter, &user, &collection_name)?;
     let grants = ListDocumentGrants::Restricted(user_grants);
 
-    generic_list_documents(
+    let list = generic_list_documents(
         &ctx.db,
         collection.id,
         DeletedDocuments::Exclude,
         GenericListDocumentsParams::builder()
             .sort_fields(search_params.sort_fields.clone())
-            .extra_fields(search_params.extra_fields.clone())
+            .extra_fields(search_params.extra_fields())
             .filter(payload.filter)
+            .projection(ReadProjection::new(&ctx.hooks, &collection, &user))
+            .first(first_params.first())
+            .explain_filter(explain_filter)
+            .count(count_mode(None, search_params.with_total))
             .build(),
         grants,
         pagination,
     )
-    .await
+    .await?;
+
+    list_or_first_response(list.0, first_params.first(), version)
 }
 
 #[cfg(test)]
@@ -283,6 +628,7 @@ mod tests {
     use pretty_assertions::assert_eq;
 
     use super::*;
+    use crate::api::list_documents::projected_fields;
 
     #[test]
     fn it_works_for_fieldop() {
@@ -431,4 +777,253 @@ mod tests {
             )]))
         )
     }
+
+    #[test]
+    fn it_projects_the_same_fields_as_list() {
+        // Arrange
+        let list_params = crate::api::list_documents::ListDocumentParams {
+            extra_fields: Some("price,author_id,length".to_string()),
+            ..Default::default()
+        };
+        let search_params = SearchDocumentParams {
+            extra_fields: Some("price,author_id,length".to_string()),
+            ..Default::default()
+        };
+
+        // Act
+        let list_projection = projected_fields(list_params.extra_fields);
+        let search_projection = projected_fields(search_params.extra_fields());
+
+        // Assert
+        assert_eq!(list_projection, search_projection);
+        assert_eq!(
+            search_projection,
+            (
+                vec![
+                    "price".to_string(),
+                    "length".to_string(),
+                    "title".to_string()
+                ],
+                true
+            )
+        );
+    }
+
+    #[test]
+    fn it_projects_only_the_title() {
+        // Arrange
+        let search_params = SearchDocumentParams {
+            extra_fields: Some("price,author_id".to_string()),
+            title_only: Some(true),
+            ..Default::default()
+        };
+
+        // Act
+        let projection = projected_fields(search_params.extra_fields());
+
+        // Assert
+        assert_eq!(projection, (vec!["title".to_string()], false));
+    }
+
+    #[test]
+    fn it_projects_all_fields_for_the_wildcard() {
+        // Arrange
+        let all_fields = SearchDocumentParams {
+            extra_fields: Some("*".to_string()),
+            ..Default::default()
+        };
+        let mixed_fields = SearchDocumentParams {
+            extra_fields: Some("*,price".to_string()),
+            ..Default::default()
+        };
+
+        // Act
+        let projection = projected_fields(all_fields.extra_fields());
+
+        // Assert
+        assert_eq!(projection, (vec!["*".to_string()], false));
+        assert!(all_fields.validate().is_ok());
+        assert!(mixed_fields.validate().is_err());
+    }
+
+    #[test]
+    fn it_accepts_all_known_operators() {
+        for operator in SEARCH_OPERATORS {
+            // Arrange
+            let s = format!(r#"{{"f":"my_name","o":"{operator}","v":["my_value"]}}"#);
+            let s_without_value = format!(r#"{{"f":"my_name","o":"{operator}"}}"#);
+
+            // Act
+            let with_value = serde_json::from_str::<SearchFilter>(&s);
+            let without_value = serde_json::from_str::<SearchFilter>(&s_without_value);
+
+            // Assert
+            assert!(
+                with_value.is_ok() || without_value.is_ok(),
+                "Operator {operator} is not accepted"
+            );
+        }
+    }
+
+    #[test]
+    fn it_parses_the_case_sensitive_text_operators() {
+        // Arrange
+        let s = r#"{"and":[{"f":"sku","o":"startswithcs","v":"AB"},{"f":"sku","o":"containstextcs","v":"x-1"}]}"#;
+
+        // Act
+        let filter = serde_json::from_str::<SearchFilter>(s).unwrap();
+
+        // Assert
+        assert_eq!(
+            filter,
+            SearchFilter::Group(SearchGroup::AndGroup(vec![
+                SearchFilter::FieldOpValue(SearchFilterFieldOpValue {
+                    field: "sku".to_string(),
+                    operation: OperationWithValue::StartsWithCs,
+                    value: Value::String("AB".to_string()),
+                }),
+                SearchFilter::FieldOpValue(SearchFilterFieldOpValue {
+                    field: "sku".to_string(),
+                    operation: OperationWithValue::ContainsTextCs,
+                    value: Value::String("x-1".to_string()),
+                }),
+            ]))
+        );
+        assert_eq!(filter.text_search_fields(), vec!["sku", "sku"]);
+    }
+
+    #[test]
+    fn it_rejects_unknown_operators() {
+        // Arrange
+        let s = r#"{"and":[{"f":"my_name","o":"eq","v":"my_value"},{"f":"other","o":"equals","v":"x"}]}"#;
+
+        // Act
+        let err = serde_json::from_str::<SearchFilter>(s).unwrap_err();
+
+        // Assert
+        assert!(
+            err.to_string().starts_with(
+                r#"Unknown filter operator "equals", valid operators are: eq, ne, lt, le, gt, ge, startswith, containstext, startswithcs, containstextcs, in, notin, arraycontains, regex, regexi, null, notnull"#
+            ),
+            "Unexpected error: {err}"
+        );
+    }
+
+    #[test]
+    fn it_rejects_operators_that_are_not_strings() {
+        // Arrange
+        let s = r#"{"f":"my_name","o":1,"v":"my_value"}"#;
+
+        // Act
+        let err = serde_json::from_str::<SearchFilter>(s).unwrap_err();
+
+        // Assert
+        assert!(
+            err.to_string().starts_with("Unknown filter operator 1,"),
+            "Unexpected error: {err}"
+        );
+    }
+
+    #[test]
+    fn it_parses_a_regex_filter() {
+        // Arrange
+        let s = r#"{"f":"title","o":"regexi","v":"\\y\\d{3}-\\d{4}\\y"}"#;
+
+        // Act
+        let filter = serde_json::from_str::<SearchFilter>(s).unwrap();
+
+        // Assert
+        assert_eq!(
+            filter,
+            SearchFilter::FieldOpValue(SearchFilterFieldOpValue {
+                field: "title".to_string(),
+                operation: OperationWithValue::RegexI,
+                value: Value::String(r"\y\d{3}-\d{4}\y".to_string()),
+            })
+        );
+        assert_eq!(filter.text_search_fields(), vec!["title"]);
+    }
+
+    #[test]
+    fn it_rejects_invalid_regex_patterns() {
+        for pattern in [
+            r#""(unclosed""#,
+            r#""[a-z""#,
+            r#""(a)\\1""#,
+            r#""(?=a)b""#,
+            r#""(?P<word>a)""#,
+            r#""\\bword\\b""#,
+            r#""\\p{L}+""#,
+        ] {
+            // Arrange
+            let s = format!(r#"{{"and":[{{"f":"title","o":"regex","v":{pattern}}}]}}"#);
+
+            // Act
+            let err = serde_json::from_str::<SearchFilter>(&s).unwrap_err();
+
+            // Assert
+            assert!(
+                err.to_string().starts_with("Invalid regex"),
+                "Unexpected error for {pattern}: {err}"
+            );
+        }
+    }
+
+    #[test]
+    fn it_accepts_postgres_regex_patterns() {
+        for pattern in [
+            r"\ylabel\y",
+            r"\m\w+\M",
+            r"[[:alpha:]]+[]\]]",
+            r"^(?:red|green)-[^-]+$",
+            r"\(1\)",
+        ] {
+            // Act
+            let result = check_pattern(Some(&Value::String(pattern.to_string())));
+
+            // Assert
+            assert_eq!(result, Ok(()), "Pattern {pattern}");
+        }
+    }
+
+    fn nested_filter(depth: usize) -> Value {
+        (0..depth).fold(
+            serde_json::json!({"f": "title", "o": "eq", "v": "Circle"}),
+            |filter, level| {
+                let group = if level % 2 == 0 { "and" } else { "or" };
+                serde_json::json!({ group: [filter] })
+            },
+        )
+    }
+
+    #[test]
+    fn it_accepts_filters_nested_up_to_the_maximum_depth() {
+        // Arrange
+        let filter = nested_filter(DEFAULT_MAX_FILTER_DEPTH);
+
+        // Act
+        let result = check_depth(&filter, DEFAULT_MAX_FILTER_DEPTH);
+
+        // Assert
+        assert_eq!(result, Ok(()));
+        assert_eq!(check_depth(&nested_filter(0), 0), Ok(()));
+    }
+
+    #[test]
+    fn it_rejects_filters_nested_past_the_maximum_depth() {
+        // Arrange
+        let filter = nested_filter(DEFAULT_MAX_FILTER_DEPTH + 1);
+        let deep_filter = nested_filter(100);
+
+        // Act
+        let err = serde_json::from_value::<SearchFilter>(filter).unwrap_err();
+        let deep_result = check_depth(&deep_filter, DEFAULT_MAX_FILTER_DEPTH);
+
+        // Assert
+        assert_eq!(
+            err.to_string(),
+            "Filter groups are nested deeper than 8 levels"
+        );
+        assert!(deep_result.is_err());
+    }
 }
@@ -0,0 +1,47 @@
+use axum::Json;
+use chrono::Utc;
+
+use crate::models::ServerTime;
+
+/// Get server time
+///
+/// Returns the current UTC timestamp of the server and its timezone (from the
+/// environment variable `TZ`), if set. Clients can compare it with their own
+/// clock before sending timestamps or date filters. No authentication is
+/// required.
+#[utoipa::path(
+    get,
+    path = "/time",
+    operation_id = "getServerTime",
+    responses(
+        (status = OK, description = "Server time", body = ServerTime ),
+    ),
+    tag = super::TAG_ADMINISTRATION,
+)]
+pub(crate) async fn api_server_time() -> Json<ServerTime> {
+    Json(ServerTime {
+        timezone: std::env::var("TZ").ok().filter(|tz| !tz.is_empty()),
+        ..ServerTime::new(Utc::now())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn it_returns_an_rfc3339_timestamp() {
+        // Act
+        let Json(time) = api_server_time().await;
+
+        // Assert
+        let value = serde_json::to_value(time).unwrap();
+        let now = value["now"].as_str().unwrap();
+        assert!(
+            DateTime::parse_from_rfc3339(now).is_ok(),
+            "{now} is not a RFC 3339 timestamp"
+        );
+    }
+}
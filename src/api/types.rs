@@ -1,36 +1,273 @@
-use serde::Deserialize;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
 use validator::Validate;
 
+use super::auth::User;
+
+/// Maximum number of items on a page for regular users
+pub(crate) const DEFAULT_MAX_LIMIT: u16 = 250;
+
+/// Number of items on a page when no limit is requested
+const DEFAULT_LIMIT: u16 = 50;
+
+lazy_static! {
+    static ref PAGE_LIMITS: PageLimits = PageLimits::from_env();
+}
+
 #[derive(Debug, Clone, Deserialize, Validate, utoipa::IntoParams)]
 #[serde(default)]
 #[into_params(parameter_in = Query)]
 pub(crate) struct Pagination {
-    #[validate(range(min = 1, max = 250))]
-    #[param(minimum = 1, maximum = 250, example = 50, default = 50)]
-    limit: u8,
+    /// Maximum number of items. Larger values are reduced to the maximum page
+    /// size (250 by default, higher for users with a large page role).
+    #[validate(range(min = 1))]
+    #[param(minimum = 1, example = 50, default = 50)]
+    limit: u16,
     #[param(example = 0, default = 0)]
     offset: u32,
 }
 
 impl Pagination {
-    pub(crate) fn new(limit: u8, offset: u32) -> Self {
+    pub(crate) fn new(limit: u16, offset: u32) -> Self {
         Self { limit, offset }
     }
 
-    pub(crate) fn limit(&self) -> u8 {
+    pub(crate) fn limit(&self) -> u16 {
         self.limit
     }
 
     pub(crate) fn offset(&self) -> u32 {
         self.offset
     }
+
+    /// Reduces the limit to the maximum number of items of the collection.
+    pub(crate) fn clamped(self, max_items: Option<u16>) -> Self {
+        match max_items {
+            Some(max_items) if self.limit > max_items => Self {
+                limit: max_items,
+                ..self
+            },
+            _ => self,
+        }
+    }
+
+    /// Reduces the limit to the maximum page size of the user.
+    pub(crate) fn limited_for(self, user: &User) -> Self {
+        self.clamped(Some(PAGE_LIMITS.max_limit(user)))
+    }
 }
 
 impl Default for Pagination {
     fn default() -> Self {
         Self {
-            limit: 50,
+            limit: PAGE_LIMITS.default_limit(),
             offset: 0,
         }
     }
 }
+
+/// Default and maximum page sizes depending on the roles of the user
+#[derive(Debug, Clone)]
+struct PageLimits {
+    default_limit: u16,
+    max_limit: u16,
+    large_page_roles: Vec<String>,
+    large_page_max_limit: u16,
+}
+
+/// Maximum page sizes that apply to every collection
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PageLimitSettings {
+    /// Number of items on a page when no limit is requested
+    default_limit: u16,
+
+    /// Maximum number of items on a page for regular users
+    max_limit: u16,
+
+    /// Maximum number of items on a page for users with a large page role
+    large_page_limit: u16,
+
+    /// Roles that permit large pages
+    large_page_roles: Vec<String>,
+}
+
+/// Returns the configured default and maximum page sizes.
+pub(crate) fn page_limit_settings() -> PageLimitSettings {
+    PAGE_LIMITS.settings()
+}
+
+impl PageLimits {
+    /// Reads the page size from `FOLIVAFY_DEFAULT_PAGE_SIZE` (default: 50), the
+    /// maximum page size from `FOLIVAFY_MAX_PAGE_SIZE` (default: 250), the comma
+    /// separated list of roles from `FOLIVAFY_LARGE_PAGE_ROLES` (default:
+    /// `A_FOLIVAFY_COLLECTION_EDITOR`) and their maximum page size from
+    /// `FOLIVAFY_LARGE_PAGE_LIMIT` (default: 1000).
+    fn from_env() -> Self {
+        let page_size = |name: &str, default: u16| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.trim().parse().ok())
+                .filter(|size| *size > 0)
+                .unwrap_or(default)
+        };
+        let default_limit = page_size("FOLIVAFY_DEFAULT_PAGE_SIZE", DEFAULT_LIMIT);
+        let max_limit = page_size("FOLIVAFY_MAX_PAGE_SIZE", DEFAULT_MAX_LIMIT);
+        let large_page_roles = std::env::var("FOLIVAFY_LARGE_PAGE_ROLES")
+            .unwrap_or_else(|_| "A_FOLIVAFY_COLLECTION_EDITOR".to_string())
+            .split(',')
+            .map(|role| role.trim().to_string())
+            .filter(|role| !role.is_empty())
+            .collect();
+        let large_page_max_limit = std::env::var("FOLIVAFY_LARGE_PAGE_LIMIT")
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(1000);
+        Self {
+            default_limit,
+            max_limit,
+            large_page_roles,
+            large_page_max_limit,
+        }
+    }
+
+    fn settings(&self) -> PageLimitSettings {
+        PageLimitSettings {
+            default_limit: self.default_limit(),
+            max_limit: self.max_limit,
+            large_page_limit: self.large_page_limit(),
+            large_page_roles: self.large_page_roles.clone(),
+        }
+    }
+
+    /// The default page size never exceeds the maximum page size.
+    fn default_limit(&self) -> u16 {
+        std::cmp::min(self.default_limit, self.max_limit)
+    }
+
+    fn large_page_limit(&self) -> u16 {
+        std::cmp::max(self.large_page_max_limit, self.max_limit)
+    }
+
+    fn max_limit(&self, user: &User) -> u16 {
+        if user.has_any_role(&self.large_page_roles) {
+            self.large_page_limit()
+        } else {
+            self.max_limit
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn user_with_roles(roles: &[&str]) -> User {
+        serde_json::from_value(json!({
+            "sub": "9d1ef2b4-c2c6-4d52-8f5c-1c3e6a7d8e90",
+            "preferred_username": "tester",
+            "realm_access": { "roles": roles }
+        }))
+        .unwrap()
+    }
+
+    fn page_limits() -> PageLimits {
+        PageLimits {
+            default_limit: 50,
+            max_limit: 250,
+            large_page_roles: vec!["A_FOLIVAFY_COLLECTION_EDITOR".to_string()],
+            large_page_max_limit: 1000,
+        }
+    }
+
+    #[test]
+    fn it_allows_large_pages_for_admins() {
+        // Arrange
+        let admin = user_with_roles(&["A_FOLIVAFY_COLLECTION_EDITOR"]);
+        let pagination = Pagination::new(1000, 0);
+
+        // Act
+        let limited = pagination.clamped(Some(page_limits().max_limit(&admin)));
+
+        // Assert
+        assert_eq!(limited.limit(), 1000);
+    }
+
+    #[test]
+    fn it_clamps_large_pages_for_regular_users() {
+        // Arrange
+        let user = user_with_roles(&["C_SHAPES_READER"]);
+        let pagination = Pagination::new(1000, 40);
+
+        // Act
+        let limited = pagination.clamped(Some(page_limits().max_limit(&user)));
+        let at_maximum = Pagination::new(250, 0).clamped(Some(page_limits().max_limit(&user)));
+
+        // Assert
+        assert_eq!((limited.limit(), limited.offset()), (250, 40));
+        assert_eq!(at_maximum.limit(), 250);
+    }
+
+    #[test]
+    fn it_uses_the_configured_page_sizes() {
+        // Arrange
+        let user = user_with_roles(&["C_SHAPES_READER"]);
+        let admin = user_with_roles(&["A_FOLIVAFY_COLLECTION_EDITOR"]);
+        let limits = PageLimits {
+            default_limit: 20,
+            max_limit: 100,
+            large_page_roles: vec!["A_FOLIVAFY_COLLECTION_EDITOR".to_string()],
+            large_page_max_limit: 50,
+        };
+
+        // Act
+        let settings = serde_json::to_value(limits.settings()).unwrap();
+
+        // Assert
+        assert_eq!(limits.default_limit(), 20);
+        assert_eq!(limits.max_limit(&user), 100);
+        assert_eq!(limits.max_limit(&admin), 100);
+        assert_eq!(
+            settings,
+            json!({
+                "defaultLimit": 20,
+                "maxLimit": 100,
+                "largePageLimit": 100,
+                "largePageRoles": ["A_FOLIVAFY_COLLECTION_EDITOR"]
+            })
+        );
+    }
+
+    #[test]
+    fn it_keeps_the_default_page_size_below_the_maximum() {
+        // Arrange
+        let limits = PageLimits {
+            default_limit: 500,
+            ..page_limits()
+        };
+
+        // Act
+        let default_limit = limits.default_limit();
+
+        // Assert
+        assert_eq!(default_limit, 250);
+    }
+
+    #[test]
+    fn it_clamps_the_limit_to_the_collection_maximum() {
+        // Arrange
+        let pagination = Pagination::new(100, 20);
+
+        // Act
+        let clamped = pagination.clone().clamped(Some(10));
+        let unclamped = pagination.clamped(None);
+        let below_maximum = Pagination::new(5, 0).clamped(Some(10));
+
+        // Assert
+        assert_eq!((clamped.limit(), clamped.offset()), (10, 20));
+        assert_eq!(unclamped.limit(), 100);
+        assert_eq!(below_maximum.limit(), 5);
+    }
+}
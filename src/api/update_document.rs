@@ -1,39 +1,70 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
-    Json,
+    response::Response,
 };
 use axum_macros::debug_handler;
+use chrono::{SecondsFormat, Utc};
 use jwt_authorizer::JwtClaims;
-use sea_orm::{prelude::Uuid, TransactionError, TransactionTrait};
-use serde_json::json;
+use sea_orm::prelude::Uuid;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
 use std::sync::Arc;
-use tracing::{debug, error, warn};
+use tracing::{debug, warn};
 use validator::Validate;
 
 use crate::api::{
     auth,
+    collection_config::CollectionConfig,
     db::{
-        get_accessible_document, get_collection_by_name, save_document_events_mails, DbGrantUpdate,
+        get_accessible_document, get_collection_by_name, save_document_events_mails, store_error,
+        transaction_with_retry, DbGrantUpdate,
     },
+    document_version::{IfMatchVersion, VERSION_FIELD},
     dto::{self, GrantForDocument},
-    grants::default_document_grants,
+    grants::{
+        default_document_grants, hook_or_default_document_grants, inherited_document_grants,
+        with_inherited_grants,
+    },
+    hook_summary::{write_response, HookSummary, PreferHookSummary},
     hooks::{HookUpdateContext, RequestContext},
-    select_document_for_update, ApiContext, ApiErrors,
+    lock_document::{ensure_collection_unlocked, ensure_unlocked},
+    parent_fields::inherit_parent_fields,
+    select_document_for_update,
+    validation_webhook::{validate_with_webhook, WriteOperation},
+    ApiContext, ApiErrors, Collection,
 };
+use crate::axumext::extractors::JsonBody;
 use crate::models::CollectionItem;
 
 use super::grants::{hook_or_default_user_grants, GrantCollection};
 
+/// Server managed field with the time of the last touch
+pub(crate) const UPDATED_FIELD: &str = "updated";
+
+#[derive(Debug, Default, Deserialize, utoipa::IntoParams)]
+#[serde(default)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct UpdateDocumentParams {
+    /// Allow changes to immutable fields, requires the collection admin role
+    #[serde(rename = "overrideImmutable")]
+    #[param(example = true, default = false)]
+    override_immutable: Option<bool>,
+}
+
 /// Replace item
 ///
-/// Replace the item data
+/// Replace the item data. Changes to the immutable fields of the collection
+/// are rejected, unless a collection admin sets `overrideImmutable`. The
+/// field `version` is incremented, with `If-Match` the update is only stored
+/// if the document still has the expected version.
 #[debug_handler]
 #[utoipa::path(
     put,
     path = "/collections/{collection_name}",
     operation_id = "updateItemById",
     params(
+        UpdateDocumentParams,
         (
             "collection_name" = String,
             Path,
@@ -42,12 +73,25 @@ use super::grants::{hook_or_default_user_grants, GrantCollection};
             max_length = 32,
             pattern = r"^[a-z][-a-z0-9]*$",
         ),
+        (
+            "Prefer" = Option<String>,
+            Header,
+            description = "`hook-summary` returns a `HookSummary` with the events produced by hooks instead of the plain message",
+        ),
+        (
+            "If-Match" = Option<String>,
+            Header,
+            description = "Expected `version` of the stored document, the update is rejected with status 409 if the document has another version",
+        ),
     ),
     responses(
-        (status = CREATED, description = "Document updated" ),
+        (status = CREATED, description = "Document updated, with `Prefer: hook-summary` a summary of the hook results", body = HookSummary ),
         (status = UNAUTHORIZED, description = "User is not a collection editor" ),
         (status = NOT_FOUND, description = "Collection not found" ),
-        (status = BAD_REQUEST, description = "Invalid request" ),
+        (status = BAD_REQUEST, description = "Invalid request or immutable field changed" ),
+        (status = CONFLICT, description = "Document has another version than `If-Match` or was changed concurrently" ),
+        (status = LOCKED, description = "Document or collection is locked" ),
+        (status = TOO_MANY_REQUESTS, description = "Write rate limit of the collection exceeded" ),
         (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
     ),
     request_body(content = CollectionItem, description = "Create a new document", content_type = "application/json"),
@@ -56,9 +100,12 @@ use super::grants::{hook_or_default_user_grants, GrantCollection};
 pub(crate) async fn api_update_document(
     State(ctx): State<ApiContext>,
     Path(collection_name): Path<String>,
+    Query(update_params): Query<UpdateDocumentParams>,
     JwtClaims(user): JwtClaims<auth::User>,
-    Json(payload): Json<CollectionItem>,
-) -> Result<(StatusCode, String), ApiErrors> {
+    prefer: PreferHookSummary,
+    if_match: IfMatchVersion,
+    JsonBody(payload): JsonBody<CollectionItem>,
+) -> Result<Response, ApiErrors> {
     // Validate the payload
     payload.validate().map_err(ApiErrors::from)?;
 
@@ -66,6 +113,179 @@ pub(crate) async fn api_update_document(
     let document_uuid = Uuid::parse_str(&document_id)
         .map_err(|_| ApiErrors::BadRequestJsonSimpleMsg("Invalid uuid".to_string()))?;
 
+    update_document(
+        ctx,
+        user,
+        collection_name,
+        document_uuid,
+        DocumentUpdate::Replace(payload.f),
+        update_params,
+        prefer,
+        if_match,
+    )
+    .await
+}
+
+/// Update item fields
+///
+/// Change some fields of the item with a JSON merge patch (RFC 7386): fields
+/// of the patch are added or overwritten, fields with `null` are removed and
+/// nested objects are merged. The patch is applied to the stored document
+/// within the transaction, so concurrent changes to other fields are kept.
+/// Changes to the immutable fields of the collection are rejected, unless a
+/// collection admin sets `overrideImmutable`.
+#[debug_handler]
+#[utoipa::path(
+    patch,
+    path = "/collections/{collection_name}/{document_id}",
+    operation_id = "patchItemById",
+    params(
+        UpdateDocumentParams,
+        (
+            "collection_name" = String,
+            Path,
+            description = "Name of the collection",
+            min_length = 1,
+            max_length = 32,
+            pattern = r"^[a-z][-a-z0-9]*$",
+        ),
+        ("document_id" = String, Path, description = "UUID of the document", format = Uuid ),
+        (
+            "Prefer" = Option<String>,
+            Header,
+            description = "`hook-summary` returns a `HookSummary` with the events produced by hooks instead of the plain message",
+        ),
+        (
+            "If-Match" = Option<String>,
+            Header,
+            description = "Expected `version` of the stored document, the update is rejected with status 409 if the document has another version",
+        ),
+    ),
+    responses(
+        (status = CREATED, description = "Document updated, with `Prefer: hook-summary` a summary of the hook results", body = HookSummary ),
+        (status = UNAUTHORIZED, description = "User is not a collection editor" ),
+        (status = NOT_FOUND, description = "Collection or document not found" ),
+        (status = BAD_REQUEST, description = "Invalid request or immutable field changed" ),
+        (status = CONFLICT, description = "Document has another version than `If-Match` or was changed concurrently" ),
+        (status = LOCKED, description = "Document or collection is locked" ),
+        (status = TOO_MANY_REQUESTS, description = "Write rate limit of the collection exceeded" ),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
+    ),
+    request_body(content = Object, description = "Fields to change, `null` removes a field", content_type = "application/merge-patch+json"),
+    tag = super::TAG_COLLECTION,
+)]
+pub(crate) async fn api_patch_document(
+    State(ctx): State<ApiContext>,
+    Path((collection_name, document_id)): Path<(String, String)>,
+    Query(update_params): Query<UpdateDocumentParams>,
+    JwtClaims(user): JwtClaims<auth::User>,
+    prefer: PreferHookSummary,
+    if_match: IfMatchVersion,
+    JsonBody(patch): JsonBody<Value>,
+) -> Result<Response, ApiErrors> {
+    let document_uuid = Uuid::parse_str(&document_id)
+        .map_err(|_| ApiErrors::BadRequestJsonSimpleMsg("Invalid uuid".to_string()))?;
+    if !patch.is_object() {
+        return Err(ApiErrors::BadRequestJsonSimpleMsg(
+            "Merge patch must be a JSON object".to_string(),
+        ));
+    }
+
+    update_document(
+        ctx,
+        user,
+        collection_name,
+        document_uuid,
+        DocumentUpdate::MergePatch(patch),
+        update_params,
+        prefer,
+        if_match,
+    )
+    .await
+}
+
+/// Touch item
+///
+/// Records that the item was updated without changing its content: the field
+/// `updated` is set to the current time and an update event is written. The
+/// update hook receives the stored fields with the new timestamp.
+#[debug_handler]
+#[utoipa::path(
+    post,
+    path = "/collections/{collection_name}/{document_id}/touch",
+    operation_id = "touchItemById",
+    params(
+        (
+            "collection_name" = String,
+            Path,
+            description = "Name of the collection",
+            min_length = 1,
+            max_length = 32,
+            pattern = r"^[a-z][-a-z0-9]*$",
+        ),
+        ("document_id" = String, Path, description = "UUID of the document", format = Uuid ),
+        (
+            "Prefer" = Option<String>,
+            Header,
+            description = "`hook-summary` returns a `HookSummary` with the events produced by hooks instead of the plain message",
+        ),
+    ),
+    responses(
+        (status = CREATED, description = "Document updated, with `Prefer: hook-summary` a summary of the hook results", body = HookSummary ),
+        (status = UNAUTHORIZED, description = "User is not a collection editor" ),
+        (status = NOT_FOUND, description = "Collection or document not found" ),
+        (status = BAD_REQUEST, description = "Invalid request" ),
+        (status = LOCKED, description = "Document or collection is locked" ),
+        (status = TOO_MANY_REQUESTS, description = "Write rate limit of the collection exceeded" ),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
+    ),
+    tag = super::TAG_COLLECTION,
+)]
+pub(crate) async fn api_touch_document(
+    State(ctx): State<ApiContext>,
+    Path((collection_name, document_id)): Path<(String, String)>,
+    JwtClaims(user): JwtClaims<auth::User>,
+    prefer: PreferHookSummary,
+) -> Result<Response, ApiErrors> {
+    let document_uuid = Uuid::parse_str(&document_id)
+        .map_err(|_| ApiErrors::BadRequestJsonSimpleMsg("Invalid uuid".to_string()))?;
+
+    update_document(
+        ctx,
+        user,
+        collection_name,
+        document_uuid,
+        DocumentUpdate::Touch,
+        UpdateDocumentParams::default(),
+        prefer,
+        IfMatchVersion::default(),
+    )
+    .await
+}
+
+/// New fields of the document
+#[derive(Clone)]
+enum DocumentUpdate {
+    /// Replaces all fields
+    Replace(Value),
+    /// JSON merge patch for the stored fields
+    MergePatch(Value),
+    /// Keeps the stored fields and sets the time of the update
+    Touch,
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn update_document(
+    ctx: ApiContext,
+    user: auth::User,
+    collection_name: String,
+    document_uuid: Uuid,
+    update: DocumentUpdate,
+    update_params: UpdateDocumentParams,
+    prefer: PreferHookSummary,
+    if_match: IfMatchVersion,
+) -> Result<Response, ApiErrors> {
+    let document_id = document_uuid.to_string();
     let collection = get_collection_by_name(&ctx.db, &collection_name).await;
     if collection.is_none() {
         return Err(ApiErrors::NotFound(collection_name));
@@ -77,16 +297,22 @@ pub(crate) async fn api_update_document(
     }
 
     let collection = collection.unwrap();
-    // Check if collection is locked
-    if collection.locked {
+    ensure_collection_unlocked(&user, &collection)?;
+
+    let override_immutable = update_params.override_immutable.unwrap_or_default();
+    if override_immutable && !user.is_collection_admin(&collection_name) {
         warn!(
-            "User {} tried to update document in locked collection {}",
-            user.name_and_sub(),
-            collection_name
+            "User {} is not permitted to change immutable fields",
+            user.name_and_sub()
         );
-        return Err(ApiErrors::BadRequestJsonSimpleMsg(
-            "Read only collection".into(),
-        ));
+        return Err(ApiErrors::PermissionDenied);
+    }
+    let config = CollectionConfig::from(&collection);
+    config.validate_document_id(&document_uuid)?;
+    ctx.write_rates.check(collection.id, &config, 1)?;
+    if let DocumentUpdate::Replace(ref f) = update {
+        config.validate_required_fields(f)?;
+        config.validate_field_depth(f)?;
     }
 
     let dto_collection: GrantCollection = (&collection).into();
@@ -94,133 +320,375 @@ pub(crate) async fn api_update_document(
         hook_or_default_user_grants(&ctx.hooks, &dto_collection, &user, ctx.data_service.clone())
             .await?;
 
-    let document = get_accessible_document(
-        &ctx,
-        &user_grants,
-        user.subuuid(),
-        &collection,
-        document_uuid,
-    )
-    .await?;
-
-    if document.is_none() {
-        return Err(ApiErrors::NotFound(format!(
-            "Document {document_id} not found"
-        )));
-    }
-
-    let hook_processor = ctx.hooks.get_update_hook(&collection.name);
-    let trigger_cron_ctx = ctx.clone();
-
-    ctx.db
-        .transaction::<_, (StatusCode, String), ApiErrors>(|txn| {
-            Box::pin(async move {
-                let document = select_document_for_update(document_uuid, txn)
-                    .await?
-                    .and_then(|doc| {
-                        if collection.oao && doc.owner != user.subuuid() {
-                            None
-                        } else {
-                            Some(doc)
-                        }
-                    });
-                if document.is_none() {
-                    debug!("Document {} not found", document_uuid);
-                    return Err(ApiErrors::PermissionDenied);
-                }
-                let document = document.unwrap();
-
-                let before_document: dto::CollectionDocument = (&document).into();
-                let mut after_document: dto::CollectionDocument = (payload).into();
-                let mut events: Vec<dto::Event> = vec![];
-                let mut mails: Vec<dto::MailMessage> = vec![];
-                let mut dbgrants: DbGrantUpdate = DbGrantUpdate::Keep;
-                let mut trigger_cron = false;
-                let request_context = Arc::new(RequestContext::new(
-                    &collection.name,
-                    collection.id,
-                    dto::UserWithRoles::read_from(&user),
-                ));
-                if let Some(ref hook_processor) = hook_processor {
-                    let ctx = HookUpdateContext::new(
-                        before_document,
-                        after_document,
-                        ctx.data_service,
-                        request_context,
-                    );
-                    let hook_result = hook_processor.on_updating(&ctx).await?;
-                    trigger_cron = hook_result.trigger_cron;
-                    drop(ctx);
-
-                    match hook_result.document {
-                        crate::api::hooks::DocumentResult::Store(document) => {
-                            after_document = document;
-                        }
-                        crate::api::hooks::DocumentResult::NoUpdate => {
-                            return Err(ApiErrors::BadRequestJsonSimpleMsg(
-                                "Not accepted for storage".into(),
-                            ))
-                        }
-                        crate::api::hooks::DocumentResult::Err(err) => return Err(err),
-                    }
-                    events.extend(hook_result.events);
-                    mails.extend(hook_result.mails);
-                    dbgrants = match hook_result.grants {
-                        crate::api::hooks::GrantSettings::Default => DbGrantUpdate::Replace(
-                            default_document_grants(collection.oao, collection.id, user.subuuid())
-                                .into_iter()
-                                .map(|g| GrantForDocument::new(g, document.id))
-                                .collect(),
-                        ),
-                        crate::api::hooks::GrantSettings::Replace(grants) => {
-                            DbGrantUpdate::Replace(grants)
-                        }
-                        crate::api::hooks::GrantSettings::NoChange => DbGrantUpdate::Keep,
-                    }
-                }
-
-                events.insert(
-                    0,
-                    dto::Event::new(
-                        document_uuid,
-                        crate::api::CATEGORY_DOCUMENT_UPDATES,
-                        json!({
-                            "user": {
-                                "id": user.subuuid(),
-                                "name": user.preferred_username(),
-                            },
-                        }),
-                    ),
-                );
-
-                let dtouser = dto::User::read_from(&user);
-                save_document_events_mails(
-                    txn,
-                    &dtouser,
-                    Some(after_document),
-                    None,
-                    events,
-                    dbgrants,
-                    mails,
-                )
-                .await
-                .map_err(|e| {
-                    error!("Update document error: {:?}", e);
-                    ApiErrors::InternalServerError
-                })?;
-                debug!(
-                    "Document {:?} updated in collection {}",
-                    document_id, collection_name
-                );
-                trigger_cron_ctx
-                    .trigger_cron_with_condition(trigger_cron)
-                    .await;
-                Ok((StatusCode::CREATED, "Document updated".to_string()))
-            })
-        })
-        .await
-        .map_err(|err| match err {
-            TransactionError::Connection(c) => Into::<ApiErrors>::into(c),
-            TransactionError::Transaction(t) => t,
+    // The document is prepared and validated without a lock, the transaction
+    // stores it only if the document was not changed in the meantime.
+    let mut attempt = 1;
+    let (summary, trigger_cron) = loop {
+        let document = get_accessible_document(
+            &ctx,
+            &user_grants,
+            user.subuuid(),
+            &collection,
+            document_uuid,
+            false,
+        )
+        .await?
+        .ok_or_else(|| ApiErrors::NotFound(format!("Document {document_id} not found")))?;
+        let prepared = prepare_update(
+            &ctx,
+            &user,
+            &collection,
+            &config,
+            &document,
+            update.clone(),
+            override_immutable,
+            if_match,
+        )
+        .await?;
+        validate_with_webhook(
+            &ctx.webhook_client,
+            &config,
+            &collection_name,
+            WriteOperation::Update,
+            &prepared.after_document,
+        )
+        .await?;
+        match store_update(&ctx, &user, document, prepared).await? {
+            Some(stored) => break stored,
+            None if attempt < MAX_UPDATE_ATTEMPTS => {
+                debug!("Document {document_id} changed concurrently, preparing again");
+                attempt += 1;
+            }
+            None => {
+                return Err(ApiErrors::Conflict(
+                    "Document was changed concurrently".to_string(),
+                ))
+            }
+        }
+    };
+    debug!(
+        "Document {:?} updated in collection {}",
+        document_id, collection_name
+    );
+    ctx.trigger_cron_with_condition(trigger_cron).await;
+    ctx.trigger_event_relay();
+    Ok(write_response(
+        prefer,
+        StatusCode::CREATED,
+        "Document updated".to_string(),
+        summary,
+    ))
+}
+
+/// Number of times an update is prepared again after a concurrent change
+const MAX_UPDATE_ATTEMPTS: usize = 3;
+
+/// The document after the update with the results of the update hook
+#[derive(Clone)]
+struct PreparedUpdate {
+    after_document: dto::CollectionDocument,
+    events: Vec<dto::Event>,
+    mails: Vec<dto::MailMessage>,
+    dbgrants: DbGrantUpdate,
+    trigger_cron: bool,
+    touched: bool,
+}
+
+/// Applies the update to the stored document and runs the update hook.
+#[allow(clippy::too_many_arguments)]
+async fn prepare_update(
+    ctx: &ApiContext,
+    user: &auth::User,
+    collection: &Collection,
+    config: &CollectionConfig,
+    document: &entity::collection_document::Model,
+    update: DocumentUpdate,
+    override_immutable: bool,
+    if_match: IfMatchVersion,
+) -> Result<PreparedUpdate, ApiErrors> {
+    let document_uuid = document.id;
+    if collection.oao && document.owner != user.subuuid() {
+        debug!("Document {} not found", document_uuid);
+        return Err(ApiErrors::PermissionDenied);
+    }
+    ensure_unlocked(document)?;
+    let version = if_match.next_version(&document.f)?;
+
+    let touched = matches!(update, DocumentUpdate::Touch);
+    let f = match update {
+        DocumentUpdate::Replace(f) => f,
+        DocumentUpdate::Touch => touched_fields(&document.f),
+        DocumentUpdate::MergePatch(patch) => {
+            let mut f = document.f.clone();
+            merge_patch(&mut f, patch);
+            config.validate_required_fields(&f)?;
+            config.validate_field_depth(&f)?;
+            f
+        }
+    };
+
+    if !override_immutable {
+        let changed_fields = config.changed_immutable_fields(&document.f, &f);
+        if !changed_fields.is_empty() {
+            debug!(
+                "Immutable fields {:?} of document {} changed",
+                changed_fields, document_uuid
+            );
+            return Err(ApiErrors::BadRequestJson(json!({
+                "message": "Immutable fields cannot be changed",
+                "fields": changed_fields,
+            })));
+        }
+    }
+
+    let before_document: dto::CollectionDocument = document.into();
+    let mut after_document: dto::CollectionDocument = CollectionItem::new(document_uuid, f).into();
+    let mut events: Vec<dto::Event> = vec![];
+    let mut mails: Vec<dto::MailMessage> = vec![];
+    let mut dbgrants: DbGrantUpdate = DbGrantUpdate::Keep;
+    let mut trigger_cron = false;
+    let request_context = Arc::new(RequestContext::new(
+        &collection.name,
+        collection.id,
+        dto::UserWithRoles::read_from(user),
+    ));
+    if let Some(hook_processor) = ctx.hooks.get_update_hook(&collection.name) {
+        let ctx = HookUpdateContext::new(
+            before_document,
+            after_document,
+            ctx.data_service.clone(),
+            request_context,
+        );
+        let hook_result = hook_processor.on_updating(&ctx).await?;
+        trigger_cron = hook_result.trigger_cron;
+        drop(ctx);
+
+        match hook_result.document {
+            crate::api::hooks::DocumentResult::Store(document) => {
+                after_document = document;
+            }
+            crate::api::hooks::DocumentResult::NoUpdate => {
+                return Err(ApiErrors::BadRequestJsonSimpleMsg(
+                    "Not accepted for storage".into(),
+                ))
+            }
+            crate::api::hooks::DocumentResult::Err(err) => return Err(err),
+        }
+        events.extend(hook_result.events);
+        mails.extend(hook_result.mails);
+        dbgrants = match hook_result.grants {
+            crate::api::hooks::GrantSettings::Default => DbGrantUpdate::Replace(
+                default_document_grants(collection.oao, collection.id, user.subuuid())
+                    .into_iter()
+                    .map(|g| GrantForDocument::new(g, document.id))
+                    .collect(),
+            ),
+            crate::api::hooks::GrantSettings::Replace(grants) => DbGrantUpdate::Replace(grants),
+            crate::api::hooks::GrantSettings::NoChange => DbGrantUpdate::Keep,
+        }
+    }
+
+    after_document.set_field(VERSION_FIELD, json!(version));
+
+    if config.parent().is_some() {
+        inherit_parent_fields(&ctx.db, config, &mut after_document).await?;
+        // The stored grants contain the grants of the previous
+        // parent, start again from the grants of the document
+        let grants = match dbgrants {
+            DbGrantUpdate::Keep => hook_or_default_document_grants(
+                &ctx.hooks,
+                collection.into(),
+                after_document.clone(),
+                ctx.data_service.clone(),
+                document.owner,
+            )
+            .await?
+            .into_iter()
+            .map(|g| GrantForDocument::new(g, document.id))
+            .collect(),
+            DbGrantUpdate::Replace(grants) => grants,
+        };
+        let inherited_grants = inherited_document_grants(&ctx.db, config, &after_document).await?;
+        dbgrants =
+            DbGrantUpdate::Replace(with_inherited_grants(grants, inherited_grants, document.id));
+    }
+
+    Ok(PreparedUpdate {
+        after_document,
+        events,
+        mails,
+        dbgrants,
+        trigger_cron,
+        touched,
+    })
+}
+
+/// Stores the prepared update, returns `None` without storing it if the
+/// document was changed after it was read.
+async fn store_update(
+    ctx: &ApiContext,
+    user: &auth::User,
+    document: entity::collection_document::Model,
+    prepared: PreparedUpdate,
+) -> Result<Option<(HookSummary, bool)>, ApiErrors> {
+    transaction_with_retry(&ctx.db, move |txn| {
+        let user = user.clone();
+        let document = document.clone();
+        let prepared = prepared.clone();
+        Box::pin(async move {
+            let document_uuid = document.id;
+            let Some(locked) = select_document_for_update(document_uuid, txn).await? else {
+                return Err(ApiErrors::NotFound(format!(
+                    "Document {document_uuid} not found"
+                )));
+            };
+            if locked != document {
+                return Ok(None);
+            }
+            let PreparedUpdate {
+                after_document,
+                mut events,
+                mails,
+                dbgrants,
+                trigger_cron,
+                touched,
+            } = prepared;
+            let summary = HookSummary::new(document_uuid, &events);
+
+            let mut payload = json!({
+                "user": {
+                    "id": user.subuuid(),
+                    "name": user.preferred_username(),
+                },
+            });
+            if touched {
+                payload["touched"] = Value::Bool(true);
+            }
+            events.insert(
+                0,
+                dto::Event::new(
+                    document_uuid,
+                    crate::api::CATEGORY_DOCUMENT_UPDATES,
+                    payload,
+                ),
+            );
+
+            let dtouser = dto::User::read_from(&user);
+            save_document_events_mails(
+                txn,
+                &dtouser,
+                Some(after_document),
+                None,
+                events,
+                dbgrants,
+                mails,
+            )
+            .await
+            .map_err(store_error)?;
+            Ok(Some((summary, trigger_cron)))
         })
+    })
+    .await
+}
+
+/// Returns the fields with the current time in the `updated` field.
+fn touched_fields(f: &Value) -> Value {
+    let mut f = f.clone();
+    merge_patch(
+        &mut f,
+        json!({ UPDATED_FIELD: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true) }),
+    );
+    f
+}
+
+/// Applies a JSON merge patch (RFC 7386) to the target.
+fn merge_patch(target: &mut Value, patch: Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch;
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+    let Value::Object(fields) = target else {
+        unreachable!()
+    };
+    for (key, value) in patch {
+        if value.is_null() {
+            fields.remove(&key);
+        } else {
+            merge_patch(fields.entry(key).or_insert(Value::Null), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn patched(target: Value, patch: Value) -> Value {
+        let mut target = target;
+        merge_patch(&mut target, patch);
+        target
+    }
+
+    #[test]
+    fn it_adds_fields() {
+        assert_eq!(
+            patched(
+                json!({"title": "Rectangle"}),
+                json!({"price": 14, "size": {"width": 2}})
+            ),
+            json!({"title": "Rectangle", "price": 14, "size": {"width": 2}})
+        );
+    }
+
+    #[test]
+    fn it_overwrites_fields() {
+        assert_eq!(
+            patched(
+                json!({"title": "Rectangle", "tags": ["a", "b"], "size": {"width": 2, "height": 3}}),
+                json!({"title": "Square", "tags": ["c"], "size": {"height": 2}})
+            ),
+            json!({"title": "Square", "tags": ["c"], "size": {"width": 2, "height": 2}})
+        );
+    }
+
+    #[test]
+    fn it_deletes_fields_with_null() {
+        assert_eq!(
+            patched(
+                json!({"title": "Rectangle", "price": 14, "size": {"width": 2, "height": 3}}),
+                json!({"price": null, "size": {"width": null}, "missing": null})
+            ),
+            json!({"title": "Rectangle", "size": {"height": 3}})
+        );
+    }
+
+    #[test]
+    fn it_replaces_non_object_values() {
+        assert_eq!(
+            patched(
+                json!({"size": 4}),
+                json!({"size": {"width": null, "height": 2}})
+            ),
+            json!({"size": {"height": 2}})
+        );
+    }
+
+    #[test]
+    fn it_touches_only_the_updated_field() {
+        // Arrange
+        let f = json!({"title": "Rectangle", "updated": "2024-01-01T00:00:00.000Z"});
+
+        // Act
+        let touched = touched_fields(&f);
+
+        // Assert
+        let updated = touched[UPDATED_FIELD].as_str().unwrap();
+        assert!(updated > "2024-01-01T00:00:00.000Z");
+        assert_eq!(touched, json!({"title": "Rectangle", "updated": updated}));
+    }
 }
@@ -0,0 +1,296 @@
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::StatusCode,
+    response::Response,
+    Json,
+};
+use jwt_authorizer::JwtClaims;
+use serde::Deserialize;
+use tracing::{debug, warn};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::axumext::extractors::{JsonBody, PathParams, ValidatedQueryParams};
+use crate::models::CollectionItem;
+
+use super::{
+    auth::User,
+    create_document::store_new_document,
+    db::get_collection_by_name,
+    hook_summary::{AcceptDocument, HookSummary, PreferHookSummary},
+    idempotency::IdempotencyKey,
+    upload_sessions::UploadStatus,
+    ApiContext, ApiErrors,
+};
+
+/// Size of the document that is uploaded in chunks
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub(crate) struct NewUpload {
+    /// Size of the JSON document in bytes, at most 64 MiB
+    #[schema(example = 5242880)]
+    size: usize,
+}
+
+#[derive(Debug, Deserialize, Validate, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct UploadChunkParams {
+    /// Position of the chunk in the document, the number of bytes received so far
+    #[param(example = 0)]
+    offset: usize,
+}
+
+/// Checks that the user is an editor of an existing collection.
+async fn check_editor(
+    ctx: &ApiContext,
+    user: &User,
+    collection_name: &str,
+) -> Result<(), ApiErrors> {
+    if !user.is_collection_editor(collection_name) {
+        warn!("User {} is not a collection editor", user.name_and_sub());
+        return Err(ApiErrors::PermissionDenied);
+    }
+    get_collection_by_name(&ctx.db, collection_name)
+        .await
+        .ok_or_else(|| ApiErrors::NotFound(collection_name.to_string()))?;
+    Ok(())
+}
+
+/// Start upload
+///
+/// Starts the upload of a new document that is too large for a single
+/// request. The document is sent in chunks and stored when the upload is
+/// finalized. An upload expires an hour after its last chunk.
+#[utoipa::path(
+    post,
+    path = "/collections/{collection_name}/uploads",
+    operation_id = "startUpload",
+    params(
+        (
+            "collection_name" = String,
+            Path,
+            description = "Name of the collection",
+            min_length = 1,
+            max_length = 32,
+            pattern = r"^[a-z][-a-z0-9]*$",
+        ),
+    ),
+    request_body(content = NewUpload, description = "Size of the document", content_type = "application/json"),
+    responses(
+        (status = CREATED, description = "Upload started", body = UploadStatus ),
+        (status = UNAUTHORIZED, description = "User is not a collection editor" ),
+        (status = NOT_FOUND, description = "Collection not found" ),
+        (status = BAD_REQUEST, description = "Invalid size" ),
+        (status = PAYLOAD_TOO_LARGE, description = "Unfinished uploads of all users exceed the upload buffer" ),
+        (status = TOO_MANY_REQUESTS, description = "User has too many unfinished uploads" ),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
+    ),
+    tag = super::TAG_COLLECTION,
+)]
+pub(crate) async fn api_start_upload(
+    State(ctx): State<ApiContext>,
+    JwtClaims(user): JwtClaims<User>,
+    Path(collection_name): Path<String>,
+    JsonBody(payload): JsonBody<NewUpload>,
+) -> Result<(StatusCode, Json<UploadStatus>), ApiErrors> {
+    check_editor(&ctx, &user, &collection_name).await?;
+    let status = ctx
+        .uploads
+        .start(&collection_name, user.subuuid(), payload.size)?;
+    debug!(
+        "User {} started upload into {collection_name}",
+        user.name_and_sub()
+    );
+    Ok((StatusCode::CREATED, Json(status)))
+}
+
+/// Get upload progress
+///
+/// Returns the number of bytes received, e. g. to resume an interrupted
+/// upload at this offset.
+#[utoipa::path(
+    get,
+    path = "/collections/{collection_name}/uploads/{upload_id}",
+    operation_id = "getUpload",
+    params(
+        (
+            "collection_name" = String,
+            Path,
+            description = "Name of the collection",
+            min_length = 1,
+            max_length = 32,
+            pattern = r"^[a-z][-a-z0-9]*$",
+        ),
+        ("upload_id" = Uuid, Path, description = "Id of the upload"),
+    ),
+    responses(
+        (status = OK, description = "Progress of the upload", body = UploadStatus ),
+        (status = UNAUTHORIZED, description = "User is not a collection editor" ),
+        (status = NOT_FOUND, description = "Collection or upload not found" ),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
+    ),
+    tag = super::TAG_COLLECTION,
+)]
+pub(crate) async fn api_get_upload(
+    State(ctx): State<ApiContext>,
+    JwtClaims(user): JwtClaims<User>,
+    PathParams((collection_name, upload_id)): PathParams<(String, Uuid)>,
+) -> Result<Json<UploadStatus>, ApiErrors> {
+    check_editor(&ctx, &user, &collection_name).await?;
+    ctx.uploads
+        .status(upload_id, &collection_name, user.subuuid())
+        .map(Json)
+}
+
+/// Upload chunk
+///
+/// Appends a chunk of the document at the given offset. A chunk at another
+/// offset than the number of bytes received so far is rejected with status
+/// 409 and the current offset.
+#[utoipa::path(
+    patch,
+    path = "/collections/{collection_name}/uploads/{upload_id}",
+    operation_id = "uploadChunk",
+    params(
+        UploadChunkParams,
+        (
+            "collection_name" = String,
+            Path,
+            description = "Name of the collection",
+            min_length = 1,
+            max_length = 32,
+            pattern = r"^[a-z][-a-z0-9]*$",
+        ),
+        ("upload_id" = Uuid, Path, description = "Id of the upload"),
+    ),
+    request_body(content = Vec<u8>, description = "Chunk of the JSON document", content_type = "application/octet-stream"),
+    responses(
+        (status = OK, description = "Chunk stored", body = UploadStatus ),
+        (status = UNAUTHORIZED, description = "User is not a collection editor" ),
+        (status = NOT_FOUND, description = "Collection or upload not found" ),
+        (status = CONFLICT, description = "Offset does not match the received bytes" ),
+        (status = BAD_REQUEST, description = "Chunk exceeds the size of the upload" ),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
+    ),
+    tag = super::TAG_COLLECTION,
+)]
+pub(crate) async fn api_upload_chunk(
+    State(ctx): State<ApiContext>,
+    JwtClaims(user): JwtClaims<User>,
+    PathParams((collection_name, upload_id)): PathParams<(String, Uuid)>,
+    ValidatedQueryParams(params): ValidatedQueryParams<UploadChunkParams>,
+    chunk: Bytes,
+) -> Result<Json<UploadStatus>, ApiErrors> {
+    check_editor(&ctx, &user, &collection_name).await?;
+    ctx.uploads
+        .append(
+            upload_id,
+            &collection_name,
+            user.subuuid(),
+            params.offset,
+            &chunk,
+        )
+        .map(Json)
+}
+
+/// Cancel upload
+///
+/// Discards the upload and the received chunks.
+#[utoipa::path(
+    delete,
+    path = "/collections/{collection_name}/uploads/{upload_id}",
+    operation_id = "cancelUpload",
+    params(
+        (
+            "collection_name" = String,
+            Path,
+            description = "Name of the collection",
+            min_length = 1,
+            max_length = 32,
+            pattern = r"^[a-z][-a-z0-9]*$",
+        ),
+        ("upload_id" = Uuid, Path, description = "Id of the upload"),
+    ),
+    responses(
+        (status = NO_CONTENT, description = "Upload discarded" ),
+        (status = UNAUTHORIZED, description = "User is not a collection editor" ),
+        (status = NOT_FOUND, description = "Collection or upload not found" ),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
+    ),
+    tag = super::TAG_COLLECTION,
+)]
+pub(crate) async fn api_cancel_upload(
+    State(ctx): State<ApiContext>,
+    JwtClaims(user): JwtClaims<User>,
+    PathParams((collection_name, upload_id)): PathParams<(String, Uuid)>,
+) -> Result<StatusCode, ApiErrors> {
+    check_editor(&ctx, &user, &collection_name).await?;
+    ctx.uploads
+        .remove(upload_id, &collection_name, user.subuuid())?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Finalize upload
+///
+/// Stores the completely uploaded document like a document that is sent to
+/// the create endpoint. The upload is discarded once the document is stored,
+/// a rejected document can be finalized again.
+#[utoipa::path(
+    post,
+    path = "/collections/{collection_name}/uploads/{upload_id}/finalize",
+    operation_id = "finalizeUpload",
+    params(
+        (
+            "collection_name" = String,
+            Path,
+            description = "Name of the collection",
+            min_length = 1,
+            max_length = 32,
+            pattern = r"^[a-z][-a-z0-9]*$",
+        ),
+        ("upload_id" = Uuid, Path, description = "Id of the upload"),
+        (
+            "Prefer" = Option<String>,
+            Header,
+            description = "`hook-summary` returns a `HookSummary` with the events produced by hooks instead of the plain message",
+        ),
+        (
+            "Accept" = Option<String>,
+            Header,
+            description = "`application/json` without `text/plain` returns the stored `CollectionItem`, including the fields set by hooks, instead of the plain message",
+        ),
+    ),
+    responses(
+        (status = CREATED, description = "Document created successfully, with `Prefer: hook-summary` a summary of the hook results, with `Accept: application/json` the stored `CollectionItem`", body = HookSummary ),
+        (status = UNAUTHORIZED, description = "User is not a collection editor" ),
+        (status = NOT_FOUND, description = "Collection or upload not found" ),
+        (status = BAD_REQUEST, description = "Upload is incomplete or the document is invalid" ),
+        (status = LOCKED, description = "Collection is read only" ),
+        (status = TOO_MANY_REQUESTS, description = "Write rate limit of the collection exceeded" ),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
+    ),
+    tag = super::TAG_COLLECTION,
+)]
+pub(crate) async fn api_finalize_upload(
+    State(ctx): State<ApiContext>,
+    JwtClaims(user): JwtClaims<User>,
+    PathParams((collection_name, upload_id)): PathParams<(String, Uuid)>,
+    prefer: PreferHookSummary,
+    accept: AcceptDocument,
+) -> Result<Response, ApiErrors> {
+    check_editor(&ctx, &user, &collection_name).await?;
+    let owner = user.subuuid();
+    let payload: CollectionItem = ctx.uploads.parse(upload_id, &collection_name, owner)?;
+    let response = store_new_document(
+        &ctx,
+        user,
+        collection_name.clone(),
+        prefer,
+        accept,
+        IdempotencyKey::default(),
+        payload,
+    )
+    .await?;
+    ctx.uploads.remove(upload_id, &collection_name, owner)?;
+    Ok(response)
+}
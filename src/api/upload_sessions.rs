@@ -0,0 +1,371 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::ApiErrors;
+
+/// Maximum size of an uploaded document in bytes
+pub(crate) const MAX_UPLOAD_SIZE: usize = 64 * 1024 * 1024;
+
+/// Time after the last chunk until an unfinished upload is discarded
+const UPLOAD_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Maximum number of unfinished uploads of a user
+const MAX_UPLOADS_PER_USER: usize = 4;
+
+/// Maximum sum of the sizes of all unfinished uploads in bytes
+const MAX_BUFFERED_SIZE: usize = 8 * MAX_UPLOAD_SIZE;
+
+/// Progress of an upload
+#[derive(Debug, PartialEq, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct UploadStatus {
+    /// Id of the upload
+    id: Uuid,
+
+    /// Size of the document in bytes
+    size: usize,
+
+    /// Number of bytes received, the offset of the next chunk
+    offset: usize,
+}
+
+#[derive(Debug)]
+struct UploadSession {
+    collection: String,
+    owner: Uuid,
+    size: usize,
+    /// Shared with a running `parse`, which reads it without the lock
+    data: Arc<Vec<u8>>,
+    touched: Instant,
+}
+
+impl UploadSession {
+    fn status(&self, id: Uuid) -> UploadStatus {
+        UploadStatus {
+            id,
+            size: self.size,
+            offset: self.data.len(),
+        }
+    }
+}
+
+/// Unfinished uploads of large documents. An upload can only be continued by
+/// the user who started it, unfinished uploads expire after an hour.
+#[derive(Debug, Default)]
+pub(crate) struct UploadSessions {
+    sessions: Mutex<HashMap<Uuid, UploadSession>>,
+}
+
+impl UploadSessions {
+    /// Starts the upload of a document with `size` bytes. A user can have
+    /// `MAX_UPLOADS_PER_USER` unfinished uploads, all unfinished uploads
+    /// together at most `MAX_BUFFERED_SIZE` bytes.
+    pub(crate) fn start(
+        &self,
+        collection: &str,
+        owner: Uuid,
+        size: usize,
+    ) -> Result<UploadStatus, ApiErrors> {
+        if size == 0 || size > MAX_UPLOAD_SIZE {
+            return Err(ApiErrors::BadRequestJsonSimpleMsg(format!(
+                "Upload size must be between 1 and {MAX_UPLOAD_SIZE} bytes"
+            )));
+        }
+        let id = Uuid::new_v4();
+        let session = UploadSession {
+            collection: collection.to_string(),
+            owner,
+            size,
+            data: Arc::new(Vec::new()),
+            touched: Instant::now(),
+        };
+        let status = session.status(id);
+        self.with_sessions(|sessions| {
+            let uploads_of_user = sessions.values().filter(|s| s.owner == owner).count();
+            if uploads_of_user >= MAX_UPLOADS_PER_USER {
+                return Err(ApiErrors::TooManyRequests {
+                    message: format!(
+                        "At most {MAX_UPLOADS_PER_USER} unfinished uploads, finalize or cancel an upload first"
+                    ),
+                    retry_after: None,
+                });
+            }
+            let buffered: usize = sessions.values().map(|s| s.size).sum();
+            if buffered + size > MAX_BUFFERED_SIZE {
+                return Err(ApiErrors::PayloadTooLarge(
+                    "Upload buffer is full, try again later".into(),
+                ));
+            }
+            sessions.insert(id, session);
+            Ok(status)
+        })
+    }
+
+    /// Returns the progress of the upload.
+    pub(crate) fn status(
+        &self,
+        id: Uuid,
+        collection: &str,
+        owner: Uuid,
+    ) -> Result<UploadStatus, ApiErrors> {
+        self.with_session(id, collection, owner, |session| Ok(session.status(id)))
+    }
+
+    /// Appends the chunk at `offset`, which must be the number of bytes
+    /// received so far. A rejected chunk is not stored.
+    pub(crate) fn append(
+        &self,
+        id: Uuid,
+        collection: &str,
+        owner: Uuid,
+        offset: usize,
+        chunk: &[u8],
+    ) -> Result<UploadStatus, ApiErrors> {
+        self.with_session(id, collection, owner, |session| {
+            if offset != session.data.len() {
+                return Err(ApiErrors::Conflict(format!(
+                    "Upload offset is {}",
+                    session.data.len()
+                )));
+            }
+            if offset + chunk.len() > session.size {
+                return Err(ApiErrors::BadRequestJsonSimpleMsg(
+                    "Chunk exceeds the size of the upload".into(),
+                ));
+            }
+            Arc::make_mut(&mut session.data).extend_from_slice(chunk);
+            session.touched = Instant::now();
+            Ok(session.status(id))
+        })
+    }
+
+    /// Parses the completely received document. The upload remains until it
+    /// is removed, e. g. to retry storing the document. The document is
+    /// parsed without blocking the other uploads.
+    pub(crate) fn parse<T: serde::de::DeserializeOwned>(
+        &self,
+        id: Uuid,
+        collection: &str,
+        owner: Uuid,
+    ) -> Result<T, ApiErrors> {
+        let data = self.with_session(id, collection, owner, |session| {
+            if session.data.len() != session.size {
+                return Err(ApiErrors::BadRequestJsonSimpleMsg(format!(
+                    "Upload is incomplete, {} of {} bytes received",
+                    session.data.len(),
+                    session.size
+                )));
+            }
+            Ok(Arc::clone(&session.data))
+        })?;
+        serde_json::from_slice(&data)
+            .map_err(|e| ApiErrors::BadRequestJsonSimpleMsg(format!("Invalid document: {e}")))
+    }
+
+    /// Discards the upload.
+    pub(crate) fn remove(&self, id: Uuid, collection: &str, owner: Uuid) -> Result<(), ApiErrors> {
+        self.with_session(id, collection, owner, |_| Ok(()))?;
+        self.with_sessions(|sessions| sessions.remove(&id));
+        Ok(())
+    }
+
+    /// Runs `f` with the upload of the user in the collection. Uploads of
+    /// other users or collections are not found.
+    fn with_session<R>(
+        &self,
+        id: Uuid,
+        collection: &str,
+        owner: Uuid,
+        f: impl FnOnce(&mut UploadSession) -> Result<R, ApiErrors>,
+    ) -> Result<R, ApiErrors> {
+        self.with_sessions(|sessions| {
+            match sessions
+                .get_mut(&id)
+                .filter(|session| session.collection == collection && session.owner == owner)
+            {
+                Some(session) => f(session),
+                None => Err(ApiErrors::NotFound(format!("Upload {id} not found"))),
+            }
+        })
+    }
+
+    fn with_sessions<R>(&self, f: impl FnOnce(&mut HashMap<Uuid, UploadSession>) -> R) -> R {
+        let mut sessions = self.sessions.lock().expect("Upload sessions poisoned");
+        discard_expired(&mut sessions, Instant::now());
+        f(&mut sessions)
+    }
+}
+
+/// Removes the uploads without a chunk within the last hour.
+fn discard_expired(sessions: &mut HashMap<Uuid, UploadSession>, now: Instant) {
+    sessions.retain(|_, session| now.duration_since(session.touched) < UPLOAD_TTL);
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    #[test]
+    fn it_assembles_a_document_from_chunks() {
+        // Arrange
+        let uploads = UploadSessions::default();
+        let owner = Uuid::new_v4();
+        let document = br#"{"id":"4b2d6f80-1a3c-4e5d-9f70-8a9b0c1d2e31","f":{"title":"Large"}}"#;
+        let upload = uploads.start("shapes", owner, document.len()).unwrap();
+
+        // Act
+        let mut offset = 0;
+        for chunk in document.chunks(20) {
+            offset = uploads
+                .append(upload.id, "shapes", owner, offset, chunk)
+                .unwrap()
+                .offset;
+        }
+        let parsed: Value = uploads.parse(upload.id, "shapes", owner).unwrap();
+
+        // Assert
+        assert_eq!(
+            parsed,
+            json!({"id": "4b2d6f80-1a3c-4e5d-9f70-8a9b0c1d2e31", "f": {"title": "Large"}})
+        );
+    }
+
+    #[test]
+    fn it_rejects_a_chunk_at_the_wrong_offset() {
+        // Arrange
+        let uploads = UploadSessions::default();
+        let owner = Uuid::new_v4();
+        let upload = uploads.start("shapes", owner, 10).unwrap();
+        uploads
+            .append(upload.id, "shapes", owner, 0, b"{}")
+            .unwrap();
+
+        // Act
+        let repeated = uploads.append(upload.id, "shapes", owner, 0, b"{}");
+        let status = uploads.status(upload.id, "shapes", owner).unwrap();
+
+        // Assert
+        assert!(matches!(
+            repeated,
+            Err(ApiErrors::Conflict(msg)) if msg == "Upload offset is 2"
+        ));
+        assert_eq!(status.offset, 2);
+    }
+
+    #[test]
+    fn it_rejects_an_incomplete_upload() {
+        // Arrange
+        let uploads = UploadSessions::default();
+        let owner = Uuid::new_v4();
+        let upload = uploads.start("shapes", owner, 10).unwrap();
+        uploads
+            .append(upload.id, "shapes", owner, 0, b"{}")
+            .unwrap();
+
+        // Act
+        let result = uploads.parse::<Value>(upload.id, "shapes", owner);
+
+        // Assert
+        assert!(matches!(
+            result,
+            Err(ApiErrors::BadRequestJsonSimpleMsg(msg)) if msg == "Upload is incomplete, 2 of 10 bytes received"
+        ));
+    }
+
+    #[test]
+    fn it_hides_the_upload_from_other_users() {
+        // Arrange
+        let uploads = UploadSessions::default();
+        let upload = uploads.start("shapes", Uuid::new_v4(), 10).unwrap();
+
+        // Act
+        let result = uploads.append(upload.id, "shapes", Uuid::new_v4(), 0, b"{}");
+
+        // Assert
+        assert!(matches!(result, Err(ApiErrors::NotFound(_))));
+    }
+
+    #[test]
+    fn it_limits_the_unfinished_uploads_of_a_user() {
+        // Arrange
+        let uploads = UploadSessions::default();
+        let owner = Uuid::new_v4();
+        for _ in 0..MAX_UPLOADS_PER_USER {
+            uploads.start("shapes", owner, 10).unwrap();
+        }
+
+        // Act
+        let result = uploads.start("shapes", owner, 10);
+        let other_user = uploads.start("shapes", Uuid::new_v4(), 10);
+
+        // Assert
+        assert!(matches!(
+            result,
+            Err(ApiErrors::TooManyRequests {
+                retry_after: None,
+                ..
+            })
+        ));
+        assert!(other_user.is_ok());
+    }
+
+    #[test]
+    fn it_limits_the_buffered_size() {
+        // Arrange
+        let uploads = UploadSessions::default();
+        for _ in 0..MAX_BUFFERED_SIZE / MAX_UPLOAD_SIZE {
+            uploads
+                .start("shapes", Uuid::new_v4(), MAX_UPLOAD_SIZE)
+                .unwrap();
+        }
+
+        // Act
+        let result = uploads.start("shapes", Uuid::new_v4(), 1);
+
+        // Assert
+        assert!(matches!(result, Err(ApiErrors::PayloadTooLarge(_))));
+    }
+
+    #[test]
+    fn it_keeps_the_upload_after_parsing() {
+        // Arrange
+        let uploads = UploadSessions::default();
+        let owner = Uuid::new_v4();
+        let upload = uploads.start("shapes", owner, 2).unwrap();
+        uploads
+            .append(upload.id, "shapes", owner, 0, b"{}")
+            .unwrap();
+
+        // Act
+        let first: Value = uploads.parse(upload.id, "shapes", owner).unwrap();
+        let second: Value = uploads.parse(upload.id, "shapes", owner).unwrap();
+
+        // Assert
+        assert_eq!(first, json!({}));
+        assert_eq!(second, json!({}));
+    }
+
+    #[test]
+    fn it_discards_expired_uploads() {
+        // Arrange
+        let uploads = UploadSessions::default();
+        let owner = Uuid::new_v4();
+        let upload = uploads.start("shapes", owner, 10).unwrap();
+        let mut sessions = uploads.sessions.lock().unwrap();
+
+        // Act
+        discard_expired(&mut sessions, Instant::now() + UPLOAD_TTL);
+
+        // Assert
+        assert!(!sessions.contains_key(&upload.id));
+    }
+}
@@ -0,0 +1,231 @@
+use std::collections::BTreeMap;
+
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use jwt_authorizer::JwtClaims;
+use serde::Serialize;
+use tracing::{error, warn};
+use validator::{Validate, ValidationError};
+
+use crate::axumext::extractors::JsonBody;
+use crate::models::CollectionItem;
+
+use super::{
+    auth::User,
+    collection_config::CollectionConfig,
+    db::{get_collection_by_name, get_document_by_id},
+    dto,
+    validation_webhook::{validate_with_webhook, WriteOperation},
+    ApiContext, ApiErrors,
+};
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+#[schema(examples(json!({
+    "valid": false,
+    "errors": {
+        "id": [{"code": "unique", "message": "A document with this id exists", "params": {}}],
+        "vat_id": [{"code": "required", "message": null, "params": {"when": "type", "equals": "company"}}]
+    }
+})))]
+pub(crate) struct DocumentValidation {
+    /// Whether the document can be created
+    valid: bool,
+
+    /// Validation errors by field, like the errors of a rejected document
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    #[schema(value_type = Object)]
+    errors: BTreeMap<String, Vec<ValidationError>>,
+}
+
+impl From<BTreeMap<String, Vec<ValidationError>>> for DocumentValidation {
+    fn from(errors: BTreeMap<String, Vec<ValidationError>>) -> Self {
+        Self {
+            valid: errors.is_empty(),
+            errors,
+        }
+    }
+}
+
+/// Validate a new document
+///
+/// Runs the validations of a new document without storing it, e. g. before a
+/// form is submitted: the request body, the document id and its uniqueness,
+/// the required fields, the nesting depth and the validation webhook of the
+/// collection. The hooks of the collection are not run.
+#[utoipa::path(
+    post,
+    path = "/collections/{collection_name}/validate",
+    operation_id = "validateDocument",
+    params(
+        (
+            "collection_name" = String,
+            Path,
+            description = "Name of the collection",
+            min_length = 1,
+            max_length = 32,
+            pattern = r"^[a-z][-a-z0-9]*$",
+        ),
+    ),
+    responses(
+        (status = OK, description = "Result of the validation", body = DocumentValidation ),
+        (status = UNAUTHORIZED, description = "User is not a collection editor" ),
+        (status = NOT_FOUND, description = "Collection not found" ),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
+    ),
+    request_body(content = CollectionItem, description = "Document to validate", content_type = "application/json"),
+    tag = super::TAG_COLLECTION,
+)]
+pub(crate) async fn api_validate_document(
+    State(ctx): State<ApiContext>,
+    JwtClaims(user): JwtClaims<User>,
+    Path(collection_name): Path<String>,
+    JsonBody(payload): JsonBody<CollectionItem>,
+) -> Result<Json<DocumentValidation>, ApiErrors> {
+    if !user.is_collection_editor(&collection_name) {
+        warn!("User {} is not a collection editor", user.name_and_sub());
+        return Err(ApiErrors::PermissionDenied);
+    }
+
+    let collection = get_collection_by_name(&ctx.db, &collection_name)
+        .await
+        .ok_or_else(|| ApiErrors::NotFound(collection_name.clone()))?;
+    let config = CollectionConfig::from(&collection);
+
+    let mut errors = payload_errors(&payload);
+    for (field, field_errors) in config.new_document_errors(&payload.id, &payload.f) {
+        errors.entry(field).or_default().extend(field_errors);
+    }
+    let existing_document = get_document_by_id(payload.id, &ctx.db).await.map_err(|e| {
+        error!("Error while reading document {}: {:?}", payload.id, e);
+        ApiErrors::InternalServerError
+    })?;
+    if existing_document.is_some() {
+        errors.entry("id".to_string()).or_default().push(
+            ValidationError::new("unique").with_message("A document with this id exists".into()),
+        );
+    }
+    if let Some(error) =
+        webhook_error(&ctx.webhook_client, &config, &collection_name, payload).await?
+    {
+        errors.entry("f".to_string()).or_default().push(error);
+    }
+
+    Ok(Json(errors.into()))
+}
+
+/// Returns the errors of the request body by field.
+fn payload_errors(payload: &CollectionItem) -> BTreeMap<String, Vec<ValidationError>> {
+    match payload.validate() {
+        Ok(()) => BTreeMap::new(),
+        Err(errors) => errors
+            .field_errors()
+            .into_iter()
+            .map(|(field, field_errors)| (field.to_string(), field_errors.clone()))
+            .collect(),
+    }
+}
+
+/// Asks the validation webhook of the collection, a rejection is returned as
+/// an error of the document fields.
+async fn webhook_error(
+    client: &reqwest::Client,
+    config: &CollectionConfig,
+    collection_name: &str,
+    payload: CollectionItem,
+) -> Result<Option<ValidationError>, ApiErrors> {
+    let document: dto::CollectionDocument = payload.into();
+    match validate_with_webhook(
+        client,
+        config,
+        collection_name,
+        WriteOperation::Create,
+        &document,
+    )
+    .await
+    {
+        Ok(()) => Ok(None),
+        Err(ApiErrors::HookRejection(rejection)) => Ok(Some(
+            ValidationError::new("webhook").with_message(rejection.message().to_string().into()),
+        )),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    use super::*;
+
+    #[test]
+    fn it_reports_a_valid_document() {
+        // Arrange
+        let errors = BTreeMap::new();
+
+        // Act
+        let validation: DocumentValidation = errors.into();
+
+        // Assert
+        assert_eq!(
+            serde_json::to_value(validation).unwrap(),
+            json!({"valid": true})
+        );
+    }
+
+    #[test]
+    fn it_reports_the_errors_by_field() {
+        // Arrange
+        let config = CollectionConfig::from_json(&json!({
+            "maxFieldDepth": 1,
+            "requiredWhen": [{"field": "vat_id", "when": "type", "equals": "company"}]
+        }));
+        let payload = CollectionItem::new(
+            Uuid::nil(),
+            json!({"type": "company", "address": {"city": "Berlin"}}),
+        );
+
+        // Act
+        let mut errors = payload_errors(&payload);
+        errors.extend(config.new_document_errors(&payload.id, &payload.f));
+        let validation: DocumentValidation = errors.into();
+
+        // Assert
+        let validation = serde_json::to_value(validation).unwrap();
+        assert_eq!(validation["valid"], json!(false));
+        assert_eq!(validation["errors"]["id"][0]["code"], json!("nil"));
+        assert_eq!(validation["errors"]["vat_id"][0]["code"], json!("required"));
+        assert_eq!(
+            validation["errors"]["f"][0]["message"],
+            json!("Document fields are nested deeper than 1 levels")
+        );
+    }
+
+    #[tokio::test]
+    async fn it_reports_a_rejection_of_the_validation_webhook() {
+        // Arrange
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/validate", listener.local_addr().unwrap());
+        drop(listener);
+        let config = CollectionConfig::from_json(&json!({
+            "validationWebhook": {"url": url, "timeoutMs": 1000}
+        }));
+        let payload = CollectionItem::new(Uuid::new_v4(), json!({"title": "ACME"}));
+
+        // Act
+        let error = webhook_error(&reqwest::Client::new(), &config, "customers", payload)
+            .await
+            .unwrap();
+
+        // Assert
+        let error = error.unwrap();
+        assert_eq!(error.code, "webhook");
+        assert_eq!(
+            error.message.as_deref(),
+            Some("Validation service unavailable")
+        );
+    }
+}
@@ -0,0 +1,238 @@
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+use tracing::{debug, error, warn};
+
+use super::{collection_config::CollectionConfig, dto, hooks::HookRejection, ApiErrors};
+
+/// Kind of write that is validated
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum WriteOperation {
+    Create,
+    Update,
+}
+
+impl WriteOperation {
+    fn as_str(&self) -> &'static str {
+        match self {
+            WriteOperation::Create => "create",
+            WriteOperation::Update => "update",
+        }
+    }
+}
+
+/// Sends the document to the validation webhook of the collection, if one is
+/// configured. A response with a status other than 2xx rejects the write with
+/// status 422 and the message of the service.
+///
+/// If the service cannot be reached, the write is accepted (`failOpen`) or
+/// rejected with status 503. Call it before the transaction of the write,
+/// the service must not hold database locks while it answers.
+pub(crate) async fn validate_with_webhook(
+    client: &reqwest::Client,
+    config: &CollectionConfig,
+    collection_name: &str,
+    operation: WriteOperation,
+    document: &dto::CollectionDocument,
+) -> Result<(), ApiErrors> {
+    let Some(webhook) = config.validation_webhook() else {
+        return Ok(());
+    };
+    debug!(
+        "Validating document {} with {}",
+        document.id(),
+        webhook.url()
+    );
+    let response = client
+        .post(webhook.url())
+        .json(&json!({
+            "collection": collection_name,
+            "operation": operation.as_str(),
+            "id": document.id(),
+            "f": document.fields(),
+        }))
+        .timeout(webhook.timeout())
+        .send()
+        .await;
+    match response {
+        Ok(response) if response.status().is_success() => Ok(()),
+        Ok(response) => {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            debug!(
+                "Document {} rejected by validation service with status {}",
+                document.id(),
+                status
+            );
+            Err(HookRejection::validation(&rejection_message(&body)).into())
+        }
+        Err(e) if webhook.fail_open() => {
+            warn!("Validation service {} failed: {}", webhook.url(), e);
+            Ok(())
+        }
+        Err(e) => {
+            error!("Validation service {} failed: {}", webhook.url(), e);
+            Err(HookRejection::new(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Validation service unavailable",
+            )
+            .into())
+        }
+    }
+}
+
+/// Uses the `message` of a JSON response or the plain text response.
+fn rejection_message(body: &str) -> String {
+    let message = serde_json::from_str::<Value>(body)
+        .ok()
+        .and_then(|value| {
+            value
+                .get("message")
+                .and_then(|m| m.as_str())
+                .map(String::from)
+        })
+        .unwrap_or_else(|| body.trim().to_string());
+    if message.is_empty() {
+        "Rejected by validation service".to_string()
+    } else {
+        message
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{routing::post, Json, Router};
+    use pretty_assertions::assert_eq;
+    use uuid::Uuid;
+
+    use super::*;
+
+    /// Accepts documents with a `vat_id`, rejects all others.
+    async fn mock_validation_server() -> String {
+        let app = Router::new().route(
+            "/validate",
+            post(|Json(body): Json<Value>| async move {
+                if body["f"].get("vat_id").is_some() {
+                    (StatusCode::OK, String::new())
+                } else {
+                    (
+                        StatusCode::BAD_REQUEST,
+                        json!({"message": "VAT id is missing"}).to_string(),
+                    )
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{addr}/validate")
+    }
+
+    async fn unreachable_url() -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+        format!("http://{addr}/validate")
+    }
+
+    fn config(url: &str, fail_open: bool) -> CollectionConfig {
+        CollectionConfig::from_json(&json!({
+            "validationWebhook": {"url": url, "timeoutMs": 1000, "failOpen": fail_open}
+        }))
+    }
+
+    fn rejection(result: Result<(), ApiErrors>) -> (StatusCode, String) {
+        match result {
+            Err(ApiErrors::HookRejection(rejection)) => {
+                (rejection.status(), rejection.message().to_string())
+            }
+            other => panic!("Expected a rejection, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn it_accepts_documents_accepted_by_the_service() {
+        // Arrange
+        let url = mock_validation_server().await;
+        let document = dto::CollectionDocument::new(Uuid::new_v4(), json!({"vat_id": "DE1"}));
+
+        // Act
+        let result = validate_with_webhook(
+            &reqwest::Client::new(),
+            &config(&url, false),
+            "customers",
+            WriteOperation::Create,
+            &document,
+        )
+        .await;
+
+        // Assert
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn it_rejects_documents_rejected_by_the_service() {
+        // Arrange
+        let url = mock_validation_server().await;
+        let document = dto::CollectionDocument::new(Uuid::new_v4(), json!({"title": "ACME"}));
+
+        // Act
+        let result = validate_with_webhook(
+            &reqwest::Client::new(),
+            &config(&url, false),
+            "customers",
+            WriteOperation::Update,
+            &document,
+        )
+        .await;
+
+        // Assert
+        assert_eq!(
+            rejection(result),
+            (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "VAT id is missing".to_string()
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn it_fails_closed_or_open_as_configured() {
+        // Arrange
+        let url = unreachable_url().await;
+        let document = dto::CollectionDocument::new(Uuid::new_v4(), json!({"vat_id": "DE1"}));
+
+        // Act
+        let closed = validate_with_webhook(
+            &reqwest::Client::new(),
+            &config(&url, false),
+            "customers",
+            WriteOperation::Create,
+            &document,
+        )
+        .await;
+        let open = validate_with_webhook(
+            &reqwest::Client::new(),
+            &config(&url, true),
+            "customers",
+            WriteOperation::Create,
+            &document,
+        )
+        .await;
+
+        // Assert
+        assert_eq!(
+            rejection(closed),
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Validation service unavailable".to_string()
+            )
+        );
+        assert!(open.is_ok());
+    }
+
+    #[test]
+    fn it_uses_the_plain_text_response_as_message() {
+        assert_eq!(rejection_message("Invalid order\n"), "Invalid order");
+        assert_eq!(rejection_message(""), "Rejected by validation service");
+    }
+}
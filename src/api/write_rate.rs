@@ -0,0 +1,136 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use uuid::Uuid;
+
+use super::{collection_config::CollectionConfig, ApiErrors};
+
+/// Length of the sliding window of the write rate limit
+const WRITE_RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Counts the recent writes of all users per collection
+#[derive(Debug, Default)]
+pub(crate) struct WriteRateLimiter {
+    writes: Mutex<HashMap<Uuid, VecDeque<Instant>>>,
+}
+
+impl WriteRateLimiter {
+    /// Records `count` writes into the collection, unless this exceeds the
+    /// `writeRateLimit` of the collection within the last minute. Rejected
+    /// writes are not recorded.
+    pub(crate) fn check(
+        &self,
+        collection: Uuid,
+        config: &CollectionConfig,
+        count: usize,
+    ) -> Result<(), ApiErrors> {
+        let Some(limit) = config.write_rate_limit() else {
+            return Ok(());
+        };
+        self.record(collection, limit, count, Instant::now())
+            .map_err(|wait| ApiErrors::TooManyRequests {
+                message: "Write rate limit of the collection exceeded".to_string(),
+                retry_after: Some(wait.as_secs_f64().ceil() as u64),
+            })
+    }
+
+    /// Returns the time until enough writes have left the window if the
+    /// writes exceed the limit.
+    fn record(
+        &self,
+        collection: Uuid,
+        limit: usize,
+        count: usize,
+        now: Instant,
+    ) -> Result<(), Duration> {
+        let mut writes = self.writes.lock().expect("Write rate limiter poisoned");
+        let recent = writes.entry(collection).or_default();
+        while recent
+            .front()
+            .is_some_and(|write| now.duration_since(*write) >= WRITE_RATE_WINDOW)
+        {
+            recent.pop_front();
+        }
+        if count > limit {
+            return Err(WRITE_RATE_WINDOW);
+        }
+        let excess = (recent.len() + count).saturating_sub(limit);
+        if excess > 0 {
+            let leaving = recent[excess - 1];
+            return Err(WRITE_RATE_WINDOW.saturating_sub(now.duration_since(leaving)));
+        }
+        recent.extend(std::iter::repeat_n(now, count));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn it_throttles_bursts_beyond_the_limit() {
+        // Arrange
+        let limiter = WriteRateLimiter::default();
+        let collection = Uuid::new_v4();
+        let start = Instant::now();
+
+        // Act
+        let within_limit: Vec<_> = (0..5)
+            .map(|i| limiter.record(collection, 5, 1, start + Duration::from_secs(i)))
+            .collect();
+        let burst = limiter.record(collection, 5, 1, start + Duration::from_secs(10));
+
+        // Assert
+        assert!(within_limit.iter().all(|r| r.is_ok()));
+        assert_eq!(burst, Err(Duration::from_secs(50)));
+    }
+
+    #[test]
+    fn it_permits_writes_after_the_window_slides() {
+        // Arrange
+        let limiter = WriteRateLimiter::default();
+        let collection = Uuid::new_v4();
+        let start = Instant::now();
+        limiter.record(collection, 2, 1, start).unwrap();
+        limiter
+            .record(collection, 2, 1, start + Duration::from_secs(30))
+            .unwrap();
+
+        // Act
+        let too_early = limiter.record(collection, 2, 1, start + Duration::from_secs(59));
+        let after_first = limiter.record(collection, 2, 1, start + Duration::from_secs(60));
+
+        // Assert
+        assert_eq!(too_early, Err(Duration::from_secs(1)));
+        assert_eq!(after_first, Ok(()));
+    }
+
+    #[test]
+    fn it_counts_batches_and_collections_separately() {
+        // Arrange
+        let limiter = WriteRateLimiter::default();
+        let collection = Uuid::new_v4();
+        let other_collection = Uuid::new_v4();
+        let now = Instant::now();
+
+        // Act
+        let batch = limiter.record(collection, 10, 8, now);
+        let exceeding_batch = limiter.record(collection, 10, 3, now);
+        let remaining = limiter.record(collection, 10, 2, now);
+        let other = limiter.record(other_collection, 10, 10, now);
+        let too_large = limiter.record(other_collection, 10, 11, now);
+
+        // Assert
+        assert_eq!(batch, Ok(()));
+        assert_eq!(exceeding_batch, Err(WRITE_RATE_WINDOW));
+        assert_eq!(remaining, Ok(()));
+        assert_eq!(other, Ok(()));
+        assert_eq!(too_large, Err(WRITE_RATE_WINDOW));
+    }
+}
@@ -2,28 +2,29 @@ use std::ops::Deref;
 
 use axum::{
     async_trait,
-    extract::{rejection::QueryRejection, FromRequestParts, Query},
+    extract::{
+        rejection::{JsonRejection, PathRejection, QueryRejection},
+        FromRequest, FromRequestParts, Path, Query, Request,
+    },
     http::request::Parts,
     http::StatusCode,
     response::{IntoResponse, Response},
 };
 use axum::{Json, RequestPartsExt};
 use serde::de::DeserializeOwned;
-use serde_json::json;
 use validator::Validate;
 
+use crate::api::{error_response::client_error, ApiErrors};
+
 #[derive(Debug, Clone, Copy, Default)]
 pub(crate) struct ValidatedQueryParams<T>(pub T);
 
 fn map_rejection(err: QueryRejection) -> Response {
     match err {
-        QueryRejection::FailedToDeserializeQueryString(inner) => (
+        QueryRejection::FailedToDeserializeQueryString(inner) => client_error(
             StatusCode::BAD_REQUEST,
-            Json(json!({
-                "message": format!("Failed to parse query string: {}", inner)
-            })),
-        )
-            .into_response(),
+            format!("Failed to parse query string: {}", inner),
+        ),
         _ => todo!(),
     }
 }
@@ -40,7 +41,7 @@ where
         let Query(query) = parts.extract::<Query<T>>().await.map_err(map_rejection)?;
         let validate_result = query.validate();
         if let Err(err) = validate_result {
-            Err((StatusCode::BAD_REQUEST, Json(err)).into_response())
+            Err(ApiErrors::from(err).into_response())
         } else {
             Ok(ValidatedQueryParams(query))
         }
@@ -54,3 +55,125 @@ impl<T> Deref for ValidatedQueryParams<T> {
         &self.0
     }
 }
+
+/// A JSON request body. Bodies that do not match the target type are
+/// rejected with status 400, all rejections have a JSON error body.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct JsonBody<T>(pub T);
+
+fn map_json_rejection(err: JsonRejection) -> Response {
+    match err {
+        JsonRejection::JsonDataError(inner) => {
+            client_error(StatusCode::BAD_REQUEST, inner.body_text())
+        }
+        _ => client_error(err.status(), err.body_text()),
+    }
+}
+
+#[async_trait]
+impl<T, S> FromRequest<S> for JsonBody<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(body) = Json::<T>::from_request(req, state)
+            .await
+            .map_err(map_json_rejection)?;
+        Ok(JsonBody(body))
+    }
+}
+
+/// Path parameters. Parameters that do not match the target type, e. g. an
+/// invalid UUID, are rejected with status 400 and a JSON error body.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct PathParams<T>(pub T);
+
+fn map_path_rejection(err: PathRejection) -> Response {
+    client_error(err.status(), err.body_text())
+}
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for PathParams<T>
+where
+    T: DeserializeOwned + Send,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Path(params) = Path::<T>::from_request_parts(parts, state)
+            .await
+            .map_err(map_path_rejection)?;
+        Ok(PathParams(params))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{body::Body, http::Request, routing::post, Router};
+    use pretty_assertions::assert_eq;
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    use super::*;
+
+    fn app() -> Router {
+        Router::new().route(
+            "/items/:id",
+            post(
+                |PathParams(id): PathParams<Uuid>, JsonBody(body): JsonBody<Value>| async move {
+                    format!("{id} {body}")
+                },
+            ),
+        )
+    }
+
+    async fn error_body(response: Response) -> Value {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn it_rejects_a_malformed_body_with_json() {
+        // Arrange
+        let request = Request::post(format!("/items/{}", Uuid::new_v4()))
+            .header("Content-Type", "application/json")
+            .body(Body::from("{\"title\":"))
+            .unwrap();
+
+        // Act
+        let response = app().oneshot(request).await.unwrap();
+
+        // Assert
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            error_body(response).await["error"]["code"],
+            json!("bad_request")
+        );
+    }
+
+    #[tokio::test]
+    async fn it_rejects_an_invalid_path_uuid_with_json() {
+        // Arrange
+        let request = Request::post("/items/not-a-uuid")
+            .header("Content-Type", "application/json")
+            .body(Body::from("{}"))
+            .unwrap();
+
+        // Act
+        let response = app().oneshot(request).await.unwrap();
+
+        // Assert
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            error_body(response).await["error"]["code"],
+            json!("bad_request")
+        );
+    }
+}
@@ -0,0 +1,210 @@
+use std::any::Any;
+
+use axum::{
+    extract::Request,
+    response::{IntoResponse, Response},
+    Router,
+};
+use lazy_static::lazy_static;
+use tower_http::{
+    catch_panic::CatchPanicLayer,
+    compression::{
+        predicate::{NotForContentType, Predicate, SizeAbove},
+        CompressionLayer,
+    },
+    request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
+    trace::TraceLayer,
+};
+use tracing::{error, Span};
+
+use crate::api::ApiErrors;
+
+const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Responses up to this size in bytes are sent uncompressed
+const DEFAULT_COMPRESSION_MIN_BYTES: u16 = 1024;
+
+lazy_static! {
+    static ref COMPRESSION_MIN_BYTES: u16 = std::env::var("FOLIVAFY_COMPRESSION_MIN_BYTES")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(DEFAULT_COMPRESSION_MIN_BYTES);
+}
+
+/// Adds a request id, tracing, gzip compression and the conversion of panics
+/// into error responses to all routes.
+///
+/// A request without an `x-request-id` header receives a new id, the id is
+/// returned in the response and is part of every log message of the request.
+///
+/// Responses larger than `FOLIVAFY_COMPRESSION_MIN_BYTES` are compressed if
+/// the request accepts `gzip`.
+pub(crate) fn request_layers(router: Router) -> Router {
+    router
+        .layer(CatchPanicLayer::custom(panic_response))
+        .layer(compression_layer(*COMPRESSION_MIN_BYTES))
+        .layer(PropagateRequestIdLayer::x_request_id())
+        .layer(TraceLayer::new_for_http().make_span_with(request_span))
+        .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+}
+
+fn compression_layer(min_bytes: u16) -> CompressionLayer<impl Predicate> {
+    CompressionLayer::new().compress_when(
+        SizeAbove::new(min_bytes)
+            .and(NotForContentType::GRPC)
+            .and(NotForContentType::IMAGES)
+            .and(NotForContentType::SSE),
+    )
+}
+
+fn request_span(request: &Request) -> Span {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|id| id.to_str().ok())
+        .unwrap_or_default();
+    tracing::info_span!(
+        "request",
+        method = %request.method(),
+        uri = %request.uri(),
+        request_id,
+    )
+}
+
+/// Logs the panic of a request handler and responds like any other internal
+/// server error.
+fn panic_response(panic: Box<dyn Any + Send + 'static>) -> Response {
+    let message = panic
+        .downcast_ref::<String>()
+        .map(String::as_str)
+        .or_else(|| panic.downcast_ref::<&str>().copied())
+        .unwrap_or("unknown cause");
+    error!("Request handler panicked: {message}");
+    ApiErrors::InternalServerError.into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use axum::{
+        body::{to_bytes, Body},
+        http::{header, StatusCode},
+        routing::get,
+    };
+    use flate2::read::GzDecoder;
+    use pretty_assertions::assert_eq;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    fn panicking_hook() -> String {
+        panic!("Hook failed")
+    }
+
+    fn app() -> Router {
+        request_layers(
+            Router::new()
+                .route("/panic", get(|| async { panicking_hook() }))
+                .route("/ok", get(|| async { "ok" }))
+                .route("/list", get(|| async { large_list() })),
+        )
+    }
+
+    fn large_list() -> String {
+        let items: Vec<String> = (0..200)
+            .map(|i| format!(r#"{{"id":{i},"f":{{"title":"Shape {i}"}}}}"#))
+            .collect();
+        format!(r#"{{"items":[{}]}}"#, items.join(","))
+    }
+
+    async fn get_list(accept_encoding: Option<&str>) -> (Option<String>, String) {
+        let mut request = Request::builder().uri("/list");
+        if let Some(accept_encoding) = accept_encoding {
+            request = request.header(header::ACCEPT_ENCODING, accept_encoding);
+        }
+        let response = app()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let encoding = response
+            .headers()
+            .get(header::CONTENT_ENCODING)
+            .map(|value| value.to_str().unwrap().to_string());
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body = match encoding.as_deref() {
+            Some("gzip") => {
+                let mut body = String::new();
+                GzDecoder::new(&bytes[..])
+                    .read_to_string(&mut body)
+                    .unwrap();
+                body
+            }
+            _ => String::from_utf8(bytes.to_vec()).unwrap(),
+        };
+        (encoding, body)
+    }
+
+    #[tokio::test]
+    async fn it_responds_to_a_panicking_hook_with_an_internal_server_error() {
+        // Arrange
+        let request = Request::builder()
+            .uri("/panic")
+            .header(REQUEST_ID_HEADER, "3f1c2b7a")
+            .body(Body::empty())
+            .unwrap();
+
+        // Act
+        let response = app().oneshot(request).await.unwrap();
+
+        // Assert
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(response.headers()[REQUEST_ID_HEADER], "3f1c2b7a");
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body, "Internal Server Error");
+    }
+
+    #[tokio::test]
+    async fn it_assigns_a_request_id() {
+        // Arrange
+        let request = Request::builder().uri("/ok").body(Body::empty()).unwrap();
+
+        // Act
+        let response = app().oneshot(request).await.unwrap();
+
+        // Assert
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().contains_key(REQUEST_ID_HEADER));
+    }
+
+    #[tokio::test]
+    async fn it_compresses_large_responses_if_gzip_is_accepted() {
+        // Act
+        let (compressed_encoding, compressed_body) = get_list(Some("gzip, deflate")).await;
+        let (plain_encoding, plain_body) = get_list(None).await;
+
+        // Assert
+        assert_eq!(compressed_encoding.as_deref(), Some("gzip"));
+        assert_eq!(plain_encoding, None);
+        assert_eq!(compressed_body, large_list());
+        assert_eq!(plain_body, large_list());
+    }
+
+    #[tokio::test]
+    async fn it_sends_small_responses_uncompressed() {
+        // Arrange
+        let request = Request::builder()
+            .uri("/ok")
+            .header(header::ACCEPT_ENCODING, "gzip")
+            .body(Body::empty())
+            .unwrap();
+
+        // Act
+        let response = app().oneshot(request).await.unwrap();
+
+        // Assert
+        assert!(!response.headers().contains_key(header::CONTENT_ENCODING));
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body, "ok");
+    }
+}
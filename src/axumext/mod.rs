@@ -1 +1,2 @@
 pub(crate) mod extractors;
+pub(crate) mod layers;
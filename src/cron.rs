@@ -1,7 +1,9 @@
 use anyhow::Result;
 use lazy_static::lazy_static;
 use sea_orm::{DatabaseTransaction, TransactionTrait};
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::{mpsc, oneshot};
 use tracing::{debug, error, info};
 use uuid::Uuid;
@@ -15,7 +17,7 @@ use crate::{
             get_collection_by_name, save_document_events_mails, DbGrantUpdate, DbListDocumentParams,
         },
         dto,
-        hooks::{HookCronContext, HookSuccessResult, Hooks},
+        hooks::{CronDefaultIntervalHookData, HookCronContext, HookSuccessResult, Hooks},
         select_document_for_update,
         types::Pagination,
         ApiErrors,
@@ -35,18 +37,47 @@ struct CronResult {
     trigger_cron: bool,
 }
 
+/// Last runs of the cron hooks with their own interval
+#[derive(Debug, Default)]
+struct CronSchedule {
+    last_runs: HashMap<CronDefaultIntervalHookData, Instant>,
+}
+
+impl CronSchedule {
+    /// Tells whether the hook is due at `now` and records the run. Hooks
+    /// without an interval run on every cron tick.
+    fn is_due(&mut self, hookdata: &CronDefaultIntervalHookData, now: Instant) -> bool {
+        let Some(interval) = hookdata.interval() else {
+            return true;
+        };
+        match self.last_runs.get(hookdata) {
+            Some(last_run) if now.duration_since(*last_run) < interval => false,
+            _ => {
+                self.last_runs.insert(hookdata.clone(), now);
+                true
+            }
+        }
+    }
+}
+
 async fn cron(
     db: sea_orm::DatabaseConnection,
     hooks: &Hooks,
     data_service: Arc<FolivafyDataService>,
+    schedule: &mut CronSchedule,
 ) -> CronResult {
     debug!("Running cron tasks");
     let mut trigger_cron = false;
     let cron_limit = 100;
     let pagination = Pagination::new(cron_limit, 0);
+    let now = Instant::now();
     let l = hooks.get_cron_default_interval_hooks();
     for (hookdata, listener) in l {
         let job_name = hookdata.job_name().to_string();
+        if !schedule.is_due(&hookdata, now) {
+            debug!("Skipping cron task: {job_name}, interval has not elapsed");
+            continue;
+        }
         let collection_name = hookdata.collection_name();
         let document_selector = hookdata.document_selector();
 
@@ -65,7 +96,10 @@ async fn cron(
                 .build();
             let (total, mut items) = list_documents(&db, &dbparams).await.unwrap_or_default();
             items.reverse();
-            info!("{job_name} found {total} documents, processing up to {cron_limit}");
+            info!(
+                "{job_name} found {} documents, processing up to {cron_limit}",
+                total.count
+            );
             loop {
                 if counter == 0 {
                     break;
@@ -142,6 +176,7 @@ pub(crate) fn setup_cron(
         tokio::time::sleep(std::time::Duration::from_secs(8)).await;
         debug!("Cron started");
         let loopdb = db;
+        let mut schedule = CronSchedule::default();
         loop {
             tokio::select! {
                 _ = &mut shutdown_cron_recv => {
@@ -150,7 +185,7 @@ pub(crate) fn setup_cron(
                 }
                 _ = interval.tick() => {
                     debug!("Cron tick");
-                    let r = cron(loopdb.clone(), &hooks, loop_data_service1.clone()).await;
+                    let r = cron(loopdb.clone(), &hooks, loop_data_service1.clone(), &mut schedule).await;
                     if r.trigger_cron {
                         debug!("Triggering cron task");
                         let _ = loop_immediate_cron_signal.send(()).await;
@@ -158,7 +193,7 @@ pub(crate) fn setup_cron(
                 }
                 _ = immediate_cron_recv.recv() => {
                     debug!("Immediate cron signal received");
-                    let r = cron(loopdb.clone(), &hooks, loop_data_service2.clone()).await;
+                    let r = cron(loopdb.clone(), &hooks, loop_data_service2.clone(), &mut schedule).await;
                     if r.trigger_cron {
                         debug!("Triggering cron task");
                         let _ = loop_immediate_cron_signal.send(()).await;
@@ -209,3 +244,52 @@ async fn check_modifications_and_update(
     })?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use pretty_assertions::assert_eq;
+
+    use crate::api::hooks::CronDocumentSelector;
+
+    use super::*;
+
+    fn hookdata(job_name: &str, interval: Option<Duration>) -> CronDefaultIntervalHookData {
+        CronDefaultIntervalHookData::new(
+            job_name,
+            "shapes",
+            CronDocumentSelector::ByFieldEqualsValue {
+                field: "state".to_string(),
+                value: "open".to_string(),
+            },
+            interval,
+        )
+    }
+
+    #[test]
+    fn it_runs_hooks_on_their_own_interval() {
+        // Arrange
+        let mut schedule = CronSchedule::default();
+        let hooks = [
+            hookdata("every-tick", None),
+            hookdata("quarter-hourly", Some(Duration::from_secs(15 * 60))),
+            hookdata("hourly", Some(Duration::from_secs(60 * 60))),
+        ];
+        let start = Instant::now();
+        let mut invocations = [0; 3];
+
+        // Act
+        for minute in (0..=120).step_by(5) {
+            let now = start + Duration::from_secs(minute * 60);
+            for (count, hook) in invocations.iter_mut().zip(hooks.iter()) {
+                if schedule.is_due(hook, now) {
+                    *count += 1;
+                }
+            }
+        }
+
+        // Assert
+        assert_eq!(invocations, [25, 9, 3]);
+    }
+}
@@ -1,7 +1,8 @@
 use anyhow::{anyhow, bail, Context, Result};
 use api::hooks::{staged_delete::add_staged_delete_hook, Hooks};
 use migration::{Migrator, MigratorTrait};
-use sea_orm::DatabaseConnection;
+use sea_orm::{ConnectOptions, DatabaseConnection};
+use std::{collections::HashMap, str::FromStr, time::Duration};
 use tokio::{sync::oneshot, task::JoinHandle};
 use tracing::{debug, error};
 
@@ -12,6 +13,7 @@ pub mod cron;
 mod mail;
 mod models;
 mod monitoring;
+pub mod publisher;
 
 pub(crate) struct BackgroundTask {
     name: String,
@@ -58,40 +60,277 @@ pub async fn danger_drop_database_tables(db: &DatabaseConnection) -> Result<(),
         .context("Database migration failed #2")
 }
 
-pub fn register_staged_delete_handler(mut hooks: Hooks) -> Result<Hooks, anyhow::Error> {
+/// Number of database connections without `FOLIVAFY_DB_MAX_CONNECTIONS`
+const DEFAULT_DB_MAX_CONNECTIONS: u32 = 50;
+
+/// Builds the options of the database connection pool from the environment
+/// variables, e. g. `db_connect_options(&std::env::vars().collect())`:
+///
+/// * `FOLIVAFY_DATABASE`: connection url
+/// * `FOLIVAFY_DB_MAX_CONNECTIONS`: maximum pool size, defaults to 50
+/// * `FOLIVAFY_DB_MIN_CONNECTIONS`: number of idle connections to keep
+/// * `FOLIVAFY_DB_CONNECT_TIMEOUT_SECS`: time limit to open a connection
+pub fn db_connect_options(vars: &HashMap<String, String>) -> Result<ConnectOptions> {
+    let url = vars
+        .get("FOLIVAFY_DATABASE")
+        .context("FOLIVAFY_DATABASE not set")?;
+    let max_connections =
+        parse_var(vars, "FOLIVAFY_DB_MAX_CONNECTIONS")?.unwrap_or(DEFAULT_DB_MAX_CONNECTIONS);
+    if max_connections == 0 {
+        bail!("FOLIVAFY_DB_MAX_CONNECTIONS must be at least 1");
+    }
+    let mut options = ConnectOptions::new(url.as_str());
+    options.max_connections(max_connections);
+    if let Some(min_connections) = parse_var(vars, "FOLIVAFY_DB_MIN_CONNECTIONS")? {
+        if min_connections > max_connections {
+            bail!("FOLIVAFY_DB_MIN_CONNECTIONS must not exceed the maximum of {max_connections}");
+        }
+        options.min_connections(min_connections);
+    }
+    if let Some(secs) = parse_var(vars, "FOLIVAFY_DB_CONNECT_TIMEOUT_SECS")? {
+        options.connect_timeout(Duration::from_secs(secs));
+    }
+    Ok(options)
+}
+
+/// Parses the variable if it is set.
+fn parse_var<T>(vars: &HashMap<String, String>, name: &str) -> Result<Option<T>>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    vars.get(name)
+        .map(|value| {
+            value
+                .trim()
+                .parse()
+                .with_context(|| format!("could not parse {name}"))
+        })
+        .transpose()
+}
+
+/// Staged deletion of a collection, configured with `FOLIVAFY_ENABLE_DELETION`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StagedDeleteConfig {
+    pub collection_name: String,
+    /// Days until a deleted document is moved to stage 2
+    pub days_stage_1: u16,
+    /// Days until a document in stage 2 is removed
+    pub days_stage_2: u16,
+}
+
+/// Registers the staged delete hooks for the collections of
+/// `FOLIVAFY_ENABLE_DELETION` and returns the parsed configuration.
+pub fn register_staged_delete_handler(
+    mut hooks: Hooks,
+) -> Result<(Hooks, Vec<StagedDeleteConfig>), anyhow::Error> {
     debug!("register_staged_delete_handler");
-    let rv = std::env::var("FOLIVAFY_ENABLE_DELETION");
-    if let Ok(v) = rv {
-        let v = v.trim();
-        if !v.is_empty() {
-            let v: Vec<&str> = v
-                .strip_prefix('(')
-                .ok_or_else(|| {
-                    anyhow!("FOLIVAFY_ENABLE_DELETION must start with an opening parenthesis.")
-                })?
-                .strip_suffix(')')
-                .ok_or_else(|| {
-                    anyhow!("FOLIVAFY_ENABLE_DELETION must end with a closing parenthesis.")
-                })?
-                .split("),(")
-                .collect();
-            for s in v {
-                debug!("Processing {s}");
-                let p: Vec<&str> = s.split(',').collect();
-                if p.len() != 3 {
-                    bail!("Invalid value {s} inside FOLIVAFY_ENABLE_DELETION");
-                }
-                let collection_name = p[0];
-                let days_stage_1: u16 = p[1]
-                    .parse()
-                    .map_err(|s| anyhow!("Invalid 1st number for {collection_name}: {s}"))?;
-                let days_stage_2: u16 = p[2]
-                    .parse()
-                    .map_err(|s| anyhow!("Invalid 2nd number for {collection_name}: {s}"))?;
-                add_staged_delete_hook(&mut hooks, collection_name, days_stage_1, days_stage_2);
-            }
+    let configs = match std::env::var("FOLIVAFY_ENABLE_DELETION") {
+        Ok(v) => parse_staged_delete_config(&v)?,
+        Err(_) => vec![],
+    };
+    for config in configs.iter() {
+        add_staged_delete_hook(
+            &mut hooks,
+            &config.collection_name,
+            config.days_stage_1,
+            config.days_stage_2,
+        );
+    }
+
+    Ok((hooks, configs))
+}
+
+/// Parses a value like `(collection-name,31,62),(other-collection,5,40)`.
+fn parse_staged_delete_config(value: &str) -> Result<Vec<StagedDeleteConfig>, anyhow::Error> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(vec![]);
+    }
+    let v: Vec<&str> = value
+        .strip_prefix('(')
+        .ok_or_else(|| anyhow!("FOLIVAFY_ENABLE_DELETION must start with an opening parenthesis."))?
+        .strip_suffix(')')
+        .ok_or_else(|| anyhow!("FOLIVAFY_ENABLE_DELETION must end with a closing parenthesis."))?
+        .split("),(")
+        .collect();
+    let mut configs = Vec::with_capacity(v.len());
+    for s in v {
+        debug!("Processing {s}");
+        let p: Vec<&str> = s.split(',').collect();
+        if p.len() != 3 {
+            bail!("Invalid value {s} inside FOLIVAFY_ENABLE_DELETION");
         }
+        let collection_name = p[0];
+        let days_stage_1: u16 = p[1]
+            .parse()
+            .map_err(|s| anyhow!("Invalid 1st number for {collection_name}: {s}"))?;
+        let days_stage_2: u16 = p[2]
+            .parse()
+            .map_err(|s| anyhow!("Invalid 2nd number for {collection_name}: {s}"))?;
+        configs.push(StagedDeleteConfig {
+            collection_name: collection_name.to_string(),
+            days_stage_1,
+            days_stage_2,
+        });
+    }
+    Ok(configs)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn options_error(pairs: &[(&str, &str)]) -> String {
+        db_connect_options(&vars(pairs)).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn it_applies_the_connection_settings() {
+        // Arrange
+        let vars = vars(&[
+            (
+                "FOLIVAFY_DATABASE",
+                "postgres://folivafy@localhost/folivafy",
+            ),
+            ("FOLIVAFY_DB_MAX_CONNECTIONS", "8"),
+            ("FOLIVAFY_DB_MIN_CONNECTIONS", " 2"),
+            ("FOLIVAFY_DB_CONNECT_TIMEOUT_SECS", "15"),
+        ]);
+
+        // Act
+        let options = db_connect_options(&vars).unwrap();
+
+        // Assert
+        assert_eq!(options.get_url(), "postgres://folivafy@localhost/folivafy");
+        assert_eq!(options.get_max_connections(), Some(8));
+        assert_eq!(options.get_min_connections(), Some(2));
+        assert_eq!(options.get_connect_timeout(), Some(Duration::from_secs(15)));
+    }
+
+    #[test]
+    fn it_defaults_to_50_connections() {
+        // Act
+        let options = db_connect_options(&vars(&[(
+            "FOLIVAFY_DATABASE",
+            "postgres://folivafy@localhost/folivafy",
+        )]))
+        .unwrap();
+
+        // Assert
+        assert_eq!(options.get_max_connections(), Some(50));
+        assert_eq!(options.get_min_connections(), None);
+        assert_eq!(options.get_connect_timeout(), None);
+    }
+
+    #[test]
+    fn it_rejects_invalid_connection_settings() {
+        let url = (
+            "FOLIVAFY_DATABASE",
+            "postgres://folivafy@localhost/folivafy",
+        );
+        assert_eq!(options_error(&[]), "FOLIVAFY_DATABASE not set");
+        assert_eq!(
+            options_error(&[url, ("FOLIVAFY_DB_MAX_CONNECTIONS", "many")]),
+            "could not parse FOLIVAFY_DB_MAX_CONNECTIONS"
+        );
+        assert_eq!(
+            options_error(&[url, ("FOLIVAFY_DB_MAX_CONNECTIONS", "0")]),
+            "FOLIVAFY_DB_MAX_CONNECTIONS must be at least 1"
+        );
+        assert_eq!(
+            options_error(&[url, ("FOLIVAFY_DB_MIN_CONNECTIONS", "-1")]),
+            "could not parse FOLIVAFY_DB_MIN_CONNECTIONS"
+        );
+        assert_eq!(
+            options_error(&[
+                url,
+                ("FOLIVAFY_DB_MAX_CONNECTIONS", "4"),
+                ("FOLIVAFY_DB_MIN_CONNECTIONS", "5")
+            ]),
+            "FOLIVAFY_DB_MIN_CONNECTIONS must not exceed the maximum of 4"
+        );
+        assert_eq!(
+            options_error(&[url, ("FOLIVAFY_DB_CONNECT_TIMEOUT_SECS", "1.5")]),
+            "could not parse FOLIVAFY_DB_CONNECT_TIMEOUT_SECS"
+        );
+    }
+
+    fn parse_error(value: &str) -> String {
+        parse_staged_delete_config(value).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn it_parses_two_collections() {
+        // Act
+        let configs =
+            parse_staged_delete_config("(collection-name,31,62),(other-collection,5,40)").unwrap();
+
+        // Assert
+        assert_eq!(
+            configs,
+            vec![
+                StagedDeleteConfig {
+                    collection_name: "collection-name".to_string(),
+                    days_stage_1: 31,
+                    days_stage_2: 62,
+                },
+                StagedDeleteConfig {
+                    collection_name: "other-collection".to_string(),
+                    days_stage_1: 5,
+                    days_stage_2: 40,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn it_accepts_an_empty_value() {
+        assert_eq!(parse_staged_delete_config(" ").unwrap(), vec![]);
     }
 
-    Ok(hooks)
+    #[test]
+    fn it_rejects_missing_parentheses() {
+        assert_eq!(
+            parse_error("shapes,1,1)"),
+            "FOLIVAFY_ENABLE_DELETION must start with an opening parenthesis."
+        );
+        assert_eq!(
+            parse_error("(shapes,1,1"),
+            "FOLIVAFY_ENABLE_DELETION must end with a closing parenthesis."
+        );
+    }
+
+    #[test]
+    fn it_rejects_a_wrong_field_count() {
+        assert_eq!(
+            parse_error("(shapes,1,1),(fluids,1)"),
+            "Invalid value fluids,1 inside FOLIVAFY_ENABLE_DELETION"
+        );
+        assert_eq!(
+            parse_error("(shapes,1,1,1)"),
+            "Invalid value shapes,1,1,1 inside FOLIVAFY_ENABLE_DELETION"
+        );
+    }
+
+    #[test]
+    fn it_rejects_non_numeric_days() {
+        assert_eq!(
+            parse_error("(shapes,one,1)"),
+            "Invalid 1st number for shapes: invalid digit found in string"
+        );
+        assert_eq!(
+            parse_error("(shapes,1,70000)"),
+            "Invalid 2nd number for shapes: number too large to fit in target type"
+        );
+    }
 }
@@ -1,4 +1,4 @@
-use std::{env, str::FromStr, sync::Arc};
+use std::{env, str::FromStr, sync::Arc, time::SystemTime};
 
 use anyhow::{anyhow, Context, Result};
 use async_trait::async_trait;
@@ -12,7 +12,7 @@ use lettre::{
     AsyncSmtpTransport, AsyncTransport, Tokio1Executor,
 };
 use sea_orm::{DatabaseConnection, DbErr, EntityTrait, Set};
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 use crate::api::{
     db::get_collection_by_name,
@@ -28,6 +28,12 @@ lazy_static! {
 }
 static FOLIVAFY_MAIL_COLLECTION_NAME: &str = "folivafy-mail";
 
+/// Retries of a mail before it is marked as failed, if not configured
+const DEFAULT_MAIL_MAX_RETRIES: u32 = 5;
+
+/// Seconds before the first retry of a mail, doubled for every further retry
+const MAIL_RETRY_DELAY_SECS: u64 = 60;
+
 #[derive(Debug, Clone)]
 pub(crate) enum SmtpConnectionType {
     Starttls,
@@ -145,13 +151,56 @@ impl SmtpClientConfiguration {
     }
 }
 
+fn mail_max_retries() -> Result<u32> {
+    env::var("FOLIVAFY_MAIL_MAX_RETRIES").map_or(Ok(DEFAULT_MAIL_MAX_RETRIES), |value| {
+        value
+            .parse()
+            .context("Invalid value for FOLIVAFY_MAIL_MAX_RETRIES")
+    })
+}
+
 struct Mailer {
     smtp_cfg: SmtpClientConfiguration,
+    max_retries: u32,
 }
 
 impl Mailer {
-    fn new(smtp_cfg: SmtpClientConfiguration) -> Self {
-        Self { smtp_cfg }
+    fn new(smtp_cfg: SmtpClientConfiguration, max_retries: u32) -> Self {
+        Self {
+            smtp_cfg,
+            max_retries,
+        }
+    }
+}
+
+/// Sends the mail once and records the attempt in the message.
+async fn send_attempt<T>(
+    transport: &T,
+    maildocument: &mut MailMessage,
+    email: lettre::Message,
+    now: u64,
+    max_retries: u32,
+) where
+    T: AsyncTransport + Sync,
+    T::Error: std::fmt::Debug,
+{
+    match transport.send(email).await {
+        Ok(_) => maildocument.set_sent(),
+        Err(e) => {
+            maildocument.set_failed_attempt(now, max_retries, MAIL_RETRY_DELAY_SECS);
+            match maildocument.next_attempt_at() {
+                Some(next_attempt_at) => warn!(
+                    "Could not send email (attempt {}), retrying at {next_attempt_at}: {:?}",
+                    maildocument.attempts(),
+                    e
+                ),
+                None => error!(
+                    "Could not send email after {} attempts, giving up: {:?}",
+                    maildocument.attempts(),
+                    e
+                ),
+            }
+        }
     }
 }
 
@@ -165,6 +214,20 @@ impl CronDefaultIntervalHook for Mailer {
                     error!("Cannot read mail message ({document_id}) from store: {}", e);
                     ApiErrors::InternalServerError
                 })?;
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        if !maildocument.is_due(now) {
+            debug!("Email {document_id} is not due for a retry");
+            return Ok(hooks::HookSuccessResult {
+                document: hooks::DocumentResult::NoUpdate,
+                grants: GrantSettings::NoChange,
+                events: vec![],
+                mails: vec![],
+                trigger_cron: false,
+            });
+        }
         let email = maildocument
             .build_mail(self.smtp_cfg.from_address.as_ref())
             .map_err(|e| {
@@ -173,35 +236,27 @@ impl CronDefaultIntervalHook for Mailer {
             })?;
         let mailer = self.smtp_cfg.transport();
 
-        // Send the email
-        match mailer.send(email).await {
-            Ok(_) => {
-                debug!("Email {document_id} sent successfully!");
-                maildocument.set_sent();
-                let o = dto::CollectionDocument::new(
-                    *document_id,
-                    serde_json::to_value(maildocument).unwrap(),
-                );
-                Ok(hooks::HookSuccessResult {
-                    document: hooks::DocumentResult::Store(o),
-                    grants: GrantSettings::NoChange,
-                    events: vec![],
-                    mails: vec![],
-                    trigger_cron: false,
-                })
-            }
-            Err(e) => {
-                error!("Could not send email: {:?}", e);
-                Err(ApiErrors::InternalServerError)
-            }
+        // Send the email, a failed attempt is stored with the message
+        send_attempt(&mailer, &mut maildocument, email, now, self.max_retries).await;
+        if let dto::MailMessageStatus::Sent(_) = maildocument.status() {
+            debug!("Email {document_id} sent successfully!");
         }
+        let o =
+            dto::CollectionDocument::new(*document_id, serde_json::to_value(maildocument).unwrap());
+        Ok(hooks::HookSuccessResult {
+            document: hooks::DocumentResult::Store(o),
+            grants: GrantSettings::NoChange,
+            events: vec![],
+            mails: vec![],
+            trigger_cron: false,
+        })
     }
 }
 
 pub(crate) async fn insert_mail_cron_hook(hooks: &Hooks, db: &DatabaseConnection) -> Result<()> {
     ensure_mail_collection_exists(db).await?;
     let smtp_cfg = SmtpClientConfiguration::from_env().await?;
-    let mailer = Arc::new(Mailer::new(smtp_cfg));
+    let mailer = Arc::new(Mailer::new(smtp_cfg, mail_max_retries()?));
     hooks.insert_cron_default_interval_hook(
         "folivafy mailer",
         "folivafy-mail",
@@ -236,3 +291,122 @@ async fn ensure_mail_collection_exists(db: &DatabaseConnection) -> Result<(), Db
         .await
         .map(|_| Ok(()))?
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use lettre::address::Envelope;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    /// Transport that fails the first `failures` attempts
+    struct FlakyTransport {
+        failures: u32,
+        attempts: AtomicU32,
+    }
+
+    #[async_trait]
+    impl AsyncTransport for FlakyTransport {
+        type Ok = ();
+        type Error = String;
+
+        async fn send_raw(
+            &self,
+            _envelope: &Envelope,
+            _email: &[u8],
+        ) -> std::result::Result<Self::Ok, Self::Error> {
+            let attempt = self.attempts.fetch_add(1, Ordering::SeqCst) + 1;
+            if attempt <= self.failures {
+                Err(format!("timeout in attempt {attempt}"))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    fn mail_message() -> MailMessage {
+        MailMessage::builder()
+            .set_to("recipient@example.com")
+            .set_subject("Reminder")
+            .set_body("Text", "<p>Text</p>")
+            .build()
+            .unwrap()
+    }
+
+    /// Runs send attempts like the cron job until the message is no longer
+    /// pending.
+    async fn send_until_done(
+        transport: &FlakyTransport,
+        maildocument: &mut MailMessage,
+        max_retries: u32,
+    ) {
+        let mut now = 1_700_000_000;
+        while *maildocument.status() == dto::MailMessageStatus::Pending {
+            let email = maildocument.build_mail("sender@example.com").unwrap();
+            send_attempt(transport, maildocument, email, now, max_retries).await;
+            now = maildocument.next_attempt_at().unwrap_or(now);
+        }
+    }
+
+    #[tokio::test]
+    async fn it_retries_transient_failures() {
+        // Arrange
+        let transport = FlakyTransport {
+            failures: 2,
+            attempts: AtomicU32::new(0),
+        };
+        let mut maildocument = mail_message();
+
+        // Act
+        send_until_done(&transport, &mut maildocument, 5).await;
+
+        // Assert
+        assert_eq!(transport.attempts.load(Ordering::SeqCst), 3);
+        assert_eq!(maildocument.attempts(), 3);
+        assert!(matches!(
+            maildocument.status(),
+            dto::MailMessageStatus::Sent(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn it_gives_up_after_max_retries() {
+        // Arrange
+        let transport = FlakyTransport {
+            failures: u32::MAX,
+            attempts: AtomicU32::new(0),
+        };
+        let mut maildocument = mail_message();
+
+        // Act
+        send_until_done(&transport, &mut maildocument, 2).await;
+
+        // Assert
+        assert_eq!(transport.attempts.load(Ordering::SeqCst), 3);
+        assert_eq!(maildocument.next_attempt_at(), None);
+        assert!(matches!(
+            maildocument.status(),
+            dto::MailMessageStatus::Failed(_)
+        ));
+    }
+
+    #[test]
+    fn it_doubles_the_retry_delay() {
+        // Arrange
+        let mut maildocument = mail_message();
+
+        // Act
+        maildocument.set_failed_attempt(1000, 5, MAIL_RETRY_DELAY_SECS);
+        let first = maildocument.next_attempt_at();
+        maildocument.set_failed_attempt(1000, 5, MAIL_RETRY_DELAY_SECS);
+        let second = maildocument.next_attempt_at();
+
+        // Assert
+        assert_eq!(first, Some(1060));
+        assert_eq!(second, Some(1120));
+        assert!(!maildocument.is_due(1119));
+        assert!(maildocument.is_due(1120));
+    }
+}
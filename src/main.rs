@@ -3,8 +3,12 @@ use std::env;
 use anyhow::Context;
 
 use dotenvy::dotenv;
-use folivafy::{api::hooks::Hooks, migrate, register_staged_delete_handler};
-use sea_orm::{ConnectOptions, Database};
+use folivafy::{
+    api::hooks::Hooks, db_connect_options, migrate, publisher::publisher_from_env,
+    register_staged_delete_handler,
+};
+use sea_orm::Database;
+use tracing::info;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 #[tokio::main]
@@ -18,13 +22,9 @@ async fn main() -> anyhow::Result<()> {
         .with(tracing_subscriber::fmt::layer())
         .init();
 
-    let db = Database::connect(
-        ConnectOptions::from(env::var("FOLIVAFY_DATABASE").context("FOLIVAFY_DATABASE not set")?)
-            .max_connections(50)
-            .to_owned(),
-    )
-    .await
-    .context("could not connect to database_url")?;
+    let db = Database::connect(db_connect_options(&env::vars().collect())?)
+        .await
+        .context("could not connect to database_url")?;
 
     migrate(&db).await?;
 
@@ -37,8 +37,15 @@ async fn main() -> anyhow::Result<()> {
                 .with_context(|| "could not parse FOLIVAFY_CRON_INTERVAL")?,
         ),
     );
-    let hooks = register_staged_delete_handler(Hooks::new())?;
-    folivafy::api::serve(db, hooks, cron_interval).await?;
+    let (hooks, staged_delete) = register_staged_delete_handler(Hooks::new())?;
+    for config in staged_delete {
+        info!(
+            "Staged deletion enabled for {} ({} / {} days)",
+            config.collection_name, config.days_stage_1, config.days_stage_2
+        );
+    }
+    let event_publisher = publisher_from_env().await?;
+    folivafy::api::serve(db, hooks, cron_interval, event_publisher).await?;
 
     Ok(())
 }
@@ -199,6 +199,417 @@ impl std::str::FromStr for Collection {
     }
 }
 
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+    validator::Validate,
+)]
+#[schema(
+    description = "Change of a document within a collection",
+    examples(
+        json!({
+            "seq": 1043,
+            "id": "9f818bff-a1b4-487a-9706-29a5ac1cf898",
+            "deleted": false,
+            "f": {
+                "title": "Rectangle",
+                "price": 14
+            }
+        })
+    )
+)]
+pub struct CollectionChange {
+    /// Sequence number of the change
+    #[serde(rename = "seq")]
+    #[schema(examples(1043))]
+    pub seq: i64,
+
+    /// Document identifier
+    #[serde(rename = "id")]
+    #[schema(examples("9f818bff-a1b4-487a-9706-29a5ac1cf898"), format = Uuid)]
+    pub id: uuid::Uuid,
+
+    /// Is the document deleted? Deleted documents are returned without fields.
+    #[serde(rename = "deleted")]
+    pub deleted: bool,
+
+    /// Field data, not provided for deleted documents
+    #[serde(rename = "f")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub f: Option<serde_json::Value>,
+}
+
+impl CollectionChange {
+    #[allow(clippy::new_without_default)]
+    #[allow(dead_code)]
+    pub fn new(seq: i64, id: uuid::Uuid, deleted: bool) -> CollectionChange {
+        CollectionChange {
+            seq,
+            id,
+            deleted,
+            f: None,
+        }
+    }
+}
+
+/// Converts the CollectionChange value to the Query Parameters representation (style=form, explode=false)
+/// specified in https://swagger.io/docs/specification/serialization/
+/// Should be implemented in a serde serializer
+impl std::string::ToString for CollectionChange {
+    fn to_string(&self) -> String {
+        let params: Vec<Option<String>> = vec![
+            Some("seq".to_string()),
+            Some(self.seq.to_string()),
+            // Skipping id in query parameter serialization
+            Some("deleted".to_string()),
+            Some(self.deleted.to_string()),
+            // Skipping f in query parameter serialization
+        ];
+
+        params.into_iter().flatten().collect::<Vec<_>>().join(",")
+    }
+}
+
+/// Converts Query Parameters representation (style=form, explode=false) to a CollectionChange value
+/// as specified in https://swagger.io/docs/specification/serialization/
+/// Should be implemented in a serde deserializer
+impl std::str::FromStr for CollectionChange {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        /// An intermediate representation of the struct to use for parsing.
+        #[derive(Default)]
+        #[allow(dead_code)]
+        struct IntermediateRep {
+            pub seq: Vec<i64>,
+            pub id: Vec<uuid::Uuid>,
+            pub deleted: Vec<bool>,
+            pub f: Vec<serde_json::Value>,
+        }
+
+        let mut intermediate_rep = IntermediateRep::default();
+
+        // Parse into intermediate representation
+        let mut string_iter = s.split(',');
+        let mut key_result = string_iter.next();
+
+        while key_result.is_some() {
+            let val = match string_iter.next() {
+                Some(x) => x,
+                None => {
+                    return std::result::Result::Err(
+                        "Missing value while parsing CollectionChange".to_string(),
+                    )
+                }
+            };
+
+            if let Some(key) = key_result {
+                #[allow(clippy::match_single_binding)]
+                match key {
+                    #[allow(clippy::redundant_clone)]
+                    "seq" => intermediate_rep.seq.push(
+                        <i64 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?,
+                    ),
+                    #[allow(clippy::redundant_clone)]
+                    "id" => intermediate_rep.id.push(
+                        <uuid::Uuid as std::str::FromStr>::from_str(val)
+                            .map_err(|x| x.to_string())?,
+                    ),
+                    #[allow(clippy::redundant_clone)]
+                    "deleted" => intermediate_rep.deleted.push(
+                        <bool as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?,
+                    ),
+                    #[allow(clippy::redundant_clone)]
+                    "f" => intermediate_rep.f.push(
+                        <serde_json::Value as std::str::FromStr>::from_str(val)
+                            .map_err(|x| x.to_string())?,
+                    ),
+                    _ => {
+                        return std::result::Result::Err(
+                            "Unexpected key while parsing CollectionChange".to_string(),
+                        )
+                    }
+                }
+            }
+
+            // Get the next key
+            key_result = string_iter.next();
+        }
+
+        // Use the intermediate representation to return the struct
+        std::result::Result::Ok(CollectionChange {
+            seq: intermediate_rep
+                .seq
+                .into_iter()
+                .next()
+                .ok_or_else(|| "seq missing in CollectionChange".to_string())?,
+            id: intermediate_rep
+                .id
+                .into_iter()
+                .next()
+                .ok_or_else(|| "id missing in CollectionChange".to_string())?,
+            deleted: intermediate_rep
+                .deleted
+                .into_iter()
+                .next()
+                .ok_or_else(|| "deleted missing in CollectionChange".to_string())?,
+            f: intermediate_rep.f.into_iter().next(),
+        })
+    }
+}
+
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+    validator::Validate,
+)]
+pub struct CollectionChangesList {
+    /// The sequence number from the request
+    #[serde(rename = "since")]
+    #[validate(range(min = 0))]
+    #[schema(examples(1042))]
+    pub since: i64,
+
+    /// Sequence number of the last change in the list, use as `since` for the next request
+    #[serde(rename = "lastSeq")]
+    #[validate(range(min = 0))]
+    #[schema(examples(1043))]
+    pub last_seq: i64,
+
+    /// Are there more changes after `lastSeq`?
+    #[serde(rename = "more")]
+    pub more: bool,
+
+    #[serde(rename = "items")]
+    pub items: Vec<models::CollectionChange>,
+}
+
+impl CollectionChangesList {
+    #[allow(clippy::new_without_default)]
+    #[allow(dead_code)]
+    pub fn new(items: Vec<models::CollectionChange>) -> CollectionChangesList {
+        CollectionChangesList {
+            since: 0,
+            last_seq: 0,
+            more: false,
+            items,
+        }
+    }
+}
+
+/// Converts the CollectionChangesList value to the Query Parameters representation (style=form, explode=false)
+/// specified in https://swagger.io/docs/specification/serialization/
+/// Should be implemented in a serde serializer
+impl std::string::ToString for CollectionChangesList {
+    fn to_string(&self) -> String {
+        let params: Vec<Option<String>> = vec![
+            Some("since".to_string()),
+            Some(self.since.to_string()),
+            Some("lastSeq".to_string()),
+            Some(self.last_seq.to_string()),
+            Some("more".to_string()),
+            Some(self.more.to_string()),
+            // Skipping items in query parameter serialization
+        ];
+
+        params.into_iter().flatten().collect::<Vec<_>>().join(",")
+    }
+}
+
+/// Converts Query Parameters representation (style=form, explode=false) to a CollectionChangesList value
+/// as specified in https://swagger.io/docs/specification/serialization/
+/// Should be implemented in a serde deserializer
+impl std::str::FromStr for CollectionChangesList {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        /// An intermediate representation of the struct to use for parsing.
+        #[derive(Default)]
+        #[allow(dead_code)]
+        struct IntermediateRep {
+            pub since: Vec<i64>,
+            pub last_seq: Vec<i64>,
+            pub more: Vec<bool>,
+            pub items: Vec<Vec<models::CollectionChange>>,
+        }
+
+        let mut intermediate_rep = IntermediateRep::default();
+
+        // Parse into intermediate representation
+        let mut string_iter = s.split(',');
+        let mut key_result = string_iter.next();
+
+        while key_result.is_some() {
+            let val = match string_iter.next() {
+                Some(x) => x,
+                None => {
+                    return std::result::Result::Err(
+                        "Missing value while parsing CollectionChangesList".to_string(),
+                    )
+                }
+            };
+
+            if let Some(key) = key_result {
+                #[allow(clippy::match_single_binding)]
+                match key {
+                    #[allow(clippy::redundant_clone)]
+                    "since" => intermediate_rep.since.push(
+                        <i64 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?,
+                    ),
+                    #[allow(clippy::redundant_clone)]
+                    "lastSeq" => intermediate_rep.last_seq.push(
+                        <i64 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?,
+                    ),
+                    #[allow(clippy::redundant_clone)]
+                    "more" => intermediate_rep.more.push(
+                        <bool as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?,
+                    ),
+                    "items" => return std::result::Result::Err(
+                        "Parsing a container in this style is not supported in CollectionChangesList"
+                            .to_string(),
+                    ),
+                    _ => {
+                        return std::result::Result::Err(
+                            "Unexpected key while parsing CollectionChangesList".to_string(),
+                        )
+                    }
+                }
+            }
+
+            // Get the next key
+            key_result = string_iter.next();
+        }
+
+        // Use the intermediate representation to return the struct
+        std::result::Result::Ok(CollectionChangesList {
+            since: intermediate_rep
+                .since
+                .into_iter()
+                .next()
+                .ok_or_else(|| "since missing in CollectionChangesList".to_string())?,
+            last_seq: intermediate_rep
+                .last_seq
+                .into_iter()
+                .next()
+                .ok_or_else(|| "lastSeq missing in CollectionChangesList".to_string())?,
+            more: intermediate_rep
+                .more
+                .into_iter()
+                .next()
+                .ok_or_else(|| "more missing in CollectionChangesList".to_string())?,
+            items: intermediate_rep
+                .items
+                .into_iter()
+                .next()
+                .ok_or_else(|| "items missing in CollectionChangesList".to_string())?,
+        })
+    }
+}
+
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+    validator::Validate,
+)]
+pub struct CollectionItemAncestors {
+    /// Ancestor documents, starting with the parent of the document
+    #[serde(rename = "items")]
+    pub items: Vec<models::CollectionItem>,
+}
+
+impl CollectionItemAncestors {
+    #[allow(clippy::new_without_default)]
+    #[allow(dead_code)]
+    pub fn new(items: Vec<models::CollectionItem>) -> CollectionItemAncestors {
+        CollectionItemAncestors { items }
+    }
+}
+
+/// Converts the CollectionItemAncestors value to the Query Parameters representation (style=form, explode=false)
+/// specified in https://swagger.io/docs/specification/serialization/
+/// Should be implemented in a serde serializer
+impl std::string::ToString for CollectionItemAncestors {
+    fn to_string(&self) -> String {
+        let params: Vec<Option<String>> = vec![
+            // Skipping items in query parameter serialization
+        ];
+
+        params.into_iter().flatten().collect::<Vec<_>>().join(",")
+    }
+}
+
+/// Converts Query Parameters representation (style=form, explode=false) to a CollectionItemAncestors value
+/// as specified in https://swagger.io/docs/specification/serialization/
+/// Should be implemented in a serde deserializer
+impl std::str::FromStr for CollectionItemAncestors {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        /// An intermediate representation of the struct to use for parsing.
+        #[derive(Default)]
+        #[allow(dead_code)]
+        struct IntermediateRep {
+            pub items: Vec<Vec<models::CollectionItem>>,
+        }
+
+        let intermediate_rep = IntermediateRep::default();
+
+        // Parse into intermediate representation
+        let mut string_iter = s.split(',');
+        let mut key_result = string_iter.next();
+
+        while key_result.is_some() {
+            let _val = match string_iter.next() {
+                Some(x) => x,
+                None => {
+                    return std::result::Result::Err(
+                        "Missing value while parsing CollectionItemAncestors".to_string(),
+                    )
+                }
+            };
+
+            if let Some(key) = key_result {
+                #[allow(clippy::match_single_binding)]
+                match key {
+                    "items" => return std::result::Result::Err(
+                        "Parsing a container in this style is not supported in CollectionItemAncestors"
+                            .to_string(),
+                    ),
+                    _ => {
+                        return std::result::Result::Err(
+                            "Unexpected key while parsing CollectionItemAncestors".to_string(),
+                        )
+                    }
+                }
+            }
+
+            // Get the next key
+            key_result = string_iter.next();
+        }
+
+        // Use the intermediate representation to return the struct
+        std::result::Result::Ok(CollectionItemAncestors {
+            items: intermediate_rep
+                .items
+                .into_iter()
+                .next()
+                .ok_or_else(|| "items missing in CollectionItemAncestors".to_string())?,
+        })
+    }
+}
+
 #[derive(
     Debug,
     Clone,
@@ -239,13 +650,34 @@ pub struct CollectionItem {
         )
     )]
     pub f: serde_json::Value,
+
+    /// Is the document deleted? Only provided when deleted documents are included.
+    #[serde(rename = "deleted")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted: Option<bool>,
+
+    /// Timestamp of the deletion. Only provided when deleted documents are included.
+    #[serde(rename = "deletedAt")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<String>,
+
+    /// Name of the collection that contains the document. Only provided when searching multiple collections.
+    #[serde(rename = "collection")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub collection: Option<String>,
 }
 
 impl CollectionItem {
     #[allow(clippy::new_without_default)]
     #[allow(dead_code)]
     pub fn new(id: uuid::Uuid, f: serde_json::Value) -> CollectionItem {
-        CollectionItem { id, f }
+        CollectionItem {
+            id,
+            f,
+            deleted: None,
+            deleted_at: None,
+            collection: None,
+        }
     }
 }
 
@@ -258,7 +690,15 @@ impl std::string::ToString for CollectionItem {
             // Skipping id in query parameter serialization
 
             // Skipping f in query parameter serialization
-
+            self.deleted
+                .as_ref()
+                .map(|deleted| ["deleted".to_string(), deleted.to_string()].join(",")),
+            self.deleted_at
+                .as_ref()
+                .map(|deleted_at| ["deletedAt".to_string(), deleted_at.to_string()].join(",")),
+            self.collection
+                .as_ref()
+                .map(|collection| ["collection".to_string(), collection.to_string()].join(",")),
         ];
 
         params.into_iter().flatten().collect::<Vec<_>>().join(",")
@@ -278,6 +718,9 @@ impl std::str::FromStr for CollectionItem {
         struct IntermediateRep {
             pub id: Vec<uuid::Uuid>,
             pub f: Vec<serde_json::Value>,
+            pub deleted: Vec<bool>,
+            pub deleted_at: Vec<String>,
+            pub collection: Vec<String>,
         }
 
         let mut intermediate_rep = IntermediateRep::default();
@@ -309,6 +752,18 @@ impl std::str::FromStr for CollectionItem {
                         <serde_json::Value as std::str::FromStr>::from_str(val)
                             .map_err(|x| x.to_string())?,
                     ),
+                    #[allow(clippy::redundant_clone)]
+                    "deleted" => intermediate_rep.deleted.push(
+                        <bool as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?,
+                    ),
+                    #[allow(clippy::redundant_clone)]
+                    "deletedAt" => intermediate_rep.deleted_at.push(
+                        <String as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?,
+                    ),
+                    #[allow(clippy::redundant_clone)]
+                    "collection" => intermediate_rep.collection.push(
+                        <String as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?,
+                    ),
                     _ => {
                         return std::result::Result::Err(
                             "Unexpected key while parsing CollectionItem".to_string(),
@@ -333,6 +788,9 @@ impl std::str::FromStr for CollectionItem {
                 .into_iter()
                 .next()
                 .ok_or_else(|| "f missing in CollectionItem".to_string())?,
+            deleted: intermediate_rep.deleted.into_iter().next(),
+            deleted_at: intermediate_rep.deleted_at.into_iter().next(),
+            collection: intermediate_rep.collection.into_iter().next(),
         })
     }
 }
@@ -393,6 +851,16 @@ pub struct CollectionItemDetails {
 
     #[serde(rename = "e")]
     pub e: Vec<models::CollectionItemEvent>,
+
+    /// Is the document deleted? Only provided for deleted documents, which are read with `includeDeleted`.
+    #[serde(rename = "deleted")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted: Option<bool>,
+
+    /// Timestamp of the deletion. Only provided for deleted documents.
+    #[serde(rename = "deletedAt")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<String>,
 }
 
 impl CollectionItemDetails {
@@ -403,7 +871,13 @@ impl CollectionItemDetails {
         f: serde_json::Value,
         e: Vec<models::CollectionItemEvent>,
     ) -> CollectionItemDetails {
-        CollectionItemDetails { id, f, e }
+        CollectionItemDetails {
+            id,
+            f,
+            e,
+            deleted: None,
+            deleted_at: None,
+        }
     }
 }
 
@@ -418,7 +892,12 @@ impl std::string::ToString for CollectionItemDetails {
             // Skipping f in query parameter serialization
 
             // Skipping e in query parameter serialization
-
+            self.deleted
+                .as_ref()
+                .map(|deleted| ["deleted".to_string(), deleted.to_string()].join(",")),
+            self.deleted_at
+                .as_ref()
+                .map(|deleted_at| ["deletedAt".to_string(), deleted_at.to_string()].join(",")),
         ];
 
         params.into_iter().flatten().collect::<Vec<_>>().join(",")
@@ -439,6 +918,8 @@ impl std::str::FromStr for CollectionItemDetails {
             pub id: Vec<uuid::Uuid>,
             pub f: Vec<serde_json::Value>,
             pub e: Vec<Vec<models::CollectionItemEvent>>,
+            pub deleted: Vec<bool>,
+            pub deleted_at: Vec<String>,
         }
 
         let mut intermediate_rep = IntermediateRep::default();
@@ -465,6 +946,10 @@ impl std::str::FromStr for CollectionItemDetails {
                     #[allow(clippy::redundant_clone)]
                     "f" => intermediate_rep.f.push(<serde_json::Value as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
                     "e" => return std::result::Result::Err("Parsing a container in this style is not supported in CollectionItemDetails".to_string()),
+                    #[allow(clippy::redundant_clone)]
+                    "deleted" => intermediate_rep.deleted.push(<bool as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
+                    #[allow(clippy::redundant_clone)]
+                    "deletedAt" => intermediate_rep.deleted_at.push(<String as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
                     _ => return std::result::Result::Err("Unexpected key while parsing CollectionItemDetails".to_string())
                 }
             }
@@ -490,6 +975,8 @@ impl std::str::FromStr for CollectionItemDetails {
                 .into_iter()
                 .next()
                 .ok_or_else(|| "e missing in CollectionItemDetails".to_string())?,
+            deleted: intermediate_rep.deleted.into_iter().next(),
+            deleted_at: intermediate_rep.deleted_at.into_iter().next(),
         })
     }
 }
@@ -515,6 +1002,11 @@ pub struct CollectionItemEvent {
     #[serde(rename = "category")]
     pub category: i32,
 
+    /// Human readable name of the category, if configured for the collection
+    #[serde(rename = "categoryName")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category_name: Option<String>,
+
     /// Event data
     #[serde(rename = "e")]
     pub e: serde_json::Value,
@@ -533,6 +1025,7 @@ impl CollectionItemEvent {
             id,
             ts,
             category,
+            category_name: None,
             e,
         }
     }
@@ -549,6 +1042,9 @@ impl std::string::ToString for CollectionItemEvent {
             // Skipping ts in query parameter serialization
             Some("category".to_string()),
             Some(self.category.to_string()),
+            self.category_name.as_ref().map(|category_name| {
+                ["categoryName".to_string(), category_name.to_string()].join(",")
+            }),
             // Skipping e in query parameter serialization
         ];
 
@@ -570,6 +1066,7 @@ impl std::str::FromStr for CollectionItemEvent {
             pub id: Vec<u32>,
             pub ts: Vec<chrono::DateTime<chrono::Utc>>,
             pub category: Vec<i32>,
+            pub category_name: Vec<String>,
             pub e: Vec<serde_json::Value>,
         }
 
@@ -606,6 +1103,10 @@ impl std::str::FromStr for CollectionItemEvent {
                         <i32 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?,
                     ),
                     #[allow(clippy::redundant_clone)]
+                    "categoryName" => intermediate_rep.category_name.push(
+                        <String as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?,
+                    ),
+                    #[allow(clippy::redundant_clone)]
                     "e" => intermediate_rep.e.push(
                         <serde_json::Value as std::str::FromStr>::from_str(val)
                             .map_err(|x| x.to_string())?,
@@ -639,6 +1140,7 @@ impl std::str::FromStr for CollectionItemEvent {
                 .into_iter()
                 .next()
                 .ok_or_else(|| "category missing in CollectionItemEvent".to_string())?,
+            category_name: intermediate_rep.category_name.into_iter().next(),
             e: intermediate_rep
                 .e
                 .into_iter()
@@ -659,9 +1161,9 @@ impl std::str::FromStr for CollectionItemEvent {
 )]
 pub struct CollectionItemsList {
     #[serde(rename = "limit")]
-    #[validate(range(min = 1, max = 250))]
-    #[schema(examples(100), minimum = 1, maximum = 250)]
-    pub limit: u8,
+    #[validate(range(min = 1))]
+    #[schema(examples(100), minimum = 1)]
+    pub limit: u16,
 
     #[serde(rename = "offset")]
     #[validate(range(min = 0))]
@@ -674,6 +1176,26 @@ pub struct CollectionItemsList {
 
     #[serde(rename = "items")]
     pub items: Vec<models::CollectionItem>,
+
+    /// Is `total` an estimate? Only returned with `approximateCount=true`
+    #[serde(rename = "totalApproximate")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_approximate: Option<bool>,
+
+    /// Filter as parsed by the server, only returned with `explainFilter=true`
+    #[serde(rename = "appliedFilter")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub applied_filter: Option<serde_json::Value>,
+
+    /// Cursor of the next page, only returned with `cursor` if more documents may follow
+    #[serde(rename = "nextCursor")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+
+    /// Do more documents follow this page? Only returned with `withTotal=false`
+    #[serde(rename = "hasMore")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub has_more: Option<bool>,
 }
 
 impl CollectionItemsList {
@@ -685,6 +1207,10 @@ impl CollectionItemsList {
             offset: 0,
             total: 0,
             items,
+            total_approximate: None,
+            applied_filter: None,
+            next_cursor: None,
+            has_more: None,
         }
     }
 }
@@ -702,6 +1228,19 @@ impl std::string::ToString for CollectionItemsList {
             Some("total".to_string()),
             Some(self.total.to_string()),
             // Skipping items in query parameter serialization
+            self.total_approximate.as_ref().map(|total_approximate| {
+                [
+                    "totalApproximate".to_string(),
+                    total_approximate.to_string(),
+                ]
+                .join(",")
+            }),
+            // Skipping appliedFilter in query parameter serialization
+            self.next_cursor
+                .as_ref()
+                .map(|next_cursor| ["nextCursor".to_string(), next_cursor.to_string()].join(",")),
+            self.has_more
+                .map(|has_more| ["hasMore".to_string(), has_more.to_string()].join(",")),
         ];
 
         params.into_iter().flatten().collect::<Vec<_>>().join(",")
@@ -719,10 +1258,14 @@ impl std::str::FromStr for CollectionItemsList {
         #[derive(Default)]
         #[allow(dead_code)]
         struct IntermediateRep {
-            pub limit: Vec<u8>,
+            pub limit: Vec<u16>,
             pub offset: Vec<u32>,
             pub total: Vec<u32>,
             pub items: Vec<Vec<models::CollectionItem>>,
+            pub total_approximate: Vec<bool>,
+            pub applied_filter: Vec<serde_json::Value>,
+            pub next_cursor: Vec<String>,
+            pub has_more: Vec<bool>,
         }
 
         let mut intermediate_rep = IntermediateRep::default();
@@ -745,9 +1288,9 @@ impl std::str::FromStr for CollectionItemsList {
                 #[allow(clippy::match_single_binding)]
                 match key {
                     #[allow(clippy::redundant_clone)]
-                    "limit" => intermediate_rep
-                        .limit
-                        .push(<u8 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
+                    "limit" => intermediate_rep.limit.push(
+                        <u16 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?,
+                    ),
                     #[allow(clippy::redundant_clone)]
                     "offset" => intermediate_rep.offset.push(
                         <u32 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?,
@@ -760,6 +1303,23 @@ impl std::str::FromStr for CollectionItemsList {
                         "Parsing a container in this style is not supported in CollectionItemsList"
                             .to_string(),
                     ),
+                    #[allow(clippy::redundant_clone)]
+                    "totalApproximate" => intermediate_rep.total_approximate.push(
+                        <bool as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?,
+                    ),
+                    #[allow(clippy::redundant_clone)]
+                    "appliedFilter" => intermediate_rep.applied_filter.push(
+                        <serde_json::Value as std::str::FromStr>::from_str(val)
+                            .map_err(|x| x.to_string())?,
+                    ),
+                    #[allow(clippy::redundant_clone)]
+                    "nextCursor" => intermediate_rep.next_cursor.push(
+                        <String as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?,
+                    ),
+                    #[allow(clippy::redundant_clone)]
+                    "hasMore" => intermediate_rep.has_more.push(
+                        <bool as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?,
+                    ),
                     _ => {
                         return std::result::Result::Err(
                             "Unexpected key while parsing CollectionItemsList".to_string(),
@@ -794,6 +1354,10 @@ impl std::str::FromStr for CollectionItemsList {
                 .into_iter()
                 .next()
                 .ok_or_else(|| "items missing in CollectionItemsList".to_string())?,
+            total_approximate: intermediate_rep.total_approximate.into_iter().next(),
+            applied_filter: intermediate_rep.applied_filter.into_iter().next(),
+            next_cursor: intermediate_rep.next_cursor.into_iter().next(),
+            has_more: intermediate_rep.has_more.into_iter().next(),
         })
     }
 }
@@ -851,9 +1415,9 @@ impl std::ops::DerefMut for CollectionName {
 )]
 pub struct CollectionsList {
     #[serde(rename = "limit")]
-    #[validate(range(min = 1, max = 250))]
-    #[schema(examples(100), minimum = 1, maximum = 250)]
-    pub limit: u8,
+    #[validate(range(min = 1))]
+    #[schema(examples(100), minimum = 1)]
+    pub limit: u16,
 
     #[serde(rename = "offset")]
     #[validate(range(min = 0))]
@@ -911,7 +1475,7 @@ impl std::str::FromStr for CollectionsList {
         #[derive(Default)]
         #[allow(dead_code)]
         struct IntermediateRep {
-            pub limit: Vec<u8>,
+            pub limit: Vec<u16>,
             pub offset: Vec<u32>,
             pub total: Vec<u32>,
             pub items: Vec<Vec<models::Collection>>,
@@ -937,9 +1501,9 @@ impl std::str::FromStr for CollectionsList {
                 #[allow(clippy::match_single_binding)]
                 match key {
                     #[allow(clippy::redundant_clone)]
-                    "limit" => intermediate_rep
-                        .limit
-                        .push(<u8 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
+                    "limit" => intermediate_rep.limit.push(
+                        <u16 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?,
+                    ),
                     #[allow(clippy::redundant_clone)]
                     "offset" => intermediate_rep.offset.push(
                         <u32 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?,
@@ -1031,6 +1595,17 @@ pub struct CreateCollectionRequest {
     /// Owner access only?
     #[serde(rename = "oao")]
     pub oao: bool,
+
+    /// Settings of the collection
+    #[serde(rename = "config")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub config: Option<crate::api::collection_config::CollectionConfig>,
+
+    /// Document fields in dotted notation that are indexed for filters, e. g. `customer.name`
+    #[serde(rename = "indexedFields")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(examples(json!(["status", "customer.name"])))]
+    pub indexed_fields: Option<Vec<String>>,
 }
 
 const COLLECTIONREQUEST_NAME_PATTERN: &str = r"^[a-z][-a-z0-9]*$";
@@ -1042,7 +1617,13 @@ impl CreateCollectionRequest {
     #[allow(clippy::new_without_default)]
     #[allow(dead_code)]
     pub fn new(name: String, title: String, oao: bool) -> CreateCollectionRequest {
-        CreateCollectionRequest { name, title, oao }
+        CreateCollectionRequest {
+            name,
+            title,
+            oao,
+            config: None,
+            indexed_fields: None,
+        }
     }
 }
 
@@ -1058,6 +1639,8 @@ impl std::string::ToString for CreateCollectionRequest {
             Some(self.title.to_string()),
             Some("oao".to_string()),
             Some(self.oao.to_string()),
+            // Skipping config in query parameter serialization
+            // Skipping indexedFields in query parameter serialization
         ];
 
         params.into_iter().flatten().collect::<Vec<_>>().join(",")
@@ -1140,6 +1723,8 @@ impl std::str::FromStr for CreateCollectionRequest {
                 .into_iter()
                 .next()
                 .ok_or_else(|| "oao missing in CreateCollectionRequest".to_string())?,
+            config: None,
+            indexed_fields: None,
         })
     }
 }
@@ -2069,6 +2654,124 @@ impl std::str::FromStr for SearchFilterOrGroup {
     }
 }
 
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+    validator::Validate,
+)]
+#[schema(
+    description = "Current time of the server",
+    examples(json!({"now": "2024-03-01T10:00:00.123Z", "timezone": "Europe/Berlin"}))
+)]
+pub struct ServerTime {
+    /// Current UTC timestamp of the server
+    #[serde(rename = "now")]
+    pub now: chrono::DateTime<chrono::Utc>,
+
+    /// Configured timezone of the server, if set
+    #[serde(rename = "timezone")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+}
+
+impl ServerTime {
+    #[allow(clippy::new_without_default)]
+    #[allow(dead_code)]
+    pub fn new(now: chrono::DateTime<chrono::Utc>) -> ServerTime {
+        ServerTime {
+            now,
+            timezone: None,
+        }
+    }
+}
+
+/// Converts the ServerTime value to the Query Parameters representation (style=form, explode=false)
+/// specified in https://swagger.io/docs/specification/serialization/
+/// Should be implemented in a serde serializer
+impl std::string::ToString for ServerTime {
+    fn to_string(&self) -> String {
+        let params: Vec<Option<String>> = vec![
+            // Skipping now in query parameter serialization
+            self.timezone
+                .as_ref()
+                .map(|timezone| ["timezone".to_string(), timezone.to_string()].join(",")),
+        ];
+
+        params.into_iter().flatten().collect::<Vec<_>>().join(",")
+    }
+}
+
+/// Converts Query Parameters representation (style=form, explode=false) to a ServerTime value
+/// as specified in https://swagger.io/docs/specification/serialization/
+/// Should be implemented in a serde deserializer
+impl std::str::FromStr for ServerTime {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        /// An intermediate representation of the struct to use for parsing.
+        #[derive(Default)]
+        #[allow(dead_code)]
+        struct IntermediateRep {
+            pub now: Vec<chrono::DateTime<chrono::Utc>>,
+            pub timezone: Vec<String>,
+        }
+
+        let mut intermediate_rep = IntermediateRep::default();
+
+        // Parse into intermediate representation
+        let mut string_iter = s.split(',');
+        let mut key_result = string_iter.next();
+
+        while key_result.is_some() {
+            let val = match string_iter.next() {
+                Some(x) => x,
+                None => {
+                    return std::result::Result::Err(
+                        "Missing value while parsing ServerTime".to_string(),
+                    )
+                }
+            };
+
+            if let Some(key) = key_result {
+                #[allow(clippy::match_single_binding)]
+                match key {
+                    #[allow(clippy::redundant_clone)]
+                    "now" => intermediate_rep.now.push(
+                        <chrono::DateTime<chrono::Utc> as std::str::FromStr>::from_str(val)
+                            .map_err(|x| x.to_string())?,
+                    ),
+                    #[allow(clippy::redundant_clone)]
+                    "timezone" => intermediate_rep.timezone.push(
+                        <String as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?,
+                    ),
+                    _ => {
+                        return std::result::Result::Err(
+                            "Unexpected key while parsing ServerTime".to_string(),
+                        )
+                    }
+                }
+            }
+
+            // Get the next key
+            key_result = string_iter.next();
+        }
+
+        // Use the intermediate representation to return the struct
+        std::result::Result::Ok(ServerTime {
+            now: intermediate_rep
+                .now
+                .into_iter()
+                .next()
+                .ok_or_else(|| "now missing in ServerTime".to_string())?,
+            timezone: intermediate_rep.timezone.into_iter().next(),
+        })
+    }
+}
+
 /// A boolean value
 #[derive(Debug, Clone, PartialEq, PartialOrd, serde::Serialize, serde::Deserialize)]
 pub struct ValueBoolean(bool);
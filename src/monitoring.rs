@@ -1,4 +1,22 @@
-use axum::{routing::get, Router};
+use std::{collections::BTreeMap, fmt::Write, sync::Mutex, time::Duration};
+
+use axum::{
+    http::{header, StatusCode},
+    routing::get,
+    Json, Router,
+};
+use lazy_static::lazy_static;
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbBackend, DbErr, Statement};
+use serde_json::{json, Value};
+use tracing::warn;
+
+/// Time after which the database check of the readiness probe fails
+const DB_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+lazy_static! {
+    /// Counters of the events that have been written since the start
+    pub(crate) static ref EVENT_METRICS: EventMetrics = EventMetrics::default();
+}
 
 pub struct HealthMonitor {}
 
@@ -12,15 +30,136 @@ impl HealthMonitor {
     }
 }
 
+/// Number of created events per collection and event category
+#[derive(Default)]
+pub(crate) struct EventMetrics {
+    created: Mutex<BTreeMap<(String, i32), u64>>,
+}
+
+impl EventMetrics {
+    pub(crate) fn count_created(&self, collection: &str, category: i32) {
+        let mut created = self.created.lock().unwrap();
+        *created
+            .entry((collection.to_string(), category))
+            .or_default() += 1;
+    }
+
+    /// Renders the counters in the Prometheus text format.
+    pub(crate) fn render(&self) -> String {
+        let created = self.created.lock().unwrap();
+        let mut s = String::from(
+            "# HELP folivafy_events_created_total Number of created events per collection and category\n\
+             # TYPE folivafy_events_created_total counter\n",
+        );
+        for ((collection, category), count) in created.iter() {
+            let _ = writeln!(
+                s,
+                "folivafy_events_created_total{{collection=\"{collection}\",category=\"{category}\"}} {count}"
+            );
+        }
+        s
+    }
+}
+
 pub(crate) fn health_routes(monitor: std::sync::Arc<HealthMonitor>) -> Router {
-    Router::new().route("/up", get(|| async { "OK" })).route(
-        "/health",
-        get(move || async move {
-            if monitor.is_healthy() {
-                (axum::http::StatusCode::OK, "OK")
-            } else {
-                (axum::http::StatusCode::INTERNAL_SERVER_ERROR, "UNHEALTHY")
-            }
-        }),
+    Router::new()
+        .route("/up", get(|| async { "OK" }))
+        .route(
+            "/health",
+            get(move || async move {
+                if monitor.is_healthy() {
+                    (axum::http::StatusCode::OK, "OK")
+                } else {
+                    (axum::http::StatusCode::INTERNAL_SERVER_ERROR, "UNHEALTHY")
+                }
+            }),
+        )
+        .route(
+            "/metrics",
+            get(|| async {
+                (
+                    [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+                    EVENT_METRICS.render(),
+                )
+            }),
+        )
+}
+
+/// Routes of the readiness probe, which checks that the database is reachable
+pub(crate) fn readiness_routes(db: DatabaseConnection) -> Router {
+    Router::new().route(
+        "/healthz",
+        get(move || async move { check_database(&db).await }),
+    )
+}
+
+/// Runs `SELECT 1` on the database. Responds with status 503 and the class of
+/// the error if the query fails or does not finish within two seconds.
+async fn check_database(db: &DatabaseConnection) -> (StatusCode, Json<Value>) {
+    let query = db.execute(Statement::from_string(DbBackend::Postgres, "SELECT 1"));
+    let error = match tokio::time::timeout(DB_CHECK_TIMEOUT, query).await {
+        Ok(Ok(_)) => return (StatusCode::OK, Json(json!({"db": "ok"}))),
+        Ok(Err(err)) => {
+            warn!("Database check failed: {err}");
+            db_error_class(&err)
+        }
+        Err(_) => {
+            warn!("Database check timed out");
+            "timeout"
+        }
+    };
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(json!({"db": "error", "error": error})),
     )
 }
+
+fn db_error_class(err: &DbErr) -> &'static str {
+    match err {
+        DbErr::ConnectionAcquire(_) | DbErr::Conn(_) => "connection",
+        DbErr::Exec(_) | DbErr::Query(_) => "query",
+        _ => "other",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn it_counts_created_events_per_collection_and_category() {
+        // Arrange
+        let metrics = EventMetrics::default();
+
+        // Act
+        metrics.count_created("shapes", 1);
+        metrics.count_created("shapes", 1);
+        metrics.count_created("shapes", 2);
+        metrics.count_created("letters", 1);
+
+        // Assert
+        assert_eq!(
+            metrics.render(),
+            "# HELP folivafy_events_created_total Number of created events per collection and category\n\
+             # TYPE folivafy_events_created_total counter\n\
+             folivafy_events_created_total{collection=\"letters\",category=\"1\"} 1\n\
+             folivafy_events_created_total{collection=\"shapes\",category=\"1\"} 2\n\
+             folivafy_events_created_total{collection=\"shapes\",category=\"2\"} 1\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn it_reports_an_unreachable_database() {
+        // Arrange
+        let db = DatabaseConnection::Disconnected;
+
+        // Act
+        let (status, Json(body)) = check_database(&db).await;
+
+        // Assert
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body, json!({"db": "error", "error": "connection"}));
+    }
+}
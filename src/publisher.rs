@@ -0,0 +1,352 @@
+use std::{env, sync::Arc};
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use entity::{collection, collection_document, event};
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, FromQueryResult, JoinType, QueryFilter,
+    QueryOrder, QuerySelect, RelationTrait, Select,
+};
+use serde::Serialize;
+use tokio::sync::{mpsc, oneshot};
+use tracing::{debug, error, info};
+use uuid::Uuid;
+
+use crate::BackgroundTask;
+
+/// Number of events that are relayed to the message broker within one run
+const RELAY_BATCH_SIZE: u64 = 100;
+
+/// Time between two runs of the event relay, if it is not triggered by a request
+const RELAY_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// A stored document event with its collection context, as it is sent to a message broker.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PublishedEvent {
+    event_id: i32,
+    collection: String,
+    document_id: Uuid,
+    category: i32,
+    user_id: Uuid,
+    timestamp: i64,
+    payload: serde_json::Value,
+}
+
+impl PublishedEvent {
+    pub fn event_id(&self) -> i32 {
+        self.event_id
+    }
+
+    pub fn collection(&self) -> &str {
+        &self.collection
+    }
+
+    pub fn category(&self) -> i32 {
+        self.category
+    }
+}
+
+#[derive(Debug, FromQueryResult)]
+struct PendingEvent {
+    id: i32,
+    timestamp: Option<sea_orm::prelude::DateTime>,
+    document_id: Uuid,
+    user: Uuid,
+    category_id: i32,
+    payload: serde_json::Value,
+    collection_name: String,
+}
+
+impl From<PendingEvent> for PublishedEvent {
+    fn from(event: PendingEvent) -> Self {
+        Self {
+            event_id: event.id,
+            collection: event.collection_name,
+            document_id: event.document_id,
+            category: event.category_id,
+            user_id: event.user,
+            timestamp: event.timestamp.unwrap_or_default().and_utc().timestamp(),
+            payload: event.payload,
+        }
+    }
+}
+
+/// Publishes document events to an external system.
+///
+/// The event relay calls the publisher for every stored event after the
+/// transaction that created the event has been committed. An event is marked
+/// as published only after `publish` returned successfully, so an event may
+/// be delivered more than once.
+#[async_trait]
+pub trait EventPublisher: Send + Sync {
+    async fn publish(&self, event: &PublishedEvent) -> Result<()>;
+}
+
+/// Discards all events.
+pub struct NoopEventPublisher;
+
+#[async_trait]
+impl EventPublisher for NoopEventPublisher {
+    async fn publish(&self, _event: &PublishedEvent) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Publishes events to a NATS server.
+///
+/// The subject is built from the prefix, the collection name and the event
+/// category, e. g. `folivafy.events.shapes.1`.
+pub struct NatsEventPublisher {
+    client: async_nats::Client,
+    subject_prefix: String,
+}
+
+impl NatsEventPublisher {
+    pub async fn connect(url: &str, subject_prefix: &str) -> Result<Self> {
+        let client = async_nats::connect(url)
+            .await
+            .with_context(|| format!("Connection to NATS server `{url}` failed"))?;
+        Ok(Self {
+            client,
+            subject_prefix: subject_prefix.to_string(),
+        })
+    }
+}
+
+#[async_trait]
+impl EventPublisher for NatsEventPublisher {
+    async fn publish(&self, event: &PublishedEvent) -> Result<()> {
+        let subject = format!(
+            "{}.{}.{}",
+            self.subject_prefix, event.collection, event.category
+        );
+        let payload = serde_json::to_vec(event).context("Serializing event")?;
+        self.client
+            .publish(subject, payload.into())
+            .await
+            .context("Publishing event")?;
+        self.client.flush().await.context("Flushing NATS client")?;
+        Ok(())
+    }
+}
+
+/// Creates the event publisher from the environment.
+///
+/// Events are published to NATS if `FOLIVAFY_NATS_URL` is set, otherwise they are discarded.
+pub async fn publisher_from_env() -> Result<Arc<dyn EventPublisher>> {
+    match env::var("FOLIVAFY_NATS_URL") {
+        Ok(url) if !url.trim().is_empty() => {
+            let subject_prefix = env::var("FOLIVAFY_NATS_SUBJECT_PREFIX")
+                .unwrap_or_else(|_| "folivafy.events".to_string());
+            info!("Publishing events to NATS server {url}");
+            Ok(Arc::new(
+                NatsEventPublisher::connect(url.trim(), &subject_prefix).await?,
+            ))
+        }
+        _ => {
+            debug!("No event publisher configured");
+            Ok(Arc::new(NoopEventPublisher))
+        }
+    }
+}
+
+fn pending_events_query() -> Select<event::Entity> {
+    event::Entity::find()
+        .select_only()
+        .columns([
+            event::Column::Id,
+            event::Column::Timestamp,
+            event::Column::DocumentId,
+            event::Column::User,
+            event::Column::CategoryId,
+            event::Column::Payload,
+        ])
+        .column_as(collection::Column::Name, "collection_name")
+        .join(
+            JoinType::InnerJoin,
+            event::Relation::CollectionDocument.def(),
+        )
+        .join(
+            JoinType::InnerJoin,
+            collection_document::Relation::Collection.def(),
+        )
+        .filter(event::Column::Published.eq(false))
+        .order_by_asc(event::Column::Id)
+        .limit(RELAY_BATCH_SIZE)
+}
+
+/// Publishes the events in order and stops at the first failure.
+///
+/// Returns the ids of the successfully published events.
+async fn publish_events(publisher: &dyn EventPublisher, events: Vec<PendingEvent>) -> Vec<i32> {
+    let mut published = vec![];
+    for pending_event in events {
+        let event = PublishedEvent::from(pending_event);
+        if let Err(e) = publisher.publish(&event).await {
+            error!("Could not publish event {}: {:?}", event.event_id, e);
+            break;
+        }
+        published.push(event.event_id);
+    }
+    published
+}
+
+async fn relay_events(db: &DatabaseConnection, publisher: &dyn EventPublisher) -> Result<()> {
+    let events = pending_events_query()
+        .into_model::<PendingEvent>()
+        .all(db)
+        .await
+        .context("Loading unpublished events")?;
+    if events.is_empty() {
+        return Ok(());
+    }
+    debug!("Relaying {} event(s)", events.len());
+    let published = publish_events(publisher, events).await;
+    if !published.is_empty() {
+        event::Entity::update_many()
+            .col_expr(event::Column::Published, true.into())
+            .filter(event::Column::Id.is_in(published))
+            .exec(db)
+            .await
+            .context("Marking events as published")?;
+    }
+    Ok(())
+}
+
+pub(crate) fn setup_event_relay(
+    db: DatabaseConnection,
+    publisher: Arc<dyn EventPublisher>,
+) -> (BackgroundTask, mpsc::Sender<()>) {
+    let mut interval = tokio::time::interval(RELAY_INTERVAL);
+    let (relay_signal, mut relay_recv) = mpsc::channel::<()>(1);
+    let (shutdown_signal, mut shutdown_recv) = oneshot::channel::<()>();
+    let join_handle = tokio::spawn(async move {
+        debug!("Event relay started");
+        loop {
+            tokio::select! {
+                _ = &mut shutdown_recv => {
+                    debug!("Event relay shutdown signal received");
+                    break;
+                }
+                _ = interval.tick() => {}
+                _ = relay_recv.recv() => {}
+            }
+            if let Err(e) = relay_events(&db, publisher.as_ref()).await {
+                error!("Event relay failed: {:?}", e);
+            }
+        }
+        debug!("Event relay exited");
+    });
+    (
+        BackgroundTask::new("event relay", join_handle, shutdown_signal),
+        relay_signal,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use anyhow::anyhow;
+    use pretty_assertions::assert_eq;
+    use sea_orm::{DbBackend, QueryTrait};
+    use serde_json::json;
+
+    use super::*;
+
+    struct MockPublisher {
+        payloads: Mutex<Vec<serde_json::Value>>,
+        fail_on_event_id: Option<i32>,
+    }
+
+    impl MockPublisher {
+        fn new(fail_on_event_id: Option<i32>) -> Self {
+            Self {
+                payloads: Mutex::new(vec![]),
+                fail_on_event_id,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl EventPublisher for MockPublisher {
+        async fn publish(&self, event: &PublishedEvent) -> Result<()> {
+            if self.fail_on_event_id == Some(event.event_id()) {
+                return Err(anyhow!("Broker unavailable"));
+            }
+            self.payloads
+                .lock()
+                .unwrap()
+                .push(serde_json::to_value(event).unwrap());
+            Ok(())
+        }
+    }
+
+    fn pending_event(id: i32) -> PendingEvent {
+        PendingEvent {
+            id,
+            timestamp: chrono::DateTime::from_timestamp(1700000000, 0).map(|t| t.naive_utc()),
+            document_id: Uuid::parse_str("a4a0ab26-3a6f-4e4a-9a43-34f6b8a9c1f0").unwrap(),
+            user: Uuid::parse_str("9d1ef2b4-c2c6-4d52-8f5c-1c3e6a7d8e90").unwrap(),
+            category_id: 3,
+            payload: json!({"color": "red"}),
+            collection_name: "shapes".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn it_publishes_events_with_collection_context() {
+        // Arrange
+        let publisher = MockPublisher::new(None);
+
+        // Act
+        let published = publish_events(&publisher, vec![pending_event(7)]).await;
+
+        // Assert
+        assert_eq!(published, vec![7]);
+        assert_eq!(
+            *publisher.payloads.lock().unwrap(),
+            vec![json!({
+                "eventId": 7,
+                "collection": "shapes",
+                "documentId": "a4a0ab26-3a6f-4e4a-9a43-34f6b8a9c1f0",
+                "category": 3,
+                "userId": "9d1ef2b4-c2c6-4d52-8f5c-1c3e6a7d8e90",
+                "timestamp": 1700000000,
+                "payload": {"color": "red"}
+            })]
+        );
+    }
+
+    #[tokio::test]
+    async fn it_stops_publishing_at_first_failure() {
+        // Arrange
+        let publisher = MockPublisher::new(Some(2));
+
+        // Act
+        let published = publish_events(
+            &publisher,
+            vec![pending_event(1), pending_event(2), pending_event(3)],
+        )
+        .await;
+
+        // Assert
+        assert_eq!(published, vec![1]);
+        assert_eq!(publisher.payloads.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn it_selects_unpublished_events_in_order() {
+        // Act
+        let sql = pending_events_query()
+            .build(DbBackend::Postgres)
+            .to_string();
+
+        // Assert
+        assert_eq!(
+            sql,
+            r#"SELECT "event"."id", "event"."timestamp", "event"."document_id", "event"."user", "event"."category_id", "event"."payload", "collection"."name" AS "collection_name" FROM "event" INNER JOIN "collection_document" ON "event"."document_id" = "collection_document"."id" INNER JOIN "collection" ON "collection_document"."collection_id" = "collection"."id" WHERE "event"."published" = FALSE ORDER BY "event"."id" ASC LIMIT 100"#
+        );
+    }
+}